@@ -0,0 +1,206 @@
+#![allow(deprecated)]
+#![allow(unexpected_cfgs)]
+
+// Test-only program that deposits into and withdraws from the AMM through CPI and records
+// what the AMM reported via return data, so integration tests can check it end to end.
+
+use anchor_lang::prelude::*;
+use amm::{
+    cpi::{ accounts, deposit, withdraw },
+    program::Amm,
+};
+
+declare_id!("6QAZQHyNSeBUpD6JHs1dXBKSWnYEWba6tUWutSidnU5b");
+
+#[program]
+pub mod amm_caller {
+    use super::*;
+
+    /// Deposits into the AMM via CPI and stores the returned `DepositResult` in the receipt.
+    pub fn deposit_via_cpi(ctx: Context<DepositViaCpi>, amount: u64, max_x: u64, max_y: u64) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let cpi_accounts = accounts::Deposit {
+            user: accounts.user.to_account_info(),
+            mint_x: accounts.mint_x.to_account_info(),
+            mint_y: accounts.mint_y.to_account_info(),
+            config: accounts.config.to_account_info(),
+            vault_x: accounts.vault_x.to_account_info(),
+            vault_y: accounts.vault_y.to_account_info(),
+            mint_lp: accounts.mint_lp.to_account_info(),
+            user_x: accounts.user_x.to_account_info(),
+            user_y: accounts.user_y.to_account_info(),
+            user_lp: accounts.user_lp.to_account_info(),
+            position: accounts.position.to_account_info(),
+            fee_state: accounts.fee_state.to_account_info(),
+            locked_position: None,
+            locked_lp: None,
+            token_program: accounts.token_program.to_account_info(),
+            associated_token_program: accounts.associated_token_program.to_account_info(),
+            system_program: accounts.system_program.to_account_info(),
+        };
+        let result = deposit(
+            CpiContext::new(accounts.amm_program.to_account_info(), cpi_accounts),
+            amount,
+            max_x,
+            max_y,
+            0,
+        )?.get();
+
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.amount_x = result.x_deposited;
+        receipt.amount_y = result.y_deposited;
+        receipt.lp_amount = result.lp_minted;
+        receipt.bump = ctx.bumps.receipt;
+        Ok(())
+    }
+
+    /// Withdraws from the AMM via CPI and stores the returned `WithdrawResult` in the receipt.
+    pub fn withdraw_via_cpi(ctx: Context<WithdrawViaCpi>, lp_amount: u64, min_x: u64, min_y: u64) -> Result<()> {
+        let accounts = &ctx.accounts;
+        let cpi_accounts = accounts::Withdraw {
+            user: accounts.user.to_account_info(),
+            recipient: accounts.user.to_account_info(),
+            mint_x: accounts.mint_x.to_account_info(),
+            mint_y: accounts.mint_y.to_account_info(),
+            config: accounts.config.to_account_info(),
+            vault_x: accounts.vault_x.to_account_info(),
+            vault_y: accounts.vault_y.to_account_info(),
+            mint_lp: accounts.mint_lp.to_account_info(),
+            user_x: accounts.user_x.to_account_info(),
+            user_y: accounts.user_y.to_account_info(),
+            user_lp: accounts.user_lp.to_account_info(),
+            position: accounts.position.to_account_info(),
+            fee_state: accounts.fee_state.to_account_info(),
+            token_program: accounts.token_program.to_account_info(),
+            associated_token_program: accounts.associated_token_program.to_account_info(),
+            system_program: accounts.system_program.to_account_info(),
+        };
+        let result = withdraw(
+            CpiContext::new(accounts.amm_program.to_account_info(), cpi_accounts),
+            lp_amount,
+            min_x,
+            min_y,
+            false,
+        )?.get();
+
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.amount_x = result.x_out;
+        receipt.amount_y = result.y_out;
+        receipt.lp_amount = lp_amount;
+        receipt.bump = ctx.bumps.receipt;
+        Ok(())
+    }
+}
+
+/// Last amounts the AMM reported to this program for a user.
+#[account]
+#[derive(InitSpace)]
+pub struct Receipt {
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub lp_amount: u64,
+    pub bump: u8,
+}
+
+// The AMM validates every pool account itself, so they are passed through unchecked.
+#[derive(Accounts)]
+pub struct DepositViaCpi<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Validated by the AMM.
+    pub mint_x: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    pub mint_y: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub vault_x: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub vault_y: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub mint_lp: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub user_x: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub user_y: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub user_lp: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub fee_state: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"receipt", user.key().as_ref()],
+        bump,
+        space = 8 + Receipt::INIT_SPACE,
+    )]
+    pub receipt: Account<'info, Receipt>,
+    /// CHECK: Validated by the AMM.
+    pub token_program: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    pub associated_token_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub amm_program: Program<'info, Amm>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawViaCpi<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Validated by the AMM.
+    pub mint_x: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    pub mint_y: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub vault_x: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub vault_y: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub mint_lp: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub user_x: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub user_y: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub user_lp: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    #[account(mut)]
+    pub fee_state: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"receipt", user.key().as_ref()],
+        bump,
+        space = 8 + Receipt::INIT_SPACE,
+    )]
+    pub receipt: Account<'info, Receipt>,
+    /// CHECK: Validated by the AMM.
+    pub token_program: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM.
+    pub associated_token_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub amm_program: Program<'info, Amm>,
+}
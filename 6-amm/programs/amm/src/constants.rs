@@ -2,3 +2,68 @@ use anchor_lang::prelude::*;
 
 #[constant]
 pub const SEED: &str = "anchor";
+
+/// Decimals of every pool's LP mint.
+#[constant]
+pub const LP_DECIMALS: u8 = 6;
+
+/// Smallest LP amount a deposit may mint, expressed at `LP_DECIMALS` (0.001 LP).
+#[constant]
+pub const MIN_DEPOSIT_LP: u64 = 1_000;
+
+/// Smallest amount of either token a deposit may pull in, in base units.
+#[constant]
+pub const MIN_DEPOSIT_TOKENS: u64 = 1_000;
+
+/// Smallest balance, in base units, a swap may leave on the output side of the reserves.
+/// Nearly empty sides break the price and the precision of later quotes. Withdrawals
+/// shrink both sides proportionally and are not bound by it.
+#[constant]
+pub const MIN_RESERVE: u64 = 1_000;
+
+/// Number of pool addresses held by the registry and by each of its overflow pages.
+pub const REGISTRY_PAGE_CAPACITY: usize = 16;
+
+/// Share of the pool fee, in basis points of the fee, paid to a host fee account when one is supplied.
+#[constant]
+pub const HOST_FEE_BPS: u16 = 2_000;
+
+/// Number of reserve snapshots kept per pool before the oldest is overwritten.
+pub const SNAPSHOT_CAPACITY: usize = 48;
+
+/// Maximum length in bytes of a pool's UTF-8 label.
+pub const LABEL_LEN: usize = 32;
+
+/// Minimum number of seconds between two checkpoints of the same pool.
+#[constant]
+pub const MIN_CHECKPOINT_INTERVAL: i64 = 3_600;
+
+/// Most pools `collect_protocol_fees_multi` processes in one instruction, to stay within compute limits.
+#[constant]
+pub const MAX_COLLECT_BATCH: u8 = 8;
+
+/// Longest an LP deposit may be locked for, in seconds (one year).
+#[constant]
+pub const MAX_LOCK_DURATION: i64 = 365 * 86_400;
+
+/// Lock duration, in seconds (30 days), from which a locked deposit earns the full boost.
+#[constant]
+pub const FULL_BOOST_LOCK_DURATION: i64 = 30 * 86_400;
+
+/// Fee-share weight of a fully boosted lock, in bps (15_000 = 1.5x).
+#[constant]
+pub const MAX_LOCK_BOOST_BPS: u16 = 15_000;
+
+/// Scales `MIN_DEPOSIT_LP` from `LP_DECIMALS` to an LP mint with `lp_decimals` decimals,
+/// so the threshold stays the same fraction of one LP token. Never returns less than 1.
+pub fn min_deposit_lp(lp_decimals: u8) -> u64 {
+    if lp_decimals >= LP_DECIMALS {
+        10u64
+            .checked_pow((lp_decimals - LP_DECIMALS) as u32)
+            .and_then(|scale| MIN_DEPOSIT_LP.checked_mul(scale))
+            .unwrap_or(u64::MAX)
+    } else {
+        let scale = 10u64.pow((LP_DECIMALS - lp_decimals) as u32);
+        (MIN_DEPOSIT_LP / scale).max(1)
+    }
+}
@@ -42,7 +42,85 @@ pub enum AmmError {
     #[msg("Insufficient Liquidity.")]
     InsufficientLiquidity,
     #[msg("Insufficient Funds.")]
-    InsufficientFunds    
+    InsufficientFunds,
+    #[msg("Account is not a pool config.")]
+    InvalidConfigAccount,
+    #[msg("Pool weights must be non-zero and sum to 100.")]
+    InvalidWeights,
+    #[msg("Deposit is below the minimum LP or token amount.")]
+    DepositTooSmall,
+    #[msg("Registry page does not match the next free registry slot.")]
+    InvalidRegistryPage,
+    #[msg("Recipient token account does not match the recipient and output mint.")]
+    InvalidRecipient,
+    #[msg("Protocol fee must be at most 10000 bps.")]
+    InvalidProtocolFee,
+    #[msg("Account is not the global config.")]
+    InvalidGlobalConfig,
+    #[msg("Deposit would exceed the pool's deposit cap.")]
+    DepositCapExceeded,
+    #[msg("Withdraw cooldown active; Position.unlock_ts holds the time withdrawals reopen.")]
+    CooldownActive,
+    #[msg("Checkpoint taken too soon after the previous one.")]
+    CheckpointTooSoon,
+    #[msg("No snapshot at that index.")]
+    SnapshotNotFound,
+    #[msg("Host fee account must be a writable token account for the output mint.")]
+    InvalidHostFeeAccount,
+    #[msg("Input amount must be greater than zero.")]
+    ZeroAmountIn,
+    #[msg("Output amount rounds down to zero.")]
+    ZeroAmountOut,
+    #[msg("LP amount must be greater than zero.")]
+    ZeroLpAmount,
+    #[msg("Curve math failed to produce an amount.")]
+    CurveMathFailed,
+    #[msg("User token balance is too low.")]
+    InsufficientUserBalance,
+    #[msg("Pool vault balance is too low to pay out.")]
+    InsufficientVaultBalance,
+    #[msg("Deposits are permanently closed for this pool.")]
+    DepositsClosed,
+    #[msg("Batch must hold between 1 and MAX_COLLECT_BATCH groups of five accounts.")]
+    InvalidBatchSize,
+    #[msg("Vault is not the pool's associated token account.")]
+    InvalidVaultAccount,
+    #[msg("Treasury token account does not match the treasury and pool mint.")]
+    InvalidTreasuryAccount,
+    #[msg("Pool label is longer than 32 bytes.")]
+    InvalidLabel,
+    #[msg("This pool is not locked.")]
+    PoolNotLocked,
+    #[msg("Lock reason is reserved for automated locks.")]
+    InvalidLockReason,
+    #[msg("Pool price deviates from the expected price by more than the allowed tolerance.")]
+    PriceDeviationTooHigh,
+    #[msg("Expected price must have a non-zero numerator and denominator.")]
+    InvalidPriceGuard,
+    #[msg("A pool's LP mint cannot be a token of another pool.")]
+    NestedPoolNotAllowed,
+    #[msg("LP position is still locked; LockedPosition.unlock_ts holds the release time.")]
+    PositionLocked,
+    #[msg("Lock duration must be between 0 and MAX_LOCK_DURATION seconds.")]
+    InvalidLockDuration,
+    #[msg("Locked deposits need the locked position and locked LP escrow accounts.")]
+    LockAccountsMissing,
+    #[msg("Pool creation is paused.")]
+    PoolCreationPaused,
+    #[msg("The global state account is required to create pools.")]
+    GlobalStateRequired,
+    #[msg("There are no fees to claim.")]
+    NothingToClaim,
+    #[msg("Slippage tolerance must be at most 10000 bps.")]
+    InvalidSlippageTolerance,
+    #[msg("The swap would leave the output reserve below the minimum.")]
+    ReserveFloorBreached,
+    #[msg("Pool creation is permissioned and a mint has not been approved.")]
+    MintNotApproved,
+    #[msg("The burn would leave less than the minimum LP supply.")]
+    BurnBelowMinimumLiquidity,
+    #[msg("Account is not the pool's fee state.")]
+    InvalidFeeState,
 }
 
 impl From<CurveError> for AmmError {
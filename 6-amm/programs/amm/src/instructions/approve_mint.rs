@@ -0,0 +1,72 @@
+// This file defines the 'ApproveMint' instruction for the AMM program.
+// It lets the AMM admin approve a mint for pool creation while creation is permissioned.
+//
+// Key roles:
+// - 'admin': The global admin; must sign and pays for the badge.
+// - 'global_state': The singleton PDA naming the admin.
+// - 'mint': The mint being approved.
+// - 'mint_badge': The approval record at [b"badge", mint].
+//
+// The approve flow:
+// - Creates the badge once; approving an already approved mint fails because the PDA exists.
+// - Records who approved the mint and when, and emits a MintApprovedEvent.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{ state::{ GlobalState, MintBadge }, error::AmmError };
+
+#[derive(Accounts)]
+pub struct ApproveMint<'info> {
+    /// The global admin.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// The global state PDA.
+    #[account(
+        seeds = [b"global"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// The mint to approve.
+    pub mint: Account<'info, Mint>,
+    /// The mint's badge PDA.
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"badge", mint.key().as_ref()],
+        bump,
+        space = 8 + MintBadge::INIT_SPACE,
+    )]
+    pub mint_badge: Account<'info, MintBadge>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ApproveMint<'info> {
+    /// Grants the mint its badge.
+    pub fn approve_mint(&mut self, bumps: ApproveMintBumps) -> Result<()> {
+        require_keys_eq!(self.global_state.admin, self.admin.key(), AmmError::InvalidAuthority);
+        let timestamp = Clock::get()?.unix_timestamp;
+
+        self.mint_badge.set_inner(MintBadge {
+            mint: self.mint.key(),
+            approved_by: self.admin.key(),
+            approved_at: timestamp,
+            bump: bumps.mint_badge,
+        });
+
+        emit!(MintApprovedEvent {
+            mint: self.mint.key(),
+            admin: self.admin.key(),
+            timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct MintApprovedEvent {
+    pub mint: Pubkey,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
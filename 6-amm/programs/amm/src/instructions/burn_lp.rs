@@ -0,0 +1,110 @@
+// This file defines the 'BurnLp' instruction for the AMM program.
+// It lets a liquidity provider burn LP tokens without withdrawing, donating their share of the
+// pool to the remaining LPs (e.g. to retire protocol-owned liquidity for good).
+//
+// Key roles:
+// - 'user': The liquidity provider; signs for and burns from their LP account.
+// - 'config': The pool's configuration PDA.
+// - 'mint_lp': The LP token mint.
+// - 'user_lp': The user's LP token account.
+// - 'position': The user's position PDA, if any; fees earned so far are settled into it.
+// - 'fee_state': The pool's LP fee index (may not exist yet).
+//
+// The burn flow:
+// - The burn must leave at least the minimum deposit's worth of LP in supply, so the
+//   reserves are never left without an owner.
+// - LP tokens are burned; nothing leaves the vaults, so every other LP token is now backed
+//   by more of the reserves.
+// - An LpBurnedEvent is emitted with the new supply.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Burn, burn, Mint, Token, TokenAccount};
+
+use crate::{
+    state::{ Config, Position },
+    error::AmmError,
+    constants::{ LP_DECIMALS, min_deposit_lp },
+};
+
+#[derive(Accounts)]
+pub struct BurnLp<'info> {
+    /// The liquidity provider donating their LP.
+    pub user: Signer<'info>,
+    /// The config PDA for the pool. Mutable to release LP fees no position can claim.
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+    /// The LP token mint (PDA, authority = config).
+    #[account(
+        mut,
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+        mint::decimals = LP_DECIMALS,
+        mint::authority = config,
+    )]
+    pub mint_lp: Account<'info, Mint>,
+    /// The user's LP token account.
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = user
+    )]
+    pub user_lp: Account<'info, TokenAccount>,
+    /// The user's position in this pool.
+    /// CHECK: Address is pinned by seeds. LPs who never deposited through the program have no
+    /// position; otherwise it is deserialized in the handler.
+    #[account(
+        mut,
+        seeds = [b"position", config.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: UncheckedAccount<'info>,
+    /// The pool's LP fee index, tracking the LP registered to earn fees.
+    /// CHECK: Address is pinned by seeds; the account may not be initialized, which means no LP is registered.
+    #[account(
+        mut,
+        seeds = [b"fee_state", config.key().as_ref()],
+        bump
+    )]
+    pub fee_state: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> BurnLp<'info> {
+    /// Burns `amount` of the user's LP tokens, leaving the reserves to the remaining LPs.
+    pub fn burn_lp(&mut self, amount: u64) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(amount > 0, AmmError::ZeroLpAmount);
+        require!(self.user_lp.amount >= amount, AmmError::InsufficientUserBalance);
+
+        let new_supply = self.mint_lp.supply.checked_sub(amount).ok_or(AmmError::Underflow)?;
+        require!(new_supply >= min_deposit_lp(self.mint_lp.decimals), AmmError::BurnBelowMinimumLiquidity);
+
+        Position::remove_lp_from_account(&self.position, &self.fee_state, &mut self.config, self.user_lp.amount, amount)?;
+
+        let cpi_accounts = Burn {
+            mint: self.mint_lp.to_account_info(),
+            from: self.user_lp.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+        burn(CpiContext::new(self.token_program.to_account_info(), cpi_accounts), amount)?;
+
+        emit!(LpBurnedEvent {
+            user: self.user.key(),
+            amount,
+            new_supply,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct LpBurnedEvent {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub new_supply: u64,
+}
@@ -0,0 +1,96 @@
+// This file defines the 'Checkpoint' instruction for the AMM program.
+// It records the pool's reserves and LP supply on-chain so APR and impermanent loss
+// can be computed from program state instead of RPC history.
+//
+// Key roles:
+// - 'payer': Anyone; pays for the snapshots account the first time.
+// - 'config': The pool's configuration PDA.
+// - 'snapshots': The pool's ring buffer of snapshots, created on first use.
+//
+// The checkpoint flow:
+// - Rejects the call if the last snapshot is less than MIN_CHECKPOINT_INTERVAL seconds old.
+// - Appends { reserve_x, reserve_y, lp_supply, timestamp, slot }, overwriting the oldest entry when full.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
+
+use crate::{
+    state::{ Config, Snapshot, Snapshots },
+    error::AmmError,
+    constants::{ LP_DECIMALS, MIN_CHECKPOINT_INTERVAL },
+};
+
+#[derive(Accounts)]
+pub struct Checkpoint<'info> {
+    /// The account paying for the snapshots account if it does not exist yet.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The mint for token X.
+    pub mint_x: Account<'info, Mint>,
+    /// The mint for token Y.
+    pub mint_y: Account<'info, Mint>,
+    /// The config PDA for the pool.
+    #[account(
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+    /// The LP token mint (PDA, authority = config).
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+        mint::decimals = LP_DECIMALS,
+        mint::authority = config,
+    )]
+    pub mint_lp: Account<'info, Mint>,
+    /// The pool's vault for token X.
+    #[account(
+        associated_token::mint = mint_x,
+        associated_token::authority = config
+    )]
+    pub vault_x: Account<'info, TokenAccount>,
+    /// The pool's vault for token Y.
+    #[account(
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+    )]
+    pub vault_y: Account<'info, TokenAccount>,
+    /// The pool's snapshot ring buffer.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"snapshots", config.key().as_ref()],
+        bump,
+        space = 8 + Snapshots::INIT_SPACE,
+    )]
+    pub snapshots: Box<Account<'info, Snapshots>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Checkpoint<'info> {
+    /// Writes a snapshot of the current reserves and LP supply.
+    pub fn checkpoint(&mut self, bumps: CheckpointBumps) -> Result<()> {
+        let clock = Clock::get()?;
+
+        if let Some(latest) = self.snapshots.latest() {
+            let elapsed = clock.unix_timestamp.saturating_sub(latest.timestamp);
+            require!(elapsed >= MIN_CHECKPOINT_INTERVAL, AmmError::CheckpointTooSoon);
+        }
+
+        let (reserve_x, reserve_y) = self.config.net_reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        self.snapshots.config = self.config.key();
+        self.snapshots.bump = bumps.snapshots;
+        self.snapshots.push(Snapshot {
+            reserve_x,
+            reserve_y,
+            lp_supply: self.mint_lp.supply,
+            timestamp: clock.unix_timestamp,
+            slot: clock.slot,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+// This file defines the 'ClaimFees' instruction for the AMM program.
+// It pays a liquidity provider the swap fees their LP tokens earned since their last claim.
+//
+// Key roles:
+// - 'user': The liquidity provider claiming fees.
+// - 'vault_x' and 'vault_y': The pool's token vaults, which hold the unclaimed LP fees.
+// - 'user_lp': The user's LP token account; may be omitted once it has been closed.
+// - 'position': The user's position PDA, holding the fee checkpoints and settled fees.
+// - 'fee_state': The pool's LP fee index (may not exist yet, in which case nothing has accrued).
+// - 'user_x' and 'user_y': The user's token accounts receiving the fees, created if needed.
+//
+// The claim flow:
+// - Fees earned since the last checkpoint ((global growth - checkpoint) * fee weight) are
+//   settled into the position and the checkpoints advanced. The fee weight is the registered
+//   LP still in the user's LP account plus the boosted weight of their locked LP; the share of
+//   registered LP that has left the account goes back to the reserves.
+// - Everything the position is owed is transferred from the vaults, signed by the config PDA.
+// - The claimed amounts are taken off the pool's unclaimed LP fees, which were never part of
+//   the reserves, so prices and other LPs' shares do not move.
+
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{Transfer, transfer, Mint, Token, TokenAccount},
+};
+
+use crate::{
+    state::{ Config, FeeState, Position },
+    error::AmmError,
+};
+
+#[derive(Accounts)]
+pub struct ClaimFees<'info> {
+    /// The liquidity provider claiming fees.
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// The mint for token X.
+    pub mint_x: Account<'info, Mint>,
+    /// The mint for token Y.
+    pub mint_y: Account<'info, Mint>,
+    /// The config PDA for the pool. Mutable to release the claimed fees.
+    #[account(
+        mut,
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+    /// The pool's vault for token X.
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config
+    )]
+    pub vault_x: Account<'info, TokenAccount>,
+    /// The pool's vault for token Y.
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+    )]
+    pub vault_y: Account<'info, TokenAccount>,
+    /// The LP token mint.
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+    )]
+    pub mint_lp: Account<'info, Mint>,
+    /// The user's LP token account. Omit it after a full exit closed the account.
+    #[account(
+        associated_token::mint = mint_lp,
+        associated_token::authority = user
+    )]
+    pub user_lp: Option<Account<'info, TokenAccount>>,
+    /// The user's position in this pool.
+    #[account(
+        mut,
+        seeds = [b"position", config.key().as_ref(), user.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Box<Account<'info, Position>>,
+    /// The pool's LP fee index.
+    /// CHECK: Address is pinned by seeds; the account may not be initialized, which means no fees have accrued.
+    #[account(
+        mut,
+        seeds = [b"fee_state", config.key().as_ref()],
+        bump
+    )]
+    pub fee_state: UncheckedAccount<'info>,
+    /// The user's token X account, created if needed.
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_x,
+        associated_token::authority = user
+    )]
+    pub user_x: Account<'info, TokenAccount>,
+    /// The user's token Y account, created if needed.
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_y,
+        associated_token::authority = user,
+    )]
+    pub user_y: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimFees<'info> {
+    /// Settles the user's position and transfers every fee it is owed.
+    pub fn claim_fees(&mut self) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+
+        let held_lp = self.user_lp.as_ref().map_or(0, |user_lp| user_lp.amount);
+        if let Some(mut fee_state) = FeeState::load(&self.fee_state)? {
+            self.position.settle_fees(&mut self.config, &mut fee_state, held_lp)?;
+            fee_state.store(&self.fee_state)?;
+        }
+
+        let (amount_x, amount_y) = (self.position.fees_owed_x, self.position.fees_owed_y);
+        require!(amount_x > 0 || amount_y > 0, AmmError::NothingToClaim);
+
+        self.transfer_fee(true, amount_x)?;
+        self.transfer_fee(false, amount_y)?;
+
+        self.config.lp_fees_owed_x = self.config.lp_fees_owed_x.checked_sub(amount_x).ok_or(AmmError::Underflow)?;
+        self.config.lp_fees_owed_y = self.config.lp_fees_owed_y.checked_sub(amount_y).ok_or(AmmError::Underflow)?;
+        self.position.fees_owed_x = 0;
+        self.position.fees_owed_y = 0;
+
+        emit!(FeesClaimedEvent {
+            user: self.user.key(),
+            config: self.config.key(),
+            amount_x,
+            amount_y,
+        });
+
+        Ok(())
+    }
+
+    /// Transfers `amount` of token X or Y from the vault to the user, signed by the config PDA.
+    fn transfer_fee(&self, is_x: bool, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let (from, to) = match is_x {
+            true => (self.vault_x.to_account_info(), self.user_x.to_account_info()),
+            false => (self.vault_y.to_account_info(), self.user_y.to_account_info()),
+        };
+        let seeds = crate::config_signer_seeds!(self.config);
+        let signer_seeds = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from,
+            to,
+            authority: self.config.to_account_info(),
+        };
+        transfer(CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds), amount)
+    }
+}
+
+#[event]
+pub struct FeesClaimedEvent {
+    pub user: Pubkey,
+    pub config: Pubkey,
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
@@ -0,0 +1,39 @@
+// This file defines the 'CloseDeposits' instruction for the AMM program.
+// It puts a pool into burn-only LP mode: no new LP can ever be minted, while swaps and
+// withdrawals continue so the pool can wind down naturally.
+//
+// Key roles:
+// - 'authority': The pool's update authority; must sign.
+// - 'config': The pool's configuration PDA.
+//
+// The LP mint authority has to stay with the config PDA, so the guarantee comes from
+// this one-way flag instead: there is no instruction that clears it.
+
+use anchor_lang::prelude::*;
+
+use crate::{ state::{ AdminAction, Config }, error::AmmError };
+
+#[derive(Accounts)]
+pub struct CloseDeposits<'info> {
+    /// The pool's update authority.
+    pub authority: Signer<'info>,
+    /// The config PDA for the pool.
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> CloseDeposits<'info> {
+    /// Irreversibly closes the pool to deposits.
+    pub fn close_deposits(&mut self) -> Result<()> {
+        require!(!self.config.deposits_closed, AmmError::DepositsClosed);
+
+        self.config.admin_action(self.config.key(), &self.authority.key(), AdminAction::CloseDeposits, 0, 1)?;
+
+        self.config.deposits_closed = true;
+        Ok(())
+    }
+}
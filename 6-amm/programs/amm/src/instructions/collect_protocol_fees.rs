@@ -0,0 +1,135 @@
+// This file defines the 'CollectProtocolFees' instruction for the AMM program.
+// It moves a pool's accrued protocol fees from its vaults to the global treasury.
+//
+// Key roles:
+// - 'payer': Anyone; pays for the treasury ATAs if they do not exist yet.
+// - 'global_config': Pins which wallet is the treasury.
+// - 'treasury_x' and 'treasury_y': The treasury's ATAs, the only possible destinations.
+// - 'vault_x' and 'vault_y': The pool's token vaults.
+//
+// The collect flow:
+// - Transfers protocol_fees_x and protocol_fees_y from the vaults using the config PDA as authority.
+// - Zeroes both accumulators. LP reserves are unaffected since they already excluded these fees.
+
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{Transfer, transfer, Mint, Token, TokenAccount},
+};
+
+use crate::state::{ Config, GlobalConfig };
+
+#[derive(Accounts)]
+pub struct CollectProtocolFees<'info> {
+    /// The account paying for any treasury ATA creation.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The mint for token X.
+    pub mint_x: Account<'info, Mint>,
+    /// The mint for token Y.
+    pub mint_y: Account<'info, Mint>,
+    /// The config PDA for the pool.
+    #[account(
+        mut,
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+    /// The global config PDA.
+    #[account(
+        has_one = treasury,
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    /// The treasury wallet from the global config.
+    pub treasury: SystemAccount<'info>,
+    /// The pool's vault for token X.
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config
+    )]
+    pub vault_x: Account<'info, TokenAccount>,
+    /// The pool's vault for token Y.
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+    )]
+    pub vault_y: Account<'info, TokenAccount>,
+    /// The treasury's token X account.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_x,
+        associated_token::authority = treasury
+    )]
+    pub treasury_x: Account<'info, TokenAccount>,
+    /// The treasury's token Y account.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_y,
+        associated_token::authority = treasury,
+    )]
+    pub treasury_y: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CollectProtocolFees<'info> {
+    /// Sends all accrued protocol fees to the treasury ATAs and resets the accumulators.
+    pub fn collect(&mut self) -> Result<()> {
+        let (amount_x, amount_y) = (self.config.protocol_fees_x, self.config.protocol_fees_y);
+
+        self.transfer_from_vault(true, amount_x)?;
+        self.transfer_from_vault(false, amount_y)?;
+
+        self.config.protocol_fees_x = 0;
+        self.config.protocol_fees_y = 0;
+
+        emit!(ProtocolFeesCollectedEvent {
+            config: self.config.key(),
+            treasury: self.treasury.key(),
+            amount_x,
+            amount_y,
+        });
+
+        Ok(())
+    }
+
+    /// Transfers `amount` of token X (`is_x`) or Y from the pool vault to the treasury.
+    fn transfer_from_vault(&self, is_x: bool, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let (from, to) = if is_x {
+            (self.vault_x.to_account_info(), self.treasury_x.to_account_info())
+        } else {
+            (self.vault_y.to_account_info(), self.treasury_y.to_account_info())
+        };
+
+        let seeds = crate::config_signer_seeds!(self.config);
+        let signer_seeds = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from,
+            to,
+            authority: self.config.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+        transfer(ctx, amount)
+    }
+}
+
+#[event]
+pub struct ProtocolFeesCollectedEvent {
+    pub config: Pubkey,
+    pub treasury: Pubkey,
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
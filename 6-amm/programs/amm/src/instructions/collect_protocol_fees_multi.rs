@@ -0,0 +1,132 @@
+// This file defines the 'CollectProtocolFeesMulti' instruction for the AMM program.
+// It lets the treasury sweep accrued protocol fees from many pools in a single instruction.
+//
+// Key roles:
+// - 'treasury': The treasury wallet from the global config; must sign.
+// - 'global_config': Pins which wallet is the treasury.
+// - remaining_accounts: One group of five accounts per pool, in this order:
+//   (config, vault_x, vault_y, treasury_x, treasury_y). Every account is writable.
+//
+// The collect flow, per pool:
+// - The config's owner, discriminator, PDA and bump are checked by hand, since it is not a typed account.
+// - The vaults must be the config's ATAs and the destinations the treasury's ATAs for the pool mints.
+// - Pools with nothing accrued are skipped.
+// - Otherwise the fees are transferred, both accumulators are zeroed and a FeesCollectedEvent is emitted.
+
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address,
+    token::{Transfer, transfer, Token},
+};
+
+use crate::{
+    state::{ Config, GlobalConfig },
+    error::AmmError,
+    constants::MAX_COLLECT_BATCH,
+};
+
+/// Number of remaining accounts describing one pool.
+const ACCOUNTS_PER_POOL: usize = 5;
+
+#[derive(Accounts)]
+pub struct CollectProtocolFeesMulti<'info> {
+    /// The treasury wallet, which cranks the batch.
+    pub treasury: Signer<'info>,
+    /// The global config PDA.
+    #[account(
+        has_one = treasury,
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> CollectProtocolFeesMulti<'info> {
+    /// Collects the protocol fees of every pool group in `pools`.
+    pub fn collect_multi(&self, pools: &'info [AccountInfo<'info>]) -> Result<()> {
+        require!(
+            !pools.is_empty()
+                && pools.len() % ACCOUNTS_PER_POOL == 0
+                && pools.len() / ACCOUNTS_PER_POOL <= MAX_COLLECT_BATCH as usize,
+            AmmError::InvalidBatchSize
+        );
+
+        for group in pools.chunks_exact(ACCOUNTS_PER_POOL) {
+            self.collect_pool(group)?;
+        }
+
+        Ok(())
+    }
+
+    /// Validates one (config, vault_x, vault_y, treasury_x, treasury_y) group and sweeps its fees.
+    fn collect_pool(&self, group: &'info [AccountInfo<'info>]) -> Result<()> {
+        let [config_info, vault_x, vault_y, treasury_x, treasury_y] = group else {
+            return err!(AmmError::InvalidBatchSize);
+        };
+
+        // Owner and discriminator are checked by the typed load; the address by re-deriving the PDA
+        let mut config = Account::<Config>::try_from(config_info).map_err(|_| AmmError::InvalidConfigAccount)?;
+        let expected = Pubkey::create_program_address(crate::config_signer_seeds!(config), &crate::ID)
+            .map_err(|_| AmmError::InvalidConfigAccount)?;
+        require_keys_eq!(config_info.key(), expected, AmmError::InvalidConfigAccount);
+
+        require_keys_eq!(vault_x.key(), get_associated_token_address(&expected, &config.mint_x), AmmError::InvalidVaultAccount);
+        require_keys_eq!(vault_y.key(), get_associated_token_address(&expected, &config.mint_y), AmmError::InvalidVaultAccount);
+        let treasury = self.treasury.key();
+        require_keys_eq!(treasury_x.key(), get_associated_token_address(&treasury, &config.mint_x), AmmError::InvalidTreasuryAccount);
+        require_keys_eq!(treasury_y.key(), get_associated_token_address(&treasury, &config.mint_y), AmmError::InvalidTreasuryAccount);
+
+        let (amount_x, amount_y) = (config.protocol_fees_x, config.protocol_fees_y);
+        if amount_x == 0 && amount_y == 0 {
+            return Ok(());
+        }
+
+        self.transfer_from_vault(&config, vault_x, treasury_x, amount_x)?;
+        self.transfer_from_vault(&config, vault_y, treasury_y, amount_y)?;
+
+        config.protocol_fees_x = 0;
+        config.protocol_fees_y = 0;
+        config.exit(&crate::ID)?;
+
+        emit!(FeesCollectedEvent {
+            config: expected,
+            treasury,
+            amount_x,
+            amount_y,
+        });
+
+        Ok(())
+    }
+
+    /// Transfers `amount` from a pool vault to a treasury account, signed by the pool's config PDA.
+    fn transfer_from_vault(
+        &self,
+        config: &Account<'info, Config>,
+        from: &AccountInfo<'info>,
+        to: &AccountInfo<'info>,
+        amount: u64,
+    ) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let seeds = crate::config_signer_seeds!(config);
+        let signer_seeds = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: from.clone(),
+            to: to.clone(),
+            authority: config.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+        transfer(ctx, amount)
+    }
+}
+
+#[event]
+pub struct FeesCollectedEvent {
+    pub config: Pubkey,
+    pub treasury: Pubkey,
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
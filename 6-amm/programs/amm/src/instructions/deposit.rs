@@ -6,20 +6,38 @@
 // - 'vault_x' and 'vault_y': The pool's token vaults.
 // - 'mint_lp': The LP token mint.
 // - 'user_lp': The user's LP token account.
+// - 'position': The user's position PDA, recording deposit time for the withdraw cooldown
+//   and the LP registered to earn swap fees.
+// - 'fee_state': The pool's LP fee index (may not exist yet).
+// - 'locked_position' and 'locked_lp': The user's LP lock record and escrow, only for locked deposits.
 //
 // The deposit flow:
 // - User transfers tokens X and Y to the pool vaults.
 // - The program mints LP tokens to the user, representing their share of the pool.
+//   With a non-zero lock duration the LP is minted into the escrow instead, and the lock's
+//   unlock time and fee-share boost are recorded in the user's locked position.
 // - Proportional math ensures fair share for all liquidity providers.
+// - The deposit time (and the resulting unlock time) is stored in the user's position.
+// - Fees earned on the user's earlier LP are settled into the position before its weight changes.
+//   Unlocked LP is registered as is; locked LP is registered scaled by the lock's boost.
+//   Pools without a fee state register nothing.
+// - The amounts actually pulled in and the LP minted are returned as return data, so CPI
+//   callers do not have to diff balances.
 
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::{ Transfer, transfer, Mint, Token, TokenAccount, MintTo, mint_to },
 };
-use constant_product_curve::ConstantProduct;
-
-use crate::{ state::Config, error::AmmError };
+use crate::{
+    state::{ Config, FeeState, LockedPosition, Position },
+    error::AmmError,
+    constants::{
+        FULL_BOOST_LOCK_DURATION, LP_DECIMALS, MAX_LOCK_BOOST_BPS, MAX_LOCK_DURATION, MIN_DEPOSIT_TOKENS,
+        min_deposit_lp,
+    },
+    math,
+};
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
@@ -31,8 +49,9 @@ pub struct Deposit<'info> {
     /// The mint for token Y.
     pub mint_y: Account<'info, Mint>,
 
-    /// The config PDA for the pool.
+    /// The config PDA for the pool. Mutable to release LP fees no position can claim.
     #[account(
+        mut,
         has_one = mint_x,
         has_one = mint_y,
         seeds = [b"config", config.seed.to_le_bytes().as_ref()],
@@ -61,7 +80,7 @@ pub struct Deposit<'info> {
         mut,
         seeds = [b"lp", config.key().as_ref()],
         bump = config.lp_bump,
-        mint::decimals = 6,
+        mint::decimals = LP_DECIMALS,
         mint::authority = config,
     )]
     pub mint_lp: Account<'info, Mint>,
@@ -91,11 +110,59 @@ pub struct Deposit<'info> {
     )]
     pub user_lp: Account<'info, TokenAccount>,
 
+    /// The user's position in this pool, created on first deposit.
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"position", config.key().as_ref(), user.key().as_ref()],
+        bump,
+        space = 8 + Position::INIT_SPACE,
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    /// The pool's LP fee index, tracking the LP registered to earn fees.
+    /// CHECK: Address is pinned by seeds; the account may not be initialized, which means no LP is registered.
+    #[account(
+        mut,
+        seeds = [b"fee_state", config.key().as_ref()],
+        bump
+    )]
+    pub fee_state: UncheckedAccount<'info>,
+
+    /// The user's LP lock record. Required for locked deposits, omitted otherwise.
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"locked_position", config.key().as_ref(), user.key().as_ref()],
+        bump,
+        space = 8 + LockedPosition::INIT_SPACE,
+    )]
+    pub locked_position: Option<Box<Account<'info, LockedPosition>>>,
+
+    /// Escrow holding the user's locked LP, owned by the config. Required for locked deposits.
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"locked_lp", config.key().as_ref(), user.key().as_ref()],
+        bump,
+        token::mint = mint_lp,
+        token::authority = config,
+    )]
+    pub locked_lp: Option<Box<Account<'info, TokenAccount>>>,
+
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
+/// What a deposit actually moved, returned by `deposit` via return data.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct DepositResult {
+    pub x_deposited: u64,
+    pub y_deposited: u64,
+    pub lp_minted: u64,
+}
+
 impl<'info> Deposit<'info> {
     /// Transfers tokens from the user to the pool vaults.
     pub fn deposit_tokens(&mut self, is_x: bool, amount: u64) -> Result<()> {
@@ -117,15 +184,20 @@ impl<'info> Deposit<'info> {
 
     /// Mints LP tokens to the user, using the config PDA as authority.
     pub fn mint_lp_tokens(&mut self, amount: u64) -> Result<()> {
+        self.mint_lp_to(self.user_lp.to_account_info(), amount)
+    }
+
+    /// Mints LP tokens to `to`, using the config PDA as authority.
+    fn mint_lp_to(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
         let cpi_program = self.token_program.to_account_info();
 
         let cpi_accounts = MintTo {
             mint: self.mint_lp.to_account_info(),
-            to: self.user_lp.to_account_info(),
+            to,
             authority: self.config.to_account_info(),
         };
 
-        let seeds = &[&b"config"[..], &self.config.seed.to_le_bytes(), &[self.config.config_bump]];
+        let seeds = crate::config_signer_seeds!(self.config);
         let signer_seeds = &[&seeds[..]];
 
         let ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
@@ -134,39 +206,142 @@ impl<'info> Deposit<'info> {
     }
 
     /// Handles the main deposit logic: proportional math, slippage checks, and LP minting.
-    pub fn deposit(&mut self, amount: u64, max_x: u64, max_y: u64) -> Result<()> {
+    /// A non-zero `lock_duration` (seconds) locks the minted LP in escrow until it elapses.
+    pub fn deposit(&mut self, amount: u64, max_x: u64, max_y: u64, lock_duration: i64, bumps: DepositBumps) -> Result<DepositResult> {
         // Check if pool is locked
         require!(!self.config.locked, AmmError::PoolLocked);
-        require!(amount != 0, AmmError::InvalidAmount);
+        require!(!self.config.deposits_closed, AmmError::DepositsClosed);
+        require!(amount != 0, AmmError::ZeroLpAmount);
+        require!((0..=MAX_LOCK_DURATION).contains(&lock_duration), AmmError::InvalidLockDuration);
+
+        // Protocol fees owed to the treasury are not part of the LPs' reserves
+        let (reserve_x, reserve_y) = self.config.net_reserves(self.vault_x.amount, self.vault_y.amount)?;
 
         let (x, y) = if self.mint_lp.supply == 0 &&
-            self.vault_x.amount == 0 &&
-            self.vault_y.amount == 0
+            reserve_x == 0 &&
+            reserve_y == 0
         {
             // First deposit - use max amounts
             (max_x, max_y)
         } else {
-            // Subsequent deposits - calculate proportional amounts
-            let amounts = ConstantProduct::xy_deposit_amounts_from_l(
-                self.vault_x.amount,
-                self.vault_y.amount,
+            // Subsequent deposits - calculate proportional amounts, rounded up in the pool's favor.
+            // Proportional joins keep every reserve ratio fixed, so this holds for weighted pools too.
+            math::deposit_amounts(
+                reserve_x,
+                reserve_y,
                 self.mint_lp.supply,
                 amount,
-                6
-            ).map_err(|_| AmmError::InvalidAmount)?;
-            (amounts.x, amounts.y)
+                self.config.decimals_x,
+                self.config.decimals_y,
+            ).ok_or(AmmError::CurveMathFailed)?
         };
 
         // Check slippage
         require!(x <= max_x && y <= max_y, AmmError::SlippageExceeded);
 
+        // Reject dust positions that would make proportional math round worse for everyone
+        require!(amount >= min_deposit_lp(self.mint_lp.decimals), AmmError::DepositTooSmall);
+        require!(x >= MIN_DEPOSIT_TOKENS && y >= MIN_DEPOSIT_TOKENS, AmmError::DepositTooSmall);
+        require!(self.user_x.amount >= x && self.user_y.amount >= y, AmmError::InsufficientUserBalance);
+
+        // Guarded launches cap how much each vault may hold
+        self.config.check_deposit_cap(self.vault_x.amount, self.vault_y.amount, x, y)?;
+
         // Perform the deposits
         self.deposit_tokens(true, x)?;
         self.deposit_tokens(false, y)?;
 
-        // Mint LP tokens
-        self.mint_lp_tokens(amount)?;
+        // Mint LP tokens, straight into escrow for a locked deposit
+        if lock_duration == 0 {
+            self.register_lp(amount)?;
+            self.mint_lp_tokens(amount)?;
+        } else {
+            self.lock_lp(amount, lock_duration, &bumps)?;
+            self.register_lp(0)?;
+        }
+
+        self.position.record_deposit(
+            self.user.key(),
+            self.config.key(),
+            self.config.withdraw_cooldown_secs,
+            bumps.position,
+        )?;
+
+        emit!(DepositEvent {
+            user: self.user.key(),
+            lp_amount: amount,
+            amount_x: x,
+            amount_y: y,
+        });
+
+        Ok(DepositResult {
+            x_deposited: x,
+            y_deposited: y,
+            lp_minted: amount,
+        })
+    }
 
+    /// Settles the user's position and registers `lp_amount` new unlocked LP along with the
+    /// current weight of their locked LP. Pools without a fee state register nothing.
+    fn register_lp(&mut self, lp_amount: u64) -> Result<()> {
+        let Some(mut fee_state) = FeeState::load(&self.fee_state)? else {
+            return Ok(());
+        };
+        self.position.add_lp(&mut self.config, &mut fee_state, self.user_lp.amount, lp_amount)?;
+        if let Some(locked_position) = &self.locked_position {
+            self.position.set_locked_weight(&mut fee_state, locked_position.fee_weight()?)?;
+        }
+        fee_state.store(&self.fee_state)
+    }
+
+    /// Mints `amount` LP into the user's escrow and extends their lock. Topping up an existing
+    /// lock keeps the later unlock time and the higher boost.
+    fn lock_lp(&mut self, amount: u64, lock_duration: i64, bumps: &DepositBumps) -> Result<()> {
+        let locked_lp = self.locked_lp.as_ref().ok_or(AmmError::LockAccountsMissing)?;
+        self.mint_lp_to(locked_lp.to_account_info(), amount)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let unlock_ts = now.checked_add(lock_duration).ok_or(AmmError::Overflow)?;
+        let boost_bps = math::lock_boost_bps(lock_duration, FULL_BOOST_LOCK_DURATION, MAX_LOCK_BOOST_BPS)
+            .ok_or(AmmError::InvalidLockDuration)?;
+
+        let (user, config) = (self.user.key(), self.config.key());
+        let locked_position = self.locked_position.as_mut().ok_or(AmmError::LockAccountsMissing)?;
+        locked_position.owner = user;
+        locked_position.config = config;
+        locked_position.lp_amount = locked_position.lp_amount.checked_add(amount).ok_or(AmmError::Overflow)?;
+        locked_position.unlock_ts = locked_position.unlock_ts.max(unlock_ts);
+        locked_position.boost_bps = locked_position.boost_bps.max(boost_bps);
+        locked_position.bump = bumps.locked_position.ok_or(AmmError::LockAccountsMissing)?;
+
+        emit!(LpLockedEvent {
+            user,
+            config,
+            lp_amount: amount,
+            total_locked: locked_position.lp_amount,
+            unlock_ts: locked_position.unlock_ts,
+            boost_bps: locked_position.boost_bps,
+        });
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[event]
+pub struct DepositEvent {
+    pub user: Pubkey,
+    pub lp_amount: u64,
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
+
+#[event]
+pub struct LpLockedEvent {
+    pub user: Pubkey,
+    pub config: Pubkey,
+    /// LP locked by this deposit.
+    pub lp_amount: u64,
+    /// LP in escrow for the user after this deposit.
+    pub total_locked: u64,
+    pub unlock_ts: i64,
+    pub boost_bps: u16,
+}
@@ -0,0 +1,112 @@
+// This file defines the 'ExtendConfig' instruction for the AMM program.
+// It grows a pool's config account up to the current `Config` size so pools created
+// before the reserved region existed can be read with the new layout, and creates the
+// pool's fee state for pools created before it existed.
+//
+// Key roles:
+// - 'payer': Whoever funds the extra rent (usually the pool authority or a crank).
+// - 'config': The pool's configuration PDA, loaded raw because older layouts are too short to deserialize.
+// - 'mint_x' and 'mint_y': The pool's mints, read to backfill cached decimals.
+// - 'fee_state': The pool's LP fee index, created if missing.
+//
+// The extend flow:
+// - Verifies the account is a config owned by this program.
+// - Tops up the rent-exempt balance for the new size and reallocates with zeroed bytes.
+// - Writes legacy defaults into the new fields so the pool behaves exactly as before.
+// - Creates the fee state if the pool has none, so swap fees start accruing to registered LP.
+// - Returns early when the account is already large enough, so it is safe to call repeatedly.
+
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+    Discriminator,
+};
+use anchor_spl::token::Mint;
+
+use crate::{ state::{ Config, FeeState }, error::AmmError };
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct ExtendConfig<'info> {
+    /// The account paying for the additional rent.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The config PDA for the pool.
+    /// CHECK: Owner and discriminator are validated in the handler; older configs cannot be deserialized.
+    #[account(
+        mut,
+        seeds = [b"config", seed.to_le_bytes().as_ref()],
+        bump,
+        owner = crate::ID,
+    )]
+    pub config: UncheckedAccount<'info>,
+    /// The mint for token X, checked against the config after it is loaded.
+    pub mint_x: Account<'info, Mint>,
+    /// The mint for token Y, checked against the config after it is loaded.
+    pub mint_y: Account<'info, Mint>,
+    /// The pool's LP fee index, created for pools that predate it.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"fee_state", config.key().as_ref()],
+        bump,
+        space = 8 + FeeState::INIT_SPACE,
+    )]
+    pub fee_state: Box<Account<'info, FeeState>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ExtendConfig<'info> {
+    /// Reallocates the config to `8 + Config::INIT_SPACE`, charging any rent difference to the payer,
+    /// and fills in a newly created fee state.
+    pub fn extend(&mut self, bumps: ExtendConfigBumps) -> Result<()> {
+        let config = self.config.to_account_info();
+
+        {
+            let data = config.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data.starts_with(Config::DISCRIMINATOR),
+                AmmError::InvalidConfigAccount
+            );
+        }
+
+        if self.fee_state.config == Pubkey::default() {
+            self.fee_state.set_inner(FeeState {
+                config: config.key(),
+                fee_growth_global_x: 0,
+                fee_growth_global_y: 0,
+                total_weight: 0,
+                bump: bumps.fee_state,
+            });
+        }
+
+        let new_len = 8 + Config::INIT_SPACE;
+        if config.data_len() >= new_len {
+            return Ok(());
+        }
+
+        // Top up rent before growing so the account stays rent exempt
+        let required = Rent::get()?.minimum_balance(new_len);
+        let shortfall = required.saturating_sub(config.lamports());
+        if shortfall > 0 {
+            let cpi_accounts = Transfer {
+                from: self.payer.to_account_info(),
+                to: config.clone(),
+            };
+            let ctx = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+            transfer(ctx, shortfall)?;
+        }
+
+        // New bytes are zeroed; fields carved from the reserved region then get their legacy defaults
+        config.realloc(new_len, true)?;
+
+        let mut data = config.try_borrow_mut_data()?;
+        let mut state = Config::try_deserialize(&mut &data[..])?;
+        require_keys_eq!(state.mint_x, self.mint_x.key(), AmmError::InvalidToken);
+        require_keys_eq!(state.mint_y, self.mint_y.key(), AmmError::InvalidToken);
+        state.apply_legacy_defaults(self.mint_x.decimals, self.mint_y.decimals);
+        state.try_serialize(&mut &mut data[..])?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+// This file defines the 'GetPoolCount' instruction for the AMM program.
+// It is a read-only view that returns how many pools have been registered via return data.
+//
+// Key roles:
+// - 'registry': The global pool registry PDA.
+//
+// Clients use the count to work out how many registry pages to fetch:
+// page 0 is the registry itself and page N lives at [b"registry", N].
+
+use anchor_lang::prelude::*;
+
+use crate::state::Registry;
+
+#[derive(Accounts)]
+pub struct GetPoolCount<'info> {
+    /// The global pool registry.
+    #[account(
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, Registry>,
+}
+
+impl<'info> GetPoolCount<'info> {
+    /// Returns the number of registered pools across all pages.
+    pub fn get_pool_count(&self) -> Result<u64> {
+        Ok(self.registry.pool_count)
+    }
+}
@@ -0,0 +1,86 @@
+// This file defines the 'GetPoolStats' instruction for the AMM program.
+// It is a read-only view that returns a pool's headline figures via return data, so
+// frontends can render a pool row from a single simulated call.
+//
+// Key roles:
+// - 'config': The pool's configuration PDA.
+// - 'vault_x' and 'vault_y': The pool's token vaults, read for current reserves.
+// - 'mint_lp': The LP token mint, read for the LP supply.
+//
+// Reserves are net of protocol fees owed to the treasury. Nothing is written.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
+
+use crate::{ state::Config, constants::LABEL_LEN };
+
+#[derive(Accounts)]
+pub struct GetPoolStats<'info> {
+    /// The mint for token X.
+    pub mint_x: Account<'info, Mint>,
+    /// The mint for token Y.
+    pub mint_y: Account<'info, Mint>,
+    /// The config PDA for the pool.
+    #[account(
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+    /// The pool's vault for token X.
+    #[account(
+        associated_token::mint = mint_x,
+        associated_token::authority = config
+    )]
+    pub vault_x: Account<'info, TokenAccount>,
+    /// The pool's vault for token Y.
+    #[account(
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+    )]
+    pub vault_y: Account<'info, TokenAccount>,
+    /// The LP token mint.
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+    )]
+    pub mint_lp: Account<'info, Mint>,
+}
+
+/// Pool summary returned by `get_pool_stats`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PoolStats {
+    /// Token X reserve owned by LPs, excluding protocol fees and unclaimed LP fees.
+    pub reserve_x: u64,
+    /// Token Y reserve owned by LPs, excluding protocol fees and unclaimed LP fees.
+    pub reserve_y: u64,
+    pub lp_supply: u64,
+    pub fee: u16,
+    pub weight_x: u8,
+    pub weight_y: u8,
+    pub locked: bool,
+    pub deposits_closed: bool,
+    /// Zero-padded UTF-8 label; all zeroes when the pool has none.
+    pub label: [u8; LABEL_LEN],
+}
+
+impl<'info> GetPoolStats<'info> {
+    /// Collects the pool's current reserves, supply and settings.
+    pub fn get_pool_stats(&self) -> Result<PoolStats> {
+        let (reserve_x, reserve_y) = self.config.net_reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let (weight_x, weight_y) = self.config.weights();
+
+        Ok(PoolStats {
+            reserve_x,
+            reserve_y,
+            lp_supply: self.mint_lp.supply,
+            fee: self.config.fee,
+            weight_x,
+            weight_y,
+            locked: self.config.locked,
+            deposits_closed: self.config.deposits_closed,
+            label: self.config.label,
+        })
+    }
+}
@@ -0,0 +1,39 @@
+// This file defines the 'GetSnapshot' instruction for the AMM program.
+// It is a read-only view that returns one of the pool's recorded snapshots via return data.
+//
+// Key roles:
+// - 'config': The pool's configuration PDA.
+// - 'snapshots': The pool's snapshot ring buffer.
+//
+// Index 0 is the most recent snapshot, 1 the one before it, and so on back to the
+// oldest entry still held in the buffer.
+
+use anchor_lang::prelude::*;
+
+use crate::{ state::{ Config, Snapshot, Snapshots }, error::AmmError };
+
+#[derive(Accounts)]
+pub struct GetSnapshot<'info> {
+    /// The config PDA for the pool.
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+    /// The pool's snapshot ring buffer.
+    #[account(
+        seeds = [b"snapshots", config.key().as_ref()],
+        bump = snapshots.bump
+    )]
+    pub snapshots: Account<'info, Snapshots>,
+}
+
+impl<'info> GetSnapshot<'info> {
+    /// Returns the snapshot `index` steps back from the latest.
+    pub fn get_snapshot(&self, index: u32) -> Result<Snapshot> {
+        self.snapshots
+            .get(index)
+            .copied()
+            .ok_or(AmmError::SnapshotNotFound.into())
+    }
+}
@@ -0,0 +1,86 @@
+// This file defines the 'GetSpotPrice' instruction for the AMM program.
+// It is a read-only view that returns the pool's decimal-normalized spot price via return data.
+//
+// Key roles:
+// - 'config': The pool's configuration PDA.
+// - 'vault_x' and 'vault_y': The pool's token vaults, read for current reserves.
+// - 'mint_x' and 'mint_y': Read for their decimals.
+//
+// The spot price flow:
+// - Reads both reserves and normalizes them by 10^decimals (and by weight for weighted pools).
+// - Returns both directions of the price in Q64.64 fixed point.
+// - Nothing is written, so clients can call it through simulation.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
+
+use crate::{ state::Config, error::AmmError, math };
+
+#[derive(Accounts)]
+pub struct GetSpotPrice<'info> {
+    /// The mint for token X.
+    pub mint_x: Account<'info, Mint>,
+    /// The mint for token Y.
+    pub mint_y: Account<'info, Mint>,
+    /// The config PDA for the pool.
+    #[account(
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+    /// The pool's vault for token X.
+    #[account(
+        associated_token::mint = mint_x,
+        associated_token::authority = config
+    )]
+    pub vault_x: Account<'info, TokenAccount>,
+    /// The pool's vault for token Y.
+    #[account(
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+    )]
+    pub vault_y: Account<'info, TokenAccount>,
+}
+
+/// Spot price returned by `get_spot_price`.
+/// Both prices are Q64.64 and already normalized by each mint's decimals.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SpotPrice {
+    /// Whole units of Y per whole unit of X.
+    pub price_x_in_y: u128,
+    /// Whole units of X per whole unit of Y.
+    pub price_y_in_x: u128,
+    pub decimals_x: u8,
+    pub decimals_y: u8,
+}
+
+impl<'info> GetSpotPrice<'info> {
+    /// Computes the spot price from the current vault balances.
+    pub fn get_spot_price(&self) -> Result<SpotPrice> {
+        let (reserve_x, reserve_y) = self.config.net_reserves(self.vault_x.amount, self.vault_y.amount)?;
+        require!(
+            reserve_x > 0 && reserve_y > 0,
+            AmmError::NoLiquidityInPool
+        );
+
+        let (decimals_x, decimals_y) = (self.mint_x.decimals, self.mint_y.decimals);
+        let (weight_x, weight_y) = self.config.weights();
+        let (price_x_in_y, price_y_in_x) = math::spot_price_q64(
+            reserve_x,
+            reserve_y,
+            decimals_x,
+            decimals_y,
+            weight_x,
+            weight_y,
+        ).ok_or(AmmError::Overflow)?;
+
+        Ok(SpotPrice {
+            price_x_in_y,
+            price_y_in_x,
+            decimals_x,
+            decimals_y,
+        })
+    }
+}
@@ -6,15 +6,33 @@
 // - 'config': The pool's configuration PDA.
 // - 'vault_x' and 'vault_y': The pool's token vaults.
 // - 'mint_lp': The LP token mint (PDA, authority = config).
+// - 'fee_state': The pool's LP fee index.
+// - 'registry' and 'registry_page': The global pool index the new config is appended to.
+// - 'global_state' and 'creation_fee_treasury': Program-wide creation settings and the wallet paid
+//   the creation fee. The global state may be omitted only with the `global-state-rollout` feature.
+// - 'badge_x' and 'badge_y': The mints' approval badges, required while creation is permissioned.
 //
 // The initialize flow:
-// - Creates the config, vaults, and LP mint with deterministic seeds.
+// - Rejects creation while paused, charges the creation fee, and resolves `u16::MAX` to the default fee.
+// - While creation is permissioned, rejects mints without a badge.
+// - Rejects mints that are themselves LP mints of this program, so pools cannot be nested.
+// - Creates the config, vaults, LP mint and fee state with deterministic seeds.
 // - Sets up pool parameters (fee, authority, etc).
+// - Stores the optional human-readable label and emits a PoolCreatedEvent.
+// - Registers the config in the registry, creating the registry or a new overflow page when needed.
 
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    solana_program::program_option::COption,
+    system_program::{ transfer, Transfer },
+};
 use anchor_spl::{associated_token::AssociatedToken, token::{Mint, Token, TokenAccount}};
 
-use crate::state::Config;
+use crate::{
+    state::{ Config, FeeState, GlobalState, MintBadge, Registry, RegistryPage },
+    error::AmmError,
+    constants::{ LABEL_LEN, LP_DECIMALS },
+};
 
 #[derive(Accounts)]
 #[instruction(seed: u64)]
@@ -32,7 +50,7 @@ pub struct Initialize<'info> {
         payer = initializer,
         seeds = [b"lp", config.key().as_ref()],
         bump,
-        mint::decimals = 6,
+        mint::decimals = LP_DECIMALS,
         mint::authority = config,
     )]
     pub mint_lp: Account<'info, Mint>,
@@ -45,6 +63,15 @@ pub struct Initialize<'info> {
         space = 8 + Config::INIT_SPACE,
     )]
     pub config: Account<'info, Config>,
+    /// The pool's LP fee index.
+    #[account(
+        init,
+        payer = initializer,
+        seeds = [b"fee_state", config.key().as_ref()],
+        bump,
+        space = 8 + FeeState::INIT_SPACE,
+    )]
+    pub fee_state: Box<Account<'info, FeeState>>,
     /// The pool's vault for token X.
     #[account(
         init,
@@ -61,6 +88,46 @@ pub struct Initialize<'info> {
         associated_token::authority = config
     )]
     pub vault_y: Account<'info, TokenAccount>,
+    /// The global pool registry, created alongside the first pool.
+    #[account(
+        init_if_needed,
+        payer = initializer,
+        seeds = [b"registry"],
+        bump,
+        space = 8 + Registry::INIT_SPACE,
+    )]
+    pub registry: Box<Account<'info, Registry>>,
+    /// The overflow page the pool is recorded on once the registry itself is full.
+    /// Must be omitted while the pool still fits on page 0.
+    #[account(
+        init_if_needed,
+        payer = initializer,
+        seeds = [b"registry", registry.next_page().to_le_bytes().as_ref()],
+        bump,
+        space = 8 + RegistryPage::INIT_SPACE,
+    )]
+    pub registry_page: Option<Box<Account<'info, RegistryPage>>>,
+    /// Program-wide pool creation settings.
+    #[account(
+        seeds = [b"global"],
+        bump = global_state.bump
+    )]
+    pub global_state: Option<Box<Account<'info, GlobalState>>>,
+    /// The global state's treasury, paid the pool creation fee. Required when that fee is non-zero.
+    #[account(mut)]
+    pub creation_fee_treasury: Option<SystemAccount<'info>>,
+    /// Approval badge of mint_x. Required while pool creation is permissioned.
+    #[account(
+        seeds = [b"badge", mint_x.key().as_ref()],
+        bump = badge_x.bump
+    )]
+    pub badge_x: Option<Box<Account<'info, MintBadge>>>,
+    /// Approval badge of mint_y. Required while pool creation is permissioned.
+    #[account(
+        seeds = [b"badge", mint_y.key().as_ref()],
+        bump = badge_y.bump
+    )]
+    pub badge_y: Option<Box<Account<'info, MintBadge>>>,
     /// Standard program accounts required for CPI and ATA creation.
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -69,7 +136,16 @@ pub struct Initialize<'info> {
 
 impl<'info> Initialize<'info> {
     /// Initializes the config state with pool parameters and bumps.
-    pub fn init(&mut self, seed: u64, fee: u16, authority: Option<Pubkey>, bumps: InitializeBumps) -> Result<()> {
+    pub fn init(&mut self, seed: u64, fee: u16, authority: Option<Pubkey>, weight_x: u8, weight_y: u8, fee_on_input: bool, label: String, bumps: InitializeBumps) -> Result<()> {
+        require!(
+            weight_x > 0 && weight_y > 0 && weight_x as u16 + weight_y as u16 == 100,
+            AmmError::InvalidWeights
+        );
+        let label = Config::encode_label(&label)?;
+        let fee = self.apply_global_state(fee)?;
+        Self::check_not_lp_mint(&self.mint_x)?;
+        Self::check_not_lp_mint(&self.mint_y)?;
+
         self.config.set_inner(
             Config { 
                 seed, 
@@ -80,7 +156,123 @@ impl<'info> Initialize<'info> {
                 locked: false, 
                 config_bump: bumps.config, 
                 lp_bump: bumps.mint_lp, 
+                weight_x,
+                weight_y,
+                fee_on_input,
+                decimals_x: self.mint_x.decimals,
+                decimals_y: self.mint_y.decimals,
+                protocol_fees_x: 0,
+                protocol_fees_y: 0,
+                deposit_cap_x: 0,
+                deposit_cap_y: 0,
+                withdraw_cooldown_secs: 0,
+                deposits_closed: false,
+                label,
+                lock_reason: 0,
+                locked_at: 0,
+                lp_fees_owed_x: 0,
+                lp_fees_owed_y: 0,
+                _reserved: [0; 29],
             });
+        self.fee_state.set_inner(FeeState {
+            config: self.config.key(),
+            fee_growth_global_x: 0,
+            fee_growth_global_y: 0,
+            total_weight: 0,
+            bump: bumps.fee_state,
+        });
+
+        self.register(bumps)?;
+
+        emit!(PoolCreatedEvent {
+            config: self.config.key(),
+            creator: self.initializer.key(),
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            fee,
+            weight_x,
+            weight_y,
+            label,
+        });
+
+        Ok(())
+    }
+
+    /// Enforces the global pool creation settings and returns the pool fee to use.
+    fn apply_global_state(&self, fee: u16) -> Result<u16> {
+        let Some(global_state) = &self.global_state else {
+            require!(cfg!(feature = "global-state-rollout"), AmmError::GlobalStateRequired);
+            require!(fee != u16::MAX, AmmError::GlobalStateRequired);
+            return Ok(fee);
+        };
+        require!(!global_state.pool_creation_paused, AmmError::PoolCreationPaused);
+        if global_state.permissioned_creation {
+            require!(self.badge_x.is_some() && self.badge_y.is_some(), AmmError::MintNotApproved);
+        }
+
+        let lamports = global_state.pool_creation_fee_lamports;
+        if lamports > 0 {
+            let treasury = self.creation_fee_treasury.as_ref().ok_or(AmmError::InvalidTreasuryAccount)?;
+            require_keys_eq!(treasury.key(), global_state.treasury, AmmError::InvalidTreasuryAccount);
+
+            let cpi_accounts = Transfer {
+                from: self.initializer.to_account_info(),
+                to: treasury.to_account_info(),
+            };
+            transfer(CpiContext::new(self.system_program.to_account_info(), cpi_accounts), lamports)?;
+        }
+
+        Ok(if fee == u16::MAX { global_state.default_fee } else { fee })
+    }
+
+    /// Rejects an LP mint of this program. Every LP mint lives at `[b"lp", config]` with the
+    /// config as its mint authority, so re-deriving that address from the mint's authority
+    /// identifies one without needing the parent config account.
+    fn check_not_lp_mint(mint: &Account<'info, Mint>) -> Result<()> {
+        if let COption::Some(authority) = mint.mint_authority {
+            let (lp_mint, _) = Pubkey::find_program_address(&[b"lp", authority.as_ref()], &crate::ID);
+            require_keys_neq!(lp_mint, mint.key(), AmmError::NestedPoolNotAllowed);
+        }
+        Ok(())
+    }
+
+    /// Appends the new config to the registry, or to the overflow page it now spills into.
+    fn register(&mut self, bumps: InitializeBumps) -> Result<()> {
+        let config = self.config.key();
+        let page = self.registry.next_page();
+
+        self.registry.bump = bumps.registry;
+        if page == 0 {
+            require!(self.registry_page.is_none(), AmmError::InvalidRegistryPage);
+            self.registry.pools.push(config);
+        } else {
+            let registry_page = self
+                .registry_page
+                .as_mut()
+                .ok_or(AmmError::InvalidRegistryPage)?;
+            registry_page.page_index = page;
+            registry_page.bump = bumps.registry_page.ok_or(AmmError::InvalidRegistryPage)?;
+            registry_page.pools.push(config);
+        }
+
+        self.registry.pool_count = self
+            .registry
+            .pool_count
+            .checked_add(1)
+            .ok_or(AmmError::Overflow)?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[event]
+pub struct PoolCreatedEvent {
+    pub config: Pubkey,
+    pub creator: Pubkey,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub fee: u16,
+    pub weight_x: u8,
+    pub weight_y: u8,
+    /// Zero-padded UTF-8 label; all zeroes when the pool has none.
+    pub label: [u8; LABEL_LEN],
+}
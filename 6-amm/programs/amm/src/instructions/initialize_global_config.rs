@@ -0,0 +1,61 @@
+// This file defines the 'InitializeGlobalConfig' instruction for the AMM program.
+// It creates the program-wide config that holds the protocol fee switch and treasury.
+//
+// Key roles:
+// - 'admin': Pays for and becomes the admin of the global config.
+// - 'global_config': The singleton PDA at [b"global_config"].
+//
+// The initialize flow:
+// - Creates the global config once; later calls fail because the PDA already exists.
+// - Stores the admin, the starting protocol fee, and the treasury wallet.
+// - Until this runs, swaps take no protocol fee.
+
+use anchor_lang::prelude::*;
+
+use crate::{ state::GlobalConfig, error::AmmError };
+
+#[derive(Accounts)]
+pub struct InitializeGlobalConfig<'info> {
+    /// The admin of the global config.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// The global config PDA.
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"global_config"],
+        bump,
+        space = 8 + GlobalConfig::INIT_SPACE,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeGlobalConfig<'info> {
+    /// Stores the admin, protocol fee, and treasury.
+    pub fn init(&mut self, default_protocol_fee_bps: u16, treasury: Pubkey, bumps: InitializeGlobalConfigBumps) -> Result<()> {
+        require!(default_protocol_fee_bps <= 10_000, AmmError::InvalidProtocolFee);
+
+        self.global_config.set_inner(GlobalConfig {
+            admin: self.admin.key(),
+            default_protocol_fee_bps,
+            treasury,
+            bump: bumps.global_config,
+        });
+
+        emit!(GlobalConfigInitializedEvent {
+            admin: self.admin.key(),
+            default_protocol_fee_bps,
+            treasury,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct GlobalConfigInitializedEvent {
+    pub admin: Pubkey,
+    pub default_protocol_fee_bps: u16,
+    pub treasury: Pubkey,
+}
@@ -0,0 +1,67 @@
+// This file defines the 'InitializeGlobalState' instruction for the AMM program.
+// It creates the program-wide pool creation settings owned by the AMM admin.
+//
+// Key roles:
+// - 'admin': Pays for and becomes the admin of the global state.
+// - 'global_state': The singleton PDA at [b"global"].
+//
+// The initialize flow:
+// - Creates the global state once; later calls fail because the PDA already exists.
+// - Stores the admin, the default pool fee, the pool creation fee and its treasury.
+// - Pool creation starts unpaused and open to any mints.
+
+use anchor_lang::prelude::*;
+
+use crate::{ state::GlobalState, error::AmmError };
+
+#[derive(Accounts)]
+pub struct InitializeGlobalState<'info> {
+    /// The admin of the global state.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// The global state PDA.
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"global"],
+        bump,
+        space = 8 + GlobalState::INIT_SPACE,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeGlobalState<'info> {
+    /// Stores the admin and the pool creation defaults.
+    pub fn init(&mut self, default_fee: u16, pool_creation_fee_lamports: u64, treasury: Pubkey, bumps: InitializeGlobalStateBumps) -> Result<()> {
+        require!(default_fee <= 10_000, AmmError::InvalidFee);
+
+        self.global_state.set_inner(GlobalState {
+            admin: self.admin.key(),
+            default_fee,
+            pool_creation_paused: false,
+            pool_creation_fee_lamports,
+            treasury,
+            bump: bumps.global_state,
+            permissioned_creation: false,
+            _reserved: [0; 31],
+        });
+
+        emit!(GlobalStateInitializedEvent {
+            admin: self.admin.key(),
+            default_fee,
+            pool_creation_fee_lamports,
+            treasury,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct GlobalStateInitializedEvent {
+    pub admin: Pubkey,
+    pub default_fee: u16,
+    pub pool_creation_fee_lamports: u64,
+    pub treasury: Pubkey,
+}
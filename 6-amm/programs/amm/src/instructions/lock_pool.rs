@@ -0,0 +1,39 @@
+// This file defines the 'LockPool' instruction for the AMM program.
+// It lets the pool authority halt deposits, swaps and withdrawals, recording why.
+//
+// Key roles:
+// - 'authority': The pool's update authority; must sign.
+// - 'config': The pool's configuration PDA.
+//
+// The authority may lock for `Manual` or `Migration` reasons. `CircuitBreaker` is reserved
+// for automated locks so integrators can trust it was not set by hand.
+
+use anchor_lang::prelude::*;
+
+use crate::{ state::{ AdminAction, Config, LockReason }, error::AmmError };
+
+#[derive(Accounts)]
+pub struct LockPool<'info> {
+    /// The pool's update authority.
+    pub authority: Signer<'info>,
+    /// The config PDA for the pool.
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> LockPool<'info> {
+    /// Locks the pool with the given reason.
+    pub fn lock_pool(&mut self, reason: LockReason) -> Result<()> {
+        require!(reason != LockReason::CircuitBreaker, AmmError::InvalidLockReason);
+
+        let config = self.config.key();
+        let authority = self.authority.key();
+        self.config.admin_action(config, &authority, AdminAction::LockPool, 0, 1)?;
+
+        self.config.lock(config, authority, reason)
+    }
+}
@@ -3,8 +3,66 @@ pub mod initialize;
 pub mod deposit;
 pub mod swap;
 pub mod withdraw;
+pub mod extend_config;
+pub mod get_spot_price;
+pub mod quote;
+pub mod get_pool_count;
+pub mod initialize_global_config;
+pub mod set_protocol_fee;
+pub mod collect_protocol_fees;
+pub mod set_deposit_cap;
+pub mod set_withdraw_cooldown;
+pub mod checkpoint;
+pub mod get_snapshot;
+pub mod close_deposits;
+pub mod swap_and_deposit;
+pub mod collect_protocol_fees_multi;
+pub mod set_label;
+pub mod get_pool_stats;
+pub mod lock_pool;
+pub mod unlock_pool;
+pub mod release_locked_lp;
+pub mod initialize_global_state;
+pub mod set_default_fee;
+pub mod set_pool_creation_paused;
+pub mod set_pool_creation_fee;
+pub mod claim_fees;
+pub mod set_permissioned_creation;
+pub mod approve_mint;
+pub mod revoke_mint;
+pub mod burn_lp;
+pub mod sync_position;
 
 pub use initialize::*;
 pub use deposit::*;
 pub use swap::*;
-pub use withdraw::*;
\ No newline at end of file
+pub use withdraw::*;
+pub use extend_config::*;
+pub use get_spot_price::*;
+pub use quote::*;
+pub use get_pool_count::*;
+pub use initialize_global_config::*;
+pub use set_protocol_fee::*;
+pub use collect_protocol_fees::*;
+pub use set_deposit_cap::*;
+pub use set_withdraw_cooldown::*;
+pub use checkpoint::*;
+pub use get_snapshot::*;
+pub use close_deposits::*;
+pub use swap_and_deposit::*;
+pub use collect_protocol_fees_multi::*;
+pub use set_label::*;
+pub use get_pool_stats::*;
+pub use lock_pool::*;
+pub use unlock_pool::*;
+pub use release_locked_lp::*;
+pub use initialize_global_state::*;
+pub use set_default_fee::*;
+pub use set_pool_creation_paused::*;
+pub use set_pool_creation_fee::*;
+pub use claim_fees::*;
+pub use set_permissioned_creation::*;
+pub use approve_mint::*;
+pub use revoke_mint::*;
+pub use burn_lp::*;
+pub use sync_position::*;
\ No newline at end of file
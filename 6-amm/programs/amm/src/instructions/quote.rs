@@ -0,0 +1,76 @@
+// This file defines the 'Quote' instruction for the AMM program.
+// It is a read-only view that returns what a swap would pay out right now via return data.
+//
+// Key roles:
+// - 'config': The pool's configuration PDA (fee, fee mode, weights).
+// - 'vault_x' and 'vault_y': The pool's token vaults, read for current reserves.
+//
+// The quote flow:
+// - Reads both reserves in the requested direction.
+// - Runs the same `Config::quote` used by `Swap::swap`, so both fee modes branch identically
+//   and a swap that would breach the reserve floor fails to quote as well.
+// - Nothing is written, so clients can call it through simulation.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
+
+use crate::{ state::Config, error::AmmError };
+
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    /// The mint for token X.
+    pub mint_x: Account<'info, Mint>,
+    /// The mint for token Y.
+    pub mint_y: Account<'info, Mint>,
+    /// The config PDA for the pool.
+    #[account(
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+    /// The pool's vault for token X.
+    #[account(
+        associated_token::mint = mint_x,
+        associated_token::authority = config
+    )]
+    pub vault_x: Account<'info, TokenAccount>,
+    /// The pool's vault for token Y.
+    #[account(
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+    )]
+    pub vault_y: Account<'info, TokenAccount>,
+}
+
+/// Swap quote returned by `quote`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SwapQuote {
+    /// Tokens the user would receive.
+    pub amount_out: u64,
+    /// Fee the pool would keep: input token when `config.fee_on_input`, output token otherwise.
+    pub fee_amount: u64,
+}
+
+impl<'info> Quote<'info> {
+    /// Quotes a swap of `amount_in` in the given direction against the current reserves.
+    pub fn quote(&self, amount_in: u64, x_to_y: bool) -> Result<SwapQuote> {
+        require!(amount_in > 0, AmmError::ZeroAmountIn);
+
+        let (reserve_x, reserve_y) = self.config.net_reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let (reserve_in, reserve_out) = if x_to_y {
+            (reserve_x, reserve_y)
+        } else {
+            (reserve_y, reserve_x)
+        };
+        require!(reserve_in > 0 && reserve_out > 0, AmmError::InsufficientLiquidity);
+
+        let amounts = self.config.quote(reserve_in, reserve_out, amount_in, x_to_y)?;
+
+        Ok(SwapQuote {
+            amount_out: amounts.amount_out,
+            fee_amount: amounts.fee_amount,
+        })
+    }
+}
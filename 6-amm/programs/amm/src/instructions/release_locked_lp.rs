@@ -0,0 +1,139 @@
+// This file defines the 'ReleaseLockedLp' instruction for the AMM program.
+// It returns a user's locked LP tokens from escrow once their lock has expired.
+//
+// Key roles:
+// - 'user': The owner of the locked position.
+// - 'config': The pool's configuration PDA. Mutable to release LP fees no position can claim.
+// - 'locked_position': The user's LP lock record.
+// - 'locked_lp': The escrow holding the locked LP, owned by the config.
+// - 'user_lp': The user's LP token account, created if needed.
+// - 'position': The user's position PDA, holding the LP registered to earn fees.
+// - 'fee_state': The pool's LP fee index (may not exist yet).
+//
+// The release flow:
+// - Fails with PositionLocked until the lock's unlock time.
+// - Transfers the whole escrow balance to the user, signed by the config PDA.
+// - Clears the amount and boost on the locked position so it can be reused for a new lock.
+// - Settles the user's position, then moves the released LP from its boosted locked weight
+//   to its registered LP, where it keeps earning at 1x.
+
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{Transfer, transfer, Mint, Token, TokenAccount},
+};
+
+use crate::{
+    state::{ Config, FeeState, LockedPosition, Position },
+    error::AmmError,
+};
+
+#[derive(Accounts)]
+pub struct ReleaseLockedLp<'info> {
+    /// The owner of the locked LP.
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// The config PDA for the pool. Mutable to release LP fees no position can claim.
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+    /// The LP token mint.
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+    )]
+    pub mint_lp: Account<'info, Mint>,
+    /// The user's LP lock record.
+    #[account(
+        mut,
+        seeds = [b"locked_position", config.key().as_ref(), user.key().as_ref()],
+        bump = locked_position.bump,
+    )]
+    pub locked_position: Box<Account<'info, LockedPosition>>,
+    /// Escrow holding the user's locked LP.
+    #[account(
+        mut,
+        seeds = [b"locked_lp", config.key().as_ref(), user.key().as_ref()],
+        bump,
+        token::mint = mint_lp,
+        token::authority = config,
+    )]
+    pub locked_lp: Account<'info, TokenAccount>,
+    /// The user's LP token account.
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_lp,
+        associated_token::authority = user
+    )]
+    pub user_lp: Account<'info, TokenAccount>,
+    /// The user's position in this pool, created by the locked deposit.
+    #[account(
+        mut,
+        seeds = [b"position", config.key().as_ref(), user.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Box<Account<'info, Position>>,
+    /// The pool's LP fee index, tracking the LP registered to earn fees.
+    /// CHECK: Address is pinned by seeds; the account may not be initialized, which means no LP is registered.
+    #[account(
+        mut,
+        seeds = [b"fee_state", config.key().as_ref()],
+        bump
+    )]
+    pub fee_state: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ReleaseLockedLp<'info> {
+    /// Moves all escrowed LP back to the user if the lock has expired.
+    pub fn release(&mut self) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp >= self.locked_position.unlock_ts,
+            AmmError::PositionLocked
+        );
+
+        let lp_amount = self.locked_lp.amount;
+        require!(lp_amount > 0, AmmError::ZeroLpAmount);
+
+        // Settle at the lock's weight, then move the LP from the locked weight to the registered balance
+        if let Some(mut fee_state) = FeeState::load(&self.fee_state)? {
+            self.position.add_lp(&mut self.config, &mut fee_state, self.user_lp.amount, lp_amount)?;
+            self.position.set_locked_weight(&mut fee_state, 0)?;
+            fee_state.store(&self.fee_state)?;
+        }
+
+        let seeds = crate::config_signer_seeds!(self.config);
+        let signer_seeds = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: self.locked_lp.to_account_info(),
+            to: self.user_lp.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+        transfer(ctx, lp_amount)?;
+
+        self.locked_position.lp_amount = 0;
+        self.locked_position.boost_bps = 0;
+
+        emit!(LpReleasedEvent {
+            user: self.user.key(),
+            config: self.config.key(),
+            lp_amount,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct LpReleasedEvent {
+    pub user: Pubkey,
+    pub config: Pubkey,
+    pub lp_amount: u64,
+}
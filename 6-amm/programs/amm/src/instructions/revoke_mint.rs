@@ -0,0 +1,56 @@
+// This file defines the 'RevokeMint' instruction for the AMM program.
+// It lets the AMM admin withdraw a mint's approval for pool creation.
+//
+// Key roles:
+// - 'admin': The global admin; must sign and receives the badge's rent.
+// - 'global_state': The singleton PDA naming the admin.
+// - 'mint_badge': The approval record being closed.
+//
+// Only new pools are affected; pools already created over the mint keep working.
+
+use anchor_lang::prelude::*;
+
+use crate::{ state::{ GlobalState, MintBadge }, error::AmmError };
+
+#[derive(Accounts)]
+pub struct RevokeMint<'info> {
+    /// The global admin.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// The global state PDA.
+    #[account(
+        seeds = [b"global"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// The badge to revoke, closed to the admin.
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"badge", mint_badge.mint.as_ref()],
+        bump = mint_badge.bump,
+    )]
+    pub mint_badge: Account<'info, MintBadge>,
+}
+
+impl<'info> RevokeMint<'info> {
+    /// Revokes the mint's badge; the account is closed once the instruction succeeds.
+    pub fn revoke_mint(&mut self) -> Result<()> {
+        require_keys_eq!(self.global_state.admin, self.admin.key(), AmmError::InvalidAuthority);
+
+        emit!(MintRevokedEvent {
+            mint: self.mint_badge.mint,
+            admin: self.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct MintRevokedEvent {
+    pub mint: Pubkey,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
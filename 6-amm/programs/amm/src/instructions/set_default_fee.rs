@@ -0,0 +1,42 @@
+// This file defines the 'SetDefaultFee' instruction for the AMM program.
+// It lets the AMM admin change the fee new pools get when created with `fee == u16::MAX`.
+//
+// Key roles:
+// - 'admin': The global admin; must sign.
+// - 'global_state': The singleton PDA holding the pool creation settings.
+//
+// Existing pools keep their own fee.
+
+use anchor_lang::prelude::*;
+
+use crate::{ state::{ GlobalAction, GlobalState }, error::AmmError };
+
+#[derive(Accounts)]
+pub struct SetDefaultFee<'info> {
+    /// The global admin.
+    pub admin: Signer<'info>,
+    /// The global state PDA.
+    #[account(
+        mut,
+        seeds = [b"global"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+impl<'info> SetDefaultFee<'info> {
+    /// Updates the default pool fee in bps.
+    pub fn set_default_fee(&mut self, default_fee: u16) -> Result<()> {
+        require!(default_fee <= 10_000, AmmError::InvalidFee);
+
+        self.global_state.admin_action(
+            &self.admin.key(),
+            GlobalAction::SetDefaultFee,
+            self.global_state.default_fee as u64,
+            default_fee as u64,
+        )?;
+
+        self.global_state.default_fee = default_fee;
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+// This file defines the 'SetDepositCap' instruction for the AMM program.
+// It lets the pool authority cap how much of each token deposits may bring into the vaults.
+//
+// Key roles:
+// - 'authority': The pool's update authority; must sign.
+// - 'config': The pool's configuration PDA.
+//
+// Caps only gate deposits; swaps and withdrawals ignore them. A cap of 0 removes
+// the limit, and caps may be raised or lowered at any time.
+
+use anchor_lang::prelude::*;
+
+use crate::state::{ AdminAction, Config };
+
+#[derive(Accounts)]
+pub struct SetDepositCap<'info> {
+    /// The pool's update authority.
+    pub authority: Signer<'info>,
+    /// The config PDA for the pool.
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetDepositCap<'info> {
+    /// Sets the maximum vault balance for each token. 0 means uncapped.
+    pub fn set_deposit_cap(&mut self, deposit_cap_x: u64, deposit_cap_y: u64) -> Result<()> {
+        let (config, authority) = (self.config.key(), self.authority.key());
+        self.config.admin_action(config, &authority, AdminAction::SetDepositCapX, self.config.deposit_cap_x, deposit_cap_x)?;
+        self.config.admin_action(config, &authority, AdminAction::SetDepositCapY, self.config.deposit_cap_y, deposit_cap_y)?;
+
+        self.config.deposit_cap_x = deposit_cap_x;
+        self.config.deposit_cap_y = deposit_cap_y;
+        Ok(())
+    }
+}
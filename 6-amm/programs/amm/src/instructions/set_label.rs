@@ -0,0 +1,50 @@
+// This file defines the 'SetLabel' instruction for the AMM program.
+// It lets the pool authority change the human-readable label frontends display for the pool.
+//
+// Key roles:
+// - 'authority': The pool's update authority; must sign.
+// - 'config': The pool's configuration PDA.
+//
+// The label is at most 32 bytes of UTF-8; an empty string clears it.
+
+use anchor_lang::prelude::*;
+
+use crate::{ state::{ AdminAction, Config }, constants::LABEL_LEN };
+
+#[derive(Accounts)]
+pub struct SetLabel<'info> {
+    /// The pool's update authority.
+    pub authority: Signer<'info>,
+    /// The config PDA for the pool.
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetLabel<'info> {
+    /// Replaces the pool label.
+    pub fn set_label(&mut self, label: String) -> Result<()> {
+        let label = Config::encode_label(&label)?;
+
+        self.config.admin_action(self.config.key(), &self.authority.key(), AdminAction::SetLabel, 0, 0)?;
+
+        emit!(LabelUpdatedEvent {
+            config: self.config.key(),
+            old_label: self.config.label,
+            new_label: label,
+        });
+
+        self.config.label = label;
+        Ok(())
+    }
+}
+
+#[event]
+pub struct LabelUpdatedEvent {
+    pub config: Pubkey,
+    pub old_label: [u8; LABEL_LEN],
+    pub new_label: [u8; LABEL_LEN],
+}
@@ -0,0 +1,40 @@
+// This file defines the 'SetPermissionedCreation' instruction for the AMM program.
+// It lets the AMM admin restrict pool creation to mints approved with `approve_mint`.
+//
+// Key roles:
+// - 'admin': The global admin; must sign.
+// - 'global_state': The singleton PDA holding the pool creation settings.
+//
+// Existing pools are unaffected, including pools over mints whose badge is later revoked.
+
+use anchor_lang::prelude::*;
+
+use crate::state::{ GlobalAction, GlobalState };
+
+#[derive(Accounts)]
+pub struct SetPermissionedCreation<'info> {
+    /// The global admin.
+    pub admin: Signer<'info>,
+    /// The global state PDA.
+    #[account(
+        mut,
+        seeds = [b"global"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+impl<'info> SetPermissionedCreation<'info> {
+    /// Turns the mint allowlist for new pools on (`true`) or off (`false`).
+    pub fn set_permissioned_creation(&mut self, permissioned: bool) -> Result<()> {
+        self.global_state.admin_action(
+            &self.admin.key(),
+            GlobalAction::SetPermissionedCreation,
+            self.global_state.permissioned_creation as u64,
+            permissioned as u64,
+        )?;
+
+        self.global_state.permissioned_creation = permissioned;
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+// This file defines the 'SetPoolCreationFee' instruction for the AMM program.
+// It lets the AMM admin change the lamports charged for creating a pool.
+//
+// Key roles:
+// - 'admin': The global admin; must sign.
+// - 'global_state': The singleton PDA holding the pool creation settings.
+//
+// Setting 0 makes pool creation free. The fee is paid to the treasury set in the global state.
+
+use anchor_lang::prelude::*;
+
+use crate::state::{ GlobalAction, GlobalState };
+
+#[derive(Accounts)]
+pub struct SetPoolCreationFee<'info> {
+    /// The global admin.
+    pub admin: Signer<'info>,
+    /// The global state PDA.
+    #[account(
+        mut,
+        seeds = [b"global"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+impl<'info> SetPoolCreationFee<'info> {
+    /// Updates the pool creation fee in lamports.
+    pub fn set_pool_creation_fee(&mut self, pool_creation_fee_lamports: u64) -> Result<()> {
+        self.global_state.admin_action(
+            &self.admin.key(),
+            GlobalAction::SetPoolCreationFee,
+            self.global_state.pool_creation_fee_lamports,
+            pool_creation_fee_lamports,
+        )?;
+
+        self.global_state.pool_creation_fee_lamports = pool_creation_fee_lamports;
+        Ok(())
+    }
+}
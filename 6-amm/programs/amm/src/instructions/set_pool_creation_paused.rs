@@ -0,0 +1,40 @@
+// This file defines the 'SetPoolCreationPaused' instruction for the AMM program.
+// It lets the AMM admin stop or resume the creation of new pools.
+//
+// Key roles:
+// - 'admin': The global admin; must sign.
+// - 'global_state': The singleton PDA holding the pool creation settings.
+//
+// Existing pools are unaffected; use `lock_pool` to halt trading in one.
+
+use anchor_lang::prelude::*;
+
+use crate::state::{ GlobalAction, GlobalState };
+
+#[derive(Accounts)]
+pub struct SetPoolCreationPaused<'info> {
+    /// The global admin.
+    pub admin: Signer<'info>,
+    /// The global state PDA.
+    #[account(
+        mut,
+        seeds = [b"global"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+impl<'info> SetPoolCreationPaused<'info> {
+    /// Pauses (`true`) or resumes (`false`) pool creation.
+    pub fn set_pool_creation_paused(&mut self, paused: bool) -> Result<()> {
+        self.global_state.admin_action(
+            &self.admin.key(),
+            GlobalAction::SetPoolCreationPaused,
+            self.global_state.pool_creation_paused as u64,
+            paused as u64,
+        )?;
+
+        self.global_state.pool_creation_paused = paused;
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+// This file defines the 'SetProtocolFee' instruction for the AMM program.
+// It lets the global admin flip the protocol fee switch or change its rate.
+//
+// Key roles:
+// - 'admin': The global admin; must sign.
+// - 'global_config': The singleton PDA holding the switch.
+//
+// The new rate applies to every pool from the next swap on. Fees already
+// accrued stay owed to the treasury.
+
+use anchor_lang::prelude::*;
+
+use crate::{ state::GlobalConfig, error::AmmError };
+
+#[derive(Accounts)]
+pub struct SetProtocolFee<'info> {
+    /// The global admin.
+    pub admin: Signer<'info>,
+    /// The global config PDA.
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+impl<'info> SetProtocolFee<'info> {
+    /// Updates the protocol fee. 0 switches it off.
+    pub fn set_protocol_fee(&mut self, protocol_fee_bps: u16) -> Result<()> {
+        require!(protocol_fee_bps <= 10_000, AmmError::InvalidProtocolFee);
+
+        let old_protocol_fee_bps = self.global_config.default_protocol_fee_bps;
+        self.global_config.default_protocol_fee_bps = protocol_fee_bps;
+
+        emit!(ProtocolFeeUpdatedEvent {
+            admin: self.admin.key(),
+            old_protocol_fee_bps,
+            new_protocol_fee_bps: protocol_fee_bps,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct ProtocolFeeUpdatedEvent {
+    pub admin: Pubkey,
+    pub old_protocol_fee_bps: u16,
+    pub new_protocol_fee_bps: u16,
+}
@@ -0,0 +1,43 @@
+// This file defines the 'SetWithdrawCooldown' instruction for the AMM program.
+// It lets the pool authority require LPs to wait after depositing before they can withdraw,
+// which makes just-in-time liquidity around large swaps unprofitable.
+//
+// Key roles:
+// - 'authority': The pool's update authority; must sign.
+// - 'config': The pool's configuration PDA.
+//
+// Each deposit stamps its unlock time from the cooldown in force at that moment, so a
+// change applies from the next deposit on. Setting 0 turns the check off entirely.
+
+use anchor_lang::prelude::*;
+
+use crate::state::{ AdminAction, Config };
+
+#[derive(Accounts)]
+pub struct SetWithdrawCooldown<'info> {
+    /// The pool's update authority.
+    pub authority: Signer<'info>,
+    /// The config PDA for the pool.
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetWithdrawCooldown<'info> {
+    /// Sets the per-user withdraw cooldown in seconds.
+    pub fn set_withdraw_cooldown(&mut self, withdraw_cooldown_secs: u32) -> Result<()> {
+        self.config.admin_action(
+            self.config.key(),
+            &self.authority.key(),
+            AdminAction::SetWithdrawCooldown,
+            self.config.withdraw_cooldown_secs as u64,
+            withdraw_cooldown_secs as u64,
+        )?;
+
+        self.config.withdraw_cooldown_secs = withdraw_cooldown_secs;
+        Ok(())
+    }
+}
@@ -3,21 +3,45 @@
 //
 // Key roles:
 // - 'user': The swapper.
+// - 'recipient' and 'recipient_dst': Optional wallet (and its output ATA) receiving the output instead of the user.
 // - 'vault_x' and 'vault_y': The pool's token vaults.
 // - 'config': The pool's configuration PDA.
+// - 'global_config': The program-wide protocol fee switch (may not exist yet).
+// - 'fee_state': The pool's LP fee index (may not exist yet).
+// - remaining_accounts[0] (optional): An aggregator's host fee token account for the output mint.
 //
 // The swap flow:
 // - User sends input tokens to the pool vault.
-// - The pool sends output tokens to the user, using the config PDA as authority.
-// - The output amount is calculated using the constant product formula and fee.
+// - The pool sends output tokens to the user (or the recipient, if given), using the config PDA as authority.
+// - The output amount is calculated using the constant product formula and fee
+//   (or the weighted constant-mean formula for non-50/50 pools). The fee is taken
+//   from the input or the output depending on the pool's fee mode.
+// - The swap may not leave less than MIN_RESERVE on the output side of the reserves, after the
+//   host fee is paid.
+// - When the protocol fee switch is on, part of the fee is set aside in the config
+//   for the treasury and excluded from the reserves LPs own.
+// - The rest of the fee is set aside for the LPs and credited to the pool's fee growth per unit
+//   of registered LP weight, to be paid out through 'claim_fees'. It is excluded from the
+//   reserves as well. Pools without a fee state, or with no LP registered, keep it in the reserves.
+// - When a price guard is supplied, the pre-trade spot price must be within its tolerance of the
+//   caller's expected price. This is checked in addition to min_amount_out.
+// - 'swap_with_bps' derives min_amount_out on-chain instead: the output must be within
+//   max_slippage_bps of the zero-fee, zero-impact output at the pre-trade spot price.
+// - When a host fee account is supplied it is paid HOST_FEE_BPS of the pool fee, in the
+//   output token, out of the pool's share. The user's output does not change.
 
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    token::{Transfer, transfer, Mint, Token, TokenAccount},
+    associated_token::{create_idempotent, get_associated_token_address, AssociatedToken, Create},
+    token::{Transfer, transfer, Token, TokenAccount},
 };
 
-use crate::{ state::Config, error::AmmError };
+use crate::{
+    state::{ Config, FeeState, GlobalConfig },
+    error::AmmError,
+    constants::{ HOST_FEE_BPS, MIN_RESERVE },
+    math::{ self, SwapAmounts },
+};
 
 #[derive(Accounts)]
 pub struct Swap<'info> {
@@ -25,17 +49,27 @@ pub struct Swap<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
     /// The mint for token X.
-    pub mint_x: Account<'info, Mint>,
+    /// CHECK: Only the key is used; it must match `config.mint_x` via `has_one`.
+    pub mint_x: UncheckedAccount<'info>,
     /// The mint for token Y.
-    pub mint_y: Account<'info, Mint>,
-    /// The config PDA for the pool.
+    /// CHECK: Only the key is used; it must match `config.mint_y` via `has_one`.
+    pub mint_y: UncheckedAccount<'info>,
+    /// The config PDA for the pool. Mutable to accrue protocol fees.
     #[account(
+        mut,
         has_one = mint_x,
         has_one = mint_y,
         seeds = [b"config", config.seed.to_le_bytes().as_ref()],
         bump = config.config_bump
     )]
     pub config: Account<'info, Config>,
+    /// The global config holding the protocol fee switch.
+    /// CHECK: Address is pinned by seeds; the account may not be initialized, which means no protocol fee.
+    #[account(
+        seeds = [b"global_config"],
+        bump
+    )]
+    pub global_config: UncheckedAccount<'info>,
     /// The pool's vault for token X.
     #[account(
         mut,
@@ -50,6 +84,14 @@ pub struct Swap<'info> {
         associated_token::authority = config,
     )]
     pub vault_y: Account<'info, TokenAccount>,
+    /// The pool's LP fee index, credited with the LP part of the fee.
+    /// CHECK: Address is pinned by seeds; the account may not be initialized, which means LP fees stay in the reserves.
+    #[account(
+        mut,
+        seeds = [b"fee_state", config.key().as_ref()],
+        bump
+    )]
+    pub fee_state: UncheckedAccount<'info>,
     /// The user's token X account.
     #[account(
         mut,
@@ -64,18 +106,49 @@ pub struct Swap<'info> {
         associated_token::authority = user,
     )]
     pub user_y: Account<'info, TokenAccount>,
-    /// Standard program accounts required for CPI and ATA creation.
+    /// Optional wallet that receives the output tokens instead of the user.
+    pub recipient: Option<SystemAccount<'info>>,
+    /// The recipient's token account for the output mint, created if needed. Required with `recipient`.
+    /// CHECK: Must be the recipient's ATA for the output mint; validated in the handler since the
+    /// output mint depends on the swap direction.
+    #[account(mut)]
+    pub recipient_dst: Option<UncheckedAccount<'info>>,
     pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
+    /// Only needed to create the recipient's ATA; omit for plain swaps to keep the transaction small.
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    /// Only needed to create the recipient's ATA.
+    pub system_program: Option<Program<'info, System>>,
+}
+
+/// Optional guard against swapping into a pool whose price was moved before the swap landed.
+///
+/// Prices use the same convention as `get_spot_price`'s `price_x_in_y`, whatever the swap
+/// direction: whole units of token Y per whole unit of token X, i.e. normalized by each
+/// mint's decimals rather than in base units. For weighted pools it is the weight-adjusted
+/// spot price. For example, expecting 1 X = 2.5 Y is `expected_price_num = 5`,
+/// `expected_price_den = 2`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PriceGuard {
+    pub expected_price_num: u64,
+    pub expected_price_den: u64,
+    /// Largest allowed distance of the spot price from the expected price, in bps of the expected price.
+    pub max_deviation_bps: u16,
 }
 
 impl<'info> Swap<'info> {
     /// Swaps tokens using the constant product formula (x*y=k) and applies the pool fee.
-    /// Transfers input tokens from user to vault, and output tokens from vault to user.
-    pub fn swap(&mut self, amount_in: u64, min_amount_out: u64, x_to_y: bool) -> Result<()> {
+    /// In fee-on-output pools the fee tokens stay in the output vault.
+    /// Transfers input tokens from user to vault, and output tokens from vault to the user or recipient.
+    pub fn swap(
+        &mut self,
+        amount_in: u64,
+        min_amount_out: u64,
+        x_to_y: bool,
+        price_guard: Option<PriceGuard>,
+        host_fee_account: Option<&AccountInfo<'info>>,
+    ) -> Result<()> {
         require!(!self.config.locked, AmmError::PoolLocked);
-        require!(amount_in > 0, AmmError::InvalidAmount);
+        require!(amount_in > 0, AmmError::ZeroAmountIn);
 
         // Select source/destination tokens
         let (user_src, user_dst, vault_src, vault_dst) = if x_to_y {
@@ -85,26 +158,47 @@ impl<'info> Swap<'info> {
         };
 
         // Ensure user has enough tokens
-        require!(user_src.amount >= amount_in, AmmError::InsufficientFunds);
+        require!(user_src.amount >= amount_in, AmmError::InsufficientUserBalance);
+
+        // Price against the LPs' reserves, which exclude protocol fees owed to the treasury
+        let (reserve_x, reserve_y) = self.config.net_reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let (reserve_in, reserve_out) = if x_to_y { (reserve_x, reserve_y) } else { (reserve_y, reserve_x) };
         // Ensure vault has enough liquidity
-        require!(vault_src.amount > 0 && vault_dst.amount > 0, AmmError::InsufficientLiquidity);
-
-        // Calculate output amount using the constant product curve
-        let (reserve_in, reserve_out) = (vault_src.amount, vault_dst.amount);
-        // Apply fee (assuming fee is in basis points, e.g., 30 = 0.3%)
-        let fee = self.config.fee as u128;
-        let amount_in_with_fee = (amount_in as u128 * (10_000 - fee)) / 10_000;
-        // Calculate output amount using constant product formula: x * y = k
-        // amount_out = (amount_in_with_fee * reserve_out) / (reserve_in + amount_in_with_fee)
-        let numerator = amount_in_with_fee * reserve_out as u128;
-        let denominator = reserve_in as u128 + amount_in_with_fee;
-        let amount_out = (numerator / denominator) as u64;
+        require!(reserve_in > 0 && reserve_out > 0, AmmError::InsufficientLiquidity);
+
+        if let Some(guard) = price_guard {
+            self.check_price_guard(reserve_x, reserve_y, &guard)?;
+        }
+
+        // Calculate output amount and fee (fee is in basis points, e.g., 30 = 0.3%)
+        let amounts = self.config.quote(reserve_in, reserve_out, amount_in, x_to_y)?;
+        let SwapAmounts { amount_out, fee_amount } = amounts;
+        let fee_on_input = self.config.fee_on_input;
+
+        // The host's cut comes out of the pool fee, so it never changes what the user receives
+        let output_mint = if x_to_y { &self.mint_y } else { &self.mint_x };
+        let host_fee = match host_fee_account {
+            Some(account) => {
+                Self::check_host_fee_account(account, &output_mint.key())?;
+                math::host_fee_out(amount_in, &amounts, fee_on_input, HOST_FEE_BPS).ok_or(AmmError::CurveMathFailed)?
+            }
+            None => 0,
+        };
 
         // Slippage protection
         require!(amount_out >= min_amount_out, AmmError::SlippageExceeded);
-        require!(amount_out > 0, AmmError::InvalidAmount);
+        require!(amount_out > 0, AmmError::ZeroAmountOut);
         // Ensure vault has enough tokens to fulfill the swap
-        require!(vault_dst.amount >= amount_out, AmmError::InsufficientLiquidity);
+        require!(reserve_out >= amount_out + host_fee, AmmError::InsufficientVaultBalance);
+        // The quote only counts an output-side fee; a fee-on-input pool pays the host from the
+        // output vault on top of it
+        require!(reserve_out - amount_out - host_fee >= MIN_RESERVE, AmmError::ReserveFloorBreached);
+
+        // Resolve where the output goes; without a recipient it is the user's own account
+        let (recipient, destination) = match &self.recipient {
+            Some(recipient) => (recipient.key(), self.recipient_destination(recipient, output_mint)?),
+            None => (self.user.key(), user_dst.to_account_info()),
+        };
 
         // Transfer input tokens from user to vault
         let cpi_program = self.token_program.to_account_info();
@@ -116,37 +210,165 @@ impl<'info> Swap<'info> {
         let cpi_ctx_in = CpiContext::new(cpi_program.clone(), transfer_in_accounts);
         transfer(cpi_ctx_in, amount_in)?;
 
-        // Transfer output tokens from vault to user using PDA authority
-        let seeds = &[&b"config"[..], &self.config.seed.to_le_bytes(), &[self.config.config_bump]];
+        // Transfer output tokens from vault to the destination using PDA authority
+        let seeds = crate::config_signer_seeds!(self.config);
         let signer_seeds = &[&seeds[..]];
         let transfer_out_accounts = Transfer {
             from: vault_dst.to_account_info(),
-            to: user_dst.to_account_info(),
+            to: destination,
             authority: self.config.to_account_info(),
         };
-        let cpi_ctx_out = CpiContext::new_with_signer(cpi_program, transfer_out_accounts, signer_seeds);
+        let cpi_ctx_out = CpiContext::new_with_signer(cpi_program.clone(), transfer_out_accounts, signer_seeds);
         transfer(cpi_ctx_out, amount_out)?;
 
+        // Pay the host from the output vault
+        if let Some(account) = host_fee_account.filter(|_| host_fee > 0) {
+            let transfer_host_accounts = Transfer {
+                from: vault_dst.to_account_info(),
+                to: account.clone(),
+                authority: self.config.to_account_info(),
+            };
+            let cpi_ctx_host = CpiContext::new_with_signer(cpi_program, transfer_host_accounts, signer_seeds);
+            transfer(cpi_ctx_host, host_fee)?;
+        }
+
+        // Set aside the protocol's share of what is left of the fee, in whichever token the fee was
+        // charged, and the LPs' share of the remainder for claim_fees
+        let fee_after_host = if host_fee_account.is_some() {
+            fee_amount - (fee_amount as u128 * HOST_FEE_BPS as u128 / 10_000) as u64
+        } else {
+            fee_amount
+        };
+        let protocol_fee_bps = GlobalConfig::protocol_fee_bps(&self.global_config.to_account_info())?;
+        let fee_in_x = fee_on_input == x_to_y;
+        let mut fee_state = FeeState::load(&self.fee_state)?;
+        let (protocol_fee, lp_fee) = self.config.accrue_swap_fees(fee_after_host, fee_in_x, protocol_fee_bps, fee_state.as_mut())?;
+        if let Some(fee_state) = &fee_state {
+            fee_state.store(&self.fee_state)?;
+        }
+
+        let set_aside = protocol_fee + lp_fee;
+        let (input_set_aside, output_set_aside) = if fee_on_input { (set_aside, 0) } else { (0, set_aside) };
+        let reserve_in_after = reserve_in + amount_in - input_set_aside;
+        let reserve_out_after = reserve_out - amount_out - host_fee - output_set_aside;
+
         // Emit swap event for tracking
         emit!(SwapEvent {
             user: self.user.key(),
+            recipient,
             amount_in,
             amount_out,
+            fee_amount,
+            protocol_fee,
+            lp_fee,
+            host_fee,
             x_to_y,
-            reserve_x: if x_to_y { vault_src.amount + amount_in } else { vault_dst.amount - amount_out },
-            reserve_y: if x_to_y { vault_dst.amount - amount_out } else { vault_src.amount + amount_in },
+            reserve_x: if x_to_y { reserve_in_after } else { reserve_out_after },
+            reserve_y: if x_to_y { reserve_out_after } else { reserve_in_after },
         });
 
         Ok(())
     }
+
+    /// Swaps with a tolerance in bps instead of a fixed `min_amount_out`, for callers that only
+    /// learn their input amount at runtime. The ideal output is `amount_in` valued at the
+    /// pre-trade spot price with no fee; the tolerance bounds the fee and price impact combined,
+    /// so it must be at least the pool fee for any swap to pass.
+    pub fn swap_with_bps(
+        &mut self,
+        amount_in: u64,
+        max_slippage_bps: u16,
+        x_to_y: bool,
+        host_fee_account: Option<&AccountInfo<'info>>,
+    ) -> Result<()> {
+        let (reserve_x, reserve_y) = self.config.net_reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let (reserve_in, reserve_out) = if x_to_y { (reserve_x, reserve_y) } else { (reserve_y, reserve_x) };
+        require!(reserve_in > 0 && reserve_out > 0, AmmError::InsufficientLiquidity);
+
+        let min_amount_out = self.config.min_out_for_slippage(reserve_in, reserve_out, amount_in, x_to_y, max_slippage_bps)?;
+        self.swap(amount_in, min_amount_out, x_to_y, None, host_fee_account)
+    }
+
+    /// Rejects the swap when the pre-trade spot price is too far from the caller's expected price.
+    fn check_price_guard(&self, reserve_x: u64, reserve_y: u64, guard: &PriceGuard) -> Result<()> {
+        require!(
+            guard.expected_price_num > 0 && guard.expected_price_den > 0,
+            AmmError::InvalidPriceGuard
+        );
+        let expected = math::div_q64(guard.expected_price_num as u128, guard.expected_price_den as u128)
+            .ok_or(AmmError::InvalidPriceGuard)?;
+
+        let (weight_x, weight_y) = self.config.weights();
+        let (spot, _) = math::spot_price_q64(
+            reserve_x,
+            reserve_y,
+            self.config.decimals_x,
+            self.config.decimals_y,
+            weight_x,
+            weight_y,
+        ).ok_or(AmmError::CurveMathFailed)?;
+
+        let deviation = math::price_deviation_bps(spot, expected).ok_or(AmmError::InvalidPriceGuard)?;
+        require!(deviation <= guard.max_deviation_bps as u128, AmmError::PriceDeviationTooHigh);
+        Ok(())
+    }
+
+    /// Checks the host fee account is a writable token account for the output mint.
+    fn check_host_fee_account(account: &AccountInfo<'info>, output_mint: &Pubkey) -> Result<()> {
+        require!(account.is_writable, AmmError::InvalidHostFeeAccount);
+        require_keys_eq!(*account.owner, Token::id(), AmmError::InvalidHostFeeAccount);
+
+        let token_account = TokenAccount::try_deserialize(&mut &account.try_borrow_data()?[..])
+            .map_err(|_| AmmError::InvalidHostFeeAccount)?;
+        require_keys_eq!(token_account.mint, *output_mint, AmmError::InvalidHostFeeAccount);
+        Ok(())
+    }
+
+    /// Creates the recipient's output ATA if it does not exist yet and checks it is the right account.
+    fn recipient_destination(&self, recipient: &SystemAccount<'info>, mint: &UncheckedAccount<'info>) -> Result<AccountInfo<'info>> {
+        let destination = self.recipient_dst.as_ref().ok_or(AmmError::InvalidRecipient)?;
+        let associated_token_program = self.associated_token_program.as_ref().ok_or(AmmError::InvalidRecipient)?;
+        let system_program = self.system_program.as_ref().ok_or(AmmError::InvalidRecipient)?;
+        require_keys_eq!(
+            destination.key(),
+            get_associated_token_address(&recipient.key(), &mint.key()),
+            AmmError::InvalidRecipient
+        );
+
+        let cpi_accounts = Create {
+            payer: self.user.to_account_info(),
+            associated_token: destination.to_account_info(),
+            authority: recipient.to_account_info(),
+            mint: mint.to_account_info(),
+            system_program: system_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+        };
+        create_idempotent(CpiContext::new(associated_token_program.to_account_info(), cpi_accounts))?;
+
+        let account = TokenAccount::try_deserialize(&mut &destination.try_borrow_data()?[..])?;
+        require_keys_eq!(account.mint, mint.key(), AmmError::InvalidRecipient);
+
+        Ok(destination.to_account_info())
+    }
 }
 
 #[event]
 pub struct SwapEvent {
     pub user: Pubkey,
+    /// Wallet that received the output; equal to `user` for a plain swap.
+    pub recipient: Pubkey,
     pub amount_in: u64,
     pub amount_out: u64,
+    /// Fee kept by the pool: input token when `config.fee_on_input`, output token otherwise.
+    pub fee_amount: u64,
+    /// Part of `fee_amount` set aside for the protocol treasury.
+    pub protocol_fee: u64,
+    /// Part of `fee_amount` set aside for LPs to claim.
+    pub lp_fee: u64,
+    /// Output tokens paid to the host fee account, out of the pool's fee; 0 without one.
+    pub host_fee: u64,
     pub x_to_y: bool,
+    /// Reserves after the swap, excluding protocol fees and unclaimed LP fees.
     pub reserve_x: u64,
     pub reserve_y: u64,
 }
\ No newline at end of file
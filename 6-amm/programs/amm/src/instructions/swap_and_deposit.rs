@@ -0,0 +1,323 @@
+// This file defines the 'SwapAndDeposit' instruction for the AMM program.
+// It lets a liquidity provider holding the two pool tokens in the wrong ratio join the pool
+// in a single instruction, instead of a separate swap and deposit.
+//
+// Key roles:
+// - 'user': The liquidity provider.
+// - 'vault_x' and 'vault_y': The pool's token vaults.
+// - 'config': The pool's configuration PDA. Mutable to accrue protocol fees on the internal swap.
+// - 'global_config': The program-wide protocol fee switch (may not exist yet).
+// - 'fee_state': The pool's LP fee index (may not exist yet).
+// - 'mint_lp' and 'user_lp': The LP token mint and the user's LP token account.
+// - 'position': The user's position PDA, recording deposit time for the withdraw cooldown
+//   and the LP registered to earn swap fees.
+//
+// The zap flow:
+// - The side the user over-supplies (relative to the pool ratio) is found from the net reserves.
+// - Part of that side is swapped against the curve so the user's leftovers match the new pool ratio.
+// - The largest proportional deposit that fits the leftovers is made and LP tokens are minted.
+//   Fees earned on the user's earlier LP are settled into the position first, and the new LP
+//   is registered to earn fees once the pool has a fee state.
+// - Any dust that does not fit the deposit stays with the user.
+// - A SwapEvent (when a swap happened) and a DepositEvent are emitted.
+
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{ Transfer, transfer, Mint, Token, TokenAccount, MintTo, mint_to },
+};
+use crate::{
+    state::{ Config, FeeState, GlobalConfig, Position },
+    error::AmmError,
+    constants::{ LP_DECIMALS, MIN_DEPOSIT_TOKENS, min_deposit_lp },
+    instructions::{ DepositEvent, SwapEvent },
+    math::{ self, SwapAmounts },
+};
+
+#[derive(Accounts)]
+pub struct SwapAndDeposit<'info> {
+    /// The user providing liquidity.
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// The mint for token X.
+    pub mint_x: Account<'info, Mint>,
+    /// The mint for token Y.
+    pub mint_y: Account<'info, Mint>,
+
+    /// The config PDA for the pool. Mutable to accrue protocol fees.
+    #[account(
+        mut,
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The global config holding the protocol fee switch.
+    /// CHECK: Address is pinned by seeds; the account may not be initialized, which means no protocol fee.
+    #[account(
+        seeds = [b"global_config"],
+        bump
+    )]
+    pub global_config: UncheckedAccount<'info>,
+
+    /// The pool's LP fee index, credited with the LP part of the swap fee.
+    /// CHECK: Address is pinned by seeds; the account may not be initialized, which means LP fees stay in the reserves.
+    #[account(
+        mut,
+        seeds = [b"fee_state", config.key().as_ref()],
+        bump
+    )]
+    pub fee_state: UncheckedAccount<'info>,
+
+    /// The pool's vault for token X.
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config
+    )]
+    pub vault_x: Account<'info, TokenAccount>,
+
+    /// The pool's vault for token Y.
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+    )]
+    pub vault_y: Account<'info, TokenAccount>,
+
+    /// The LP token mint (PDA, authority = config).
+    #[account(
+        mut,
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+        mint::decimals = LP_DECIMALS,
+        mint::authority = config,
+    )]
+    pub mint_lp: Account<'info, Mint>,
+
+    /// The user's token X account.
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = user
+    )]
+    pub user_x: Account<'info, TokenAccount>,
+
+    /// The user's token Y account.
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = user,
+    )]
+    pub user_y: Account<'info, TokenAccount>,
+
+    /// The user's LP token account.
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_lp,
+        associated_token::authority = user
+    )]
+    pub user_lp: Account<'info, TokenAccount>,
+
+    /// The user's position in this pool, created on first deposit.
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"position", config.key().as_ref(), user.key().as_ref()],
+        bump,
+        space = 8 + Position::INIT_SPACE,
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SwapAndDeposit<'info> {
+    /// Swaps the excess of `amount_x`/`amount_y` into the other token, then deposits the
+    /// balanced amounts and mints at least `min_lp` LP tokens to the user.
+    pub fn swap_and_deposit(&mut self, amount_x: u64, amount_y: u64, min_lp: u64, bumps: SwapAndDepositBumps) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(!self.config.deposits_closed, AmmError::DepositsClosed);
+        require!(amount_x > 0 || amount_y > 0, AmmError::ZeroAmountIn);
+        require!(self.user_x.amount >= amount_x && self.user_y.amount >= amount_y, AmmError::InsufficientUserBalance);
+
+        // A zap joins an existing ratio; the first deposit sets the ratio and goes through `deposit`
+        let (reserve_x, reserve_y) = self.config.net_reserves(self.vault_x.amount, self.vault_y.amount)?;
+        require!(self.mint_lp.supply > 0 && reserve_x > 0 && reserve_y > 0, AmmError::NoLiquidityInPool);
+
+        // The user over-supplies X when amount_x / amount_y > reserve_x / reserve_y
+        let x_to_y = (amount_x as u128) * (reserve_y as u128) > (amount_y as u128) * (reserve_x as u128);
+        let (reserve_in, reserve_out, amount_in, amount_other) = if x_to_y {
+            (reserve_x, reserve_y, amount_x, amount_y)
+        } else {
+            (reserve_y, reserve_x, amount_y, amount_x)
+        };
+
+        let swap_amount = math::zap_swap_amount(
+            reserve_in,
+            reserve_out,
+            amount_in,
+            amount_other,
+            &self.config.swap_params(x_to_y),
+        ).ok_or(AmmError::CurveMathFailed)?;
+
+        let (mut deposit_x, mut deposit_y) = (amount_x, amount_y);
+        if swap_amount > 0 {
+            let amount_out = self.swap_excess(reserve_in, reserve_out, swap_amount, x_to_y)?;
+            if x_to_y {
+                deposit_x -= swap_amount;
+                deposit_y += amount_out;
+            } else {
+                deposit_y -= swap_amount;
+                deposit_x += amount_out;
+            }
+        }
+
+        // Size the deposit against the reserves the swap left behind
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        let (reserve_x, reserve_y) = self.config.net_reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let lp_amount = math::lp_for_amounts(
+            reserve_x,
+            reserve_y,
+            self.mint_lp.supply,
+            deposit_x,
+            deposit_y,
+            self.config.decimals_x,
+            self.config.decimals_y,
+        ).ok_or(AmmError::CurveMathFailed)?;
+        require!(lp_amount >= min_lp, AmmError::SlippageExceeded);
+        require!(lp_amount != 0, AmmError::ZeroLpAmount);
+
+        let (x, y) = math::deposit_amounts(
+            reserve_x,
+            reserve_y,
+            self.mint_lp.supply,
+            lp_amount,
+            self.config.decimals_x,
+            self.config.decimals_y,
+        ).ok_or(AmmError::CurveMathFailed)?;
+
+        // Same dust floor and launch cap as a plain deposit
+        require!(lp_amount >= min_deposit_lp(self.mint_lp.decimals), AmmError::DepositTooSmall);
+        require!(x >= MIN_DEPOSIT_TOKENS && y >= MIN_DEPOSIT_TOKENS, AmmError::DepositTooSmall);
+        self.config.check_deposit_cap(self.vault_x.amount, self.vault_y.amount, x, y)?;
+
+        self.transfer_in(true, x)?;
+        self.transfer_in(false, y)?;
+        // Settle fees on the LP held so far before the new LP starts earning
+        if let Some(mut fee_state) = FeeState::load(&self.fee_state)? {
+            self.position.add_lp(&mut self.config, &mut fee_state, self.user_lp.amount, lp_amount)?;
+            fee_state.store(&self.fee_state)?;
+        }
+        self.mint_lp_tokens(lp_amount)?;
+
+        self.position.record_deposit(
+            self.user.key(),
+            self.config.key(),
+            self.config.withdraw_cooldown_secs,
+            bumps.position,
+        )?;
+
+        emit!(DepositEvent {
+            user: self.user.key(),
+            lp_amount,
+            amount_x: x,
+            amount_y: y,
+        });
+
+        Ok(())
+    }
+
+    /// Runs the internal swap against the curve, accrues the protocol fee and emits a SwapEvent.
+    /// Returns the output amount credited to the user.
+    fn swap_excess(&mut self, reserve_in: u64, reserve_out: u64, amount_in: u64, x_to_y: bool) -> Result<u64> {
+        let SwapAmounts { amount_out, fee_amount } = self.config.quote(reserve_in, reserve_out, amount_in, x_to_y)?;
+        require!(amount_out > 0, AmmError::ZeroAmountOut);
+        require!(reserve_out >= amount_out, AmmError::InsufficientVaultBalance);
+
+        let (user_src, user_dst, vault_src, vault_dst) = if x_to_y {
+            (&self.user_x, &self.user_y, &self.vault_x, &self.vault_y)
+        } else {
+            (&self.user_y, &self.user_x, &self.vault_y, &self.vault_x)
+        };
+
+        let cpi_program = self.token_program.to_account_info();
+        let transfer_in_accounts = Transfer {
+            from: user_src.to_account_info(),
+            to: vault_src.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+        transfer(CpiContext::new(cpi_program.clone(), transfer_in_accounts), amount_in)?;
+
+        let seeds = crate::config_signer_seeds!(self.config);
+        let signer_seeds = &[&seeds[..]];
+        let transfer_out_accounts = Transfer {
+            from: vault_dst.to_account_info(),
+            to: user_dst.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+        transfer(CpiContext::new_with_signer(cpi_program, transfer_out_accounts, signer_seeds), amount_out)?;
+
+        let fee_on_input = self.config.fee_on_input;
+        let protocol_fee_bps = GlobalConfig::protocol_fee_bps(&self.global_config.to_account_info())?;
+        let mut fee_state = FeeState::load(&self.fee_state)?;
+        let (protocol_fee, lp_fee) = self.config.accrue_swap_fees(fee_amount, fee_on_input == x_to_y, protocol_fee_bps, fee_state.as_mut())?;
+        if let Some(fee_state) = &fee_state {
+            fee_state.store(&self.fee_state)?;
+        }
+
+        let set_aside = protocol_fee + lp_fee;
+        let (input_set_aside, output_set_aside) = if fee_on_input { (set_aside, 0) } else { (0, set_aside) };
+        let reserve_in_after = reserve_in + amount_in - input_set_aside;
+        let reserve_out_after = reserve_out - amount_out - output_set_aside;
+
+        emit!(SwapEvent {
+            user: self.user.key(),
+            recipient: self.user.key(),
+            amount_in,
+            amount_out,
+            fee_amount,
+            protocol_fee,
+            lp_fee,
+            host_fee: 0,
+            x_to_y,
+            reserve_x: if x_to_y { reserve_in_after } else { reserve_out_after },
+            reserve_y: if x_to_y { reserve_out_after } else { reserve_in_after },
+        });
+
+        Ok(amount_out)
+    }
+
+    /// Transfers tokens from the user to the pool vaults.
+    fn transfer_in(&self, is_x: bool, amount: u64) -> Result<()> {
+        let (from, to) = match is_x {
+            true => (self.user_x.to_account_info(), self.vault_x.to_account_info()),
+            false => (self.user_y.to_account_info(), self.vault_y.to_account_info()),
+        };
+        let cpi_accounts = Transfer {
+            from,
+            to,
+            authority: self.user.to_account_info(),
+        };
+        transfer(CpiContext::new(self.token_program.to_account_info(), cpi_accounts), amount)
+    }
+
+    /// Mints LP tokens to the user, using the config PDA as authority.
+    fn mint_lp_tokens(&self, amount: u64) -> Result<()> {
+        let cpi_accounts = MintTo {
+            mint: self.mint_lp.to_account_info(),
+            to: self.user_lp.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+        let seeds = crate::config_signer_seeds!(self.config);
+        let signer_seeds = &[&seeds[..]];
+
+        mint_to(CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds), amount)
+    }
+}
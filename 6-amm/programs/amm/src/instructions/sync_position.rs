@@ -0,0 +1,109 @@
+// This file defines the 'SyncPosition' instruction for the AMM program.
+// It registers the LP a user holds to earn swap fees when the program did not register it itself,
+// e.g. LP received by transfer, or held or locked before the pool had a fee state.
+//
+// Key roles:
+// - 'user': The liquidity provider.
+// - 'config': The pool's configuration PDA. Mutable to release LP fees no position can claim.
+// - 'mint_lp' and 'user_lp': The LP token mint and the user's LP token account.
+// - 'position': The user's position PDA, created if needed.
+// - 'locked_position': The user's LP lock record; omit it when the user has never locked LP.
+// - 'fee_state': The pool's LP fee index. Pools without one have nothing to register with.
+//
+// The sync flow:
+// - Fees earned so far are settled into the position.
+// - The position's registered LP is set to the LP in the user's LP account.
+// - With a locked position, the boosted weight of the locked LP is registered as well.
+// - A position created here records no deposit, so it does not start a withdraw cooldown.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
+
+use crate::state::{ Config, FeeState, LockedPosition, Position };
+
+#[derive(Accounts)]
+pub struct SyncPosition<'info> {
+    /// The liquidity provider registering their LP.
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// The config PDA for the pool. Mutable to release LP fees no position can claim.
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+    /// The LP token mint.
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+    )]
+    pub mint_lp: Account<'info, Mint>,
+    /// The user's LP token account.
+    #[account(
+        associated_token::mint = mint_lp,
+        associated_token::authority = user
+    )]
+    pub user_lp: Account<'info, TokenAccount>,
+    /// The user's position in this pool, created if needed.
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"position", config.key().as_ref(), user.key().as_ref()],
+        bump,
+        space = 8 + Position::INIT_SPACE,
+    )]
+    pub position: Box<Account<'info, Position>>,
+    /// The user's LP lock record. Omit it when the user has no locked position.
+    #[account(
+        seeds = [b"locked_position", config.key().as_ref(), user.key().as_ref()],
+        bump = locked_position.bump,
+    )]
+    pub locked_position: Option<Box<Account<'info, LockedPosition>>>,
+    /// The pool's LP fee index.
+    #[account(
+        mut,
+        seeds = [b"fee_state", config.key().as_ref()],
+        bump = fee_state.bump,
+    )]
+    pub fee_state: Box<Account<'info, FeeState>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SyncPosition<'info> {
+    /// Settles the user's position and registers everything they hold to earn fees.
+    pub fn sync_position(&mut self, bumps: SyncPositionBumps) -> Result<()> {
+        let (user, config) = (self.user.key(), self.config.key());
+        if self.position.owner == Pubkey::default() {
+            self.position.owner = user;
+            self.position.config = config;
+            self.position.bump = bumps.position;
+        }
+
+        let held_lp = self.user_lp.amount;
+        self.position.settle_fees(&mut self.config, &mut self.fee_state, held_lp)?;
+        self.position.set_lp_balance(&mut self.fee_state, held_lp)?;
+        if let Some(locked_position) = &self.locked_position {
+            self.position.set_locked_weight(&mut self.fee_state, locked_position.fee_weight()?)?;
+        }
+
+        emit!(PositionSyncedEvent {
+            user,
+            config,
+            lp_balance: self.position.lp_balance,
+            locked_weight: self.position.locked_weight,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct PositionSyncedEvent {
+    pub user: Pubkey,
+    pub config: Pubkey,
+    /// LP registered to earn fees after the sync.
+    pub lp_balance: u64,
+    /// Fee weight of the user's locked LP after the sync.
+    pub locked_weight: u64,
+}
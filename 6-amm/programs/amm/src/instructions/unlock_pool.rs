@@ -0,0 +1,34 @@
+// This file defines the 'UnlockPool' instruction for the AMM program.
+// It lets the pool authority reopen a locked pool, whatever the lock reason was.
+//
+// Key roles:
+// - 'authority': The pool's update authority; must sign.
+// - 'config': The pool's configuration PDA.
+
+use anchor_lang::prelude::*;
+
+use crate::state::{ AdminAction, Config };
+
+#[derive(Accounts)]
+pub struct UnlockPool<'info> {
+    /// The pool's update authority.
+    pub authority: Signer<'info>,
+    /// The config PDA for the pool.
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> UnlockPool<'info> {
+    /// Unlocks the pool and clears the stored lock reason.
+    pub fn unlock_pool(&mut self) -> Result<()> {
+        let config = self.config.key();
+        let authority = self.authority.key();
+        self.config.admin_action(config, &authority, AdminAction::UnlockPool, 1, 0)?;
+
+        self.config.unlock(config, authority)
+    }
+}
@@ -2,35 +2,46 @@
 // It allows users to burn their LP tokens and withdraw their proportional share of the pool's tokens.
 //
 // Key roles:
-// - 'user': The liquidity remover.
+// - 'user': The liquidity remover; signs for and burns from their LP account.
+// - 'recipient': Receives the withdrawn tokens (pass 'user' to withdraw to yourself).
 // - 'vault_x' and 'vault_y': The pool's token vaults.
 // - 'mint_lp': The LP token mint.
 // - 'user_lp': The user's LP token account.
+// - 'position': The user's position PDA, checked against the pool's withdraw cooldown.
+//   Fees earned so far are settled into it before its registered LP shrinks.
+// - 'fee_state': The pool's LP fee index (may not exist yet).
 //
 // The withdraw flow:
+// - If the pool has a cooldown, the user's last deposit must be old enough.
 // - User burns LP tokens.
-// - The program transfers the user's proportional share of both tokens from the vaults to the user.
+// - The program transfers the user's proportional share of both tokens from the vaults to the recipient,
+//   creating the recipient's token accounts if needed.
 // - Proportional math ensures fair share for all liquidity providers.
+// - The amounts paid out are returned as return data for CPI callers.
+// - With 'close_empty_lp', a fully exited user's LP token account is closed and its rent returned.
 
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Burn, burn, Transfer, transfer, Mint, Token, TokenAccount},
+    token::{Burn, burn, CloseAccount, close_account, Transfer, transfer, Mint, Token, TokenAccount},
 };
 
-use crate::{ state::Config, error::AmmError };
+use crate::{ state::{ Config, Position }, error::AmmError, constants::LP_DECIMALS, compute_proportional_share };
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
     /// The user removing liquidity.
     #[account(mut)]
     pub user: Signer<'info>,
+    /// The wallet receiving the withdrawn tokens. Usually the user, but may be e.g. a cold wallet.
+    pub recipient: SystemAccount<'info>,
     /// The mint for token X.
     pub mint_x: Account<'info, Mint>,
     /// The mint for token Y.
     pub mint_y: Account<'info, Mint>,
-    /// The config PDA for the pool.
+    /// The config PDA for the pool. Mutable to release LP fees no position can claim.
     #[account(
+        mut,
         has_one = mint_x,
         has_one = mint_y,
         seeds = [b"config", config.seed.to_le_bytes().as_ref()],
@@ -56,22 +67,24 @@ pub struct Withdraw<'info> {
         mut,
         seeds = [b"lp", config.key().as_ref()],
         bump,
-        mint::decimals = 6,
+        mint::decimals = LP_DECIMALS,
         mint::authority = config,
     )]
     pub mint_lp: Account<'info, Mint>,
-    /// The user's token X account.
+    /// The recipient's token X account, created if needed.
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = mint_x,
-        associated_token::authority = user
+        associated_token::authority = recipient
     )]
     pub user_x: Account<'info, TokenAccount>,
-    /// The user's token Y account.
+    /// The recipient's token Y account, created if needed.
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = mint_y,
-        associated_token::authority = user,
+        associated_token::authority = recipient,
     )]
     pub user_y: Account<'info, TokenAccount>,
     /// The user's LP token account.
@@ -81,39 +94,59 @@ pub struct Withdraw<'info> {
         associated_token::authority = user
     )]
     pub user_lp: Account<'info, TokenAccount>,
+    /// The user's position in this pool.
+    /// CHECK: Address is pinned by seeds. LPs who never deposited through the program have no
+    /// position and no cooldown; otherwise it is deserialized in the handler.
+    #[account(
+        mut,
+        seeds = [b"position", config.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: UncheckedAccount<'info>,
+    /// The pool's LP fee index, tracking the LP registered to earn fees.
+    /// CHECK: Address is pinned by seeds; the account may not be initialized, which means no LP is registered.
+    #[account(
+        mut,
+        seeds = [b"fee_state", config.key().as_ref()],
+        bump
+    )]
+    pub fee_state: UncheckedAccount<'info>,
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
+/// What a withdraw paid out, returned by `withdraw` via return data.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct WithdrawResult {
+    pub x_out: u64,
+    pub y_out: u64,
+}
+
 impl<'info> Withdraw<'info> {
-    /// Burns the user's LP tokens and transfers their proportional share of vault_x and vault_y to them.
+    /// Burns the user's LP tokens and transfers their proportional share of vault_x and vault_y to the recipient.
     /// Checks for pool lock and sufficient LP tokens.
-    pub fn withdraw(&mut self, lp_amount: u64, min_x: u64, min_y: u64) -> Result<()> {
+    /// With `close_empty_lp`, closes the user's LP account if the withdraw left it empty.
+    pub fn withdraw(&mut self, lp_amount: u64, min_x: u64, min_y: u64, close_empty_lp: bool) -> Result<WithdrawResult> {
         // Check if pool is locked
         require!(!self.config.locked, AmmError::PoolLocked);
-        require!(lp_amount > 0, AmmError::InvalidAmount);
-        require!(self.user_lp.amount >= lp_amount, AmmError::InsufficientFunds);
+        require!(lp_amount > 0, AmmError::ZeroLpAmount);
+        require!(self.user_lp.amount >= lp_amount, AmmError::InsufficientUserBalance);
         require!(self.mint_lp.supply > 0, AmmError::NoLiquidityInPool);
+        self.check_cooldown()?;
+        Position::remove_lp_from_account(&self.position, &self.fee_state, &mut self.config, self.user_lp.amount, lp_amount)?;
 
-        // Calculate proportional amounts to withdraw
+        // Calculate proportional amounts to withdraw, excluding protocol fees owed to the treasury
+        let (reserve_x, reserve_y) = self.config.net_reserves(self.vault_x.amount, self.vault_y.amount)?;
         let total_lp = self.mint_lp.supply;
-        let x_out = (self.vault_x.amount as u128)
-            .checked_mul(lp_amount as u128)
-            .unwrap()
-            .checked_div(total_lp as u128)
-            .unwrap() as u64;
-        let y_out = (self.vault_y.amount as u128)
-            .checked_mul(lp_amount as u128)
-            .unwrap()
-            .checked_div(total_lp as u128)
-            .unwrap() as u64;
+        let x_out = compute_proportional_share(reserve_x, lp_amount, total_lp).ok_or(AmmError::CurveMathFailed)?;
+        let y_out = compute_proportional_share(reserve_y, lp_amount, total_lp).ok_or(AmmError::CurveMathFailed)?;
 
         // Slippage protection (optional, but recommended)
         require!(x_out >= min_x && y_out >= min_y, AmmError::SlippageExceeded);
-        require!(x_out > 0 && y_out > 0, AmmError::InvalidAmount);
-        require!(self.vault_x.amount >= x_out, AmmError::InsufficientLiquidity);
-        require!(self.vault_y.amount >= y_out, AmmError::InsufficientLiquidity);
+        require!(x_out > 0 && y_out > 0, AmmError::ZeroAmountOut);
+        require!(reserve_x >= x_out, AmmError::InsufficientVaultBalance);
+        require!(reserve_y >= y_out, AmmError::InsufficientVaultBalance);
 
         // Burn LP tokens from user
         let cpi_program = self.token_program.to_account_info();
@@ -125,8 +158,8 @@ impl<'info> Withdraw<'info> {
         let burn_ctx = CpiContext::new(cpi_program.clone(), burn_accounts);
         burn(burn_ctx, lp_amount)?;
 
-        // Transfer X from vault to user
-        let seeds = &[&b"config"[..], &self.config.seed.to_le_bytes(), &[self.config.config_bump]];
+        // Transfer X from vault to recipient
+        let seeds = crate::config_signer_seeds!(self.config);
         let signer_seeds = &[&seeds[..]];
         let transfer_x_accounts = Transfer {
             from: self.vault_x.to_account_info(),
@@ -136,7 +169,7 @@ impl<'info> Withdraw<'info> {
         let transfer_x_ctx = CpiContext::new_with_signer(cpi_program.clone(), transfer_x_accounts, signer_seeds);
         transfer(transfer_x_ctx, x_out)?;
 
-        // Transfer Y from vault to user
+        // Transfer Y from vault to recipient
         let transfer_y_accounts = Transfer {
             from: self.vault_y.to_account_info(),
             to: self.user_y.to_account_info(),
@@ -145,6 +178,60 @@ impl<'info> Withdraw<'info> {
         let transfer_y_ctx = CpiContext::new_with_signer(cpi_program, transfer_y_accounts, signer_seeds);
         transfer(transfer_y_ctx, y_out)?;
 
+        if close_empty_lp {
+            self.close_lp_if_empty()?;
+        }
+
+        emit!(WithdrawEvent {
+            user: self.user.key(),
+            recipient: self.recipient.key(),
+            lp_amount,
+            amount_x: x_out,
+            amount_y: y_out,
+        });
+
+        Ok(WithdrawResult { x_out, y_out })
+    }
+
+    /// Closes the user's LP token account, refunding its rent to the user, when it holds exactly
+    /// zero LP after the burn. Any dust left keeps the account open.
+    fn close_lp_if_empty(&mut self) -> Result<()> {
+        self.user_lp.reload()?;
+        if self.user_lp.amount != 0 {
+            return Ok(());
+        }
+
+        let cpi_accounts = CloseAccount {
+            account: self.user_lp.to_account_info(),
+            destination: self.user.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+        close_account(CpiContext::new(self.token_program.to_account_info(), cpi_accounts))
+    }
+
+    /// Rejects the withdraw while the user's deposit cooldown is still running.
+    fn check_cooldown(&self) -> Result<()> {
+        if self.config.withdraw_cooldown_secs == 0 || self.position.data_is_empty() {
+            return Ok(());
+        }
+
+        let data = self.position.try_borrow_data()?;
+        let position = Position::try_deserialize(&mut &data[..])?;
+        require!(
+            Clock::get()?.unix_timestamp >= position.unlock_ts,
+            AmmError::CooldownActive
+        );
         Ok(())
     }
-} 
\ No newline at end of file
+}
+
+#[event]
+pub struct WithdrawEvent {
+    pub user: Pubkey,
+    /// Wallet the withdrawn tokens were sent to.
+    pub recipient: Pubkey,
+    pub lp_amount: u64,
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
+ 
\ No newline at end of file
@@ -5,6 +5,7 @@
 pub mod constants;
 pub mod error;
 pub mod instructions;
+pub mod math;
 pub mod state;
 
 use anchor_lang::prelude::*;
@@ -19,27 +20,278 @@ declare_id!("7TLxX95eiarxKFaxw7D4GKgtQianhuaGtPzW8nnNyZGb");
 pub mod amm {
     use super::*;
 
-    /// Initializes a new AMM pool with the given seed, fee, and optional authority.
-    /// Creates the config, LP mint, and vaults for both tokens.
-    pub fn initialize(ctx: Context<Initialize>, seed: u64, fee: u16, authority: Option<Pubkey>) -> Result<()> {
-        ctx.accounts.init(seed, fee, authority, ctx.bumps)
+    /// Initializes a new AMM pool with the given seed, fee, optional authority, weights, and fee mode.
+    /// Creates the config, LP mint, and vaults for both tokens. Use 50/50 for a classic x*y=k pool.
+    /// `label` is an optional display name of at most 32 bytes; pass an empty string for none.
+    /// Pass `fee == u16::MAX` to use the global state's default fee. Creation is rejected while
+    /// the global state pauses it, and the creation fee is charged to the initializer.
+    pub fn initialize(ctx: Context<Initialize>, seed: u64, fee: u16, authority: Option<Pubkey>, weight_x: u8, weight_y: u8, fee_on_input: bool, label: String) -> Result<()> {
+        ctx.accounts.init(seed, fee, authority, weight_x, weight_y, fee_on_input, label, ctx.bumps)
     }
 
     /// Deposits tokens into the pool and mints LP tokens to the user.
     /// The user receives LP tokens representing their share of the pool.
-    pub fn deposit(ctx: Context<Deposit>, amount: u64, max_x: u64, max_y: u64) -> Result<()> {
-        ctx.accounts.deposit(amount, max_x, max_y)
+    /// Returns the token amounts actually deposited and the LP minted via return data.
+    /// A non-zero `lock_duration` (seconds) mints the LP into a per-user escrow instead; it can be
+    /// released with `release_locked_lp` once the lock expires and earns a fee-share boost.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64, max_x: u64, max_y: u64, lock_duration: i64) -> Result<DepositResult> {
+        ctx.accounts.deposit(amount, max_x, max_y, lock_duration, ctx.bumps)
     }
 
-    /// Swaps tokens using the constant product formula (x*y=k).
+    /// Swaps tokens using the constant product formula (x*y=k), or the weighted
+    /// constant-mean formula for non-50/50 pools.
     /// The user provides the input amount, minimum output, and direction (x_to_y).
-    pub fn swap(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64, x_to_y: bool) -> Result<()> {
-        ctx.accounts.swap(amount_in, min_amount_out, x_to_y)
+    /// An optional host fee token account for the output mint may be passed as the first
+    /// remaining account; it receives `HOST_FEE_BPS` of the pool fee.
+    /// An optional `price_guard` rejects the swap if the pre-trade spot price has moved too far
+    /// from the caller's expected price; `min_amount_out` still applies.
+    pub fn swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        x_to_y: bool,
+        price_guard: Option<PriceGuard>,
+    ) -> Result<()> {
+        ctx.accounts.swap(amount_in, min_amount_out, x_to_y, price_guard, ctx.remaining_accounts.first())
+    }
+
+    /// Swaps like `swap`, but bounds the output by a tolerance instead of a fixed minimum:
+    /// it must be within `max_slippage_bps` of `amount_in` valued at the pre-trade spot price.
+    /// The tolerance covers the pool fee and price impact combined, so it must exceed the fee.
+    /// Meant for CPI callers that cannot compute `min_amount_out` ahead of time.
+    pub fn swap_with_bps<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+        amount_in: u64,
+        max_slippage_bps: u16,
+        x_to_y: bool,
+    ) -> Result<()> {
+        ctx.accounts.swap_with_bps(amount_in, max_slippage_bps, x_to_y, ctx.remaining_accounts.first())
     }
 
     /// Withdraws liquidity by burning LP tokens and transferring the user's share of the pool tokens.
     /// The user receives their proportional share of both vault_x and vault_y.
-    pub fn withdraw(ctx: Context<Withdraw>, lp_amount: u64, min_x: u64, min_y: u64) -> Result<()> {
-        ctx.accounts.withdraw(lp_amount, min_x, min_y)
+    /// Returns the token amounts paid out via return data.
+    /// With `close_empty_lp`, a user left with exactly zero LP gets their LP account closed and its rent back.
+    pub fn withdraw(ctx: Context<Withdraw>, lp_amount: u64, min_x: u64, min_y: u64, close_empty_lp: bool) -> Result<WithdrawResult> {
+        ctx.accounts.withdraw(lp_amount, min_x, min_y, close_empty_lp)
+    }
+
+    /// Grows an existing pool's config account to the current `Config` size and creates its
+    /// fee state if it has none. Idempotent: pools that are already up to date are left untouched.
+    pub fn extend_config(ctx: Context<ExtendConfig>, _seed: u64) -> Result<()> {
+        ctx.accounts.extend(ctx.bumps)
+    }
+
+    /// Returns the pool's spot price in both directions, normalized by each mint's decimals.
+    /// Read-only; intended to be called through simulation.
+    pub fn get_spot_price(ctx: Context<GetSpotPrice>) -> Result<SpotPrice> {
+        ctx.accounts.get_spot_price()
+    }
+
+    /// Quotes a swap without executing it, using exactly the same math as `swap`.
+    /// Read-only; intended to be called through simulation.
+    pub fn quote(ctx: Context<Quote>, amount_in: u64, x_to_y: bool) -> Result<SwapQuote> {
+        ctx.accounts.quote(amount_in, x_to_y)
+    }
+
+    /// Returns the number of pools recorded in the global registry.
+    /// Read-only; intended to be called through simulation.
+    pub fn get_pool_count(ctx: Context<GetPoolCount>) -> Result<u64> {
+        ctx.accounts.get_pool_count()
+    }
+
+    /// Creates the program-wide config holding the protocol fee switch and treasury.
+    /// The signer becomes the global admin.
+    pub fn initialize_global_config(ctx: Context<InitializeGlobalConfig>, default_protocol_fee_bps: u16, treasury: Pubkey) -> Result<()> {
+        ctx.accounts.init(default_protocol_fee_bps, treasury, ctx.bumps)
+    }
+
+    /// Sets the share of every swap fee (in bps of the fee) that goes to the protocol treasury.
+    /// Requires the global admin signature; 0 switches the protocol fee off.
+    pub fn set_protocol_fee(ctx: Context<SetProtocolFee>, protocol_fee_bps: u16) -> Result<()> {
+        ctx.accounts.set_protocol_fee(protocol_fee_bps)
+    }
+
+    /// Transfers a pool's accrued protocol fees to the global treasury's token accounts.
+    pub fn collect_protocol_fees(ctx: Context<CollectProtocolFees>) -> Result<()> {
+        ctx.accounts.collect()
+    }
+
+    /// Caps the vault balances deposits may reach (0 = uncapped). Requires the pool authority.
+    pub fn set_deposit_cap(ctx: Context<SetDepositCap>, deposit_cap_x: u64, deposit_cap_y: u64) -> Result<()> {
+        ctx.accounts.set_deposit_cap(deposit_cap_x, deposit_cap_y)
+    }
+
+    /// Sets how long each user must wait after a deposit before withdrawing (0 = no cooldown).
+    /// Requires the pool authority.
+    pub fn set_withdraw_cooldown(ctx: Context<SetWithdrawCooldown>, withdraw_cooldown_secs: u32) -> Result<()> {
+        ctx.accounts.set_withdraw_cooldown(withdraw_cooldown_secs)
+    }
+
+    /// Records the pool's reserves, LP supply, time, and slot in its snapshot ring buffer.
+    /// Permissionless, but at most once every `MIN_CHECKPOINT_INTERVAL` seconds per pool.
+    pub fn checkpoint(ctx: Context<Checkpoint>) -> Result<()> {
+        ctx.accounts.checkpoint(ctx.bumps)
+    }
+
+    /// Returns the snapshot `index` steps back from the most recent one (0 = latest).
+    /// Read-only; intended to be called through simulation.
+    pub fn get_snapshot(ctx: Context<GetSnapshot>, index: u32) -> Result<Snapshot> {
+        ctx.accounts.get_snapshot(index)
+    }
+
+    /// Permanently closes the pool to deposits (burn-only LP). Swaps and withdrawals continue.
+    /// Requires the pool authority; cannot be undone.
+    pub fn close_deposits(ctx: Context<CloseDeposits>) -> Result<()> {
+        ctx.accounts.close_deposits()
+    }
+
+    /// Deposits tokens supplied in any ratio: swaps the excess side against the curve, then
+    /// deposits the balanced amounts and mints at least `min_lp` LP tokens. Leftover dust stays with the user.
+    pub fn swap_and_deposit(ctx: Context<SwapAndDeposit>, amount_x: u64, amount_y: u64, min_lp: u64) -> Result<()> {
+        ctx.accounts.swap_and_deposit(amount_x, amount_y, min_lp, ctx.bumps)
+    }
+
+    /// Transfers the accrued protocol fees of up to `MAX_COLLECT_BATCH` pools to the treasury.
+    /// Pools are passed as remaining accounts in groups of
+    /// (config, vault_x, vault_y, treasury_x, treasury_y); pools with nothing accrued are skipped.
+    pub fn collect_protocol_fees_multi<'info>(ctx: Context<'_, '_, 'info, 'info, CollectProtocolFeesMulti<'info>>) -> Result<()> {
+        ctx.accounts.collect_multi(ctx.remaining_accounts)
+    }
+
+    /// Replaces the pool's display label (at most 32 bytes of UTF-8; empty clears it).
+    /// Requires the pool authority.
+    pub fn set_label(ctx: Context<SetLabel>, label: String) -> Result<()> {
+        ctx.accounts.set_label(label)
+    }
+
+    /// Returns the pool's net reserves, LP supply, fee, weights, status flags and label.
+    /// Read-only; intended to be called through simulation.
+    pub fn get_pool_stats(ctx: Context<GetPoolStats>) -> Result<PoolStats> {
+        ctx.accounts.get_pool_stats()
+    }
+
+    /// Locks the pool, halting deposits, swaps and withdrawals, and records the reason.
+    /// Requires the pool authority; `CircuitBreaker` is reserved for automated locks.
+    pub fn lock_pool(ctx: Context<LockPool>, reason: LockReason) -> Result<()> {
+        ctx.accounts.lock_pool(reason)
+    }
+
+    /// Unlocks a locked pool. Requires the pool authority.
+    pub fn unlock_pool(ctx: Context<UnlockPool>) -> Result<()> {
+        ctx.accounts.unlock_pool()
+    }
+
+    /// Returns the caller's locked LP from escrow once their lock has expired.
+    pub fn release_locked_lp(ctx: Context<ReleaseLockedLp>) -> Result<()> {
+        ctx.accounts.release()
+    }
+
+    /// Creates the program-wide pool creation settings. The signer becomes the AMM admin.
+    pub fn initialize_global_state(ctx: Context<InitializeGlobalState>, default_fee: u16, pool_creation_fee_lamports: u64, treasury: Pubkey) -> Result<()> {
+        ctx.accounts.init(default_fee, pool_creation_fee_lamports, treasury, ctx.bumps)
+    }
+
+    /// Sets the fee new pools get when created with `fee == u16::MAX`. Requires the AMM admin.
+    pub fn set_default_fee(ctx: Context<SetDefaultFee>, default_fee: u16) -> Result<()> {
+        ctx.accounts.set_default_fee(default_fee)
+    }
+
+    /// Pauses or resumes pool creation. Requires the AMM admin.
+    pub fn set_pool_creation_paused(ctx: Context<SetPoolCreationPaused>, paused: bool) -> Result<()> {
+        ctx.accounts.set_pool_creation_paused(paused)
+    }
+
+    /// Sets the lamports charged for creating a pool; 0 makes it free. Requires the AMM admin.
+    pub fn set_pool_creation_fee(ctx: Context<SetPoolCreationFee>, pool_creation_fee_lamports: u64) -> Result<()> {
+        ctx.accounts.set_pool_creation_fee(pool_creation_fee_lamports)
+    }
+
+    /// Pays the caller the swap fees their LP earned since their last claim.
+    /// LP fees are held outside the reserves until claimed, so claiming does not move the price.
+    pub fn claim_fees(ctx: Context<ClaimFees>) -> Result<()> {
+        ctx.accounts.claim_fees()
+    }
+
+    /// Registers the LP in the caller's LP account, and the boosted weight of their locked LP,
+    /// to earn swap fees. Only needed for LP the program has not registered itself, e.g. LP
+    /// received by transfer or held from before the pool had a fee state.
+    pub fn sync_position(ctx: Context<SyncPosition>) -> Result<()> {
+        ctx.accounts.sync_position(ctx.bumps)
+    }
+
+    /// Restricts pool creation to approved mints (`true`) or opens it to any mint (`false`).
+    /// Requires the AMM admin.
+    pub fn set_permissioned_creation(ctx: Context<SetPermissionedCreation>, permissioned: bool) -> Result<()> {
+        ctx.accounts.set_permissioned_creation(permissioned)
+    }
+
+    /// Approves a mint for pool creation while creation is permissioned. Requires the AMM admin.
+    pub fn approve_mint(ctx: Context<ApproveMint>) -> Result<()> {
+        ctx.accounts.approve_mint(ctx.bumps)
+    }
+
+    /// Revokes a mint's approval; existing pools over it are unaffected. Requires the AMM admin.
+    pub fn revoke_mint(ctx: Context<RevokeMint>) -> Result<()> {
+        ctx.accounts.revoke_mint()
+    }
+
+    /// Burns the caller's LP tokens without withdrawing, donating their share of the reserves
+    /// to the remaining LPs. At least the minimum deposit's worth of LP must remain in supply.
+    pub fn burn_lp(ctx: Context<BurnLp>, amount: u64) -> Result<()> {
+        ctx.accounts.burn_lp(amount)
+    }
+}
+
+// Pure reference math shared by the instruction handlers and off-chain clients
+
+/// Output of swapping `amount_in` into a classic 50/50 pool that charges `fee_bps` on the input.
+/// Same-decimal reserves are assumed. Returns `None` on overflow or a fee above 10_000 bps.
+pub fn compute_swap_out(reserve_in: u64, reserve_out: u64, amount_in: u64, fee_bps: u16) -> Option<u64> {
+    let params = math::SwapParams {
+        fee_bps,
+        fee_on_input: true,
+        weight_in: 50,
+        weight_out: 50,
+        decimals_in: LP_DECIMALS,
+        decimals_out: LP_DECIMALS,
+    };
+    math::quote_swap(reserve_in, reserve_out, amount_in, &params).map(|quote| quote.amount_out)
+}
+
+/// Portion of `reserve` owned by `lp_amount` out of `lp_supply` LP tokens, rounded down.
+/// Returns `None` when `lp_supply` is zero.
+pub fn compute_proportional_share(reserve: u64, lp_amount: u64, lp_supply: u64) -> Option<u64> {
+    if lp_supply == 0 {
+        return None;
+    }
+    let share = (reserve as u128).checked_mul(lp_amount as u128)? / lp_supply as u128;
+    u64::try_from(share).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swap_out_known_vector() {
+        // 30 bps on a 1M/2M pool: 9_970 in after fee, 9_970 * 2_000_000 / 1_009_970 = 19_743
+        assert_eq!(compute_swap_out(1_000_000, 2_000_000, 10_000, 30), Some(19_743));
+        assert_eq!(compute_swap_out(2_000_000, 1_000_000, 10_000, 30), Some(4_960));
+        assert_eq!(compute_swap_out(1_000_000, 2_000_000, 10_000, 0), Some(19_801));
+    }
+
+    #[test]
+    fn swap_out_rejects_invalid_fee() {
+        assert_eq!(compute_swap_out(1_000_000, 2_000_000, 10_000, 10_001), None);
+    }
+
+    #[test]
+    fn proportional_share_known_vectors() {
+        assert_eq!(compute_proportional_share(1_000_000, 250, 1_000), Some(250_000));
+        // Rounds down in the pool's favor
+        assert_eq!(compute_proportional_share(10, 1, 3), Some(3));
+        assert_eq!(compute_proportional_share(u64::MAX, u64::MAX, u64::MAX), Some(u64::MAX));
+        assert_eq!(compute_proportional_share(1_000, 1, 0), None);
     }
 }
@@ -0,0 +1,994 @@
+// Fixed-point helpers shared by the AMM instructions.
+//
+// Prices are expressed as unsigned Q64.64 numbers: the upper 64 bits hold the
+// integer part and the lower 64 bits the fraction. Reserves are normalized by
+// `10^decimals` before dividing, so a price is always "whole units of Y per whole unit of X".
+//
+// Curve math runs on reserves normalized to a common `COMMON_DECIMALS` basis so pools
+// whose mints have different decimals (e.g. 9 vs 6) price and round consistently.
+// Results are denormalized back to native units, rounding in the pool's favor.
+//
+// Weighted pools use the constant-mean invariant `reserve_x^wx * reserve_y^wy = k`,
+// which needs a fractional power. It is computed as `exp(ln(base) * exponent)` with
+// series approximations whose error is far below one base unit, and results are
+// biased in the pool's favor so the approximation can never pay out too much.
+
+/// Number of fractional bits in a Q64.64 value.
+pub const Q64_FRACTION_BITS: u32 = 64;
+
+/// `1.0` in Q64.64.
+pub const Q64_ONE: u128 = 1 << Q64_FRACTION_BITS;
+
+/// `ln(2)` in Q64.64.
+pub const LN2_Q64: u128 = 12_786_308_645_202_655_660;
+
+/// Relative margin (2^-48) added to fractional powers to absorb approximation error.
+pub const POW_ERROR_SHIFT: u32 = 48;
+
+/// Decimal basis that curve math normalizes both tokens to.
+pub const COMMON_DECIMALS: u8 = 9;
+
+/// Divides `num / den` and returns the result in Q64.64, rounding down.
+/// Returns `None` on division by zero or when the integer part does not fit in 64 bits.
+pub fn div_q64(num: u128, den: u128) -> Option<u128> {
+    if den == 0 || den > u128::MAX >> 1 {
+        return None;
+    }
+
+    let integer = num / den;
+    if integer > u64::MAX as u128 {
+        return None;
+    }
+
+    // Long division for the fractional bits keeps every intermediate below 2 * den
+    let mut rem = num % den;
+    let mut fraction: u128 = 0;
+    for _ in 0..Q64_FRACTION_BITS {
+        rem <<= 1;
+        fraction <<= 1;
+        if rem >= den {
+            rem -= den;
+            fraction |= 1;
+        }
+    }
+
+    Some((integer << Q64_FRACTION_BITS) | fraction)
+}
+
+/// Returns `10^decimals` as a u128, or `None` if it does not fit.
+pub fn pow10(decimals: u8) -> Option<u128> {
+    10u128.checked_pow(decimals as u32)
+}
+
+/// Computes `a * b / c` with a 256-bit intermediate product.
+/// Returns `None` on division by zero or if the quotient does not fit in u128.
+pub fn mul_div(a: u128, b: u128, c: u128, round_up: bool) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+
+    // Full 256-bit product as (hi, lo) from 64-bit limbs
+    let mask = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & mask);
+    let (b1, b0) = (b >> 64, b & mask);
+    let p00 = a0 * b0;
+    let p01 = a0 * b1;
+    let p10 = a1 * b0;
+    let p11 = a1 * b1;
+    let mid = (p00 >> 64) + (p01 & mask) + (p10 & mask);
+    let lo = (p00 & mask) | (mid << 64);
+    let hi = p11 + (p01 >> 64) + (p10 >> 64) + (mid >> 64);
+
+    if hi >= c {
+        return None;
+    }
+    if hi == 0 {
+        let q = lo / c;
+        return Some(if round_up && lo % c != 0 { q.checked_add(1)? } else { q });
+    }
+
+    // Long division of (hi, lo) by c; the remainder always stays below c
+    let mut rem = hi;
+    let mut q: u128 = 0;
+    for i in (0..128).rev() {
+        let carry = rem >> 127;
+        rem = (rem << 1) | ((lo >> i) & 1);
+        q <<= 1;
+        if carry == 1 || rem >= c {
+            rem = rem.wrapping_sub(c);
+            q |= 1;
+        }
+    }
+
+    Some(if round_up && rem != 0 { q.checked_add(1)? } else { q })
+}
+
+/// Converts a native amount with `decimals` decimals to the `COMMON_DECIMALS` basis.
+/// Scaling up is exact; scaling down (mints with more than 9 decimals) rounds as requested.
+pub fn normalize(amount: u64, decimals: u8, round_up: bool) -> Option<u128> {
+    if decimals <= COMMON_DECIMALS {
+        (amount as u128).checked_mul(pow10(COMMON_DECIMALS - decimals)?)
+    } else {
+        let scale = pow10(decimals - COMMON_DECIMALS)?;
+        let amount = amount as u128;
+        Some(if round_up { amount.div_ceil(scale) } else { amount / scale })
+    }
+}
+
+/// Converts a `COMMON_DECIMALS` amount back to native units with `decimals` decimals.
+/// Returns `None` if the result does not fit in a u64.
+pub fn denormalize(amount: u128, decimals: u8, round_up: bool) -> Option<u64> {
+    let native = if decimals <= COMMON_DECIMALS {
+        let scale = pow10(COMMON_DECIMALS - decimals)?;
+        if round_up { amount.div_ceil(scale) } else { amount / scale }
+    } else {
+        amount.checked_mul(pow10(decimals - COMMON_DECIMALS)?)?
+    };
+    u64::try_from(native).ok()
+}
+
+/// Proportional deposit: the X and Y amounts required to mint `lp_amount` against the
+/// current reserves, rounded up so the pool is never underpaid.
+pub fn deposit_amounts(
+    reserve_x: u64,
+    reserve_y: u64,
+    lp_supply: u64,
+    lp_amount: u64,
+    decimals_x: u8,
+    decimals_y: u8,
+) -> Option<(u64, u64)> {
+    if lp_supply == 0 {
+        return None;
+    }
+
+    let side = |reserve: u64, decimals: u8| {
+        let reserve = normalize(reserve, decimals, true)?;
+        let amount = mul_div(reserve, lp_amount as u128, lp_supply as u128, true)?;
+        denormalize(amount, decimals, true)
+    };
+
+    Some((side(reserve_x, decimals_x)?, side(reserve_y, decimals_y)?))
+}
+
+/// Computes the decimal-normalized spot price of each token in terms of the other.
+/// For weighted pools the price is `(reserve_y / weight_y) / (reserve_x / weight_x)`.
+///
+/// Returns `(price_x_in_y, price_y_in_x)` in Q64.64, or `None` if either reserve is empty
+/// or a price cannot be represented.
+pub fn spot_price_q64(
+    reserve_x: u64,
+    reserve_y: u64,
+    decimals_x: u8,
+    decimals_y: u8,
+    weight_x: u8,
+    weight_y: u8,
+) -> Option<(u128, u128)> {
+    if reserve_x == 0 || reserve_y == 0 || weight_x == 0 || weight_y == 0 {
+        return None;
+    }
+
+    // (reserve_y / 10^dy / wy) / (reserve_x / 10^dx / wx) == reserve_y * 10^dx * wx / (reserve_x * 10^dy * wy)
+    let scaled_y = (reserve_y as u128)
+        .checked_mul(pow10(decimals_x)?)?
+        .checked_mul(weight_x as u128)?;
+    let scaled_x = (reserve_x as u128)
+        .checked_mul(pow10(decimals_y)?)?
+        .checked_mul(weight_y as u128)?;
+
+    Some((div_q64(scaled_y, scaled_x)?, div_q64(scaled_x, scaled_y)?))
+}
+
+/// Natural logarithm of a positive Q64.64 value, returned as a signed Q64.64.
+pub fn ln_q64(x: u128) -> Option<i128> {
+    if x == 0 {
+        return None;
+    }
+
+    // Split x = m * 2^k with m in [1, 2)
+    let msb = 127 - x.leading_zeros() as i32;
+    let k = msb - Q64_FRACTION_BITS as i32;
+    let m = if k >= 0 { x >> k } else { x << (-k) };
+
+    // ln(m) = 2 * atanh(z) with z = (m - 1) / (m + 1) in [0, 1/3)
+    let z = div_q64(m - Q64_ONE, m + Q64_ONE)?;
+    let z2 = (z * z) >> Q64_FRACTION_BITS;
+    let mut term = z;
+    let mut sum: u128 = 0;
+    let mut n: u128 = 1;
+    while term > 0 {
+        sum += term / n;
+        term = (term * z2) >> Q64_FRACTION_BITS;
+        n += 2;
+    }
+
+    Some(k as i128 * LN2_Q64 as i128 + 2 * sum as i128)
+}
+
+/// Exponential of a signed Q64.64 value, returned as an unsigned Q64.64.
+/// Returns `None` if the result does not fit.
+pub fn exp_q64(y: i128) -> Option<u128> {
+    // Split y = k * ln(2) + r with r in [0, ln(2))
+    let k = y.div_euclid(LN2_Q64 as i128);
+    let r = y.rem_euclid(LN2_Q64 as i128) as u128;
+
+    // Taylor series for exp(r); every term stays below 2^65
+    let mut term = Q64_ONE;
+    let mut sum = Q64_ONE;
+    let mut n: u128 = 1;
+    loop {
+        term = ((term * r) >> Q64_FRACTION_BITS) / n;
+        if term == 0 {
+            break;
+        }
+        sum += term;
+        n += 1;
+    }
+
+    if k >= 0 {
+        if k >= 62 {
+            return None;
+        }
+        Some(sum << k)
+    } else {
+        let shift = (-k) as u32;
+        Some(if shift >= 128 { 0 } else { sum >> shift })
+    }
+}
+
+/// Raises a Q64.64 `base` to the rational power `exp_num / exp_den`.
+pub fn pow_q64(base: u128, exp_num: u32, exp_den: u32) -> Option<u128> {
+    if exp_den == 0 {
+        return None;
+    }
+    if base == 0 {
+        return Some(0);
+    }
+
+    let ln = ln_q64(base)?;
+    exp_q64(ln.checked_mul(exp_num as i128)? / exp_den as i128)
+}
+
+/// Output amount for a weighted constant-mean swap:
+/// `reserve_out * (1 - (reserve_in / (reserve_in + amount_in))^(weight_in / weight_out))`.
+///
+/// `amount_in` must already have the fee removed. The result is rounded down and the
+/// power is nudged up by `2^-POW_ERROR_SHIFT` so approximation error always favors the pool.
+pub fn weighted_swap_out(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    weight_in: u8,
+    weight_out: u8,
+) -> Option<u64> {
+    if reserve_in == 0 || reserve_out == 0 || weight_in == 0 || weight_out == 0 {
+        return None;
+    }
+
+    let ratio = div_q64(reserve_in as u128, reserve_in as u128 + amount_in as u128)?;
+    let pow = pow_q64(ratio, weight_in as u32, weight_out as u32)?;
+    let pow = pow
+        .checked_add((pow >> POW_ERROR_SHIFT) + 1)?
+        .min(Q64_ONE);
+
+    // reserve_out < 2^64 and (1 - pow) <= 2^64, so the product fits in u128
+    let out = (reserve_out as u128 * (Q64_ONE - pow)) >> Q64_FRACTION_BITS;
+    u64::try_from(out).ok()
+}
+
+/// Amounts produced by quoting a swap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapAmounts {
+    /// Tokens sent to the user, net of any output-side fee.
+    pub amount_out: u64,
+    /// Fee kept by the pool; input token when fees are charged on input, output token otherwise.
+    pub fee_amount: u64,
+}
+
+/// Pool parameters needed to quote a swap, already oriented in the swap direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapParams {
+    pub fee_bps: u16,
+    pub fee_on_input: bool,
+    pub weight_in: u8,
+    pub weight_out: u8,
+    pub decimals_in: u8,
+    pub decimals_out: u8,
+}
+
+/// Constant product output: `amount_in * reserve_out / (reserve_in + amount_in)`, rounded down.
+pub fn constant_product_out(reserve_in: u64, reserve_out: u64, amount_in: u64) -> Option<u64> {
+    let numerator = (amount_in as u128).checked_mul(reserve_out as u128)?;
+    let denominator = (reserve_in as u128).checked_add(amount_in as u128)?;
+    if denominator == 0 {
+        return None;
+    }
+    u64::try_from(numerator / denominator).ok()
+}
+
+/// Constant product output computed on `COMMON_DECIMALS`-normalized reserves.
+/// The input side is rounded against the trader and the output is rounded down.
+pub fn normalized_constant_product_out(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    decimals_in: u8,
+    decimals_out: u8,
+) -> Option<u64> {
+    let reserve_in = normalize(reserve_in, decimals_in, true)?;
+    let reserve_out = normalize(reserve_out, decimals_out, false)?;
+    let amount_in = normalize(amount_in, decimals_in, false)?;
+
+    let out = mul_div(amount_in, reserve_out, reserve_in.checked_add(amount_in)?, false)?;
+    denormalize(out, decimals_out, false)
+}
+
+/// Quotes a swap for either fee mode. Both the swap handler and the quote view go through this.
+///
+/// - Fee on input: `fee_bps` is removed from `amount_in` before the curve is applied.
+/// - Fee on output: the curve is applied to the full `amount_in` and `fee_bps` of the result
+///   (rounded up) stays in the output vault.
+///
+/// Even weights use the integer constant product path on normalized reserves; other weights
+/// use the weighted constant-mean curve, which only depends on same-token ratios.
+pub fn quote_swap(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    params: &SwapParams,
+) -> Option<SwapAmounts> {
+    let SwapParams { fee_bps, fee_on_input, weight_in, weight_out, decimals_in, decimals_out } = *params;
+    let fee_bps = fee_bps as u128;
+    if fee_bps > 10_000 {
+        return None;
+    }
+
+    let curve_out = |amount: u64| {
+        if weight_in == weight_out {
+            normalized_constant_product_out(reserve_in, reserve_out, amount, decimals_in, decimals_out)
+        } else {
+            weighted_swap_out(reserve_in, reserve_out, amount, weight_in, weight_out)
+        }
+    };
+
+    if fee_on_input {
+        let amount_in_with_fee = (amount_in as u128 * (10_000 - fee_bps) / 10_000) as u64;
+        Some(SwapAmounts {
+            amount_out: curve_out(amount_in_with_fee)?,
+            fee_amount: amount_in - amount_in_with_fee,
+        })
+    } else {
+        let raw_out = curve_out(amount_in)?;
+        let fee_amount = ((raw_out as u128 * fee_bps).div_ceil(10_000)) as u64;
+        Some(SwapAmounts {
+            amount_out: raw_out - fee_amount,
+            fee_amount,
+        })
+    }
+}
+
+/// Output a swap would pay with no fee and no price impact, i.e. `amount_in` valued at the
+/// pre-trade spot price: `amount_in * (reserve_out / weight_out) / (reserve_in / weight_in)`,
+/// rounded down. Reserves and amounts are all native units, so mint decimals cancel out.
+pub fn ideal_swap_out(reserve_in: u64, reserve_out: u64, amount_in: u64, params: &SwapParams) -> Option<u128> {
+    let numerator = (amount_in as u128).checked_mul(params.weight_in as u128)?;
+    let denominator = (reserve_in as u128).checked_mul(params.weight_out as u128)?;
+    mul_div(numerator, reserve_out as u128, denominator, false)
+}
+
+/// Smallest output a swap may pay under a `max_slippage_bps` tolerance: the ideal output
+/// from `ideal_swap_out` less `max_slippage_bps` of it, rounded up. The tolerance bounds the
+/// fee and the price impact combined. Returns `None` for tolerances above 10_000 bps; an ideal
+/// output beyond u64 saturates, so no real swap can meet it.
+pub fn min_out_for_slippage(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    params: &SwapParams,
+    max_slippage_bps: u16,
+) -> Option<u64> {
+    if max_slippage_bps > 10_000 {
+        return None;
+    }
+    let ideal = ideal_swap_out(reserve_in, reserve_out, amount_in, params)?;
+    let min_out = mul_div(ideal, (10_000 - max_slippage_bps) as u128, 10_000, true)?;
+    Some(u64::try_from(min_out).unwrap_or(u64::MAX))
+}
+
+/// Host (aggregator) fee for a swap: `host_fee_bps` of the swap fee, paid in the output token.
+/// Fees charged on input are valued at the swap's own execution price, so the host is paid
+/// out of the pool's fee in either mode and the user's output is unchanged.
+pub fn host_fee_out(amount_in: u64, amounts: &SwapAmounts, fee_on_input: bool, host_fee_bps: u16) -> Option<u64> {
+    let fee_value = if fee_on_input {
+        let net_in = amount_in.checked_sub(amounts.fee_amount)?;
+        if net_in == 0 {
+            return Some(0);
+        }
+        mul_div(amounts.amount_out as u128, amounts.fee_amount as u128, net_in as u128, false)?
+    } else {
+        amounts.fee_amount as u128
+    };
+    u64::try_from(fee_value * host_fee_bps as u128 / 10_000).ok()
+}
+
+/// Amount of the excess token to swap so that what the user keeps matches the pool ratio
+/// after the swap has moved the reserves, for a swap-then-deposit zap.
+///
+/// Returns the largest `s <= amount_in` with
+/// `(amount_in - s) * (reserve_out - out(s)) >= (amount_other + out(s)) * (reserve_in + s)`.
+/// The search bisects over `quote_swap`, so it holds for every curve and fee mode.
+pub fn zap_swap_amount(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    amount_other: u64,
+    params: &SwapParams,
+) -> Option<u64> {
+    let balanced = |s: u64| -> Option<bool> {
+        let out = if s == 0 { 0 } else { quote_swap(reserve_in, reserve_out, s, params)?.amount_out };
+        let kept = ((amount_in - s) as u128).checked_mul(reserve_out.checked_sub(out)? as u128)?;
+        let pool = (amount_other as u128 + out as u128).checked_mul(reserve_in as u128 + s as u128)?;
+        Some(kept >= pool)
+    };
+
+    if !balanced(0)? {
+        return None;
+    }
+    let (mut lo, mut hi) = (0u64, amount_in);
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        if balanced(mid)? { lo = mid } else { hi = mid - 1 }
+    }
+    Some(lo)
+}
+
+/// Largest LP amount whose `deposit_amounts` fit within `amount_x` and `amount_y`.
+pub fn lp_for_amounts(
+    reserve_x: u64,
+    reserve_y: u64,
+    lp_supply: u64,
+    amount_x: u64,
+    amount_y: u64,
+    decimals_x: u8,
+    decimals_y: u8,
+) -> Option<u64> {
+    if reserve_x == 0 || reserve_y == 0 {
+        return None;
+    }
+    let upper = |amount: u64, reserve: u64| mul_div(amount as u128, lp_supply as u128, reserve as u128, false);
+    let mut hi = u64::try_from(upper(amount_x, reserve_x)?.min(upper(amount_y, reserve_y)?)).ok()?;
+    let mut lo = 0u64;
+
+    let fits = |lp: u64| -> Option<bool> {
+        let (x, y) = deposit_amounts(reserve_x, reserve_y, lp_supply, lp, decimals_x, decimals_y)?;
+        Some(x <= amount_x && y <= amount_y)
+    };
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        if fits(mid)? { lo = mid } else { hi = mid - 1 }
+    }
+    Some(lo)
+}
+
+/// Relative distance of `actual` from `expected`, in basis points of `expected`, rounded up.
+/// Both prices must use the same fixed-point scale. Returns `None` when `expected` is 0.
+pub fn price_deviation_bps(actual: u128, expected: u128) -> Option<u128> {
+    mul_div(actual.abs_diff(expected), 10_000, expected, true)
+}
+
+/// Fee-share weight of an LP lock, in bps (10_000 = 1x). Grows linearly from 1x for an instant
+/// lock to `max_boost_bps` at `full_boost_duration` seconds and stays there for longer locks.
+pub fn lock_boost_bps(lock_duration: i64, full_boost_duration: i64, max_boost_bps: u16) -> Option<u16> {
+    if lock_duration < 0 || full_boost_duration <= 0 || max_boost_bps < 10_000 {
+        return None;
+    }
+    let duration = lock_duration.min(full_boost_duration) as u128;
+    let extra = (max_boost_bps - 10_000) as u128 * duration / full_boost_duration as u128;
+    Some(10_000 + extra as u16)
+}
+
+/// Fee weight of `lp_amount` locked LP with a `boost_bps` fee-share boost (10_000 = 1x).
+/// Rounds down.
+pub fn locked_fee_weight(lp_amount: u64, boost_bps: u16) -> Option<u64> {
+    u64::try_from(lp_amount as u128 * boost_bps as u128 / 10_000).ok()
+}
+
+/// Growth of the per-unit-of-weight fee index, in Q64.64, when `fee_amount` is shared by
+/// `total_weight` units of fee weight. Rounds down so the index never promises more than was collected.
+pub fn fee_growth_delta(fee_amount: u64, total_weight: u64) -> Option<u128> {
+    div_q64(fee_amount as u128, total_weight as u128)
+}
+
+/// Fees earned by `weight` units of fee weight while the fee index grew from `checkpoint` to `growth`.
+/// Rounds down, so the sum over every holder never exceeds the fees collected.
+pub fn fees_earned(growth: u128, checkpoint: u128, weight: u64) -> Option<u64> {
+    let delta = growth.checked_sub(checkpoint)?;
+    u64::try_from(mul_div(delta, weight as u128, Q64_ONE, false)?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn div_q64_exact_and_fractional() {
+        assert_eq!(div_q64(2, 1), Some(2 * Q64_ONE));
+        assert_eq!(div_q64(1, 2), Some(Q64_ONE / 2));
+        assert_eq!(div_q64(1, 3), Some(Q64_ONE / 3));
+        assert_eq!(div_q64(0, 7), Some(0));
+        assert_eq!(div_q64(1, 0), None);
+    }
+
+    #[test]
+    fn div_q64_rejects_integer_overflow() {
+        assert_eq!(div_q64(u64::MAX as u128, 1), Some((u64::MAX as u128) << 64));
+        assert_eq!(div_q64(u64::MAX as u128 + 1, 1), None);
+    }
+
+    #[test]
+    fn price_deviation_is_relative_to_the_expected_price() {
+        assert_eq!(price_deviation_bps(2 * Q64_ONE, 2 * Q64_ONE), Some(0));
+        assert_eq!(price_deviation_bps(101 * Q64_ONE, 100 * Q64_ONE), Some(100));
+        assert_eq!(price_deviation_bps(99 * Q64_ONE, 100 * Q64_ONE), Some(100));
+        // Rounds up so a guard never lets a slightly larger move through
+        assert_eq!(price_deviation_bps(100_001, 100_000), Some(1));
+        assert_eq!(price_deviation_bps(Q64_ONE, 0), None);
+    }
+
+    #[test]
+    fn spot_price_equal_decimals() {
+        let (x_in_y, y_in_x) = spot_price_q64(100_000, 200_000, 6, 6, 50, 50).unwrap();
+        assert_eq!(x_in_y, 2 * Q64_ONE);
+        assert_eq!(y_in_x, Q64_ONE / 2);
+    }
+
+    #[test]
+    fn spot_price_normalizes_mismatched_decimals() {
+        // 1 whole X (0 decimals) against 5 whole Y (9 decimals)
+        let (x_in_y, y_in_x) = spot_price_q64(1, 5_000_000_000, 0, 9, 50, 50).unwrap();
+        assert_eq!(x_in_y, 5 * Q64_ONE);
+        assert_eq!(y_in_x, Q64_ONE / 5);
+
+        // The same pool with the decimals swapped onto the other side
+        let (x_in_y, y_in_x) = spot_price_q64(5_000_000_000, 1, 9, 0, 50, 50).unwrap();
+        assert_eq!(x_in_y, Q64_ONE / 5);
+        assert_eq!(y_in_x, 5 * Q64_ONE);
+    }
+
+    #[test]
+    fn spot_price_extreme_reserves() {
+        // Largest Y reserve against a single base unit of X, 0 vs 9 decimals
+        let (x_in_y, y_in_x) = spot_price_q64(1, u64::MAX, 0, 9, 50, 50).unwrap();
+        assert_eq!(x_in_y >> 64, (u64::MAX / 1_000_000_000) as u128);
+        assert_eq!(y_in_x >> 64, 0);
+        assert!(y_in_x > 0);
+
+        // Inverted decimals push the price past the Q64.64 integer range
+        assert_eq!(spot_price_q64(1, u64::MAX, 9, 0, 50, 50), None);
+    }
+
+    #[test]
+    fn spot_price_empty_reserves() {
+        assert_eq!(spot_price_q64(0, 1, 6, 6, 50, 50), None);
+        assert_eq!(spot_price_q64(1, 0, 6, 6, 50, 50), None);
+    }
+
+    #[test]
+    fn spot_price_weighted() {
+        // 80/20 pool holding equal value: 4 Y per X
+        let (x_in_y, y_in_x) = spot_price_q64(1_000_000, 1_000_000, 6, 6, 80, 20).unwrap();
+        assert_eq!(x_in_y, 4 * Q64_ONE);
+        assert_eq!(y_in_x, Q64_ONE / 4);
+    }
+
+    #[test]
+    fn ln_and_exp_round_trip() {
+        assert_eq!(ln_q64(Q64_ONE), Some(0));
+        assert_eq!(ln_q64(0), None);
+
+        let ln2 = ln_q64(2 * Q64_ONE).unwrap();
+        assert!((ln2 - LN2_Q64 as i128).abs() < 4);
+
+        for x in [Q64_ONE / 1_000_000, Q64_ONE / 3, Q64_ONE, 7 * Q64_ONE / 2, 1_000 * Q64_ONE] {
+            let back = exp_q64(ln_q64(x).unwrap()).unwrap();
+            let diff = back.abs_diff(x);
+            assert!(diff <= (x >> 50) + 16, "round trip drifted for {x}: {back}");
+        }
+    }
+
+    #[test]
+    fn pow_matches_integer_powers() {
+        let half = Q64_ONE / 2;
+        let quarter = pow_q64(half, 2, 1).unwrap();
+        assert!(quarter.abs_diff(Q64_ONE / 4) < 1 << 16);
+
+        let root = pow_q64(Q64_ONE / 4, 1, 2).unwrap();
+        assert!(root.abs_diff(half) < 1 << 16);
+    }
+
+    /// Reference outputs computed off-chain in f64:
+    /// `reserve_out * (1 - (reserve_in / (reserve_in + amount_in)) ** (weight_in / weight_out))`
+    #[test]
+    fn weighted_swap_matches_f64_reference() {
+        let cases: [(u64, u64, u64, u8, u8, f64); 5] = [
+            (1_000_000, 4_000_000, 10_000, 80, 20, 156_078.622_068_735_13),
+            (4_000_000, 1_000_000, 10_000, 20, 80, 624.025_264_843_286_4),
+            (1_000_000_000, 250_000_000, 500_000_000, 80, 20, 200_617_283.950_617_28),
+            (1_000_000_000_000_000, 1_000_000_000_000, 100_000_000_000_000, 30, 70, 40_024_216_074.617_48),
+            (1_000_000, 1_000_000, 1_000_000, 50, 50, 500_000.0),
+        ];
+
+        for (reserve_in, reserve_out, amount_in, weight_in, weight_out, expected) in cases {
+            let out = weighted_swap_out(reserve_in, reserve_out, amount_in, weight_in, weight_out).unwrap();
+            // Never pays more than the exact value, and stays within a few base units of it
+            assert!(out as f64 <= expected.floor() + 1.0, "overpaid: {out} vs {expected}");
+            let tolerance = (expected * 1e-12).max(2.0);
+            assert!(expected - out as f64 <= tolerance, "too far below reference: {out} vs {expected}");
+        }
+    }
+
+    #[test]
+    fn weighted_swap_preserves_invariant() {
+        // 80/20 pool: reserve_x^0.8 * reserve_y^0.2 must not decrease after a swap
+        let (rx, ry, amount_in) = (5_000_000u64, 2_000_000u64, 750_000u64);
+        let out = weighted_swap_out(rx, ry, amount_in, 80, 20).unwrap();
+        let before = (rx as f64).powf(0.8) * (ry as f64).powf(0.2);
+        let after = ((rx + amount_in) as f64).powf(0.8) * ((ry - out) as f64).powf(0.2);
+        assert!(after >= before);
+    }
+
+    fn params(fee_bps: u16, fee_on_input: bool) -> SwapParams {
+        SwapParams { fee_bps, fee_on_input, weight_in: 50, weight_out: 50, decimals_in: 6, decimals_out: 6 }
+    }
+
+    #[test]
+    fn quote_fee_on_input_matches_inline_formula() {
+        let quote = quote_swap(1_000_000, 2_000_000, 10_000, &params(30, true)).unwrap();
+        // 10_000 * 0.997 = 9_970 in; 9_970 * 2_000_000 / 1_009_970 = 19_743
+        assert_eq!(quote.fee_amount, 30);
+        assert_eq!(quote.amount_out, 19_743);
+    }
+
+    #[test]
+    fn quote_fee_on_output_is_denominated_in_output_token() {
+        let quote = quote_swap(1_000_000, 2_000_000, 10_000, &params(30, false)).unwrap();
+        // Raw output 10_000 * 2_000_000 / 1_010_000 = 19_801; fee ceil(19_801 * 0.003) = 60
+        assert_eq!(quote.fee_amount, 60);
+        assert_eq!(quote.amount_out, 19_741);
+    }
+
+    #[test]
+    fn quote_never_decreases_k_in_either_mode() {
+        let reserves = [(1_000u64, 1_000u64), (1_000_000, 2_000_000), (7_777_777, 13), (u32::MAX as u64, 5_000_000)];
+        let amounts = [1u64, 17, 1_000, 250_000, 10_000_000];
+        let fees = [0u16, 1, 30, 500, 10_000];
+
+        for (reserve_in, reserve_out) in reserves {
+            for amount_in in amounts {
+                for fee in fees {
+                    for fee_on_input in [true, false] {
+                        let quote = quote_swap(reserve_in, reserve_out, amount_in, &params(fee, fee_on_input)).unwrap();
+                        let k_before = reserve_in as u128 * reserve_out as u128;
+                        let k_after = (reserve_in + amount_in) as u128 * (reserve_out - quote.amount_out) as u128;
+                        assert!(k_after >= k_before, "pool lost value: {reserve_in}/{reserve_out} in={amount_in} fee={fee}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ideal_out_is_the_spot_price_value_of_the_input() {
+        assert_eq!(ideal_swap_out(1_000_000, 2_000_000, 10_000, &params(30, true)), Some(20_000));
+        // 80/20 pool: spot price is (reserve_out / 20) / (reserve_in / 80)
+        let weighted = SwapParams { weight_in: 80, weight_out: 20, ..params(0, true) };
+        assert_eq!(ideal_swap_out(1_000_000, 1_000_000, 1_000, &weighted), Some(4_000));
+        assert_eq!(ideal_swap_out(0, 1_000, 1, &params(0, true)), None);
+    }
+
+    #[test]
+    fn slippage_bound_covers_fee_and_price_impact() {
+        // Deep pool: 30 bps fee plus ~1% impact fits in 150 bps but not in 100 bps
+        let out = quote_swap(1_000_000, 2_000_000, 10_000, &params(30, true)).unwrap().amount_out;
+        assert!(out >= min_out_for_slippage(1_000_000, 2_000_000, 10_000, &params(30, true), 150).unwrap());
+        assert!(out < min_out_for_slippage(1_000_000, 2_000_000, 10_000, &params(30, true), 100).unwrap());
+
+        // Thin pool: swapping 10% of the reserve moves the price ~9% before any fee
+        let out = quote_swap(1_000, 1_000, 100, &params(0, true)).unwrap().amount_out;
+        assert_eq!(out, 90);
+        assert_eq!(min_out_for_slippage(1_000, 1_000, 100, &params(0, true), 1_000), Some(90));
+        assert_eq!(min_out_for_slippage(1_000, 1_000, 100, &params(0, true), 999), Some(91));
+
+        // Both fee modes are held to the same bound
+        for fee_on_input in [true, false] {
+            let out = quote_swap(1_000, 1_000, 100, &params(500, fee_on_input)).unwrap().amount_out;
+            assert!(out < min_out_for_slippage(1_000, 1_000, 100, &params(500, fee_on_input), 1_000).unwrap());
+            assert!(out >= min_out_for_slippage(1_000, 1_000, 100, &params(500, fee_on_input), 1_500).unwrap());
+        }
+
+        assert_eq!(min_out_for_slippage(1_000, 1_000, 100, &params(0, true), 10_000), Some(0));
+        assert_eq!(min_out_for_slippage(1_000, 1_000, 100, &params(0, true), 10_001), None);
+    }
+
+    #[test]
+    fn quote_rejects_fee_above_100_percent() {
+        assert_eq!(quote_swap(1_000, 1_000, 10, &params(10_001, true)), None);
+    }
+
+    #[test]
+    fn mul_div_handles_256_bit_products() {
+        assert_eq!(mul_div(6, 7, 3, false), Some(14));
+        assert_eq!(mul_div(7, 7, 3, false), Some(16));
+        assert_eq!(mul_div(7, 7, 3, true), Some(17));
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX, false), Some(u128::MAX));
+        assert_eq!(mul_div(1 << 100, 1 << 100, 1 << 90, false), Some(1 << 110));
+        assert_eq!(mul_div((1 << 100) + 1, 1 << 100, 1 << 90, true), Some((1 << 110) + (1 << 10)));
+        assert_eq!(mul_div(u128::MAX, 2, 1, false), None);
+        assert_eq!(mul_div(1, 1, 0, false), None);
+    }
+
+    #[test]
+    fn normalize_round_trips() {
+        assert_eq!(normalize(1_234, 6, false), Some(1_234_000));
+        assert_eq!(normalize(1_234, 9, false), Some(1_234));
+        assert_eq!(normalize(1_234, 0, false), Some(1_234_000_000_000));
+        assert_eq!(normalize(1_999, 12, false), Some(1));
+        assert_eq!(normalize(1_999, 12, true), Some(2));
+
+        assert_eq!(denormalize(1_234_000, 6, false), Some(1_234));
+        assert_eq!(denormalize(1_234_567, 6, false), Some(1_234));
+        assert_eq!(denormalize(1_234_567, 6, true), Some(1_235));
+        assert_eq!(denormalize(u64::MAX as u128 * 1_000, 6, false), Some(u64::MAX));
+        assert_eq!(denormalize(u64::MAX as u128 + 1, 9, false), None);
+    }
+
+    /// Regression for a 9/6 pool: the library call `xy_deposit_amounts_from_l(.., 6)` used
+    /// `6` as a fixed-point multiplier, truncating the (supply + amount) / supply ratio to a
+    /// multiple of 1/6 and charging nothing for a 1% deposit.
+    #[test]
+    fn deposit_amounts_nine_six_pool() {
+        // 1 X (9 decimals) and 2 Y (6 decimals) backing 1 LP; deposit 1% of supply
+        let (reserve_x, reserve_y, supply, amount) = (1_000_000_000u64, 2_000_000u64, 1_000_000u64, 10_000u64);
+
+        let legacy = |reserve: u64| {
+            let ratio = (supply as u128 + amount as u128) * 6 / supply as u128;
+            (reserve as u128 * ratio / 6 - reserve as u128) as u64
+        };
+        assert_eq!((legacy(reserve_x), legacy(reserve_y)), (0, 0));
+
+        assert_eq!(
+            deposit_amounts(reserve_x, reserve_y, supply, amount, 9, 6),
+            Some((10_000_000, 20_000))
+        );
+    }
+
+    #[test]
+    fn deposit_amounts_round_up() {
+        // 1/3 of a 10-unit reserve must cost 4, never 3
+        assert_eq!(deposit_amounts(10, 10, 3, 1, 6, 6), Some((4, 4)));
+        assert_eq!(deposit_amounts(1, 1, 0, 1, 6, 6), None);
+    }
+
+    #[test]
+    fn normalized_curve_matches_native_for_small_decimals() {
+        for (decimals_in, decimals_out) in [(6, 6), (9, 6), (6, 9), (0, 9)] {
+            for amount_in in [1u64, 999, 123_456, 10_000_000] {
+                assert_eq!(
+                    normalized_constant_product_out(1_000_000_000, 2_000_000, amount_in, decimals_in, decimals_out),
+                    constant_product_out(1_000_000_000, 2_000_000, amount_in),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn normalized_curve_favors_pool_for_large_decimals() {
+        // 12-decimal input loses sub-basis precision; output must never exceed the exact value
+        let exact = constant_product_out(1_000_000_000_000_000, 2_000_000, 1_234_567_891).unwrap();
+        let normalized = normalized_constant_product_out(1_000_000_000_000_000, 2_000_000, 1_234_567_891, 12, 6).unwrap();
+        assert!(normalized <= exact);
+    }
+
+    /// The formula `Swap::swap` computed inline before quotes moved into this module: the fee is
+    /// removed from the input, then `x * y = k` is applied in native units, rounding down.
+    fn inline_swap_out(reserve_in: u64, reserve_out: u64, amount_in: u64, fee_bps: u16) -> u64 {
+        let amount_in_with_fee = amount_in as u128 * (10_000 - fee_bps as u128) / 10_000;
+        (amount_in_with_fee * reserve_out as u128 / (reserve_in as u128 + amount_in_with_fee)) as u64
+    }
+
+    /// Differential check of the shared quote against the old inline formula over a grid of
+    /// reserves, amounts and fees. Documents every behavioral difference of the switch:
+    /// - fee on input, up to 9 decimals: identical output and fee;
+    /// - fee on input, more than 9 decimals: never more than the inline formula;
+    /// - fee on output: the full input goes through the curve and the fee comes out of the
+    ///   result, so output plus fee equals the fee-free inline output.
+    #[test]
+    fn quote_matches_inline_formula_across_grid() {
+        let reserves = [
+            (1_000u64, 1_000u64),
+            (1_000_000, 2_000_000),
+            (7_777_777, 13),
+            (u32::MAX as u64, 5_000_000),
+            (1 << 50, 1 << 40),
+        ];
+        let amounts = [1u64, 17, 1_000, 250_000, 10_000_000, 1 << 40];
+        let fees = [0u16, 1, 30, 500, 9_999, 10_000];
+
+        for (reserve_in, reserve_out) in reserves {
+            for amount_in in amounts {
+                for fee in fees {
+                    let inline = inline_swap_out(reserve_in, reserve_out, amount_in, fee);
+                    let case = format!("{reserve_in}/{reserve_out} in={amount_in} fee={fee}");
+
+                    for decimals in [0, 6, 9] {
+                        let swap_params = SwapParams { decimals_in: decimals, decimals_out: decimals, ..params(fee, true) };
+                        let quote = quote_swap(reserve_in, reserve_out, amount_in, &swap_params).unwrap();
+                        assert_eq!(quote.amount_out, inline, "{case} decimals={decimals}");
+                        assert_eq!(quote.fee_amount, amount_in - (amount_in as u128 * (10_000 - fee as u128) / 10_000) as u64);
+                    }
+
+                    let swap_params = SwapParams { decimals_in: 12, decimals_out: 12, ..params(fee, true) };
+                    let quote = quote_swap(reserve_in, reserve_out, amount_in, &swap_params).unwrap();
+                    assert!(quote.amount_out <= inline, "{case} decimals=12");
+
+                    let quote = quote_swap(reserve_in, reserve_out, amount_in, &params(fee, false)).unwrap();
+                    assert_eq!(quote.amount_out + quote.fee_amount, inline_swap_out(reserve_in, reserve_out, amount_in, 0), "{case} fee on output");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lock_boost_scales_up_to_the_full_duration() {
+        const DAY: i64 = 86_400;
+        assert_eq!(lock_boost_bps(0, 30 * DAY, 15_000), Some(10_000));
+        assert_eq!(lock_boost_bps(15 * DAY, 30 * DAY, 15_000), Some(12_500));
+        assert_eq!(lock_boost_bps(30 * DAY, 30 * DAY, 15_000), Some(15_000));
+        assert_eq!(lock_boost_bps(365 * DAY, 30 * DAY, 15_000), Some(15_000));
+        assert_eq!(lock_boost_bps(-1, 30 * DAY, 15_000), None);
+        assert_eq!(lock_boost_bps(DAY, 0, 15_000), None);
+    }
+
+    #[test]
+    fn locked_fee_weight_scales_by_boost() {
+        assert_eq!(locked_fee_weight(20_000, 10_000), Some(20_000));
+        assert_eq!(locked_fee_weight(20_000, 15_000), Some(30_000));
+        assert_eq!(locked_fee_weight(3, 12_500), Some(3));
+        assert_eq!(locked_fee_weight(0, 15_000), Some(0));
+        assert_eq!(locked_fee_weight(u64::MAX, 10_000), Some(u64::MAX));
+        assert_eq!(locked_fee_weight(u64::MAX, 15_000), None);
+    }
+
+    #[test]
+    fn fees_earned_splits_fees_pro_rata() {
+        let growth = fee_growth_delta(1_000, 4_000).unwrap();
+        assert_eq!(growth, Q64_ONE / 4);
+        assert_eq!(fees_earned(growth, 0, 1_000), Some(250));
+        assert_eq!(fees_earned(growth, 0, 3_000), Some(750));
+        assert_eq!(fees_earned(growth, growth, 3_000), Some(0));
+        assert_eq!(fees_earned(0, growth, 1), None);
+        assert_eq!(fee_growth_delta(1, 0), None);
+    }
+
+    /// Replays a pseudo-random mix of deposits, withdrawals, fee-bearing swaps and claims
+    /// with several LPs, settling each LP before its balance changes exactly as the program
+    /// does. Claims must never exceed collected fees, and whatever was not claimed plus
+    /// every claim must add back up to the fees collected.
+    #[test]
+    fn fee_claims_plus_unclaimed_equal_fees_collected() {
+        struct Lp {
+            balance: u64,
+            checkpoint: u128,
+            owed: u64,
+            claimed: u64,
+        }
+
+        let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = |bound: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % bound
+        };
+
+        for _ in 0..50 {
+            let mut lps: Vec<Lp> = (0..4)
+                .map(|_| Lp { balance: 0, checkpoint: 0, owed: 0, claimed: 0 })
+                .collect();
+            let (mut growth, mut supply, mut collected, mut unclaimed, mut swaps) = (0u128, 0u64, 0u64, 0u64, 0u64);
+
+            for _ in 0..200 {
+                let lp = next(lps.len() as u64) as usize;
+                let lp = &mut lps[lp];
+                let settle = |lp: &mut Lp, growth: u128| {
+                    lp.owed += fees_earned(growth, lp.checkpoint, lp.balance).unwrap();
+                    lp.checkpoint = growth;
+                };
+
+                match next(4) {
+                    0 => {
+                        let amount = 1 + next(1_000_000);
+                        settle(lp, growth);
+                        lp.balance += amount;
+                        supply += amount;
+                    }
+                    1 if lp.balance > 0 => {
+                        let amount = 1 + next(lp.balance);
+                        settle(lp, growth);
+                        lp.balance -= amount;
+                        supply -= amount;
+                    }
+                    2 if supply > 0 => {
+                        let fee = next(10_000);
+                        growth += fee_growth_delta(fee, supply).unwrap();
+                        collected += fee;
+                        unclaimed += fee;
+                        swaps += 1;
+                    }
+                    _ => {
+                        settle(lp, growth);
+                        unclaimed -= lp.owed;
+                        lp.claimed += lp.owed;
+                        lp.owed = 0;
+                    }
+                }
+            }
+
+            // Settling everyone leaves at most one unit of rounding dust per LP per swap behind
+            let mut owed = 0;
+            for lp in lps.iter_mut() {
+                lp.owed += fees_earned(growth, lp.checkpoint, lp.balance).unwrap();
+                owed += lp.owed;
+            }
+            let claimed: u64 = lps.iter().map(|lp| lp.claimed).sum();
+            assert_eq!(claimed + unclaimed, collected);
+            assert!(claimed + owed <= collected);
+            assert!(collected - (claimed + owed) <= swaps * lps.len() as u64);
+        }
+    }
+
+    #[test]
+    fn host_fee_is_a_slice_of_the_pool_fee() {
+        // Fee on output: 20% of the 60 Y fee
+        let output = quote_swap(1_000_000, 2_000_000, 10_000, &params(30, false)).unwrap();
+        assert_eq!(host_fee_out(10_000, &output, false, 2_000), Some(12));
+
+        // Fee on input: the 30 X fee is worth 19_743 * 30 / 9_970 = 59 Y, 20% of which is 11 Y
+        let input = quote_swap(1_000_000, 2_000_000, 10_000, &params(30, true)).unwrap();
+        assert_eq!(host_fee_out(10_000, &input, true, 2_000), Some(11));
+
+        // Never more than the whole fee
+        assert_eq!(host_fee_out(10_000, &output, false, 10_000), Some(output.fee_amount));
+    }
+
+    #[test]
+    fn zap_swap_amount_balances_the_leftover() {
+        let p = params(30, true);
+        let (reserve_in, reserve_out) = (1_000_000u64, 1_000_000u64);
+        let s = zap_swap_amount(reserve_in, reserve_out, 100_000, 0, &p).unwrap();
+
+        // Single-sided zap into an even pool swaps a bit under half
+        assert!((48_000..49_000).contains(&s), "swapped {}", s);
+
+        let ratio_after = |s: u64| {
+            let out = quote_swap(reserve_in, reserve_out, s, &p).unwrap().amount_out;
+            ((100_000 - s) as f64 / out as f64, (reserve_in + s) as f64 / (reserve_out - out) as f64)
+        };
+        let (kept, pool) = ratio_after(s);
+        assert!((kept - pool).abs() / pool < 1e-3);
+
+        // Already balanced input needs no swap
+        assert_eq!(zap_swap_amount(1_000_000, 2_000_000, 1_000, 2_000, &p), Some(0));
+    }
+
+    #[test]
+    fn lp_for_amounts_is_the_largest_fitting_deposit() {
+        let (rx, ry, supply) = (1_000_000u64, 2_000_000u64, 1_000_000u64);
+        let lp = lp_for_amounts(rx, ry, supply, 10_000, 25_000, 6, 6).unwrap();
+        // X is the binding side: 1% of the pool
+        assert_eq!(lp, 10_000);
+        let (x, y) = deposit_amounts(rx, ry, supply, lp, 6, 6).unwrap();
+        assert!(x <= 10_000 && y <= 25_000);
+        let (x, y) = deposit_amounts(rx, ry, supply, lp + 1, 6, 6).unwrap();
+        assert!(x > 10_000 || y > 25_000);
+    }
+}
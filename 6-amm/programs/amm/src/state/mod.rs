@@ -1,5 +1,26 @@
 use anchor_lang::prelude::*;
 
+use crate::{
+    constants::{ LABEL_LEN, MIN_RESERVE, REGISTRY_PAGE_CAPACITY, SNAPSHOT_CAPACITY },
+    error::AmmError,
+    math::{ self, SwapAmounts, SwapParams },
+};
+
+/// Canonical signer seeds for a pool's config PDA: `[b"config", seed, config_bump]`.
+/// Every CPI signed by the config must build its seeds through this macro so a change to
+/// the seed scheme only has to be made here.
+///
+/// ```ignore
+/// let seeds = config_signer_seeds!(self.config);
+/// let signer_seeds = &[&seeds[..]];
+/// ```
+#[macro_export]
+macro_rules! config_signer_seeds {
+    ($config:expr) => {
+        &[&b"config"[..], &$config.seed.to_le_bytes(), &[$config.config_bump]]
+    };
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct Config {
@@ -11,4 +32,887 @@ pub struct Config {
     pub locked: bool,
     pub config_bump: u8,
     pub lp_bump: u8,
-}
\ No newline at end of file
+    /// Pool weight of token X as a percentage (weight_x + weight_y == 100).
+    pub weight_x: u8,
+    /// Pool weight of token Y as a percentage.
+    pub weight_y: u8,
+    /// Whether the swap fee is taken from the input amount (true) or from the output amount (false).
+    pub fee_on_input: bool,
+    /// Decimals of mint_x, cached so curve math can normalize reserves.
+    pub decimals_x: u8,
+    /// Decimals of mint_y.
+    pub decimals_y: u8,
+    /// Protocol fees in token X sitting in vault_x but owed to the global treasury.
+    pub protocol_fees_x: u64,
+    /// Protocol fees in token Y sitting in vault_y but owed to the global treasury.
+    pub protocol_fees_y: u64,
+    /// Maximum vault_x balance deposits may reach; 0 means uncapped.
+    pub deposit_cap_x: u64,
+    /// Maximum vault_y balance deposits may reach; 0 means uncapped.
+    pub deposit_cap_y: u64,
+    /// Seconds a user must wait after their last deposit before withdrawing; 0 disables the cooldown.
+    pub withdraw_cooldown_secs: u32,
+    /// Once set, deposits are rejected for good so LP supply can only shrink.
+    /// Swaps and withdrawals keep working so the pool can wind down.
+    pub deposits_closed: bool,
+    /// Human-readable pool name such as "JUP/USDC 0.3%", UTF-8 and zero-padded. All zeroes means no label.
+    pub label: [u8; LABEL_LEN],
+    /// `LockReason` discriminant of the current lock; only meaningful while `locked` is set.
+    pub lock_reason: u8,
+    /// Unix timestamp the pool was locked at; 0 while unlocked.
+    pub locked_at: i64,
+    /// LP swap fees in token X sitting in vault_x until claimed through `claim_fees`.
+    pub lp_fees_owed_x: u64,
+    /// LP swap fees in token Y sitting in vault_y until claimed through `claim_fees`.
+    pub lp_fees_owed_y: u64,
+    /// Zeroed space kept at the end of the account so new fields can be added
+    /// without shifting the layout. New fields are carved out of this region.
+    pub _reserved: [u8; 29],
+}
+
+impl Config {
+    /// Returns the pool weights as percentages.
+    /// Configs extended from the pre-weights layout read as 0/0 and are treated as 50/50.
+    pub fn weights(&self) -> (u8, u8) {
+        if self.weight_x == 0 && self.weight_y == 0 {
+            (50, 50)
+        } else {
+            (self.weight_x, self.weight_y)
+        }
+    }
+
+    /// Quotes a swap against the given reserves using this pool's fee, fee mode, and weights.
+    /// Fails with `ReserveFloorBreached` if the swap would leave less than `MIN_RESERVE` on
+    /// the output side, after an output-side fee is set aside.
+    pub fn quote(&self, reserve_in: u64, reserve_out: u64, amount_in: u64, x_to_y: bool) -> Result<SwapAmounts> {
+        let amounts = math::quote_swap(reserve_in, reserve_out, amount_in, &self.swap_params(x_to_y))
+            .ok_or(AmmError::CurveMathFailed)?;
+
+        let output_fee = if self.fee_on_input { 0 } else { amounts.fee_amount };
+        let reserve_out_after = reserve_out.saturating_sub(amounts.amount_out).saturating_sub(output_fee);
+        require!(reserve_out_after >= MIN_RESERVE, AmmError::ReserveFloorBreached);
+        Ok(amounts)
+    }
+
+    /// Smallest output a swap may pay under a `max_slippage_bps` tolerance against the pre-trade
+    /// spot price, using the same curve parameters as `quote`. The reserves must be non-empty.
+    pub fn min_out_for_slippage(&self, reserve_in: u64, reserve_out: u64, amount_in: u64, x_to_y: bool, max_slippage_bps: u16) -> Result<u64> {
+        require!(max_slippage_bps <= 10_000, AmmError::InvalidSlippageTolerance);
+        math::min_out_for_slippage(reserve_in, reserve_out, amount_in, &self.swap_params(x_to_y), max_slippage_bps)
+            .ok_or(AmmError::CurveMathFailed.into())
+    }
+
+    /// Checks that `signer` is the pool's update authority.
+    pub fn check_authority(&self, signer: &Pubkey) -> Result<()> {
+        let authority = self.authority.ok_or(AmmError::NoAuthoritySet)?;
+        require_keys_eq!(authority, *signer, AmmError::InvalidAuthority);
+        Ok(())
+    }
+
+    /// Authorizes an admin change and emits its `AdminActionEvent`. Every authority-gated
+    /// instruction that mutates the config must call this before writing the new value.
+    pub fn admin_action(&self, config: Pubkey, authority: &Pubkey, action: AdminAction, old_value: u64, new_value: u64) -> Result<()> {
+        self.check_authority(authority)?;
+
+        emit!(AdminActionEvent {
+            config,
+            authority: *authority,
+            action: action as u8,
+            old_value,
+            new_value,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Packs a label into the fixed-size `label` field, zero-padded. Empty labels are allowed.
+    /// `String` arguments are already checked for valid UTF-8 when the instruction is decoded.
+    pub fn encode_label(label: &str) -> Result<[u8; LABEL_LEN]> {
+        let bytes = label.as_bytes();
+        require!(bytes.len() <= LABEL_LEN, AmmError::InvalidLabel);
+
+        let mut encoded = [0u8; LABEL_LEN];
+        encoded[..bytes.len()].copy_from_slice(bytes);
+        Ok(encoded)
+    }
+
+    /// Locks the pool and records why. Every code path that locks a pool, manual or automated,
+    /// must go through this so the state and the `PoolLockedEvent` stay in step.
+    pub fn lock(&mut self, config: Pubkey, by: Pubkey, reason: LockReason) -> Result<()> {
+        require!(!self.locked, AmmError::PoolLocked);
+        let timestamp = Clock::get()?.unix_timestamp;
+
+        self.locked = true;
+        self.lock_reason = reason as u8;
+        self.locked_at = timestamp;
+
+        emit!(PoolLockedEvent { config, by, reason: reason as u8, timestamp });
+        Ok(())
+    }
+
+    /// Unlocks the pool, reporting the reason it had been locked for, and clears the lock state.
+    pub fn unlock(&mut self, config: Pubkey, by: Pubkey) -> Result<()> {
+        require!(self.locked, AmmError::PoolNotLocked);
+        let reason = self.lock_reason;
+
+        self.locked = false;
+        self.lock_reason = 0;
+        self.locked_at = 0;
+
+        emit!(PoolUnlockedEvent { config, by, reason, timestamp: Clock::get()?.unix_timestamp });
+        Ok(())
+    }
+
+    /// Rejects a deposit of `x`/`y` that would take either vault above its cap.
+    pub fn check_deposit_cap(&self, vault_x: u64, vault_y: u64, x: u64, y: u64) -> Result<()> {
+        for (vault, amount, cap) in [(vault_x, x, self.deposit_cap_x), (vault_y, y, self.deposit_cap_y)] {
+            if cap == 0 {
+                continue;
+            }
+            let after = vault.checked_add(amount).ok_or(AmmError::Overflow)?;
+            require!(after <= cap, AmmError::DepositCapExceeded);
+        }
+        Ok(())
+    }
+
+    /// Vault balances minus the protocol fees owed to the treasury and the swap fees waiting to be
+    /// claimed by LPs, i.e. the liquidity backing the LP tokens.
+    /// All pricing and LP accounting must use these rather than the raw vault amounts.
+    pub fn net_reserves(&self, vault_x: u64, vault_y: u64) -> Result<(u64, u64)> {
+        let set_aside_x = self.protocol_fees_x.checked_add(self.lp_fees_owed_x).ok_or(AmmError::Overflow)?;
+        let set_aside_y = self.protocol_fees_y.checked_add(self.lp_fees_owed_y).ok_or(AmmError::Overflow)?;
+        Ok((
+            vault_x.checked_sub(set_aside_x).ok_or(AmmError::Underflow)?,
+            vault_y.checked_sub(set_aside_y).ok_or(AmmError::Underflow)?,
+        ))
+    }
+
+    /// Sets aside `protocol_fee_bps` of a swap fee for the treasury.
+    /// `fee_in_x` tells which token the fee was charged in. Returns the protocol share.
+    pub fn accrue_protocol_fee(&mut self, fee_amount: u64, fee_in_x: bool, protocol_fee_bps: u16) -> Result<u64> {
+        let share = (fee_amount as u128)
+            .checked_mul(protocol_fee_bps as u128)
+            .ok_or(AmmError::Overflow)?
+            / 10_000;
+        let share = share as u64;
+
+        let accumulator = if fee_in_x { &mut self.protocol_fees_x } else { &mut self.protocol_fees_y };
+        *accumulator = accumulator.checked_add(share).ok_or(AmmError::Overflow)?;
+        Ok(share)
+    }
+
+    /// Splits the pool's part of a swap fee between the treasury and the LPs and sets both aside.
+    /// Returns the (protocol, LP) shares.
+    pub fn accrue_swap_fees(&mut self, fee_amount: u64, fee_in_x: bool, protocol_fee_bps: u16, fee_state: Option<&mut FeeState>) -> Result<(u64, u64)> {
+        let protocol_fee = self.accrue_protocol_fee(fee_amount, fee_in_x, protocol_fee_bps)?;
+        let lp_fee = self.accrue_lp_fee(fee_amount - protocol_fee, fee_in_x, fee_state)?;
+        Ok((protocol_fee, lp_fee))
+    }
+
+    /// Sets aside the LPs' part of a swap fee for `claim_fees` and grows the pool's fee index
+    /// by its share per unit of registered fee weight. Without a fee state, or with no weight
+    /// registered, there is nobody to credit and the fee stays in the reserves.
+    /// Returns the amount set aside.
+    pub fn accrue_lp_fee(&mut self, lp_fee: u64, fee_in_x: bool, fee_state: Option<&mut FeeState>) -> Result<u64> {
+        let Some(fee_state) = fee_state else {
+            return Ok(0);
+        };
+        if lp_fee == 0 || fee_state.total_weight == 0 {
+            return Ok(0);
+        }
+        let delta = math::fee_growth_delta(lp_fee, fee_state.total_weight).ok_or(AmmError::CurveMathFailed)?;
+
+        let (growth, owed) = if fee_in_x {
+            (&mut fee_state.fee_growth_global_x, &mut self.lp_fees_owed_x)
+        } else {
+            (&mut fee_state.fee_growth_global_y, &mut self.lp_fees_owed_y)
+        };
+        *growth = growth.checked_add(delta).ok_or(AmmError::Overflow)?;
+        *owed = owed.checked_add(lp_fee).ok_or(AmmError::Overflow)?;
+        Ok(lp_fee)
+    }
+
+    /// Returns LP fees that were set aside but that no position can claim any more to the reserves.
+    pub fn release_lp_fees(&mut self, amount_x: u64, amount_y: u64) -> Result<()> {
+        self.lp_fees_owed_x = self.lp_fees_owed_x.checked_sub(amount_x).ok_or(AmmError::Underflow)?;
+        self.lp_fees_owed_y = self.lp_fees_owed_y.checked_sub(amount_y).ok_or(AmmError::Underflow)?;
+        Ok(())
+    }
+
+    /// Curve parameters oriented in the swap direction.
+    pub fn swap_params(&self, x_to_y: bool) -> SwapParams {
+        let (weight_x, weight_y) = self.weights();
+        let (weight_in, weight_out, decimals_in, decimals_out) = if x_to_y {
+            (weight_x, weight_y, self.decimals_x, self.decimals_y)
+        } else {
+            (weight_y, weight_x, self.decimals_y, self.decimals_x)
+        };
+        SwapParams {
+            fee_bps: self.fee,
+            fee_on_input: self.fee_on_input,
+            weight_in,
+            weight_out,
+            decimals_in,
+            decimals_out,
+        }
+    }
+
+    /// Sets fields carved from the reserved region to the values that reproduce the
+    /// behavior of pools created before those fields existed. Called once when a
+    /// legacy config is extended, since its new bytes all start zeroed.
+    pub fn apply_legacy_defaults(&mut self, decimals_x: u8, decimals_y: u8) {
+        self.weight_x = 50;
+        self.weight_y = 50;
+        self.fee_on_input = true;
+        self.decimals_x = decimals_x;
+        self.decimals_y = decimals_y;
+    }
+}
+
+/// Kinds of authority-gated config changes, as reported in `AdminActionEvent::action`.
+/// Discriminants are part of the event format; only append new variants.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AdminAction {
+    SetDepositCapX = 0,
+    SetDepositCapY = 1,
+    SetWithdrawCooldown = 2,
+    CloseDeposits = 3,
+    /// Label changes report 0/0 as old and new values; the label itself is in `LabelUpdatedEvent`.
+    SetLabel = 4,
+    LockPool = 5,
+    UnlockPool = 6,
+}
+
+/// Why a pool was locked, as stored in `Config::lock_reason` and reported in the lock events.
+/// Discriminants are part of the account and event format; only append new variants.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LockReason {
+    /// Locked by the pool authority.
+    Manual = 0,
+    /// Locked automatically by a safety check; never accepted from `lock_pool`.
+    CircuitBreaker = 1,
+    /// Locked by the pool authority ahead of a migration.
+    Migration = 2,
+}
+
+#[event]
+pub struct PoolLockedEvent {
+    pub config: Pubkey,
+    /// Signer or program path that locked the pool.
+    pub by: Pubkey,
+    /// A `LockReason` discriminant.
+    pub reason: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolUnlockedEvent {
+    pub config: Pubkey,
+    pub by: Pubkey,
+    /// The `LockReason` discriminant the pool had been locked with.
+    pub reason: u8,
+    pub timestamp: i64,
+}
+
+/// Single event stream for every admin change to a pool config.
+#[event]
+pub struct AdminActionEvent {
+    pub config: Pubkey,
+    pub authority: Pubkey,
+    /// An `AdminAction` discriminant.
+    pub action: u8,
+    pub old_value: u64,
+    pub new_value: u64,
+    pub timestamp: i64,
+}
+
+/// Singleton index of every pool config, so clients can discover pools without
+/// scanning all program accounts. Holds the first page of addresses itself;
+/// later pools spill into `RegistryPage` accounts.
+#[account]
+#[derive(InitSpace)]
+pub struct Registry {
+    /// Total number of pools registered across all pages.
+    pub pool_count: u64,
+    pub bump: u8,
+    /// Config addresses of the first `REGISTRY_PAGE_CAPACITY` pools.
+    #[max_len(REGISTRY_PAGE_CAPACITY)]
+    pub pools: Vec<Pubkey>,
+}
+
+impl Registry {
+    /// Page the next registered pool lands on. Page 0 is the registry account itself.
+    pub fn next_page(&self) -> u64 {
+        self.pool_count / REGISTRY_PAGE_CAPACITY as u64
+    }
+}
+
+/// Overflow page of the registry, at `[b"registry", page_index]` with `page_index >= 1`.
+#[account]
+#[derive(InitSpace)]
+pub struct RegistryPage {
+    pub page_index: u64,
+    pub bump: u8,
+    /// Config addresses registered on this page, in creation order.
+    #[max_len(REGISTRY_PAGE_CAPACITY)]
+    pub pools: Vec<Pubkey>,
+}
+
+/// Program-wide settings, at `[b"global_config"]`. Holds the protocol fee switch that
+/// applies to every pool; while this account does not exist no protocol fee is taken.
+#[account]
+#[derive(InitSpace)]
+pub struct GlobalConfig {
+    /// The only key allowed to change the protocol fee.
+    pub admin: Pubkey,
+    /// Share of each swap fee, in basis points of the fee, set aside for the treasury. 0 turns the switch off.
+    pub default_protocol_fee_bps: u16,
+    /// Wallet whose ATAs receive collected protocol fees.
+    pub treasury: Pubkey,
+    pub bump: u8,
+}
+
+impl GlobalConfig {
+    /// Reads the protocol fee from the global config PDA, treating a not-yet-created
+    /// account as a switched-off protocol fee.
+    pub fn protocol_fee_bps(info: &AccountInfo) -> Result<u16> {
+        if info.data_is_empty() {
+            return Ok(0);
+        }
+        require_keys_eq!(*info.owner, crate::ID, AmmError::InvalidGlobalConfig);
+        let global = GlobalConfig::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        Ok(global.default_protocol_fee_bps)
+    }
+}
+
+/// A pool's LP fee index, at `[b"fee_state", config]`. Created with the pool, or by
+/// `extend_config` for pools created before it; while it does not exist LP fees stay in the reserves.
+/// Only LP registered in a position earns: growth is shared over the registered fee weight,
+/// not over the LP supply.
+#[account]
+#[derive(InitSpace)]
+pub struct FeeState {
+    pub config: Pubkey,
+    /// Fees in token X earned per unit of fee weight since the fee state was created, in Q64.64.
+    pub fee_growth_global_x: u128,
+    /// Fees in token Y earned per unit of fee weight since the fee state was created, in Q64.64.
+    pub fee_growth_global_y: u128,
+    /// Sum of every position's fee weight (`Position::fee_weight`).
+    pub total_weight: u64,
+    pub bump: u8,
+}
+
+impl FeeState {
+    /// Reads the pool's fee state, treating a not-yet-created account as LP fee accounting
+    /// being off.
+    pub fn load(info: &AccountInfo) -> Result<Option<FeeState>> {
+        if info.data_is_empty() {
+            return Ok(None);
+        }
+        require_keys_eq!(*info.owner, crate::ID, AmmError::InvalidFeeState);
+        let fee_state = FeeState::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        Ok(Some(fee_state))
+    }
+
+    /// Writes the fee state back to the account `load` read it from.
+    pub fn store(&self, info: &AccountInfo) -> Result<()> {
+        let mut data = info.try_borrow_mut_data()?;
+        self.try_serialize(&mut &mut data[..])
+    }
+
+    /// Replaces a position's `old` fee weight with `new` in the total.
+    fn reweigh(&mut self, old: u64, new: u64) -> Result<()> {
+        self.total_weight = self.total_weight
+            .checked_sub(old)
+            .ok_or(AmmError::Underflow)?
+            .checked_add(new)
+            .ok_or(AmmError::Overflow)?;
+        Ok(())
+    }
+}
+
+/// Program-wide pool creation settings, at `[b"global"]`, owned by the AMM admin.
+#[account]
+#[derive(InitSpace)]
+pub struct GlobalState {
+    /// The only key allowed to change these settings.
+    pub admin: Pubkey,
+    /// Fee in bps used by `initialize` when the caller passes `u16::MAX`.
+    pub default_fee: u16,
+    /// While set, `initialize` rejects new pools.
+    pub pool_creation_paused: bool,
+    /// Lamports charged to the pool creator and sent to `treasury`; 0 means free.
+    pub pool_creation_fee_lamports: u64,
+    /// Wallet receiving pool creation fees.
+    pub treasury: Pubkey,
+    pub bump: u8,
+    /// While set, `initialize` only accepts pools whose two mints both hold a `MintBadge`.
+    pub permissioned_creation: bool,
+    /// Zeroed space for new settings, carved out like `Config::_reserved`.
+    pub _reserved: [u8; 31],
+}
+
+impl GlobalState {
+    /// Authorizes an admin change and emits its `GlobalStateUpdatedEvent`. Every admin
+    /// setter must call this before writing the new value.
+    pub fn admin_action(&self, admin: &Pubkey, action: GlobalAction, old_value: u64, new_value: u64) -> Result<()> {
+        require_keys_eq!(self.admin, *admin, AmmError::InvalidAuthority);
+
+        emit!(GlobalStateUpdatedEvent {
+            admin: *admin,
+            action: action as u8,
+            old_value,
+            new_value,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+/// Kinds of admin changes to the global state, as reported in `GlobalStateUpdatedEvent::action`.
+/// Discriminants are part of the event format; only append new variants.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum GlobalAction {
+    SetDefaultFee = 0,
+    SetPoolCreationPaused = 1,
+    SetPoolCreationFee = 2,
+    SetPermissionedCreation = 3,
+}
+
+/// Single event stream for every admin change to the global state.
+#[event]
+pub struct GlobalStateUpdatedEvent {
+    pub admin: Pubkey,
+    /// A `GlobalAction` discriminant.
+    pub action: u8,
+    pub old_value: u64,
+    pub new_value: u64,
+    pub timestamp: i64,
+}
+
+/// Marks a mint as approved for pool creation while `GlobalState::permissioned_creation` is on,
+/// at `[b"badge", mint]`. Granted by `approve_mint` and closed by `revoke_mint`.
+#[account]
+#[derive(InitSpace)]
+pub struct MintBadge {
+    pub mint: Pubkey,
+    /// Admin who approved the mint.
+    pub approved_by: Pubkey,
+    pub approved_at: i64,
+    pub bump: u8,
+}
+
+/// A user's liquidity position in one pool, at `[b"position", config, owner]`.
+/// Created on the user's first deposit and refreshed on every deposit after that.
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey,
+    pub config: Pubkey,
+    /// Unix timestamp of the user's most recent deposit.
+    pub last_deposit_ts: i64,
+    /// Unix timestamp from which the user may withdraw while the pool has a cooldown.
+    /// Clients compare this with the cluster clock to show the remaining time.
+    pub unlock_ts: i64,
+    pub bump: u8,
+    /// LP tokens registered to earn swap fees, as of the last settlement.
+    pub lp_balance: u64,
+    /// `FeeState::fee_growth_global_x` as of the last settlement.
+    pub fee_growth_checkpoint_x: u128,
+    /// `FeeState::fee_growth_global_y` as of the last settlement.
+    pub fee_growth_checkpoint_y: u128,
+    /// Settled token X fees not yet claimed.
+    pub fees_owed_x: u64,
+    /// Settled token Y fees not yet claimed.
+    pub fees_owed_y: u64,
+    /// Fee weight of the owner's locked LP (`LockedPosition::fee_weight`) as of the last settlement.
+    pub locked_weight: u64,
+}
+
+impl Position {
+    /// Stamps a deposit by `owner` so the withdraw cooldown restarts from now.
+    pub fn record_deposit(&mut self, owner: Pubkey, config: Pubkey, cooldown_secs: u32, bump: u8) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+
+        self.owner = owner;
+        self.config = config;
+        self.last_deposit_ts = now;
+        self.unlock_ts = now.checked_add(cooldown_secs as i64).ok_or(AmmError::Overflow)?;
+        self.bump = bump;
+        Ok(())
+    }
+
+    /// Fee weight the position is registered with in the pool's fee state.
+    pub fn fee_weight(&self) -> Result<u64> {
+        Ok(self.lp_balance.checked_add(self.locked_weight).ok_or(AmmError::Overflow)?)
+    }
+
+    /// Credits the fees earned since the last settlement to `fees_owed_x/y` and moves the
+    /// checkpoints up to the pool's current fee growth. Must run before the position's weight
+    /// changes. Registered LP no longer held in the owner's account (`held_lp`) earns nothing:
+    /// it is unregistered and its share is released back to the reserves.
+    pub fn settle_fees(&mut self, config: &mut Config, fee_state: &mut FeeState, held_lp: u64) -> Result<()> {
+        let registered = self.fee_weight()?;
+        let held_lp = self.lp_balance.min(held_lp);
+        let eligible = held_lp.checked_add(self.locked_weight).ok_or(AmmError::Overflow)?;
+        let (growth_x, growth_y) = (fee_state.fee_growth_global_x, fee_state.fee_growth_global_y);
+
+        let earned_x = math::fees_earned(growth_x, self.fee_growth_checkpoint_x, eligible)
+            .ok_or(AmmError::CurveMathFailed)?;
+        let earned_y = math::fees_earned(growth_y, self.fee_growth_checkpoint_y, eligible)
+            .ok_or(AmmError::CurveMathFailed)?;
+        let forfeited_x = math::fees_earned(growth_x, self.fee_growth_checkpoint_x, registered)
+            .ok_or(AmmError::CurveMathFailed)? - earned_x;
+        let forfeited_y = math::fees_earned(growth_y, self.fee_growth_checkpoint_y, registered)
+            .ok_or(AmmError::CurveMathFailed)? - earned_y;
+        config.release_lp_fees(forfeited_x, forfeited_y)?;
+
+        self.fees_owed_x = self.fees_owed_x.checked_add(earned_x).ok_or(AmmError::Overflow)?;
+        self.fees_owed_y = self.fees_owed_y.checked_add(earned_y).ok_or(AmmError::Overflow)?;
+        self.fee_growth_checkpoint_x = growth_x;
+        self.fee_growth_checkpoint_y = growth_y;
+        self.set_lp_balance(fee_state, held_lp)
+    }
+
+    /// Registers `lp_balance` LP in place of the current balance. Settle first so the new
+    /// balance only earns from now on.
+    pub fn set_lp_balance(&mut self, fee_state: &mut FeeState, lp_balance: u64) -> Result<()> {
+        fee_state.reweigh(self.lp_balance, lp_balance)?;
+        self.lp_balance = lp_balance;
+        Ok(())
+    }
+
+    /// Registers `locked_weight` for the owner's locked LP in place of the current weight.
+    /// Settle first so the new weight only earns from now on.
+    pub fn set_locked_weight(&mut self, fee_state: &mut FeeState, locked_weight: u64) -> Result<()> {
+        fee_state.reweigh(self.locked_weight, locked_weight)?;
+        self.locked_weight = locked_weight;
+        Ok(())
+    }
+
+    /// Settles fees, then registers `lp_amount` LP arriving in the owner's account (`held_lp`
+    /// before it arrives).
+    pub fn add_lp(&mut self, config: &mut Config, fee_state: &mut FeeState, held_lp: u64, lp_amount: u64) -> Result<()> {
+        self.settle_fees(config, fee_state, held_lp)?;
+        let lp_balance = self.lp_balance.checked_add(lp_amount).ok_or(AmmError::Overflow)?;
+        self.set_lp_balance(fee_state, lp_balance)
+    }
+
+    /// Settles fees, then unregisters `lp_amount` LP leaving the owner's account (`held_lp`
+    /// before it leaves).
+    pub fn remove_lp(&mut self, config: &mut Config, fee_state: &mut FeeState, held_lp: u64, lp_amount: u64) -> Result<()> {
+        self.settle_fees(config, fee_state, held_lp)?;
+        let lp_balance = self.lp_balance.min(held_lp.saturating_sub(lp_amount));
+        self.set_lp_balance(fee_state, lp_balance)
+    }
+
+    /// Applies `remove_lp` to the position stored in `info`, against the pool's fee state in
+    /// `fee_state_info`. LPs who never deposited through the program have no position, and
+    /// pools without a fee state have nothing registered, so neither has anything to update.
+    pub fn remove_lp_from_account(
+        info: &AccountInfo,
+        fee_state_info: &AccountInfo,
+        config: &mut Config,
+        held_lp: u64,
+        lp_amount: u64,
+    ) -> Result<()> {
+        if info.data_is_empty() {
+            return Ok(());
+        }
+        let Some(mut fee_state) = FeeState::load(fee_state_info)? else {
+            return Ok(());
+        };
+
+        let mut data = info.try_borrow_mut_data()?;
+        let mut position = Position::try_deserialize(&mut &data[..])?;
+        position.remove_lp(config, &mut fee_state, held_lp, lp_amount)?;
+        position.try_serialize(&mut &mut data[..])?;
+        fee_state.store(fee_state_info)
+    }
+}
+
+/// LP tokens a user has locked in one pool, at `[b"locked_position", config, owner]`.
+/// The tokens themselves sit in the `[b"locked_lp", config, owner]` escrow owned by the config.
+#[account]
+#[derive(InitSpace)]
+pub struct LockedPosition {
+    pub owner: Pubkey,
+    pub config: Pubkey,
+    /// LP tokens currently held in escrow for the owner.
+    pub lp_amount: u64,
+    /// Unix timestamp from which the escrowed LP can be released.
+    pub unlock_ts: i64,
+    /// Fee-share weight of the locked LP in bps (10_000 = 1x).
+    pub boost_bps: u16,
+    pub bump: u8,
+}
+
+impl LockedPosition {
+    /// Fee weight of the locked LP, registered on the owner's position as `locked_weight`.
+    pub fn fee_weight(&self) -> Result<u64> {
+        Ok(math::locked_fee_weight(self.lp_amount, self.boost_bps).ok_or(AmmError::Overflow)?)
+    }
+}
+
+/// Pool state recorded by one `checkpoint` call.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
+pub struct Snapshot {
+    /// Reserves owned by LPs, excluding protocol fees and unclaimed LP fees.
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+    pub lp_supply: u64,
+    pub timestamp: i64,
+    pub slot: u64,
+}
+
+/// Ring buffer of a pool's reserve snapshots, at `[b"snapshots", config]`.
+#[account]
+#[derive(InitSpace)]
+pub struct Snapshots {
+    pub config: Pubkey,
+    /// Slot in `entries` the next snapshot is written to once the buffer is full.
+    pub head: u32,
+    pub bump: u8,
+    #[max_len(SNAPSHOT_CAPACITY)]
+    pub entries: Vec<Snapshot>,
+}
+
+impl Snapshots {
+    /// Appends a snapshot, overwriting the oldest one when the buffer is full.
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.entries.len() < SNAPSHOT_CAPACITY {
+            self.entries.push(snapshot);
+        } else {
+            self.entries[self.head as usize] = snapshot;
+            self.head = ((self.head as usize + 1) % SNAPSHOT_CAPACITY) as u32;
+        }
+    }
+
+    /// The most recent snapshot, if any.
+    pub fn latest(&self) -> Option<&Snapshot> {
+        self.get(0)
+    }
+
+    /// Snapshot `index` steps back from the most recent one (0 = latest).
+    pub fn get(&self, index: u32) -> Option<&Snapshot> {
+        let len = self.entries.len();
+        let index = index as usize;
+        if index >= len {
+            return None;
+        }
+        // While filling, entries are in order; once full, `head` points at the oldest entry
+        let newest = if len < SNAPSHOT_CAPACITY { len - 1 } else { (self.head as usize + len - 1) % len };
+        self.entries.get((newest + len - index) % len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::Discriminator;
+
+    use super::*;
+
+    fn config(seed: u64, config_bump: u8) -> Config {
+        Config {
+            seed,
+            authority: None,
+            mint_x: Pubkey::default(),
+            mint_y: Pubkey::default(),
+            fee: 0,
+            locked: false,
+            config_bump,
+            lp_bump: 0,
+            weight_x: 50,
+            weight_y: 50,
+            fee_on_input: true,
+            decimals_x: 6,
+            decimals_y: 6,
+            protocol_fees_x: 0,
+            protocol_fees_y: 0,
+            deposit_cap_x: 0,
+            deposit_cap_y: 0,
+            withdraw_cooldown_secs: 0,
+            deposits_closed: false,
+            label: [0; LABEL_LEN],
+            lock_reason: 0,
+            locked_at: 0,
+            lp_fees_owed_x: 0,
+            lp_fees_owed_y: 0,
+            _reserved: [0; 29],
+        }
+    }
+
+    fn fee_state() -> FeeState {
+        FeeState {
+            config: Pubkey::default(),
+            fee_growth_global_x: 0,
+            fee_growth_global_y: 0,
+            total_weight: 0,
+            bump: 0,
+        }
+    }
+
+    fn position() -> Position {
+        Position {
+            owner: Pubkey::default(),
+            config: Pubkey::default(),
+            last_deposit_ts: 0,
+            unlock_ts: 0,
+            bump: 0,
+            lp_balance: 0,
+            fee_growth_checkpoint_x: 0,
+            fee_growth_checkpoint_y: 0,
+            fees_owed_x: 0,
+            fees_owed_y: 0,
+            locked_weight: 0,
+        }
+    }
+
+    #[test]
+    fn config_signer_seeds_derive_the_config_pda() {
+        for seed in [0u64, 1, 123_456_789, u64::MAX] {
+            let (expected, bump) = Pubkey::find_program_address(&[b"config", &seed.to_le_bytes()], &crate::ID);
+
+            let config = config(seed, bump);
+            let seeds = crate::config_signer_seeds!(config);
+
+            assert_eq!(Pubkey::create_program_address(seeds, &crate::ID).unwrap(), expected);
+        }
+    }
+
+    /// Configs written before the LP fee fields were carved out of the reserved region must
+    /// still load as-is, with no fees owed.
+    #[test]
+    fn config_written_before_lp_fees_loads_unchanged() {
+        let authority = Pubkey::new_unique();
+        let mint_x = Pubkey::new_unique();
+        let mint_y = Pubkey::new_unique();
+
+        // Discriminator and fields as laid out before lp_fees_owed_x/y, ending in 45 reserved bytes
+        let mut data = Config::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&7u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(mint_x.as_ref());
+        data.extend_from_slice(mint_y.as_ref());
+        data.extend_from_slice(&30u16.to_le_bytes());
+        data.extend_from_slice(&[0, 254, 253, 80, 20, 1, 6, 9]);
+        data.extend_from_slice(&11u64.to_le_bytes());
+        data.extend_from_slice(&12u64.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&60u32.to_le_bytes());
+        data.push(0);
+        data.extend_from_slice(&Config::encode_label("JUP/USDC 0.3%").unwrap());
+        data.push(0);
+        data.extend_from_slice(&0i64.to_le_bytes());
+        data.extend_from_slice(&[0; 45]);
+        assert_eq!(data.len(), 246);
+        assert_eq!(data.len(), 8 + Config::INIT_SPACE);
+
+        let loaded = Config::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(loaded.seed, 7);
+        assert_eq!(loaded.authority, Some(authority));
+        assert_eq!((loaded.mint_x, loaded.mint_y), (mint_x, mint_y));
+        assert_eq!(loaded.fee, 30);
+        assert_eq!((loaded.config_bump, loaded.lp_bump), (254, 253));
+        assert_eq!(loaded.weights(), (80, 20));
+        assert_eq!((loaded.decimals_x, loaded.decimals_y), (6, 9));
+        assert_eq!((loaded.protocol_fees_x, loaded.protocol_fees_y), (11, 12));
+        assert_eq!(loaded.withdraw_cooldown_secs, 60);
+        assert_eq!((loaded.lp_fees_owed_x, loaded.lp_fees_owed_y), (0, 0));
+        assert_eq!(loaded.net_reserves(100, 100).unwrap(), (89, 88));
+
+        let mut written = Vec::new();
+        loaded.try_serialize(&mut written).unwrap();
+        assert_eq!(written, data);
+    }
+
+    #[test]
+    fn lp_fees_stay_in_reserves_without_registered_weight() {
+        let mut config = config(0, 0);
+        assert_eq!(config.accrue_swap_fees(1_000, true, 0, None).unwrap(), (0, 0));
+
+        let mut fee_state = fee_state();
+        assert_eq!(config.accrue_swap_fees(1_000, true, 0, Some(&mut fee_state)).unwrap(), (0, 0));
+        assert_eq!(fee_state.fee_growth_global_x, 0);
+        assert_eq!(config.lp_fees_owed_x, 0);
+    }
+
+    #[test]
+    fn fee_growth_is_shared_over_registered_and_boosted_locked_weight() {
+        let mut config = config(0, 0);
+        let mut fee_state = fee_state();
+
+        // 1_000 LP registered unlocked, 1_000 LP locked at a 1.5x boost
+        let mut unlocked = position();
+        unlocked.add_lp(&mut config, &mut fee_state, 0, 1_000).unwrap();
+        let locked_position = LockedPosition {
+            owner: Pubkey::default(),
+            config: Pubkey::default(),
+            lp_amount: 1_000,
+            unlock_ts: 0,
+            boost_bps: 15_000,
+            bump: 0,
+        };
+        let mut locked = position();
+        locked.settle_fees(&mut config, &mut fee_state, 0).unwrap();
+        locked.set_locked_weight(&mut fee_state, locked_position.fee_weight().unwrap()).unwrap();
+        assert_eq!(fee_state.total_weight, 2_500);
+
+        // Unregistered LP elsewhere does not dilute the fee: 2_500 over 2_500 weight
+        assert_eq!(config.accrue_swap_fees(2_500, true, 0, Some(&mut fee_state)).unwrap(), (0, 2_500));
+        assert_eq!(config.lp_fees_owed_x, 2_500);
+
+        unlocked.settle_fees(&mut config, &mut fee_state, 1_000).unwrap();
+        locked.settle_fees(&mut config, &mut fee_state, 0).unwrap();
+        assert_eq!(unlocked.fees_owed_x, 1_000);
+        assert_eq!(locked.fees_owed_x, 1_500);
+        assert_eq!(config.lp_fees_owed_x, 2_500);
+        assert_eq!(fee_state.total_weight, 2_500);
+
+        // Releasing the lock moves the LP into the registered balance at 1x
+        locked.add_lp(&mut config, &mut fee_state, 0, 1_000).unwrap();
+        locked.set_locked_weight(&mut fee_state, 0).unwrap();
+        assert_eq!(locked.lp_balance, 1_000);
+        assert_eq!(fee_state.total_weight, 2_000);
+    }
+
+    #[test]
+    fn settling_releases_the_share_of_lp_no_longer_held() {
+        let mut config = config(0, 0);
+        let mut fee_state = fee_state();
+        let mut position = position();
+        position.add_lp(&mut config, &mut fee_state, 0, 1_000).unwrap();
+        config.accrue_swap_fees(1_000, false, 0, Some(&mut fee_state)).unwrap();
+
+        // 600 of the registered LP was moved away: its 600 in fees go back to the reserves
+        position.settle_fees(&mut config, &mut fee_state, 400).unwrap();
+        assert_eq!(position.fees_owed_y, 400);
+        assert_eq!(position.lp_balance, 400);
+        assert_eq!(config.lp_fees_owed_y, 400);
+        assert_eq!(fee_state.total_weight, 400);
+
+        position.remove_lp(&mut config, &mut fee_state, 400, 400).unwrap();
+        assert_eq!(position.lp_balance, 0);
+        assert_eq!(fee_state.total_weight, 0);
+        assert_eq!(position.fees_owed_y, 400);
+    }
+
+    #[test]
+    fn encode_label_zero_pads_and_caps_length() {
+        assert_eq!(Config::encode_label("").unwrap(), [0; LABEL_LEN]);
+
+        let label = Config::encode_label("JUP/USDC 0.3%").unwrap();
+        assert_eq!(&label[..13], b"JUP/USDC 0.3%");
+        assert!(label[13..].iter().all(|&b| b == 0));
+
+        assert!(Config::encode_label(&"x".repeat(LABEL_LEN)).is_ok());
+        assert!(Config::encode_label(&"x".repeat(LABEL_LEN + 1)).is_err());
+        // Multi-byte characters count by bytes, not chars
+        assert!(Config::encode_label(&"é".repeat(17)).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Amm } from "../target/types/amm";
+import { AmmCaller } from "../target/types/amm_caller";
 import { PublicKey, SystemProgram, Keypair } from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
@@ -9,8 +10,11 @@ import {
   getAssociatedTokenAddress,
   getOrCreateAssociatedTokenAccount,
   mintTo,
+  transfer,
 } from "@solana/spl-token";
 import { assert } from "chai";
+import * as fs from "fs";
+import * as path from "path";
 
 type AmmContext = {
   initializer: Keypair;
@@ -32,12 +36,17 @@ describe("amm", () => {
   anchor.setProvider(anchor.AnchorProvider.env());
   const provider = anchor.getProvider();
   const program = anchor.workspace.amm as Program<Amm>;
+  const REGISTRY_PAGE_CAPACITY = 16;
+  // Global config keys, set up by the protocol fee tests and reused by the batch collect tests
+  const protocolTreasury = Keypair.generate();
+  let protocolAdmin: Keypair;
+  // Admin of the global state, set up by the global state tests and reused by the mint allowlist tests
+  let globalAdmin: Keypair;
 
   
-  const setupPool = async (): Promise<AmmContext> => {
+  const setupPool = async (seed = new anchor.BN(123456789), decimalsX = 6, decimalsY = 6): Promise<AmmContext> => {
     const initializer = Keypair.generate();
     const user = Keypair.generate();
-    const seed = new anchor.BN(123456789);
     const fee = 500;
 
   const initializerAirdrop = await provider.connection.requestAirdrop(
@@ -68,8 +77,8 @@ await provider.connection.confirmTransaction({
 // Add a small delay to ensure accounts are funded
 await new Promise((resolve) => setTimeout(resolve, 1000));
 
-const mintX = await createMint(provider.connection, initializer, initializer.publicKey, null, 6);
-const mintY = await createMint(provider.connection, initializer, initializer.publicKey, null, 6);
+const mintX = await createMint(provider.connection, initializer, initializer.publicKey, null, decimalsX);
+const mintY = await createMint(provider.connection, initializer, initializer.publicKey, null, decimalsY);
 
 const [config, configBump] = await PublicKey.findProgramAddressSync(
   [Buffer.from("config"), seed.toArrayLike(Buffer, "le", 8)],
@@ -93,7 +102,7 @@ const userAtaY = (await getOrCreateAssociatedTokenAccount(provider.connection, u
     mintX,
     userAtaX,
     initializer,
-    1_000_000
+    1_000_000 * 10 ** (decimalsX - 6)
   );
 
   // Mint tokens to user's Y  accounts
@@ -103,7 +112,7 @@ const userAtaY = (await getOrCreateAssociatedTokenAccount(provider.connection, u
     mintY,
     userAtaY,
     initializer,
-    1_000_000
+    1_000_000 * 10 ** (decimalsY - 6)
   );
 
 
@@ -123,6 +132,169 @@ return {
 };
 };
 
+const registryAccounts = async () => {
+  const [registry] = PublicKey.findProgramAddressSync([Buffer.from("registry")], program.programId);
+  const info = await program.account.registry.fetchNullable(registry);
+  const poolCount = info ? info.poolCount.toNumber() : 0;
+  const page = Math.floor(poolCount / REGISTRY_PAGE_CAPACITY);
+
+  // Page 0 is the registry itself; later pools need their overflow page passed in
+  const registryPage = page === 0
+    ? null
+    : PublicKey.findProgramAddressSync(
+        [Buffer.from("registry"), new anchor.BN(page).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+
+  return { registry, registryPage };
+};
+
+const initializePool = async (
+  ctx: AmmContext,
+  weightX = 50,
+  weightY = 50,
+  feeOnInput = true,
+  authority: PublicKey | null = null,
+  label = "",
+  creationAccounts: {
+    globalState: PublicKey | null;
+    creationFeeTreasury: PublicKey | null;
+    badgeX?: PublicKey | null;
+    badgeY?: PublicKey | null;
+  } = {
+    globalState: null,
+    creationFeeTreasury: null,
+  }
+) => {
+  const signature = await program.methods
+    .initialize(ctx.seed, ctx.fee, authority, weightX, weightY, feeOnInput, label)
+    .accounts({
+      initializer: ctx.initializer.publicKey,
+      mintX: ctx.mintX,
+      mintY: ctx.mintY,
+      //@ts-ignore
+      mintLp: ctx.mintLp,
+      config: ctx.config,
+      vaultX: ctx.vaultX,
+      vaultY: ctx.vaultY,
+      ...(await registryAccounts()),
+      badgeX: null,
+      badgeY: null,
+      ...creationAccounts,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([ctx.initializer])
+    .rpc();
+
+  ctx.userAtaLp = await getAssociatedTokenAddress(ctx.mintLp, ctx.user.publicKey);
+  return signature;
+};
+
+const depositTo = async (ctx: AmmContext, amount: number, maxX: number, maxY: number) => {
+  await program.methods
+    .deposit(new anchor.BN(amount), new anchor.BN(maxX), new anchor.BN(maxY), new anchor.BN(0))
+    .accounts({
+      user: ctx.user.publicKey,
+      //@ts-ignore
+      mintX: ctx.mintX,
+      mintY: ctx.mintY,
+      config: ctx.config,
+      vaultX: ctx.vaultX,
+      vaultY: ctx.vaultY,
+      mintLp: ctx.mintLp,
+      userX: ctx.userAtaX,
+      userY: ctx.userAtaY,
+      userLp: ctx.userAtaLp,
+      lockedPosition: null,
+      lockedLp: null,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([ctx.user])
+    .rpc();
+};
+
+const withdrawFrom = async (ctx: AmmContext, lpAmount: number, minX = 0, minY = 0, closeEmptyLp = false) => {
+  await program.methods
+    .withdraw(new anchor.BN(lpAmount), new anchor.BN(minX), new anchor.BN(minY), closeEmptyLp)
+    .accounts({
+      user: ctx.user.publicKey,
+      recipient: ctx.user.publicKey,
+      //@ts-ignore
+      mintX: ctx.mintX,
+      mintY: ctx.mintY,
+      config: ctx.config,
+      vaultX: ctx.vaultX,
+      vaultY: ctx.vaultY,
+      mintLp: ctx.mintLp,
+      userX: ctx.userAtaX,
+      userY: ctx.userAtaY,
+      userLp: ctx.userAtaLp,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([ctx.user])
+    .rpc();
+};
+
+const swapIn = async (
+  ctx: AmmContext,
+  amountIn: number,
+  minOut: number,
+  xToY: boolean,
+  remainingAccounts: anchor.web3.AccountMeta[] = [],
+  priceGuard: { expectedPriceNum: anchor.BN; expectedPriceDen: anchor.BN; maxDeviationBps: number } | null = null
+) => {
+  await program.methods
+    .swap(new anchor.BN(amountIn), new anchor.BN(minOut), xToY, priceGuard)
+    .accounts({
+      user: ctx.user.publicKey,
+      //@ts-ignore
+      mintX: ctx.mintX,
+      mintY: ctx.mintY,
+      config: ctx.config,
+      vaultX: ctx.vaultX,
+      vaultY: ctx.vaultY,
+      userX: ctx.userAtaX,
+      userY: ctx.userAtaY,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: null,
+      systemProgram: null,
+    })
+    .remainingAccounts(remainingAccounts)
+    .signers([ctx.user])
+    .rpc();
+};
+
+const quote = async (ctx: AmmContext, amountIn: number, xToY: boolean) =>
+  program.methods
+    .quote(new anchor.BN(amountIn), xToY)
+    .accounts({
+      mintX: ctx.mintX,
+      mintY: ctx.mintY,
+      //@ts-ignore
+      config: ctx.config,
+      vaultX: ctx.vaultX,
+      vaultY: ctx.vaultY,
+    })
+    .view();
+
+const balance = async (ata: PublicKey) =>
+  BigInt((await provider.connection.getTokenAccountBalance(ata)).value.amount);
+
+const expectError = async (promise: Promise<unknown>, code: string) => {
+  try {
+    await promise;
+    assert.fail(`Expected ${code}`);
+  } catch (err: any) {
+    assert.include(err.toString(), code);
+  }
+};
+
 describe("initialize", () => {
     let context: AmmContext;
 
@@ -131,7 +303,7 @@ describe("initialize", () => {
       
       // Initialize the AMM pool first
       await program.methods
-        .initialize(baseContext.seed, baseContext.fee, null)
+        .initialize(baseContext.seed, baseContext.fee, null, 50, 50, true, "")
         .accounts({
           initializer: baseContext.initializer.publicKey,
           mintX: baseContext.mintX,
@@ -141,6 +313,11 @@ describe("initialize", () => {
           config: baseContext.config,
           vaultX: baseContext.vaultX,
           vaultY: baseContext.vaultY,
+          ...(await registryAccounts()),
+          globalState: null,
+          creationFeeTreasury: null,
+          badgeX: null,
+          badgeY: null,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -173,7 +350,7 @@ describe("initialize", () => {
       const { user, mintX, mintY, config, vaultX, vaultY, mintLp, userAtaX, userAtaY, userAtaLp } = context;
   
       await program.methods
-        .deposit(new anchor.BN(100_000), new anchor.BN(100_000), new anchor.BN(200_000))
+        .deposit(new anchor.BN(100_000), new anchor.BN(100_000), new anchor.BN(200_000), new anchor.BN(0))
         .accounts({
           user: user.publicKey,
           //@ts-ignore
@@ -186,6 +363,8 @@ describe("initialize", () => {
           userX: userAtaX,
           userY: userAtaY,
           userLp: userAtaLp,
+          lockedPosition: null,
+          lockedLp: null,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -193,6 +372,59 @@ describe("initialize", () => {
         .signers([user])
         .rpc();
     });
+
+    it("Extends config idempotently and keeps the pool usable", async () => {
+      const { user, seed, config, mintX, mintY } = context;
+
+      const before = await provider.connection.getAccountInfo(config);
+
+      // Calling twice must be a no-op on a pool that already has the current layout
+      for (let i = 0; i < 2; i++) {
+        await program.methods
+          .extendConfig(seed)
+          .accounts({
+            payer: user.publicKey,
+            //@ts-ignore
+            config,
+            mintX,
+            mintY,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user])
+          .rpc();
+      }
+
+      const after = await provider.connection.getAccountInfo(config);
+      assert.equal(after.data.length, before.data.length, "Config size should be unchanged");
+      assert.equal(after.lamports, before.lamports, "No rent should be charged");
+
+      const state = await program.account.config.fetch(config);
+      assert.ok(state.seed.eq(seed), "Config should still deserialize");
+    });
+
+    it("Returns the normalized spot price", async () => {
+      const { mintX, mintY, config, vaultX, vaultY } = context;
+
+      const price = await program.methods
+        .getSpotPrice()
+        .accounts({
+          mintX,
+          mintY,
+          //@ts-ignore
+          config,
+          vaultX,
+          vaultY,
+        })
+        .view();
+
+      // 100_000 X against 200_000 Y, both with 6 decimals
+      const one = new anchor.BN(1).shln(64);
+      assert.ok(price.priceXInY.eq(one.muln(2)), "1 X should be worth 2 Y");
+      assert.ok(price.priceYInX.eq(one.divn(2)), "1 Y should be worth 0.5 X");
+      assert.equal(price.decimalsX, 6);
+      assert.equal(price.decimalsY, 6);
+    });
+
     it("Swaps X for Y", async () => {
        const { user, mintX, mintY, config, vaultX, vaultY, userAtaX, userAtaY, initializer } = context;
   
@@ -201,7 +433,7 @@ describe("initialize", () => {
        const yBefore = BigInt((await provider.connection.getTokenAccountBalance(userAtaY)).value.amount);
   
        await program.methods
-         .swap(new anchor.BN(50_000), new anchor.BN(1), true)
+         .swap(new anchor.BN(50_000), new anchor.BN(1), true, null)
          .accounts({
            user: user.publicKey,
            //@ts-ignore
@@ -213,8 +445,8 @@ describe("initialize", () => {
            userX: userAtaX,
            userY: userAtaY,
            tokenProgram: TOKEN_PROGRAM_ID,
-           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-           systemProgram: SystemProgram.programId,
+           associatedTokenProgram: null,
+           systemProgram: null,
          })
          .signers([user])
          .rpc();
@@ -231,7 +463,7 @@ describe("initialize", () => {
        const xBefore = BigInt((await provider.connection.getTokenAccountBalance(userAtaX)).value.amount);
   
        await program.methods
-         .swap(new anchor.BN(50_000), new anchor.BN(1), false)
+         .swap(new anchor.BN(50_000), new anchor.BN(1), false, null)
          .accounts({
            user: user.publicKey,
            //@ts-ignore
@@ -243,8 +475,8 @@ describe("initialize", () => {
            userX: userAtaX,
            userY: userAtaY,
            tokenProgram: TOKEN_PROGRAM_ID,
-           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-           systemProgram: SystemProgram.programId,
+           associatedTokenProgram: null,
+           systemProgram: null,
          })
          .signers([user])
          .rpc();
@@ -261,9 +493,10 @@ describe("initialize", () => {
       const yBefore = BigInt((await provider.connection.getTokenAccountBalance(userAtaY)).value.amount);
 
       await program.methods
-        .withdraw(new anchor.BN(lpBalance), new anchor.BN(0), new anchor.BN(0))
+        .withdraw(new anchor.BN(lpBalance), new anchor.BN(0), new anchor.BN(0), false)
         .accounts({
           user: user.publicKey,
+          recipient: user.publicKey,
           //@ts-ignore
           mintX,
           mintY,
@@ -287,6 +520,1836 @@ describe("initialize", () => {
       assert.ok(yAfter > yBefore, "Y should increase after withdraw");
     });
   });
+
+  describe("weighted pool", () => {
+    let context: AmmContext;
+
+    before(async () => {
+      context = await setupPool(new anchor.BN(8020));
+      const { initializer, seed, fee, mintX, mintY, mintLp, config, vaultX, vaultY } = context;
+
+      await program.methods
+        .initialize(seed, fee, null, 80, 20, true, "")
+        .accounts({
+          initializer: initializer.publicKey,
+          mintX,
+          mintY,
+          //@ts-ignore
+          mintLp,
+          config,
+          vaultX,
+          vaultY,
+          ...(await registryAccounts()),
+          globalState: null,
+          creationFeeTreasury: null,
+          badgeX: null,
+          badgeY: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([initializer])
+        .rpc();
+
+      context.userAtaLp = await getAssociatedTokenAddress(mintLp, context.user.publicKey);
+    });
+
+    it("Rejects weights that do not sum to 100", async () => {
+      const other = await setupPool(new anchor.BN(8021));
+      try {
+        await program.methods
+          .initialize(other.seed, other.fee, null, 80, 30, true, "")
+          .accounts({
+            initializer: other.initializer.publicKey,
+            mintX: other.mintX,
+            mintY: other.mintY,
+            //@ts-ignore
+            mintLp: other.mintLp,
+            config: other.config,
+            vaultX: other.vaultX,
+            vaultY: other.vaultY,
+            ...(await registryAccounts()),
+            globalState: null,
+            creationFeeTreasury: null,
+            badgeX: null,
+            badgeY: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([other.initializer])
+          .rpc();
+        assert.fail("Initialize should fail");
+      } catch (err: any) {
+        assert.include(err.toString(), "InvalidWeights");
+      }
+    });
+
+    it("Swaps along the 80/20 curve", async () => {
+      const { user, mintX, mintY, config, vaultX, vaultY, mintLp, userAtaX, userAtaY, userAtaLp } = context;
+
+      // 100_000 X at 80% against 25_000 Y at 20%: 1 X is worth 1 Y
+      await program.methods
+        .deposit(new anchor.BN(100_000), new anchor.BN(100_000), new anchor.BN(25_000), new anchor.BN(0))
+        .accounts({
+          user: user.publicKey,
+          //@ts-ignore
+          mintX,
+          mintY,
+          config,
+          vaultX,
+          vaultY,
+          mintLp,
+          userX: userAtaX,
+          userY: userAtaY,
+          userLp: userAtaLp,
+          lockedPosition: null,
+          lockedLp: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+
+      const yBefore = BigInt((await provider.connection.getTokenAccountBalance(userAtaY)).value.amount);
+
+      await program.methods
+        .swap(new anchor.BN(1_000), new anchor.BN(1), true, null)
+        .accounts({
+          user: user.publicKey,
+          //@ts-ignore
+          mintX,
+          mintY,
+          config,
+          vaultX,
+          vaultY,
+          userX: userAtaX,
+          userY: userAtaY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: null,
+          systemProgram: null,
+        })
+        .signers([user])
+        .rpc();
+
+      const yAfter = BigInt((await provider.connection.getTokenAccountBalance(userAtaY)).value.amount);
+      // f64 reference: 25_000 * (1 - (100_000 / 100_950) ** 4) = 927.86 (5% fee applied to input)
+      const out = Number(yAfter - yBefore);
+      assert.isAtMost(out, 927);
+      assert.isAtLeast(out, 926);
+    });
+  });
+
+  describe("deposit minimums", () => {
+    let context: AmmContext;
+
+    before(async () => {
+      context = await setupPool(new anchor.BN(316));
+      await initializePool(context);
+    });
+
+    it("Rejects a first deposit one unit below the token minimum", async () => {
+      await expectError(depositTo(context, 1_000, 999, 1_000), "DepositTooSmall");
+      await expectError(depositTo(context, 1_000, 1_000, 999), "DepositTooSmall");
+    });
+
+    it("Rejects minting one unit below the LP minimum", async () => {
+      await expectError(depositTo(context, 999, 1_000, 1_000), "DepositTooSmall");
+    });
+
+    it("Accepts a deposit exactly at both minimums", async () => {
+      await depositTo(context, 1_000, 1_000, 1_000);
+      const lp = await provider.connection.getTokenAccountBalance(context.userAtaLp);
+      assert.equal(lp.value.amount, "1000");
+    });
+  });
+
+  describe("fee modes", () => {
+    const runMode = async (seed: number, feeOnInput: boolean) => {
+      const ctx = await setupPool(new anchor.BN(seed));
+      await initializePool(ctx, 50, 50, feeOnInput);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      const q = await quote(ctx, 10_000, true);
+      const [userYBefore, vaultXBefore, vaultYBefore] = await Promise.all([
+        balance(ctx.userAtaY), balance(ctx.vaultX), balance(ctx.vaultY),
+      ]);
+
+      await swapIn(ctx, 10_000, q.amountOut.toNumber(), true);
+
+      const [userYAfter, vaultXAfter, vaultYAfter] = await Promise.all([
+        balance(ctx.userAtaY), balance(ctx.vaultX), balance(ctx.vaultY),
+      ]);
+
+      // The swap pays exactly what the quote promised
+      assert.equal(userYAfter - userYBefore, BigInt(q.amountOut.toString()));
+      // k never decreases
+      assert.ok(vaultXAfter * vaultYAfter >= vaultXBefore * vaultYBefore, "Pool lost value");
+      return q;
+    };
+
+    it("Charges the fee in the input token when fee_on_input is set", async () => {
+      const q = await runMode(3171, true);
+      // 5% of the 10_000 X input
+      assert.equal(q.feeAmount.toNumber(), 500);
+    });
+
+    it("Charges the fee in the output token when fee_on_input is cleared", async () => {
+      const q = await runMode(3172, false);
+      // Raw output 10_000 * 100_000 / 110_000 = 9_090; fee ceil(9_090 * 5%) = 455 Y
+      assert.equal(q.feeAmount.toNumber(), 455);
+      assert.equal(q.amountOut.toNumber(), 9_090 - 455);
+    });
+  });
+
+  describe("mismatched decimals", () => {
+    it("Deposits proportionally into a 9/6 decimal pool", async () => {
+      const ctx = await setupPool(new anchor.BN(318), 9, 6);
+      await initializePool(ctx);
+      // 0.1 X and 0.2 Y backing 1 LP
+      await depositTo(ctx, 1_000_000, 100_000_000, 200_000);
+
+      const [xBefore, yBefore] = await Promise.all([balance(ctx.userAtaX), balance(ctx.userAtaY)]);
+      // 1% of supply must cost 1% of each reserve in native units
+      await depositTo(ctx, 10_000, 1_000_000, 2_000);
+      const [xAfter, yAfter] = await Promise.all([balance(ctx.userAtaX), balance(ctx.userAtaY)]);
+
+      assert.equal(xBefore - xAfter, BigInt(1_000_000));
+      assert.equal(yBefore - yAfter, BigInt(2_000));
+    });
+  });
+
+  describe("pool registry", () => {
+    it("Registers pools and rolls over to an overflow page", async () => {
+      const ctx = await setupPool(new anchor.BN(3190));
+      const poolCount = async () => (await program.methods.getPoolCount().view()).toNumber();
+
+      // Fill whatever is left of the current page, then register one more pool
+      const start = await poolCount();
+      const toCreate = REGISTRY_PAGE_CAPACITY - (start % REGISTRY_PAGE_CAPACITY) + 1;
+
+      const created: PublicKey[] = [];
+      for (let i = 0; i < toCreate; i++) {
+        const seed = new anchor.BN(319_000 + i);
+        const [config] = PublicKey.findProgramAddressSync(
+          [Buffer.from("config"), seed.toArrayLike(Buffer, "le", 8)],
+          program.programId
+        );
+        const [mintLp] = PublicKey.findProgramAddressSync([Buffer.from("lp"), config.toBuffer()], program.programId);
+
+        await program.methods
+          .initialize(seed, ctx.fee, null, 50, 50, true, "")
+          .accounts({
+            initializer: ctx.initializer.publicKey,
+            mintX: ctx.mintX,
+            mintY: ctx.mintY,
+            //@ts-ignore
+            mintLp,
+            config,
+            vaultX: await getAssociatedTokenAddress(ctx.mintX, config, true),
+            vaultY: await getAssociatedTokenAddress(ctx.mintY, config, true),
+            ...(await registryAccounts()),
+            globalState: null,
+            creationFeeTreasury: null,
+            badgeX: null,
+            badgeY: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([ctx.initializer])
+          .rpc();
+        created.push(config);
+      }
+
+      const count = await poolCount();
+      assert.equal(count, start + toCreate);
+
+      // The last pool is the first entry of a fresh overflow page
+      const page = Math.floor((count - 1) / REGISTRY_PAGE_CAPACITY);
+      assert.ok(page >= 1);
+      const [pagePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("registry"), new anchor.BN(page).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const registryPage = await program.account.registryPage.fetch(pagePda);
+      assert.equal(registryPage.pageIndex.toNumber(), page);
+      assert.equal(registryPage.pools.length, 1);
+      assert.ok(registryPage.pools[0].equals(created[created.length - 1]));
+    });
+  });
+
+  describe("withdraw recipient", () => {
+    it("Sends withdrawn tokens to a separate recipient", async () => {
+      const ctx = await setupPool(new anchor.BN(320));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      // A fresh wallet with no token accounts yet
+      const recipient = Keypair.generate().publicKey;
+      const recipientX = await getAssociatedTokenAddress(ctx.mintX, recipient);
+      const recipientY = await getAssociatedTokenAddress(ctx.mintY, recipient);
+      const [userXBefore, lpBefore] = await Promise.all([balance(ctx.userAtaX), balance(ctx.userAtaLp)]);
+
+      await program.methods
+        .withdraw(new anchor.BN(50_000), new anchor.BN(0), new anchor.BN(0), false)
+        .accounts({
+          user: ctx.user.publicKey,
+          recipient,
+          //@ts-ignore
+          mintX: ctx.mintX,
+          mintY: ctx.mintY,
+          config: ctx.config,
+          vaultX: ctx.vaultX,
+          vaultY: ctx.vaultY,
+          mintLp: ctx.mintLp,
+          userX: recipientX,
+          userY: recipientY,
+          userLp: ctx.userAtaLp,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ctx.user])
+        .rpc();
+
+      // LP is burned from the signer, tokens land with the recipient
+      assert.equal(lpBefore - (await balance(ctx.userAtaLp)), BigInt(50_000));
+      assert.equal(await balance(recipientX), BigInt(50_000));
+      assert.equal(await balance(recipientY), BigInt(50_000));
+      assert.equal(await balance(ctx.userAtaX), userXBefore);
+    });
+  });
+
+  describe("swap recipient", () => {
+    it("Delivers swap output to a recipient's new token account", async () => {
+      const ctx = await setupPool(new anchor.BN(321));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      const recipient = Keypair.generate().publicKey;
+      const recipientY = await getAssociatedTokenAddress(ctx.mintY, recipient);
+      const q = await quote(ctx, 10_000, true);
+      const [userXBefore, userYBefore] = await Promise.all([balance(ctx.userAtaX), balance(ctx.userAtaY)]);
+
+      await program.methods
+        .swap(new anchor.BN(10_000), q.amountOut, true, null)
+        .accounts({
+          user: ctx.user.publicKey,
+          //@ts-ignore
+          mintX: ctx.mintX,
+          mintY: ctx.mintY,
+          config: ctx.config,
+          vaultX: ctx.vaultX,
+          vaultY: ctx.vaultY,
+          userX: ctx.userAtaX,
+          userY: ctx.userAtaY,
+          recipient,
+          recipientDst: recipientY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ctx.user])
+        .rpc();
+
+      // Input comes from the signer, output goes to the recipient
+      assert.equal(userXBefore - (await balance(ctx.userAtaX)), BigInt(10_000));
+      assert.equal(await balance(ctx.userAtaY), userYBefore);
+      assert.equal(await balance(recipientY), BigInt(q.amountOut.toString()));
+    });
+
+    it("Rejects a recipient account for the wrong mint", async () => {
+      const ctx = await setupPool(new anchor.BN(3211));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      const recipient = Keypair.generate().publicKey;
+      await expectError(
+        program.methods
+          .swap(new anchor.BN(10_000), new anchor.BN(1), true, null)
+          .accounts({
+            user: ctx.user.publicKey,
+            //@ts-ignore
+            mintX: ctx.mintX,
+            mintY: ctx.mintY,
+            config: ctx.config,
+            vaultX: ctx.vaultX,
+            vaultY: ctx.vaultY,
+            userX: ctx.userAtaX,
+            userY: ctx.userAtaY,
+            recipient,
+            // X-to-Y swaps pay out Y, so an X account is invalid
+            recipientDst: await getAssociatedTokenAddress(ctx.mintX, recipient),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([ctx.user])
+          .rpc(),
+        "InvalidRecipient"
+      );
+    });
+  });
+
+  describe("protocol fee", () => {
+    it("Diverts the protocol share of fees to the treasury", async () => {
+      const ctx = await setupPool(new anchor.BN(323));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      protocolAdmin = ctx.initializer;
+      const admin = protocolAdmin;
+      const treasury = protocolTreasury.publicKey;
+      const [globalConfig] = PublicKey.findProgramAddressSync([Buffer.from("global_config")], program.programId);
+
+      await program.methods
+        .initializeGlobalConfig(0, treasury)
+        .accounts({ admin: admin.publicKey })
+        .signers([admin])
+        .rpc();
+
+      // Only the admin can flip the switch
+      await expectError(
+        program.methods
+          .setProtocolFee(2_000)
+          .accounts({ admin: ctx.user.publicKey })
+          .signers([ctx.user])
+          .rpc(),
+        "ConstraintHasOne"
+      );
+      await program.methods
+        .setProtocolFee(2_000)
+        .accounts({ admin: admin.publicKey })
+        .signers([admin])
+        .rpc();
+
+      // 5% fee on 10_000 X is 500 X; 20% of that belongs to the protocol
+      await swapIn(ctx, 10_000, 1, true);
+      const config = await program.account.config.fetch(ctx.config);
+      assert.equal(config.protocolFeesX.toNumber(), 100);
+      assert.equal(config.protocolFeesY.toNumber(), 0);
+
+      const treasuryX = await getAssociatedTokenAddress(ctx.mintX, treasury);
+      const treasuryY = await getAssociatedTokenAddress(ctx.mintY, treasury);
+      await program.methods
+        .collectProtocolFees()
+        .accounts({
+          payer: ctx.user.publicKey,
+          //@ts-ignore
+          mintX: ctx.mintX,
+          mintY: ctx.mintY,
+          config: ctx.config,
+          globalConfig,
+          treasury,
+          vaultX: ctx.vaultX,
+          vaultY: ctx.vaultY,
+          treasuryX,
+          treasuryY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ctx.user])
+        .rpc();
+
+      assert.equal(await balance(treasuryX), BigInt(100));
+      assert.equal(await balance(treasuryY), BigInt(0));
+      const collected = await program.account.config.fetch(ctx.config);
+      assert.equal(collected.protocolFeesX.toNumber(), 0);
+
+      // Switch it back off so other pools in this run are unaffected
+      await program.methods
+        .setProtocolFee(0)
+        .accounts({ admin: admin.publicKey })
+        .signers([admin])
+        .rpc();
+    });
+  });
+
+  describe("deposit cap", () => {
+    it("Accepts deposits up to the cap exactly and rejects the next one", async () => {
+      const ctx = await setupPool(new anchor.BN(325));
+      const authority = ctx.initializer;
+      await initializePool(ctx, 50, 50, true, authority.publicKey);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      const setCap = (signer: Keypair, capX: number, capY: number) =>
+        program.methods
+          .setDepositCap(new anchor.BN(capX), new anchor.BN(capY))
+          .accounts({ authority: signer.publicKey, config: ctx.config })
+          .signers([signer])
+          .rpc();
+
+      await expectError(setCap(ctx.user, 150_000, 150_000), "InvalidAuthority");
+      await setCap(authority, 150_000, 150_000);
+
+      // Lands both vaults exactly on the cap
+      await depositTo(ctx, 50_000, 50_000, 50_000);
+      assert.equal(await balance(ctx.vaultX), BigInt(150_000));
+      assert.equal(await balance(ctx.vaultY), BigInt(150_000));
+
+      await expectError(depositTo(ctx, 1_000, 1_000, 1_000), "DepositCapExceeded");
+
+      // Swaps are not capped even though they grow the input vault
+      await swapIn(ctx, 1_000, 1, true);
+
+      // Removing the cap needs no migration
+      await setCap(authority, 0, 0);
+      await depositTo(ctx, 2_000, 3_000, 3_000);
+    });
+  });
+
+  describe("withdraw cooldown", () => {
+    it("Blocks withdrawals until the depositor's cooldown has elapsed", async () => {
+      const ctx = await setupPool(new anchor.BN(326));
+      const authority = ctx.initializer;
+      await initializePool(ctx, 50, 50, true, authority.publicKey);
+
+      await program.methods
+        .setWithdrawCooldown(3)
+        .accounts({ authority: authority.publicKey, config: ctx.config })
+        .signers([authority])
+        .rpc();
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      const [positionPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("position"), ctx.config.toBuffer(), ctx.user.publicKey.toBuffer()],
+        program.programId
+      );
+      const position = await program.account.position.fetch(positionPda);
+      const unlockTs = position.unlockTs.toNumber();
+      assert.equal(unlockTs - position.lastDepositTs.toNumber(), 3);
+
+      const withdraw = () =>
+        program.methods
+          .withdraw(new anchor.BN(10_000), new anchor.BN(0), new anchor.BN(0), false)
+          .accounts({
+            user: ctx.user.publicKey,
+            recipient: ctx.user.publicKey,
+            //@ts-ignore
+            mintX: ctx.mintX,
+            mintY: ctx.mintY,
+            config: ctx.config,
+            vaultX: ctx.vaultX,
+            vaultY: ctx.vaultY,
+            mintLp: ctx.mintLp,
+            userX: ctx.userAtaX,
+            userY: ctx.userAtaY,
+            userLp: ctx.userAtaLp,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([ctx.user])
+          .rpc();
+
+      // Just before the boundary
+      await expectError(withdraw(), "CooldownActive");
+
+      // The local validator cannot warp, so wait for the cluster clock to reach unlock_ts
+      const clock = async () => provider.connection.getBlockTime(await provider.connection.getSlot());
+      while ((await clock()) < unlockTs) {
+        await new Promise((resolve) => setTimeout(resolve, 400));
+      }
+
+      // Just after the boundary
+      await withdraw();
+    });
+  });
+
+  describe("admin action events", () => {
+    it("Emits an AdminActionEvent for every admin change", async () => {
+      const ctx = await setupPool(new anchor.BN(327));
+      const authority = ctx.initializer;
+      await initializePool(ctx, 50, 50, true, authority.publicKey);
+
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const adminEvents = async (signature: string) => {
+        const tx = await provider.connection.getTransaction(signature, {
+          commitment: "confirmed",
+          maxSupportedTransactionVersion: 0,
+        });
+        return [...parser.parseLogs(tx.meta.logMessages)]
+          .filter((event) => event.name === "adminActionEvent")
+          .map((event) => event.data);
+      };
+
+      const capTx = await program.methods
+        .setDepositCap(new anchor.BN(500_000), new anchor.BN(600_000))
+        .accounts({ authority: authority.publicKey, config: ctx.config })
+        .signers([authority])
+        .rpc({ commitment: "confirmed" });
+      const capEvents = await adminEvents(capTx);
+      assert.deepEqual(
+        capEvents.map((e: any) => [e.action, e.oldValue.toNumber(), e.newValue.toNumber()]),
+        [[0, 0, 500_000], [1, 0, 600_000]]
+      );
+      assert.ok(capEvents[0].config.equals(ctx.config));
+      assert.ok(capEvents[0].authority.equals(authority.publicKey));
+
+      const cooldownTx = await program.methods
+        .setWithdrawCooldown(60)
+        .accounts({ authority: authority.publicKey, config: ctx.config })
+        .signers([authority])
+        .rpc({ commitment: "confirmed" });
+      const cooldownEvents = await adminEvents(cooldownTx);
+      assert.equal(cooldownEvents.length, 1);
+      assert.equal(cooldownEvents[0].action, 2);
+      assert.equal(cooldownEvents[0].newValue.toNumber(), 60);
+    });
+  });
+
+  describe("checkpoints", () => {
+    it("Records a reserve snapshot and rate limits checkpoints", async () => {
+      const ctx = await setupPool(new anchor.BN(328));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 200_000);
+
+      const checkpoint = () =>
+        program.methods
+          .checkpoint()
+          .accounts({
+            payer: ctx.user.publicKey,
+            //@ts-ignore
+            mintX: ctx.mintX,
+            mintY: ctx.mintY,
+            config: ctx.config,
+            mintLp: ctx.mintLp,
+            vaultX: ctx.vaultX,
+            vaultY: ctx.vaultY,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([ctx.user])
+          .rpc();
+      const snapshot = (index: number) =>
+        program.methods
+          .getSnapshot(index)
+          .accounts({ config: ctx.config })
+          .view();
+
+      await checkpoint();
+      const latest = await snapshot(0);
+      assert.equal(latest.reserveX.toNumber(), 100_000);
+      assert.equal(latest.reserveY.toNumber(), 200_000);
+      assert.equal(latest.lpSupply.toNumber(), 100_000);
+      assert.ok(latest.slot.toNumber() > 0);
+
+      await expectError(checkpoint(), "CheckpointTooSoon");
+
+      const [snapshotsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("snapshots"), ctx.config.toBuffer()],
+        program.programId
+      );
+      const snapshots = await program.account.snapshots.fetch(snapshotsPda);
+      assert.equal(snapshots.entries.length, 1);
+    });
+  });
+
+  describe("swap footprint", () => {
+    it("Keeps plain swaps free of the ATA and System programs", async () => {
+      const ctx = await setupPool(new anchor.BN(329));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      const signature = await program.methods
+        .swap(new anchor.BN(1_000), new anchor.BN(1), true, null)
+        .accounts({
+          user: ctx.user.publicKey,
+          //@ts-ignore
+          mintX: ctx.mintX,
+          mintY: ctx.mintY,
+          config: ctx.config,
+          vaultX: ctx.vaultX,
+          vaultY: ctx.vaultY,
+          userX: ctx.userAtaX,
+          userY: ctx.userAtaY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: null,
+          systemProgram: null,
+        })
+        .signers([ctx.user])
+        .rpc({ commitment: "confirmed" });
+
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const keys = tx.transaction.message.staticAccountKeys;
+      const size = tx.transaction.message.serialize().length + 1 + 64 * tx.transaction.signatures.length;
+
+      // Each dropped account saves 32 bytes of message plus its deserialization CU
+      assert.ok(!keys.some((key) => key.equals(ASSOCIATED_TOKEN_PROGRAM_ID)));
+      assert.ok(!keys.some((key) => key.equals(SystemProgram.programId)));
+      console.log(`      swap: ${tx.meta.computeUnitsConsumed} CU, ${size} bytes, ${keys.length} account keys`);
+    });
+  });
+
+  describe("host fee", () => {
+    const hostAccount = async (ctx: AmmContext, mint: PublicKey) =>
+      (await getOrCreateAssociatedTokenAccount(provider.connection, ctx.user, mint, Keypair.generate().publicKey)).address;
+    const writable = (pubkey: PublicKey) => [{ pubkey, isSigner: false, isWritable: true }];
+
+    it("Pays the host a slice of the pool fee without changing the user's output", async () => {
+      const ctx = await setupPool(new anchor.BN(330));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      const host = await hostAccount(ctx, ctx.mintY);
+      const q = await quote(ctx, 10_000, true);
+      const userYBefore = await balance(ctx.userAtaY);
+
+      await swapIn(ctx, 10_000, q.amountOut.toNumber(), true, writable(host));
+
+      assert.equal((await balance(ctx.userAtaY)) - userYBefore, BigInt(q.amountOut.toString()));
+      // 500 X fee is worth 8_675 * 500 / 9_500 = 456 Y; the host gets 20% of it
+      assert.equal(await balance(host), BigInt(91));
+    });
+
+    it("Leaves fees with the pool when no host account is supplied", async () => {
+      const ctx = await setupPool(new anchor.BN(3301));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      const q = await quote(ctx, 10_000, true);
+      const vaultYBefore = await balance(ctx.vaultY);
+      await swapIn(ctx, 10_000, q.amountOut.toNumber(), true);
+      assert.equal(vaultYBefore - (await balance(ctx.vaultY)), BigInt(q.amountOut.toString()));
+    });
+
+    it("Rejects a host account for the wrong mint", async () => {
+      const ctx = await setupPool(new anchor.BN(3302));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      const host = await hostAccount(ctx, ctx.mintX);
+      await expectError(swapIn(ctx, 10_000, 1, true, writable(host)), "InvalidHostFeeAccount");
+    });
+  });
+
+  describe("error codes", () => {
+    let ctx: AmmContext;
+
+    before(async () => {
+      ctx = await setupPool(new anchor.BN(331));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+    });
+
+    it("Rejects a zero swap input with ZeroAmountIn", async () => {
+      await expectError(swapIn(ctx, 0, 0, true), "ZeroAmountIn");
+    });
+
+    it("Rejects a swap larger than the user's balance with InsufficientUserBalance", async () => {
+      await expectError(swapIn(ctx, 950_000, 0, true), "InsufficientUserBalance");
+    });
+
+    it("Rejects a swap whose output rounds to zero with ZeroAmountOut", async () => {
+      // 1 token in minus the 5% fee leaves nothing to trade
+      await expectError(swapIn(ctx, 1, 0, true), "ZeroAmountOut");
+    });
+
+    it("Rejects a zero LP deposit with ZeroLpAmount", async () => {
+      await expectError(depositTo(ctx, 0, 1_000, 1_000), "ZeroLpAmount");
+    });
+
+    it("Rejects a deposit larger than the user's balance with InsufficientUserBalance", async () => {
+      await expectError(depositTo(ctx, 1_000_000, 2_000_000, 2_000_000), "InsufficientUserBalance");
+    });
+
+    it("Rejects a zero LP withdraw with ZeroLpAmount", async () => {
+      await expectError(withdrawFrom(ctx, 0), "ZeroLpAmount");
+    });
+
+    it("Rejects withdrawing more LP than held with InsufficientUserBalance", async () => {
+      await expectError(withdrawFrom(ctx, 100_001), "InsufficientUserBalance");
+    });
+  });
+
+  describe("close deposits", () => {
+    it("Closes deposits irreversibly while swaps and withdrawals continue", async () => {
+      const ctx = await setupPool(new anchor.BN(333));
+      const authority = ctx.initializer;
+      await initializePool(ctx, 50, 50, true, authority.publicKey);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      const closeDeposits = (signer: Keypair) =>
+        program.methods
+          .closeDeposits()
+          .accounts({ authority: signer.publicKey, config: ctx.config })
+          .signers([signer])
+          .rpc();
+
+      await expectError(closeDeposits(ctx.user), "InvalidAuthority");
+      await closeDeposits(authority);
+      assert.isTrue((await program.account.config.fetch(ctx.config)).depositsClosed);
+
+      await expectError(depositTo(ctx, 10_000, 20_000, 20_000), "DepositsClosed");
+      await swapIn(ctx, 1_000, 1, true);
+      await withdrawFrom(ctx, 50_000);
+
+      // There is no way back
+      await expectError(closeDeposits(authority), "DepositsClosed");
+      assert.isTrue((await program.account.config.fetch(ctx.config)).depositsClosed);
+    });
+  });
+
+  describe("swap and deposit", () => {
+    const swapAndDeposit = (ctx: AmmContext, amountX: number, amountY: number, minLp: number) =>
+      program.methods
+        .swapAndDeposit(new anchor.BN(amountX), new anchor.BN(amountY), new anchor.BN(minLp))
+        .accounts({
+          user: ctx.user.publicKey,
+          //@ts-ignore
+          mintX: ctx.mintX,
+          mintY: ctx.mintY,
+          config: ctx.config,
+          vaultX: ctx.vaultX,
+          vaultY: ctx.vaultY,
+          mintLp: ctx.mintLp,
+          userX: ctx.userAtaX,
+          userY: ctx.userAtaY,
+          userLp: ctx.userAtaLp,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ctx.user])
+        .rpc();
+
+    it("Zaps a single-sided amount into a balanced deposit", async () => {
+      const ctx = await setupPool(new anchor.BN(334));
+      await initializePool(ctx, 50, 50, true);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      const lpBefore = await balance(ctx.userAtaLp);
+      const xBefore = await balance(ctx.userAtaX);
+      const yBefore = await balance(ctx.userAtaY);
+
+      await expectError(swapAndDeposit(ctx, 20_000, 0, 20_000), "SlippageExceeded");
+      await swapAndDeposit(ctx, 20_000, 0, 1);
+
+      const minted = Number((await balance(ctx.userAtaLp)) - lpBefore);
+      const spentX = Number(xBefore - (await balance(ctx.userAtaX)));
+      const yDelta = Number((await balance(ctx.userAtaY)) - yBefore);
+
+      // Roughly half the X is swapped, so the LP share is a little under 10%
+      assert.isAbove(minted, 9_000);
+      assert.isBelow(minted, 10_000);
+      // Only dust is left over from the swapped Y
+      assert.isAtMost(spentX, 20_000);
+      assert.isAtLeast(yDelta, 0);
+      assert.isBelow(yDelta, 10);
+    });
+
+    it("Rejects zaps into an empty pool", async () => {
+      const ctx = await setupPool(new anchor.BN(3341));
+      await initializePool(ctx, 50, 50, true);
+      await expectError(swapAndDeposit(ctx, 20_000, 0, 1), "NoLiquidityInPool");
+    });
+  });
+
+  describe("batch protocol fee collection", () => {
+    const setProtocolFee = (bps: number) =>
+      program.methods
+        .setProtocolFee(bps)
+        .accounts({ admin: protocolAdmin.publicKey })
+        .signers([protocolAdmin])
+        .rpc();
+
+    const poolGroup = async (ctx: AmmContext) => {
+      const treasuryX = await getAssociatedTokenAddress(ctx.mintX, protocolTreasury.publicKey);
+      const treasuryY = await getAssociatedTokenAddress(ctx.mintY, protocolTreasury.publicKey);
+      return [ctx.config, ctx.vaultX, ctx.vaultY, treasuryX, treasuryY].map((pubkey) => ({
+        pubkey,
+        isSigner: false,
+        isWritable: true,
+      }));
+    };
+
+    const collectMulti = (groups: { pubkey: PublicKey; isSigner: boolean; isWritable: boolean }[][]) =>
+      program.methods
+        .collectProtocolFeesMulti()
+        .accounts({ treasury: protocolTreasury.publicKey, tokenProgram: TOKEN_PROGRAM_ID })
+        .remainingAccounts(groups.flat())
+        .signers([protocolTreasury])
+        .rpc();
+
+    it("Sweeps several pools at once and skips pools with nothing accrued", async () => {
+      const pools: AmmContext[] = [];
+      for (const seed of [3351, 3352, 3353]) {
+        const ctx = await setupPool(new anchor.BN(seed));
+        await initializePool(ctx);
+        await depositTo(ctx, 100_000, 100_000, 100_000);
+        pools.push(ctx);
+      }
+
+      // The last pool trades while the switch is off, so it accrues nothing
+      await setProtocolFee(2_000);
+      await swapIn(pools[0], 10_000, 1, true);
+      await swapIn(pools[1], 10_000, 1, false);
+      await setProtocolFee(0);
+      await swapIn(pools[2], 10_000, 1, true);
+
+      for (const ctx of pools.slice(0, 2)) {
+        for (const mint of [ctx.mintX, ctx.mintY]) {
+          await getOrCreateAssociatedTokenAccount(provider.connection, ctx.user, mint, protocolTreasury.publicKey);
+        }
+      }
+
+      const groups = await Promise.all(pools.map(poolGroup));
+      await expectError(collectMulti([]), "InvalidBatchSize");
+      await expectError(collectMulti(Array(9).fill(groups[2])), "InvalidBatchSize");
+      await expectError(
+        collectMulti([[groups[0][0], groups[1][1], ...groups[0].slice(2)]]),
+        "InvalidVaultAccount"
+      );
+
+      await collectMulti(groups);
+
+      assert.equal(await balance(groups[0][3].pubkey), BigInt(100));
+      assert.equal(await balance(groups[1][4].pubkey), BigInt(100));
+      for (const ctx of pools) {
+        const config = await program.account.config.fetch(ctx.config);
+        assert.equal(config.protocolFeesX.toNumber(), 0);
+        assert.equal(config.protocolFeesY.toNumber(), 0);
+      }
+
+      // Running the crank again is a no-op rather than an error
+      await collectMulti(groups);
+      assert.equal(await balance(groups[0][3].pubkey), BigInt(100));
+    });
+  });
+
+  describe("pool label", () => {
+    const decodeLabel = (label: number[]) => Buffer.from(label).toString("utf8").replace(/\0+$/, "");
+
+    const poolStats = (ctx: AmmContext) =>
+      program.methods
+        .getPoolStats()
+        .accounts({
+          //@ts-ignore
+          mintX: ctx.mintX,
+          mintY: ctx.mintY,
+          config: ctx.config,
+          vaultX: ctx.vaultX,
+          vaultY: ctx.vaultY,
+          mintLp: ctx.mintLp,
+        })
+        .view();
+
+    it("Stores the label at creation and lets the authority change it", async () => {
+      const ctx = await setupPool(new anchor.BN(336));
+      const authority = ctx.initializer;
+
+      await expectError(initializePool(ctx, 50, 50, true, authority.publicKey, "x".repeat(33)), "InvalidLabel");
+      const signature = await initializePool(ctx, 50, 50, true, authority.publicKey, "JUP/USDC 0.3%");
+
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const created = [...parser.parseLogs(tx.meta.logMessages)].find((event) => event.name === "poolCreatedEvent");
+      assert.equal(decodeLabel(created.data.label), "JUP/USDC 0.3%");
+
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+      const stats = await poolStats(ctx);
+      assert.equal(decodeLabel(stats.label), "JUP/USDC 0.3%");
+      assert.equal(stats.reserveX.toNumber(), 100_000);
+      assert.equal(stats.lpSupply.toNumber(), 100_000);
+
+      const setLabel = (signer: Keypair, label: string) =>
+        program.methods
+          .setLabel(label)
+          .accounts({ authority: signer.publicKey, config: ctx.config })
+          .signers([signer])
+          .rpc();
+
+      await expectError(setLabel(ctx.user, "hijacked"), "InvalidAuthority");
+      await setLabel(authority, "JUP/USDC 0.05%");
+      assert.equal(decodeLabel((await program.account.config.fetch(ctx.config)).label), "JUP/USDC 0.05%");
+
+      // Empty labels are allowed and clear the field
+      await setLabel(authority, "");
+      assert.deepEqual((await program.account.config.fetch(ctx.config)).label, Array(32).fill(0));
+    });
+
+    it("Creates pools without a label", async () => {
+      const ctx = await setupPool(new anchor.BN(3361));
+      await initializePool(ctx);
+      assert.deepEqual((await program.account.config.fetch(ctx.config)).label, Array(32).fill(0));
+    });
+  });
+
+  describe("lock events", () => {
+    it("Records and reports why a pool was locked", async () => {
+      const ctx = await setupPool(new anchor.BN(337));
+      const authority = ctx.initializer;
+      await initializePool(ctx, 50, 50, true, authority.publicKey);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const eventOf = async (signature: string, name: string) => {
+        const tx = await provider.connection.getTransaction(signature, {
+          commitment: "confirmed",
+          maxSupportedTransactionVersion: 0,
+        });
+        return [...parser.parseLogs(tx.meta.logMessages)].find((event) => event.name === name).data;
+      };
+      const lockPool = (signer: Keypair, reason: object) =>
+        program.methods
+          .lockPool(reason as any)
+          .accounts({ authority: signer.publicKey, config: ctx.config })
+          .signers([signer])
+          .rpc({ commitment: "confirmed" });
+      const unlockPool = (signer: Keypair) =>
+        program.methods
+          .unlockPool()
+          .accounts({ authority: signer.publicKey, config: ctx.config })
+          .signers([signer])
+          .rpc({ commitment: "confirmed" });
+
+      await expectError(lockPool(ctx.user, { manual: {} }), "InvalidAuthority");
+      await expectError(lockPool(authority, { circuitBreaker: {} }), "InvalidLockReason");
+      await expectError(unlockPool(authority), "PoolNotLocked");
+
+      const locked = await eventOf(await lockPool(authority, { manual: {} }), "poolLockedEvent");
+      assert.equal(locked.reason, 0);
+      assert.isTrue(locked.by.equals(authority.publicKey));
+      let config = await program.account.config.fetch(ctx.config);
+      assert.isTrue(config.locked);
+      assert.equal(config.lockReason, 0);
+      assert.equal(config.lockedAt.toNumber(), locked.timestamp.toNumber());
+
+      await expectError(swapIn(ctx, 1_000, 1, true), "PoolLocked");
+      await expectError(lockPool(authority, { migration: {} }), "PoolLocked");
+
+      const unlocked = await eventOf(await unlockPool(authority), "poolUnlockedEvent");
+      assert.equal(unlocked.reason, 0);
+      config = await program.account.config.fetch(ctx.config);
+      assert.isFalse(config.locked);
+      assert.equal(config.lockedAt.toNumber(), 0);
+      await swapIn(ctx, 1_000, 1, true);
+
+      const migration = await eventOf(await lockPool(authority, { migration: {} }), "poolLockedEvent");
+      assert.equal(migration.reason, 2);
+      assert.equal((await program.account.config.fetch(ctx.config)).lockReason, 2);
+      assert.equal((await eventOf(await unlockPool(authority), "poolUnlockedEvent")).reason, 2);
+    });
+  });
+
+  describe("price guard", () => {
+    const guard = (num: number, den: number, maxDeviationBps: number) => ({
+      expectedPriceNum: new anchor.BN(num),
+      expectedPriceDen: new anchor.BN(den),
+      maxDeviationBps,
+    });
+
+    it("Rejects swaps when the pool price moved past the caller's tolerance", async () => {
+      // X has 6 decimals and Y has 9, so equal whole-token reserves price 1 X at 2 Y
+      const ctx = await setupPool(new anchor.BN(338), 6, 9);
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 200_000_000);
+
+      // Within 1% of the expected Y-per-X price, in either direction of the swap
+      await swapIn(ctx, 1_000, 1, true, [], guard(2, 1, 100));
+      await swapIn(ctx, 1_000_000, 1, false, [], guard(196, 100, 100));
+
+      // Someone moves the price about 10% before the guarded swap lands
+      await swapIn(ctx, 5_000, 1, true);
+      await expectError(swapIn(ctx, 1_000, 1, true, [], guard(2, 1, 100)), "PriceDeviationTooHigh");
+      await swapIn(ctx, 1_000, 1, true, [], guard(2, 1, 1_500));
+
+      // min_amount_out still applies on top of a passing guard
+      await expectError(swapIn(ctx, 1_000, 1_000_000_000, true, [], guard(2, 1, 1_500)), "SlippageExceeded");
+      await expectError(swapIn(ctx, 1_000, 1, true, [], guard(2, 0, 100)), "InvalidPriceGuard");
+    });
+  });
+
+  describe("nested pools", () => {
+    it("Rejects a pool over another pool's LP mint", async () => {
+      const parent = await setupPool(new anchor.BN(339));
+      await initializePool(parent);
+      await depositTo(parent, 100_000, 100_000, 100_000);
+
+      // Same pool B over A's LP mint, on either side
+      const overX = await setupPool(new anchor.BN(3391));
+      overX.mintX = parent.mintLp;
+      overX.vaultX = await getAssociatedTokenAddress(parent.mintLp, overX.config, true);
+      await expectError(initializePool(overX), "NestedPoolNotAllowed");
+
+      const overY = await setupPool(new anchor.BN(3392));
+      overY.mintY = parent.mintLp;
+      overY.vaultY = await getAssociatedTokenAddress(parent.mintLp, overY.config, true);
+      await expectError(initializePool(overY), "NestedPoolNotAllowed");
+
+      // Ordinary mints are still fine
+      const plain = await setupPool(new anchor.BN(3393));
+      await initializePool(plain);
+    });
+  });
+
+  describe("return data", () => {
+    const caller = anchor.workspace.ammCaller as Program<AmmCaller>;
+
+    it("Reports actual deposit and withdraw amounts to CPI callers", async () => {
+      const ctx = await setupPool(new anchor.BN(340));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 200_000);
+
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from("position"), ctx.config.toBuffer(), ctx.user.publicKey.toBuffer()],
+        program.programId
+      );
+      const [feeState] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_state"), ctx.config.toBuffer()],
+        program.programId
+      );
+      const [receipt] = PublicKey.findProgramAddressSync(
+        [Buffer.from("receipt"), ctx.user.publicKey.toBuffer()],
+        caller.programId
+      );
+      const accounts = {
+        user: ctx.user.publicKey,
+        mintX: ctx.mintX,
+        mintY: ctx.mintY,
+        config: ctx.config,
+        vaultX: ctx.vaultX,
+        vaultY: ctx.vaultY,
+        mintLp: ctx.mintLp,
+        userX: ctx.userAtaX,
+        userY: ctx.userAtaY,
+        userLp: ctx.userAtaLp,
+        position,
+        feeState,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        ammProgram: program.programId,
+      };
+
+      // Generous maximums; the pool only pulls in the proportional amounts
+      const xBefore = await balance(ctx.userAtaX);
+      await caller.methods
+        .depositViaCpi(new anchor.BN(10_000), new anchor.BN(50_000), new anchor.BN(50_000))
+        //@ts-ignore
+        .accounts(accounts)
+        .signers([ctx.user])
+        .rpc();
+
+      let reported = await caller.account.receipt.fetch(receipt);
+      assert.equal(reported.amountX.toNumber(), 10_000);
+      assert.equal(reported.amountY.toNumber(), 20_000);
+      assert.equal(reported.lpAmount.toNumber(), 10_000);
+      assert.equal(xBefore - (await balance(ctx.userAtaX)), BigInt(10_000));
+
+      await caller.methods
+        .withdrawViaCpi(new anchor.BN(55_000), new anchor.BN(0), new anchor.BN(0))
+        //@ts-ignore
+        .accounts(accounts)
+        .signers([ctx.user])
+        .rpc();
+
+      reported = await caller.account.receipt.fetch(receipt);
+      assert.equal(reported.amountX.toNumber(), 55_000);
+      assert.equal(reported.amountY.toNumber(), 110_000);
+      assert.equal(reported.lpAmount.toNumber(), 55_000);
+    });
+  });
+
+  describe("locked deposits", () => {
+    it("Escrows locked LP and releases it only after the lock expires", async () => {
+      const ctx = await setupPool(new anchor.BN(341));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      const [lockedPosition] = PublicKey.findProgramAddressSync(
+        [Buffer.from("locked_position"), ctx.config.toBuffer(), ctx.user.publicKey.toBuffer()],
+        program.programId
+      );
+      const [lockedLp] = PublicKey.findProgramAddressSync(
+        [Buffer.from("locked_lp"), ctx.config.toBuffer(), ctx.user.publicKey.toBuffer()],
+        program.programId
+      );
+      const [userPosition] = PublicKey.findProgramAddressSync(
+        [Buffer.from("position"), ctx.config.toBuffer(), ctx.user.publicKey.toBuffer()],
+        program.programId
+      );
+      const depositLocked = (lockDuration: number, withLockAccounts = true) =>
+        program.methods
+          .deposit(new anchor.BN(20_000), new anchor.BN(20_000), new anchor.BN(20_000), new anchor.BN(lockDuration))
+          .accounts({
+            user: ctx.user.publicKey,
+            //@ts-ignore
+            mintX: ctx.mintX,
+            mintY: ctx.mintY,
+            config: ctx.config,
+            vaultX: ctx.vaultX,
+            vaultY: ctx.vaultY,
+            mintLp: ctx.mintLp,
+            userX: ctx.userAtaX,
+            userY: ctx.userAtaY,
+            userLp: ctx.userAtaLp,
+            lockedPosition: withLockAccounts ? lockedPosition : null,
+            lockedLp: withLockAccounts ? lockedLp : null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([ctx.user])
+          .rpc();
+      const release = () =>
+        program.methods
+          .releaseLockedLp()
+          .accounts({
+            user: ctx.user.publicKey,
+            //@ts-ignore
+            config: ctx.config,
+            mintLp: ctx.mintLp,
+            lockedPosition,
+            lockedLp,
+            userLp: ctx.userAtaLp,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([ctx.user])
+          .rpc();
+
+      await expectError(depositLocked(-1), "InvalidLockDuration");
+      await expectError(depositLocked(3, false), "LockAccountsMissing");
+
+      const lpBefore = await balance(ctx.userAtaLp);
+      await depositLocked(3);
+
+      // The LP went to escrow, not to the user
+      assert.equal(await balance(ctx.userAtaLp), lpBefore);
+      assert.equal(await balance(lockedLp), BigInt(20_000));
+      const position = await program.account.lockedPosition.fetch(lockedPosition);
+      assert.equal(position.lpAmount.toNumber(), 20_000);
+      // A 3 second lock barely earns any boost over 1x
+      assert.equal(position.boostBps, 10_000);
+      // The locked LP is registered to earn fees at its boosted weight
+      let registered = await program.account.position.fetch(userPosition);
+      assert.equal(registered.lpBalance.toNumber(), 100_000);
+      assert.equal(registered.lockedWeight.toNumber(), 20_000);
+
+      await expectError(release(), "PositionLocked");
+
+      await new Promise((resolve) => setTimeout(resolve, 5_000));
+      await release();
+      assert.equal(await balance(ctx.userAtaLp), lpBefore + BigInt(20_000));
+      assert.equal(await balance(lockedLp), BigInt(0));
+      assert.equal((await program.account.lockedPosition.fetch(lockedPosition)).lpAmount.toNumber(), 0);
+      // The released LP moved from the locked weight to the registered balance
+      registered = await program.account.position.fetch(userPosition);
+      assert.equal(registered.lpBalance.toNumber(), 120_000);
+      assert.equal(registered.lockedWeight.toNumber(), 0);
+
+      // Released LP withdraws like any other
+      await withdrawFrom(ctx, 20_000);
+    });
+  });
+
+  describe("global state", () => {
+    it("Applies the admin's pool creation settings", async () => {
+      globalAdmin = Keypair.generate();
+      const admin = globalAdmin;
+      const treasury = Keypair.generate().publicKey;
+      const [globalState] = PublicKey.findProgramAddressSync([Buffer.from("global")], program.programId);
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(admin.publicKey, anchor.web3.LAMPORTS_PER_SOL),
+        "confirmed"
+      );
+
+      await program.methods
+        .initializeGlobalState(30, new anchor.BN(10_000_000), treasury)
+        .accounts({ admin: admin.publicKey })
+        .signers([admin])
+        .rpc();
+
+      const withGlobalState = { globalState, creationFeeTreasury: treasury };
+      const setPaused = (signer: Keypair, paused: boolean) =>
+        program.methods
+          .setPoolCreationPaused(paused)
+          .accounts({ admin: signer.publicKey })
+          .signers([signer])
+          .rpc();
+
+      // Paused: nothing can be created through the global state
+      const paused = await setupPool(new anchor.BN(342));
+      await expectError(setPaused(paused.user, true), "InvalidAuthority");
+      await setPaused(admin, true);
+      await expectError(initializePool(paused, 50, 50, true, null, "", withGlobalState), "PoolCreationPaused");
+      await setPaused(admin, false);
+
+      // u16::MAX falls back to the default fee and the creation fee reaches the treasury
+      const ctx = await setupPool(new anchor.BN(3421));
+      ctx.fee = 65_535;
+      await expectError(
+        initializePool(ctx, 50, 50, true, null, "", { globalState, creationFeeTreasury: null }),
+        "InvalidTreasuryAccount"
+      );
+      await initializePool(ctx, 50, 50, true, null, "", withGlobalState);
+      assert.equal((await program.account.config.fetch(ctx.config)).fee, 30);
+      assert.equal(await provider.connection.getBalance(treasury), 10_000_000);
+
+      // Explicit fees are kept, and free creation needs no treasury
+      await program.methods
+        .setPoolCreationFee(new anchor.BN(0))
+        .accounts({ admin: admin.publicKey })
+        .signers([admin])
+        .rpc();
+      const explicit = await setupPool(new anchor.BN(3422));
+      await initializePool(explicit, 50, 50, true, null, "", { globalState, creationFeeTreasury: null });
+      assert.equal((await program.account.config.fetch(explicit.config)).fee, explicit.fee);
+
+      await program.methods
+        .setDefaultFee(25)
+        .accounts({ admin: admin.publicKey })
+        .signers([admin])
+        .rpc();
+      assert.equal((await program.account.globalState.fetch(globalState)).defaultFee, 25);
+
+      // During rollout pools can still be created without the global state
+      await initializePool(paused);
+    });
+  });
+
+  describe("close empty LP account", () => {
+    it("Closes the LP account and refunds its rent only once it is empty", async () => {
+      const ctx = await setupPool(new anchor.BN(343));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      // Dust remains, so the account stays open
+      await withdrawFrom(ctx, 40_000, 0, 0, true);
+      assert.equal(await balance(ctx.userAtaLp), BigInt(60_000));
+
+      // Without the flag an emptied account is left alone
+      await depositTo(ctx, 10_000, 10_000, 10_000);
+      await withdrawFrom(ctx, 70_000);
+      assert.equal(await balance(ctx.userAtaLp), BigInt(0));
+
+      await depositTo(ctx, 10_000, 10_000, 10_000);
+      const rent = await provider.connection.getBalance(ctx.userAtaLp);
+      const lamportsBefore = await provider.connection.getBalance(ctx.user.publicKey);
+      await withdrawFrom(ctx, 10_000, 0, 0, true);
+
+      assert.isNull(await provider.connection.getAccountInfo(ctx.userAtaLp));
+      assert.equal(await provider.connection.getBalance(ctx.user.publicKey), lamportsBefore + rent);
+    });
+  });
+
+  describe("LP fee claims", () => {
+    const claimFees = (ctx: AmmContext, userLp: PublicKey | null = ctx.userAtaLp) =>
+      program.methods
+        .claimFees()
+        .accounts({
+          user: ctx.user.publicKey,
+          //@ts-ignore
+          mintX: ctx.mintX,
+          mintY: ctx.mintY,
+          config: ctx.config,
+          vaultX: ctx.vaultX,
+          vaultY: ctx.vaultY,
+          userLp,
+          userX: ctx.userAtaX,
+          userY: ctx.userAtaY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ctx.user])
+        .rpc();
+
+    const poolStats = (ctx: AmmContext) =>
+      program.methods
+        .getPoolStats()
+        .accounts({
+          //@ts-ignore
+          mintX: ctx.mintX,
+          mintY: ctx.mintY,
+          config: ctx.config,
+          vaultX: ctx.vaultX,
+          vaultY: ctx.vaultY,
+          mintLp: ctx.mintLp,
+        })
+        .view();
+
+    it("Keeps LP fees out of the reserves and pays them out on claim", async () => {
+      const ctx = await setupPool(new anchor.BN(345));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      await swapIn(ctx, 10_000, 1, true);
+      await swapIn(ctx, 10_000, 1, false);
+
+      const config = await program.account.config.fetch(ctx.config);
+      const owedX = BigInt(config.lpFeesOwedX.toString());
+      const owedY = BigInt(config.lpFeesOwedY.toString());
+      assert.ok(owedX > BigInt(0) && owedY > BigInt(0), "Swaps should accrue LP fees");
+
+      // Unclaimed LP fees are not priced into the pool
+      const stats = await poolStats(ctx);
+      const protocolX = BigInt(config.protocolFeesX.toString());
+      assert.equal(BigInt(stats.reserveX.toString()), (await balance(ctx.vaultX)) - protocolX - owedX);
+
+      const [userXBefore, userYBefore] = [await balance(ctx.userAtaX), await balance(ctx.userAtaY)];
+      const vaultXBefore = await balance(ctx.vaultX);
+      await claimFees(ctx);
+      const claimedX = (await balance(ctx.userAtaX)) - userXBefore;
+      const claimedY = (await balance(ctx.userAtaY)) - userYBefore;
+
+      // The only LP gets everything but rounding dust
+      assert.ok(claimedX <= owedX && owedX - claimedX <= BigInt(1));
+      assert.ok(claimedY <= owedY && owedY - claimedY <= BigInt(1));
+
+      // The claim comes out of the set-aside fees only: reserves are untouched
+      assert.equal(await balance(ctx.vaultX), vaultXBefore - claimedX);
+      const statsAfter = await poolStats(ctx);
+      assert.equal(statsAfter.reserveX.toString(), stats.reserveX.toString());
+      assert.equal(statsAfter.reserveY.toString(), stats.reserveY.toString());
+      const after = await program.account.config.fetch(ctx.config);
+      assert.equal(BigInt(after.lpFeesOwedX.toString()), owedX - claimedX);
+
+      await expectError(claimFees(ctx), "NothingToClaim");
+    });
+
+    it("Lets a fully exited LP claim after closing their LP account", async () => {
+      const ctx = await setupPool(new anchor.BN(3451));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+      await swapIn(ctx, 10_000, 1, true);
+
+      await withdrawFrom(ctx, 100_000, 0, 0, true);
+      assert.isNull(await provider.connection.getAccountInfo(ctx.userAtaLp));
+
+      // Fees earned before the exit were settled into the position by the withdraw
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from("position"), ctx.config.toBuffer(), ctx.user.publicKey.toBuffer()],
+        program.programId
+      );
+      const settled = await program.account.position.fetch(position);
+      assert.equal(settled.lpBalance.toNumber(), 0);
+      assert.ok(settled.feesOwedX.toNumber() > 0);
+
+      const userXBefore = await balance(ctx.userAtaX);
+      await claimFees(ctx, null);
+      assert.equal(await balance(ctx.userAtaX) - userXBefore, BigInt(settled.feesOwedX.toString()));
+    });
+
+    it("Pays only registered LP and returns the share of LP moved away to the reserves", async () => {
+      const ctx = await setupPool(new anchor.BN(3452));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      // Half the LP moves to a wallet that has not registered it
+      const other = Keypair.generate();
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(other.publicKey, anchor.web3.LAMPORTS_PER_SOL),
+        "confirmed"
+      );
+      const otherCtx: AmmContext = {
+        ...ctx,
+        user: other,
+        userAtaX: await getAssociatedTokenAddress(ctx.mintX, other.publicKey),
+        userAtaY: await getAssociatedTokenAddress(ctx.mintY, other.publicKey),
+        userAtaLp: (await getOrCreateAssociatedTokenAccount(provider.connection, other, ctx.mintLp, other.publicKey)).address,
+      };
+      await transfer(provider.connection, ctx.user, ctx.userAtaLp, otherCtx.userAtaLp, ctx.user, 50_000);
+
+      await swapIn(ctx, 10_000, 1, true);
+      const owedX = BigInt((await program.account.config.fetch(ctx.config)).lpFeesOwedX.toString());
+
+      // The user earns on the half still held; the moved half's share goes back to the reserves
+      let userXBefore = await balance(ctx.userAtaX);
+      await claimFees(ctx);
+      const claimedX = (await balance(ctx.userAtaX)) - userXBefore;
+      assert.ok(claimedX <= owedX / BigInt(2) && owedX / BigInt(2) - claimedX <= BigInt(1));
+      assert.ok(BigInt((await program.account.config.fetch(ctx.config)).lpFeesOwedX.toString()) <= BigInt(1));
+      await expectError(claimFees(otherCtx), "AccountNotInitialized");
+
+      // Once registered, the other wallet shares later fees equally
+      await program.methods
+        .syncPosition()
+        .accounts({
+          user: other.publicKey,
+          //@ts-ignore
+          config: ctx.config,
+          mintLp: ctx.mintLp,
+          userLp: otherCtx.userAtaLp,
+          lockedPosition: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([other])
+        .rpc();
+      const [otherPosition] = PublicKey.findProgramAddressSync(
+        [Buffer.from("position"), ctx.config.toBuffer(), other.publicKey.toBuffer()],
+        program.programId
+      );
+      const synced = await program.account.position.fetch(otherPosition);
+      assert.equal(synced.lpBalance.toNumber(), 50_000);
+      assert.equal(synced.unlockTs.toNumber(), 0);
+
+      const dust = BigInt((await program.account.config.fetch(ctx.config)).lpFeesOwedX.toString());
+      await swapIn(ctx, 10_000, 1, true);
+      const earnedX = BigInt((await program.account.config.fetch(ctx.config)).lpFeesOwedX.toString()) - dust;
+
+      userXBefore = await balance(ctx.userAtaX);
+      await claimFees(ctx);
+      await claimFees(otherCtx);
+      const userClaimedX = (await balance(ctx.userAtaX)) - userXBefore;
+      const otherClaimedX = await balance(otherCtx.userAtaX);
+      assert.ok(earnedX / BigInt(2) - userClaimedX <= BigInt(1));
+      assert.ok(earnedX / BigInt(2) - otherClaimedX <= BigInt(1));
+    });
+  });
+
+  describe("swap with bps tolerance", () => {
+    const swapWithBps = (ctx: AmmContext, amountIn: number, maxSlippageBps: number, xToY: boolean) =>
+      program.methods
+        .swapWithBps(new anchor.BN(amountIn), maxSlippageBps, xToY)
+        .accounts({
+          user: ctx.user.publicKey,
+          //@ts-ignore
+          mintX: ctx.mintX,
+          mintY: ctx.mintY,
+          config: ctx.config,
+          vaultX: ctx.vaultX,
+          vaultY: ctx.vaultY,
+          userX: ctx.userAtaX,
+          userY: ctx.userAtaY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: null,
+          systemProgram: null,
+        })
+        .signers([ctx.user])
+        .rpc();
+
+    it("Bounds fee plus price impact in a thin pool", async () => {
+      const ctx = await setupPool(new anchor.BN(346));
+      await initializePool(ctx);
+      await depositTo(ctx, 1_000, 2_000, 2_000);
+
+      // Spot price is 1:1, so the ideal output for 200 X is 200 Y. The 5% fee and ~9% impact
+      // leave 173, which a 10% tolerance (min 180) rejects and a 15% tolerance (min 170) accepts.
+      const q = await quote(ctx, 200, true);
+      assert.equal(q.amountOut.toNumber(), 173);
+      await expectError(swapWithBps(ctx, 200, 1_000, true), "SlippageExceeded");
+
+      const yBefore = await balance(ctx.userAtaY);
+      await swapWithBps(ctx, 200, 1_500, true);
+      assert.equal((await balance(ctx.userAtaY)) - yBefore, BigInt(173));
+    });
+
+    it("Never passes a tolerance below the pool fee", async () => {
+      const ctx = await setupPool(new anchor.BN(3461));
+      await initializePool(ctx);
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+
+      // The 5% fee alone exceeds a 4.99% tolerance, whatever the depth
+      await expectError(swapWithBps(ctx, 1_000, 499, true), "SlippageExceeded");
+      await expectError(swapWithBps(ctx, 1_000, 10_001, true), "InvalidSlippageTolerance");
+    });
+  });
+
+  describe("reserve floor", () => {
+    const expectViewError = async (promise: Promise<unknown>, code: string) => {
+      try {
+        await promise;
+        assert.fail(`Expected ${code}`);
+      } catch (err: any) {
+        const logs: string[] = err.simulationResponse?.logs ?? err.logs ?? [];
+        assert.include(`${err} ${logs.join("\n")}`, code);
+      }
+    };
+
+    it("Rejects swaps and quotes that would leave less than MIN_RESERVE", async () => {
+      const ctx = await setupPool(new anchor.BN(347));
+      await initializePool(ctx);
+      await depositTo(ctx, 1_000, 2_000, 2_000);
+
+      // 2_111 X is 2_005 after the 5% fee and would pay out 1_001 of the 2_000 Y
+      await expectViewError(quote(ctx, 2_111, true), "ReserveFloorBreached");
+      await expectError(swapIn(ctx, 2_111, 1, true), "ReserveFloorBreached");
+
+      // 2_110 X pays out exactly down to the floor, so a host fee on top of it would breach it
+      const q = await quote(ctx, 2_110, true);
+      assert.equal(q.amountOut.toNumber(), 1_000);
+      const host = (
+        await getOrCreateAssociatedTokenAccount(provider.connection, ctx.user, ctx.mintY, Keypair.generate().publicKey)
+      ).address;
+      await expectError(
+        swapIn(ctx, 2_110, 1_000, true, [{ pubkey: host, isSigner: false, isWritable: true }]),
+        "ReserveFloorBreached"
+      );
+      await swapIn(ctx, 2_110, 1_000, true);
+      assert.equal(await balance(ctx.vaultY), BigInt(1_000));
+
+      // Withdrawals may take both sides below the floor
+      await withdrawFrom(ctx, 1_000);
+      assert.equal(await balance(ctx.userAtaLp), BigInt(0));
+      assert.ok((await balance(ctx.vaultY)) < BigInt(1_000));
+    });
+  });
+
+  describe("mint allowlist", () => {
+    const [globalState] = PublicKey.findProgramAddressSync([Buffer.from("global")], program.programId);
+    const badgeOf = (mint: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("badge"), mint.toBuffer()], program.programId)[0];
+    const setPermissioned = (permissioned: boolean) =>
+      program.methods
+        .setPermissionedCreation(permissioned)
+        .accounts({ admin: globalAdmin.publicKey })
+        .signers([globalAdmin])
+        .rpc();
+    const approve = (signer: Keypair, mint: PublicKey) =>
+      program.methods
+        .approveMint()
+        .accounts({ admin: signer.publicKey, mint })
+        .signers([signer])
+        .rpc();
+
+    after(async () => {
+      await setPermissioned(false);
+    });
+
+    it("Only creates pools over approved mints while creation is permissioned", async () => {
+      const ctx = await setupPool(new anchor.BN(348));
+      const badges = { globalState, creationFeeTreasury: null, badgeX: badgeOf(ctx.mintX), badgeY: badgeOf(ctx.mintY) };
+      const noBadges = { globalState, creationFeeTreasury: null };
+
+      await expectError(approve(ctx.user, ctx.mintX), "InvalidAuthority");
+      await expectError(
+        program.methods.setPermissionedCreation(true).accounts({ admin: ctx.user.publicKey }).signers([ctx.user]).rpc(),
+        "InvalidAuthority"
+      );
+
+      const approveSig = await approve(globalAdmin, ctx.mintX);
+      const badge = await program.account.mintBadge.fetch(badgeOf(ctx.mintX));
+      assert.ok(badge.mint.equals(ctx.mintX));
+      assert.ok(badge.approvedBy.equals(globalAdmin.publicKey));
+
+      const tx = await provider.connection.getTransaction(approveSig, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const events = [...parser.parseLogs(tx.meta.logMessages)];
+      assert.ok(events.some((e) => e.name === "mintApprovedEvent" && e.data.mint.equals(ctx.mintX)));
+
+      // Only X is approved
+      await setPermissioned(true);
+      await expectError(initializePool(ctx, 50, 50, true, null, "", noBadges), "MintNotApproved");
+      await expectError(
+        initializePool(ctx, 50, 50, true, null, "", { ...noBadges, badgeX: badgeOf(ctx.mintX) }),
+        "MintNotApproved"
+      );
+
+      await approve(globalAdmin, ctx.mintY);
+      await initializePool(ctx, 50, 50, true, null, "", badges);
+      assert.ok((await program.account.config.fetch(ctx.config)).mintX.equals(ctx.mintX));
+    });
+
+    it("Revokes badges and ignores them while creation is open", async () => {
+      const ctx = await setupPool(new anchor.BN(3481));
+      const noBadges = { globalState, creationFeeTreasury: null };
+
+      await approve(globalAdmin, ctx.mintX);
+      await approve(globalAdmin, ctx.mintY);
+      await program.methods
+        .revokeMint()
+        .accounts({ admin: globalAdmin.publicKey, mintBadge: badgeOf(ctx.mintY) })
+        .signers([globalAdmin])
+        .rpc();
+      assert.isNull(await provider.connection.getAccountInfo(badgeOf(ctx.mintY)));
+
+      await setPermissioned(true);
+      await expectError(
+        initializePool(ctx, 50, 50, true, null, "", { ...noBadges, badgeX: badgeOf(ctx.mintX) }),
+        "MintNotApproved"
+      );
+
+      // With creation open again, no badges are needed
+      await setPermissioned(false);
+      await initializePool(ctx, 50, 50, true, null, "", noBadges);
+    });
+  });
+
+  describe("burn LP", () => {
+    // Same pool as `ctx`, seen from a second, freshly funded LP
+    const secondLp = async (ctx: AmmContext): Promise<AmmContext> => {
+      const user = Keypair.generate();
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(user.publicKey, anchor.web3.LAMPORTS_PER_SOL),
+        "confirmed"
+      );
+      const userAtaX = (await getOrCreateAssociatedTokenAccount(provider.connection, user, ctx.mintX, user.publicKey)).address;
+      const userAtaY = (await getOrCreateAssociatedTokenAccount(provider.connection, user, ctx.mintY, user.publicKey)).address;
+      await mintTo(provider.connection, ctx.initializer, ctx.mintX, userAtaX, ctx.initializer, 1_000_000);
+      await mintTo(provider.connection, ctx.initializer, ctx.mintY, userAtaY, ctx.initializer, 1_000_000);
+      const userAtaLp = await getAssociatedTokenAddress(ctx.mintLp, user.publicKey);
+      return { ...ctx, user, userAtaX, userAtaY, userAtaLp };
+    };
+
+    const burnLp = (ctx: AmmContext, amount: number) =>
+      program.methods
+        .burnLp(new anchor.BN(amount))
+        .accounts({
+          user: ctx.user.publicKey,
+          //@ts-ignore
+          config: ctx.config,
+          mintLp: ctx.mintLp,
+          userLp: ctx.userAtaLp,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([ctx.user])
+        .rpc();
+
+    it("Donates the burned share to the remaining LPs", async () => {
+      const donor = await setupPool(new anchor.BN(349));
+      await initializePool(donor);
+      await depositTo(donor, 100_000, 100_000, 100_000);
+      const holder = await secondLp(donor);
+      await depositTo(holder, 100_000, 100_000, 100_000);
+
+      // Half of the supply is burned, so the holder's 100_000 LP now owns all the reserves
+      const sig = await burnLp(donor, 100_000);
+      assert.equal(await balance(donor.userAtaLp), BigInt(0));
+      assert.equal(await balance(donor.vaultX), BigInt(200_000));
+
+      const tx = await provider.connection.getTransaction(sig, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const burned = [...parser.parseLogs(tx.meta.logMessages)].find((e) => e.name === "lpBurnedEvent");
+      assert.ok(burned.data.user.equals(donor.user.publicKey));
+      assert.equal(burned.data.amount.toNumber(), 100_000);
+      assert.equal(burned.data.newSupply.toNumber(), 100_000);
+
+      // The donor's position no longer earns on the burned LP
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from("position"), donor.config.toBuffer(), donor.user.publicKey.toBuffer()],
+        program.programId
+      );
+      assert.equal((await program.account.position.fetch(position)).lpBalance.toNumber(), 0);
+
+      const [xBefore, yBefore] = [await balance(holder.userAtaX), await balance(holder.userAtaY)];
+      await withdrawFrom(holder, 50_000);
+      assert.equal((await balance(holder.userAtaX)) - xBefore, BigInt(100_000));
+      assert.equal((await balance(holder.userAtaY)) - yBefore, BigInt(100_000));
+    });
+
+    it("Keeps the minimum LP supply", async () => {
+      const ctx = await setupPool(new anchor.BN(3491));
+      await initializePool(ctx);
+      await depositTo(ctx, 10_000, 10_000, 10_000);
+
+      await expectError(burnLp(ctx, 10_000), "BurnBelowMinimumLiquidity");
+      await expectError(burnLp(ctx, 9_001), "BurnBelowMinimumLiquidity");
+      await expectError(burnLp(ctx, 0), "ZeroLpAmount");
+      await burnLp(ctx, 9_000);
+      assert.equal(await balance(ctx.userAtaLp), BigInt(1_000));
+    });
+  });
+
+  describe("legacy config", () => {
+    // A pool created before the reserved region existed. Its 118 byte config and LP mint are loaded
+    // into the validator from tests/fixtures (see Anchor.toml); the mints are created here from the
+    // fixture keypairs the config points at.
+    const legacyConfigLen = 118;
+    const fixtureKeypair = (name: string) =>
+      Keypair.fromSecretKey(
+        Uint8Array.from(JSON.parse(fs.readFileSync(path.join(__dirname, "fixtures", `${name}.json`), "utf8")))
+      );
+
+    it("Extends a pre-extension config and keeps the pool usable", async () => {
+      const seed = new anchor.BN(313);
+      const initializer = Keypair.generate();
+      const user = Keypair.generate();
+      for (const wallet of [initializer, user]) {
+        await provider.connection.confirmTransaction(
+          await provider.connection.requestAirdrop(wallet.publicKey, 2 * anchor.web3.LAMPORTS_PER_SOL),
+          "confirmed"
+        );
+      }
+
+      const mintX = await createMint(provider.connection, initializer, initializer.publicKey, null, 6, fixtureKeypair("legacy-mint-x"));
+      const mintY = await createMint(provider.connection, initializer, initializer.publicKey, null, 6, fixtureKeypair("legacy-mint-y"));
+      const [config] = PublicKey.findProgramAddressSync(
+        [Buffer.from("config"), seed.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const [mintLp] = PublicKey.findProgramAddressSync([Buffer.from("lp"), config.toBuffer()], program.programId);
+      const ctx: AmmContext = {
+        initializer,
+        user,
+        mintX,
+        mintY,
+        mintLp,
+        config,
+        vaultX: (await getOrCreateAssociatedTokenAccount(provider.connection, initializer, mintX, config, true)).address,
+        vaultY: (await getOrCreateAssociatedTokenAccount(provider.connection, initializer, mintY, config, true)).address,
+        userAtaX: (await getOrCreateAssociatedTokenAccount(provider.connection, user, mintX, user.publicKey)).address,
+        userAtaY: (await getOrCreateAssociatedTokenAccount(provider.connection, user, mintY, user.publicKey)).address,
+        userAtaLp: await getAssociatedTokenAddress(mintLp, user.publicKey),
+        seed,
+        fee: 500,
+      };
+      await mintTo(provider.connection, initializer, mintX, ctx.userAtaX, initializer, 1_000_000);
+      await mintTo(provider.connection, initializer, mintY, ctx.userAtaY, initializer, 1_000_000);
+
+      const legacy = await provider.connection.getAccountInfo(config);
+      assert.equal(legacy.data.length, legacyConfigLen);
+
+      await program.methods
+        .extendConfig(seed)
+        .accounts({
+          payer: user.publicKey,
+          //@ts-ignore
+          config,
+          mintX,
+          mintY,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+
+      // Grown to the current layout, rent exempt at its new size, with the legacy defaults
+      const extended = await provider.connection.getAccountInfo(config);
+      assert.equal(extended.data.length, program.account.config.size);
+      assert.equal(
+        extended.lamports,
+        await provider.connection.getMinimumBalanceForRentExemption(program.account.config.size)
+      );
+      const state = await program.account.config.fetch(config);
+      assert.ok(state.seed.eq(seed));
+      assert.ok(state.mintX.equals(mintX));
+      assert.equal(state.fee, 500);
+      assert.equal(state.weightX, 50);
+      assert.equal(state.weightY, 50);
+      assert.isTrue(state.feeOnInput);
+      assert.equal(state.decimalsX, 6);
+      assert.equal(state.decimalsY, 6);
+
+      await depositTo(ctx, 100_000, 100_000, 100_000);
+      assert.equal(await balance(ctx.userAtaLp), BigInt(100_000));
+
+      const q = await quote(ctx, 10_000, true);
+      const yBefore = await balance(ctx.userAtaY);
+      await swapIn(ctx, 10_000, q.amountOut.toNumber(), true);
+      assert.equal((await balance(ctx.userAtaY)) - yBefore, BigInt(q.amountOut.toString()));
+
+      const xBefore = await balance(ctx.userAtaX);
+      await withdrawFrom(ctx, 100_000);
+      assert.equal(await balance(ctx.userAtaLp), BigInt(0));
+      assert.ok((await balance(ctx.userAtaX)) > xBefore);
+    });
+  });
   });
 
 
@@ -2,3 +2,100 @@ use anchor_lang::prelude::*;
 
 #[constant]
 pub const SEED: &str = "anchor";
+
+/// Highest fee in basis points the marketplace can charge on a sale
+#[constant]
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// Basis points in 100%
+#[constant]
+pub const BPS_DENOMINATOR: u16 = 10_000;
+
+/// Decimals of the reward points mint
+#[constant]
+pub const REWARDS_DECIMALS: u8 = 6;
+
+/// Longest marketplace name in bytes, the most a single PDA seed can hold
+#[constant]
+pub const MAX_MARKETPLACE_NAME_LEN: usize = 32;
+
+/// Most NFTs `bulk_list` and `bulk_delist` process in one instruction,
+/// so a batch fits in a legacy transaction and its compute budget
+#[constant]
+pub const MAX_BULK_ITEMS: u8 = 4;
+
+/// Most listings `purchase_many` buys in one instruction; each purchase creates token accounts
+/// and mints rewards, so a full batch needs an address lookup table and a raised compute limit
+#[constant]
+pub const MAX_PURCHASE_MANY_ITEMS: u8 = 5;
+
+/// Most NFTs a bundle listing can hold, so listing and buying it fits in a legacy transaction
+#[constant]
+pub const MAX_BUNDLE_ITEMS: u8 = 4;
+
+/// Longest buyer protection window a listing can hold its sale proceeds for, 30 days
+#[constant]
+pub const MAX_PROTECTION_WINDOW_SECS: u32 = 30 * 24 * 60 * 60;
+
+/// Number of seller volume tiers a marketplace can discount its fee with
+#[constant]
+pub const FEE_TIER_COUNT: usize = 3;
+
+/// Most recipients the marketplace fee can be split among
+#[constant]
+pub const MAX_FEE_SPLITS: usize = 4;
+
+/// Largest bounty a marketplace can pay for refunding someone else's expired offer, 0.001 SOL
+#[constant]
+pub const MAX_CRANK_FEE_LAMPORTS: u64 = 1_000_000;
+
+/// Largest reward a marketplace can pay from its treasury for cleaning up an expired listing, 0.001 SOL
+#[constant]
+pub const MAX_CRANK_REWARD_LAMPORTS: u64 = 1_000_000;
+
+/// Most installments a purchase can be split into
+#[constant]
+pub const MAX_INSTALLMENTS: u8 = 12;
+
+/// Most recent sales kept in an NFT's provenance history
+#[constant]
+pub const PROVENANCE_CAPACITY: usize = 8;
+
+/// Hourly buckets of a collection's rolling sale volume, covering about a day
+#[constant]
+pub const VOLUME_BUCKET_COUNT: usize = 24;
+
+/// Seconds of sales each collection volume bucket counts
+#[constant]
+pub const VOLUME_BUCKET_SECS: i64 = 60 * 60;
+
+/// Seconds a seller's counter-offer stays open before the original offer terms apply again
+#[constant]
+pub const COUNTER_OFFER_SECS: i64 = 60 * 60;
+
+/// Listing addresses held by each page of a marketplace's listing index
+#[constant]
+pub const LISTING_INDEX_PAGE_SIZE: usize = 16;
+
+/// Number of listing categories; category values range from 0 to LISTING_CATEGORY_COUNT - 1
+#[constant]
+pub const LISTING_CATEGORY_COUNT: u8 = 6;
+
+/// Categories a listing can be filed under, stored on the listing as their `u8` value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ListingCategory {
+    Uncategorized = 0,
+    Art = 1,
+    Gaming = 2,
+    Domains = 3,
+    Music = 4,
+    Collectibles = 5,
+}
+
+impl ListingCategory {
+    /// Whether a raw category value names a known category
+    pub fn is_known(category: u8) -> bool {
+        category < LISTING_CATEGORY_COUNT
+    }
+}
@@ -2,8 +2,8 @@ use anchor_lang::prelude::*;
 
 #[error_code]
 pub enum MarketplaceError {
-  #[msg("Invalid fee percentage")]
-  InvalidFeePercentage,
+  #[msg("Invalid fee basis points")]
+  InvalidFeeBps,
 
   #[msg("Invalid price")]
   InvalidPrice,
@@ -12,5 +12,299 @@ pub enum MarketplaceError {
   ListingNotActive,
 
   #[msg("Math overflow")]
-  MathOverflow
-}
\ No newline at end of file
+  MathOverflow,
+
+  #[msg("Only the seller can modify this listing")]
+  NotListingSeller,
+
+  #[msg("Offer expiry must be in the future")]
+  InvalidOfferExpiry,
+
+  #[msg("Offer has expired")]
+  OfferExpired,
+
+  #[msg("Offer has not expired")]
+  OfferNotExpired,
+
+  #[msg("Signer is not the marketplace admin")]
+  Unauthorized,
+
+  #[msg("Amount exceeds the treasury balance above the rent-exempt minimum")]
+  InsufficientTreasuryBalance,
+
+  #[msg("NFT collection is not approved for listing")]
+  CollectionNotAllowed,
+
+  #[msg("NFT claims a collection that is not verified")]
+  CollectionNotVerified,
+
+  #[msg("Listing expiry must be 0 or in the future")]
+  InvalidListingExpiry,
+
+  #[msg("Listing has expired")]
+  ListingExpired,
+
+  #[msg("Listing has not expired")]
+  ListingNotExpired,
+
+  #[msg("Payment token accounts are required on this marketplace")]
+  MissingPaymentAccounts,
+
+  #[msg("Payment mint does not match the marketplace")]
+  InvalidPaymentMint,
+
+  #[msg("Only available on marketplaces paid in SOL")]
+  NativePaymentOnly,
+
+  #[msg("Auction end time must be in the future")]
+  InvalidAuctionEnd,
+
+  #[msg("Auction has ended")]
+  AuctionEnded,
+
+  #[msg("Auction has not ended")]
+  AuctionNotEnded,
+
+  #[msg("Bid is below the highest bid plus the minimum increment")]
+  BidTooLow,
+
+  #[msg("Account does not match the auction's highest bidder")]
+  InvalidBidder,
+
+  #[msg("Token account for the NFT recipient is required to settle")]
+  MissingSettlementAccount,
+
+  #[msg("Dutch listing has not started")]
+  DutchNotStarted,
+
+  #[msg("Dutch listing needs start price above a non-zero floor and an end after the start")]
+  InvalidDutchSchedule,
+
+  #[msg("Only fixed-price listings can be repriced")]
+  NotFixedPriceListing,
+
+  #[msg("Account is not a marketplace")]
+  InvalidMarketplaceAccount,
+
+  #[msg("Marketplace already uses the current layout")]
+  AlreadyMigrated,
+
+  #[msg("Reward rate must be at most 10000 basis points")]
+  InvalidRewardRate,
+
+  #[msg("Listing token account is required for escrowed listings")]
+  MissingListingTokenAccount,
+
+  #[msg("Seller token account, master edition and metadata program are required for escrowless listings")]
+  MissingEscrowlessAccounts,
+
+  #[msg("Marketplace is paused")]
+  MarketplacePaused,
+
+  #[msg("Quantity must be non-zero, and exactly 1 for an NFT with a master edition")]
+  InvalidQuantity,
+
+  #[msg("Tokens without a master edition must have zero decimals")]
+  NotSemiFungible,
+
+  #[msg("Requested more tokens than remain in the listing")]
+  InsufficientQuantity,
+
+  #[msg("Offers can only be made on single-token listings")]
+  NotSingleTokenListing,
+
+  #[msg("Metadata, master edition, token records and the instructions sysvar are required for programmable NFTs")]
+  MissingProgrammableAccounts,
+
+  #[msg("Programmable NFTs can only be listed, delisted and purchased")]
+  ProgrammableNftUnsupported,
+
+  #[msg("This private listing is reserved for another buyer")]
+  NotAllowedBuyer,
+
+  #[msg("Bulk batches need one group of accounts per item and at most 4 items")]
+  InvalidBatchSize,
+
+  #[msg("Bulk item accounts do not match the expected mint, token accounts, listing or metadata")]
+  InvalidBulkAccount,
+
+  #[msg("Bulk listing and delisting only support escrowed, non-programmable NFTs")]
+  UnsupportedBulkItem,
+
+  #[msg("Fee recipient does not match the marketplace")]
+  InvalidFeeRecipient,
+
+  #[msg("Marketplace name must be at most 32 bytes")]
+  InvalidMarketplaceName,
+
+  #[msg("Listing belongs to another marketplace")]
+  ListingMarketplaceMismatch,
+
+  #[msg("Token is not an NFT held by the seller or a semi-fungible asset")]
+  NotAnNft,
+
+  #[msg("Buyer protection needs a single-token SOL listing and a window of at most 30 days")]
+  InvalidProtectionWindow,
+
+  #[msg("Sale escrow must be passed exactly when the listing has buyer protection")]
+  InvalidSaleEscrow,
+
+  #[msg("Sale is disputed and can only be resolved by the marketplace admin")]
+  SaleDisputed,
+
+  #[msg("Protection window has not ended")]
+  ProtectionWindowOpen,
+
+  #[msg("Protection window has ended or the sale is already disputed")]
+  ProtectionWindowClosed,
+
+  #[msg("Sale is not disputed")]
+  SaleNotDisputed,
+
+  #[msg("Referral share must be at most 1000 basis points")]
+  InvalidReferralBps,
+
+  #[msg("NFT is not a verified member of the offer's collection")]
+  NotInOfferCollection,
+
+  #[msg("Signer does not hold the NFT")]
+  NotNftHolder,
+
+  #[msg("Payment split does not add up to the price")]
+  PaymentSplitMismatch,
+
+  #[msg("Listing start must be before its expiry")]
+  InvalidListingStart,
+
+  #[msg("Sale has not started")]
+  SaleNotStarted,
+
+  #[msg("Fee tiers must ascend in volume, not raise the fee and not exceed the maximum fee")]
+  InvalidFeeTiers,
+
+  #[msg("NFT is non-transferable")]
+  NonTransferableNft,
+
+  #[msg("NFT mint freezes new token accounts by default")]
+  FrozenByDefaultNft,
+
+  #[msg("NFT mints with a transfer hook are not supported")]
+  TransferHookUnsupported,
+
+  #[msg("Sellers cannot buy their own NFT")]
+  SelfPurchase,
+
+  #[msg("Crank fee must be at most 1000000 lamports")]
+  InvalidCrankFee,
+
+  #[msg("Proceeds account must be passed exactly for pull-payment sales in SOL")]
+  InvalidProceedsAccount,
+
+  #[msg("No proceeds to claim")]
+  NoProceedsToClaim,
+
+  #[msg("Secondary price must be non-zero, in another currency and on a fixed-price, unprotected listing")]
+  InvalidSecondaryPrice,
+
+  #[msg("Listing has no secondary price")]
+  NoSecondaryPrice,
+
+  #[msg("Crank reward must be at most 1000000 lamports")]
+  InvalidCrankReward,
+
+  #[msg("NFT metadata changed since it was listed")]
+  MetadataChanged,
+
+  #[msg("Metadata account does not belong to the NFT")]
+  InvalidMetadataAccount,
+
+  #[msg("Batch purchases need one group of accounts per listing and at most 5 listings")]
+  InvalidPurchaseBatchSize,
+
+  #[msg("Batch purchase would spend more than the buyer's maximum total")]
+  MaxTotalExceeded,
+
+  #[msg("Batch purchases only support escrowed, non-programmable listings without buyer protection on marketplaces paying sellers directly in SOL")]
+  UnsupportedPurchaseItem,
+
+  #[msg("Bundles need one group of accounts per NFT and between 1 and 4 NFTs")]
+  InvalidBundleSize,
+
+  #[msg("Bundle accounts do not match the bundle's mints, vaults or token accounts")]
+  InvalidBundleAccount,
+
+  #[msg("Bundles only support escrowed, non-programmable NFTs on marketplaces paying sellers directly in SOL")]
+  UnsupportedBundle,
+
+  #[msg("Installment plans need 2 to 12 payments and a non-zero interval")]
+  InvalidInstallmentSchedule,
+
+  #[msg("Installment forfeit share must be at most 10000 basis points")]
+  InvalidInstallmentTerms,
+
+  #[msg("Installment purchases only support escrowed, non-programmable listings without buyer protection on marketplaces paying sellers directly in SOL")]
+  UnsupportedInstallment,
+
+  #[msg("The installment plan is not past its grace period")]
+  InstallmentNotDefaulted,
+
+  #[msg("Buy-now price must be at least the start and reserve prices")]
+  InvalidBuyNowPrice,
+
+  #[msg("Buy-now is disabled or bidding already reached the buy-now price")]
+  BuyNowUnavailable,
+
+  #[msg("Collection stats do not belong to the listing's marketplace and collection")]
+  InvalidCollectionStats,
+
+  #[msg("Holder discount must be at most the maximum fee and needs a non-zero threshold")]
+  InvalidHolderDiscount,
+
+  #[msg("Unknown listing category")]
+  InvalidCategory,
+
+  #[msg("The offer is not the listing's current best offer")]
+  NotBestOffer,
+
+  #[msg("Counter-offer must be non-zero and differ from the offered amount")]
+  InvalidCounterOffer,
+
+  #[msg("The offer has no open counter-offer")]
+  NoCounterOffer,
+
+  #[msg("The wallet is blacklisted on this marketplace")]
+  WalletBlacklisted,
+
+  #[msg("Fee splits must name distinct recipients with shares summing to 100%")]
+  InvalidFeeSplits,
+
+  #[msg("Listing would run longer than the marketplace allows")]
+  ListingDurationTooLong,
+
+  #[msg("Seller account does not match the listing's seller")]
+  ListingSellerMismatch,
+
+  #[msg("Only the marketplace admin can force-delist a listing")]
+  NotDelistAdmin,
+
+  #[msg("Listing index accounts do not match the listing's index position")]
+  InvalidListingIndex,
+
+  #[msg("Account is still actionable or not in a state garbage collection closes")]
+  NotCollectable,
+
+  #[msg("Price is outside the marketplace's minimum and maximum prices")]
+  PriceOutOfBounds,
+
+  #[msg("Minimum price must not exceed the maximum price")]
+  InvalidPriceBounds,
+
+  #[msg("Fill exceeds the tokens the offer still buys")]
+  OfferOverfilled,
+
+  #[msg("Listing does not sell the offer's mint")]
+  OfferMintMismatch,
+
+  #[msg("Only the buyer can cancel an offer")]
+  NotOfferBuyer
+}
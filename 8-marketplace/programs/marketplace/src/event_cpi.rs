@@ -0,0 +1,44 @@
+use anchor_lang::{
+    event::EVENT_IX_TAG_LE,
+    Event,
+    prelude::*,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program::invoke_signed,
+    },
+};
+
+/// Seed of the PDA signing the program's event self-CPIs, as added by `#[event_cpi]`
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+
+/// Emit an event as a self-CPI, the way `emit_cpi!` does
+/// - Instruction methods hold their accounts but not the context `emit_cpi!` expands against
+/// - The event stays in the transaction's inner instructions when its logs are truncated
+///
+/// # Arguments
+/// * `event_authority` - The `event_authority` account added by `#[event_cpi]`
+/// * `bump` - Its PDA bump, as validated into the context's bumps
+/// * `event` - The event to emit
+///
+/// # Returns
+/// * `Result<()>` - Success or error
+pub fn emit_cpi<E: Event>(event_authority: &AccountInfo, bump: u8, event: &E) -> Result<()> {
+    let data = EVENT_IX_TAG_LE
+        .iter()
+        .copied()
+        .chain(event.data())
+        .collect::<Vec<u8>>();
+    let ix = Instruction::new_with_bytes(
+        crate::ID,
+        &data,
+        vec![AccountMeta::new_readonly(event_authority.key(), true)],
+    );
+
+    invoke_signed(
+        &ix,
+        std::slice::from_ref(event_authority),
+        &[&[EVENT_AUTHORITY_SEED, &[bump]]],
+    )
+    .map_err(Into::into)
+}
+
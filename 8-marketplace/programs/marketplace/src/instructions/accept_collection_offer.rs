@@ -0,0 +1,258 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{Metadata, MetadataAccount},
+    token::{transfer_checked, Mint, Token, TokenAccount, TransferChecked},
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    programmable::is_programmable,
+    state::{Blacklist, CollectionConfig, CollectionOffer, Marketplace, Provenance, SaleRecord},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptCollectionOffer<'info> {
+    /// The holder selling an NFT of the collection
+    /// - Must sign the NFT transfer
+    /// - Receives the offered price minus fees
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// The buyer who made the offer
+    /// - Validated against the offer's buyer field
+    /// - Receives the NFT, and the offer account rent once the offer is filled
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+
+    /// The NFT mint account being sold
+    pub nft: Box<Account<'info, Mint>>,
+
+    /// The metadata account for the NFT
+    /// - Must be the metadata PDA derived from the NFT mint
+    /// - Must carry the offer's collection, verified
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            nft.key().as_ref(),
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub metadata: Box<Account<'info, MetadataAccount>>,
+
+    /// The seller's token account holding the NFT
+    #[account(
+        mut,
+        associated_token::mint = nft,
+        associated_token::authority = seller,
+    )]
+    pub seller_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The buyer's token account to receive the NFT
+    /// - Created by the seller if the buyer does not have one yet
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = nft,
+        associated_token::authority = buyer
+    )]
+    pub buyer_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The collection offer being accepted
+    /// - Uses PDA with marketplace, collection mint and buyer as seeds
+    /// - Pays out one NFT's price per acceptance and is closed to the buyer once filled
+    #[account(
+        mut,
+        seeds = [
+            b"coll_offer",
+            marketplace.key().as_ref(),
+            collection_offer.collection_mint.as_ref(),
+            buyer.key().as_ref(),
+        ],
+        bump = collection_offer.bump,
+        has_one = buyer,
+        has_one = marketplace,
+    )]
+    pub collection_offer: Box<Account<'info, CollectionOffer>>,
+
+    /// The collection config approving the offer's collection
+    /// - Uses PDA with marketplace and collection mint as seeds
+    /// - Not required to exist when the marketplace has open listings
+    ///
+    /// CHECK: Address is pinned by seeds; the account is loaded in `verify_nft`
+    #[account(
+        seeds = [
+            b"collection",
+            marketplace.key().as_ref(),
+            collection_offer.collection_mint.as_ref(),
+        ],
+        bump,
+    )]
+    pub collection_config: UncheckedAccount<'info>,
+
+    /// The marketplace state account
+    /// - Contains fee percentage for calculations
+    /// - Must not be paused
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        constraint = !marketplace.paused @ MarketplaceError::MarketplacePaused,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The account receiving marketplace fees
+    /// - Must be the fee recipient configured on the marketplace
+    #[account(
+        mut,
+        address = marketplace.fee_recipient @ MarketplaceError::InvalidFeeRecipient,
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+
+    /// The NFT's sale history
+    /// - Created on its first sale, paid by the seller accepting the offer
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + Provenance::INIT_SPACE,
+        seeds = [b"provenance", marketplace.key().as_ref(), nft.key().as_ref()],
+        bump,
+    )]
+    pub provenance: Box<Account<'info, Provenance>>,
+
+    /// The seller's blacklist entry, which must not exist
+    /// - Uses PDA with marketplace and seller as seeds
+    ///
+    /// CHECK: Address is pinned by seeds; only its existence is checked
+    #[account(
+        seeds = [b"blacklist", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump,
+        constraint = !Blacklist::is_blacklisted(&seller_blacklist) @ MarketplaceError::WalletBlacklisted,
+    )]
+    pub seller_blacklist: UncheckedAccount<'info>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub metadata_program: Program<'info, Metadata>,
+}
+
+impl<'info> AcceptCollectionOffer<'info> {
+    /// Sell one NFT of the collection into the offer
+    /// - Closes the offer to the buyer once its last NFT is bought
+    ///
+    /// # Arguments
+    /// * `bumps` - PDA bump values for the provenance account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn accept_collection_offer(&mut self, bumps: AcceptCollectionOfferBumps) -> Result<()> {
+        require!(
+            !self.collection_offer.is_expired(Clock::get()?.unix_timestamp),
+            MarketplaceError::OfferExpired
+        );
+        require_keys_neq!(self.seller.key(), self.buyer.key(), MarketplaceError::SelfPurchase);
+        // The offer buys one-of-one NFTs, moved with a plain SPL transfer
+        require!(
+            self.nft.decimals == 0 && self.nft.supply == 1,
+            MarketplaceError::NotAnNft
+        );
+        require!(!is_programmable(&self.metadata), MarketplaceError::ProgrammableNftUnsupported);
+
+        // Same collection rules as listing, and the collection must be the offer's
+        let collection = CollectionConfig::verify_nft(
+            &self.marketplace,
+            &self.metadata,
+            &self.collection_offer.collection_mint,
+            &self.collection_config,
+        )?;
+        require!(
+            collection == Some(self.collection_offer.collection_mint),
+            MarketplaceError::NotInOfferCollection
+        );
+
+        self.pay_from_escrow()?;
+        self.transfer_nft()?;
+        if self.provenance.is_uninitialized() {
+            self.provenance.set_inner(Provenance::new(
+                self.marketplace.key(),
+                self.nft.key(),
+                bumps.provenance,
+            ));
+        }
+        self.provenance.record_sale(SaleRecord {
+            price: self.collection_offer.price,
+            buyer: self.buyer.key(),
+            seller: self.seller.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        self.collection_offer.quantity -= 1;
+        emit_cpi(&self.event_authority, bumps.event_authority, &CollectionOfferAcceptedEvent {
+            collection_offer: self.collection_offer.key(),
+            nft: self.nft.key(),
+            seller: self.seller.key(),
+            buyer: self.buyer.key(),
+            price: self.collection_offer.price,
+            remaining: self.collection_offer.quantity,
+        })?;
+
+        if self.collection_offer.quantity == 0 {
+            self.collection_offer.close(self.buyer.to_account_info())?;
+        }
+
+        Ok(())
+    }
+
+    /// Split one NFT's price between seller and fee recipient
+    /// - Fee is calculated exactly like a regular purchase
+    /// - The offer account is program owned, so lamports are moved directly
+    fn pay_from_escrow(&mut self) -> Result<()> {
+        let price = self.collection_offer.price;
+        let fee_lamports = self.marketplace.fee_for(price)?;
+        let seller_lamports = price
+            .checked_sub(fee_lamports)
+            .ok_or(MarketplaceError::MathOverflow)?;
+
+        self.collection_offer.sub_lamports(price)?;
+        self.fee_recipient.add_lamports(fee_lamports)?;
+        self.seller.add_lamports(seller_lamports)?;
+        self.marketplace.record_sale(price, fee_lamports);
+
+        Ok(())
+    }
+
+    /// Transfer the NFT from the seller to the buyer
+    fn transfer_nft(&self) -> Result<()> {
+        let cpi_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.seller_token_account.to_account_info(),
+                mint: self.nft.to_account_info(),
+                to: self.buyer_token_account.to_account_info(),
+                authority: self.seller.to_account_info(),
+            },
+        );
+        transfer_checked(cpi_ctx, 1, self.nft.decimals)
+    }
+}
+
+#[event]
+pub struct CollectionOfferAcceptedEvent {
+    pub collection_offer: Pubkey,
+    pub nft: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub remaining: u64,
+}
@@ -0,0 +1,317 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{close_account, transfer_checked, CloseAccount, Mint, Token, TokenAccount, TransferChecked},
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{
+        Listing, ListingIndex, Marketplace, Offer, Provenance, SaleRecord, SellerStats,
+    },
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptCounter<'info> {
+    /// The buyer accepting the seller's counter-offer
+    /// - Validated against the offer's buyer field
+    /// - Tops up or is refunded the difference to the offered amount
+    /// - Receives the NFT and the offer account rent
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The seller who listed the NFT
+    /// - Validated against the seller stored in the listing
+    /// - Receives the counter amount minus fees and the listing rent
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+
+    /// The NFT mint account being sold
+    pub nft: Box<Account<'info, Mint>>,
+
+    /// The listing account being fulfilled
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    /// - Closed and rent refunded to seller after the sale
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            listing.seller.as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = seller @ MarketplaceError::NotListingSeller,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+        close = seller
+    )]
+    pub listing: Box<Account<'info, Listing>>,
+
+    /// Token account holding the NFT during listing
+    /// - Owned by the listing PDA
+    /// - Emptied and closed to the seller after the sale
+    #[account(
+        mut,
+        associated_token::mint = nft,
+        associated_token::authority = listing,
+    )]
+    pub listing_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The buyer's token account to receive the NFT
+    /// - Created by the buyer if it does not exist yet
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = nft,
+        associated_token::authority = buyer
+    )]
+    pub buyer_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The countered offer
+    /// - Uses PDA with listing and buyer as seeds
+    /// - Its escrow is adjusted to the counter amount, paid out and closed to the buyer
+    #[account(
+        mut,
+        seeds = [b"offer", listing.key().as_ref(), buyer.key().as_ref()],
+        bump = offer.bump,
+        has_one = buyer,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+        close = buyer
+    )]
+    pub offer: Account<'info, Offer>,
+
+    /// The marketplace state account
+    /// - Contains fee percentage for calculations
+    /// - Must not be paused
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        constraint = !marketplace.paused @ MarketplaceError::MarketplacePaused,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The seller's statistics account
+    /// - Sets the seller's fee tier
+    /// - Counts the sale and the closed listing
+    #[account(
+        mut,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump = seller_stats.bump,
+    )]
+    pub seller_stats: Box<Account<'info, SellerStats>>,
+
+    /// The account receiving marketplace fees
+    /// - Must be the fee recipient configured on the marketplace
+    #[account(
+        mut,
+        address = marketplace.fee_recipient @ MarketplaceError::InvalidFeeRecipient,
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+
+    /// The NFT's sale history
+    /// - Created on its first sale, paid by the buyer
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + Provenance::INIT_SPACE,
+        seeds = [b"provenance", marketplace.key().as_ref(), nft.key().as_ref()],
+        bump,
+    )]
+    pub provenance: Box<Account<'info, Provenance>>,
+
+    /// The listing index page holding the listing's entry
+    /// - Only required for listings in the marketplace's listing index
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing index page holding the index's last entry
+    /// - Only required when it is another page than `listing_index`
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub last_listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing at the index's last position, moved into the freed position
+    /// - Only required when it is another listing than the one closing
+    ///
+    /// CHECK: Matched against the index entry in `ListingIndex::unindex`
+    #[account(mut)]
+    pub moved_listing: Option<UncheckedAccount<'info>>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> AcceptCounter<'info> {
+    /// Accept the seller's open counter-offer and execute the sale at the counter amount
+    /// - The escrow is topped up from, or partially refunded to, the buyer first
+    ///
+    /// # Arguments
+    /// * `bumps` - PDA bump values for the provenance account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn accept_counter(&mut self, bumps: AcceptCounterBumps) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let counter_amount = self
+            .offer
+            .open_counter(now)
+            .ok_or(MarketplaceError::NoCounterOffer)?;
+        // Validate the listing can still be sold through the offer
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+        require!(self.listing.quantity == 1, MarketplaceError::NotSingleTokenListing);
+        require!(!self.listing.programmable, MarketplaceError::ProgrammableNftUnsupported);
+        require!(
+            self.listing.can_buy(&self.buyer.key()),
+            MarketplaceError::NotAllowedBuyer
+        );
+        require!(!self.offer.is_expired(now), MarketplaceError::OfferExpired);
+        require!(self.listing.has_started(now), MarketplaceError::SaleNotStarted);
+        require_keys_neq!(self.buyer.key(), self.listing.seller, MarketplaceError::SelfPurchase);
+
+        let offer_amount = self.offer.amount;
+        self.adjust_escrow(counter_amount)?;
+        self.pay_from_escrow()?;
+        self.transfer_nft()?;
+        if self.provenance.is_uninitialized() {
+            self.provenance.set_inner(Provenance::new(
+                self.marketplace.key(),
+                self.nft.key(),
+                bumps.provenance,
+            ));
+        }
+        self.provenance.record_sale(SaleRecord {
+            price: counter_amount,
+            buyer: self.buyer.key(),
+            seller: self.seller.key(),
+            timestamp: now,
+        });
+
+        // Mark listing as inactive (though it will be closed anyway)
+        self.listing.is_active = false;
+        self.marketplace.listing_closed();
+        self.seller_stats.listing_closed();
+        ListingIndex::unindex(
+            &mut self.marketplace,
+            &mut self.listing,
+            self.listing_index.as_deref_mut(),
+            self.last_listing_index.as_deref_mut(),
+            self.moved_listing.as_ref(),
+        )?;
+
+        emit_cpi(&self.event_authority, bumps.event_authority, &CounterOfferAcceptedEvent {
+            offer: self.offer.key(),
+            listing: self.listing.key(),
+            seller: self.seller.key(),
+            buyer: self.buyer.key(),
+            offer_amount,
+            amount: counter_amount,
+        })?;
+
+        Ok(())
+    }
+
+    /// Bring the escrow from the offered amount to the counter amount
+    /// - A higher counter is topped up by the buyer, a lower one refunds the difference
+    fn adjust_escrow(&mut self, counter_amount: u64) -> Result<()> {
+        let offer_amount = self.offer.amount;
+        if counter_amount > offer_amount {
+            let cpi_ctx = CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.buyer.to_account_info(),
+                    to: self.offer.to_account_info(),
+                },
+            );
+            transfer(cpi_ctx, counter_amount - offer_amount)?;
+        } else {
+            let refund = offer_amount - counter_amount;
+            self.offer.sub_lamports(refund)?;
+            self.buyer.add_lamports(refund)?;
+        }
+        self.offer.amount = counter_amount;
+
+        Ok(())
+    }
+
+    /// Split the escrowed counter amount between seller and fee recipient
+    /// - Split exactly like a regular purchase, at the seller's fee tier
+    /// - The offer account is program owned, so lamports are moved directly
+    fn pay_from_escrow(&mut self) -> Result<()> {
+        let amount = self.offer.amount;
+        let fee_bps = self.marketplace.fee_bps_for_volume(self.seller_stats.volume);
+        let split = self.marketplace.split_payment(amount, fee_bps, false)?;
+
+        self.offer.sub_lamports(amount)?;
+        self.fee_recipient.add_lamports(split.marketplace_fee)?;
+        self.seller.add_lamports(split.seller_proceeds)?;
+        self.marketplace.record_sale(amount, split.marketplace_fee);
+        self.seller_stats.record_sale(amount);
+
+        Ok(())
+    }
+
+    /// Transfer the NFT from the listing vault to the buyer and close the vault
+    fn transfer_nft(&mut self) -> Result<()> {
+        // Create seeds for PDA signing
+        let marketplace = self.marketplace.key();
+        let seller = self.seller.key();
+        let nft = self.nft.key();
+        let nonce = self.listing.nonce.to_le_bytes();
+        let listing_seeds: &[&[u8]] = &[
+            b"listing",
+            marketplace.as_ref(),
+            seller.as_ref(),
+            nft.as_ref(),
+            &nonce,
+            &[self.listing.bump],
+        ];
+        let signer = &[listing_seeds];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.listing_token_account.to_account_info(),
+                mint: self.nft.to_account_info(),
+                to: self.buyer_token_account.to_account_info(),
+                authority: self.listing.to_account_info(),
+            },
+            signer,
+        );
+        transfer_checked(cpi_ctx, 1, self.nft.decimals)?;
+
+        // Return the empty vault's rent to the seller
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.listing_token_account.to_account_info(),
+                destination: self.seller.to_account_info(),
+                authority: self.listing.to_account_info(),
+            },
+            signer,
+        );
+        close_account(cpi_ctx)
+    }
+}
+
+#[event]
+pub struct CounterOfferAcceptedEvent {
+    pub offer: Pubkey,
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub offer_amount: u64,
+    pub amount: u64,
+}
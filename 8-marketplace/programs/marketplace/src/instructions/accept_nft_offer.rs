@@ -0,0 +1,198 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{transfer_checked, Mint, Token, TokenAccount, TransferChecked},
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Blacklist, Marketplace, NftOffer, Provenance, SaleRecord},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptNftOffer<'info> {
+    /// Whoever holds the NFT when the offer is accepted
+    /// - Must sign the NFT transfer
+    /// - Receives the offer amount minus fees
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    /// The buyer who made the offer
+    /// - Validated against the offer's buyer field
+    /// - Receives the NFT and the offer account rent
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+
+    /// The NFT mint account being sold
+    pub nft: Box<Account<'info, Mint>>,
+
+    /// The holder's token account, proving ownership at execution
+    #[account(
+        mut,
+        associated_token::mint = nft,
+        associated_token::authority = holder,
+        constraint = holder_token_account.amount == 1 @ MarketplaceError::NotNftHolder,
+    )]
+    pub holder_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The buyer's token account to receive the NFT
+    /// - Created by the holder if the buyer does not have one yet
+    #[account(
+        init_if_needed,
+        payer = holder,
+        associated_token::mint = nft,
+        associated_token::authority = buyer
+    )]
+    pub buyer_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The offer being accepted
+    /// - Uses PDA with marketplace, NFT mint and buyer as seeds
+    /// - Pays out its escrow and is closed to the buyer
+    #[account(
+        mut,
+        seeds = [
+            b"nft_offer",
+            marketplace.key().as_ref(),
+            nft.key().as_ref(),
+            buyer.key().as_ref(),
+        ],
+        bump = nft_offer.bump,
+        has_one = buyer,
+        close = buyer
+    )]
+    pub nft_offer: Account<'info, NftOffer>,
+
+    /// The marketplace state account
+    /// - Contains fee percentage for calculations
+    /// - Must not be paused
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        constraint = !marketplace.paused @ MarketplaceError::MarketplacePaused,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The account receiving marketplace fees
+    /// - Must be the fee recipient configured on the marketplace
+    #[account(
+        mut,
+        address = marketplace.fee_recipient @ MarketplaceError::InvalidFeeRecipient,
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+
+    /// The NFT's sale history
+    /// - Created on its first sale, paid by the holder accepting the offer
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + Provenance::INIT_SPACE,
+        seeds = [b"provenance", marketplace.key().as_ref(), nft.key().as_ref()],
+        bump,
+    )]
+    pub provenance: Box<Account<'info, Provenance>>,
+
+    /// The holder's blacklist entry, which must not exist
+    /// - Uses PDA with marketplace and holder as seeds
+    ///
+    /// CHECK: Address is pinned by seeds; only its existence is checked
+    #[account(
+        seeds = [b"blacklist", marketplace.key().as_ref(), holder.key().as_ref()],
+        bump,
+        constraint = !Blacklist::is_blacklisted(&holder_blacklist) @ MarketplaceError::WalletBlacklisted,
+    )]
+    pub holder_blacklist: UncheckedAccount<'info>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> AcceptNftOffer<'info> {
+    /// Pay the holder and fee recipient from escrow and send the NFT to the buyer
+    /// - Frozen NFTs, such as escrowless listings and pNFTs, cannot be transferred and fail here
+    ///
+    /// # Arguments
+    /// * `bumps` - PDA bump values for the provenance account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn accept_nft_offer(&mut self, bumps: AcceptNftOfferBumps) -> Result<()> {
+        require!(
+            !self.nft_offer.is_expired(Clock::get()?.unix_timestamp),
+            MarketplaceError::OfferExpired
+        );
+        require_keys_neq!(self.holder.key(), self.buyer.key(), MarketplaceError::SelfPurchase);
+
+        self.pay_from_escrow()?;
+
+        let cpi_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.holder_token_account.to_account_info(),
+                mint: self.nft.to_account_info(),
+                to: self.buyer_token_account.to_account_info(),
+                authority: self.holder.to_account_info(),
+            },
+        );
+        transfer_checked(cpi_ctx, 1, self.nft.decimals)?;
+        if self.provenance.is_uninitialized() {
+            self.provenance.set_inner(Provenance::new(
+                self.marketplace.key(),
+                self.nft.key(),
+                bumps.provenance,
+            ));
+        }
+        self.provenance.record_sale(SaleRecord {
+            price: self.nft_offer.amount,
+            buyer: self.buyer.key(),
+            seller: self.holder.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        emit_cpi(&self.event_authority, bumps.event_authority, &NftOfferAcceptedEvent {
+            nft_offer: self.nft_offer.key(),
+            nft: self.nft.key(),
+            holder: self.holder.key(),
+            buyer: self.buyer.key(),
+            amount: self.nft_offer.amount,
+        })?;
+
+        Ok(())
+    }
+
+    /// Split the escrowed offer amount between holder and fee recipient
+    /// - Fee is calculated exactly like a regular purchase
+    /// - The offer account is program owned, so lamports are moved directly
+    fn pay_from_escrow(&mut self) -> Result<()> {
+        let amount = self.nft_offer.amount;
+        let fee_lamports = self.marketplace.fee_for(amount)?;
+        let holder_lamports = amount
+            .checked_sub(fee_lamports)
+            .ok_or(MarketplaceError::MathOverflow)?;
+
+        self.nft_offer.sub_lamports(amount)?;
+        self.fee_recipient.add_lamports(fee_lamports)?;
+        self.holder.add_lamports(holder_lamports)?;
+        self.marketplace.record_sale(amount, fee_lamports);
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct NftOfferAcceptedEvent {
+    pub nft_offer: Pubkey,
+    pub nft: Pubkey,
+    pub holder: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+}
@@ -0,0 +1,435 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{close_account, transfer_checked, CloseAccount, Mint, Token, TokenAccount, TransferChecked},
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{
+        Blacklist, Listing, ListingIndex, Marketplace, Offer, Provenance, SaleRecord, SellerStats,
+    },
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptOffer<'info> {
+    /// The seller who listed the NFT
+    /// - Must sign and match the seller stored in the listing
+    /// - Receives the offer amount minus fees and the listing rent
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// The buyer who made the offer
+    /// - Validated against the offer's buyer field
+    /// - Receives the NFT and the offer account rent
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+
+    /// The NFT mint account being sold
+    pub nft: Box<Account<'info, Mint>>,
+
+    /// The listing account being fulfilled
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    /// - Closed and rent refunded to seller after the sale
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            listing.seller.as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = seller @ MarketplaceError::NotListingSeller,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+        close = seller
+    )]
+    pub listing: Box<Account<'info, Listing>>,
+
+    /// Token account holding the NFT during listing
+    /// - Owned by the listing PDA
+    /// - Emptied and closed to the seller after the sale
+    #[account(
+        mut,
+        associated_token::mint = nft,
+        associated_token::authority = listing,
+    )]
+    pub listing_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The buyer's token account to receive the NFT
+    /// - Created by the seller if the buyer does not have one yet
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = nft,
+        associated_token::authority = buyer
+    )]
+    pub buyer_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The offer being accepted
+    /// - Uses PDA with listing and buyer as seeds
+    /// - Pays out its escrow and is closed to the buyer
+    #[account(
+        mut,
+        seeds = [b"offer", listing.key().as_ref(), buyer.key().as_ref()],
+        bump = offer.bump,
+        has_one = buyer,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+        close = buyer
+    )]
+    pub offer: Account<'info, Offer>,
+
+    /// The marketplace state account
+    /// - Contains fee percentage for calculations
+    /// - Must not be paused
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        constraint = !marketplace.paused @ MarketplaceError::MarketplacePaused,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The seller's statistics account
+    /// - Sets the seller's fee tier
+    /// - Counts the sale and the closed listing
+    #[account(
+        mut,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump = seller_stats.bump,
+    )]
+    pub seller_stats: Box<Account<'info, SellerStats>>,
+
+    /// The account receiving marketplace fees
+    /// - Must be the fee recipient configured on the marketplace
+    #[account(
+        mut,
+        address = marketplace.fee_recipient @ MarketplaceError::InvalidFeeRecipient,
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+
+    /// The NFT's sale history
+    /// - Created on its first sale, paid by the seller accepting the offer
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + Provenance::INIT_SPACE,
+        seeds = [b"provenance", marketplace.key().as_ref(), nft.key().as_ref()],
+        bump,
+    )]
+    pub provenance: Box<Account<'info, Provenance>>,
+
+    /// The mint a token offer is escrowed in
+    /// - Only required when accepting a token offer
+    pub payment_mint: Option<Box<Account<'info, Mint>>>,
+
+    /// Token account holding the offered tokens
+    /// - Only required for token offers; emptied and closed to the buyer
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = offer,
+    )]
+    pub offer_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// The seller's payment token account
+    /// - Receives a token offer minus fees
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = payment_mint,
+        associated_token::authority = seller,
+    )]
+    pub seller_payment_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// The fee recipient's payment token account
+    /// - Receives the fee of a token offer
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = payment_mint,
+        associated_token::authority = fee_recipient,
+    )]
+    pub fee_recipient_payment_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// The seller's blacklist entry, which must not exist
+    /// - Uses PDA with marketplace and seller as seeds
+    ///
+    /// CHECK: Address is pinned by seeds; only its existence is checked
+    #[account(
+        seeds = [b"blacklist", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump,
+        constraint = !Blacklist::is_blacklisted(&seller_blacklist) @ MarketplaceError::WalletBlacklisted,
+    )]
+    pub seller_blacklist: UncheckedAccount<'info>,
+
+    /// The listing index page holding the listing's entry
+    /// - Only required for listings in the marketplace's listing index
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing index page holding the index's last entry
+    /// - Only required when it is another page than `listing_index`
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub last_listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing at the index's last position, moved into the freed position
+    /// - Only required when it is another listing than the one closing
+    ///
+    /// CHECK: Matched against the index entry in `ListingIndex::unindex`
+    #[account(mut)]
+    pub moved_listing: Option<UncheckedAccount<'info>>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> AcceptOffer<'info> {
+    /// Pay the seller and fee recipient from escrow and send the NFT to the buyer
+    ///
+    /// # Arguments
+    /// * `bumps` - PDA bump values for the provenance account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn accept_offer(&mut self, bumps: AcceptOfferBumps) -> Result<()> {
+        // Validate listing is active and the offer is still open
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+        // An offer buys the whole listing, which is a single NFT
+        require!(self.listing.quantity == 1, MarketplaceError::NotSingleTokenListing);
+        // Offers move the NFT with a plain SPL transfer
+        require!(!self.listing.programmable, MarketplaceError::ProgrammableNftUnsupported);
+        // The listing may have been made private after the offer was placed
+        require!(
+            self.listing.can_buy(&self.buyer.key()),
+            MarketplaceError::NotAllowedBuyer
+        );
+        require!(
+            !self.offer.is_expired(Clock::get()?.unix_timestamp),
+            MarketplaceError::OfferExpired
+        );
+        // Offers cannot buy a scheduled listing before its sale opens
+        require!(
+            self.listing.has_started(Clock::get()?.unix_timestamp),
+            MarketplaceError::SaleNotStarted
+        );
+        require_keys_neq!(self.buyer.key(), self.listing.seller, MarketplaceError::SelfPurchase);
+
+        let volume = self.pay_from_escrow()?;
+        self.transfer_nft()?;
+        if self.provenance.is_uninitialized() {
+            self.provenance.set_inner(Provenance::new(
+                self.marketplace.key(),
+                self.nft.key(),
+                bumps.provenance,
+            ));
+        }
+        self.provenance.record_sale(SaleRecord {
+            price: volume,
+            buyer: self.buyer.key(),
+            seller: self.seller.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        // Mark listing as inactive (though it will be closed anyway)
+        self.listing.is_active = false;
+        self.marketplace.listing_closed();
+        self.seller_stats.listing_closed();
+        ListingIndex::unindex(
+            &mut self.marketplace,
+            &mut self.listing,
+            self.listing_index.as_deref_mut(),
+            self.last_listing_index.as_deref_mut(),
+            self.moved_listing.as_ref(),
+        )?;
+
+        emit_cpi(&self.event_authority, bumps.event_authority, &OfferAcceptedEvent {
+            offer: self.offer.key(),
+            listing: self.listing.key(),
+            seller: self.seller.key(),
+            buyer: self.buyer.key(),
+            amount: self.offer.amount,
+            payment_mint: self.offer.payment_mint,
+        })?;
+
+        Ok(())
+    }
+
+    /// Accept the listing's best offer, whichever buyer made it
+    /// - The offer passed must still be the tracked best offer for the tracked amount,
+    ///   so an offer replaced or cancelled since the seller looked is not accepted instead
+    ///
+    /// # Arguments
+    /// * `bumps` - PDA bump values for the provenance account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn accept_best_offer(&mut self, bumps: AcceptOfferBumps) -> Result<()> {
+        require!(
+            self.listing.is_best_offer(&self.offer.key(), self.offer.amount),
+            MarketplaceError::NotBestOffer
+        );
+
+        self.accept_offer(bumps)
+    }
+
+    /// Split the escrowed offer amount between seller and fee recipient
+    /// - Split exactly like a regular purchase, at the seller's fee tier
+    /// - The offer account is program owned, so lamports are moved directly
+    /// - Token offers are paid out of the offer's vault, which is then closed
+    ///
+    /// # Returns
+    /// * `Result<u64>` - The sale volume, 0 for offers outside the marketplace currency
+    fn pay_from_escrow(&mut self) -> Result<u64> {
+        let amount = self.offer.amount;
+        let fee_bps = self.marketplace.fee_bps_for_volume(self.seller_stats.volume);
+        let split = self.marketplace.split_payment(amount, fee_bps, false)?;
+        let (fee, seller_amount) = (split.marketplace_fee, split.seller_proceeds);
+
+        match self.offer.payment_mint {
+            Some(payment_mint) => self.pay_tokens(payment_mint, fee, seller_amount)?,
+            None => {
+                self.offer.sub_lamports(amount)?;
+                self.fee_recipient.add_lamports(fee)?;
+                self.seller.add_lamports(seller_amount)?;
+            }
+        }
+
+        // Volume and fees are kept in the marketplace currency, like secondary price sales
+        if self.offer.payment_mint != self.marketplace.payment_mint {
+            self.marketplace.record_sale(0, 0);
+            self.seller_stats.record_sale(0);
+            return Ok(0);
+        }
+        self.marketplace.record_sale(amount, fee);
+        self.seller_stats.record_sale(amount);
+
+        Ok(amount)
+    }
+
+    /// Transfer a token offer from its vault to the fee recipient and seller, then close the vault
+    fn pay_tokens(&self, payment_mint: Pubkey, fee: u64, seller_amount: u64) -> Result<()> {
+        let (Some(mint), Some(vault), Some(seller_account), Some(fee_account)) = (
+            self.payment_mint.as_ref(),
+            self.offer_vault.as_ref(),
+            self.seller_payment_account.as_ref(),
+            self.fee_recipient_payment_account.as_ref(),
+        ) else {
+            return err!(MarketplaceError::MissingPaymentAccounts);
+        };
+        require_keys_eq!(mint.key(), payment_mint, MarketplaceError::InvalidPaymentMint);
+
+        // Create seeds for PDA signing
+        let listing = self.listing.key();
+        let buyer = self.buyer.key();
+        let offer_seeds: &[&[u8]] = &[
+            b"offer",
+            listing.as_ref(),
+            buyer.as_ref(),
+            &[self.offer.bump],
+        ];
+        let signer = &[offer_seeds];
+
+        // Skipped when the fee rounds to zero
+        if fee > 0 {
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: vault.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: fee_account.to_account_info(),
+                    authority: self.offer.to_account_info(),
+                },
+                signer,
+            );
+            transfer_checked(cpi_ctx, fee, mint.decimals)?;
+        }
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: vault.to_account_info(),
+                mint: mint.to_account_info(),
+                to: seller_account.to_account_info(),
+                authority: self.offer.to_account_info(),
+            },
+            signer,
+        );
+        transfer_checked(cpi_ctx, seller_amount, mint.decimals)?;
+
+        // Return the empty vault's rent to the buyer, who paid it
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: vault.to_account_info(),
+                destination: self.buyer.to_account_info(),
+                authority: self.offer.to_account_info(),
+            },
+            signer,
+        );
+        close_account(cpi_ctx)
+    }
+
+    /// Transfer the NFT from the listing vault to the buyer and close the vault
+    fn transfer_nft(&mut self) -> Result<()> {
+        // Create seeds for PDA signing
+        let marketplace = self.marketplace.key();
+        let seller = self.seller.key();
+        let nft = self.nft.key();
+        let nonce = self.listing.nonce.to_le_bytes();
+        let listing_seeds: &[&[u8]] = &[
+            b"listing",
+            marketplace.as_ref(),
+            seller.as_ref(),
+            nft.as_ref(),
+            &nonce,
+            &[self.listing.bump],
+        ];
+        let signer = &[listing_seeds];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.listing_token_account.to_account_info(),
+                mint: self.nft.to_account_info(),
+                to: self.buyer_token_account.to_account_info(),
+                authority: self.listing.to_account_info(),
+            },
+            signer,
+        );
+        transfer_checked(cpi_ctx, 1, self.nft.decimals)?;
+
+        // Return the empty vault's rent to the seller
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.listing_token_account.to_account_info(),
+                destination: self.seller.to_account_info(),
+                authority: self.listing.to_account_info(),
+            },
+            signer,
+        );
+        close_account(cpi_ctx)
+    }
+}
+
+#[event]
+pub struct OfferAcceptedEvent {
+    pub offer: Pubkey,
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    /// None for offers in lamports
+    pub payment_mint: Option<Pubkey>,
+}
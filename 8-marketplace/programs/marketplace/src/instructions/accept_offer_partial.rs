@@ -0,0 +1,340 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{close_account, transfer_checked, CloseAccount, Mint, Token, TokenAccount, TransferChecked},
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{
+        Blacklist, Listing, ListingIndex, Marketplace, Provenance, QuantityOffer, SaleRecord,
+        SellerStats,
+    },
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptOfferPartial<'info> {
+    /// The seller filling the offer from their listing
+    /// - Must sign and match the seller stored in the listing
+    /// - Receives the fill's payment minus fees, and the listing rent once it sells out
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// The buyer who made the offer
+    /// - Validated against the offer's buyer field
+    /// - Receives the tokens, and the offer account rent once it is filled
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+
+    /// The token mint being sold
+    /// - Must be the mint the offer buys
+    #[account(
+        constraint = nft.key() == offer.mint @ MarketplaceError::OfferMintMismatch,
+    )]
+    pub nft: Box<Account<'info, Mint>>,
+
+    /// The listing the tokens are sold from
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    /// - Need not be the listing the offer was made on
+    /// - Closed and rent refunded to the seller once its last token is sold
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            listing.seller.as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = seller @ MarketplaceError::NotListingSeller,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+    )]
+    pub listing: Box<Account<'info, Listing>>,
+
+    /// Token account holding the listed tokens
+    /// - Owned by the listing PDA
+    /// - Closed to the seller once the listing sells out
+    #[account(
+        mut,
+        associated_token::mint = nft,
+        associated_token::authority = listing,
+    )]
+    pub listing_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The buyer's token account to receive the tokens
+    /// - Created by the seller if the buyer does not have one yet
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = nft,
+        associated_token::authority = buyer
+    )]
+    pub buyer_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The quantity offer being filled
+    /// - Derived from the stored listing, which may differ from the one being sold from
+    /// - Pays out the fill from its escrow and is closed to the buyer once filled
+    #[account(
+        mut,
+        seeds = [b"quantity_offer", offer.listing.as_ref(), buyer.key().as_ref()],
+        bump = offer.bump,
+        has_one = buyer,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+    )]
+    pub offer: Box<Account<'info, QuantityOffer>>,
+
+    /// The marketplace state account
+    /// - Contains fee percentage for calculations
+    /// - Must not be paused
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        constraint = !marketplace.paused @ MarketplaceError::MarketplacePaused,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The seller's statistics account
+    /// - Sets the seller's fee tier
+    /// - Counts the sale, and the closed listing once it sells out
+    #[account(
+        mut,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump = seller_stats.bump,
+    )]
+    pub seller_stats: Box<Account<'info, SellerStats>>,
+
+    /// The account receiving marketplace fees
+    /// - Must be the fee recipient configured on the marketplace
+    #[account(
+        mut,
+        address = marketplace.fee_recipient @ MarketplaceError::InvalidFeeRecipient,
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+
+    /// The token's sale history
+    /// - Created on its first sale, paid by the seller filling the offer
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + Provenance::INIT_SPACE,
+        seeds = [b"provenance", marketplace.key().as_ref(), nft.key().as_ref()],
+        bump,
+    )]
+    pub provenance: Box<Account<'info, Provenance>>,
+
+    /// The seller's blacklist entry, which must not exist
+    /// - Uses PDA with marketplace and seller as seeds
+    ///
+    /// CHECK: Address is pinned by seeds; only its existence is checked
+    #[account(
+        seeds = [b"blacklist", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump,
+        constraint = !Blacklist::is_blacklisted(&seller_blacklist) @ MarketplaceError::WalletBlacklisted,
+    )]
+    pub seller_blacklist: UncheckedAccount<'info>,
+
+    /// The listing index page holding the listing's entry
+    /// - Only required for listings in the marketplace's listing index
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing index page holding the index's last entry
+    /// - Only required when it is another page than `listing_index`
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub last_listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing at the index's last position, moved into the freed position
+    /// - Only required when it is another listing than the one closing
+    ///
+    /// CHECK: Matched against the index entry in `ListingIndex::unindex`
+    #[account(mut)]
+    pub moved_listing: Option<UncheckedAccount<'info>>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> AcceptOfferPartial<'info> {
+    /// Sell part of a listing into a quantity offer
+    /// - Closes the offer to the buyer once it is filled, and the listing once it sells out
+    ///
+    /// # Arguments
+    /// * `fill_quantity` - Tokens to sell, at most what both the offer and the listing have left
+    /// * `bumps` - PDA bump values for the provenance account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn accept_offer_partial(
+        &mut self,
+        fill_quantity: u64,
+        bumps: AcceptOfferPartialBumps,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+        // Fills move the tokens with a plain SPL transfer
+        require!(!self.listing.programmable, MarketplaceError::ProgrammableNftUnsupported);
+        require!(
+            self.listing.can_buy(&self.buyer.key()),
+            MarketplaceError::NotAllowedBuyer
+        );
+        require!(!self.offer.is_expired(now), MarketplaceError::OfferExpired);
+        require!(self.listing.has_started(now), MarketplaceError::SaleNotStarted);
+        require_keys_neq!(self.buyer.key(), self.listing.seller, MarketplaceError::SelfPurchase);
+
+        let payment = self.offer.fill_cost(fill_quantity)?;
+        require!(
+            fill_quantity <= self.listing.quantity,
+            MarketplaceError::InsufficientQuantity
+        );
+
+        let (seller_proceeds, fee) = self.pay_from_escrow(payment)?;
+        self.transfer_tokens(fill_quantity)?;
+        if self.provenance.is_uninitialized() {
+            self.provenance.set_inner(Provenance::new(
+                self.marketplace.key(),
+                self.nft.key(),
+                bumps.provenance,
+            ));
+        }
+        self.provenance.record_sale(SaleRecord {
+            price: payment,
+            buyer: self.buyer.key(),
+            seller: self.seller.key(),
+            timestamp: now,
+        });
+
+        self.offer.quantity -= fill_quantity;
+        self.offer.amount -= payment;
+        self.listing.quantity -= fill_quantity;
+
+        emit_cpi(&self.event_authority, bumps.event_authority, &OfferPartiallyAcceptedEvent {
+            offer: self.offer.key(),
+            listing: self.listing.key(),
+            seller: self.seller.key(),
+            buyer: self.buyer.key(),
+            quantity: fill_quantity,
+            price_per_unit: self.offer.price_per_unit,
+            seller_proceeds,
+            marketplace_fee: fee,
+            offer_remaining: self.offer.quantity,
+            listing_remaining: self.listing.quantity,
+        })?;
+
+        if self.listing.quantity == 0 {
+            self.listing.is_active = false;
+            self.marketplace.listing_closed();
+            self.seller_stats.listing_closed();
+            ListingIndex::unindex(
+                &mut self.marketplace,
+                &mut self.listing,
+                self.listing_index.as_deref_mut(),
+                self.last_listing_index.as_deref_mut(),
+                self.moved_listing.as_ref(),
+            )?;
+            self.listing.close(self.seller.to_account_info())?;
+        }
+        if self.offer.quantity == 0 {
+            self.offer.close(self.buyer.to_account_info())?;
+        }
+
+        Ok(())
+    }
+
+    /// Split a fill's payment between seller and fee recipient
+    /// - Split exactly like a regular purchase, at the seller's fee tier
+    /// - The offer account is program owned, so lamports are moved directly
+    ///
+    /// # Arguments
+    /// * `payment` - The fill's payment, at most the escrow
+    ///
+    /// # Returns
+    /// * `Result<(u64, u64)>` - The seller's proceeds and the fee
+    fn pay_from_escrow(&mut self, payment: u64) -> Result<(u64, u64)> {
+        let fee_bps = self.marketplace.fee_bps_for_volume(self.seller_stats.volume);
+        let split = self.marketplace.split_payment(payment, fee_bps, false)?;
+        // The fee is taken out of the payment, so rounding never pays out more than it
+        require!(
+            split.total() == Some(payment),
+            MarketplaceError::PaymentSplitMismatch
+        );
+
+        self.offer.sub_lamports(payment)?;
+        self.fee_recipient.add_lamports(split.fee())?;
+        self.seller.add_lamports(split.seller_proceeds)?;
+        self.marketplace.record_sale(payment, split.fee());
+        self.seller_stats.record_sale(payment);
+
+        Ok((split.seller_proceeds, split.fee()))
+    }
+
+    /// Transfer the filled tokens from the listing vault to the buyer
+    /// - Closes the vault once the listing sells out
+    fn transfer_tokens(&self, amount: u64) -> Result<()> {
+        // Create seeds for PDA signing
+        let marketplace = self.marketplace.key();
+        let seller = self.seller.key();
+        let nft = self.nft.key();
+        let nonce = self.listing.nonce.to_le_bytes();
+        let listing_seeds: &[&[u8]] = &[
+            b"listing",
+            marketplace.as_ref(),
+            seller.as_ref(),
+            nft.as_ref(),
+            &nonce,
+            &[self.listing.bump],
+        ];
+        let signer = &[listing_seeds];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.listing_token_account.to_account_info(),
+                mint: self.nft.to_account_info(),
+                to: self.buyer_token_account.to_account_info(),
+                authority: self.listing.to_account_info(),
+            },
+            signer,
+        );
+        transfer_checked(cpi_ctx, amount, self.nft.decimals)?;
+        if amount < self.listing.quantity {
+            return Ok(());
+        }
+
+        // Return the empty vault's rent to the seller
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.listing_token_account.to_account_info(),
+                destination: self.seller.to_account_info(),
+                authority: self.listing.to_account_info(),
+            },
+            signer,
+        );
+        close_account(cpi_ctx)
+    }
+}
+
+#[event]
+pub struct OfferPartiallyAcceptedEvent {
+    pub offer: Pubkey,
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub quantity: u64,
+    pub price_per_unit: u64,
+    pub seller_proceeds: u64,
+    pub marketplace_fee: u64,
+    pub offer_remaining: u64,
+    pub listing_remaining: u64,
+}
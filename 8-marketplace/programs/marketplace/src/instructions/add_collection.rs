@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    error::MarketplaceError,
+    state::{CollectionConfig, Marketplace},
+};
+
+#[derive(Accounts)]
+pub struct AddCollection<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    /// - Pays for the collection config account
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The collection mint being approved for listing
+    pub collection_mint: Account<'info, Mint>,
+
+    /// The collection config account
+    /// - Uses PDA with marketplace and collection mint as seeds
+    /// - Its existence is what approves the collection
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + CollectionConfig::INIT_SPACE,
+        seeds = [
+            b"collection",
+            marketplace.key().as_ref(),
+            collection_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+
+    /// The marketplace state account
+    /// - Validates the admin
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// Required system program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AddCollection<'info> {
+    /// Approve a collection so its verified NFTs can be listed
+    ///
+    /// # Arguments
+    /// * `bumps` - PDA bump values for the collection config account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn add_collection(&mut self, bumps: AddCollectionBumps) -> Result<()> {
+        self.collection_config.set_inner(CollectionConfig {
+            marketplace: self.marketplace.key(),
+            collection_mint: self.collection_mint.key(),
+            bump: bumps.collection_config,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{close_account, transfer_checked, CloseAccount, Mint, Token, TokenAccount, TransferChecked},
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Listing, ListingIndex, Marketplace, SellerStats},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AdminDelist<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    /// - Pays for the seller's token account if it was closed
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The seller who listed the NFT
+    /// - Validated against the listing's seller field
+    /// - Receives the NFT back and the listing and vault rent
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+
+    /// The NFT mint account of the listing
+    pub nft: Box<Account<'info, Mint>>,
+
+    /// The listing being removed
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    /// - Derived from the stored seller so a wrong seller account gets a dedicated error
+    /// - Closed and rent refunded to seller
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            listing.seller.as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = seller @ MarketplaceError::ListingSellerMismatch,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+        close = seller
+    )]
+    pub listing: Box<Account<'info, Listing>>,
+
+    /// Token account holding the NFT during listing
+    /// - Owned by the listing PDA
+    /// - Emptied and closed to the seller
+    #[account(
+        mut,
+        associated_token::mint = nft,
+        associated_token::authority = listing,
+    )]
+    pub listing_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The seller's token account to receive the NFT
+    /// - Always the seller's associated token account, so the NFT cannot be redirected
+    #[account(
+        init_if_needed,
+        payer = admin,
+        associated_token::mint = nft,
+        associated_token::authority = seller,
+    )]
+    pub seller_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The marketplace state account
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::NotDelistAdmin,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The seller's statistics account
+    /// - Counts the closed listing off the seller's active listings
+    #[account(
+        mut,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump = seller_stats.bump,
+    )]
+    pub seller_stats: Box<Account<'info, SellerStats>>,
+
+    /// The listing index page holding the listing's entry
+    /// - Only required for listings in the marketplace's listing index
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing index page holding the index's last entry
+    /// - Only required when it is another page than `listing_index`
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub last_listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing at the index's last position, moved into the freed position
+    /// - Only required when it is another listing than the one closing
+    ///
+    /// CHECK: Matched against the index entry in `ListingIndex::unindex`
+    #[account(mut)]
+    pub moved_listing: Option<UncheckedAccount<'info>>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> AdminDelist<'info> {
+    /// Return the NFT of a listing to its seller and close the vault, on the admin's authority
+    /// - Used for listings of removed or flagged collections, which stay purchasable otherwise
+    /// - Escrowless listings have no vault and pNFT vaults are frozen, so neither is supported
+    ///
+    /// # Arguments
+    /// * `reason` - Why the listing was removed, recorded in the event for off-chain reporting
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn admin_delist(&mut self, reason: u8, event_authority_bump: u8) -> Result<()> {
+        require!(!self.listing.programmable, MarketplaceError::ProgrammableNftUnsupported);
+        // Listings locked by an installment plan hold the buyer's payments; the plan must end first
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+
+        // Create seeds for PDA signing
+        let marketplace = self.marketplace.key();
+        let seller = self.seller.key();
+        let nft = self.nft.key();
+        let nonce = self.listing.nonce.to_le_bytes();
+        let listing_seeds: &[&[u8]] = &[
+            b"listing",
+            marketplace.as_ref(),
+            seller.as_ref(),
+            nft.as_ref(),
+            &nonce,
+            &[self.listing.bump],
+        ];
+        let signer = &[listing_seeds];
+
+        // Transfer the unsold tokens back to seller
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.listing_token_account.to_account_info(),
+                mint: self.nft.to_account_info(),
+                to: self.seller_token_account.to_account_info(),
+                authority: self.listing.to_account_info(),
+            },
+            signer,
+        );
+        transfer_checked(cpi_ctx, self.listing.quantity, self.nft.decimals)?;
+
+        // Return the empty vault's rent to the seller
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.listing_token_account.to_account_info(),
+                destination: self.seller.to_account_info(),
+                authority: self.listing.to_account_info(),
+            },
+            signer,
+        );
+        close_account(cpi_ctx)?;
+
+        self.listing.is_active = false;
+        self.marketplace.listing_closed();
+        self.seller_stats.listing_closed();
+        ListingIndex::unindex(
+            &mut self.marketplace,
+            &mut self.listing,
+            self.listing_index.as_deref_mut(),
+            self.last_listing_index.as_deref_mut(),
+            self.moved_listing.as_ref(),
+        )?;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &AdminDelistEvent {
+            listing: self.listing.key(),
+            mint: nft,
+            seller,
+            reason,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct AdminDelistEvent {
+    pub listing: Pubkey,
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+    pub reason: u8,
+}
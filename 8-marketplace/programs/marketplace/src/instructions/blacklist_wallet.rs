@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::MarketplaceError,
+    state::{Blacklist, Marketplace},
+};
+
+#[derive(Accounts)]
+pub struct BlacklistWallet<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    /// - Pays for the blacklist entry
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The wallet being blocked from selling
+    ///
+    /// CHECK: Only its address is used, as a seed of the blacklist entry
+    pub wallet: UncheckedAccount<'info>,
+
+    /// The blacklist entry
+    /// - Uses PDA with marketplace and wallet as seeds
+    /// - Its existence is what blocks the wallet
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Blacklist::INIT_SPACE,
+        seeds = [b"blacklist", marketplace.key().as_ref(), wallet.key().as_ref()],
+        bump,
+    )]
+    pub blacklist: Account<'info, Blacklist>,
+
+    /// The marketplace state account
+    /// - Validates the admin
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// Required system program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> BlacklistWallet<'info> {
+    /// Block a wallet from listing, auctioning and accepting offers on the marketplace
+    /// - Existing listings stay up until delisted, but can no longer be sold through offers
+    ///
+    /// # Arguments
+    /// * `bumps` - PDA bump values for the blacklist entry
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn blacklist_wallet(&mut self, bumps: BlacklistWalletBumps) -> Result<()> {
+        self.blacklist.set_inner(Blacklist {
+            marketplace: self.marketplace.key(),
+            wallet: self.wallet.key(),
+            bump: bumps.blacklist,
+        });
+
+        Ok(())
+    }
+}
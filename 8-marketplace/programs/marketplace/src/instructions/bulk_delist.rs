@@ -0,0 +1,190 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address,
+    token::{close_account, transfer_checked, CloseAccount, Mint, Token, TransferChecked},
+};
+
+use crate::{
+    constants::MAX_BULK_ITEMS,
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Listing, ListingIndex, Marketplace, SellerStats},
+};
+
+/// Number of remaining accounts describing one listing of a bulk delisting
+const ACCOUNTS_PER_DELISTING: usize = 7;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct BulkDelist<'info> {
+    /// The seller who listed the NFTs
+    /// - Receives the NFTs back and the listing and vault rent
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// The marketplace state account for validation
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// The seller's statistics account
+    /// - Counts the closed listings off the seller's active listings
+    #[account(
+        mut,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump = seller_stats.bump,
+    )]
+    pub seller_stats: Account<'info, SellerStats>,
+
+    /// Required programs
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> BulkDelist<'info> {
+    /// Delist every listing of the batch
+    /// - Each listing goes through the same checks, transfer and closures as `delist_nft`
+    /// - Any failing item fails the whole instruction, so the batch is atomic
+    ///
+    /// # Arguments
+    /// * `items` - Groups of (mint, seller token account, vault, listing, listing index, last
+    ///   listing index, moved listing) accounts; the index accounts are those `delist_nft` takes,
+    ///   with the program id for any that is not needed
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn bulk_delist(
+        &mut self,
+        items: &'info [AccountInfo<'info>],
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        let groups = items.chunks_exact(ACCOUNTS_PER_DELISTING);
+        require!(
+            !items.is_empty()
+                && groups.remainder().is_empty()
+                && groups.len() <= MAX_BULK_ITEMS as usize,
+            MarketplaceError::InvalidBatchSize
+        );
+
+        for group in groups {
+            self.delist_item(group, event_authority_bump)?;
+        }
+
+        Ok(())
+    }
+
+    /// Validate one group and return its tokens
+    fn delist_item(
+        &mut self,
+        group: &'info [AccountInfo<'info>],
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        let [mint_info, seller_token_account, vault, listing_info, index_accounts @ ..] = group
+        else {
+            return err!(MarketplaceError::InvalidBatchSize);
+        };
+
+        // Owners and layouts are checked by the typed loads; the listing address by re-deriving the PDA
+        let mint = Account::<Mint>::try_from(mint_info)
+            .map_err(|_| MarketplaceError::InvalidBulkAccount)?;
+        let mut listing = Account::<Listing>::try_from(listing_info)
+            .map_err(|_| MarketplaceError::InvalidBulkAccount)?;
+
+        let seller = self.seller.key();
+        let marketplace = self.marketplace.key();
+        let nft = mint.key();
+        let nonce = listing.nonce.to_le_bytes();
+        let listing_seeds: &[&[u8]] = &[
+            b"listing",
+            marketplace.as_ref(),
+            seller.as_ref(),
+            nft.as_ref(),
+            &nonce,
+            &[listing.bump],
+        ];
+        let expected_listing = Pubkey::create_program_address(listing_seeds, &crate::ID)
+            .map_err(|_| MarketplaceError::InvalidBulkAccount)?;
+        require_keys_eq!(listing_info.key(), expected_listing, MarketplaceError::InvalidBulkAccount);
+        require_keys_eq!(
+            seller_token_account.key(),
+            get_associated_token_address(&seller, &nft),
+            MarketplaceError::InvalidBulkAccount
+        );
+        require_keys_eq!(
+            vault.key(),
+            get_associated_token_address(&expected_listing, &nft),
+            MarketplaceError::InvalidBulkAccount
+        );
+
+        require_keys_eq!(
+            listing.marketplace,
+            marketplace,
+            MarketplaceError::ListingMarketplaceMismatch
+        );
+
+        // Validate listing is active and seller matches
+        require!(
+            listing.is_active && listing.seller == seller,
+            MarketplaceError::ListingNotActive
+        );
+        // Escrowless and pNFT listings need metadata accounts per item
+        require!(
+            !listing.escrowless && !listing.programmable,
+            MarketplaceError::UnsupportedBulkItem
+        );
+        let signer = &[listing_seeds];
+
+        // Transfer the unsold tokens back to seller
+        let transfer_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: vault.clone(),
+                mint: mint_info.clone(),
+                to: seller_token_account.clone(),
+                authority: listing_info.clone(),
+            },
+            signer,
+        );
+        transfer_checked(transfer_ctx, listing.quantity, mint.decimals)?;
+
+        // Return the empty vault's rent to the seller
+        let close_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: vault.clone(),
+                destination: self.seller.to_account_info(),
+                authority: listing_info.clone(),
+            },
+            signer,
+        );
+        close_account(close_ctx)?;
+
+        self.marketplace.listing_closed();
+        self.seller_stats.listing_closed();
+        ListingIndex::unindex_remaining(&mut self.marketplace, &mut listing, index_accounts)?;
+        listing.close(self.seller.to_account_info())?;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &NftDelistedEvent {
+            listing: expected_listing,
+            seller,
+            nft,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct NftDelistedEvent {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub nft: Pubkey,
+}
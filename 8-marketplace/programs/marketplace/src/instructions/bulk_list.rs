@@ -0,0 +1,275 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{create_account, CreateAccount},
+};
+use anchor_spl::{
+    associated_token::{create, get_associated_token_address, AssociatedToken, Create},
+    metadata::{Metadata, MetadataAccount},
+    token::{transfer_checked, Mint, Token, TransferChecked},
+};
+
+use crate::{
+    constants::{ListingCategory, MAX_BULK_ITEMS},
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    instructions::NftListedEvent,
+    programmable::is_programmable,
+    state::{Blacklist, CollectionConfig, Listing, Marketplace, SellerStats},
+};
+
+/// Number of remaining accounts describing one NFT of a bulk listing
+const ACCOUNTS_PER_LISTING: usize = 5;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct BulkList<'info> {
+    /// The seller who owns the NFTs and pays for the listings
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Validates this is the correct marketplace instance
+    /// - Must not be paused
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        constraint = !marketplace.paused @ MarketplaceError::MarketplacePaused,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The seller's statistics account
+    /// - Uses PDA with marketplace and seller as seeds
+    /// - Created on the seller's first listing; hands out each listing's nonce
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + SellerStats::INIT_SPACE,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump,
+    )]
+    pub seller_stats: Box<Account<'info, SellerStats>>,
+
+    /// The collection mint every NFT of the batch belongs to
+    /// - Used for collection verification
+    pub collection_mint: Account<'info, Mint>,
+
+    /// The collection config approving the collection for listing
+    /// - Uses PDA with marketplace and collection mint as seeds
+    /// - Not required to exist when the marketplace has open listings
+    ///
+    /// CHECK: Address is pinned by seeds; the account is loaded in `verify_nft`
+    #[account(
+        seeds = [
+            b"collection",
+            marketplace.key().as_ref(),
+            collection_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub collection_config: UncheckedAccount<'info>,
+
+    /// The seller's blacklist entry, which must not exist
+    /// - Uses PDA with marketplace and seller as seeds
+    ///
+    /// CHECK: Address is pinned by seeds; only its existence is checked
+    #[account(
+        seeds = [b"blacklist", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump,
+        constraint = !Blacklist::is_blacklisted(&seller_blacklist) @ MarketplaceError::WalletBlacklisted,
+    )]
+    pub seller_blacklist: UncheckedAccount<'info>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub metadata_program: Program<'info, Metadata>,
+}
+
+impl<'info> BulkList<'info> {
+    /// List every NFT of the batch at its price
+    /// - Each NFT goes through the same checks and escrow as `list_nft`, at quantity 1 with no expiry
+    /// - Any failing item fails the whole instruction, so the batch is atomic
+    ///
+    /// # Arguments
+    /// * `prices` - The price of each NFT, in the order of the account groups
+    /// * `items` - Groups of (mint, seller token account, vault, listing, metadata) accounts
+    /// * `bumps` - PDA bump values for the seller statistics account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn bulk_list(
+        &mut self,
+        prices: &[u64],
+        items: &'info [AccountInfo<'info>],
+        bumps: BulkListBumps,
+    ) -> Result<()> {
+        require!(
+            !prices.is_empty()
+                && prices.len() <= MAX_BULK_ITEMS as usize
+                && items.len() == prices.len() * ACCOUNTS_PER_LISTING,
+            MarketplaceError::InvalidBatchSize
+        );
+
+        if self.seller_stats.is_uninitialized() {
+            self.seller_stats.set_inner(SellerStats::new(
+                self.marketplace.key(),
+                self.seller.key(),
+                bumps.seller_stats,
+            ));
+        }
+
+        for (group, price) in items.chunks_exact(ACCOUNTS_PER_LISTING).zip(prices) {
+            self.list_item(group, *price, bumps.event_authority)?;
+        }
+
+        Ok(())
+    }
+
+    /// Validate one (mint, seller token account, vault, listing, metadata) group and list its NFT
+    fn list_item(
+        &mut self,
+        group: &'info [AccountInfo<'info>],
+        price: u64,
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        let [mint_info, seller_token_account, vault, listing_info, metadata_info] = group else {
+            return err!(MarketplaceError::InvalidBatchSize);
+        };
+        require!(price > 0, MarketplaceError::InvalidPrice);
+        require!(self.marketplace.price_in_bounds(price), MarketplaceError::PriceOutOfBounds);
+
+        // Owners and layouts are checked by the typed loads; addresses against the mint
+        let mint = Account::<Mint>::try_from(mint_info)
+            .map_err(|_| MarketplaceError::InvalidBulkAccount)?;
+        let metadata = Account::<MetadataAccount>::try_from(metadata_info)
+            .map_err(|_| MarketplaceError::InvalidBulkAccount)?;
+        require_keys_eq!(metadata.mint, mint.key(), MarketplaceError::InvalidBulkAccount);
+
+        let seller = self.seller.key();
+        let marketplace = self.marketplace.key();
+        let nft = mint.key();
+        // Items take consecutive nonces in the order of the account groups
+        let nonce = self.seller_stats.listing_opened()?;
+        let nonce_bytes = nonce.to_le_bytes();
+        let (expected_listing, bump) = Pubkey::find_program_address(
+            &[b"listing", marketplace.as_ref(), seller.as_ref(), nft.as_ref(), &nonce_bytes],
+            &crate::ID,
+        );
+        require_keys_eq!(listing_info.key(), expected_listing, MarketplaceError::InvalidBulkAccount);
+        require_keys_eq!(
+            seller_token_account.key(),
+            get_associated_token_address(&seller, &nft),
+            MarketplaceError::InvalidBulkAccount
+        );
+        require_keys_eq!(
+            vault.key(),
+            get_associated_token_address(&expected_listing, &nft),
+            MarketplaceError::InvalidBulkAccount
+        );
+
+        // Bulk listings are plain one-token escrows; pNFTs need token records per item
+        require!(mint.decimals == 0, MarketplaceError::NotSemiFungible);
+        require!(!is_programmable(&metadata), MarketplaceError::UnsupportedBulkItem);
+        let collection = CollectionConfig::verify_nft(
+            &self.marketplace,
+            &metadata,
+            &self.collection_mint.key(),
+            &self.collection_config.to_account_info(),
+        )?;
+
+        // Create the listing PDA, as `init` does in `list_nft`
+        let listing_seeds: &[&[u8]] = &[
+            b"listing",
+            marketplace.as_ref(),
+            seller.as_ref(),
+            nft.as_ref(),
+            &nonce_bytes,
+            &[bump],
+        ];
+        let signer = &[listing_seeds];
+        let space = 8 + Listing::INIT_SPACE;
+        let create_ctx = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            CreateAccount {
+                from: self.seller.to_account_info(),
+                to: listing_info.clone(),
+            },
+            signer,
+        );
+        create_account(create_ctx, Rent::get()?.minimum_balance(space), space as u64, &crate::ID)?;
+
+        let listing = Listing {
+            seller,
+            marketplace,
+            mint: nft,
+            price,
+            bump,
+            is_active: true,
+            // Bulk listings are filed under a category with `update_listing_category`
+            category: ListingCategory::Uncategorized as u8,
+            collection,
+            expiry: 0,
+            dutch: None,
+            escrowless: false,
+            quantity: 1,
+            programmable: false,
+            allowed_buyer: None,
+            protection_window_secs: 0,
+            start_ts: 0,
+            nonce,
+            secondary_price: None,
+            metadata_hash: Listing::hash_metadata(&metadata.name, &metadata.symbol, &metadata.uri),
+            best_offer_amount: 0,
+            best_offer: Pubkey::default(),
+            // Only `list_nft` indexes listings
+            index_position: None,
+        };
+        listing.try_serialize(&mut &mut listing_info.try_borrow_mut_data()?[..])?;
+
+        // Create the vault owned by the listing
+        let vault_ctx = CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            Create {
+                payer: self.seller.to_account_info(),
+                associated_token: vault.clone(),
+                authority: listing_info.clone(),
+                mint: mint_info.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+        );
+        create(vault_ctx)?;
+
+        // Escrow the NFT in the vault
+        let transfer_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: seller_token_account.clone(),
+                mint: mint_info.clone(),
+                to: vault.clone(),
+                authority: self.seller.to_account_info(),
+            },
+        );
+        transfer_checked(transfer_ctx, 1, mint.decimals)?;
+        self.marketplace.listing_opened();
+
+        emit_cpi(&self.event_authority, event_authority_bump, &NftListedEvent {
+            listing: expected_listing,
+            seller,
+            nft,
+            price,
+            start_ts: 0,
+            secondary_price: None,
+            category: ListingCategory::Uncategorized as u8,
+        })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{close_account, transfer_checked, CloseAccount, Mint, Token, TokenAccount, TransferChecked},
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Auction, Marketplace, Provenance, SaleRecord},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct BuyNow<'info> {
+    /// The buyer ending the auction at its buy-now price
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The seller who created the auction
+    /// - Validated against the auction's seller field
+    /// - Receives the buy-now price minus fees and all account rent
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+
+    /// The NFT mint account being auctioned
+    pub nft: Box<Account<'info, Mint>>,
+
+    /// The auction being ended
+    /// - Must match the PDA derived from marketplace, seller, and NFT
+    /// - Closed and rent refunded to seller
+    #[account(
+        mut,
+        seeds = [
+            b"auction",
+            marketplace.key().as_ref(),
+            seller.key().as_ref(),
+            nft.key().as_ref(),
+        ],
+        bump = auction.bump,
+        has_one = seller,
+        close = seller
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+
+    /// Token account holding the NFT during the auction
+    /// - Emptied and closed to the seller
+    #[account(
+        mut,
+        associated_token::mint = nft,
+        associated_token::authority = auction,
+    )]
+    pub auction_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Escrow holding the highest bid
+    /// - Refunds the highest bid and returns its rent to the seller
+    #[account(
+        mut,
+        seeds = [b"bid_escrow", auction.key().as_ref()],
+        bump = auction.escrow_bump,
+    )]
+    pub bid_escrow: SystemAccount<'info>,
+
+    /// The current highest bidder
+    /// - Required once the auction has a bid, and must match the highest bidder
+    /// - Refunded their bid in the same instruction
+    #[account(mut)]
+    pub previous_bidder: Option<SystemAccount<'info>>,
+
+    /// The buyer's token account to receive the NFT
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = nft,
+        associated_token::authority = buyer,
+    )]
+    pub buyer_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The marketplace state account
+    /// - Contains fee percentage for calculations
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The account receiving marketplace fees
+    /// - Must be the fee recipient configured on the marketplace
+    #[account(
+        mut,
+        address = marketplace.fee_recipient @ MarketplaceError::InvalidFeeRecipient,
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+
+    /// The NFT's sale history
+    /// - Created on its first sale, paid by the buyer
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + Provenance::INIT_SPACE,
+        seeds = [b"provenance", marketplace.key().as_ref(), nft.key().as_ref()],
+        bump,
+    )]
+    pub provenance: Box<Account<'info, Provenance>>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> BuyNow<'info> {
+    /// End a running auction at its buy-now price and settle it at once
+    /// - The highest bidder is refunded and the price is split like a settled auction
+    ///
+    /// # Arguments
+    /// * `bumps` - PDA bump values for the provenance account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn buy_now(&mut self, bumps: BuyNowBumps) -> Result<()> {
+        require!(
+            !self.auction.has_ended(Clock::get()?.unix_timestamp),
+            MarketplaceError::AuctionEnded
+        );
+        let price = self
+            .auction
+            .buy_now_price()
+            .ok_or(MarketplaceError::BuyNowUnavailable)?;
+        require_keys_neq!(self.buyer.key(), self.seller.key(), MarketplaceError::SelfPurchase);
+
+        let refunded_bidder = self.auction.highest_bidder;
+        let refunded_bid = self.auction.highest_bid;
+        self.drain_escrow(refunded_bidder, refunded_bid)?;
+
+        let fee = self.marketplace.fee_for(price)?;
+        self.pay_seller(price, fee)?;
+        self.marketplace.record_sale(price, fee);
+        self.transfer_nft()?;
+        if self.provenance.is_uninitialized() {
+            self.provenance.set_inner(Provenance::new(
+                self.marketplace.key(),
+                self.nft.key(),
+                bumps.provenance,
+            ));
+        }
+        self.provenance.record_sale(SaleRecord {
+            price,
+            buyer: self.buyer.key(),
+            seller: self.seller.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        emit_cpi(&self.event_authority, bumps.event_authority, &AuctionBoughtNowEvent {
+            auction: self.auction.key(),
+            seller: self.seller.key(),
+            buyer: self.buyer.key(),
+            price,
+            fee,
+            refunded_bidder,
+            refunded_bid,
+        })?;
+
+        Ok(())
+    }
+
+    /// Refund the highest bid, if any, and return the escrow's rent to the seller
+    fn drain_escrow(&self, highest_bidder: Option<Pubkey>, highest_bid: u64) -> Result<()> {
+        // Create seeds for PDA signing
+        let auction = self.auction.key();
+        let escrow_seeds: &[&[u8]] = &[
+            b"bid_escrow",
+            auction.as_ref(),
+            &[self.auction.escrow_bump],
+        ];
+        let signer = &[escrow_seeds];
+
+        if let Some(highest_bidder) = highest_bidder {
+            let previous_bidder = self
+                .previous_bidder
+                .as_ref()
+                .ok_or(MarketplaceError::InvalidBidder)?;
+            require_keys_eq!(previous_bidder.key(), highest_bidder, MarketplaceError::InvalidBidder);
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.bid_escrow.to_account_info(),
+                    to: previous_bidder.to_account_info(),
+                },
+                signer,
+            );
+            transfer(cpi_ctx, highest_bid)?;
+        }
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.bid_escrow.to_account_info(),
+                to: self.seller.to_account_info(),
+            },
+            signer,
+        );
+        transfer(cpi_ctx, self.bid_escrow.lamports())
+    }
+
+    /// Pay the fee to the fee recipient and the rest of the price to the seller
+    fn pay_seller(&self, price: u64, fee: u64) -> Result<()> {
+        if fee > 0 {
+            let cpi_ctx = CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.buyer.to_account_info(),
+                    to: self.fee_recipient.to_account_info(),
+                },
+            );
+            transfer(cpi_ctx, fee)?;
+        }
+
+        let cpi_ctx = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.buyer.to_account_info(),
+                to: self.seller.to_account_info(),
+            },
+        );
+        transfer(cpi_ctx, price - fee)
+    }
+
+    /// Transfer the NFT from the auction vault to the buyer, then close the vault
+    fn transfer_nft(&self) -> Result<()> {
+        // Create seeds for PDA signing
+        let marketplace = self.marketplace.key();
+        let seller = self.seller.key();
+        let nft = self.nft.key();
+        let auction_seeds: &[&[u8]] = &[
+            b"auction",
+            marketplace.as_ref(),
+            seller.as_ref(),
+            nft.as_ref(),
+            &[self.auction.bump],
+        ];
+        let signer = &[auction_seeds];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.auction_token_account.to_account_info(),
+                mint: self.nft.to_account_info(),
+                to: self.buyer_token_account.to_account_info(),
+                authority: self.auction.to_account_info(),
+            },
+            signer,
+        );
+        transfer_checked(cpi_ctx, 1, self.nft.decimals)?;
+
+        // Return the empty vault's rent to the seller
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.auction_token_account.to_account_info(),
+                destination: self.seller.to_account_info(),
+                authority: self.auction.to_account_info(),
+            },
+            signer,
+        );
+        close_account(cpi_ctx)
+    }
+}
+
+#[event]
+pub struct AuctionBoughtNowEvent {
+    pub auction: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub fee: u64,
+    pub refunded_bidder: Option<Pubkey>,
+    pub refunded_bid: u64,
+}
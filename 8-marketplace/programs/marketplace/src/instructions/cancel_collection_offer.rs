@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{CollectionOffer, Marketplace},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelCollectionOffer<'info> {
+    /// The account closing the offer
+    /// - Must be the buyer to cancel it
+    /// - Anyone can refund it once expired, for the marketplace's crank bounty
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The buyer who made the offer
+    /// - Validated against the offer's buyer field
+    /// - Receives the escrowed lamports and the offer account rent
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+
+    /// The collection offer being cancelled
+    /// - Derived from its stored marketplace and collection mint
+    /// - Closed to the buyer, which refunds the escrow together with the rent
+    #[account(
+        mut,
+        seeds = [
+            b"coll_offer",
+            collection_offer.marketplace.as_ref(),
+            collection_offer.collection_mint.as_ref(),
+            buyer.key().as_ref(),
+        ],
+        bump = collection_offer.bump,
+        has_one = buyer,
+        has_one = marketplace,
+        close = buyer
+    )]
+    pub collection_offer: Account<'info, CollectionOffer>,
+
+    /// The marketplace the offer was made on
+    /// - Sets the bounty for refunding an expired offer on the buyer's behalf
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> CancelCollectionOffer<'info> {
+    /// Cancel the buyer's own offer, refunding the escrow
+    ///
+    /// # Arguments
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn cancel_collection_offer(&mut self, event_authority_bump: u8) -> Result<()> {
+        require_keys_eq!(
+            self.authority.key(),
+            self.buyer.key(),
+            MarketplaceError::NotOfferBuyer
+        );
+
+        self.close_offer(0, event_authority_bump)
+    }
+
+    /// Refund an expired offer to the buyer
+    /// - Refunding someone else's offer earns the bounty, paid out of the escrow
+    ///
+    /// # Arguments
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn refund_expired_collection_offer(&mut self, event_authority_bump: u8) -> Result<()> {
+        require!(
+            self.collection_offer.is_expired(Clock::get()?.unix_timestamp),
+            MarketplaceError::OfferNotExpired
+        );
+
+        let bounty = if self.authority.key() == self.buyer.key() {
+            0
+        } else {
+            self.marketplace.crank_bounty(self.collection_offer.escrowed()?)
+        };
+        self.close_offer(bounty, event_authority_bump)
+    }
+
+    /// Pay the bounty and emit the cancellation; the offer is closed to the buyer afterwards
+    fn close_offer(&mut self, bounty: u64, event_authority_bump: u8) -> Result<()> {
+        let escrowed = self.collection_offer.escrowed()?;
+        if bounty > 0 {
+            self.collection_offer.sub_lamports(bounty)?;
+            self.authority.add_lamports(bounty)?;
+        }
+
+        emit_cpi(&self.event_authority, event_authority_bump, &CollectionOfferCancelledEvent {
+            collection_offer: self.collection_offer.key(),
+            collection_mint: self.collection_offer.collection_mint,
+            buyer: self.buyer.key(),
+            cancelled_by: self.authority.key(),
+            refunded: escrowed - bounty,
+            bounty,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct CollectionOfferCancelledEvent {
+    pub collection_offer: Pubkey,
+    pub collection_mint: Pubkey,
+    pub buyer: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub refunded: u64,
+    pub bounty: u64,
+}
@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Marketplace, NftOffer},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelNftOffer<'info> {
+    /// The account closing the offer
+    /// - Must be the buyer to cancel it
+    /// - Anyone can refund it once expired, for the marketplace's crank bounty
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The buyer who made the offer
+    /// - Validated against the offer's buyer field
+    /// - Receives the escrowed lamports and the offer account rent
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+
+    /// The offer being cancelled
+    /// - Derived from its stored marketplace and NFT mint
+    /// - Closed to the buyer, which refunds the escrow together with the rent
+    #[account(
+        mut,
+        seeds = [
+            b"nft_offer",
+            nft_offer.marketplace.as_ref(),
+            nft_offer.mint.as_ref(),
+            buyer.key().as_ref(),
+        ],
+        bump = nft_offer.bump,
+        has_one = buyer,
+        has_one = marketplace,
+        close = buyer
+    )]
+    pub nft_offer: Account<'info, NftOffer>,
+
+    /// The marketplace the offer was made on
+    /// - Sets the bounty for refunding an expired offer on the buyer's behalf
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> CancelNftOffer<'info> {
+    /// Cancel the buyer's own offer, refunding the escrow
+    ///
+    /// # Arguments
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn cancel_nft_offer(&mut self, event_authority_bump: u8) -> Result<()> {
+        require_keys_eq!(
+            self.authority.key(),
+            self.buyer.key(),
+            MarketplaceError::NotOfferBuyer
+        );
+
+        self.close_offer(0, event_authority_bump)
+    }
+
+    /// Refund an expired offer to the buyer
+    /// - Refunding someone else's offer earns the bounty, paid out of the escrow
+    ///
+    /// # Arguments
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn refund_expired_nft_offer(&mut self, event_authority_bump: u8) -> Result<()> {
+        require!(
+            self.nft_offer.is_expired(Clock::get()?.unix_timestamp),
+            MarketplaceError::OfferNotExpired
+        );
+
+        let bounty = if self.authority.key() == self.buyer.key() {
+            0
+        } else {
+            self.marketplace.crank_bounty(self.nft_offer.amount)
+        };
+        self.close_offer(bounty, event_authority_bump)
+    }
+
+    /// Pay the bounty and emit the cancellation; the offer is closed to the buyer afterwards
+    fn close_offer(&mut self, bounty: u64, event_authority_bump: u8) -> Result<()> {
+        if bounty > 0 {
+            self.nft_offer.sub_lamports(bounty)?;
+            self.authority.add_lamports(bounty)?;
+        }
+
+        emit_cpi(&self.event_authority, event_authority_bump, &NftOfferCancelledEvent {
+            nft_offer: self.nft_offer.key(),
+            nft: self.nft_offer.mint,
+            buyer: self.buyer.key(),
+            cancelled_by: self.authority.key(),
+            amount: self.nft_offer.amount,
+            bounty,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct NftOfferCancelledEvent {
+    pub nft_offer: Pubkey,
+    pub nft: Pubkey,
+    pub buyer: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub amount: u64,
+    pub bounty: u64,
+}
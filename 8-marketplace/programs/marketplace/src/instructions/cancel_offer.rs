@@ -0,0 +1,244 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{
+    close_account, transfer_checked, CloseAccount, Mint, Token, TokenAccount, TransferChecked,
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Listing, Marketplace, Offer},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelOffer<'info> {
+    /// The account closing the offer
+    /// - Must be the buyer to cancel it
+    /// - Anyone can refund it once expired, for the marketplace's crank bounty
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The buyer who made the offer
+    /// - Validated against the offer's buyer field
+    /// - Receives the escrowed lamports and the offer account rent
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+
+    /// The offer being cancelled
+    /// - Derived from the stored listing so offers stay cancelable after the listing is closed
+    /// - Closed to the buyer, which refunds the escrow together with the rent
+    #[account(
+        mut,
+        seeds = [b"offer", offer.listing.as_ref(), buyer.key().as_ref()],
+        bump = offer.bump,
+        has_one = buyer,
+        has_one = marketplace,
+        close = buyer
+    )]
+    pub offer: Account<'info, Offer>,
+
+    /// The marketplace the offer was made on
+    /// - Sets the bounty for refunding an expired offer on the buyer's behalf
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// The listing the offer was made on
+    /// - Passed while the listing is open, so a cancelled best offer stops being tracked
+    #[account(
+        mut,
+        address = offer.listing,
+    )]
+    pub listing: Option<Account<'info, Listing>>,
+
+    /// The mint a token offer is escrowed in
+    /// - Only required when cancelling a token offer
+    pub payment_mint: Option<Account<'info, Mint>>,
+
+    /// Token account holding the offered tokens
+    /// - Only required for token offers; emptied and closed to the buyer
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = offer,
+    )]
+    pub offer_vault: Option<Account<'info, TokenAccount>>,
+
+    /// The buyer's payment token account
+    /// - Refunded the offered tokens
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = buyer,
+    )]
+    pub buyer_payment_account: Option<Account<'info, TokenAccount>>,
+
+    /// Token program for refunding token offers
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+impl<'info> CancelOffer<'info> {
+    /// Cancel the buyer's own offer, refunding the escrow
+    ///
+    /// # Arguments
+    /// * `candidates` - Other open offers on the listing to pick the next best offer from
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn cancel_offer(
+        &mut self,
+        candidates: &'info [AccountInfo<'info>],
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        require_keys_eq!(
+            self.authority.key(),
+            self.buyer.key(),
+            MarketplaceError::NotOfferBuyer
+        );
+
+        self.close_offer(0, candidates, event_authority_bump)
+    }
+
+    /// Refund an expired offer to the buyer
+    /// - Refunding someone else's offer earns the bounty, paid out of the escrow; token offers
+    ///   escrow no lamports to pay a bounty from
+    ///
+    /// # Arguments
+    /// * `candidates` - Other open offers on the listing to pick the next best offer from
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn refund_expired_offer(
+        &mut self,
+        candidates: &'info [AccountInfo<'info>],
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        require!(
+            self.offer.is_expired(Clock::get()?.unix_timestamp),
+            MarketplaceError::OfferNotExpired
+        );
+
+        let bounty = if self.authority.key() == self.buyer.key()
+            || self.offer.payment_mint.is_some()
+        {
+            0
+        } else {
+            self.marketplace.crank_bounty(self.offer.amount)
+        };
+        self.close_offer(bounty, candidates, event_authority_bump)
+    }
+
+    /// Pay the bounty, refund the offer and emit the cancellation
+    /// - Token offers are refunded from their vault, which is closed
+    /// - Closing the listing's best offer recomputes it from the candidate offers passed,
+    ///   or leaves the listing without one
+    fn close_offer(
+        &mut self,
+        bounty: u64,
+        candidates: &'info [AccountInfo<'info>],
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        if bounty > 0 {
+            self.offer.sub_lamports(bounty)?;
+            self.authority.add_lamports(bounty)?;
+        }
+        if let Some(payment_mint) = self.offer.payment_mint {
+            self.refund_tokens(payment_mint)?;
+        }
+
+        let offer = self.offer.key();
+        if let Some(listing) = self.listing.as_mut() {
+            if listing.offer_closed(&offer) {
+                // Offers on another listing, in another currency, expired or not offers at all
+                // are skipped
+                for info in candidates {
+                    if let Ok(candidate) = Account::<Offer>::try_from(info) {
+                        if info.key() != offer
+                            && candidate.listing == listing.key()
+                            && candidate.payment_mint == self.marketplace.payment_mint
+                            && !candidate.is_expired(now)
+                        {
+                            listing.offer_made(info.key(), candidate.amount);
+                        }
+                    }
+                }
+            }
+        }
+
+        emit_cpi(&self.event_authority, event_authority_bump, &OfferCancelledEvent {
+            offer: self.offer.key(),
+            listing: self.offer.listing,
+            buyer: self.buyer.key(),
+            cancelled_by: self.authority.key(),
+            amount: self.offer.amount,
+            bounty,
+        })?;
+
+        Ok(())
+    }
+
+    /// Return a token offer from its vault to the buyer, then close the vault to the buyer
+    fn refund_tokens(&self, payment_mint: Pubkey) -> Result<()> {
+        let (Some(mint), Some(vault), Some(buyer_account), Some(token_program)) = (
+            self.payment_mint.as_ref(),
+            self.offer_vault.as_ref(),
+            self.buyer_payment_account.as_ref(),
+            self.token_program.as_ref(),
+        ) else {
+            return err!(MarketplaceError::MissingPaymentAccounts);
+        };
+        require_keys_eq!(mint.key(), payment_mint, MarketplaceError::InvalidPaymentMint);
+
+        // Create seeds for PDA signing
+        let buyer = self.buyer.key();
+        let offer_seeds: &[&[u8]] = &[
+            b"offer",
+            self.offer.listing.as_ref(),
+            buyer.as_ref(),
+            &[self.offer.bump],
+        ];
+        let signer = &[offer_seeds];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: vault.to_account_info(),
+                mint: mint.to_account_info(),
+                to: buyer_account.to_account_info(),
+                authority: self.offer.to_account_info(),
+            },
+            signer,
+        );
+        transfer_checked(cpi_ctx, self.offer.amount, mint.decimals)?;
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            CloseAccount {
+                account: vault.to_account_info(),
+                destination: self.buyer.to_account_info(),
+                authority: self.offer.to_account_info(),
+            },
+            signer,
+        );
+        close_account(cpi_ctx)
+    }
+}
+
+#[event]
+pub struct OfferCancelledEvent {
+    pub offer: Pubkey,
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub amount: u64,
+    pub bounty: u64,
+}
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::MarketplaceError, event_cpi::emit_cpi, state::Proceeds};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimProceeds<'info> {
+    /// The seller the proceeds belong to
+    /// - Must match the seller stored in the proceeds account
+    pub seller: Signer<'info>,
+
+    /// The account the proceeds are swept to
+    /// - Must sign, so proceeds only go where the seller's wallet agrees to receive them
+    #[account(mut)]
+    pub destination: Signer<'info>,
+
+    /// The seller's proceeds account
+    /// - Uses PDA with marketplace and seller as seeds
+    /// - Stays open with its rent, ready for the seller's next sale
+    #[account(
+        mut,
+        seeds = [b"proceeds", proceeds.marketplace.as_ref(), seller.key().as_ref()],
+        bump = proceeds.bump,
+        has_one = seller,
+    )]
+    pub proceeds: Account<'info, Proceeds>,
+}
+
+impl<'info> ClaimProceeds<'info> {
+    /// Sweep the seller's unclaimed proceeds to the destination
+    /// - Works while the marketplace is paused, like delisting
+    /// - The proceeds account is program owned, so lamports are moved directly
+    ///
+    /// # Arguments
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn claim_proceeds(&mut self, event_authority_bump: u8) -> Result<()> {
+        let amount = self.proceeds.take();
+        require!(amount > 0, MarketplaceError::NoProceedsToClaim);
+
+        self.proceeds.sub_lamports(amount)?;
+        self.destination.add_lamports(amount)?;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &ProceedsClaimedEvent {
+            proceeds: self.proceeds.key(),
+            marketplace: self.proceeds.marketplace,
+            seller: self.seller.key(),
+            destination: self.destination.key(),
+            amount,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct ProceedsClaimedEvent {
+    pub proceeds: Pubkey,
+    pub marketplace: Pubkey,
+    pub seller: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
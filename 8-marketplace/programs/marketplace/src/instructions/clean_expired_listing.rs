@@ -0,0 +1,252 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{close_account, transfer_checked, CloseAccount, Mint, Token, TokenAccount, TransferChecked},
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Listing, ListingIndex, Marketplace, SellerStats},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CleanExpiredListing<'info> {
+    /// Anyone cleaning up the expired listing
+    /// - Pays for the seller's token account if it was closed
+    /// - Receives the marketplace's crank reward from the treasury
+    #[account(mut)]
+    pub cleaner: Signer<'info>,
+
+    /// The seller who listed the NFT
+    /// - Validated against the listing's seller field
+    /// - Receives the NFT back and the listing and vault rent
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+
+    /// The NFT mint account of the expired listing
+    pub nft: Box<Account<'info, Mint>>,
+
+    /// The expired listing account
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    /// - Closed and rent refunded to seller
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            seller.key().as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = seller,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+        close = seller
+    )]
+    pub listing: Box<Account<'info, Listing>>,
+
+    /// Token account holding the NFT during listing
+    /// - Owned by the listing PDA
+    /// - Emptied and closed to the seller
+    #[account(
+        mut,
+        associated_token::mint = nft,
+        associated_token::authority = listing,
+    )]
+    pub listing_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The seller's token account to receive the NFT
+    #[account(
+        init_if_needed,
+        payer = cleaner,
+        associated_token::mint = nft,
+        associated_token::authority = seller,
+    )]
+    pub seller_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The marketplace state account for validation
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The seller's statistics account
+    /// - Counts the closed listing off the seller's active listings
+    #[account(
+        mut,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump = seller_stats.bump,
+    )]
+    pub seller_stats: Box<Account<'info, SellerStats>>,
+
+    /// The listing index page holding the listing's entry
+    /// - Only required for listings in the marketplace's listing index
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing index page holding the index's last entry
+    /// - Only required when it is another page than `listing_index`
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub last_listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing at the index's last position, moved into the freed position
+    /// - Only required when it is another listing than the one closing
+    ///
+    /// CHECK: Matched against the index entry in `ListingIndex::unindex`
+    #[account(mut)]
+    pub moved_listing: Option<UncheckedAccount<'info>>,
+
+    /// Treasury account funding the crank reward
+    /// - Signs the reward transfer with its PDA seeds
+    #[account(
+        mut,
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump = marketplace.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> CleanExpiredListing<'info> {
+    /// Return the NFT of an expired listing to the seller, close the vault and reward the cleaner
+    ///
+    /// # Arguments
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn clean_expired_listing(&mut self, event_authority_bump: u8) -> Result<()> {
+        // Listings locked by an installment plan keep their NFT until the plan ends
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+        require!(
+            self.listing.is_expired(Clock::get()?.unix_timestamp),
+            MarketplaceError::ListingNotExpired
+        );
+        // pNFT vaults are frozen; the seller delists them through Token Metadata instead
+        require!(!self.listing.programmable, MarketplaceError::ProgrammableNftUnsupported);
+
+        // Create seeds for PDA signing
+        let marketplace = self.marketplace.key();
+        let seller = self.seller.key();
+        let nft = self.nft.key();
+        let nonce = self.listing.nonce.to_le_bytes();
+        let listing_seeds: &[&[u8]] = &[
+            b"listing",
+            marketplace.as_ref(),
+            seller.as_ref(),
+            nft.as_ref(),
+            &nonce,
+            &[self.listing.bump],
+        ];
+        let signer = &[listing_seeds];
+
+        // Transfer the unsold tokens back to seller
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.listing_token_account.to_account_info(),
+                mint: self.nft.to_account_info(),
+                to: self.seller_token_account.to_account_info(),
+                authority: self.listing.to_account_info(),
+            },
+            signer,
+        );
+        transfer_checked(cpi_ctx, self.listing.quantity, self.nft.decimals)?;
+
+        // Return the empty vault's rent to the seller
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.listing_token_account.to_account_info(),
+                destination: self.seller.to_account_info(),
+                authority: self.listing.to_account_info(),
+            },
+            signer,
+        );
+        close_account(cpi_ctx)?;
+
+        self.listing.is_active = false;
+        self.marketplace.listing_closed();
+        self.seller_stats.listing_closed();
+        ListingIndex::unindex(
+            &mut self.marketplace,
+            &mut self.listing,
+            self.listing_index.as_deref_mut(),
+            self.last_listing_index.as_deref_mut(),
+            self.moved_listing.as_ref(),
+        )?;
+        let reward = self.pay_crank_reward()?;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &ExpiredListingCleanedEvent {
+            listing: self.listing.key(),
+            seller,
+            nft,
+            cleaned_by: self.cleaner.key(),
+            expiry: self.listing.expiry,
+            reward,
+        })?;
+
+        Ok(())
+    }
+
+    /// Pay the cleaner the marketplace's crank reward out of the treasury
+    /// - Only lamports above the treasury's rent-exempt minimum are spent
+    ///
+    /// # Returns
+    /// * `Result<u64>` - The reward paid, 0 when unset or the treasury cannot cover it
+    fn pay_crank_reward(&self) -> Result<u64> {
+        let rent_floor = Rent::get()?.minimum_balance(0);
+        let available = self.treasury.lamports().saturating_sub(rent_floor);
+        let reward = self.marketplace.crank_reward(available);
+        if reward == 0 {
+            return Ok(0);
+        }
+
+        // Create seeds for PDA signing
+        let marketplace = self.marketplace.key();
+        let treasury_seeds: &[&[u8]] = &[
+            b"treasury",
+            marketplace.as_ref(),
+            &[self.marketplace.treasury_bump],
+        ];
+        let signer = &[treasury_seeds];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.treasury.to_account_info(),
+                to: self.cleaner.to_account_info(),
+            },
+            signer,
+        );
+        transfer(cpi_ctx, reward)?;
+
+        Ok(reward)
+    }
+}
+
+#[event]
+pub struct ExpiredListingCleanedEvent {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub nft: Pubkey,
+    pub cleaned_by: Pubkey,
+    pub expiry: i64,
+    pub reward: u64,
+}
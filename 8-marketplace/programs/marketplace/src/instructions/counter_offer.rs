@@ -0,0 +1,106 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    constants::COUNTER_OFFER_SECS,
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Listing, Marketplace, Offer},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CounterOffer<'info> {
+    /// The seller who listed the NFT
+    /// - Must sign and match the seller stored in the listing
+    pub seller: Signer<'info>,
+
+    /// The NFT mint account of the listing
+    pub nft: Account<'info, Mint>,
+
+    /// The listing the offer was made on
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    #[account(
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            listing.seller.as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = seller @ MarketplaceError::NotListingSeller,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    /// The offer being countered
+    /// - Uses PDA with listing and buyer as seeds
+    /// - Records the counter amount and its expiry
+    #[account(
+        mut,
+        seeds = [b"offer", listing.key().as_ref(), offer.buyer.as_ref()],
+        bump = offer.bump,
+    )]
+    pub offer: Account<'info, Offer>,
+
+    /// The marketplace state account for validation
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> CounterOffer<'info> {
+    /// Counter the buyer's offer with another amount
+    /// - The counter-offer stays open for `COUNTER_OFFER_SECS`, at most until the offer expires
+    /// - Countering again replaces the open counter-offer
+    ///
+    /// # Arguments
+    /// * `amount` - The amount in lamports the seller would sell for
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn counter_offer(&mut self, amount: u64, event_authority_bump: u8) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+        require!(!self.offer.is_expired(now), MarketplaceError::OfferExpired);
+        // Accepting a counter-offer tops up or refunds lamports
+        require!(self.offer.payment_mint.is_none(), MarketplaceError::NativePaymentOnly);
+        require!(
+            amount > 0 && amount != self.offer.amount,
+            MarketplaceError::InvalidCounterOffer
+        );
+
+        let expiry = self.offer.expiry.min(now + COUNTER_OFFER_SECS);
+        self.offer.counter_amount = Some(amount);
+        self.offer.counter_expiry = expiry;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &CounterOfferMadeEvent {
+            offer: self.offer.key(),
+            listing: self.listing.key(),
+            buyer: self.offer.buyer,
+            offer_amount: self.offer.amount,
+            counter_amount: amount,
+            expiry,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct CounterOfferMadeEvent {
+    pub offer: Pubkey,
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub offer_amount: u64,
+    pub counter_amount: u64,
+    pub expiry: i64,
+}
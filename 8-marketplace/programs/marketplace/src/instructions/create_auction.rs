@@ -0,0 +1,273 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{MasterEditionAccount, Metadata, MetadataAccount},
+    token::{transfer_checked, Mint, Token, TokenAccount, TransferChecked},
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    programmable::is_programmable,
+    state::{Auction, Blacklist, CollectionConfig, Marketplace},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateAuction<'info> {
+    /// The seller who owns the NFT and is creating the auction
+    /// - Pays for the auction accounts and funds the bid escrow's rent
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// The NFT mint account to be auctioned
+    pub nft: Box<Account<'info, Mint>>,
+
+    /// The auction state account
+    /// - Stores seller, mint, bidding rules and the highest bid
+    /// - Uses PDA with marketplace, seller, and NFT mint as seeds
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + Auction::INIT_SPACE,
+        seeds = [
+            b"auction",
+            marketplace.key().as_ref(),
+            seller.key().as_ref(),
+            nft.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+
+    /// Token account that will hold the NFT during the auction
+    /// - Owned by the auction PDA
+    #[account(
+        init,
+        payer = seller,
+        associated_token::mint = nft,
+        associated_token::authority = auction,
+    )]
+    pub auction_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The seller's token account containing the NFT
+    #[account(
+        mut,
+        associated_token::mint = nft,
+        associated_token::authority = seller,
+    )]
+    pub seller_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Escrow holding the highest bid
+    /// - System owned PDA with auction key as seed
+    /// - Funded with the rent-exempt minimum so bids of any size can be refunded
+    #[account(
+        mut,
+        seeds = [b"bid_escrow", auction.key().as_ref()],
+        bump,
+    )]
+    pub bid_escrow: SystemAccount<'info>,
+
+    /// The marketplace state account
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The collection mint that this NFT belongs to
+    pub collection_mint: Box<Account<'info, Mint>>,
+
+    /// The collection config approving the collection for sale
+    /// CHECK: Address is pinned by seeds; the account is loaded in `CollectionConfig::verify_nft`
+    #[account(
+        seeds = [
+            b"collection",
+            marketplace.key().as_ref(),
+            collection_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub collection_config: UncheckedAccount<'info>,
+
+    /// The metadata account for the NFT
+    /// - Must be the metadata PDA derived from the NFT mint
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            nft.key().as_ref(),
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub metadata: Box<Account<'info, MetadataAccount>>,
+
+    /// The master edition account for the NFT
+    /// - Proves this is a valid NFT (not just a token)
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            nft.key().as_ref(),
+            b"edition"
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub master_edition: Box<Account<'info, MasterEditionAccount>>,
+
+    /// The seller's blacklist entry, which must not exist
+    /// - Uses PDA with marketplace and seller as seeds
+    ///
+    /// CHECK: Address is pinned by seeds; only its existence is checked
+    #[account(
+        seeds = [b"blacklist", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump,
+        constraint = !Blacklist::is_blacklisted(&seller_blacklist) @ MarketplaceError::WalletBlacklisted,
+    )]
+    pub seller_blacklist: UncheckedAccount<'info>,
+
+    /// Required programs
+    pub metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> CreateAuction<'info> {
+    /// Initialize the auction and escrow the NFT
+    ///
+    /// # Arguments
+    /// * `start_price` - The lowest accepted first bid in lamports
+    /// * `min_increment` - The amount each new bid must add to the highest bid
+    /// * `end_time` - Unix timestamp at which bidding closes
+    /// * `reserve_price` - The lowest highest bid the NFT is sold for; 0 sets no reserve
+    /// * `reserve_hidden` - Whether the reserve is left out of the auction's events
+    /// * `buy_now_price` - The price ending the auction at once, at least the start and reserve
+    ///   prices; None disables buy-now
+    /// * `bumps` - PDA bump values for the auction and bid escrow
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_auction(
+        &mut self,
+        start_price: u64,
+        min_increment: u64,
+        end_time: i64,
+        reserve_price: u64,
+        reserve_hidden: bool,
+        buy_now_price: Option<u64>,
+        bumps: CreateAuctionBumps,
+    ) -> Result<()> {
+        require!(start_price > 0, MarketplaceError::InvalidPrice);
+        // A buy-now price is a price the auction can sell at too
+        let buy_now_in_bounds = match buy_now_price {
+            Some(price) => self.marketplace.price_in_bounds(price),
+            None => true,
+        };
+        require!(
+            self.marketplace.price_in_bounds(start_price) && buy_now_in_bounds,
+            MarketplaceError::PriceOutOfBounds
+        );
+        require!(
+            !matches!(buy_now_price, Some(price) if price < start_price || price < reserve_price),
+            MarketplaceError::InvalidBuyNowPrice
+        );
+        require!(
+            end_time > Clock::get()?.unix_timestamp,
+            MarketplaceError::InvalidAuctionEnd
+        );
+        // Bids are escrowed in lamports
+        require!(
+            self.marketplace.payment_mint.is_none(),
+            MarketplaceError::NativePaymentOnly
+        );
+        // Auctions move the NFT with plain SPL transfers
+        require!(
+            !is_programmable(&self.metadata),
+            MarketplaceError::ProgrammableNftUnsupported
+        );
+        CollectionConfig::verify_nft(
+            &self.marketplace,
+            &self.metadata,
+            &self.collection_mint.key(),
+            &self.collection_config,
+        )?;
+
+        self.auction.set_inner(Auction {
+            seller: self.seller.key(),
+            mint: self.nft.key(),
+            start_price,
+            min_increment,
+            end_time,
+            highest_bid: 0,
+            highest_bidder: None,
+            bump: bumps.auction,
+            escrow_bump: bumps.bid_escrow,
+            reserve_price,
+            reserve_hidden,
+            buy_now_price,
+        });
+
+        // Fund the escrow's rent so it stays rent exempt between bids
+        let rent_floor = Rent::get()?.minimum_balance(0);
+        let escrow_top_up = rent_floor.saturating_sub(self.bid_escrow.lamports());
+        if escrow_top_up > 0 {
+            let cpi_ctx = CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.seller.to_account_info(),
+                    to: self.bid_escrow.to_account_info(),
+                },
+            );
+            transfer(cpi_ctx, escrow_top_up)?;
+        }
+
+        // Transfer exactly 1 NFT into the auction vault
+        let cpi_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.seller_token_account.to_account_info(),
+                mint: self.nft.to_account_info(),
+                to: self.auction_token_account.to_account_info(),
+                authority: self.seller.to_account_info(),
+            },
+        );
+        transfer_checked(cpi_ctx, 1, self.nft.decimals)?;
+
+        emit_cpi(&self.event_authority, bumps.event_authority, &AuctionCreatedEvent {
+            auction: self.auction.key(),
+            seller: self.seller.key(),
+            nft: self.nft.key(),
+            start_price,
+            min_increment,
+            end_time,
+            reserve_price: (!reserve_hidden).then_some(reserve_price),
+            buy_now_price,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct AuctionCreatedEvent {
+    pub auction: Pubkey,
+    pub seller: Pubkey,
+    pub nft: Pubkey,
+    pub start_price: u64,
+    pub min_increment: u64,
+    pub end_time: i64,
+    /// None when the seller hid the reserve
+    pub reserve_price: Option<u64>,
+    pub buy_now_price: Option<u64>,
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Marketplace, SellerStats};
+
+#[derive(Accounts)]
+pub struct CreateSellerStats<'info> {
+    /// The seller creating their statistics account
+    /// - Pays the account rent; listing creates the account otherwise
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// The seller statistics account
+    /// - Uses PDA with marketplace and seller as seeds
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + SellerStats::INIT_SPACE,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump,
+    )]
+    pub seller_stats: Account<'info, SellerStats>,
+
+    /// The marketplace state account for validation
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// Required system program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateSellerStats<'info> {
+    /// Start the seller's statistics at zero
+    ///
+    /// # Arguments
+    /// * `bumps` - PDA bump values for the statistics account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn create_seller_stats(&mut self, bumps: CreateSellerStatsBumps) -> Result<()> {
+        self.seller_stats.set_inner(SellerStats::new(
+            self.marketplace.key(),
+            self.seller.key(),
+            bumps.seller_stats,
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{InstallmentPlan, Listing, Marketplace, SellerStats},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DefaultInstallment<'info> {
+    /// The seller who listed the NFT
+    /// - Receives the forfeited share of the installments
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// The buyer of the installment plan
+    /// - Refunded the rest of the installments and the plan rent
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+
+    /// The NFT mint account of the listing
+    pub nft: InterfaceAccount<'info, Mint>,
+
+    /// The marketplace state account
+    /// - Supplies the grace period and forfeit share
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// The listing the plan was buying
+    /// - Reactivated with the NFT still in its vault
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            seller.key().as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    /// The defaulted installment plan
+    /// - Closed to the buyer once the installments are paid out
+    #[account(
+        mut,
+        seeds = [b"installment", listing.key().as_ref()],
+        bump = installment_plan.bump,
+        has_one = buyer @ MarketplaceError::Unauthorized,
+        has_one = seller @ MarketplaceError::Unauthorized,
+        has_one = listing @ MarketplaceError::Unauthorized,
+        close = buyer,
+    )]
+    pub installment_plan: Account<'info, InstallmentPlan>,
+
+    /// The seller's statistics account
+    /// - Picks the seller's fee tier for the forfeited share
+    #[account(
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump = seller_stats.bump,
+    )]
+    pub seller_stats: Account<'info, SellerStats>,
+
+    /// The account sale fees are paid to
+    /// - Must match the marketplace's configured fee recipient
+    #[account(
+        mut,
+        address = marketplace.fee_recipient @ MarketplaceError::InvalidFeeRecipient,
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+}
+
+impl<'info> DefaultInstallment<'info> {
+    /// End a plan whose next installment is overdue past the grace period
+    /// - The seller keeps the marketplace's forfeit share of the payments, less the usual fee
+    /// - The buyer is refunded the rest and the NFT is listed again
+    ///
+    /// # Arguments
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn default_installment(&mut self, event_authority_bump: u8) -> Result<()> {
+        require!(
+            self.installment_plan.is_defaulted(
+                Clock::get()?.unix_timestamp,
+                self.marketplace.installment_grace_secs
+            ),
+            MarketplaceError::InstallmentNotDefaulted
+        );
+
+        let paid = self.installment_plan.paid;
+        let forfeited = self.marketplace.installment_forfeit(paid)?;
+        let refunded = paid - forfeited;
+
+        // The forfeited share is paid out like a sale, with the seller's fee tier at this time
+        let fee_bps = self.marketplace.fee_bps_for_volume(self.seller_stats.volume);
+        let split = self.marketplace.split_payment(forfeited, fee_bps, false)?;
+        require!(
+            split.total() == Some(forfeited),
+            MarketplaceError::PaymentSplitMismatch
+        );
+
+        // The plan account is program owned, so lamports are moved directly
+        self.installment_plan.sub_lamports(paid)?;
+        self.fee_recipient.add_lamports(split.marketplace_fee)?;
+        self.seller.add_lamports(split.seller_proceeds)?;
+        self.buyer.add_lamports(refunded)?;
+
+        self.listing.is_active = true;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &InstallmentDefaultedEvent {
+            installment_plan: self.installment_plan.key(),
+            listing: self.listing.key(),
+            buyer: self.buyer.key(),
+            seller: self.seller.key(),
+            paid,
+            forfeited,
+            refunded,
+            marketplace_fee: split.marketplace_fee,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct InstallmentDefaultedEvent {
+    pub installment_plan: Pubkey,
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub paid: u64,
+    pub forfeited: u64,
+    pub refunded: u64,
+    pub marketplace_fee: u64,
+}
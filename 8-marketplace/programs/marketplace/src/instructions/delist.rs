@@ -1,44 +1,57 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    token::{transfer_checked, Token, TransferChecked, Mint, TokenAccount},
-    // token_interface::{},
+    associated_token::AssociatedToken,
+    metadata::{thaw_delegated_account, Metadata, ThawDelegatedAccount},
+    token_interface::{
+        close_account, revoke, transfer_checked, CloseAccount, Mint, Revoke, TokenAccount,
+        TokenInterface, TransferChecked,
+    },
 };
 
 use crate::{
     error::MarketplaceError,
-    state::{Listing, Marketplace},
+    programmable::ProgrammableTransfer,
+    state::{CollectionStats, Listing, ListingIndex, Marketplace, SellerStats},
 };
 
 #[derive(Accounts)]
 pub struct DelistNft<'info> {
     /// The NFT mint account being delisted
-    pub nft: Account<'info, Mint>,
+    /// - Owned by the SPL Token or the Token-2022 program
+    #[account(mint::token_program = token_program)]
+    pub nft: InterfaceAccount<'info, Mint>,
 
     /// The listing account to be closed
-    /// - Must match the PDA derived from marketplace, seller, and NFT
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    /// - Derived from the stored seller so a wrong signer gets a dedicated error
     /// - Closed and rent refunded to seller after successful delisting
     #[account(
         mut,
         seeds = [
             b"listing",
             marketplace.key().as_ref(),
-            seller.key().as_ref(),
+            listing.seller.as_ref(),
             nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
         ],
-        bump,
+        bump = listing.bump,
+        has_one = seller @ MarketplaceError::NotListingSeller,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
         close = seller
     )]
     pub listing: Account<'info, Listing>,
 
     /// Token account holding the NFT during listing
     /// - Owned by the listing PDA
-    /// - Will be emptied during delisting
+    /// - Emptied and closed during delisting, rent refunded to seller
+    /// - Only required for escrowed listings
     #[account(
         mut,
         associated_token::mint = nft,
-        associated_token::authority = listing
+        associated_token::authority = listing,
+        associated_token::token_program = token_program,
     )]
-    pub listing_token_account: Account<'info, TokenAccount>,
+    pub listing_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
 
     /// The seller who originally listed the NFT
     /// - Must be the same as the seller in the listing
@@ -48,28 +61,127 @@ pub struct DelistNft<'info> {
 
     /// The seller's token account to receive the NFT
     /// - Must be owned by the seller
-    /// - Will receive the NFT back
+    /// - Will receive the NFT back, or be thawed and revoked for escrowless listings
     #[account(
         mut,
         associated_token::mint = nft,
         associated_token::authority = seller,
+        associated_token::token_program = token_program,
     )]
-    pub seller_token_account: Account<'info, TokenAccount>,
+    pub seller_token_account: InterfaceAccount<'info, TokenAccount>,
 
     /// The marketplace state account for validation
+    /// - Updates the marketplace statistics
     #[account(
-        seeds = [b"marketplace"],
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
         bump = marketplace.bump,
     )]
     pub marketplace: Account<'info, Marketplace>,
 
+    /// The seller's statistics account
+    /// - Counts the closed listing off the seller's active listings
+    #[account(
+        mut,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump = seller_stats.bump,
+    )]
+    pub seller_stats: Box<Account<'info, SellerStats>>,
+
+    /// The listing index page holding the listing's entry
+    /// - Only required for listings in the marketplace's listing index
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing index page holding the index's last entry
+    /// - Only required when it is another page than `listing_index`
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub last_listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing at the index's last position, moved into the freed position
+    /// - Only required when it is another listing than the one closing
+    ///
+    /// CHECK: Matched against the index entry in `ListingIndex::unindex`
+    #[account(mut)]
+    pub moved_listing: Option<UncheckedAccount<'info>>,
+
+    /// The statistics of the listing's collection
+    /// - Forgets the floor when it was this listing's
+    /// - Optional; must belong to the listing's marketplace and collection
+    #[account(
+        mut,
+        constraint = collection_stats.marketplace == marketplace.key()
+            && listing.collection == Some(collection_stats.collection)
+            @ MarketplaceError::InvalidCollectionStats,
+    )]
+    pub collection_stats: Option<Box<Account<'info, CollectionStats>>>,
+
+    /// The master edition account for the NFT
+    /// - Only required for escrowless listings, to thaw the seller's token account,
+    ///   and for programmable NFTs
+    ///
+    /// CHECK: Validated by the metadata program against the mint during thaw or transfer
+    pub master_edition: Option<UncheckedAccount<'info>>,
+
+    /// The metadata account for the NFT
+    /// - Only required for programmable NFTs
+    ///
+    /// CHECK: Validated by the metadata program during the transfer
+    #[account(mut)]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// The token record of the listing's vault
+    /// - Only required for programmable NFTs
+    ///
+    /// CHECK: Validated by the metadata program during the transfer
+    #[account(mut)]
+    pub listing_token_record: Option<UncheckedAccount<'info>>,
+
+    /// The token record of the seller's token account
+    /// - Only required for programmable NFTs
+    ///
+    /// CHECK: Validated by the metadata program during the transfer
+    #[account(mut)]
+    pub seller_token_record: Option<UncheckedAccount<'info>>,
+
+    /// The rule set of the programmable NFT
+    /// - Only required for programmable NFTs with a rule set
+    ///
+    /// CHECK: Validated by the metadata program against the NFT's programmable config
+    pub authorization_rules: Option<UncheckedAccount<'info>>,
+
+    /// The Token Auth Rules program
+    /// - Only required for programmable NFTs with a rule set
+    ///
+    /// CHECK: Validated by the metadata program
+    pub authorization_rules_program: Option<UncheckedAccount<'info>>,
+
+    /// The instructions sysvar read by the metadata program
+    /// - Only required for programmable NFTs
+    ///
+    /// CHECK: Address is pinned to the instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub sysvar_instructions: Option<UncheckedAccount<'info>>,
+
     /// Required programs
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    /// The token program owning the NFT mint, SPL Token or Token-2022
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Metadata program, only required for escrowless listings and programmable NFTs
+    pub metadata_program: Option<Program<'info, Metadata>>,
+
+    /// Associated token program, only required for programmable NFTs
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
 }
 
 impl<'info> DelistNft<'info> {
     /// Transfer the NFT back to the seller and validate listing state
+    /// - Closes the emptied vault, refunding its rent to the seller
     /// 
     /// # Returns
     /// * `Result<()>` - Success or error from the transfer
@@ -79,25 +191,52 @@ impl<'info> DelistNft<'info> {
             self.listing.is_active && self.listing.seller == self.seller.key(),
             MarketplaceError::ListingNotActive
         );
+        self.marketplace.listing_closed();
+        self.seller_stats.listing_closed();
+        if let Some(collection_stats) = self.collection_stats.as_mut() {
+            collection_stats.listing_closed(self.listing.key());
+        }
+        ListingIndex::unindex(
+            &mut self.marketplace,
+            &mut self.listing,
+            self.listing_index.as_deref_mut(),
+            self.last_listing_index.as_deref_mut(),
+            self.moved_listing.as_ref(),
+        )?;
 
         // Create seeds for PDA signing
         let marketplace = self.marketplace.key();
         let seller = self.seller.key();
         let nft = self.nft.key();
+        let nonce = self.listing.nonce.to_le_bytes();
         let listing_seeds: &[&[u8]] = &[
             b"listing",
             marketplace.as_ref(),
             seller.as_ref(),
             nft.as_ref(),
+            &nonce,
             &[self.listing.bump],
         ];
         let signer = &[listing_seeds];
 
+        if self.listing.escrowless {
+            return self.thaw_and_revoke(signer);
+        }
+        // pNFTs are frozen in the vault and move through Token Metadata
+        if self.listing.programmable {
+            return self.programmable_transfer()?.invoke_signed(signer);
+        }
+
+        let listing_token_account = self
+            .listing_token_account
+            .as_ref()
+            .ok_or(MarketplaceError::MissingListingTokenAccount)?;
+
         // Create CPI context with PDA signer
         let cpi_ctx = CpiContext::new_with_signer(
             self.token_program.to_account_info(),
             TransferChecked {
-                from: self.listing_token_account.to_account_info(),
+                from: listing_token_account.to_account_info(),
                 mint: self.nft.to_account_info(),
                 to: self.seller_token_account.to_account_info(),
                 authority: self.listing.to_account_info(),
@@ -105,7 +244,114 @@ impl<'info> DelistNft<'info> {
             signer,
         );
 
-        // Transfer the NFT back to seller
-        transfer_checked(cpi_ctx, 1, self.nft.decimals)
+        // Transfer the unsold tokens back to seller
+        transfer_checked(cpi_ctx, self.listing.quantity, self.nft.decimals)?;
+
+        // Return the empty vault's rent to the seller
+        let close_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: listing_token_account.to_account_info(),
+                destination: self.seller.to_account_info(),
+                authority: self.listing.to_account_info(),
+            },
+            signer,
+        );
+        close_account(close_ctx)
+    }
+
+    /// Collect the accounts of a Token Metadata transfer from the vault back to the seller
+    ///
+    /// # Returns
+    /// * `Result<ProgrammableTransfer>` - The transfer, or an error if a pNFT account is missing
+    fn programmable_transfer(&self) -> Result<ProgrammableTransfer<'info>> {
+        let listing_token_account = self
+            .listing_token_account
+            .as_ref()
+            .ok_or(MarketplaceError::MissingListingTokenAccount)?;
+        let (
+            Some(master_edition),
+            Some(metadata),
+            Some(listing_token_record),
+            Some(seller_token_record),
+            Some(sysvar_instructions),
+            Some(metadata_program),
+            Some(associated_token_program),
+        ) = (
+            self.master_edition.as_ref(),
+            self.metadata.as_ref(),
+            self.listing_token_record.as_ref(),
+            self.seller_token_record.as_ref(),
+            self.sysvar_instructions.as_ref(),
+            self.metadata_program.as_ref(),
+            self.associated_token_program.as_ref(),
+        )
+        else {
+            return err!(MarketplaceError::MissingProgrammableAccounts);
+        };
+
+        Ok(ProgrammableTransfer {
+            source: listing_token_account.to_account_info(),
+            source_owner: self.listing.to_account_info(),
+            source_token_record: listing_token_record.to_account_info(),
+            destination: self.seller_token_account.to_account_info(),
+            destination_owner: self.seller.to_account_info(),
+            destination_token_record: seller_token_record.to_account_info(),
+            mint: self.nft.to_account_info(),
+            metadata: metadata.to_account_info(),
+            edition: master_edition.to_account_info(),
+            authority: self.listing.to_account_info(),
+            payer: self.seller.to_account_info(),
+            authorization_rules: self.authorization_rules.as_ref().map(|a| a.to_account_info()),
+            authorization_rules_program: self
+                .authorization_rules_program
+                .as_ref()
+                .map(|a| a.to_account_info()),
+            sysvar_instructions: sysvar_instructions.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            associated_token_program: associated_token_program.to_account_info(),
+            metadata_program: metadata_program.to_account_info(),
+        })
+    }
+
+    /// Give an escrowless NFT back to the seller
+    /// - Thaws the seller's token account and removes the listing as delegate
+    ///
+    /// # Arguments
+    /// * `signer` - The listing PDA signer seeds
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error from the CPIs
+    fn thaw_and_revoke(&self, signer: &[&[&[u8]]]) -> Result<()> {
+        let (Some(master_edition), Some(metadata_program)) =
+            (self.master_edition.as_ref(), self.metadata_program.as_ref())
+        else {
+            return err!(MarketplaceError::MissingEscrowlessAccounts);
+        };
+
+        let thaw_ctx = CpiContext::new_with_signer(
+            metadata_program.to_account_info(),
+            ThawDelegatedAccount {
+                metadata: metadata_program.to_account_info(),
+                delegate: self.listing.to_account_info(),
+                token_account: self.seller_token_account.to_account_info(),
+                edition: master_edition.to_account_info(),
+                mint: self.nft.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+            signer,
+        );
+        thaw_delegated_account(thaw_ctx)?;
+
+        // The seller owns the token account, so they revoke the delegate themselves
+        let revoke_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            Revoke {
+                source: self.seller_token_account.to_account_info(),
+                authority: self.seller.to_account_info(),
+            },
+        );
+        revoke(revoke_ctx)
     }
-}
\ No newline at end of file
+}
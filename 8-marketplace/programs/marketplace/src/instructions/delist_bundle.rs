@@ -0,0 +1,172 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address,
+    token::{close_account, transfer_checked, CloseAccount, Mint, Token, TransferChecked},
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{BundleListing, Marketplace, SellerStats},
+};
+
+/// Number of remaining accounts describing one NFT of a bundle delisting
+const ACCOUNTS_PER_BUNDLE_DELISTING: usize = 3;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DelistBundle<'info> {
+    /// The seller who listed the bundle
+    /// - Receives the NFTs back and the bundle and vault rent
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// The marketplace state account for validation
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// The bundle being delisted
+    /// - Closed to the seller once every NFT is back
+    #[account(
+        mut,
+        seeds = [
+            b"bundle",
+            marketplace.key().as_ref(),
+            seller.key().as_ref(),
+            bundle.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = bundle.bump,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+        close = seller,
+    )]
+    pub bundle: Account<'info, BundleListing>,
+
+    /// The seller's statistics account
+    /// - Counts the bundle off the seller's active listings
+    #[account(
+        mut,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump = seller_stats.bump,
+    )]
+    pub seller_stats: Account<'info, SellerStats>,
+
+    /// Required programs
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> DelistBundle<'info> {
+    /// Return every NFT of the bundle to the seller and close its vaults
+    ///
+    /// # Arguments
+    /// * `items` - Groups of (mint, vault, seller token account) accounts, one per bundled NFT
+    ///   in listing order
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn delist_bundle(
+        &mut self,
+        items: &'info [AccountInfo<'info>],
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        let groups = items.chunks_exact(ACCOUNTS_PER_BUNDLE_DELISTING);
+        require!(groups.remainder().is_empty(), MarketplaceError::InvalidBundleSize);
+        let mints: Vec<Pubkey> = groups.clone().map(|group| group[0].key()).collect();
+        require!(
+            self.bundle.is_whole_bundle(&mints),
+            MarketplaceError::InvalidBundleAccount
+        );
+
+        for group in groups {
+            self.return_item(group)?;
+        }
+
+        self.marketplace.listing_closed();
+        self.seller_stats.listing_closed();
+
+        emit_cpi(&self.event_authority, event_authority_bump, &BundleDelistedEvent {
+            bundle: self.bundle.key(),
+            seller: self.seller.key(),
+            mints,
+        })?;
+
+        Ok(())
+    }
+
+    /// Validate one (mint, vault, seller token account) group and return its NFT
+    /// - Closes the emptied vault, refunding the seller
+    fn return_item(&self, group: &'info [AccountInfo<'info>]) -> Result<()> {
+        let [mint_info, vault, seller_token_account] = group else {
+            return err!(MarketplaceError::InvalidBundleSize);
+        };
+
+        let mint = Account::<Mint>::try_from(mint_info)
+            .map_err(|_| MarketplaceError::InvalidBundleAccount)?;
+        let nft = mint.key();
+        let seller = self.seller.key();
+        let bundle = self.bundle.key();
+        require_keys_eq!(
+            vault.key(),
+            get_associated_token_address(&bundle, &nft),
+            MarketplaceError::InvalidBundleAccount
+        );
+        require_keys_eq!(
+            seller_token_account.key(),
+            get_associated_token_address(&seller, &nft),
+            MarketplaceError::InvalidBundleAccount
+        );
+
+        // Create seeds for PDA signing
+        let marketplace = self.marketplace.key();
+        let nonce = self.bundle.nonce.to_le_bytes();
+        let bundle_seeds: &[&[u8]] = &[
+            b"bundle",
+            marketplace.as_ref(),
+            seller.as_ref(),
+            &nonce,
+            &[self.bundle.bump],
+        ];
+        let signer = &[bundle_seeds];
+
+        // Transfer the NFT back to seller
+        let transfer_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: vault.clone(),
+                mint: mint_info.clone(),
+                to: seller_token_account.clone(),
+                authority: self.bundle.to_account_info(),
+            },
+            signer,
+        );
+        transfer_checked(transfer_ctx, 1, mint.decimals)?;
+
+        // Return the empty vault's rent to the seller
+        let close_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: vault.clone(),
+                destination: self.seller.to_account_info(),
+                authority: self.bundle.to_account_info(),
+            },
+            signer,
+        );
+        close_account(close_ctx)
+    }
+}
+
+#[event]
+pub struct BundleDelistedEvent {
+    pub bundle: Pubkey,
+    pub seller: Pubkey,
+    pub mints: Vec<Pubkey>,
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::MarketplaceError, event_cpi::emit_cpi, state::SaleEscrow};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DisputeSale<'info> {
+    /// The buyer who purchased the NFT
+    /// - Must sign and match the buyer stored in the escrow
+    pub buyer: Signer<'info>,
+
+    /// The sale escrow being disputed
+    /// - Flagged so only the marketplace admin can resolve it
+    #[account(
+        mut,
+        seeds = [b"sale_escrow", sale_escrow.listing.as_ref(), buyer.key().as_ref()],
+        bump = sale_escrow.bump,
+        has_one = buyer,
+    )]
+    pub sale_escrow: Account<'info, SaleEscrow>,
+}
+
+impl<'info> DisputeSale<'info> {
+    /// Flag the sale as disputed within its protection window
+    ///
+    /// # Arguments
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn dispute_sale(&mut self, event_authority_bump: u8) -> Result<()> {
+        require!(
+            self.sale_escrow.can_dispute(Clock::get()?.unix_timestamp),
+            MarketplaceError::ProtectionWindowClosed
+        );
+
+        self.sale_escrow.disputed = true;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &SaleDisputedEvent {
+            sale_escrow: self.sale_escrow.key(),
+            listing: self.sale_escrow.listing,
+            seller: self.sale_escrow.seller,
+            buyer: self.buyer.key(),
+            amount: self.sale_escrow.amount,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct SaleDisputedEvent {
+    pub sale_escrow: Pubkey,
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+}
@@ -0,0 +1,99 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Listing, Marketplace},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExtendListing<'info> {
+    /// The seller who originally listed the NFT
+    /// - Must sign and match the seller stored in the listing
+    pub seller: Signer<'info>,
+
+    /// The NFT mint account of the listing
+    pub nft: Account<'info, Mint>,
+
+    /// The listing account being extended
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    /// - Derived from the stored seller so a wrong signer gets a dedicated error
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            listing.seller.as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = seller @ MarketplaceError::NotListingSeller,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    /// The marketplace state account holding the maximum listing duration
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> ExtendListing<'info> {
+    /// Move the expiry of a listing without touching the NFT
+    /// - Also revives a listing that expired but has not been cleaned up yet
+    ///
+    /// # Arguments
+    /// * `new_expiry` - Unix timestamp from which the listing can no longer be purchased
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn extend_listing(&mut self, new_expiry: i64, event_authority_bump: u8) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+        require!(new_expiry > now, MarketplaceError::InvalidListingExpiry);
+        require!(
+            !self.marketplace.exceeds_listing_duration(now, new_expiry),
+            MarketplaceError::ListingDurationTooLong
+        );
+        // A scheduled sale must still open before the listing expires
+        require!(
+            self.listing.start_ts < new_expiry,
+            MarketplaceError::InvalidListingStart
+        );
+
+        // Purchases check the expiry stored in the account, so the new one applies at once
+        let old_expiry = self.listing.expiry;
+        let revived = self.listing.is_expired(now);
+        self.listing.expiry = new_expiry;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &ListingExtendedEvent {
+            listing: self.listing.key(),
+            seller: self.seller.key(),
+            old_expiry,
+            new_expiry,
+            revived,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct ListingExtendedEvent {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub old_expiry: i64,
+    pub new_expiry: i64,
+    /// Whether the listing had already expired
+    pub revived: bool,
+}
@@ -0,0 +1,243 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{self, transfer, Transfer},
+    Discriminator,
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{CollectionOffer, Marketplace, NftOffer, Offer},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct GarbageCollect<'info> {
+    /// Anyone cleaning up the account
+    /// - Paid the marketplace's crank bounty out of the reclaimed rent
+    #[account(mut)]
+    pub collector: Signer<'info>,
+
+    /// The account being collected
+    /// - Must be in one of the states `GarbageKind` enumerates
+    ///
+    /// CHECK: Owner, discriminator and address are checked in `garbage_collect`
+    #[account(mut)]
+    pub target: UncheckedAccount<'info>,
+
+    /// The rightful owner of the escrowed funds
+    /// - The buyer for offers, the seller for a bid escrow
+    /// - Refunded everything the account holds but the bounty
+    #[account(mut)]
+    pub owner: SystemAccount<'info>,
+
+    /// The marketplace the account belongs to
+    /// - Sets the bounty
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// Proof the account can no longer be acted on
+    /// - The listing of a listing offer, or the auction of a bid escrow, which must be closed
+    ///
+    /// CHECK: Only its address and whether it is closed are read
+    pub proof: Option<UncheckedAccount<'info>>,
+
+    /// The NFT a bid escrow's auction sold, deriving the auction's address
+    /// - Only required for bid escrows
+    ///
+    /// CHECK: Only its address is read
+    pub nft: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> GarbageCollect<'info> {
+    /// Close an account nothing can act on anymore, refunding its owner and paying the bounty
+    /// - Anything outside the states `GarbageKind` enumerates is rejected, including auctions
+    ///   awaiting `settle_auction`, expired offers on open listings and token offers
+    ///
+    /// # Arguments
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn garbage_collect(&mut self, event_authority_bump: u8) -> Result<()> {
+        let kind = self.collectable_kind(Clock::get()?.unix_timestamp)?;
+
+        let target = self.target.to_account_info();
+        let bounty = self
+            .marketplace
+            .crank_bounty(Rent::get()?.minimum_balance(target.data_len()));
+        let refunded = target
+            .lamports()
+            .checked_sub(bounty)
+            .ok_or(MarketplaceError::MathOverflow)?;
+
+        match kind {
+            GarbageKind::SettledAuctionEscrow => self.drain_bid_escrow(bounty, refunded)?,
+            // Offers escrow their lamports on the account itself, next to the rent
+            _ => {
+                target.sub_lamports(bounty + refunded)?;
+                self.collector.add_lamports(bounty)?;
+                self.owner.add_lamports(refunded)?;
+                target.assign(&system_program::ID);
+                target.realloc(0, false)?;
+            }
+        }
+
+        emit_cpi(&self.event_authority, event_authority_bump, &GarbageCollectedEvent {
+            account: target.key(),
+            kind,
+            owner: self.owner.key(),
+            collector: self.collector.key(),
+            refunded,
+            bounty,
+        })?;
+
+        Ok(())
+    }
+
+    /// The closable state the target is in
+    /// - Program accounts must belong to the marketplace and the owner passed
+    ///
+    /// # Arguments
+    /// * `now` - The current unix timestamp
+    ///
+    /// # Returns
+    /// * `Result<GarbageKind>` - The state, or `NotCollectable` for anything else
+    fn collectable_kind(&self, now: i64) -> Result<GarbageKind> {
+        let target = self.target.to_account_info();
+
+        if target.owner == &crate::ID {
+            let data = target.try_borrow_data()?;
+            if data.starts_with(Offer::DISCRIMINATOR) {
+                let offer = Offer::try_deserialize(&mut &data[..])?;
+                self.check_owner(offer.marketplace, offer.buyer)?;
+                require!(
+                    offer.is_collectable(self.proof_closed(offer.listing)?),
+                    MarketplaceError::NotCollectable
+                );
+                return Ok(GarbageKind::ClosedListingOffer);
+            }
+            if data.starts_with(NftOffer::DISCRIMINATOR) {
+                let offer = NftOffer::try_deserialize(&mut &data[..])?;
+                self.check_owner(offer.marketplace, offer.buyer)?;
+                require!(offer.is_expired(now), MarketplaceError::NotCollectable);
+                return Ok(GarbageKind::ExpiredNftOffer);
+            }
+            if data.starts_with(CollectionOffer::DISCRIMINATOR) {
+                let offer = CollectionOffer::try_deserialize(&mut &data[..])?;
+                self.check_owner(offer.marketplace, offer.buyer)?;
+                require!(offer.is_expired(now), MarketplaceError::NotCollectable);
+                return Ok(GarbageKind::ExpiredCollectionOffer);
+            }
+        } else if target.owner == &system_program::ID
+            && target.data_is_empty()
+            && target.lamports() > 0
+        {
+            // Bid escrows are system accounts, so they are recognized by their address
+            let nft = self.nft.as_ref().ok_or(MarketplaceError::NotCollectable)?;
+            let (auction, _) = Pubkey::find_program_address(
+                &[
+                    b"auction",
+                    self.marketplace.key().as_ref(),
+                    self.owner.key().as_ref(),
+                    nft.key().as_ref(),
+                ],
+                &crate::ID,
+            );
+            require_keys_eq!(
+                target.key(),
+                Self::bid_escrow_address(&auction).0,
+                MarketplaceError::NotCollectable
+            );
+            require!(self.proof_closed(auction)?, MarketplaceError::NotCollectable);
+            return Ok(GarbageKind::SettledAuctionEscrow);
+        }
+
+        err!(MarketplaceError::NotCollectable)
+    }
+
+    /// Check a program account belongs to this marketplace and refunds the owner passed
+    fn check_owner(&self, marketplace: Pubkey, owner: Pubkey) -> Result<()> {
+        require_keys_eq!(marketplace, self.marketplace.key(), MarketplaceError::NotCollectable);
+        require_keys_eq!(owner, self.owner.key(), MarketplaceError::NotCollectable);
+        Ok(())
+    }
+
+    /// Whether the proof account is the expected one and has been closed
+    ///
+    /// # Arguments
+    /// * `expected` - The address the proof must have
+    fn proof_closed(&self, expected: Pubkey) -> Result<bool> {
+        let proof = self.proof.as_ref().ok_or(MarketplaceError::NotCollectable)?;
+        require_keys_eq!(proof.key(), expected, MarketplaceError::NotCollectable);
+        Ok(proof.owner == &system_program::ID && proof.data_is_empty())
+    }
+
+    /// The bid escrow PDA of an auction and its bump
+    fn bid_escrow_address(auction: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"bid_escrow", auction.as_ref()], &crate::ID)
+    }
+
+    /// Empty a settled auction's bid escrow, paying the bounty and refunding the rest
+    /// - The escrow is a system account, so it pays out through the system program
+    fn drain_bid_escrow(&self, bounty: u64, refunded: u64) -> Result<()> {
+        // Create seeds for PDA signing
+        let (auction, bump) = match self.proof.as_ref() {
+            Some(auction) => (auction.key(), Self::bid_escrow_address(&auction.key()).1),
+            None => return err!(MarketplaceError::NotCollectable),
+        };
+        let escrow_seeds: &[&[u8]] = &[b"bid_escrow", auction.as_ref(), &[bump]];
+        let signer = &[escrow_seeds];
+
+        for (to, amount) in [
+            (self.collector.to_account_info(), bounty),
+            (self.owner.to_account_info(), refunded),
+        ] {
+            if amount > 0 {
+                let cpi_ctx = CpiContext::new_with_signer(
+                    self.system_program.to_account_info(),
+                    Transfer {
+                        from: self.target.to_account_info(),
+                        to,
+                    },
+                    signer,
+                );
+                transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The states `garbage_collect` closes, each leaving nothing that could still act on the account
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GarbageKind {
+    /// A lamport offer whose listing was closed by a purchase, delisting or cleanup
+    ClosedListingOffer,
+    /// An NFT offer past its expiry
+    ExpiredNftOffer,
+    /// A collection offer past its expiry
+    ExpiredCollectionOffer,
+    /// A bid escrow holding lamports after its auction was settled and closed
+    SettledAuctionEscrow,
+}
+
+#[event]
+pub struct GarbageCollectedEvent {
+    pub account: Pubkey,
+    pub kind: GarbageKind,
+    pub owner: Pubkey,
+    pub collector: Pubkey,
+    pub refunded: u64,
+    pub bounty: u64,
+}
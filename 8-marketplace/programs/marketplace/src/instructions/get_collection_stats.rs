@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{CollectionStats, CollectionSummary};
+
+#[derive(Accounts)]
+pub struct GetCollectionStats<'info> {
+    /// The collection statistics to read
+    #[account(
+        seeds = [
+            b"collection_stats",
+            collection_stats.marketplace.as_ref(),
+            collection_stats.collection.as_ref(),
+        ],
+        bump = collection_stats.bump,
+    )]
+    pub collection_stats: Account<'info, CollectionStats>,
+}
+
+impl<'info> GetCollectionStats<'info> {
+    /// Read the collection floor, last sale and volume
+    /// - Returned as instruction return data, so frontends can simulate the call
+    ///
+    /// # Returns
+    /// * `Result<CollectionSummary>` - The statistics, with the volume of about the last day
+    pub fn get_collection_stats(&self) -> Result<CollectionSummary> {
+        Ok(self.collection_stats.summary(Clock::get()?.unix_timestamp))
+    }
+}
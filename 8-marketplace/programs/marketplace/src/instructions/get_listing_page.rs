@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{ListingIndex, ListingPage, Marketplace};
+
+#[derive(Accounts)]
+#[instruction(page: u64)]
+pub struct GetListingPage<'info> {
+    /// The marketplace whose listing index is read
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// The requested page of the listing index
+    /// - Uses PDA with marketplace and page number as seeds
+    #[account(
+        seeds = [b"index", marketplace.key().as_ref(), page.to_le_bytes().as_ref()],
+        bump = listing_index.bump,
+    )]
+    pub listing_index: Account<'info, ListingIndex>,
+}
+
+impl<'info> GetListingPage<'info> {
+    /// Read one page of the marketplace's listing index
+    /// - Returned as instruction return data, so clients can simulate the call instead of
+    ///   scanning every program account
+    ///
+    /// # Returns
+    /// * `Result<ListingPage>` - The listing addresses on the page and the size of the index
+    pub fn get_listing_page(&self) -> Result<ListingPage> {
+        Ok(ListingPage {
+            page: self.listing_index.page,
+            listings: self.listing_index.listings.clone(),
+            total_listings: self.marketplace.listing_index_len,
+        })
+    }
+}
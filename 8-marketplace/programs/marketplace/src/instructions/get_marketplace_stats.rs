@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Marketplace, MarketplaceStats};
+
+#[derive(Accounts)]
+pub struct GetMarketplaceStats<'info> {
+    /// The marketplace state account to read statistics from
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> GetMarketplaceStats<'info> {
+    /// Read the marketplace statistics
+    /// - Returned as instruction return data, so dashboards can simulate the call
+    ///
+    /// # Returns
+    /// * `Result<MarketplaceStats>` - Volume, sales count, fees and open listings
+    pub fn get_marketplace_stats(&self) -> Result<MarketplaceStats> {
+        Ok(self.marketplace.stats())
+    }
+}
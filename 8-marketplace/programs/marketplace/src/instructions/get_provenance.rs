@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Provenance, ProvenanceHistory};
+
+#[derive(Accounts)]
+pub struct GetProvenance<'info> {
+    /// The provenance account of the NFT to read the sale history of
+    #[account(
+        seeds = [
+            b"provenance",
+            provenance.marketplace.as_ref(),
+            provenance.mint.as_ref(),
+        ],
+        bump = provenance.bump,
+    )]
+    pub provenance: Account<'info, Provenance>,
+}
+
+impl<'info> GetProvenance<'info> {
+    /// Read the sale history of an NFT
+    /// - Returned as instruction return data, so explorers can simulate the call
+    ///
+    /// # Returns
+    /// * `Result<ProvenanceHistory>` - Totals and the most recent sales, oldest first
+    pub fn get_provenance(&self) -> Result<ProvenanceHistory> {
+        Ok(ProvenanceHistory {
+            mint: self.provenance.mint,
+            total_sales: self.provenance.total_sales,
+            lifetime_volume: self.provenance.lifetime_volume,
+            sales: self.provenance.history(),
+        })
+    }
+}
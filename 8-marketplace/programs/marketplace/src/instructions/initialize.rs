@@ -1,22 +1,34 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token};
 
-use crate::{error::MarketplaceError, state::Marketplace};
+use crate::{
+    constants::{FEE_TIER_COUNT, MAX_FEE_BPS, MAX_MARKETPLACE_NAME_LEN, REWARDS_DECIMALS},
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{FeeTier, Marketplace},
+};
 
+#[event_cpi]
 #[derive(Accounts)]
+#[instruction(fee_bps: u16, fee_recipient: Pubkey, name: String)]
 pub struct InitializeMarketplace<'info> {
     /// The admin account that will manage the marketplace
     /// Must be mutable to pay for account creation
-    #[account(mut)]
+    /// - The name is checked here, before it is used as a PDA seed
+    #[account(
+        mut,
+        constraint = name.len() <= MAX_MARKETPLACE_NAME_LEN @ MarketplaceError::InvalidMarketplaceName,
+    )]
     pub admin: Signer<'info>,
 
     /// The marketplace state account
-    /// - Initialized with a PDA using "marketplace" seed
+    /// - Initialized with a PDA using "marketplace" seed, admin key and name
     /// - Stores admin pubkey, fee percentage, and bump values
     #[account(
         init,
         payer = admin,
         space = 8 + Marketplace::INIT_SPACE,
-        seeds = [b"marketplace"],
+        seeds = [b"marketplace", admin.key().as_ref(), name.as_bytes()],
         bump
     )]
     pub marketplace: Account<'info, Marketplace>,
@@ -30,38 +42,117 @@ pub struct InitializeMarketplace<'info> {
     )]
     pub treasury: SystemAccount<'info>,
 
-    /// Required system program for account creation
+    /// The SPL token mint listings are priced in
+    /// - None keeps prices in native SOL
+    pub payment_mint: Option<Account<'info, Mint>>,
+
+    /// Reward points mint for buyers and sellers
+    /// - Uses PDA with "rewards" seed and marketplace key
+    /// - Minted on each purchase by the marketplace PDA
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"rewards", marketplace.key().as_ref()],
+        bump,
+        mint::decimals = REWARDS_DECIMALS,
+        mint::authority = marketplace,
+    )]
+    pub rewards_mint: Account<'info, Mint>,
+
+    /// Required programs for account creation
     pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
 }
 
 impl<'info> InitializeMarketplace<'info> {
     /// Initialize the marketplace with admin and fee configuration
     ///
     /// # Arguments
-    /// * `fee_percentage` - The percentage fee (0-100) charged on each sale
+    /// * `fee_bps` - The fee in basis points (0-MAX_FEE_BPS) charged on each sale
+    /// * `fee_recipient` - The account sale fees are paid to, usually the treasury PDA
+    /// * `name` - Name telling apart the marketplaces of this admin
     /// * `bumps` - PDA bump values for deterministic addresses
     ///
     /// # Returns
     /// * `Result<()>` - Success or error
     pub fn initialize_marketplace(
         &mut self,
-        fee_percentage: u8,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+        name: String,
         bumps: InitializeMarketplaceBumps,
     ) -> Result<()> {
-        // Validate fee percentage is reasonable (0-100%)
-        require!(
-            fee_percentage <= 100,
-            MarketplaceError::InvalidFeePercentage
-        );
+        // Validate fee is reasonable (0-MAX_FEE_BPS)
+        require!(fee_bps <= MAX_FEE_BPS, MarketplaceError::InvalidFeeBps);
 
         // Initialize marketplace state with provided parameters
         self.marketplace.set_inner(Marketplace {
             admin: self.admin.key(),
-            fee_percentage,
+            fee_bps,
             bump: bumps.marketplace,
             treasury_bump: bumps.treasury, // Fixed: should be treasury bump, not marketplace bump
+            open_listings: false,
+            payment_mint: self.payment_mint.as_ref().map(|mint| mint.key()),
+            rewards_bump: bumps.rewards_mint,
+            // Rewards start off until the admin sets a rate
+            reward_rate_bps: 0,
+            paused: false,
+            fee_recipient,
+            total_volume: 0,
+            sales_count: 0,
+            total_fees: 0,
+            active_listings: 0,
+            name,
+            // Referrals start off until the admin sets a share
+            referral_bps: 0,
+            // Every seller pays the base fee until the admin sets tiers
+            fee_tiers: [FeeTier::default(); FEE_TIER_COUNT],
+            // Expired offers are refunded without a bounty until the admin sets one
+            crank_fee_lamports: 0,
+            // Sellers are paid directly until the admin turns pull payments on
+            pull_payments: false,
+            created_at: Clock::get()?.unix_timestamp,
+            // Expired listings are cleaned up without a reward until the admin sets one
+            crank_reward_lamports: 0,
+            // Missed installments can be defaulted at once with a full refund until the admin sets terms
+            installment_grace_secs: 0,
+            installment_forfeit_bps: 0,
+            // Reward holders pay the full fee until the admin sets a discount
+            discount_threshold: 0,
+            discount_bps: 0,
+            // Blacklisted wallets can still buy until the admin blocks them
+            block_blacklisted_buyers: false,
+            // The whole fee goes to the fee recipient until the admin sets splits
+            fee_splits: Vec::new(),
+            // Listings can be extended without limit until the admin sets a maximum duration
+            max_listing_duration: 0,
+            listing_index_len: 0,
+            // Any non-zero price is accepted until the admin sets bounds
+            min_price: 0,
+            max_price: 0,
         });
 
+        emit_cpi(&self.event_authority, bumps.event_authority, &MarketplaceInitializedEvent {
+            marketplace: self.marketplace.key(),
+            admin: self.admin.key(),
+            fee_bps,
+            treasury: self.treasury.key(),
+            fee_recipient,
+            name: self.marketplace.name.clone(),
+            payment_mint: self.marketplace.payment_mint,
+        })?;
+
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[event]
+pub struct MarketplaceInitializedEvent {
+    pub marketplace: Pubkey,
+    pub admin: Pubkey,
+    pub fee_bps: u16,
+    pub treasury: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub name: String,
+    pub payment_mint: Option<Pubkey>,
+}
@@ -0,0 +1,245 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{create, get_associated_token_address, AssociatedToken, Create},
+    metadata::{Metadata, MetadataAccount},
+    token::{transfer_checked, Mint, Token, TransferChecked},
+};
+
+use crate::{
+    constants::MAX_BUNDLE_ITEMS,
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    programmable::is_programmable,
+    state::{Blacklist, BundleListing, CollectionConfig, Marketplace, SellerStats},
+};
+
+/// Number of remaining accounts describing one NFT of a bundle listing
+const ACCOUNTS_PER_BUNDLE_ITEM: usize = 4;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ListBundle<'info> {
+    /// The seller who owns the NFTs and pays for the bundle
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Validates this is the correct marketplace instance
+    /// - Must not be paused
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        constraint = !marketplace.paused @ MarketplaceError::MarketplacePaused,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The seller's statistics account
+    /// - Uses PDA with marketplace and seller as seeds
+    /// - Created on the seller's first listing; hands out the bundle's nonce
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + SellerStats::INIT_SPACE,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump,
+    )]
+    pub seller_stats: Box<Account<'info, SellerStats>>,
+
+    /// The bundle listing account
+    /// - Uses PDA with marketplace, seller and the seller's listing nonce as seeds
+    /// - Owns the vault of every NFT in the bundle
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + BundleListing::INIT_SPACE,
+        seeds = [
+            b"bundle",
+            marketplace.key().as_ref(),
+            seller.key().as_ref(),
+            seller_stats.listing_nonce.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub bundle: Box<Account<'info, BundleListing>>,
+
+    /// The collection mint every NFT of the bundle belongs to
+    /// - Used for collection verification
+    pub collection_mint: Account<'info, Mint>,
+
+    /// The collection config approving the collection for listing
+    /// - Uses PDA with marketplace and collection mint as seeds
+    /// - Not required to exist when the marketplace has open listings
+    ///
+    /// CHECK: Address is pinned by seeds; the account is loaded in `verify_nft`
+    #[account(
+        seeds = [
+            b"collection",
+            marketplace.key().as_ref(),
+            collection_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub collection_config: UncheckedAccount<'info>,
+
+    /// The seller's blacklist entry, which must not exist
+    /// - Uses PDA with marketplace and seller as seeds
+    ///
+    /// CHECK: Address is pinned by seeds; only its existence is checked
+    #[account(
+        seeds = [b"blacklist", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump,
+        constraint = !Blacklist::is_blacklisted(&seller_blacklist) @ MarketplaceError::WalletBlacklisted,
+    )]
+    pub seller_blacklist: UncheckedAccount<'info>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub metadata_program: Program<'info, Metadata>,
+}
+
+impl<'info> ListBundle<'info> {
+    /// List the NFTs of the batch as one bundle sold for a single price
+    /// - Each NFT goes through the same checks as `list_nft` and is escrowed under the bundle
+    /// - Any failing item fails the whole instruction
+    ///
+    /// # Arguments
+    /// * `price` - The price of the whole bundle in lamports
+    /// * `items` - Groups of (mint, seller token account, vault, metadata) accounts
+    /// * `bumps` - PDA bump values for the seller statistics and bundle accounts
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn list_bundle(
+        &mut self,
+        price: u64,
+        items: &'info [AccountInfo<'info>],
+        bumps: ListBundleBumps,
+    ) -> Result<()> {
+        let groups = items.chunks_exact(ACCOUNTS_PER_BUNDLE_ITEM);
+        require!(
+            !items.is_empty()
+                && groups.remainder().is_empty()
+                && groups.len() <= MAX_BUNDLE_ITEMS as usize,
+            MarketplaceError::InvalidBundleSize
+        );
+        require!(price > 0, MarketplaceError::InvalidPrice);
+        require!(self.marketplace.price_in_bounds(price), MarketplaceError::PriceOutOfBounds);
+
+        if self.seller_stats.is_uninitialized() {
+            self.seller_stats.set_inner(SellerStats::new(
+                self.marketplace.key(),
+                self.seller.key(),
+                bumps.seller_stats,
+            ));
+        }
+        let nonce = self.seller_stats.listing_opened()?;
+        self.marketplace.listing_opened();
+
+        let mut mints = Vec::with_capacity(groups.len());
+        for group in groups {
+            mints.push(self.escrow_item(group)?);
+        }
+
+        self.bundle.set_inner(BundleListing {
+            seller: self.seller.key(),
+            marketplace: self.marketplace.key(),
+            price,
+            mints: mints.clone(),
+            nonce,
+            bump: bumps.bundle,
+        });
+
+        emit_cpi(&self.event_authority, bumps.event_authority, &BundleListedEvent {
+            bundle: self.bundle.key(),
+            seller: self.seller.key(),
+            mints,
+            price,
+        })?;
+
+        Ok(())
+    }
+
+    /// Validate one (mint, seller token account, vault, metadata) group and escrow its NFT
+    ///
+    /// # Returns
+    /// * `Result<Pubkey>` - The mint of the escrowed NFT
+    fn escrow_item(&self, group: &'info [AccountInfo<'info>]) -> Result<Pubkey> {
+        let [mint_info, seller_token_account, vault, metadata_info] = group else {
+            return err!(MarketplaceError::InvalidBundleSize);
+        };
+
+        // Owners and layouts are checked by the typed loads; addresses against the mint
+        let mint = Account::<Mint>::try_from(mint_info)
+            .map_err(|_| MarketplaceError::InvalidBundleAccount)?;
+        let metadata = Account::<MetadataAccount>::try_from(metadata_info)
+            .map_err(|_| MarketplaceError::InvalidBundleAccount)?;
+        require_keys_eq!(metadata.mint, mint.key(), MarketplaceError::InvalidBundleAccount);
+
+        let nft = mint.key();
+        let bundle = self.bundle.key();
+        require_keys_eq!(
+            seller_token_account.key(),
+            get_associated_token_address(&self.seller.key(), &nft),
+            MarketplaceError::InvalidBundleAccount
+        );
+        require_keys_eq!(
+            vault.key(),
+            get_associated_token_address(&bundle, &nft),
+            MarketplaceError::InvalidBundleAccount
+        );
+
+        // Bundles hold plain one-token escrows; pNFTs need token records per item
+        require!(mint.decimals == 0, MarketplaceError::NotSemiFungible);
+        require!(!is_programmable(&metadata), MarketplaceError::UnsupportedBundle);
+        CollectionConfig::verify_nft(
+            &self.marketplace,
+            &metadata,
+            &self.collection_mint.key(),
+            &self.collection_config.to_account_info(),
+        )?;
+
+        // Create the vault owned by the bundle; fails on a mint passed twice
+        let vault_ctx = CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            Create {
+                payer: self.seller.to_account_info(),
+                associated_token: vault.clone(),
+                authority: self.bundle.to_account_info(),
+                mint: mint_info.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+        );
+        create(vault_ctx)?;
+
+        // Escrow the NFT in the vault
+        let transfer_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: seller_token_account.clone(),
+                mint: mint_info.clone(),
+                to: vault.clone(),
+                authority: self.seller.to_account_info(),
+            },
+        );
+        transfer_checked(transfer_ctx, 1, mint.decimals)?;
+
+        Ok(nft)
+    }
+}
+
+#[event]
+pub struct BundleListedEvent {
+    pub bundle: Pubkey,
+    pub seller: Pubkey,
+    pub mints: Vec<Pubkey>,
+    pub price: u64,
+}
@@ -1,16 +1,23 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    metadata::{MasterEditionAccount, Metadata, MetadataAccount},
-    token::{transfer_checked, Token, TransferChecked, Mint, TokenAccount},
-    // token_interface::{},
+    metadata::{mpl_token_metadata::types::TokenStandard, MasterEditionAccount, Metadata, MetadataAccount},
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
 use crate::{
+    constants::{ListingCategory, MAX_PROTECTION_WINDOW_SECS},
     error::MarketplaceError,
-    state::{Listing, Marketplace},
+    event_cpi::emit_cpi,
+    programmable::{is_programmable, ProgrammableTransfer},
+    state::{
+        Blacklist, CollectionConfig, CollectionStats, DutchPricing, Listing, ListingIndex,
+        Marketplace, SecondaryPrice, SellerStats,
+    },
+    token_extensions::check_nft_extensions,
 };
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ListNft<'info> {
 
@@ -20,11 +27,25 @@ pub struct ListNft<'info> {
 
 
     /// The NFT mint account to be listed
-    pub nft: Account<'info, Mint>,
+    /// - Owned by the SPL Token or the Token-2022 program
+    #[account(mint::token_program = token_program)]
+    pub nft: InterfaceAccount<'info, Mint>,
+
+    /// The seller's statistics account
+    /// - Uses PDA with marketplace and seller as seeds
+    /// - Created on the seller's first listing; hands out the listing's nonce
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + SellerStats::INIT_SPACE,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump,
+    )]
+    pub seller_stats: Box<Account<'info, SellerStats>>,
 
     /// The listing state account
     /// - Stores seller, mint, price, and status information
-    /// - Uses PDA with marketplace, seller, and NFT mint as seeds
+    /// - Uses PDA with marketplace, seller, NFT mint and the seller's listing nonce as seeds
     #[account(
         init,
         payer = seller,
@@ -34,6 +55,7 @@ pub struct ListNft<'info> {
             marketplace.key().as_ref(),
             seller.key().as_ref(),
             nft.key().as_ref(),
+            seller_stats.listing_nonce.to_le_bytes().as_ref(),
         ],
         bump,
     )]
@@ -47,8 +69,9 @@ pub struct ListNft<'info> {
         payer = seller,
         associated_token::mint = nft,
         associated_token::authority = listing,
+        associated_token::token_program = token_program,
     )]
-    pub listing_token_account: Account<'info, TokenAccount>,
+    pub listing_token_account: InterfaceAccount<'info, TokenAccount>,
 
 
 
@@ -59,26 +82,85 @@ pub struct ListNft<'info> {
         mut,
         associated_token::mint = nft,
         associated_token::authority = seller,
+        associated_token::token_program = token_program,
         constraint = seller_token_account.owner == seller.key()
     )]
-    pub seller_token_account: Account<'info, TokenAccount>,
+    pub seller_token_account: InterfaceAccount<'info, TokenAccount>,
 
     /// The marketplace state account
     /// - Validates this is the correct marketplace instance
+    /// - Must not be paused
+    /// - Updates the marketplace statistics
     #[account(
-        seeds = [b"marketplace"],
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
         bump = marketplace.bump,
+        constraint = !marketplace.paused @ MarketplaceError::MarketplacePaused,
     )]
     pub marketplace: Account<'info, Marketplace>,
 
+    /// The listing index page the listing is appended to
+    /// - Uses PDA with marketplace and page number as seeds
+    /// - Created by the seller whose listing starts a new page
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + ListingIndex::INIT_SPACE,
+        seeds = [
+            b"index",
+            marketplace.key().as_ref(),
+            ListingIndex::page_of(marketplace.listing_index_len).to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub listing_index: Box<Account<'info, ListingIndex>>,
+
     /// The collection mint that this NFT belongs to
     /// - Used for collection verification
-    pub collection_mint: Account<'info, Mint>,
+    pub collection_mint: InterfaceAccount<'info, Mint>,
+
+    /// The collection config approving the collection for listing
+    /// - Uses PDA with marketplace and collection mint as seeds
+    /// - Not required to exist when the marketplace has open listings
+    ///
+    /// CHECK: Address is pinned by seeds; the account is loaded in `verify_collection`
+    #[account(
+        seeds = [
+            b"collection",
+            marketplace.key().as_ref(),
+            collection_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub collection_config: UncheckedAccount<'info>,
+
+    /// The collection's floor and sale statistics
+    /// - Uses PDA with marketplace and collection mint as seeds
+    /// - Optional; only for NFTs of a verified collection, created on the first tracked listing
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + CollectionStats::INIT_SPACE,
+        seeds = [
+            b"collection_stats",
+            marketplace.key().as_ref(),
+            collection_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub collection_stats: Option<Box<Account<'info, CollectionStats>>>,
 
     /// The metadata account for the NFT
+    /// - Must be the metadata PDA derived from the NFT mint
     /// - Contains collection information and verification status
-    /// - Must be from a verified collection
+    /// - Must be from a verified, approved collection unless listings are open
+    /// - Writable for programmable NFT transfers
     #[account(
+        mut,
         seeds = [
             b"metadata",
             metadata_program.key().as_ref(),
@@ -86,13 +168,12 @@ pub struct ListNft<'info> {
         ],
         seeds::program = metadata_program.key(),
         bump,
-        constraint = metadata.collection.as_ref().unwrap().key.as_ref() == collection_mint.key().as_ref(),
-        constraint = metadata.collection.as_ref().unwrap().verified == true,
     )]
     pub metadata: Account<'info, MetadataAccount>,
 
     /// The master edition account for the NFT
     /// - Proves this is a valid NFT (not just a token)
+    /// - Omitted for semi-fungible tokens, which have none
     #[account(
         seeds = [
             b"metadata", 
@@ -103,21 +184,71 @@ pub struct ListNft<'info> {
         seeds::program = metadata_program.key(),
         bump,
     )]
-    pub master_edition: Account<'info, MasterEditionAccount>,
+    pub master_edition: Option<Box<Account<'info, MasterEditionAccount>>>,
+
+    /// The token record of the seller's token account
+    /// - Only required for programmable NFTs
+    ///
+    /// CHECK: Validated by the metadata program during the transfer
+    #[account(mut)]
+    pub seller_token_record: Option<UncheckedAccount<'info>>,
+
+    /// The token record of the listing's vault, created by the transfer
+    /// - Only required for programmable NFTs
+    ///
+    /// CHECK: Validated by the metadata program during the transfer
+    #[account(mut)]
+    pub listing_token_record: Option<UncheckedAccount<'info>>,
+
+    /// The rule set of the programmable NFT
+    /// - Only required for programmable NFTs with a rule set
+    ///
+    /// CHECK: Validated by the metadata program against the NFT's programmable config
+    pub authorization_rules: Option<UncheckedAccount<'info>>,
+
+    /// The Token Auth Rules program
+    /// - Only required for programmable NFTs with a rule set
+    ///
+    /// CHECK: Validated by the metadata program
+    pub authorization_rules_program: Option<UncheckedAccount<'info>>,
+
+    /// The instructions sysvar read by the metadata program
+    /// - Only required for programmable NFTs
+    ///
+    /// CHECK: Address is pinned to the instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub sysvar_instructions: Option<UncheckedAccount<'info>>,
+
+    /// The seller's blacklist entry, which must not exist
+    /// - Uses PDA with marketplace and seller as seeds
+    ///
+    /// CHECK: Address is pinned by seeds; only its existence is checked
+    #[account(
+        seeds = [b"blacklist", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump,
+        constraint = !Blacklist::is_blacklisted(&seller_blacklist) @ MarketplaceError::WalletBlacklisted,
+    )]
+    pub seller_blacklist: UncheckedAccount<'info>,
 
     /// Required programs for the instruction
     pub metadata_program: Program<'info, Metadata>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    /// The token program owning the NFT mint, SPL Token or Token-2022
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 impl<'info> ListNft<'info> {
-    /// Transfer the NFT from seller to the listing account
+    /// Transfer the listed tokens from seller to the listing account
     ///
     /// # Returns
     /// * `Result<()>` - Success or error from the transfer
     pub fn transfer_nft(&mut self) -> Result<()> {
+        // pNFTs are frozen in their token accounts and move through Token Metadata
+        if self.listing.programmable {
+            return self.programmable_transfer()?.invoke_signed(&[]);
+        }
+
         // Create CPI context for token transfer
         let cpi_ctx = CpiContext::new(
             self.token_program.to_account_info(),
@@ -129,31 +260,322 @@ impl<'info> ListNft<'info> {
             },
         );
 
-        // Transfer exactly 1 NFT (decimals from mint account)
-        transfer_checked(cpi_ctx, 1, self.nft.decimals)
+        // Transfer the listed quantity, 1 for an NFT (decimals from mint account)
+        transfer_checked(cpi_ctx, self.listing.quantity, self.nft.decimals)
+    }
+
+    /// Count the listing in its collection's statistics, if they were passed
+    /// - Public fixed-price listings are floor candidates; Dutch prices still decline and are not
+    ///
+    /// # Arguments
+    /// * `bump` - PDA bump of the collection statistics account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn track_collection_listing(&mut self, bump: Option<u8>) -> Result<()> {
+        let Some(collection_stats) = self.collection_stats.as_mut() else {
+            return Ok(());
+        };
+        require!(
+            self.listing.collection == Some(self.collection_mint.key()),
+            MarketplaceError::InvalidCollectionStats
+        );
+
+        if collection_stats.is_uninitialized() {
+            collection_stats.set_inner(CollectionStats::new(
+                self.marketplace.key(),
+                self.collection_mint.key(),
+                bump.ok_or(MarketplaceError::InvalidCollectionStats)?,
+            ));
+        }
+        let price = match self.listing.dutch {
+            Some(_) => None,
+            None => self.listing.floor_candidate_price(Clock::get()?.unix_timestamp),
+        };
+        collection_stats.listing_opened(self.listing.key(), price);
+
+        Ok(())
+    }
+
+    /// Announce the new listing, including when its sale opens for countdowns
+    pub fn emit_listed(&self, event_authority_bump: u8) -> Result<()> {
+        emit_cpi(&self.event_authority, event_authority_bump, &NftListedEvent {
+            listing: self.listing.key(),
+            seller: self.seller.key(),
+            nft: self.nft.key(),
+            price: self.listing.price,
+            start_ts: self.listing.sale_start(),
+            secondary_price: self.listing.secondary_price,
+            category: self.listing.category,
+        })
+    }
+
+    /// Collect the accounts of a Token Metadata transfer from the seller to the vault
+    ///
+    /// # Returns
+    /// * `Result<ProgrammableTransfer>` - The transfer, or an error if a pNFT account is missing
+    fn programmable_transfer(&self) -> Result<ProgrammableTransfer<'info>> {
+        let (
+            Some(master_edition),
+            Some(seller_token_record),
+            Some(listing_token_record),
+            Some(sysvar_instructions),
+        ) = (
+            self.master_edition.as_ref(),
+            self.seller_token_record.as_ref(),
+            self.listing_token_record.as_ref(),
+            self.sysvar_instructions.as_ref(),
+        )
+        else {
+            return err!(MarketplaceError::MissingProgrammableAccounts);
+        };
+
+        Ok(ProgrammableTransfer {
+            source: self.seller_token_account.to_account_info(),
+            source_owner: self.seller.to_account_info(),
+            source_token_record: seller_token_record.to_account_info(),
+            destination: self.listing_token_account.to_account_info(),
+            destination_owner: self.listing.to_account_info(),
+            destination_token_record: listing_token_record.to_account_info(),
+            mint: self.nft.to_account_info(),
+            metadata: self.metadata.to_account_info(),
+            edition: master_edition.to_account_info(),
+            authority: self.seller.to_account_info(),
+            payer: self.seller.to_account_info(),
+            authorization_rules: self.authorization_rules.as_ref().map(|a| a.to_account_info()),
+            authorization_rules_program: self
+                .authorization_rules_program
+                .as_ref()
+                .map(|a| a.to_account_info()),
+            sysvar_instructions: sysvar_instructions.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            associated_token_program: self.associated_token_program.to_account_info(),
+            metadata_program: self.metadata_program.to_account_info(),
+        })
+    }
+
+    /// Check the NFT's collection and return it for the listing
+    /// - A claimed collection must always be verified
+    /// - Unless the marketplace has open listings, it must also be approved on this marketplace
+    ///
+    /// # Returns
+    /// * `Result<Option<Pubkey>>` - The verified collection mint, or None if the NFT has no collection
+    pub fn verify_collection(&self) -> Result<Option<Pubkey>> {
+        CollectionConfig::verify_nft(
+            &self.marketplace,
+            &self.metadata,
+            &self.collection_mint.key(),
+            &self.collection_config,
+        )
     }
 
     /// Initialize the listing state with seller and price information
     ///
     /// # Arguments
-    /// * `price` - The listing price in lamports
-    /// * `bumps` - PDA bump values for the listing account
+    /// * `price_per_unit` - The price per token in lamports, or payment token base units
+    /// * `expiry` - Unix timestamp from which the listing can no longer be purchased, 0 for never
+    /// * `quantity` - Tokens to list; 1 for an NFT
+    /// * `allowed_buyer` - The only wallet allowed to buy, or None for a public listing
+    /// * `protection_window_secs` - Seconds purchases hold the proceeds open to disputes, 0 for none
+    /// * `start_ts` - Unix timestamp from which the listing can be purchased, 0 for immediately
+    /// * `secondary_price` - A price per token in another SPL token the seller also accepts, or None
+    /// * `category` - The listing's category, one of the `ListingCategory` values
+    /// * `collection` - The verified collection mint returned by `verify_collection`
+    /// * `bumps` - PDA bump values for the listing and seller statistics accounts
     ///
     /// # Returns
     /// * `Result<()>` - Success or error
-    pub fn initialize_listing(&mut self, price: u64, bumps: ListNftBumps) -> Result<()> {
-        // Validate price is greater than 0
-        require!(price > 0, MarketplaceError::InvalidPrice);
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_listing(
+        &mut self,
+        price_per_unit: u64,
+        expiry: i64,
+        quantity: u64,
+        allowed_buyer: Option<Pubkey>,
+        protection_window_secs: u32,
+        start_ts: i64,
+        secondary_price: Option<SecondaryPrice>,
+        category: u8,
+        collection: Option<Pubkey>,
+        bumps: ListNftBumps,
+    ) -> Result<()> {
+        // Validate price is greater than 0 and expiry is unset or in the future
+        require!(price_per_unit > 0, MarketplaceError::InvalidPrice);
+        require!(
+            self.marketplace.price_in_bounds(price_per_unit),
+            MarketplaceError::PriceOutOfBounds
+        );
+        require!(
+            expiry == 0 || expiry > Clock::get()?.unix_timestamp,
+            MarketplaceError::InvalidListingExpiry
+        );
+        // A scheduled sale must open before the listing expires
+        require!(
+            start_ts >= 0 && (start_ts == 0 || expiry == 0 || start_ts < expiry),
+            MarketplaceError::InvalidListingStart
+        );
+
+        // Fungible currencies such as USDC have decimals and never list, even 1 unit at a time
+        require!(quantity > 0, MarketplaceError::InvalidQuantity);
+        require!(ListingCategory::is_known(category), MarketplaceError::InvalidCategory);
+        require!(self.nft.decimals == 0, MarketplaceError::NotAnNft);
+        check_nft_extensions(&self.nft.to_account_info())?;
+
+        // A master edition marks a one-of-one NFT the seller holds; anything else must be a fungible asset
+        match self.master_edition {
+            Some(_) => {
+                require!(quantity == 1, MarketplaceError::InvalidQuantity);
+                require!(
+                    self.nft.supply == 1 && self.seller_token_account.amount == 1,
+                    MarketplaceError::NotAnNft
+                );
+            }
+            None => require!(
+                self.metadata.token_standard == Some(TokenStandard::FungibleAsset),
+                MarketplaceError::NotAnNft
+            ),
+        }
+
+        // Protected proceeds are escrowed in lamports, one sale per listing
+        require!(
+            protection_window_secs == 0
+                || (quantity == 1
+                    && self.marketplace.payment_mint.is_none()
+                    && protection_window_secs <= MAX_PROTECTION_WINDOW_SECS),
+            MarketplaceError::InvalidProtectionWindow
+        );
+
+        // Every listing takes a fresh nonce, so relisting never reuses an address
+        if self.seller_stats.is_uninitialized() {
+            self.seller_stats.set_inner(SellerStats::new(
+                self.marketplace.key(),
+                self.seller.key(),
+                bumps.seller_stats,
+            ));
+        }
+        let nonce = self.seller_stats.listing_opened()?;
 
         // Initialize listing state
         self.listing.set_inner(Listing {
             seller: self.seller.key(),
+            marketplace: self.marketplace.key(),
             mint: self.nft.key(),
-            price,
+            price: price_per_unit,
             bump: bumps.listing,
             is_active: true,
+            category,
+            collection,
+            expiry,
+            dutch: None,
+            escrowless: false,
+            quantity,
+            programmable: is_programmable(&self.metadata),
+            allowed_buyer,
+            protection_window_secs,
+            start_ts,
+            nonce,
+            secondary_price,
+            metadata_hash: Listing::hash_metadata(
+                &self.metadata.name,
+                &self.metadata.symbol,
+                &self.metadata.uri,
+            ),
+            best_offer_amount: 0,
+            best_offer: Pubkey::default(),
+            // Set by `index_listing`
+            index_position: None,
         });
+        if let Some(secondary) = secondary_price {
+            require!(
+                self.listing
+                    .accepts_secondary_price(&secondary, self.marketplace.payment_mint),
+                MarketplaceError::InvalidSecondaryPrice
+            );
+        }
+        self.marketplace.listing_opened();
+
+        Ok(())
+    }
+
+    /// Append the new listing to the marketplace's listing index
+    ///
+    /// # Arguments
+    /// * `listing_index_bump` - PDA bump of the index page, used when the page is new
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn index_listing(&mut self, listing_index_bump: u8) -> Result<()> {
+        let len = self.marketplace.listing_index_len;
+        if self.listing_index.is_uninitialized() {
+            self.listing_index.set_inner(ListingIndex::new(
+                self.marketplace.key(),
+                ListingIndex::page_of(len),
+                listing_index_bump,
+            ));
+        }
+
+        let position = self.listing_index.push(len, self.listing.key())?;
+        self.listing.index_position = Some(position);
+        self.marketplace.listing_index_len = len + 1;
+        Ok(())
+    }
+
+    /// Initialize a Dutch listing whose price declines linearly to a floor
+    ///
+    /// # Arguments
+    /// * `dutch` - The start price, floor price and schedule of the listing
+    /// * `collection` - The verified collection mint returned by `verify_collection`
+    /// * `bumps` - PDA bump values for the listing and seller statistics accounts
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn initialize_dutch_listing(
+        &mut self,
+        dutch: DutchPricing,
+        collection: Option<Pubkey>,
+        bumps: ListNftBumps,
+    ) -> Result<()> {
+        // Validate the price declines to a non-zero floor over a schedule that has not ended
+        require!(
+            dutch.floor_price > 0
+                && dutch.start_price > dutch.floor_price
+                && dutch.end_ts > dutch.start_ts
+                && dutch.end_ts > Clock::get()?.unix_timestamp,
+            MarketplaceError::InvalidDutchSchedule
+        );
+        // The start price is checked with the listing, so the whole decline stays in bounds
+        require!(
+            self.marketplace.price_in_bounds(dutch.floor_price),
+            MarketplaceError::PriceOutOfBounds
+        );
+
+        // Dutch listings never expire; `price` keeps the start price for display
+        self.initialize_listing(
+            dutch.start_price,
+            0,
+            1,
+            None,
+            0,
+            0,
+            None,
+            ListingCategory::Uncategorized as u8,
+            collection,
+            bumps,
+        )?;
+        self.listing.dutch = Some(dutch);
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[event]
+pub struct NftListedEvent {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub nft: Pubkey,
+    pub price: u64,
+    pub start_ts: i64,
+    pub secondary_price: Option<SecondaryPrice>,
+    pub category: u8,
+}
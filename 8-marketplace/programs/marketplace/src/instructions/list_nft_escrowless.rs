@@ -0,0 +1,283 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    metadata::{
+        freeze_delegated_account, FreezeDelegatedAccount, MasterEditionAccount, Metadata,
+        MetadataAccount,
+    },
+    token::{approve, Approve, Mint, Token, TokenAccount},
+};
+
+use crate::{
+    constants::ListingCategory,
+    error::MarketplaceError,
+    programmable::is_programmable,
+    state::{Blacklist, CollectionConfig, Listing, Marketplace, SellerStats},
+};
+
+/// Lists an NFT without moving it out of the seller's wallet
+#[derive(Accounts)]
+pub struct ListNftEscrowless<'info> {
+    /// The seller who owns the NFT and is creating the listing
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// The NFT mint account to be listed
+    pub nft: Account<'info, Mint>,
+
+    /// The seller's statistics account
+    /// - Uses PDA with marketplace and seller as seeds
+    /// - Created on the seller's first listing; hands out the listing's nonce
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + SellerStats::INIT_SPACE,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump,
+    )]
+    pub seller_stats: Box<Account<'info, SellerStats>>,
+
+    /// The listing state account
+    /// - Stores seller, mint, price, and status information
+    /// - Uses PDA with marketplace, seller, NFT mint and the seller's listing nonce as seeds
+    /// - Becomes the delegate of the seller's token account
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + Listing::INIT_SPACE,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            seller.key().as_ref(),
+            nft.key().as_ref(),
+            seller_stats.listing_nonce.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    /// The seller's token account containing the NFT
+    /// - Must have the NFT to be listed
+    /// - Delegated to the listing and frozen until purchase or delisting
+    #[account(
+        mut,
+        associated_token::mint = nft,
+        associated_token::authority = seller,
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+
+    /// The marketplace state account
+    /// - Validates this is the correct marketplace instance
+    /// - Must not be paused
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        constraint = !marketplace.paused @ MarketplaceError::MarketplacePaused,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// The collection mint that this NFT belongs to
+    /// - Used for collection verification
+    pub collection_mint: Account<'info, Mint>,
+
+    /// The collection config approving the collection for listing
+    /// - Uses PDA with marketplace and collection mint as seeds
+    /// - Not required to exist when the marketplace has open listings
+    ///
+    /// CHECK: Address is pinned by seeds; the account is loaded in `verify_collection`
+    #[account(
+        seeds = [
+            b"collection",
+            marketplace.key().as_ref(),
+            collection_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub collection_config: UncheckedAccount<'info>,
+
+    /// The metadata account for the NFT
+    /// - Must be from a verified, approved collection unless listings are open
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            nft.key().as_ref(),
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub metadata: Account<'info, MetadataAccount>,
+
+    /// The master edition account for the NFT
+    /// - Freeze authority of the mint, used to freeze the seller's token account
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            nft.key().as_ref(),
+            b"edition"
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub master_edition: Account<'info, MasterEditionAccount>,
+
+    /// The seller's blacklist entry, which must not exist
+    /// - Uses PDA with marketplace and seller as seeds
+    ///
+    /// CHECK: Address is pinned by seeds; only its existence is checked
+    #[account(
+        seeds = [b"blacklist", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump,
+        constraint = !Blacklist::is_blacklisted(&seller_blacklist) @ MarketplaceError::WalletBlacklisted,
+    )]
+    pub seller_blacklist: UncheckedAccount<'info>,
+
+    /// Required programs for the instruction
+    pub metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> ListNftEscrowless<'info> {
+    /// Check the NFT's collection and return it for the listing
+    ///
+    /// # Returns
+    /// * `Result<Option<Pubkey>>` - The verified collection mint, or None if the NFT has no collection
+    pub fn verify_collection(&self) -> Result<Option<Pubkey>> {
+        CollectionConfig::verify_nft(
+            &self.marketplace,
+            &self.metadata,
+            &self.collection_mint.key(),
+            &self.collection_config,
+        )
+    }
+
+    /// Initialize the escrowless listing state
+    ///
+    /// # Arguments
+    /// * `price` - The listing price in lamports, or payment token base units
+    /// * `expiry` - Unix timestamp from which the listing can no longer be purchased, 0 for never
+    /// * `collection` - The verified collection mint returned by `verify_collection`
+    /// * `bumps` - PDA bump values for the listing and seller statistics accounts
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn initialize_listing(
+        &mut self,
+        price: u64,
+        expiry: i64,
+        collection: Option<Pubkey>,
+        bumps: ListNftEscrowlessBumps,
+    ) -> Result<()> {
+        // Validate price is greater than 0 and expiry is unset or in the future
+        require!(price > 0, MarketplaceError::InvalidPrice);
+        require!(self.marketplace.price_in_bounds(price), MarketplaceError::PriceOutOfBounds);
+        // pNFT token accounts are already frozen by Token Metadata and cannot be delegated and frozen here
+        require!(
+            !is_programmable(&self.metadata),
+            MarketplaceError::ProgrammableNftUnsupported
+        );
+        require!(
+            expiry == 0 || expiry > Clock::get()?.unix_timestamp,
+            MarketplaceError::InvalidListingExpiry
+        );
+
+        // Every listing takes a fresh nonce, so relisting never reuses an address
+        if self.seller_stats.is_uninitialized() {
+            self.seller_stats.set_inner(SellerStats::new(
+                self.marketplace.key(),
+                self.seller.key(),
+                bumps.seller_stats,
+            ));
+        }
+        let nonce = self.seller_stats.listing_opened()?;
+
+        self.listing.set_inner(Listing {
+            seller: self.seller.key(),
+            marketplace: self.marketplace.key(),
+            mint: self.nft.key(),
+            price,
+            bump: bumps.listing,
+            is_active: true,
+            // Escrowless listings are filed under a category with `update_listing_category`
+            category: ListingCategory::Uncategorized as u8,
+            collection,
+            expiry,
+            dutch: None,
+            escrowless: true,
+            quantity: 1,
+            programmable: false,
+            allowed_buyer: None,
+            protection_window_secs: 0,
+            start_ts: 0,
+            nonce,
+            secondary_price: None,
+            metadata_hash: Listing::hash_metadata(
+                &self.metadata.name,
+                &self.metadata.symbol,
+                &self.metadata.uri,
+            ),
+            best_offer_amount: 0,
+            best_offer: Pubkey::default(),
+            // Only `list_nft` indexes listings
+            index_position: None,
+        });
+        self.marketplace.listing_opened();
+
+        Ok(())
+    }
+
+    /// Delegate the NFT to the listing and freeze it in the seller's wallet
+    /// - The seller keeps custody but cannot move the NFT while it is listed
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error from the CPIs
+    pub fn delegate_and_freeze(&mut self) -> Result<()> {
+        // Approve the listing PDA as delegate for the single NFT
+        let approve_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            Approve {
+                to: self.seller_token_account.to_account_info(),
+                delegate: self.listing.to_account_info(),
+                authority: self.seller.to_account_info(),
+            },
+        );
+        approve(approve_ctx, 1)?;
+
+        // Create seeds for PDA signing
+        let marketplace = self.marketplace.key();
+        let seller = self.seller.key();
+        let nft = self.nft.key();
+        let nonce = self.listing.nonce.to_le_bytes();
+        let listing_seeds: &[&[u8]] = &[
+            b"listing",
+            marketplace.as_ref(),
+            seller.as_ref(),
+            nft.as_ref(),
+            &nonce,
+            &[self.listing.bump],
+        ];
+        let signer = &[listing_seeds];
+
+        // Freeze through the master edition, which is the mint's freeze authority
+        let freeze_ctx = CpiContext::new_with_signer(
+            self.metadata_program.to_account_info(),
+            FreezeDelegatedAccount {
+                metadata: self.metadata.to_account_info(),
+                delegate: self.listing.to_account_info(),
+                token_account: self.seller_token_account.to_account_info(),
+                edition: self.master_edition.to_account_info(),
+                mint: self.nft.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+            signer,
+        );
+        freeze_delegated_account(freeze_ctx)
+    }
+}
@@ -0,0 +1,129 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::token::Mint;
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{CollectionOffer, Marketplace},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MakeCollectionOffer<'info> {
+    /// The buyer making the offer
+    /// - Pays the offered lamports into escrow and the offer account rent
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The collection mint whose NFTs the offer buys
+    pub collection_mint: Account<'info, Mint>,
+
+    /// The collection offer state account
+    /// - Uses PDA with marketplace, collection mint and buyer as seeds
+    /// - Holds the offered lamports in escrow until accepted or cancelled
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + CollectionOffer::INIT_SPACE,
+        seeds = [
+            b"coll_offer",
+            marketplace.key().as_ref(),
+            collection_mint.key().as_ref(),
+            buyer.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub collection_offer: Account<'info, CollectionOffer>,
+
+    /// The marketplace state account for validation
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// Required system program for account creation and the escrow transfer
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeCollectionOffer<'info> {
+    /// Record the offer and move the lamports for every NFT it buys into escrow
+    ///
+    /// # Arguments
+    /// * `price` - The offered amount per NFT in lamports
+    /// * `quantity` - NFTs of the collection the offer buys
+    /// * `expiry` - Unix timestamp after which the offer can no longer be accepted
+    /// * `bumps` - PDA bump values for the offer account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn make_collection_offer(
+        &mut self,
+        price: u64,
+        quantity: u64,
+        expiry: i64,
+        bumps: MakeCollectionOfferBumps,
+    ) -> Result<()> {
+        require!(price > 0, MarketplaceError::InvalidPrice);
+        require!(self.marketplace.price_in_bounds(price), MarketplaceError::PriceOutOfBounds);
+        require!(quantity > 0, MarketplaceError::InvalidQuantity);
+        // Offers escrow lamports, so they only make sense where sales are paid in SOL
+        require!(
+            self.marketplace.payment_mint.is_none(),
+            MarketplaceError::NativePaymentOnly
+        );
+        require!(
+            expiry > Clock::get()?.unix_timestamp,
+            MarketplaceError::InvalidOfferExpiry
+        );
+
+        self.collection_offer.set_inner(CollectionOffer {
+            marketplace: self.marketplace.key(),
+            collection_mint: self.collection_mint.key(),
+            buyer: self.buyer.key(),
+            price,
+            quantity,
+            expiry,
+            bump: bumps.collection_offer,
+        });
+
+        // Escrow the offered lamports on the offer account
+        let escrowed = self.collection_offer.escrowed()?;
+        let cpi_ctx = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.buyer.to_account_info(),
+                to: self.collection_offer.to_account_info(),
+            },
+        );
+        transfer(cpi_ctx, escrowed)?;
+
+        emit_cpi(&self.event_authority, bumps.event_authority, &CollectionOfferMadeEvent {
+            collection_offer: self.collection_offer.key(),
+            collection_mint: self.collection_mint.key(),
+            buyer: self.buyer.key(),
+            price,
+            quantity,
+            expiry,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct CollectionOfferMadeEvent {
+    pub collection_offer: Pubkey,
+    pub collection_mint: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub quantity: u64,
+    pub expiry: i64,
+}
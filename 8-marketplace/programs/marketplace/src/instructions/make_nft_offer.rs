@@ -0,0 +1,121 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::token::Mint;
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Marketplace, NftOffer},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MakeNftOffer<'info> {
+    /// The buyer making the offer
+    /// - Pays the offer amount into escrow and the offer account rent
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The NFT mint the offer buys
+    /// - No listing needs to exist for it
+    #[account(
+        constraint = nft.decimals == 0 && nft.supply == 1 @ MarketplaceError::NotAnNft,
+    )]
+    pub nft: Account<'info, Mint>,
+
+    /// The NFT offer state account
+    /// - Uses PDA with marketplace, NFT mint and buyer as seeds
+    /// - Holds the offered lamports in escrow until accepted or cancelled
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + NftOffer::INIT_SPACE,
+        seeds = [
+            b"nft_offer",
+            marketplace.key().as_ref(),
+            nft.key().as_ref(),
+            buyer.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub nft_offer: Account<'info, NftOffer>,
+
+    /// The marketplace state account for validation
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// Required system program for account creation and the escrow transfer
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeNftOffer<'info> {
+    /// Record the offer and move the offered lamports into escrow
+    ///
+    /// # Arguments
+    /// * `amount` - The offered amount in lamports
+    /// * `expiry` - Unix timestamp after which the offer can no longer be accepted
+    /// * `bumps` - PDA bump values for the offer account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn make_nft_offer(&mut self, amount: u64, expiry: i64, bumps: MakeNftOfferBumps) -> Result<()> {
+        require!(amount > 0, MarketplaceError::InvalidPrice);
+        require!(self.marketplace.price_in_bounds(amount), MarketplaceError::PriceOutOfBounds);
+        // Offers escrow lamports, so they only make sense where sales are paid in SOL
+        require!(
+            self.marketplace.payment_mint.is_none(),
+            MarketplaceError::NativePaymentOnly
+        );
+        require!(
+            expiry > Clock::get()?.unix_timestamp,
+            MarketplaceError::InvalidOfferExpiry
+        );
+
+        self.nft_offer.set_inner(NftOffer {
+            marketplace: self.marketplace.key(),
+            mint: self.nft.key(),
+            buyer: self.buyer.key(),
+            amount,
+            expiry,
+            bump: bumps.nft_offer,
+        });
+
+        // Escrow the offered lamports on the offer account
+        let cpi_ctx = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.buyer.to_account_info(),
+                to: self.nft_offer.to_account_info(),
+            },
+        );
+        transfer(cpi_ctx, amount)?;
+
+        emit_cpi(&self.event_authority, bumps.event_authority, &NftOfferMadeEvent {
+            nft_offer: self.nft_offer.key(),
+            nft: self.nft.key(),
+            buyer: self.buyer.key(),
+            amount,
+            expiry,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct NftOfferMadeEvent {
+    pub nft_offer: Pubkey,
+    pub nft: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub expiry: i64,
+}
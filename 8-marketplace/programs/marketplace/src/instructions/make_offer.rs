@@ -0,0 +1,143 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::token::Mint;
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Listing, Marketplace, Offer},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MakeOffer<'info> {
+    /// The buyer making the offer
+    /// - Pays the offer amount into escrow and the offer account rent
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The NFT mint account of the listing
+    pub nft: Account<'info, Mint>,
+
+    /// The listing the offer is made on
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    /// - Tracks the offer if it is the new best offer
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            listing.seller.as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    /// The offer state account
+    /// - Uses PDA with listing and buyer as seeds
+    /// - Holds the offered lamports in escrow until accepted or cancelled
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Offer::INIT_SPACE,
+        seeds = [b"offer", listing.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub offer: Account<'info, Offer>,
+
+    /// The marketplace state account for validation
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// Required system program for account creation and the escrow transfer
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeOffer<'info> {
+    /// Record the offer and move the offered lamports into escrow
+    ///
+    /// # Arguments
+    /// * `amount` - The offered amount in lamports
+    /// * `expiry` - Unix timestamp after which the offer can no longer be accepted
+    /// * `bumps` - PDA bump values for the offer account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn make_offer(&mut self, amount: u64, expiry: i64, bumps: MakeOfferBumps) -> Result<()> {
+        // Validate listing is active, amount is greater than 0 and expiry is in the future
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+        // An offer buys the whole listing, which is a single NFT
+        require!(self.listing.quantity == 1, MarketplaceError::NotSingleTokenListing);
+        // Offers move the NFT with a plain SPL transfer
+        require!(!self.listing.programmable, MarketplaceError::ProgrammableNftUnsupported);
+        require!(
+            self.listing.can_buy(&self.buyer.key()),
+            MarketplaceError::NotAllowedBuyer
+        );
+        require!(amount > 0, MarketplaceError::InvalidPrice);
+        require!(self.marketplace.price_in_bounds(amount), MarketplaceError::PriceOutOfBounds);
+        // Offers escrow lamports, so they only make sense where sales are paid in SOL
+        require!(
+            self.marketplace.payment_mint.is_none(),
+            MarketplaceError::NativePaymentOnly
+        );
+        require!(
+            expiry > Clock::get()?.unix_timestamp,
+            MarketplaceError::InvalidOfferExpiry
+        );
+
+        self.offer.set_inner(Offer {
+            buyer: self.buyer.key(),
+            listing: self.listing.key(),
+            marketplace: self.marketplace.key(),
+            amount,
+            expiry,
+            bump: bumps.offer,
+            counter_amount: None,
+            counter_expiry: 0,
+            payment_mint: None,
+        });
+
+        // Escrow the offered lamports on the offer account
+        let cpi_ctx = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.buyer.to_account_info(),
+                to: self.offer.to_account_info(),
+            },
+        );
+        transfer(cpi_ctx, amount)?;
+        self.listing.offer_made(self.offer.key(), amount);
+
+        emit_cpi(&self.event_authority, bumps.event_authority, &OfferMadeEvent {
+            offer: self.offer.key(),
+            listing: self.listing.key(),
+            buyer: self.buyer.key(),
+            amount,
+            expiry,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct OfferMadeEvent {
+    pub offer: Pubkey,
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub expiry: i64,
+}
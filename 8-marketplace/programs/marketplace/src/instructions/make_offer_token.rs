@@ -0,0 +1,189 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{transfer_checked, Mint, Token, TokenAccount, TransferChecked},
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Listing, Marketplace, Offer},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MakeOfferToken<'info> {
+    /// The buyer making the offer
+    /// - Pays the offered tokens into escrow and the offer and vault rent
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The NFT mint account of the listing
+    pub nft: Box<Account<'info, Mint>>,
+
+    /// The listing the offer is made on
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    /// - Tracks the offer if it is in the marketplace currency and the new best offer
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            listing.seller.as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+    )]
+    pub listing: Box<Account<'info, Listing>>,
+
+    /// The offer state account
+    /// - Uses PDA with listing and buyer as seeds, like offers in lamports
+    /// - Owns the vault holding the offered tokens until accepted or cancelled
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Offer::INIT_SPACE,
+        seeds = [b"offer", listing.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub offer: Box<Account<'info, Offer>>,
+
+    /// The mint the offer is made in
+    /// - Must be the marketplace payment mint or the listing's secondary price mint
+    pub payment_mint: Box<Account<'info, Mint>>,
+
+    /// The buyer's payment token account
+    /// - Pays the offered amount into the vault
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = buyer,
+    )]
+    pub buyer_payment_account: Box<Account<'info, TokenAccount>>,
+
+    /// Token account holding the offered tokens
+    /// - Owned by the offer PDA
+    #[account(
+        init,
+        payer = buyer,
+        associated_token::mint = payment_mint,
+        associated_token::authority = offer,
+    )]
+    pub offer_vault: Box<Account<'info, TokenAccount>>,
+
+    /// The marketplace state account for validation
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> MakeOfferToken<'info> {
+    /// Record the offer and move the offered tokens into the offer's vault
+    ///
+    /// # Arguments
+    /// * `amount` - The offered amount in base units of the payment mint
+    /// * `expiry` - Unix timestamp after which the offer can no longer be accepted
+    /// * `bumps` - PDA bump values for the offer account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn make_offer_token(
+        &mut self,
+        amount: u64,
+        expiry: i64,
+        bumps: MakeOfferTokenBumps,
+    ) -> Result<()> {
+        // Validate listing is active, amount is greater than 0 and expiry is in the future
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+        // An offer buys the whole listing, which is a single NFT
+        require!(self.listing.quantity == 1, MarketplaceError::NotSingleTokenListing);
+        // Offers move the NFT with a plain SPL transfer
+        require!(!self.listing.programmable, MarketplaceError::ProgrammableNftUnsupported);
+        require!(
+            self.listing.can_buy(&self.buyer.key()),
+            MarketplaceError::NotAllowedBuyer
+        );
+        require!(amount > 0, MarketplaceError::InvalidPrice);
+        require!(
+            expiry > Clock::get()?.unix_timestamp,
+            MarketplaceError::InvalidOfferExpiry
+        );
+
+        // Only currencies the listing is priced in can be offered
+        let payment_mint = self.payment_mint.key();
+        require!(
+            self.marketplace.payment_mint == Some(payment_mint)
+                || self.listing.secondary_price.map(|secondary| secondary.mint) == Some(payment_mint),
+            MarketplaceError::InvalidPaymentMint
+        );
+        // Bounds are set in the marketplace currency, so offers in the secondary currency skip them
+        require!(
+            self.marketplace.payment_mint != Some(payment_mint)
+                || self.marketplace.price_in_bounds(amount),
+            MarketplaceError::PriceOutOfBounds
+        );
+
+        self.offer.set_inner(Offer {
+            buyer: self.buyer.key(),
+            listing: self.listing.key(),
+            marketplace: self.marketplace.key(),
+            amount,
+            expiry,
+            bump: bumps.offer,
+            counter_amount: None,
+            counter_expiry: 0,
+            payment_mint: Some(payment_mint),
+        });
+
+        // Escrow the offered tokens in the offer's vault
+        let cpi_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.buyer_payment_account.to_account_info(),
+                mint: self.payment_mint.to_account_info(),
+                to: self.offer_vault.to_account_info(),
+                authority: self.buyer.to_account_info(),
+            },
+        );
+        transfer_checked(cpi_ctx, amount, self.payment_mint.decimals)?;
+
+        // The best offer only compares offers in the marketplace currency
+        if self.marketplace.payment_mint == Some(payment_mint) {
+            self.listing.offer_made(self.offer.key(), amount);
+        }
+
+        emit_cpi(&self.event_authority, bumps.event_authority, &TokenOfferMadeEvent {
+            offer: self.offer.key(),
+            listing: self.listing.key(),
+            buyer: self.buyer.key(),
+            payment_mint,
+            amount,
+            expiry,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct TokenOfferMadeEvent {
+    pub offer: Pubkey,
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub payment_mint: Pubkey,
+    pub amount: u64,
+    pub expiry: i64,
+}
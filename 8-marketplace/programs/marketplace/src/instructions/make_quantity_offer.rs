@@ -0,0 +1,155 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::token::Mint;
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Listing, Marketplace, QuantityOffer},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MakeQuantityOffer<'info> {
+    /// The buyer making the offer
+    /// - Pays the escrow for every token and the offer account rent
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The token mint of the listing
+    pub nft: Account<'info, Mint>,
+
+    /// The listing the offer is made on
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    /// - Only anchors the offer; any listing of the mint can fill it
+    #[account(
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            listing.seller.as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    /// The offer state account
+    /// - Uses PDA with listing and buyer as seeds
+    /// - Holds the offered lamports in escrow until filled or cancelled
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + QuantityOffer::INIT_SPACE,
+        seeds = [b"quantity_offer", listing.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub offer: Account<'info, QuantityOffer>,
+
+    /// The marketplace state account for validation
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// Required system program for account creation and the escrow transfer
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeQuantityOffer<'info> {
+    /// Offer to buy a number of semi-fungible tokens at a price per token
+    /// - The offer is not tracked as the listing's best offer, which prices the whole listing
+    ///
+    /// # Arguments
+    /// * `quantity` - Tokens the offer buys
+    /// * `price_per_unit` - The offered lamports per token
+    /// * `expiry` - Unix timestamp after which the offer can no longer be filled
+    /// * `bumps` - PDA bump values for the offer account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn make_quantity_offer(
+        &mut self,
+        quantity: u64,
+        price_per_unit: u64,
+        expiry: i64,
+        bumps: MakeQuantityOfferBumps,
+    ) -> Result<()> {
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+        // Fills move the tokens with a plain SPL transfer
+        require!(!self.listing.programmable, MarketplaceError::ProgrammableNftUnsupported);
+        require!(
+            self.listing.can_buy(&self.buyer.key()),
+            MarketplaceError::NotAllowedBuyer
+        );
+        require!(quantity > 0, MarketplaceError::InvalidQuantity);
+        require!(price_per_unit > 0, MarketplaceError::InvalidPrice);
+        require!(
+            self.marketplace.price_in_bounds(price_per_unit),
+            MarketplaceError::PriceOutOfBounds
+        );
+        // Offers escrow lamports, so they only make sense where sales are paid in SOL
+        require!(
+            self.marketplace.payment_mint.is_none(),
+            MarketplaceError::NativePaymentOnly
+        );
+        require!(
+            expiry > Clock::get()?.unix_timestamp,
+            MarketplaceError::InvalidOfferExpiry
+        );
+        let amount = price_per_unit
+            .checked_mul(quantity)
+            .ok_or(MarketplaceError::MathOverflow)?;
+
+        self.offer.set_inner(QuantityOffer {
+            buyer: self.buyer.key(),
+            listing: self.listing.key(),
+            marketplace: self.marketplace.key(),
+            mint: self.listing.mint,
+            quantity,
+            price_per_unit,
+            amount,
+            expiry,
+            bump: bumps.offer,
+        });
+
+        // Escrow the offered lamports on the offer account
+        let cpi_ctx = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.buyer.to_account_info(),
+                to: self.offer.to_account_info(),
+            },
+        );
+        transfer(cpi_ctx, amount)?;
+
+        emit_cpi(&self.event_authority, bumps.event_authority, &QuantityOfferMadeEvent {
+            offer: self.offer.key(),
+            mint: self.listing.mint,
+            buyer: self.buyer.key(),
+            quantity,
+            price_per_unit,
+            expiry,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct QuantityOfferMadeEvent {
+    pub offer: Pubkey,
+    pub mint: Pubkey,
+    pub buyer: Pubkey,
+    pub quantity: u64,
+    pub price_per_unit: u64,
+    pub expiry: i64,
+}
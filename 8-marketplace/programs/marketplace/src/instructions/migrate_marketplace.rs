@@ -0,0 +1,110 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+    Discriminator,
+};
+
+use anchor_spl::token::{Mint, Token};
+
+use crate::{
+    constants::REWARDS_DECIMALS,
+    error::MarketplaceError,
+    state::{LegacyMarketplace, Marketplace},
+};
+
+#[derive(Accounts)]
+pub struct MigrateMarketplace<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the legacy marketplace state
+    /// - Pays for the extra rent of the larger account
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account in the legacy layout
+    /// - Validated with the PDA using "marketplace" seed and admin key
+    ///
+    /// CHECK: Discriminator and layout are validated in the handler; legacy accounts cannot be deserialized
+    #[account(
+        mut,
+        seeds = [b"marketplace", admin.key().as_ref()],
+        bump,
+        owner = crate::ID,
+    )]
+    pub marketplace: UncheckedAccount<'info>,
+
+    /// Reward points mint, which legacy marketplaces never created
+    /// - Uses PDA with "rewards" seed and marketplace key
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"rewards", marketplace.key().as_ref()],
+        bump,
+        mint::decimals = REWARDS_DECIMALS,
+        mint::authority = marketplace,
+    )]
+    pub rewards_mint: Account<'info, Mint>,
+
+    /// Required programs for the rent top up and the rewards mint
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> MigrateMarketplace<'info> {
+    /// Rewrite a marketplace from whole-percent fees to basis points
+    /// - Grows the account to the current layout and keeps every other setting
+    /// - Rewards start off, as on a new marketplace
+    /// - Fees keep going to the treasury PDA
+    ///
+    /// # Arguments
+    /// * `bumps` - PDA bump values, including the new rewards mint
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn migrate_marketplace(&mut self, bumps: MigrateMarketplaceBumps) -> Result<()> {
+        let marketplace = self.marketplace.to_account_info();
+
+        let legacy = {
+            let data = marketplace.try_borrow_data()?;
+            require!(
+                data.starts_with(Marketplace::DISCRIMINATOR),
+                MarketplaceError::InvalidMarketplaceAccount
+            );
+            require!(
+                data.len() == 8 + LegacyMarketplace::SPACE,
+                MarketplaceError::AlreadyMigrated
+            );
+            LegacyMarketplace::deserialize(&mut &data[8..])?
+        };
+        require_keys_eq!(legacy.admin, self.admin.key(), MarketplaceError::Unauthorized);
+
+        // Top up rent before growing so the account stays rent exempt
+        let new_len = 8 + Marketplace::INIT_SPACE;
+        let required = Rent::get()?.minimum_balance(new_len);
+        let shortfall = required.saturating_sub(marketplace.lamports());
+        if shortfall > 0 {
+            let cpi_ctx = CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.admin.to_account_info(),
+                    to: marketplace.clone(),
+                },
+            );
+            transfer(cpi_ctx, shortfall)?;
+        }
+
+        marketplace.realloc(new_len, true)?;
+
+        let mut migrated = Marketplace::from(legacy);
+        migrated.rewards_bump = bumps.rewards_mint;
+        migrated.fee_recipient = Pubkey::create_program_address(
+            &[b"treasury", marketplace.key.as_ref(), &[migrated.treasury_bump]],
+            &crate::ID,
+        )
+        .map_err(|_| MarketplaceError::InvalidMarketplaceAccount)?;
+
+        let mut data = marketplace.try_borrow_mut_data()?;
+        migrated.try_serialize(&mut &mut data[..])?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+    Discriminator,
+};
+
+use crate::{error::MarketplaceError, state::Marketplace};
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct MigrateMarketplaceStats<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    /// - Pays for the extra rent of the larger account
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account without statistics, a name or a referral share
+    /// - Validated with the PDA using "marketplace" seed, admin key and name; empty for marketplaces
+    ///   created before names
+    ///
+    /// CHECK: Discriminator and size are validated in the handler; the short account cannot be deserialized
+    #[account(
+        mut,
+        seeds = [b"marketplace", admin.key().as_ref(), name.as_bytes()],
+        bump,
+        owner = crate::ID,
+    )]
+    pub marketplace: UncheckedAccount<'info>,
+
+    /// Required program for the rent top up
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateMarketplaceStats<'info> {
+    /// Grow a marketplace created before statistics, names, referrals, fee tiers, crank fees,
+    /// pull payments, creation times, crank rewards, installment terms, holder discounts, the
+    /// blacklisted buyer flag, fee splits, the maximum listing duration, the listing index or
+    /// price bounds
+    /// - The statistics, name, referral share, fee tiers, crank fee, pull-payment flag, creation
+    ///   time, crank reward, installment terms, holder discount, blacklisted buyer flag, fee
+    ///   splits, maximum listing duration, listing index length and price bounds are the last
+    ///   fields of the layout, so zero filling starts the statistics at zero, leaves the empty
+    ///   name its PDA was derived with, turns referrals, fee tiers, crank bounties, crank
+    ///   rewards, holder discounts, fee splits and price bounds off, keeps paying sellers
+    ///   directly, leaves the creation time unknown, refunds defaulted installments in full, lets
+    ///   blacklisted wallets buy, extends listings without limit and starts the listing index
+    ///   empty
+    /// - Listings opened before the migration are not counted in `active_listings` nor indexed
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn migrate_marketplace_stats(&mut self) -> Result<()> {
+        let marketplace = self.marketplace.to_account_info();
+
+        let new_len = 8 + Marketplace::INIT_SPACE;
+        {
+            let data = marketplace.try_borrow_data()?;
+            require!(
+                data.starts_with(Marketplace::DISCRIMINATOR),
+                MarketplaceError::InvalidMarketplaceAccount
+            );
+            require!(
+                data.len() >= new_len - Marketplace::ZEROED_TAIL_SPACE && data.len() < new_len,
+                MarketplaceError::AlreadyMigrated
+            );
+            // The admin is the first field of every layout
+            require!(
+                data[8..40] == self.admin.key().to_bytes(),
+                MarketplaceError::Unauthorized
+            );
+        }
+
+        // Top up rent before growing so the account stays rent exempt
+        let required = Rent::get()?.minimum_balance(new_len);
+        let shortfall = required.saturating_sub(marketplace.lamports());
+        if shortfall > 0 {
+            let cpi_ctx = CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.admin.to_account_info(),
+                    to: marketplace.clone(),
+                },
+            );
+            transfer(cpi_ctx, shortfall)?;
+        }
+
+        marketplace.realloc(new_len, true)?;
+
+        Ok(())
+    }
+}
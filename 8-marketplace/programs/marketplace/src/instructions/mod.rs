@@ -4,8 +4,221 @@ pub use initialize::*;
 pub mod list_nft;
 pub use list_nft::*;
 
+pub mod list_nft_escrowless;
+pub use list_nft_escrowless::*;
+
 pub mod delist;
 pub use delist::*;
 
+pub mod bulk_list;
+pub use bulk_list::*;
+
+pub mod bulk_delist;
+pub use bulk_delist::*;
+
+pub mod list_bundle;
+pub use list_bundle::*;
+
+pub mod delist_bundle;
+pub use delist_bundle::*;
+
 pub mod purchase;
-pub use purchase::*;
\ No newline at end of file
+pub use purchase::*;
+
+pub mod purchase_many;
+pub use purchase_many::*;
+
+pub mod purchase_bundle;
+pub use purchase_bundle::*;
+
+pub mod update_listing_price;
+pub use update_listing_price::*;
+
+pub mod update_listing_category;
+pub use update_listing_category::*;
+
+pub mod update_allowed_buyer;
+pub use update_allowed_buyer::*;
+
+pub mod update_secondary_price;
+pub use update_secondary_price::*;
+
+pub mod make_offer;
+pub use make_offer::*;
+
+pub mod make_offer_token;
+pub use make_offer_token::*;
+
+pub mod accept_offer;
+pub use accept_offer::*;
+
+pub mod cancel_offer;
+pub use cancel_offer::*;
+
+pub mod counter_offer;
+pub use counter_offer::*;
+
+pub mod accept_counter;
+pub use accept_counter::*;
+
+pub mod reject_counter;
+pub use reject_counter::*;
+
+pub mod withdraw_treasury;
+pub use withdraw_treasury::*;
+
+pub mod update_fee;
+pub use update_fee::*;
+
+pub mod add_collection;
+pub use add_collection::*;
+
+pub mod remove_collection;
+pub use remove_collection::*;
+
+pub mod set_open_listings;
+pub use set_open_listings::*;
+
+pub mod clean_expired_listing;
+pub use clean_expired_listing::*;
+
+pub mod create_auction;
+pub use create_auction::*;
+
+pub mod place_bid;
+pub use place_bid::*;
+
+pub mod settle_auction;
+pub use settle_auction::*;
+
+pub mod buy_now;
+pub use buy_now::*;
+
+pub mod migrate_marketplace;
+pub use migrate_marketplace::*;
+
+pub mod migrate_marketplace_stats;
+pub use migrate_marketplace_stats::*;
+
+pub mod get_marketplace_stats;
+pub use get_marketplace_stats::*;
+
+pub mod get_provenance;
+pub use get_provenance::*;
+
+pub mod refresh_floor;
+pub use refresh_floor::*;
+
+pub mod get_collection_stats;
+pub use get_collection_stats::*;
+
+pub mod set_reward_rate;
+pub use set_reward_rate::*;
+
+pub mod set_paused;
+pub use set_paused::*;
+
+pub mod set_fee_recipient;
+pub use set_fee_recipient::*;
+
+pub mod release_sale;
+pub use release_sale::*;
+
+pub mod dispute_sale;
+pub use dispute_sale::*;
+
+pub mod resolve_sale_dispute;
+pub use resolve_sale_dispute::*;
+
+pub mod set_referral_bps;
+pub use set_referral_bps::*;
+
+pub mod make_collection_offer;
+pub use make_collection_offer::*;
+
+pub mod accept_collection_offer;
+pub use accept_collection_offer::*;
+
+pub mod cancel_collection_offer;
+pub use cancel_collection_offer::*;
+
+pub mod make_nft_offer;
+pub use make_nft_offer::*;
+
+pub mod accept_nft_offer;
+pub use accept_nft_offer::*;
+
+pub mod cancel_nft_offer;
+pub use cancel_nft_offer::*;
+
+pub mod admin_delist;
+pub use admin_delist::*;
+
+pub mod set_fee_tiers;
+pub use set_fee_tiers::*;
+
+pub mod create_seller_stats;
+pub use create_seller_stats::*;
+
+pub mod set_crank_fee;
+pub use set_crank_fee::*;
+
+pub mod set_pull_payments;
+pub use set_pull_payments::*;
+
+pub mod claim_proceeds;
+pub use claim_proceeds::*;
+
+pub mod set_crank_reward;
+pub use set_crank_reward::*;
+
+pub mod set_installment_terms;
+pub use set_installment_terms::*;
+
+pub mod set_holder_discount;
+pub use set_holder_discount::*;
+
+pub mod start_installment_purchase;
+pub use start_installment_purchase::*;
+
+pub mod pay_installment;
+pub use pay_installment::*;
+
+pub mod default_installment;
+pub use default_installment::*;
+
+pub mod blacklist_wallet;
+pub use blacklist_wallet::*;
+
+pub mod unblacklist_wallet;
+pub use unblacklist_wallet::*;
+
+pub mod set_block_blacklisted_buyers;
+pub use set_block_blacklisted_buyers::*;
+
+pub mod set_fee_splits;
+pub use set_fee_splits::*;
+
+pub mod set_max_listing_duration;
+pub use set_max_listing_duration::*;
+
+pub mod extend_listing;
+pub use extend_listing::*;
+
+pub mod get_listing_page;
+pub use get_listing_page::*;
+
+pub mod garbage_collect;
+pub use garbage_collect::*;
+
+pub mod set_price_bounds;
+pub use set_price_bounds::*;
+
+pub mod make_quantity_offer;
+pub use make_quantity_offer::*;
+
+pub mod accept_offer_partial;
+pub use accept_offer_partial::*;
+
+pub mod cancel_quantity_offer;
+pub use cancel_quantity_offer::*;
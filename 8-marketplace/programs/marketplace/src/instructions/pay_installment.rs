@@ -0,0 +1,273 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{InstallmentPlan, Listing, ListingIndex, Marketplace, SellerStats},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PayInstallment<'info> {
+    /// The buyer of the installment plan
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The seller who listed the NFT
+    /// - Paid the price and refunded the listing and vault rent after the last installment
+    ///
+    /// CHECK: Part of the listing PDA seeds and matched against the plan
+    #[account(mut)]
+    pub seller: AccountInfo<'info>,
+
+    /// The NFT mint account of the listing
+    #[account(mint::token_program = token_program)]
+    pub nft: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The marketplace state account
+    /// - Records the sale after the last installment
+    /// - Installments can be paid while the marketplace is paused, so buyers never default for it
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The listing being bought
+    /// - Closed to the seller after the last installment
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            seller.key().as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+    )]
+    pub listing: Box<Account<'info, Listing>>,
+
+    /// The vault escrowing the NFT until the last installment
+    #[account(
+        mut,
+        associated_token::mint = nft,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program,
+    )]
+    pub listing_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The buyer's token account receiving the NFT after the last installment
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = nft,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The installment plan being paid
+    /// - Closed to the buyer after the last installment
+    #[account(
+        mut,
+        seeds = [b"installment", listing.key().as_ref()],
+        bump = installment_plan.bump,
+        has_one = buyer @ MarketplaceError::Unauthorized,
+        has_one = seller @ MarketplaceError::Unauthorized,
+        has_one = listing @ MarketplaceError::Unauthorized,
+    )]
+    pub installment_plan: Box<Account<'info, InstallmentPlan>>,
+
+    /// The seller's statistics account
+    /// - Picks the seller's fee tier and records the sale after the last installment
+    #[account(
+        mut,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump = seller_stats.bump,
+    )]
+    pub seller_stats: Box<Account<'info, SellerStats>>,
+
+    /// The account sale fees are paid to
+    /// - Must match the marketplace's configured fee recipient
+    #[account(
+        mut,
+        address = marketplace.fee_recipient @ MarketplaceError::InvalidFeeRecipient,
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+
+    /// The listing index page holding the listing's entry
+    /// - Only required for listings in the marketplace's listing index
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing index page holding the index's last entry
+    /// - Only required when it is another page than `listing_index`
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub last_listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing at the index's last position, moved into the freed position
+    /// - Only required when it is another listing than the one closing
+    ///
+    /// CHECK: Matched against the index entry in `ListingIndex::unindex`
+    #[account(mut)]
+    pub moved_listing: Option<UncheckedAccount<'info>>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> PayInstallment<'info> {
+    /// Pay the next installment; the last one completes the purchase
+    /// - Late installments are accepted until the seller defaults the plan
+    ///
+    /// # Arguments
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn pay_installment(&mut self, event_authority_bump: u8) -> Result<()> {
+        let amount = self.installment_plan.next_installment();
+        let cpi_ctx = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.buyer.to_account_info(),
+                to: self.installment_plan.to_account_info(),
+            },
+        );
+        transfer(cpi_ctx, amount)?;
+        self.installment_plan.record_payment(amount)?;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &InstallmentPaidEvent {
+            installment_plan: self.installment_plan.key(),
+            buyer: self.buyer.key(),
+            amount,
+            payments_made: self.installment_plan.payments_made,
+            num_payments: self.installment_plan.num_payments,
+        })?;
+
+        if self.installment_plan.is_complete() {
+            self.complete_purchase(event_authority_bump)?;
+        }
+
+        Ok(())
+    }
+
+    /// Move the NFT to the buyer and pay out the installments with the usual fee split
+    fn complete_purchase(&mut self, event_authority_bump: u8) -> Result<()> {
+        // Create seeds for PDA signing
+        let marketplace = self.marketplace.key();
+        let seller = self.seller.key();
+        let nft = self.nft.key();
+        let nonce = self.listing.nonce.to_le_bytes();
+        let listing_seeds: &[&[u8]] = &[
+            b"listing",
+            marketplace.as_ref(),
+            seller.as_ref(),
+            nft.as_ref(),
+            &nonce,
+            &[self.listing.bump],
+        ];
+        let signer = &[listing_seeds];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.listing_token_account.to_account_info(),
+                mint: self.nft.to_account_info(),
+                to: self.buyer_token_account.to_account_info(),
+                authority: self.listing.to_account_info(),
+            },
+            signer,
+        );
+        transfer_checked(transfer_ctx, self.listing.quantity, self.nft.decimals)?;
+
+        // Return the empty vault's rent to the seller
+        let close_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.listing_token_account.to_account_info(),
+                destination: self.seller.to_account_info(),
+                authority: self.listing.to_account_info(),
+            },
+            signer,
+        );
+        close_account(close_ctx)?;
+
+        // The fee is split at payout, with the seller's fee tier at this time
+        let price = self.installment_plan.price;
+        let fee_bps = self.marketplace.fee_bps_for_volume(self.seller_stats.volume);
+        let split = self.marketplace.split_payment(price, fee_bps, false)?;
+        require!(
+            split.total() == Some(price),
+            MarketplaceError::PaymentSplitMismatch
+        );
+        // The plan account is program owned, so lamports are moved directly
+        self.installment_plan.sub_lamports(price)?;
+        self.fee_recipient.add_lamports(split.marketplace_fee)?;
+        self.seller.add_lamports(split.seller_proceeds)?;
+
+        self.marketplace.record_sale(price, split.fee());
+        self.marketplace.listing_closed();
+        self.seller_stats.record_sale(price);
+        self.seller_stats.listing_closed();
+        ListingIndex::unindex(
+            &mut self.marketplace,
+            &mut self.listing,
+            self.listing_index.as_deref_mut(),
+            self.last_listing_index.as_deref_mut(),
+            self.moved_listing.as_ref(),
+        )?;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &InstallmentPurchaseCompletedEvent {
+            installment_plan: self.installment_plan.key(),
+            listing: self.listing.key(),
+            buyer: self.buyer.key(),
+            seller,
+            price,
+            seller_proceeds: split.seller_proceeds,
+            marketplace_fee: split.marketplace_fee,
+        })?;
+
+        self.listing.close(self.seller.to_account_info())?;
+        self.installment_plan.close(self.buyer.to_account_info())
+    }
+}
+
+#[event]
+pub struct InstallmentPaidEvent {
+    pub installment_plan: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub payments_made: u8,
+    pub num_payments: u8,
+}
+
+#[event]
+pub struct InstallmentPurchaseCompletedEvent {
+    pub installment_plan: Pubkey,
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+    pub seller_proceeds: u64,
+    pub marketplace_fee: u64,
+}
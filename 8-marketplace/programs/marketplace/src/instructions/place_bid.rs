@@ -0,0 +1,148 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Auction, Marketplace},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    /// The bidder placing the new highest bid
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    /// The auction being bid on
+    /// - Must match the PDA derived from marketplace, seller, and NFT
+    #[account(
+        mut,
+        seeds = [
+            b"auction",
+            marketplace.key().as_ref(),
+            auction.seller.as_ref(),
+            auction.mint.as_ref(),
+        ],
+        bump = auction.bump,
+    )]
+    pub auction: Account<'info, Auction>,
+
+    /// Escrow holding the highest bid
+    #[account(
+        mut,
+        seeds = [b"bid_escrow", auction.key().as_ref()],
+        bump = auction.escrow_bump,
+    )]
+    pub bid_escrow: SystemAccount<'info>,
+
+    /// The bidder being outbid
+    /// - Required once the auction has a bid, and must match the highest bidder
+    /// - Refunded their bid in the same instruction
+    #[account(mut)]
+    pub previous_bidder: Option<SystemAccount<'info>>,
+
+    /// The marketplace state account for validation
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// Required system program for the escrow transfers
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PlaceBid<'info> {
+    /// Escrow a new highest bid and refund the previous one
+    ///
+    /// # Arguments
+    /// * `amount` - The bid in lamports
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn place_bid(&mut self, amount: u64, event_authority_bump: u8) -> Result<()> {
+        require!(
+            !self.auction.has_ended(Clock::get()?.unix_timestamp),
+            MarketplaceError::AuctionEnded
+        );
+        let min_bid = self
+            .auction
+            .min_next_bid()
+            .ok_or(MarketplaceError::MathOverflow)?;
+        require!(amount >= min_bid, MarketplaceError::BidTooLow);
+
+        // Escrow the new bid before the old one leaves, so the escrow never dips below rent
+        let cpi_ctx = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.bidder.to_account_info(),
+                to: self.bid_escrow.to_account_info(),
+            },
+        );
+        transfer(cpi_ctx, amount)?;
+
+        let previous_bidder = self.auction.highest_bidder;
+        let previous_bid = self.auction.highest_bid;
+        if let Some(previous) = previous_bidder {
+            self.refund_previous_bidder(previous, previous_bid)?;
+        }
+
+        self.auction.highest_bid = amount;
+        self.auction.highest_bidder = Some(self.bidder.key());
+
+        emit_cpi(&self.event_authority, event_authority_bump, &BidPlacedEvent {
+            auction: self.auction.key(),
+            bidder: self.bidder.key(),
+            amount,
+            previous_bidder,
+            previous_bid,
+        })?;
+
+        Ok(())
+    }
+
+    /// Return the outbid amount from the escrow to the previous highest bidder
+    fn refund_previous_bidder(&self, previous: Pubkey, amount: u64) -> Result<()> {
+        let previous_bidder = self
+            .previous_bidder
+            .as_ref()
+            .ok_or(MarketplaceError::InvalidBidder)?;
+        require_keys_eq!(previous_bidder.key(), previous, MarketplaceError::InvalidBidder);
+
+        // Create seeds for PDA signing
+        let auction = self.auction.key();
+        let escrow_seeds: &[&[u8]] = &[
+            b"bid_escrow",
+            auction.as_ref(),
+            &[self.auction.escrow_bump],
+        ];
+        let signer = &[escrow_seeds];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.bid_escrow.to_account_info(),
+                to: previous_bidder.to_account_info(),
+            },
+            signer,
+        );
+        transfer(cpi_ctx, amount)
+    }
+}
+
+#[event]
+pub struct BidPlacedEvent {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub previous_bidder: Option<Pubkey>,
+    pub previous_bid: u64,
+}
@@ -1,48 +1,125 @@
 use anchor_lang::{
     prelude::*,
-    system_program::{transfer, Transfer},
+    system_program::{self, transfer, Transfer},
 };
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    token::{transfer_checked, Token, TransferChecked, Mint, TokenAccount},
-    // token_interface::{},
+    associated_token::{get_associated_token_address, AssociatedToken},
+    metadata::{
+        mpl_token_metadata, thaw_delegated_account, Metadata, MetadataAccount, ThawDelegatedAccount,
+    },
+    token::{mint_to, transfer_checked, Mint, MintTo, Token, TokenAccount, TransferChecked},
+    token_interface::{self, close_account, CloseAccount, TokenInterface},
 };
 
 use crate::{
     error::MarketplaceError,
-    state::{Listing, Marketplace},
+    event_cpi::emit_cpi,
+    programmable::ProgrammableTransfer,
+    state::{
+        Blacklist, CollectionStats, Listing, ListingIndex, Marketplace, PaymentSplit, Proceeds,
+        Provenance, SaleEscrow, SaleRecord, SellerStats,
+    },
 };
 
 /// and collects marketplace fees
+#[event_cpi]
 #[derive(Accounts)]
 pub struct PurchaseNft<'info> {
     /// The NFT mint account being purchased
-    pub nft: Account<'info, Mint>,
+    /// - Owned by the SPL Token or the Token-2022 program
+    #[account(mint::token_program = token_program)]
+    pub nft: Box<InterfaceAccount<'info, token_interface::Mint>>,
 
     /// The listing account being fulfilled
     /// - Contains price and seller information
+    /// - Derived from the stored seller so a wrong seller account gets a dedicated error
     /// - Closed after successful purchase
     #[account(
         mut,
         seeds = [
             b"listing",
             marketplace.key().as_ref(),
-            seller.key().as_ref(),
+            listing.seller.as_ref(),
             nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
         ],
-        bump
+        bump = listing.bump,
+        has_one = seller @ MarketplaceError::ListingSellerMismatch,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch
     )]
     pub listing: Account<'info, Listing>,
 
     /// Token account holding the NFT during listing
     /// - Owned by the listing PDA
     /// - NFT transferred from here to buyer
+    /// - Only required for escrowed listings
     #[account(
         mut,
         associated_token::mint = nft,
         associated_token::authority = listing,
+        associated_token::token_program = token_program,
     )]
-    pub listing_token_account: Account<'info, TokenAccount>,
+    pub listing_token_account: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
+
+    /// The seller's token account, frozen with the listing as delegate
+    /// - NFT thawed and transferred from here to buyer
+    /// - Only required for escrowless listings
+    #[account(
+        mut,
+        associated_token::mint = nft,
+        associated_token::authority = seller,
+        associated_token::token_program = token_program,
+    )]
+    pub seller_token_account: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
+
+    /// The master edition account for the NFT
+    /// - Only required for escrowless listings, to thaw the seller's token account,
+    ///   and for programmable NFTs
+    ///
+    /// CHECK: Validated by the metadata program against the mint during thaw or transfer
+    pub master_edition: Option<UncheckedAccount<'info>>,
+
+    /// The metadata account for the NFT
+    /// - Compared against the metadata hash stored on the listing
+    /// - Only optional when the buyer accepts changed metadata and the NFT is not programmable
+    ///
+    /// CHECK: Loaded as a Token Metadata account of the NFT, and validated by the metadata program
+    /// during programmable transfers
+    #[account(mut)]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// The token record of the listing's vault
+    /// - Only required for programmable NFTs
+    ///
+    /// CHECK: Validated by the metadata program during the transfer
+    #[account(mut)]
+    pub listing_token_record: Option<UncheckedAccount<'info>>,
+
+    /// The token record of the buyer's token account, created by the transfer
+    /// - Only required for programmable NFTs
+    ///
+    /// CHECK: Validated by the metadata program during the transfer
+    #[account(mut)]
+    pub buyer_token_record: Option<UncheckedAccount<'info>>,
+
+    /// The rule set of the programmable NFT
+    /// - Only required for programmable NFTs with a rule set
+    ///
+    /// CHECK: Validated by the metadata program against the NFT's programmable config
+    pub authorization_rules: Option<UncheckedAccount<'info>>,
+
+    /// The Token Auth Rules program
+    /// - Only required for programmable NFTs with a rule set
+    ///
+    /// CHECK: Validated by the metadata program
+    pub authorization_rules_program: Option<UncheckedAccount<'info>>,
+
+    /// The instructions sysvar read by the metadata program
+    /// - Only required for programmable NFTs
+    ///
+    /// CHECK: Address is pinned to the instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub sysvar_instructions: Option<UncheckedAccount<'info>>,
 
     /// The buyer purchasing the NFT
     /// - Pays for the NFT plus marketplace fees
@@ -57,70 +134,297 @@ pub struct PurchaseNft<'info> {
         init_if_needed,
         payer = buyer,
         associated_token::mint = nft,
-        associated_token::authority = buyer
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
     )]
-    pub buyer_token_account: Account<'info, TokenAccount>,
+    pub buyer_token_account: Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
 
     /// The seller who listed the NFT
     /// - Receives payment minus marketplace fees
     /// - Validated against the listing's seller field
-    /// CHECK: Seller account is validated in the instruction logic
+    ///
+    /// CHECK: Matched against the listing's seller by the listing's `has_one` constraint
     #[account(mut)]
     pub seller: AccountInfo<'info>,
 
     /// The marketplace state account
     /// - Contains fee percentage for calculations
+    /// - Must not be paused
+    /// - Updates the marketplace statistics
     #[account(
-        seeds = [b"marketplace"],
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
         bump = marketplace.bump,
+        constraint = !marketplace.paused @ MarketplaceError::MarketplacePaused,
     )]
     pub marketplace: Account<'info, Marketplace>,
 
-    /// Treasury account for collecting marketplace fees
-    /// - Receives the calculated fee percentage
+    /// The account receiving marketplace fees
+    /// - Must be the fee recipient configured on the marketplace
+    /// - Paid nothing while fee splits are set: their recipients, or on token purchases their
+    ///   existing payment mint ATAs, are passed as remaining accounts instead
+    #[account(
+        mut,
+        address = marketplace.fee_recipient @ MarketplaceError::InvalidFeeRecipient,
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+
+    /// The marketplace payment mint
+    /// - Only required when the marketplace is priced in an SPL token
+    pub payment_mint: Option<Box<Account<'info, Mint>>>,
+
+    /// The buyer's payment token account
+    /// - Pays the listing price
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = spl_token_program,
+    )]
+    pub buyer_payment_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// The seller's payment token account
+    /// - Receives payment minus marketplace fees
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = payment_mint,
+        associated_token::authority = seller,
+        associated_token::token_program = spl_token_program,
+    )]
+    pub seller_payment_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// The fee recipient's payment token account
+    /// - Receives the calculated fee percentage, unless fee splits are set
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = payment_mint,
+        associated_token::authority = fee_recipient,
+        associated_token::token_program = spl_token_program,
+    )]
+    pub fee_recipient_payment_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// The wallet that referred the buyer
+    /// - Receives its share of the marketplace fee when the marketplace pays referrals
+    #[account(mut)]
+    pub referrer: Option<SystemAccount<'info>>,
+
+    /// The referrer's payment token account
+    /// - Only required for referred purchases when the marketplace is priced in an SPL token
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = payment_mint,
+        associated_token::authority = referrer,
+        associated_token::token_program = spl_token_program,
+    )]
+    pub referrer_payment_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// The escrow holding the seller proceeds of a protected listing
+    /// - Only required when the listing has a protection window
+    /// - Paid for by the buyer, who gets the rent back when the sale is resolved
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + SaleEscrow::INIT_SPACE,
+        seeds = [b"sale_escrow", listing.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub sale_escrow: Option<Box<Account<'info, SaleEscrow>>>,
+
+    /// The seller's claimable proceeds on a pull-payment marketplace
+    /// - Only required for unprotected SOL sales while the marketplace has pull payments on
+    /// - Created by the buyer on the seller's first such sale
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + Proceeds::INIT_SPACE,
+        seeds = [b"proceeds", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump,
+    )]
+    pub proceeds: Option<Box<Account<'info, Proceeds>>>,
+
+    /// The seller's lifetime statistics, which pick the seller's fee tier
+    /// - Created when the seller listed, since it hands out the listing's nonce
+    /// - Counts the listing off the seller's active listings once sold out
     #[account(
         mut,
-        seeds = [b"treasury", marketplace.key().as_ref()],
-        bump
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump = seller_stats.bump,
     )]
-    pub treasury: SystemAccount<'info>,
+    pub seller_stats: Box<Account<'info, SellerStats>>,
+
+    /// The listing index page holding the listing's entry
+    /// - Only required for listings in the marketplace's listing index
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing index page holding the index's last entry
+    /// - Only required when it is another page than `listing_index`
+    #[account(mut, has_one = marketplace @ MarketplaceError::InvalidListingIndex)]
+    pub last_listing_index: Option<Box<Account<'info, ListingIndex>>>,
+
+    /// The listing at the index's last position, moved into the freed position
+    /// - Only required when it is another listing than the one closing
+    ///
+    /// CHECK: Matched against the index entry in `ListingIndex::unindex`
+    #[account(mut)]
+    pub moved_listing: Option<UncheckedAccount<'info>>,
+
+    /// The statistics of the listing's collection
+    /// - Records the last sale and rolling volume; forgets the floor once the listing sells out
+    /// - Optional; must belong to the listing's marketplace and collection
+    #[account(
+        mut,
+        constraint = collection_stats.marketplace == marketplace.key()
+            && listing.collection == Some(collection_stats.collection)
+            @ MarketplaceError::InvalidCollectionStats,
+    )]
+    pub collection_stats: Option<Box<Account<'info, CollectionStats>>>,
+
+    /// The marketplace reward points mint
+    /// - Minted to buyer and seller when rewards are on
+    #[account(
+        mut,
+        seeds = [b"rewards", marketplace.key().as_ref()],
+        bump = marketplace.rewards_bump,
+    )]
+    pub rewards_mint: Box<Account<'info, Mint>>,
+
+    /// The buyer's reward points account
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = spl_token_program,
+    )]
+    pub buyer_rewards_account: Box<Account<'info, TokenAccount>>,
+
+    /// The seller's reward points account
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = seller,
+        associated_token::token_program = spl_token_program,
+    )]
+    pub seller_rewards_account: Box<Account<'info, TokenAccount>>,
+
+    /// The NFT's sale history
+    /// - Created on its first sale, paid by the buyer
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + Provenance::INIT_SPACE,
+        seeds = [b"provenance", marketplace.key().as_ref(), nft.key().as_ref()],
+        bump,
+    )]
+    pub provenance: Box<Account<'info, Provenance>>,
+
+    /// The buyer's blacklist entry
+    /// - Uses PDA with marketplace and buyer as seeds
+    /// - Must not exist when the marketplace blocks blacklisted buyers
+    ///
+    /// CHECK: Address is pinned by seeds; only its existence is checked
+    #[account(
+        seeds = [b"blacklist", marketplace.key().as_ref(), buyer.key().as_ref()],
+        bump,
+        constraint = !marketplace.block_blacklisted_buyers
+            || !Blacklist::is_blacklisted(&buyer_blacklist) @ MarketplaceError::WalletBlacklisted,
+    )]
+    pub buyer_blacklist: UncheckedAccount<'info>,
 
     /// Required programs
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    /// The token program owning the NFT mint, SPL Token or Token-2022
+    pub token_program: Interface<'info, TokenInterface>,
+    /// The SPL Token program owning the payment and reward points mints
+    pub spl_token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// Metadata program, only required for escrowless listings and programmable NFTs
+    pub metadata_program: Option<Program<'info, Metadata>>,
 }
 
 impl<'info> PurchaseNft<'info> {
-    /// Transfer the NFT from listing to buyer
-    /// 
+    /// Transfer the purchased tokens from listing to buyer
+    ///
+    /// # Arguments
+    /// * `amount` - Tokens to buy; the whole listing for an NFT
+    /// * `accept_changed_metadata` - Whether to buy even if the name, symbol or URI changed since listing
+    ///
     /// # Returns
     /// * `Result<()>` - Success or error from the transfer
-    pub fn transfer_nft(&mut self) -> Result<()> {
-        // Validate listing is active and seller matches
+    pub fn transfer_nft(&mut self, amount: u64, accept_changed_metadata: bool) -> Result<()> {
+        // Validate listing is active; the seller is matched by the account constraints
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
         require!(
-            self.listing.is_active && self.listing.seller == self.seller.key(),
-            MarketplaceError::ListingNotActive
+            !self.listing.is_expired(Clock::get()?.unix_timestamp),
+            MarketplaceError::ListingExpired
         );
+        require!(
+            self.listing.has_started(Clock::get()?.unix_timestamp),
+            MarketplaceError::SaleNotStarted
+        );
+        require!(
+            amount > 0 && amount <= self.listing.quantity,
+            MarketplaceError::InsufficientQuantity
+        );
+        require!(
+            self.listing.can_buy(&self.buyer.key()),
+            MarketplaceError::NotAllowedBuyer
+        );
+        // Self-trades would only farm rewards and inflate volume; `delist_nft` returns the NFT
+        require_keys_neq!(self.buyer.key(), self.listing.seller, MarketplaceError::SelfPurchase);
+        require!(
+            self.sale_escrow.is_some() == self.listing.is_protected(),
+            MarketplaceError::InvalidSaleEscrow
+        );
+        if !accept_changed_metadata {
+            self.check_metadata_unchanged()?;
+        }
 
         // Create seeds for PDA signing
         let marketplace = self.marketplace.key();
         let seller = self.seller.key();
         let nft = self.nft.key();
+        let nonce = self.listing.nonce.to_le_bytes();
         let listing_seeds: &[&[u8]] = &[
             b"listing",
             marketplace.as_ref(),
             seller.as_ref(),
             nft.as_ref(),
+            &nonce,
             &[self.listing.bump],
         ];
         let signer = &[listing_seeds];
 
-        // Create CPI context with PDA signer
+        // pNFTs are frozen in the vault and move through Token Metadata
+        if self.listing.programmable {
+            return self.programmable_transfer()?.invoke_signed(signer);
+        }
+
+        // Escrowless NFTs are still in the seller's wallet, frozen with the listing as delegate
+        let from = if self.listing.escrowless {
+            self.thaw_seller_token_account(signer)?
+        } else {
+            self.listing_token_account
+                .as_ref()
+                .ok_or(MarketplaceError::MissingListingTokenAccount)?
+                .to_account_info()
+        };
+
+        // Create CPI context with PDA signer, as vault owner or as delegate
         let cpi_ctx = CpiContext::new_with_signer(
             self.token_program.to_account_info(),
-            TransferChecked {
-                from: self.listing_token_account.to_account_info(),
+            token_interface::TransferChecked {
+                from,
                 mint: self.nft.to_account_info(),
                 to: self.buyer_token_account.to_account_info(),
                 authority: self.listing.to_account_info(),
@@ -128,56 +432,777 @@ impl<'info> PurchaseNft<'info> {
             signer,
         );
 
-        // Transfer the NFT to the buyer
-        transfer_checked(cpi_ctx, 1, self.nft.decimals)
+        // Transfer the tokens to the buyer; spending the delegated amount clears the delegate
+        token_interface::transfer_checked(cpi_ctx, amount, self.nft.decimals)
     }
 
-    /// Transfer SOL payment from buyer to seller and treasury
-    /// 
+    /// Check the NFT's name, symbol and URI still hash to what the seller listed
+    /// - Catches update authorities swapping the image or attributes after listing
+    ///
     /// # Returns
-    /// * `Result<()>` - Success or error from the transfers
-    pub fn transfer_sol(&mut self) -> Result<()> {
-        // Calculate marketplace fee (percentage of listing price)
-        let fee_lamports = (self.marketplace.fee_percentage as u64)
-            .checked_mul(self.listing.price)
-            .ok_or(MarketplaceError::MathOverflow)?
-            .checked_div(100)
-            .ok_or(MarketplaceError::MathOverflow)?;
+    /// * `Result<()>` - Success, or `MetadataChanged` if the metadata differs
+    fn check_metadata_unchanged(&self) -> Result<()> {
+        let metadata_info = self
+            .metadata
+            .as_ref()
+            .ok_or(MarketplaceError::InvalidMetadataAccount)?;
+        check_listed_metadata(&self.listing, &self.nft.key(), metadata_info)
+    }
+
+    /// Collect the accounts of a Token Metadata transfer from the vault to the buyer
+    ///
+    /// # Returns
+    /// * `Result<ProgrammableTransfer>` - The transfer, or an error if a pNFT account is missing
+    fn programmable_transfer(&self) -> Result<ProgrammableTransfer<'info>> {
+        let listing_token_account = self
+            .listing_token_account
+            .as_ref()
+            .ok_or(MarketplaceError::MissingListingTokenAccount)?;
+        let (
+            Some(master_edition),
+            Some(metadata),
+            Some(listing_token_record),
+            Some(buyer_token_record),
+            Some(sysvar_instructions),
+            Some(metadata_program),
+        ) = (
+            self.master_edition.as_ref(),
+            self.metadata.as_ref(),
+            self.listing_token_record.as_ref(),
+            self.buyer_token_record.as_ref(),
+            self.sysvar_instructions.as_ref(),
+            self.metadata_program.as_ref(),
+        )
+        else {
+            return err!(MarketplaceError::MissingProgrammableAccounts);
+        };
 
-        // Calculate seller payment (listing price minus fees)
-        let seller_lamports = self
+        Ok(ProgrammableTransfer {
+            source: listing_token_account.to_account_info(),
+            source_owner: self.listing.to_account_info(),
+            source_token_record: listing_token_record.to_account_info(),
+            destination: self.buyer_token_account.to_account_info(),
+            destination_owner: self.buyer.to_account_info(),
+            destination_token_record: buyer_token_record.to_account_info(),
+            mint: self.nft.to_account_info(),
+            metadata: metadata.to_account_info(),
+            edition: master_edition.to_account_info(),
+            authority: self.listing.to_account_info(),
+            payer: self.buyer.to_account_info(),
+            authorization_rules: self.authorization_rules.as_ref().map(|a| a.to_account_info()),
+            authorization_rules_program: self
+                .authorization_rules_program
+                .as_ref()
+                .map(|a| a.to_account_info()),
+            sysvar_instructions: sysvar_instructions.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            associated_token_program: self.associated_token_program.to_account_info(),
+            metadata_program: metadata_program.to_account_info(),
+        })
+    }
+
+    /// Thaw the seller's token account of an escrowless listing
+    ///
+    /// # Arguments
+    /// * `signer` - The listing PDA signer seeds
+    ///
+    /// # Returns
+    /// * `Result<AccountInfo>` - The thawed seller token account, to transfer from
+    fn thaw_seller_token_account(&self, signer: &[&[&[u8]]]) -> Result<AccountInfo<'info>> {
+        let (Some(seller_token_account), Some(master_edition), Some(metadata_program)) = (
+            self.seller_token_account.as_ref(),
+            self.master_edition.as_ref(),
+            self.metadata_program.as_ref(),
+        ) else {
+            return err!(MarketplaceError::MissingEscrowlessAccounts);
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            metadata_program.to_account_info(),
+            ThawDelegatedAccount {
+                metadata: metadata_program.to_account_info(),
+                delegate: self.listing.to_account_info(),
+                token_account: seller_token_account.to_account_info(),
+                edition: master_edition.to_account_info(),
+                mint: self.nft.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+            signer,
+        );
+        thaw_delegated_account(cpi_ctx)?;
+
+        Ok(seller_token_account.to_account_info())
+    }
+
+    /// Transfer the payment from buyer to seller and fee recipient
+    /// - In SOL, or in the payment mint when the marketplace has one
+    ///
+    /// # Arguments
+    /// * `amount` - Tokens bought, charged at the listing's price per token
+    /// * `fee_split_recipients` - The marketplace's fee split recipients in stored order, or their
+    ///   payment mint ATAs on token purchases
+    ///
+    /// # Returns
+    /// * `Result<(u64, PaymentSplit, u16)>` - The total price paid, how it was split and the
+    ///   holder discount taken off the fee rate
+    pub fn transfer_payment(
+        &mut self,
+        amount: u64,
+        fee_split_recipients: &[AccountInfo<'info>],
+    ) -> Result<(u64, PaymentSplit, u16)> {
+        // Dutch listings are charged their decayed price at execution time
+        let price = self
             .listing
-            .price
-            .checked_sub(fee_lamports)
-            .ok_or(MarketplaceError::MathOverflow)?;
+            .total_price(Clock::get()?.unix_timestamp, amount)?;
+
+        // High-volume sellers pay the fee of the tier their volume before this sale reaches
+        let (fee_bps, fee_discount_bps) = self.fee_bps();
+
+        // Every unit of the price must reach exactly one recipient
+        let split = self
+            .marketplace
+            .split_payment(price, fee_bps, self.referrer.is_some())?;
+        require!(
+            split.total() == Some(price),
+            MarketplaceError::PaymentSplitMismatch
+        );
+        require!(
+            self.proceeds.is_some() == self.pays_into_proceeds(),
+            MarketplaceError::InvalidProceedsAccount
+        );
 
-        // Transfer fee to treasury
-        let treasury_transfer_ctx = CpiContext::new(
+        match self.marketplace.payment_mint {
+            Some(payment_mint) => {
+                self.transfer_tokens(payment_mint, &split, fee_split_recipients)?
+            }
+            None => self.transfer_sol(&split, fee_split_recipients)?,
+        }
+
+        Ok((price, split, fee_discount_bps))
+    }
+
+    /// Transfer the payment from buyer to seller and fee recipient in the listing's secondary mint
+    /// - The fee is taken at the same rate as in the marketplace currency
+    ///
+    /// # Arguments
+    /// * `amount` - Tokens bought, charged at the listing's secondary price per token
+    /// * `fee_split_recipients` - The secondary mint ATAs of the marketplace's fee split
+    ///   recipients, in stored order
+    ///
+    /// # Returns
+    /// * `Result<(Pubkey, u64, PaymentSplit, u16)>` - The secondary mint, the total price paid in
+    ///   it, how it was split and the holder discount taken off the fee rate
+    pub fn transfer_secondary_payment(
+        &mut self,
+        amount: u64,
+        fee_split_recipients: &[AccountInfo<'info>],
+    ) -> Result<(Pubkey, u64, PaymentSplit, u16)> {
+        let (mint, price) = self.listing.total_secondary_price(amount)?;
+        let (fee_bps, fee_discount_bps) = self.fee_bps();
+
+        // Every unit of the price must reach exactly one recipient
+        let split = self
+            .marketplace
+            .split_payment(price, fee_bps, self.referrer.is_some())?;
+        require!(
+            split.total() == Some(price),
+            MarketplaceError::PaymentSplitMismatch
+        );
+        // Token accounts can be owned by any address, so sellers are always paid directly
+        require!(self.proceeds.is_none(), MarketplaceError::InvalidProceedsAccount);
+
+        self.transfer_tokens(mint, &split, fee_split_recipients)?;
+
+        Ok((mint, price, split, fee_discount_bps))
+    }
+
+    /// The fee rate of this sale: the seller's volume tier, less the buyer's holder discount
+    /// - The buyer's reward points are read before this sale's points are minted
+    ///
+    /// # Returns
+    /// * `(u16, u16)` - The fee rate and the holder discount taken off it
+    fn fee_bps(&self) -> (u16, u16) {
+        let fee_bps = self.marketplace.fee_bps_for_volume(self.seller_stats.volume);
+        self.marketplace
+            .holder_fee_bps(fee_bps, self.buyer_rewards_account.amount)
+    }
+
+    /// Transfer SOL payment from buyer to seller and fee recipient
+    ///
+    /// # Arguments
+    /// * `split` - The sale price split into lamports per recipient
+    /// * `fee_split_recipients` - The marketplace's fee split recipients in stored order
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error from the transfers
+    pub fn transfer_sol(
+        &mut self,
+        split: &PaymentSplit,
+        fee_split_recipients: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        // Transfer fee to the fee recipient or its split recipients, less the referrer's share
+        // Skipped when it rounds to zero, e.g. on one-lamport sales
+        if split.marketplace_fee > 0 {
+            self.transfer_fee(split.marketplace_fee, fee_split_recipients)?;
+        }
+
+        // Transfer the referral to the referrer
+        if let Some(referrer) = self.referrer.as_ref().filter(|_| split.referral_paid > 0) {
+            let referral_transfer_ctx = CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.buyer.to_account_info(),
+                    to: referrer.to_account_info(),
+                },
+            );
+            transfer(referral_transfer_ctx, split.referral_paid)?;
+        }
+
+        // Transfer remaining payment to seller, or park it in the sale escrow of a protected listing
+        // or in the seller's proceeds account on a pull-payment marketplace
+        let proceeds_to = match (self.sale_escrow.as_ref(), self.proceeds.as_ref()) {
+            (Some(sale_escrow), _) => sale_escrow.to_account_info(),
+            (None, Some(proceeds)) => proceeds.to_account_info(),
+            (None, None) => self.seller.to_account_info(),
+        };
+        let seller_transfer_ctx = CpiContext::new(
             self.system_program.to_account_info(),
             Transfer {
                 from: self.buyer.to_account_info(),
-                to: self.treasury.to_account_info(),
+                to: proceeds_to,
             },
         );
-        transfer(treasury_transfer_ctx, fee_lamports)?;
+        transfer(seller_transfer_ctx, split.seller_proceeds)?;
+
+        self.credit_proceeds(split.seller_proceeds)
+    }
+
+    /// Transfer the marketplace fee in SOL
+    /// - Without fee splits the whole fee goes to the fee recipient
+    /// - With fee splits each recipient gets its share, the rounding dust going to the first
+    ///
+    /// # Arguments
+    /// * `fee` - The marketplace fee in lamports
+    /// * `fee_split_recipients` - Accounts matching the marketplace's fee splits, in stored order
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error from the transfers
+    fn transfer_fee(&self, fee: u64, fee_split_recipients: &[AccountInfo<'info>]) -> Result<()> {
+        if self.marketplace.fee_splits.is_empty() {
+            let fee_transfer_ctx = CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.buyer.to_account_info(),
+                    to: self.fee_recipient.to_account_info(),
+                },
+            );
+            return transfer(fee_transfer_ctx, fee);
+        }
+
+        let recipients = fee_split_recipients
+            .get(..self.marketplace.fee_splits.len())
+            .ok_or(MarketplaceError::InvalidFeeRecipient)?;
+        let shares = self.marketplace.split_fee(fee)?;
+        let payouts = self.marketplace.fee_splits.iter().zip(recipients).zip(shares);
+        for ((fee_split, recipient), share) in payouts {
+            require_keys_eq!(
+                recipient.key(),
+                fee_split.recipient,
+                MarketplaceError::InvalidFeeRecipient
+            );
+            if share == 0 {
+                continue;
+            }
+
+            let fee_transfer_ctx = CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.buyer.to_account_info(),
+                    to: recipient.clone(),
+                },
+            );
+            transfer(fee_transfer_ctx, share)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether the seller proceeds of this purchase go to the seller's Proceeds PDA
+    /// - Only SOL sales: token accounts can be owned by any address, so sellers always receive them
+    /// - Protected listings keep parking their proceeds in the sale escrow
+    fn pays_into_proceeds(&self) -> bool {
+        self.marketplace.pull_payments
+            && self.marketplace.payment_mint.is_none()
+            && !self.listing.is_protected()
+    }
+
+    /// Count the lamports just transferred onto the seller's proceeds account as claimable
+    /// - Does nothing when the seller is paid directly or through a sale escrow
+    ///
+    /// # Arguments
+    /// * `amount` - The seller proceeds of the sale
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    fn credit_proceeds(&mut self, amount: u64) -> Result<()> {
+        let marketplace = self.marketplace.key();
+        let seller = self.seller.key();
+        let Some(proceeds) = self.proceeds.as_mut() else {
+            return Ok(());
+        };
+
+        if proceeds.is_uninitialized() {
+            let (_, bump) = Pubkey::find_program_address(
+                &[b"proceeds", marketplace.as_ref(), seller.as_ref()],
+                &crate::ID,
+            );
+            proceeds.set_inner(Proceeds::new(marketplace, seller, bump));
+        }
+        proceeds.credit(amount)
+    }
+
+    /// Transfer payment token from buyer to seller and fee recipient
+    ///
+    /// # Arguments
+    /// * `payment_mint` - The mint the sale is priced in, the marketplace's or the listing's secondary
+    /// * `split` - The sale price split into payment token base units per recipient
+    /// * `fee_split_recipients` - The payment mint ATAs of the marketplace's fee split recipients,
+    ///   in stored order
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error from the transfers
+    pub fn transfer_tokens(
+        &mut self,
+        payment_mint: Pubkey,
+        split: &PaymentSplit,
+        fee_split_recipients: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let (Some(mint), Some(buyer_account), Some(seller_account), Some(fee_account)) = (
+            self.payment_mint.as_ref(),
+            self.buyer_payment_account.as_ref(),
+            self.seller_payment_account.as_ref(),
+            self.fee_recipient_payment_account.as_ref(),
+        ) else {
+            return err!(MarketplaceError::MissingPaymentAccounts);
+        };
+        require_keys_eq!(mint.key(), payment_mint, MarketplaceError::InvalidPaymentMint);
+        // Sale escrows only hold lamports
+        require!(self.sale_escrow.is_none(), MarketplaceError::InvalidSaleEscrow);
+
+        // Transfer fee to the fee recipient or its split recipients, less the referrer's share
+        // Skipped when it rounds to zero, e.g. on one-unit sales
+        if split.marketplace_fee > 0 {
+            self.transfer_token_fee(
+                split.marketplace_fee,
+                mint,
+                buyer_account,
+                fee_account,
+                fee_split_recipients,
+            )?;
+        }
+
+        // Transfer the referral to the referrer
+        if split.referral_paid > 0 {
+            let referrer_account = self
+                .referrer_payment_account
+                .as_ref()
+                .ok_or(MarketplaceError::MissingPaymentAccounts)?;
+            let referral_transfer_ctx = CpiContext::new(
+                self.spl_token_program.to_account_info(),
+                TransferChecked {
+                    from: buyer_account.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: referrer_account.to_account_info(),
+                    authority: self.buyer.to_account_info(),
+                },
+            );
+            transfer_checked(referral_transfer_ctx, split.referral_paid, mint.decimals)?;
+        }
 
         // Transfer remaining payment to seller
         let seller_transfer_ctx = CpiContext::new(
-            self.system_program.to_account_info(),
-            Transfer {
-                from: self.buyer.to_account_info(),
-                to: self.seller.to_account_info(),
+            self.spl_token_program.to_account_info(),
+            TransferChecked {
+                from: buyer_account.to_account_info(),
+                mint: mint.to_account_info(),
+                to: seller_account.to_account_info(),
+                authority: self.buyer.to_account_info(),
             },
         );
-        transfer(seller_transfer_ctx, seller_lamports)?;
+        transfer_checked(seller_transfer_ctx, split.seller_proceeds, mint.decimals)
+    }
+
+    /// Transfer the marketplace fee in the payment mint
+    /// - Without fee splits the whole fee goes to the fee recipient's payment account
+    /// - With fee splits each recipient's ATA gets its share, the rounding dust going to the first
+    ///
+    /// # Arguments
+    /// * `fee` - The marketplace fee in payment token base units
+    /// * `mint` - The payment mint
+    /// * `buyer_account` - The buyer's payment account
+    /// * `fee_account` - The fee recipient's payment account
+    /// * `fee_split_recipients` - ATAs matching the marketplace's fee splits, in stored order
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error from the transfers
+    fn transfer_token_fee(
+        &self,
+        fee: u64,
+        mint: &Account<'info, Mint>,
+        buyer_account: &Account<'info, TokenAccount>,
+        fee_account: &Account<'info, TokenAccount>,
+        fee_split_recipients: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let fee_transfer = |to: AccountInfo<'info>, amount: u64| {
+            let fee_transfer_ctx = CpiContext::new(
+                self.spl_token_program.to_account_info(),
+                TransferChecked {
+                    from: buyer_account.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to,
+                    authority: self.buyer.to_account_info(),
+                },
+            );
+            transfer_checked(fee_transfer_ctx, amount, mint.decimals)
+        };
+
+        if self.marketplace.fee_splits.is_empty() {
+            return fee_transfer(fee_account.to_account_info(), fee);
+        }
+
+        let recipients = fee_split_recipients
+            .get(..self.marketplace.fee_splits.len())
+            .ok_or(MarketplaceError::InvalidFeeRecipient)?;
+        let shares = self.marketplace.split_fee(fee)?;
+        let payouts = self.marketplace.fee_splits.iter().zip(recipients).zip(shares);
+        for ((fee_split, recipient), share) in payouts {
+            require_keys_eq!(
+                recipient.key(),
+                get_associated_token_address(&fee_split.recipient, &mint.key()),
+                MarketplaceError::InvalidFeeRecipient
+            );
+            if share == 0 {
+                continue;
+            }
+
+            fee_transfer(recipient.clone(), share)?;
+        }
 
         Ok(())
     }
 
-   
-    pub fn delist_nft(&mut self) -> Result<()> {
-        // Mark listing as inactive (though it will be closed anyway)
-        self.listing.is_active = false;
+    /// Record the proceeds parked in the sale escrow of a protected listing
+    /// - Does nothing for unprotected listings, whose seller is already paid
+    /// - The NFT is already with the buyer; only the proceeds wait for the window
+    ///
+    /// # Arguments
+    /// * `split` - The payment split, as returned by `transfer_payment`
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn open_sale_escrow(
+        &mut self,
+        split: &PaymentSplit,
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        if !self.listing.is_protected() {
+            return Ok(());
+        }
+
+        let amount = split.seller_proceeds;
+        let release_ts = Clock::get()?
+            .unix_timestamp
+            .checked_add(self.listing.protection_window_secs.into())
+            .ok_or(MarketplaceError::MathOverflow)?;
+        let listing = self.listing.key();
+        let buyer = self.buyer.key();
+
+        let Some(sale_escrow) = self.sale_escrow.as_mut() else {
+            return err!(MarketplaceError::InvalidSaleEscrow);
+        };
+        let (_, bump) = Pubkey::find_program_address(
+            &[b"sale_escrow", listing.as_ref(), buyer.as_ref()],
+            &crate::ID,
+        );
+        sale_escrow.set_inner(SaleEscrow {
+            marketplace: self.marketplace.key(),
+            listing,
+            seller: self.seller.key(),
+            buyer,
+            amount,
+            release_ts,
+            disputed: false,
+            bump,
+        });
+
+        emit_cpi(&self.event_authority, event_authority_bump, &SaleEscrowedEvent {
+            sale_escrow: sale_escrow.key(),
+            listing,
+            buyer,
+            seller: self.seller.key(),
+            amount,
+            release_ts,
+        })?;
+
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Mint reward points for the sale to buyer and seller
+    /// - Skipped entirely when the reward rate is zero
+    ///
+    /// # Arguments
+    /// * `price` - The total price paid, as returned by `transfer_payment`
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error from the mints
+    pub fn mint_rewards(&mut self, price: u64, event_authority_bump: u8) -> Result<()> {
+        if self.marketplace.reward_rate_bps == 0 {
+            return Ok(());
+        }
+
+        let (buyer_amount, seller_amount) = self.marketplace.rewards_for(price)?;
+
+        // Create seeds for marketplace PDA signing
+        let marketplace_seeds: &[&[u8]] = &[
+            b"marketplace",
+            self.marketplace.admin.as_ref(),
+            self.marketplace.name.as_bytes(),
+            &[self.marketplace.bump],
+        ];
+        let signer = &[marketplace_seeds];
+
+        for (to, amount) in [
+            (self.buyer_rewards_account.to_account_info(), buyer_amount),
+            (self.seller_rewards_account.to_account_info(), seller_amount),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.spl_token_program.to_account_info(),
+                MintTo {
+                    mint: self.rewards_mint.to_account_info(),
+                    to,
+                    authority: self.marketplace.to_account_info(),
+                },
+                signer,
+            );
+            mint_to(cpi_ctx, amount)?;
+        }
+
+        emit_cpi(&self.event_authority, event_authority_bump, &RewardsMintedEvent {
+            listing: self.listing.key(),
+            buyer: self.buyer.key(),
+            seller: self.seller.key(),
+            buyer_amount,
+            seller_amount,
+        })?;
+
+        Ok(())
+    }
+
+    /// Count the sold tokens off the listing and the sale in the marketplace statistics
+    /// - Closes the listing and its vault, refunding the seller, once nothing remains
+    ///
+    /// # Arguments
+    /// * `amount` - Tokens bought
+    /// * `price` - The total price paid, as returned by `transfer_payment`
+    /// * `split` - How the price was paid out, as returned by `transfer_payment`
+    /// * `fee_discount_bps` - The holder discount taken off the fee rate, as returned by `transfer_payment`
+    /// * `secondary_mint` - The listing's secondary mint when the sale was paid in it
+    /// * `provenance_bump` - PDA bump of the NFT's provenance account
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_sale(
+        &mut self,
+        amount: u64,
+        price: u64,
+        split: &PaymentSplit,
+        fee_discount_bps: u16,
+        secondary_mint: Option<Pubkey>,
+        provenance_bump: u8,
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        emit_cpi(&self.event_authority, event_authority_bump, &NftPurchasedEvent {
+            listing: self.listing.key(),
+            buyer: self.buyer.key(),
+            seller: self.seller.key(),
+            amount,
+            price,
+            private: self.listing.allowed_buyer.is_some(),
+            referrer: self.referrer.as_ref().map(|referrer| referrer.key()),
+            seller_proceeds: split.seller_proceeds,
+            marketplace_fee: split.marketplace_fee,
+            royalty_paid: split.royalty_paid,
+            referral_paid: split.referral_paid,
+            fee_discount_bps,
+            // The system program id stands for native SOL
+            payment_mint: secondary_mint
+                .or(self.marketplace.payment_mint)
+                .unwrap_or(system_program::ID),
+        })?;
+
+        // Volume and fees are kept in the marketplace currency, so secondary sales only count
+        let volume = match secondary_mint {
+            Some(_) => {
+                self.marketplace.record_sale(0, 0);
+                self.seller_stats.record_sale(0);
+                0
+            }
+            None => {
+                self.marketplace.record_sale(price, split.fee());
+                self.seller_stats.record_sale(price);
+                price
+            }
+        };
+
+        if self.provenance.is_uninitialized() {
+            self.provenance.set_inner(Provenance::new(
+                self.marketplace.key(),
+                self.nft.key(),
+                provenance_bump,
+            ));
+        }
+        self.provenance.record_sale(SaleRecord {
+            price: volume,
+            buyer: self.buyer.key(),
+            seller: self.seller.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        if let Some(collection_stats) = self.collection_stats.as_mut() {
+            if secondary_mint.is_none() {
+                collection_stats.record_sale(price, price / amount, Clock::get()?.unix_timestamp);
+            }
+        }
+
+        self.listing.quantity -= amount;
+        if self.listing.quantity > 0 {
+            return Ok(());
+        }
+
+        self.listing.is_active = false;
+        self.marketplace.listing_closed();
+        self.seller_stats.listing_closed();
+        if let Some(collection_stats) = self.collection_stats.as_mut() {
+            collection_stats.listing_closed(self.listing.key());
+        }
+        ListingIndex::unindex(
+            &mut self.marketplace,
+            &mut self.listing,
+            self.listing_index.as_deref_mut(),
+            self.last_listing_index.as_deref_mut(),
+            self.moved_listing.as_ref(),
+        )?;
+
+        // pNFT vaults are left frozen by Token Metadata, so only SPL vaults can be closed
+        let vault = self
+            .listing_token_account
+            .as_ref()
+            .filter(|_| !self.listing.programmable);
+        if let Some(listing_token_account) = vault {
+            // Create seeds for PDA signing
+            let marketplace = self.marketplace.key();
+            let seller = self.seller.key();
+            let nft = self.nft.key();
+            let nonce = self.listing.nonce.to_le_bytes();
+            let listing_seeds: &[&[u8]] = &[
+                b"listing",
+                marketplace.as_ref(),
+                seller.as_ref(),
+                nft.as_ref(),
+                &nonce,
+                &[self.listing.bump],
+            ];
+            let signer = &[listing_seeds];
+
+            // Return the empty vault's rent to the seller
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                CloseAccount {
+                    account: listing_token_account.to_account_info(),
+                    destination: self.seller.to_account_info(),
+                    authority: self.listing.to_account_info(),
+                },
+                signer,
+            );
+            close_account(cpi_ctx)?;
+        }
+
+        self.listing.close(self.seller.to_account_info())
+    }
+}
+
+/// Check an NFT's metadata account still hashes to what the seller listed
+/// - Shared by `purchase_nft` and `purchase_many`
+///
+/// # Arguments
+/// * `listing` - The listing being bought
+/// * `nft` - The listed mint
+/// * `metadata_info` - The NFT's metadata account
+///
+/// # Returns
+/// * `Result<()>` - Success, or `MetadataChanged` if the metadata differs
+pub(crate) fn check_listed_metadata(
+    listing: &Listing,
+    nft: &Pubkey,
+    metadata_info: &AccountInfo,
+) -> Result<()> {
+    // The owner and layout are checked here; the address against the mint
+    require_keys_eq!(
+        *metadata_info.owner,
+        mpl_token_metadata::ID,
+        MarketplaceError::InvalidMetadataAccount
+    );
+    let metadata = MetadataAccount::try_deserialize(&mut &metadata_info.try_borrow_data()?[..])
+        .map_err(|_| MarketplaceError::InvalidMetadataAccount)?;
+    require_keys_eq!(metadata.mint, *nft, MarketplaceError::InvalidMetadataAccount);
+
+    require!(
+        Listing::hash_metadata(&metadata.name, &metadata.symbol, &metadata.uri)
+            == listing.metadata_hash,
+        MarketplaceError::MetadataChanged
+    );
+    Ok(())
+}
+
+#[event]
+pub struct NftPurchasedEvent {
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub amount: u64,
+    pub price: u64,
+    pub private: bool,
+    pub referrer: Option<Pubkey>,
+    pub seller_proceeds: u64,
+    pub marketplace_fee: u64,
+    pub royalty_paid: u64,
+    pub referral_paid: u64,
+    pub fee_discount_bps: u16,
+    pub payment_mint: Pubkey,
+}
+
+#[event]
+pub struct SaleEscrowedEvent {
+    pub sale_escrow: Pubkey,
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub amount: u64,
+    pub release_ts: i64,
+}
+
+#[event]
+pub struct RewardsMintedEvent {
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub buyer_amount: u64,
+    pub seller_amount: u64,
+}
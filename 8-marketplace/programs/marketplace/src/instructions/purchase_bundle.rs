@@ -0,0 +1,336 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::{create_idempotent, get_associated_token_address, AssociatedToken, Create},
+    token::{
+        close_account, mint_to, transfer_checked, CloseAccount, Mint, MintTo, Token, TokenAccount,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    instructions::RewardsMintedEvent,
+    state::{BundleListing, Marketplace, PaymentSplit, SellerStats},
+};
+
+/// Number of remaining accounts describing one NFT of a bundle purchase
+const ACCOUNTS_PER_BUNDLE_PURCHASE: usize = 3;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PurchaseBundle<'info> {
+    /// The buyer paying for the whole bundle
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The seller who listed the bundle
+    /// - Receives the payment and the bundle and vault rent
+    ///
+    /// CHECK: Part of the bundle PDA seeds
+    #[account(mut)]
+    pub seller: AccountInfo<'info>,
+
+    /// The marketplace state account
+    /// - Validates this is the correct marketplace instance
+    /// - Must not be paused
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        constraint = !marketplace.paused @ MarketplaceError::MarketplacePaused,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The bundle being bought
+    /// - Closed to the seller once every NFT has moved to the buyer
+    #[account(
+        mut,
+        seeds = [
+            b"bundle",
+            marketplace.key().as_ref(),
+            seller.key().as_ref(),
+            bundle.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = bundle.bump,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+        close = seller,
+    )]
+    pub bundle: Box<Account<'info, BundleListing>>,
+
+    /// The seller's statistics account
+    /// - Picks the seller's fee tier and records the sale
+    #[account(
+        mut,
+        seeds = [b"seller_stats", marketplace.key().as_ref(), seller.key().as_ref()],
+        bump = seller_stats.bump,
+    )]
+    pub seller_stats: Box<Account<'info, SellerStats>>,
+
+    /// The account sale fees are paid to
+    /// - Must match the marketplace's configured fee recipient
+    #[account(
+        mut,
+        address = marketplace.fee_recipient @ MarketplaceError::InvalidFeeRecipient,
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+
+    /// Reward points mint of the marketplace
+    #[account(
+        mut,
+        seeds = [b"rewards", marketplace.key().as_ref()],
+        bump = marketplace.rewards_bump,
+    )]
+    pub rewards_mint: Box<Account<'info, Mint>>,
+
+    /// The buyer's reward points account
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = buyer,
+    )]
+    pub buyer_rewards_account: Box<Account<'info, TokenAccount>>,
+
+    /// The seller's reward points account
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = seller,
+    )]
+    pub seller_rewards_account: Box<Account<'info, TokenAccount>>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> PurchaseBundle<'info> {
+    /// Buy every NFT of the bundle for its single price
+    /// - The price is split once, with the seller's fee tier, as in `purchase_nft`
+    /// - Bundles can only be bought whole
+    ///
+    /// # Arguments
+    /// * `items` - Groups of (mint, vault, buyer token account) accounts, one per bundled NFT
+    ///   in listing order
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn purchase_bundle(
+        &mut self,
+        items: &'info [AccountInfo<'info>],
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        let groups = items.chunks_exact(ACCOUNTS_PER_BUNDLE_PURCHASE);
+        require!(groups.remainder().is_empty(), MarketplaceError::InvalidBundleSize);
+        let mints: Vec<Pubkey> = groups.clone().map(|group| group[0].key()).collect();
+        require!(
+            self.bundle.is_whole_bundle(&mints),
+            MarketplaceError::InvalidBundleAccount
+        );
+        // Token payments and proceeds accounts are not supported for bundles
+        require!(
+            self.marketplace.payment_mint.is_none() && !self.marketplace.pull_payments,
+            MarketplaceError::UnsupportedBundle
+        );
+        // Self-trades would only farm rewards and inflate volume; `delist_bundle` returns the NFTs
+        require_keys_neq!(self.buyer.key(), self.seller.key(), MarketplaceError::SelfPurchase);
+
+        for group in groups {
+            self.transfer_item(group)?;
+        }
+
+        let price = self.bundle.price;
+        let fee_bps = self.marketplace.fee_bps_for_volume(self.seller_stats.volume);
+        let split = self.marketplace.split_payment(price, fee_bps, false)?;
+        require!(
+            split.total() == Some(price),
+            MarketplaceError::PaymentSplitMismatch
+        );
+        self.transfer_sol(&split)?;
+        self.mint_rewards(price, event_authority_bump)?;
+
+        self.marketplace.record_sale(price, split.fee());
+        self.marketplace.listing_closed();
+        self.seller_stats.record_sale(price);
+        self.seller_stats.listing_closed();
+
+        emit_cpi(&self.event_authority, event_authority_bump, &BundlePurchasedEvent {
+            bundle: self.bundle.key(),
+            buyer: self.buyer.key(),
+            seller: self.seller.key(),
+            mints,
+            price,
+            seller_proceeds: split.seller_proceeds,
+            marketplace_fee: split.marketplace_fee,
+        })?;
+
+        Ok(())
+    }
+
+    /// Validate one (mint, vault, buyer token account) group and move its NFT to the buyer
+    /// - Closes the emptied vault, refunding the seller
+    fn transfer_item(&self, group: &'info [AccountInfo<'info>]) -> Result<()> {
+        let [mint_info, vault, buyer_token_account] = group else {
+            return err!(MarketplaceError::InvalidBundleSize);
+        };
+
+        let mint = Account::<Mint>::try_from(mint_info)
+            .map_err(|_| MarketplaceError::InvalidBundleAccount)?;
+        let nft = mint.key();
+        let bundle = self.bundle.key();
+        require_keys_eq!(
+            vault.key(),
+            get_associated_token_address(&bundle, &nft),
+            MarketplaceError::InvalidBundleAccount
+        );
+        require_keys_eq!(
+            buyer_token_account.key(),
+            get_associated_token_address(&self.buyer.key(), &nft),
+            MarketplaceError::InvalidBundleAccount
+        );
+
+        let create_ctx = CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            Create {
+                payer: self.buyer.to_account_info(),
+                associated_token: buyer_token_account.clone(),
+                authority: self.buyer.to_account_info(),
+                mint: mint_info.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+        );
+        create_idempotent(create_ctx)?;
+
+        // Create seeds for PDA signing
+        let marketplace = self.marketplace.key();
+        let seller = self.seller.key();
+        let nonce = self.bundle.nonce.to_le_bytes();
+        let bundle_seeds: &[&[u8]] = &[
+            b"bundle",
+            marketplace.as_ref(),
+            seller.as_ref(),
+            &nonce,
+            &[self.bundle.bump],
+        ];
+        let signer = &[bundle_seeds];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: vault.clone(),
+                mint: mint_info.clone(),
+                to: buyer_token_account.clone(),
+                authority: self.bundle.to_account_info(),
+            },
+            signer,
+        );
+        transfer_checked(transfer_ctx, 1, mint.decimals)?;
+
+        // Return the empty vault's rent to the seller
+        let close_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: vault.clone(),
+                destination: self.seller.to_account_info(),
+                authority: self.bundle.to_account_info(),
+            },
+            signer,
+        );
+        close_account(close_ctx)
+    }
+
+    /// Transfer the SOL payment from buyer to seller and fee recipient
+    fn transfer_sol(&self, split: &PaymentSplit) -> Result<()> {
+        // Skipped when the fee rounds to zero, e.g. on one-lamport sales
+        if split.marketplace_fee > 0 {
+            let fee_transfer_ctx = CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.buyer.to_account_info(),
+                    to: self.fee_recipient.to_account_info(),
+                },
+            );
+            transfer(fee_transfer_ctx, split.marketplace_fee)?;
+        }
+
+        let seller_transfer_ctx = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.buyer.to_account_info(),
+                to: self.seller.to_account_info(),
+            },
+        );
+        transfer(seller_transfer_ctx, split.seller_proceeds)
+    }
+
+    /// Mint reward points for the bundle to buyer and seller, when the marketplace has a rate
+    fn mint_rewards(&self, price: u64, event_authority_bump: u8) -> Result<()> {
+        if self.marketplace.reward_rate_bps == 0 {
+            return Ok(());
+        }
+
+        let (buyer_amount, seller_amount) = self.marketplace.rewards_for(price)?;
+
+        // Create seeds for marketplace PDA signing
+        let marketplace_seeds: &[&[u8]] = &[
+            b"marketplace",
+            self.marketplace.admin.as_ref(),
+            self.marketplace.name.as_bytes(),
+            &[self.marketplace.bump],
+        ];
+        let signer = &[marketplace_seeds];
+
+        for (to, amount) in [
+            (self.buyer_rewards_account.to_account_info(), buyer_amount),
+            (self.seller_rewards_account.to_account_info(), seller_amount),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                MintTo {
+                    mint: self.rewards_mint.to_account_info(),
+                    to,
+                    authority: self.marketplace.to_account_info(),
+                },
+                signer,
+            );
+            mint_to(cpi_ctx, amount)?;
+        }
+
+        emit_cpi(&self.event_authority, event_authority_bump, &RewardsMintedEvent {
+            listing: self.bundle.key(),
+            buyer: self.buyer.key(),
+            seller: self.seller.key(),
+            buyer_amount,
+            seller_amount,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct BundlePurchasedEvent {
+    pub bundle: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub mints: Vec<Pubkey>,
+    pub price: u64,
+    pub seller_proceeds: u64,
+    pub marketplace_fee: u64,
+}
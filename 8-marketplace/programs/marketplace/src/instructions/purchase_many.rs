@@ -0,0 +1,413 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{self, transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::{create_idempotent, get_associated_token_address, AssociatedToken, Create},
+    token::{
+        close_account, mint_to, transfer_checked, CloseAccount, Mint, MintTo, Token, TokenAccount,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    constants::MAX_PURCHASE_MANY_ITEMS,
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    instructions::{check_listed_metadata, NftPurchasedEvent, RewardsMintedEvent},
+    state::{Listing, ListingIndex, Marketplace, PaymentSplit, SellerStats},
+};
+
+/// Number of remaining accounts describing one listing of a batch purchase
+const ACCOUNTS_PER_PURCHASE: usize = 11;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PurchaseMany<'info> {
+    /// The buyer paying for every listing of the batch
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Validates this is the correct marketplace instance
+    /// - Must not be paused
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        constraint = !marketplace.paused @ MarketplaceError::MarketplacePaused,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The account sale fees are paid to
+    /// - Must match the marketplace's configured fee recipient
+    #[account(
+        mut,
+        address = marketplace.fee_recipient @ MarketplaceError::InvalidFeeRecipient,
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+
+    /// Reward points mint of the marketplace
+    #[account(
+        mut,
+        seeds = [b"rewards", marketplace.key().as_ref()],
+        bump = marketplace.rewards_bump,
+    )]
+    pub rewards_mint: Box<Account<'info, Mint>>,
+
+    /// The buyer's reward points account, credited once per purchased listing
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = buyer,
+    )]
+    pub buyer_rewards_account: Box<Account<'info, TokenAccount>>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> PurchaseMany<'info> {
+    /// Buy every listing of the batch, as `purchase_nft` would one by one
+    /// - Each listing is bought whole, with its own fee transfer, rewards and purchase event
+    /// - Any failing item fails the whole instruction, so the batch is atomic
+    ///
+    /// # Arguments
+    /// * `max_total` - The most lamports the buyer agrees to pay for the whole batch
+    /// * `items` - Groups of (mint, listing, vault, buyer token account, seller, seller statistics,
+    ///   metadata, seller rewards account, listing index, last listing index, moved listing)
+    ///   accounts; the index accounts are those `purchase_nft` takes, with the program id for any
+    ///   that is not needed
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn purchase_many(
+        &mut self,
+        max_total: u64,
+        items: &'info [AccountInfo<'info>],
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        let groups = items.chunks_exact(ACCOUNTS_PER_PURCHASE);
+        require!(
+            !items.is_empty()
+                && groups.remainder().is_empty()
+                && groups.len() <= MAX_PURCHASE_MANY_ITEMS as usize,
+            MarketplaceError::InvalidPurchaseBatchSize
+        );
+        // Token payments and proceeds accounts would need more accounts per item
+        require!(
+            self.marketplace.payment_mint.is_none() && !self.marketplace.pull_payments,
+            MarketplaceError::UnsupportedPurchaseItem
+        );
+
+        let mut spent: u64 = 0;
+        for group in groups {
+            let price = self.purchase_item(group, max_total - spent, event_authority_bump)?;
+            spent += price;
+        }
+
+        Ok(())
+    }
+
+    /// Validate one group and buy its listing
+    ///
+    /// # Arguments
+    /// * `group` - The accounts of the listing
+    /// * `budget` - What is left of the buyer's maximum total
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<u64>` - The price paid for the listing
+    fn purchase_item(
+        &mut self,
+        group: &'info [AccountInfo<'info>],
+        budget: u64,
+        event_authority_bump: u8,
+    ) -> Result<u64> {
+        let [
+            mint_info,
+            listing_info,
+            vault,
+            buyer_token_account,
+            seller_info,
+            seller_stats_info,
+            metadata_info,
+            seller_rewards_account,
+            index_accounts @ ..,
+        ] = group
+        else {
+            return err!(MarketplaceError::InvalidPurchaseBatchSize);
+        };
+
+        // Owners and layouts are checked by the typed loads; addresses by re-deriving them
+        let mint = Account::<Mint>::try_from(mint_info)
+            .map_err(|_| MarketplaceError::InvalidBulkAccount)?;
+        let mut listing = Account::<Listing>::try_from(listing_info)
+            .map_err(|_| MarketplaceError::InvalidBulkAccount)?;
+        let mut seller_stats = Account::<SellerStats>::try_from(seller_stats_info)
+            .map_err(|_| MarketplaceError::InvalidBulkAccount)?;
+
+        let buyer = self.buyer.key();
+        let seller = listing.seller;
+        let marketplace = self.marketplace.key();
+        let nft = mint.key();
+        let nonce = listing.nonce.to_le_bytes();
+        let listing_seeds: &[&[u8]] = &[
+            b"listing",
+            marketplace.as_ref(),
+            seller.as_ref(),
+            nft.as_ref(),
+            &nonce,
+            &[listing.bump],
+        ];
+        let expected_listing = Pubkey::create_program_address(listing_seeds, &crate::ID)
+            .map_err(|_| MarketplaceError::InvalidBulkAccount)?;
+        let expected_seller_stats = Pubkey::create_program_address(
+            &[
+                b"seller_stats",
+                marketplace.as_ref(),
+                seller.as_ref(),
+                &[seller_stats.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| MarketplaceError::InvalidBulkAccount)?;
+        require_keys_eq!(listing_info.key(), expected_listing, MarketplaceError::InvalidBulkAccount);
+        require_keys_eq!(seller_info.key(), seller, MarketplaceError::InvalidBulkAccount);
+        require_keys_eq!(
+            seller_stats_info.key(),
+            expected_seller_stats,
+            MarketplaceError::InvalidBulkAccount
+        );
+        require_keys_eq!(
+            vault.key(),
+            get_associated_token_address(&expected_listing, &nft),
+            MarketplaceError::InvalidBulkAccount
+        );
+        require_keys_eq!(
+            buyer_token_account.key(),
+            get_associated_token_address(&buyer, &nft),
+            MarketplaceError::InvalidBulkAccount
+        );
+        require_keys_eq!(
+            seller_rewards_account.key(),
+            get_associated_token_address(&seller, &self.rewards_mint.key()),
+            MarketplaceError::InvalidBulkAccount
+        );
+
+        require_keys_eq!(
+            listing.marketplace,
+            marketplace,
+            MarketplaceError::ListingMarketplaceMismatch
+        );
+
+        // The same checks as `purchase_nft`
+        let now = Clock::get()?.unix_timestamp;
+        require!(listing.is_active, MarketplaceError::ListingNotActive);
+        require!(!listing.is_expired(now), MarketplaceError::ListingExpired);
+        require!(listing.has_started(now), MarketplaceError::SaleNotStarted);
+        require!(listing.can_buy(&buyer), MarketplaceError::NotAllowedBuyer);
+        require_keys_neq!(buyer, seller, MarketplaceError::SelfPurchase);
+        // Escrowless, pNFT and protected listings need more accounts per item
+        require!(
+            !listing.escrowless && !listing.programmable && !listing.is_protected(),
+            MarketplaceError::UnsupportedPurchaseItem
+        );
+        check_listed_metadata(&listing, &nft, metadata_info)?;
+
+        // Dutch listings are charged their decayed price at execution time
+        let amount = listing.quantity;
+        let price = listing.total_price(now, amount)?;
+        require!(price <= budget, MarketplaceError::MaxTotalExceeded);
+
+        // High-volume sellers pay the fee of the tier their volume before this sale reaches, less
+        // the buyer's holder discount on their reward points from before the batch
+        let fee_bps = self.marketplace.fee_bps_for_volume(seller_stats.volume);
+        let (fee_bps, fee_discount_bps) = self
+            .marketplace
+            .holder_fee_bps(fee_bps, self.buyer_rewards_account.amount);
+        let split = self.marketplace.split_payment(price, fee_bps, false)?;
+        require!(
+            split.total() == Some(price),
+            MarketplaceError::PaymentSplitMismatch
+        );
+
+        let signer = &[listing_seeds];
+        self.create_token_account(buyer_token_account, &self.buyer.to_account_info(), mint_info)?;
+
+        // Transfer the tokens to the buyer
+        let transfer_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: vault.clone(),
+                mint: mint_info.clone(),
+                to: buyer_token_account.clone(),
+                authority: listing_info.clone(),
+            },
+            signer,
+        );
+        transfer_checked(transfer_ctx, amount, mint.decimals)?;
+
+        // Return the empty vault's rent to the seller
+        let close_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: vault.clone(),
+                destination: seller_info.clone(),
+                authority: listing_info.clone(),
+            },
+            signer,
+        );
+        close_account(close_ctx)?;
+
+        self.transfer_sol(seller_info, &split)?;
+        self.mint_rewards(
+            expected_listing,
+            seller_info,
+            seller_rewards_account,
+            price,
+            event_authority_bump,
+        )?;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &NftPurchasedEvent {
+            listing: expected_listing,
+            buyer,
+            seller,
+            amount,
+            price,
+            private: listing.allowed_buyer.is_some(),
+            referrer: None,
+            seller_proceeds: split.seller_proceeds,
+            marketplace_fee: split.marketplace_fee,
+            royalty_paid: split.royalty_paid,
+            referral_paid: split.referral_paid,
+            fee_discount_bps,
+            // The system program id stands for native SOL
+            payment_mint: system_program::ID,
+        })?;
+
+        self.marketplace.record_sale(price, split.fee());
+        self.marketplace.listing_closed();
+        seller_stats.record_sale(price);
+        seller_stats.listing_closed();
+        // Written back now, so a later item of the same seller loads the updated statistics
+        seller_stats.exit(&crate::ID)?;
+        ListingIndex::unindex_remaining(&mut self.marketplace, &mut listing, index_accounts)?;
+        listing.close(seller_info.clone())?;
+
+        Ok(price)
+    }
+
+    /// Create an associated token account paid by the buyer, unless it already exists
+    fn create_token_account(
+        &self,
+        associated_token: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+        mint: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let cpi_ctx = CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            Create {
+                payer: self.buyer.to_account_info(),
+                associated_token: associated_token.clone(),
+                authority: authority.clone(),
+                mint: mint.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+        );
+        create_idempotent(cpi_ctx)
+    }
+
+    /// Transfer the SOL payment of one listing from buyer to seller and fee recipient
+    fn transfer_sol(&self, seller: &AccountInfo<'info>, split: &PaymentSplit) -> Result<()> {
+        // Skipped when the fee rounds to zero, e.g. on one-lamport sales
+        if split.marketplace_fee > 0 {
+            let fee_transfer_ctx = CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.buyer.to_account_info(),
+                    to: self.fee_recipient.to_account_info(),
+                },
+            );
+            transfer(fee_transfer_ctx, split.marketplace_fee)?;
+        }
+
+        let seller_transfer_ctx = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.buyer.to_account_info(),
+                to: seller.clone(),
+            },
+        );
+        transfer(seller_transfer_ctx, split.seller_proceeds)
+    }
+
+    /// Mint reward points for one listing to the buyer and its seller, when the marketplace has a rate
+    fn mint_rewards(
+        &self,
+        listing: Pubkey,
+        seller: &AccountInfo<'info>,
+        seller_rewards_account: &AccountInfo<'info>,
+        price: u64,
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        if self.marketplace.reward_rate_bps == 0 {
+            return Ok(());
+        }
+
+        let (buyer_amount, seller_amount) = self.marketplace.rewards_for(price)?;
+        self.create_token_account(
+            seller_rewards_account,
+            seller,
+            &self.rewards_mint.to_account_info(),
+        )?;
+
+        // Create seeds for marketplace PDA signing
+        let marketplace_seeds: &[&[u8]] = &[
+            b"marketplace",
+            self.marketplace.admin.as_ref(),
+            self.marketplace.name.as_bytes(),
+            &[self.marketplace.bump],
+        ];
+        let signer = &[marketplace_seeds];
+
+        for (to, amount) in [
+            (self.buyer_rewards_account.to_account_info(), buyer_amount),
+            (seller_rewards_account.clone(), seller_amount),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                MintTo {
+                    mint: self.rewards_mint.to_account_info(),
+                    to,
+                    authority: self.marketplace.to_account_info(),
+                },
+                signer,
+            );
+            mint_to(cpi_ctx, amount)?;
+        }
+
+        emit_cpi(&self.event_authority, event_authority_bump, &RewardsMintedEvent {
+            listing,
+            buyer: self.buyer.key(),
+            seller: seller.key(),
+            buyer_amount,
+            seller_amount,
+        })?;
+
+        Ok(())
+    }
+}
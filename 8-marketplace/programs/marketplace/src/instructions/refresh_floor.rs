@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{CollectionStats, Listing},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RefreshFloor<'info> {
+    /// The collection statistics whose floor is recomputed
+    /// - Must match the PDA derived from its marketplace and collection mint
+    #[account(
+        mut,
+        seeds = [
+            b"collection_stats",
+            collection_stats.marketplace.as_ref(),
+            collection_stats.collection.as_ref(),
+        ],
+        bump = collection_stats.bump,
+    )]
+    pub collection_stats: Account<'info, CollectionStats>,
+}
+
+impl<'info> RefreshFloor<'info> {
+    /// Recompute the floor from candidate listings
+    /// - Permissionless: the floor only drops to a cheaper open listing of the collection, unless
+    ///   the stored floor listing is passed and proven closed, repriced or no longer buyable
+    /// - Accounts that are not open listings of the collection are skipped, so a candidate sold
+    ///   in the meantime does not fail the refresh
+    ///
+    /// # Arguments
+    /// * `candidates` - Listing accounts to consider
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn refresh_floor(
+        &mut self,
+        candidates: &'info [AccountInfo<'info>],
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        require!(!candidates.is_empty(), MarketplaceError::InvalidBatchSize);
+        let now = Clock::get()?.unix_timestamp;
+
+        let mut cheapest: Option<(Pubkey, u64)> = None;
+        let mut floor_stale = false;
+        for info in candidates {
+            let price = Account::<Listing>::try_from(info)
+                .ok()
+                .filter(|listing| {
+                    listing.marketplace == self.collection_stats.marketplace
+                        && listing.collection == Some(self.collection_stats.collection)
+                })
+                .and_then(|listing| listing.floor_candidate_price(now));
+
+            if self.collection_stats.floor_listing == Some(info.key()) {
+                floor_stale = price != self.collection_stats.floor_price;
+            }
+            if let Some(price) = price {
+                if !matches!(cheapest, Some((_, best)) if best <= price) {
+                    cheapest = Some((info.key(), price));
+                }
+            }
+        }
+
+        if floor_stale {
+            self.collection_stats.clear_floor();
+        }
+        if let Some((listing, price)) = cheapest {
+            self.collection_stats.offer_floor(listing, price);
+        }
+
+        emit_cpi(&self.event_authority, event_authority_bump, &CollectionFloorRefreshedEvent {
+            collection: self.collection_stats.collection,
+            floor_price: self.collection_stats.floor_price,
+            floor_listing: self.collection_stats.floor_listing,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct CollectionFloorRefreshedEvent {
+    pub collection: Pubkey,
+    pub floor_price: Option<u64>,
+    pub floor_listing: Option<Pubkey>,
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::MarketplaceError, event_cpi::emit_cpi, state::Offer};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RejectCounter<'info> {
+    /// The buyer who made the offer
+    /// - Validated against the offer's buyer field
+    pub buyer: Signer<'info>,
+
+    /// The countered offer
+    /// - Derived from the stored listing, like cancellations
+    /// - Returns to its original terms
+    #[account(
+        mut,
+        seeds = [b"offer", offer.listing.as_ref(), buyer.key().as_ref()],
+        bump = offer.bump,
+        has_one = buyer,
+    )]
+    pub offer: Account<'info, Offer>,
+}
+
+impl<'info> RejectCounter<'info> {
+    /// Reject the seller's counter-offer, restoring the original offer terms
+    ///
+    /// # Arguments
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn reject_counter(&mut self, event_authority_bump: u8) -> Result<()> {
+        let counter_amount = self
+            .offer
+            .open_counter(Clock::get()?.unix_timestamp)
+            .ok_or(MarketplaceError::NoCounterOffer)?;
+
+        self.offer.counter_amount = None;
+        self.offer.counter_expiry = 0;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &CounterOfferRejectedEvent {
+            offer: self.offer.key(),
+            listing: self.offer.listing,
+            buyer: self.buyer.key(),
+            offer_amount: self.offer.amount,
+            counter_amount,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct CounterOfferRejectedEvent {
+    pub offer: Pubkey,
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub offer_amount: u64,
+    pub counter_amount: u64,
+}
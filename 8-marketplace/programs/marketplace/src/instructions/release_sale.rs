@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::MarketplaceError, event_cpi::emit_cpi, state::SaleEscrow};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReleaseSale<'info> {
+    /// The account releasing the proceeds
+    /// - The buyer can release at any time unless the sale is disputed
+    /// - Anyone can release once the protection window has ended
+    pub authority: Signer<'info>,
+
+    /// The seller who listed the NFT
+    /// - Validated against the escrow's seller field
+    /// - Receives the escrowed proceeds
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+
+    /// The buyer who purchased the NFT
+    /// - Validated against the escrow's buyer field
+    /// - Receives the escrow account rent
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+
+    /// The sale escrow being released
+    /// - Derived from the stored listing, which is closed once the NFT sold
+    /// - Closed to the buyer after the proceeds are paid out
+    #[account(
+        mut,
+        seeds = [b"sale_escrow", sale_escrow.listing.as_ref(), buyer.key().as_ref()],
+        bump = sale_escrow.bump,
+        has_one = seller,
+        has_one = buyer,
+        close = buyer
+    )]
+    pub sale_escrow: Account<'info, SaleEscrow>,
+}
+
+impl<'info> ReleaseSale<'info> {
+    /// Pay the escrowed proceeds to the seller
+    /// - The escrow account is program owned, so lamports are moved directly
+    ///
+    /// # Arguments
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn release_sale(&mut self, event_authority_bump: u8) -> Result<()> {
+        require!(!self.sale_escrow.disputed, MarketplaceError::SaleDisputed);
+        let window_ended = self.sale_escrow.can_release(Clock::get()?.unix_timestamp);
+        require!(
+            window_ended || self.authority.key() == self.buyer.key(),
+            MarketplaceError::ProtectionWindowOpen
+        );
+
+        let amount = self.sale_escrow.amount;
+        self.sale_escrow.sub_lamports(amount)?;
+        self.seller.add_lamports(amount)?;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &SaleReleasedEvent {
+            sale_escrow: self.sale_escrow.key(),
+            listing: self.sale_escrow.listing,
+            seller: self.seller.key(),
+            buyer: self.buyer.key(),
+            released_by: self.authority.key(),
+            amount,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct SaleReleasedEvent {
+    pub sale_escrow: Pubkey,
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub released_by: Pubkey,
+    pub amount: u64,
+}
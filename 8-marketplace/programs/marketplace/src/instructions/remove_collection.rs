@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::MarketplaceError,
+    state::{CollectionConfig, Marketplace},
+};
+
+#[derive(Accounts)]
+pub struct RemoveCollection<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    /// - Receives the collection config rent
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The collection config account being removed
+    /// - Closed and rent refunded to the admin
+    /// - Existing listings from the collection are not affected
+    #[account(
+        mut,
+        seeds = [
+            b"collection",
+            marketplace.key().as_ref(),
+            collection_config.collection_mint.as_ref(),
+        ],
+        bump = collection_config.bump,
+        close = admin
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+
+    /// The marketplace state account
+    /// - Validates the admin
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Marketplace, SaleEscrow},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ResolveSaleDispute<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace the disputed sale was made on
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// The seller who listed the NFT
+    /// - Validated against the escrow's seller field
+    /// - Receives the proceeds when the dispute is resolved in their favour
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+
+    /// The buyer who disputed the sale
+    /// - Validated against the escrow's buyer field
+    /// - Receives the escrow account rent, and the proceeds when refunded
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+
+    /// The disputed sale escrow
+    /// - Closed to the buyer once resolved
+    #[account(
+        mut,
+        seeds = [b"sale_escrow", sale_escrow.listing.as_ref(), buyer.key().as_ref()],
+        bump = sale_escrow.bump,
+        has_one = marketplace,
+        has_one = seller,
+        has_one = buyer,
+        close = buyer
+    )]
+    pub sale_escrow: Account<'info, SaleEscrow>,
+}
+
+impl<'info> ResolveSaleDispute<'info> {
+    /// Pay the escrowed proceeds of a disputed sale to either party
+    /// - The NFT stays with the buyer; only the proceeds are decided here
+    /// - Refunding the buyer leaves the proceeds in the escrow, which closing returns to them
+    ///
+    /// # Arguments
+    /// * `refund_buyer` - Whether the proceeds go back to the buyer instead of to the seller
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn resolve_sale_dispute(
+        &mut self,
+        refund_buyer: bool,
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        require!(self.sale_escrow.disputed, MarketplaceError::SaleNotDisputed);
+
+        let amount = self.sale_escrow.amount;
+        let resolved_to = if refund_buyer {
+            self.buyer.key()
+        } else {
+            self.sale_escrow.sub_lamports(amount)?;
+            self.seller.add_lamports(amount)?;
+            self.seller.key()
+        };
+
+        emit_cpi(&self.event_authority, event_authority_bump, &SaleDisputeResolvedEvent {
+            sale_escrow: self.sale_escrow.key(),
+            listing: self.sale_escrow.listing,
+            resolved_to,
+            amount,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct SaleDisputeResolvedEvent {
+    pub sale_escrow: Pubkey,
+    pub listing: Pubkey,
+    pub resolved_to: Pubkey,
+    pub amount: u64,
+}
@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::MarketplaceError, state::Marketplace};
+
+#[derive(Accounts)]
+pub struct SetBlockBlacklistedBuyers<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new buyer policy
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetBlockBlacklistedBuyers<'info> {
+    /// Choose whether blacklisted wallets are also blocked from purchasing listings
+    /// - Blacklisted wallets can never list, auction or accept offers, whatever this flag says
+    ///
+    /// # Arguments
+    /// * `block_blacklisted_buyers` - Whether purchases by blacklisted buyers are rejected
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_block_blacklisted_buyers(&mut self, block_blacklisted_buyers: bool) -> Result<()> {
+        self.marketplace.block_blacklisted_buyers = block_blacklisted_buyers;
+        Ok(())
+    }
+}
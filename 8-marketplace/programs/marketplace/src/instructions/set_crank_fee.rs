@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::MAX_CRANK_FEE_LAMPORTS, error::MarketplaceError, state::Marketplace};
+
+#[derive(Accounts)]
+pub struct SetCrankFee<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new crank fee
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetCrankFee<'info> {
+    /// Update the bounty paid for refunding someone else's expired offer
+    /// - The bounty comes out of the refunded escrow, so it is capped to stay tiny
+    ///
+    /// # Arguments
+    /// * `crank_fee_lamports` - Bounty in lamports (0-MAX_CRANK_FEE_LAMPORTS); 0 pays no bounty
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_crank_fee(&mut self, crank_fee_lamports: u64) -> Result<()> {
+        require!(
+            crank_fee_lamports <= MAX_CRANK_FEE_LAMPORTS,
+            MarketplaceError::InvalidCrankFee
+        );
+
+        self.marketplace.crank_fee_lamports = crank_fee_lamports;
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::MAX_CRANK_REWARD_LAMPORTS, error::MarketplaceError, state::Marketplace};
+
+#[derive(Accounts)]
+pub struct SetCrankReward<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new crank reward
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetCrankReward<'info> {
+    /// Update the reward paid from the treasury for cleaning up an expired listing
+    /// - Capped so a stream of short listings cannot drain the treasury quickly
+    ///
+    /// # Arguments
+    /// * `crank_reward_lamports` - Reward in lamports (0-MAX_CRANK_REWARD_LAMPORTS); 0 pays no reward
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_crank_reward(&mut self, crank_reward_lamports: u64) -> Result<()> {
+        require!(
+            crank_reward_lamports <= MAX_CRANK_REWARD_LAMPORTS,
+            MarketplaceError::InvalidCrankReward
+        );
+
+        self.marketplace.crank_reward_lamports = crank_reward_lamports;
+        Ok(())
+    }
+}
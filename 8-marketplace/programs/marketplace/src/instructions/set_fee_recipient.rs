@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::MarketplaceError, event_cpi::emit_cpi, state::Marketplace};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetFeeRecipient<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new fee recipient
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetFeeRecipient<'info> {
+    /// Route future sale fees to another account
+    /// - Listings store no fee recipient, so existing listings pay the new one when they sell
+    /// - The recipient must be rent exempt to receive SOL fees smaller than the rent minimum
+    ///
+    /// # Arguments
+    /// * `fee_recipient` - The account sale fees are paid to
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_fee_recipient(
+        &mut self,
+        fee_recipient: Pubkey,
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        let old_fee_recipient = self.marketplace.fee_recipient;
+        self.marketplace.fee_recipient = fee_recipient;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &FeeRecipientUpdatedEvent {
+            marketplace: self.marketplace.key(),
+            old_fee_recipient,
+            new_fee_recipient: fee_recipient,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct FeeRecipientUpdatedEvent {
+    pub marketplace: Pubkey,
+    pub old_fee_recipient: Pubkey,
+    pub new_fee_recipient: Pubkey,
+}
@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::MarketplaceError,
+    state::{FeeSplit, Marketplace},
+};
+
+#[derive(Accounts)]
+pub struct SetFeeSplits<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new fee split list
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetFeeSplits<'info> {
+    /// Replace the recipients sharing the marketplace fee
+    /// - Token purchases pay each recipient's payment mint ATA, which must already exist
+    ///
+    /// # Arguments
+    /// * `fee_splits` - Distinct recipients with shares summing to BPS_DENOMINATOR; empty pays
+    ///   the whole fee to the fee recipient
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_fee_splits(&mut self, fee_splits: Vec<FeeSplit>) -> Result<()> {
+        require!(
+            Marketplace::valid_fee_splits(&fee_splits),
+            MarketplaceError::InvalidFeeSplits
+        );
+
+        self.marketplace.fee_splits = fee_splits;
+        Ok(())
+    }
+}
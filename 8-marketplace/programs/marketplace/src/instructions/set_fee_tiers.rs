@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::FEE_TIER_COUNT,
+    error::MarketplaceError,
+    state::{FeeTier, Marketplace},
+};
+
+#[derive(Accounts)]
+pub struct SetFeeTiers<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new fee tier table
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetFeeTiers<'info> {
+    /// Replace the seller volume tiers that discount the marketplace fee
+    ///
+    /// # Arguments
+    /// * `fee_tiers` - Tiers in ascending thresholds with non-increasing fees; unset tiers last
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_fee_tiers(&mut self, fee_tiers: [FeeTier; FEE_TIER_COUNT]) -> Result<()> {
+        require!(
+            Marketplace::valid_fee_tiers(&fee_tiers),
+            MarketplaceError::InvalidFeeTiers
+        );
+
+        self.marketplace.fee_tiers = fee_tiers;
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::MAX_FEE_BPS, error::MarketplaceError, state::Marketplace};
+
+#[derive(Accounts)]
+pub struct SetHolderDiscount<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new holder discount
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetHolderDiscount<'info> {
+    /// Update the fee discount for buyers holding reward points
+    /// - Applies to purchases from now on
+    ///
+    /// # Arguments
+    /// * `threshold` - Reward points a buyer must hold for the discount
+    /// * `discount_bps` - Basis points taken off the fee rate, at most MAX_FEE_BPS; 0 turns it off
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_holder_discount(&mut self, threshold: u64, discount_bps: u16) -> Result<()> {
+        // A zero threshold would discount every buyer, which is a fee change rather than a perk
+        require!(
+            discount_bps <= MAX_FEE_BPS && (discount_bps == 0 || threshold > 0),
+            MarketplaceError::InvalidHolderDiscount
+        );
+
+        self.marketplace.discount_threshold = threshold;
+        self.marketplace.discount_bps = discount_bps;
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::BPS_DENOMINATOR, error::MarketplaceError, state::Marketplace};
+
+#[derive(Accounts)]
+pub struct SetInstallmentTerms<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new installment terms
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetInstallmentTerms<'info> {
+    /// Update how defaulted installment plans are handled
+    /// - Applies to plans defaulted from now on, including running ones
+    ///
+    /// # Arguments
+    /// * `grace_secs` - Seconds past a missed due date before the seller can default the plan
+    /// * `forfeit_bps` - Share of the paid installments kept for the seller (0-10000)
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_installment_terms(&mut self, grace_secs: u32, forfeit_bps: u16) -> Result<()> {
+        require!(
+            forfeit_bps <= BPS_DENOMINATOR,
+            MarketplaceError::InvalidInstallmentTerms
+        );
+
+        self.marketplace.installment_grace_secs = grace_secs;
+        self.marketplace.installment_forfeit_bps = forfeit_bps;
+        Ok(())
+    }
+}
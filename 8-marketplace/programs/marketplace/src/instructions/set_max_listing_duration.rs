@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::MarketplaceError, state::Marketplace};
+
+#[derive(Accounts)]
+pub struct SetMaxListingDuration<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new maximum listing duration
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetMaxListingDuration<'info> {
+    /// Update the longest time a listing can be extended to run for
+    ///
+    /// # Arguments
+    /// * `max_listing_duration` - Seconds from the extension; 0 sets no limit
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_max_listing_duration(&mut self, max_listing_duration: u32) -> Result<()> {
+        self.marketplace.max_listing_duration = max_listing_duration;
+        Ok(())
+    }
+}
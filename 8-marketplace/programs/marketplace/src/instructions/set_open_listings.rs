@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::MarketplaceError, state::Marketplace};
+
+#[derive(Accounts)]
+pub struct SetOpenListings<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new curation setting
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetOpenListings<'info> {
+    /// Turn the collection whitelist check off or back on
+    ///
+    /// # Arguments
+    /// * `open_listings` - Whether NFTs from any collection can be listed
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_open_listings(&mut self, open_listings: bool) -> Result<()> {
+        self.marketplace.open_listings = open_listings;
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::MarketplaceError, event_cpi::emit_cpi, state::Marketplace};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new pause state
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetPaused<'info> {
+    /// Halt or resume listing, purchases and offer acceptance
+    /// - Delisting is never paused
+    ///
+    /// # Arguments
+    /// * `paused` - Whether the marketplace is halted
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_paused(&mut self, paused: bool, event_authority_bump: u8) -> Result<()> {
+        self.marketplace.paused = paused;
+
+        let marketplace = self.marketplace.key();
+        let admin = self.admin.key();
+        if paused {
+            emit_cpi(
+                &self.event_authority,
+                event_authority_bump,
+                &MarketplacePausedEvent { marketplace, admin },
+            )?;
+        } else {
+            emit_cpi(
+                &self.event_authority,
+                event_authority_bump,
+                &MarketplaceUnpausedEvent { marketplace, admin },
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct MarketplacePausedEvent {
+    pub marketplace: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct MarketplaceUnpausedEvent {
+    pub marketplace: Pubkey,
+    pub admin: Pubkey,
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::MarketplaceError, state::Marketplace};
+
+#[derive(Accounts)]
+pub struct SetPriceBounds<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new price bounds
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetPriceBounds<'info> {
+    /// Update the lowest and highest prices listings, auctions and offers can be made at
+    /// - Only checked when a price is set; open listings and offers keep their prices
+    ///
+    /// # Arguments
+    /// * `min_price` - The lowest price in the marketplace currency; 0 sets no minimum
+    /// * `max_price` - The highest price in the marketplace currency; 0 sets no maximum
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_price_bounds(&mut self, min_price: u64, max_price: u64) -> Result<()> {
+        require!(
+            max_price == 0 || min_price <= max_price,
+            MarketplaceError::InvalidPriceBounds
+        );
+
+        self.marketplace.min_price = min_price;
+        self.marketplace.max_price = max_price;
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::MarketplaceError, state::Marketplace};
+
+#[derive(Accounts)]
+pub struct SetPullPayments<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new payment mode
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetPullPayments<'info> {
+    /// Switch SOL purchases between paying sellers directly and crediting their Proceeds PDA
+    /// - Proceeds already credited stay claimable after pull payments are turned off
+    ///
+    /// # Arguments
+    /// * `pull_payments` - Whether sellers claim their proceeds with `claim_proceeds`
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_pull_payments(&mut self, pull_payments: bool) -> Result<()> {
+        self.marketplace.pull_payments = pull_payments;
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::MAX_FEE_BPS, error::MarketplaceError, state::Marketplace};
+
+#[derive(Accounts)]
+pub struct SetReferralBps<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new referral share
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetReferralBps<'info> {
+    /// Update the share of the price paid to the referrer of a purchase
+    /// - The referral comes out of the marketplace fee, so it is capped like the fee
+    ///
+    /// # Arguments
+    /// * `referral_bps` - Referral share in basis points of the price (0-MAX_FEE_BPS); 0 turns referrals off
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_referral_bps(&mut self, referral_bps: u16) -> Result<()> {
+        require!(referral_bps <= MAX_FEE_BPS, MarketplaceError::InvalidReferralBps);
+
+        self.marketplace.referral_bps = referral_bps;
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::BPS_DENOMINATOR, error::MarketplaceError, state::Marketplace};
+
+#[derive(Accounts)]
+pub struct SetRewardRate<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new reward rate
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> SetRewardRate<'info> {
+    /// Update the reward points minted on each sale
+    ///
+    /// # Arguments
+    /// * `reward_rate_bps` - Buyer reward in basis points of the price (0-BPS_DENOMINATOR); 0 turns rewards off
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn set_reward_rate(&mut self, reward_rate_bps: u16) -> Result<()> {
+        require!(reward_rate_bps <= BPS_DENOMINATOR, MarketplaceError::InvalidRewardRate);
+
+        self.marketplace.reward_rate_bps = reward_rate_bps;
+        Ok(())
+    }
+}
@@ -0,0 +1,316 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{close_account, transfer_checked, CloseAccount, Mint, Token, TokenAccount, TransferChecked},
+};
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Auction, Marketplace, Provenance, SaleRecord},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    /// Anyone settling the auction after it ended
+    /// - Pays for the NFT recipient's token account if needed
+    #[account(mut)]
+    pub settler: Signer<'info>,
+
+    /// The seller who created the auction
+    /// - Validated against the auction's seller field
+    /// - Receives the winning bid minus fees and all account rent
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+
+    /// The highest bidder
+    /// - Required when the auction has a bid, and must match the highest bidder
+    /// - Refunded their bid when it is below the reserve price
+    #[account(mut)]
+    pub winner: Option<SystemAccount<'info>>,
+
+    /// The NFT mint account being auctioned
+    pub nft: Box<Account<'info, Mint>>,
+
+    /// The auction being settled
+    /// - Must match the PDA derived from marketplace, seller, and NFT
+    /// - Closed and rent refunded to seller
+    #[account(
+        mut,
+        seeds = [
+            b"auction",
+            marketplace.key().as_ref(),
+            seller.key().as_ref(),
+            nft.key().as_ref(),
+        ],
+        bump = auction.bump,
+        has_one = seller,
+        close = seller
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+
+    /// Token account holding the NFT during the auction
+    /// - Emptied and closed to the seller
+    #[account(
+        mut,
+        associated_token::mint = nft,
+        associated_token::authority = auction,
+    )]
+    pub auction_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Escrow holding the highest bid
+    /// - Drained to the fee recipient and seller
+    #[account(
+        mut,
+        seeds = [b"bid_escrow", auction.key().as_ref()],
+        bump = auction.escrow_bump,
+    )]
+    pub bid_escrow: SystemAccount<'info>,
+
+    /// The winner's token account to receive the NFT
+    /// - Required when the highest bid meets the reserve price
+    #[account(
+        init_if_needed,
+        payer = settler,
+        associated_token::mint = nft,
+        associated_token::authority = winner,
+    )]
+    pub winner_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// The seller's token account to receive the NFT back
+    /// - Required when the auction has no bids or the highest bid is below the reserve price
+    #[account(
+        init_if_needed,
+        payer = settler,
+        associated_token::mint = nft,
+        associated_token::authority = seller,
+    )]
+    pub seller_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// The marketplace state account
+    /// - Contains fee percentage for calculations
+    /// - Updates the marketplace statistics
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// The account receiving marketplace fees
+    /// - Must be the fee recipient configured on the marketplace
+    #[account(
+        mut,
+        address = marketplace.fee_recipient @ MarketplaceError::InvalidFeeRecipient,
+    )]
+    pub fee_recipient: SystemAccount<'info>,
+
+    /// The NFT's sale history
+    /// - Created on its first sale, paid by the settler
+    #[account(
+        init_if_needed,
+        payer = settler,
+        space = 8 + Provenance::INIT_SPACE,
+        seeds = [b"provenance", marketplace.key().as_ref(), nft.key().as_ref()],
+        bump,
+    )]
+    pub provenance: Box<Account<'info, Provenance>>,
+
+    /// Required programs
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> SettleAuction<'info> {
+    /// Send the NFT to the winner and pay the seller, or return the NFT if nobody bid
+    /// - A highest bid below the reserve price is refunded and the NFT returned as well
+    ///
+    /// # Arguments
+    /// * `bumps` - PDA bump values for the provenance account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn settle_auction(&mut self, bumps: SettleAuctionBumps) -> Result<()> {
+        require!(
+            self.auction.has_ended(Clock::get()?.unix_timestamp),
+            MarketplaceError::AuctionNotEnded
+        );
+
+        let highest_bidder = self.auction.highest_bidder;
+        if let Some(highest_bidder) = highest_bidder {
+            let winner_account = self.winner.as_ref().ok_or(MarketplaceError::InvalidBidder)?;
+            require_keys_eq!(winner_account.key(), highest_bidder, MarketplaceError::InvalidBidder);
+        }
+
+        let reserve_met = self.auction.reserve_met();
+        let (winner, amount, fee) = match highest_bidder {
+            Some(winner) if reserve_met => {
+                let amount = self.auction.highest_bid;
+                let fee = self.marketplace.fee_for(amount)?;
+                self.marketplace.record_sale(amount, fee);
+                (Some(winner), amount, fee)
+            }
+            Some(_) => {
+                self.refund_highest_bid()?;
+                (None, 0, 0)
+            }
+            None => (None, 0, 0),
+        };
+
+        self.payout_escrow(fee)?;
+        self.transfer_nft(winner.is_some())?;
+
+        if let Some(winner) = winner {
+            if self.provenance.is_uninitialized() {
+                self.provenance.set_inner(Provenance::new(
+                    self.marketplace.key(),
+                    self.nft.key(),
+                    bumps.provenance,
+                ));
+            }
+            self.provenance.record_sale(SaleRecord {
+                price: amount,
+                buyer: winner,
+                seller: self.seller.key(),
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+
+        emit_cpi(&self.event_authority, bumps.event_authority, &AuctionSettledEvent {
+            auction: self.auction.key(),
+            seller: self.seller.key(),
+            winner,
+            amount,
+            fee,
+            reserve_met: highest_bidder.is_some() && reserve_met,
+        })?;
+
+        Ok(())
+    }
+
+    /// Return the highest bid from the escrow to the bidder when it is below the reserve
+    fn refund_highest_bid(&self) -> Result<()> {
+        let winner = self.winner.as_ref().ok_or(MarketplaceError::InvalidBidder)?;
+
+        // Create seeds for PDA signing
+        let auction = self.auction.key();
+        let escrow_seeds: &[&[u8]] = &[
+            b"bid_escrow",
+            auction.as_ref(),
+            &[self.auction.escrow_bump],
+        ];
+        let signer = &[escrow_seeds];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.bid_escrow.to_account_info(),
+                to: winner.to_account_info(),
+            },
+            signer,
+        );
+        transfer(cpi_ctx, self.auction.highest_bid)
+    }
+
+    /// Pay the fee to the fee recipient and everything else in the escrow to the seller
+    /// - With no bids, the escrow only holds the rent the seller funded
+    fn payout_escrow(&self, fee: u64) -> Result<()> {
+        // Create seeds for PDA signing
+        let auction = self.auction.key();
+        let escrow_seeds: &[&[u8]] = &[
+            b"bid_escrow",
+            auction.as_ref(),
+            &[self.auction.escrow_bump],
+        ];
+        let signer = &[escrow_seeds];
+
+        if fee > 0 {
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.bid_escrow.to_account_info(),
+                    to: self.fee_recipient.to_account_info(),
+                },
+                signer,
+            );
+            transfer(cpi_ctx, fee)?;
+        }
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.bid_escrow.to_account_info(),
+                to: self.seller.to_account_info(),
+            },
+            signer,
+        );
+        transfer(cpi_ctx, self.bid_escrow.lamports())
+    }
+
+    /// Transfer the NFT from the auction vault to the winner or back to the seller, then close the vault
+    fn transfer_nft(&self, has_winner: bool) -> Result<()> {
+        let destination = if has_winner {
+            self.winner_token_account.as_ref()
+        } else {
+            self.seller_token_account.as_ref()
+        }
+        .ok_or(MarketplaceError::MissingSettlementAccount)?;
+
+        // Create seeds for PDA signing
+        let marketplace = self.marketplace.key();
+        let seller = self.seller.key();
+        let nft = self.nft.key();
+        let auction_seeds: &[&[u8]] = &[
+            b"auction",
+            marketplace.as_ref(),
+            seller.as_ref(),
+            nft.as_ref(),
+            &[self.auction.bump],
+        ];
+        let signer = &[auction_seeds];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.auction_token_account.to_account_info(),
+                mint: self.nft.to_account_info(),
+                to: destination.to_account_info(),
+                authority: self.auction.to_account_info(),
+            },
+            signer,
+        );
+        transfer_checked(cpi_ctx, 1, self.nft.decimals)?;
+
+        // Return the empty vault's rent to the seller
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.auction_token_account.to_account_info(),
+                destination: self.seller.to_account_info(),
+                authority: self.auction.to_account_info(),
+            },
+            signer,
+        );
+        close_account(cpi_ctx)
+    }
+}
+
+#[event]
+pub struct AuctionSettledEvent {
+    pub auction: Pubkey,
+    pub seller: Pubkey,
+    pub winner: Option<Pubkey>,
+    pub amount: u64,
+    pub fee: u64,
+    /// False when nobody bid or the highest bid was refunded below the reserve price
+    pub reserve_met: bool,
+}
@@ -0,0 +1,169 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    constants::MAX_INSTALLMENTS,
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{InstallmentPlan, Listing, Marketplace},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct StartInstallmentPurchase<'info> {
+    /// The buyer starting the plan and paying its first installment
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The seller who listed the NFT
+    ///
+    /// CHECK: Part of the listing PDA seeds
+    pub seller: AccountInfo<'info>,
+
+    /// The NFT mint account of the listing
+    pub nft: InterfaceAccount<'info, Mint>,
+
+    /// The marketplace state account
+    /// - Must not be paused
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        constraint = !marketplace.paused @ MarketplaceError::MarketplacePaused,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// The listing being bought
+    /// - Deactivated while the plan runs, so nobody else can buy or delist it
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            seller.key().as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    /// The installment plan
+    /// - Uses PDA with "installment" seed and the listing key
+    /// - Holds the paid installments until the last one or a default
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + InstallmentPlan::INIT_SPACE,
+        seeds = [b"installment", listing.key().as_ref()],
+        bump,
+    )]
+    pub installment_plan: Account<'info, InstallmentPlan>,
+
+    /// Required program for account creation and the payment
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> StartInstallmentPurchase<'info> {
+    /// Lock the listing to the buyer at its current price and take the first installment
+    ///
+    /// # Arguments
+    /// * `num_payments` - Number of installments the price is split into (2-MAX_INSTALLMENTS)
+    /// * `interval_secs` - Seconds between two installment due dates
+    /// * `bumps` - PDA bump values for the installment plan
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn start_installment_purchase(
+        &mut self,
+        num_payments: u8,
+        interval_secs: u32,
+        bumps: StartInstallmentPurchaseBumps,
+    ) -> Result<()> {
+        require!(
+            (2..=MAX_INSTALLMENTS).contains(&num_payments) && interval_secs > 0,
+            MarketplaceError::InvalidInstallmentSchedule
+        );
+        // Installments are held as lamports and pay out like a direct SOL purchase
+        require!(
+            self.marketplace.payment_mint.is_none() && !self.marketplace.pull_payments,
+            MarketplaceError::UnsupportedInstallment
+        );
+
+        // The same checks as `purchase_nft`
+        let now = Clock::get()?.unix_timestamp;
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+        require!(!self.listing.is_expired(now), MarketplaceError::ListingExpired);
+        require!(self.listing.has_started(now), MarketplaceError::SaleNotStarted);
+        require!(
+            self.listing.can_buy(&self.buyer.key()),
+            MarketplaceError::NotAllowedBuyer
+        );
+        require_keys_neq!(self.buyer.key(), self.listing.seller, MarketplaceError::SelfPurchase);
+        // The NFT must stay in the vault until the last installment
+        require!(
+            !self.listing.escrowless && !self.listing.programmable && !self.listing.is_protected(),
+            MarketplaceError::UnsupportedInstallment
+        );
+
+        // Dutch listings are locked at their decayed price
+        let price = self.listing.total_price(now, self.listing.quantity)?;
+        self.installment_plan.set_inner(InstallmentPlan {
+            marketplace: self.marketplace.key(),
+            listing: self.listing.key(),
+            buyer: self.buyer.key(),
+            seller: self.listing.seller,
+            price,
+            num_payments,
+            payments_made: 0,
+            interval_secs,
+            start_ts: now,
+            paid: 0,
+            bump: bumps.installment_plan,
+        });
+        self.listing.is_active = false;
+
+        let amount = self.installment_plan.next_installment();
+        let cpi_ctx = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.buyer.to_account_info(),
+                to: self.installment_plan.to_account_info(),
+            },
+        );
+        transfer(cpi_ctx, amount)?;
+        self.installment_plan.record_payment(amount)?;
+
+        emit_cpi(&self.event_authority, bumps.event_authority, &InstallmentPurchaseStartedEvent {
+            installment_plan: self.installment_plan.key(),
+            listing: self.listing.key(),
+            buyer: self.buyer.key(),
+            seller: self.listing.seller,
+            price,
+            num_payments,
+            interval_secs,
+            first_payment: amount,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct InstallmentPurchaseStartedEvent {
+    pub installment_plan: Pubkey,
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+    pub num_payments: u8,
+    pub interval_secs: u32,
+    pub first_payment: u64,
+}
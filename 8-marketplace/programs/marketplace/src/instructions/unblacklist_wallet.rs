@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::MarketplaceError,
+    state::{Blacklist, Marketplace},
+};
+
+#[derive(Accounts)]
+pub struct UnblacklistWallet<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    /// - Receives the blacklist entry rent
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The blacklist entry being removed
+    /// - Closed and rent refunded to the admin, which unblocks the wallet
+    #[account(
+        mut,
+        seeds = [b"blacklist", marketplace.key().as_ref(), blacklist.wallet.as_ref()],
+        bump = blacklist.bump,
+        close = admin
+    )]
+    pub blacklist: Account<'info, Blacklist>,
+
+    /// The marketplace state account
+    /// - Validates the admin
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Listing, Marketplace},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateAllowedBuyer<'info> {
+    /// The seller who originally listed the NFT
+    /// - Must sign and match the seller stored in the listing
+    pub seller: Signer<'info>,
+
+    /// The NFT mint account of the listing
+    pub nft: Account<'info, Mint>,
+
+    /// The listing account being retargeted
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    /// - Derived from the stored seller so a wrong signer gets a dedicated error
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            listing.seller.as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = seller @ MarketplaceError::NotListingSeller,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    /// The marketplace state account for validation
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> UpdateAllowedBuyer<'info> {
+    /// Reserve an active listing for another buyer, or open it to everyone
+    ///
+    /// # Arguments
+    /// * `allowed_buyer` - The only wallet allowed to buy, or None for a public listing
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn update_allowed_buyer(
+        &mut self,
+        allowed_buyer: Option<Pubkey>,
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+
+        let old_allowed_buyer = self.listing.allowed_buyer;
+        self.listing.allowed_buyer = allowed_buyer;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &AllowedBuyerUpdatedEvent {
+            listing: self.listing.key(),
+            old_allowed_buyer,
+            new_allowed_buyer: allowed_buyer,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct AllowedBuyerUpdatedEvent {
+    pub listing: Pubkey,
+    pub old_allowed_buyer: Option<Pubkey>,
+    pub new_allowed_buyer: Option<Pubkey>,
+}
@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::MAX_FEE_BPS,
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::Marketplace,
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateFee<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The marketplace state account
+    /// - Updated with the new fee
+    #[account(
+        mut,
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> UpdateFee<'info> {
+    /// Update the fee charged on each sale
+    /// - Purchases read the marketplace account, so existing listings pay the new fee
+    ///
+    /// # Arguments
+    /// * `new_fee_bps` - The new fee in basis points (0-MAX_FEE_BPS) charged on each sale
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn update_fee(&mut self, new_fee_bps: u16, event_authority_bump: u8) -> Result<()> {
+        require!(new_fee_bps <= MAX_FEE_BPS, MarketplaceError::InvalidFeeBps);
+
+        let old_fee_bps = self.marketplace.fee_bps;
+        self.marketplace.fee_bps = new_fee_bps;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &FeeUpdatedEvent {
+            admin: self.admin.key(),
+            old_fee_bps,
+            new_fee_bps,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct FeeUpdatedEvent {
+    pub admin: Pubkey,
+    pub old_fee_bps: u16,
+    pub new_fee_bps: u16,
+}
@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    constants::ListingCategory,
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Listing, Marketplace},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateListingCategory<'info> {
+    /// The seller who originally listed the NFT
+    /// - Must sign and match the seller stored in the listing
+    pub seller: Signer<'info>,
+
+    /// The NFT mint account of the listing
+    pub nft: Account<'info, Mint>,
+
+    /// The listing account being recategorized
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    /// - Derived from the stored seller so a wrong signer gets a dedicated error
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            listing.seller.as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = seller @ MarketplaceError::NotListingSeller,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    /// The marketplace state account for validation
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> UpdateListingCategory<'info> {
+    /// Move an active listing to another category
+    ///
+    /// # Arguments
+    /// * `new_category` - The new category, one of the `ListingCategory` values
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn update_listing_category(
+        &mut self,
+        new_category: u8,
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+        require!(ListingCategory::is_known(new_category), MarketplaceError::InvalidCategory);
+
+        let old_category = self.listing.category;
+        self.listing.category = new_category;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &ListingCategoryUpdatedEvent {
+            listing: self.listing.key(),
+            old_category,
+            new_category,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct ListingCategoryUpdatedEvent {
+    pub listing: Pubkey,
+    pub old_category: u8,
+    pub new_category: u8,
+}
@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Listing, Marketplace},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateListingPrice<'info> {
+    /// The seller who originally listed the NFT
+    /// - Must sign and match the seller stored in the listing
+    pub seller: Signer<'info>,
+
+    /// The NFT mint account of the listing
+    pub nft: Account<'info, Mint>,
+
+    /// The listing account being repriced
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    /// - Derived from the stored seller so a wrong signer gets a dedicated error
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            listing.seller.as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = seller @ MarketplaceError::NotListingSeller,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    /// The marketplace state account for validation
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> UpdateListingPrice<'info> {
+    /// Update the price of an active listing in place
+    ///
+    /// # Arguments
+    /// * `new_price` - The new listing price in lamports
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn update_listing_price(&mut self, new_price: u64, event_authority_bump: u8) -> Result<()> {
+        // Validate listing is still active, fixed-price, and the new price is greater than 0
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+        require!(new_price > 0, MarketplaceError::InvalidPrice);
+        require!(self.marketplace.price_in_bounds(new_price), MarketplaceError::PriceOutOfBounds);
+        require!(self.listing.dutch.is_none(), MarketplaceError::NotFixedPriceListing);
+
+        // Purchases read the price from the account, so they see whichever value lands first
+        let old_price = self.listing.price;
+        self.listing.price = new_price;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &ListingUpdatedEvent {
+            listing: self.listing.key(),
+            old_price,
+            new_price,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct ListingUpdatedEvent {
+    pub listing: Pubkey,
+    pub old_price: u64,
+    pub new_price: u64,
+}
@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    error::MarketplaceError,
+    event_cpi::emit_cpi,
+    state::{Listing, Marketplace, SecondaryPrice},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateSecondaryPrice<'info> {
+    /// The seller who originally listed the NFT
+    /// - Must sign and match the seller stored in the listing
+    pub seller: Signer<'info>,
+
+    /// The NFT mint account of the listing
+    pub nft: Account<'info, Mint>,
+
+    /// The listing account being repriced
+    /// - Must match the PDA derived from marketplace, seller, NFT and listing nonce
+    /// - Derived from the stored seller so a wrong signer gets a dedicated error
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            marketplace.key().as_ref(),
+            listing.seller.as_ref(),
+            nft.key().as_ref(),
+            listing.nonce.to_le_bytes().as_ref(),
+        ],
+        bump = listing.bump,
+        has_one = seller @ MarketplaceError::NotListingSeller,
+        has_one = marketplace @ MarketplaceError::ListingMarketplaceMismatch,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    /// The marketplace state account for validation
+    /// - Its payment mint is the currency the secondary price must differ from
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> UpdateSecondaryPrice<'info> {
+    /// Set, change or remove the second currency an active listing also sells for
+    ///
+    /// # Arguments
+    /// * `secondary_price` - The SPL token mint and price per token, or None to sell only in the
+    ///   marketplace currency
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn update_secondary_price(
+        &mut self,
+        secondary_price: Option<SecondaryPrice>,
+        event_authority_bump: u8,
+    ) -> Result<()> {
+        require!(self.listing.is_active, MarketplaceError::ListingNotActive);
+        if let Some(secondary) = secondary_price {
+            require!(
+                self.listing
+                    .accepts_secondary_price(&secondary, self.marketplace.payment_mint),
+                MarketplaceError::InvalidSecondaryPrice
+            );
+        }
+
+        // Purchases read the price from the account, so they see whichever value lands first
+        let old_secondary_price = self.listing.secondary_price;
+        self.listing.secondary_price = secondary_price;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &SecondaryPriceUpdatedEvent {
+            listing: self.listing.key(),
+            old_secondary_price,
+            new_secondary_price: secondary_price,
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct SecondaryPriceUpdatedEvent {
+    pub listing: Pubkey,
+    pub old_secondary_price: Option<SecondaryPrice>,
+    pub new_secondary_price: Option<SecondaryPrice>,
+}
@@ -0,0 +1,99 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use crate::{error::MarketplaceError, event_cpi::emit_cpi, state::Marketplace};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    /// The admin account that manages the marketplace
+    /// - Must match the admin stored in the marketplace state
+    pub admin: Signer<'info>,
+
+    /// The account receiving the withdrawn fees
+    #[account(mut)]
+    pub destination: SystemAccount<'info>,
+
+    /// The marketplace state account
+    /// - Validates the admin and provides the treasury bump
+    #[account(
+        seeds = [
+            b"marketplace",
+            marketplace.admin.as_ref(),
+            marketplace.name.as_bytes(),
+        ],
+        bump = marketplace.bump,
+        has_one = admin @ MarketplaceError::Unauthorized,
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// Treasury account holding the collected marketplace fees
+    /// - Signs the transfer with its PDA seeds
+    #[account(
+        mut,
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump = marketplace.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    /// Required system program for the transfer
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawTreasury<'info> {
+    /// Transfer collected fees from the treasury to the destination
+    ///
+    /// # Arguments
+    /// * `amount` - The amount of lamports to withdraw
+    /// * `event_authority_bump` - PDA bump of the event authority
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn withdraw_treasury(&mut self, amount: u64, event_authority_bump: u8) -> Result<()> {
+        // Only lamports above the rent-exempt minimum can be withdrawn
+        let rent_floor = Rent::get()?.minimum_balance(0);
+        let available = self.treasury.lamports().saturating_sub(rent_floor);
+        require!(
+            amount <= available,
+            MarketplaceError::InsufficientTreasuryBalance
+        );
+
+        // Create seeds for PDA signing
+        let marketplace = self.marketplace.key();
+        let treasury_seeds: &[&[u8]] = &[
+            b"treasury",
+            marketplace.as_ref(),
+            &[self.marketplace.treasury_bump],
+        ];
+        let signer = &[treasury_seeds];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.treasury.to_account_info(),
+                to: self.destination.to_account_info(),
+            },
+            signer,
+        );
+        transfer(cpi_ctx, amount)?;
+
+        emit_cpi(&self.event_authority, event_authority_bump, &TreasuryWithdrawEvent {
+            admin: self.admin.key(),
+            destination: self.destination.key(),
+            amount,
+            remaining: self.treasury.lamports(),
+        })?;
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct TreasuryWithdrawEvent {
+    pub admin: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
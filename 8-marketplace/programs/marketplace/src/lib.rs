@@ -3,8 +3,11 @@
 
 pub mod constants;
 pub mod error;
+pub mod event_cpi;
 pub mod instructions;
+pub mod programmable;
 pub mod state;
+pub mod token_extensions;
 
 use anchor_lang::prelude::*;
 
@@ -19,15 +22,87 @@ declare_id!("6Cjww82bLkYs4cXngwh6rjXT3PywKrE6m2cYKYFs2idd");
 pub mod marketplace {
     use super::*;
 
-    pub fn initialize_marketplace(ctx: Context<InitializeMarketplace>, fee_percentage: u8) -> Result<()> {
-        ctx.accounts.initialize_marketplace(fee_percentage, ctx.bumps)?;
+    pub fn initialize_marketplace(
+        ctx: Context<InitializeMarketplace>,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+        name: String,
+    ) -> Result<()> {
+        ctx.accounts.initialize_marketplace(fee_bps, fee_recipient, name, ctx.bumps)?;
         Ok(())
     }
 
 
-    pub fn list_nft(ctx: Context<ListNft>, price: u64) -> Result<()> {
-        ctx.accounts.initialize_listing(price, ctx.bumps)?;
-        ctx.accounts.transfer_nft()
+    #[allow(clippy::too_many_arguments)]
+    pub fn list_nft(
+        ctx: Context<ListNft>,
+        price_per_unit: u64,
+        expiry: i64,
+        quantity: u64,
+        allowed_buyer: Option<Pubkey>,
+        protection_window_secs: u32,
+        start_ts: i64,
+        secondary_price: Option<SecondaryPrice>,
+        category: u8,
+    ) -> Result<()> {
+        let collection = ctx.accounts.verify_collection()?;
+        let collection_stats_bump = ctx.bumps.collection_stats;
+        let listing_index_bump = ctx.bumps.listing_index;
+        let event_authority_bump = ctx.bumps.event_authority;
+        ctx.accounts.initialize_listing(
+            price_per_unit,
+            expiry,
+            quantity,
+            allowed_buyer,
+            protection_window_secs,
+            start_ts,
+            secondary_price,
+            category,
+            collection,
+            ctx.bumps,
+        )?;
+        ctx.accounts.index_listing(listing_index_bump)?;
+        ctx.accounts.track_collection_listing(collection_stats_bump)?;
+        ctx.accounts.transfer_nft()?;
+        ctx.accounts.emit_listed(event_authority_bump)?;
+        Ok(())
+    }
+
+
+    pub fn list_nft_dutch(
+        ctx: Context<ListNft>,
+        start_price: u64,
+        floor_price: u64,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<()> {
+        let collection = ctx.accounts.verify_collection()?;
+        let dutch = DutchPricing {
+            start_price,
+            floor_price,
+            start_ts,
+            end_ts,
+        };
+        let collection_stats_bump = ctx.bumps.collection_stats;
+        let listing_index_bump = ctx.bumps.listing_index;
+        let event_authority_bump = ctx.bumps.event_authority;
+        ctx.accounts.initialize_dutch_listing(dutch, collection, ctx.bumps)?;
+        ctx.accounts.index_listing(listing_index_bump)?;
+        ctx.accounts.track_collection_listing(collection_stats_bump)?;
+        ctx.accounts.transfer_nft()?;
+        ctx.accounts.emit_listed(event_authority_bump)?;
+        Ok(())
+    }
+
+
+    pub fn list_nft_escrowless(
+        ctx: Context<ListNftEscrowless>,
+        price: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        let collection = ctx.accounts.verify_collection()?;
+        ctx.accounts.initialize_listing(price, expiry, collection, ctx.bumps)?;
+        ctx.accounts.delegate_and_freeze()
     }
 
 
@@ -35,9 +110,429 @@ pub mod marketplace {
         ctx.accounts.transfer_back_nft()
     }
 
-    pub fn purchase_nft(ctx: Context<PurchaseNft>) -> Result<()> {
-        ctx.accounts.transfer_nft()?;
-        ctx.accounts.transfer_sol()?;
-        ctx.accounts.delist_nft()
+    pub fn bulk_list<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BulkList<'info>>,
+        prices: Vec<u64>,
+    ) -> Result<()> {
+        ctx.accounts.bulk_list(&prices, ctx.remaining_accounts, ctx.bumps)
+    }
+
+    pub fn bulk_delist<'info>(ctx: Context<'_, '_, 'info, 'info, BulkDelist<'info>>) -> Result<()> {
+        ctx.accounts.bulk_delist(ctx.remaining_accounts, ctx.bumps.event_authority)
+    }
+
+    pub fn list_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ListBundle<'info>>,
+        price: u64,
+    ) -> Result<()> {
+        ctx.accounts.list_bundle(price, ctx.remaining_accounts, ctx.bumps)
+    }
+
+    pub fn delist_bundle<'info>(ctx: Context<'_, '_, 'info, 'info, DelistBundle<'info>>) -> Result<()> {
+        ctx.accounts.delist_bundle(ctx.remaining_accounts, ctx.bumps.event_authority)
+    }
+
+    pub fn purchase_nft<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PurchaseNft<'info>>,
+        accept_changed_metadata: bool,
+    ) -> Result<()> {
+        let amount = ctx.accounts.listing.quantity;
+        purchase_quantity(ctx, amount, accept_changed_metadata)
+    }
+
+    pub fn purchase_quantity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PurchaseNft<'info>>,
+        amount: u64,
+        accept_changed_metadata: bool,
+    ) -> Result<()> {
+        ctx.accounts.transfer_nft(amount, accept_changed_metadata)?;
+        let (total, split, fee_discount_bps) = ctx
+            .accounts
+            .transfer_payment(amount, ctx.remaining_accounts)?;
+        ctx.accounts.open_sale_escrow(&split, ctx.bumps.event_authority)?;
+        ctx.accounts.mint_rewards(total, ctx.bumps.event_authority)?;
+        ctx.accounts.record_sale(
+            amount,
+            total,
+            &split,
+            fee_discount_bps,
+            None,
+            ctx.bumps.provenance,
+            ctx.bumps.event_authority,
+        )
+    }
+
+    pub fn purchase_nft_with_token<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PurchaseNft<'info>>,
+        accept_changed_metadata: bool,
+    ) -> Result<()> {
+        let amount = ctx.accounts.listing.quantity;
+        ctx.accounts.transfer_nft(amount, accept_changed_metadata)?;
+        // Reward points are rated in the marketplace currency, so secondary sales earn none
+        let (mint, total, split, fee_discount_bps) = ctx
+            .accounts
+            .transfer_secondary_payment(amount, ctx.remaining_accounts)?;
+        ctx.accounts.record_sale(
+            amount,
+            total,
+            &split,
+            fee_discount_bps,
+            Some(mint),
+            ctx.bumps.provenance,
+            ctx.bumps.event_authority,
+        )
+    }
+
+    pub fn purchase_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PurchaseMany<'info>>,
+        max_total: u64,
+    ) -> Result<()> {
+        ctx.accounts.purchase_many(max_total, ctx.remaining_accounts, ctx.bumps.event_authority)
+    }
+
+    pub fn purchase_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PurchaseBundle<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.purchase_bundle(ctx.remaining_accounts, ctx.bumps.event_authority)
+    }
+
+    pub fn start_installment_purchase(
+        ctx: Context<StartInstallmentPurchase>,
+        num_payments: u8,
+        interval_secs: u32,
+    ) -> Result<()> {
+        ctx.accounts
+            .start_installment_purchase(num_payments, interval_secs, ctx.bumps)
+    }
+
+    pub fn pay_installment(ctx: Context<PayInstallment>) -> Result<()> {
+        ctx.accounts.pay_installment(ctx.bumps.event_authority)
+    }
+
+    pub fn default_installment(ctx: Context<DefaultInstallment>) -> Result<()> {
+        ctx.accounts.default_installment(ctx.bumps.event_authority)
+    }
+
+    pub fn release_sale(ctx: Context<ReleaseSale>) -> Result<()> {
+        ctx.accounts.release_sale(ctx.bumps.event_authority)
+    }
+
+    pub fn dispute_sale(ctx: Context<DisputeSale>) -> Result<()> {
+        ctx.accounts.dispute_sale(ctx.bumps.event_authority)
+    }
+
+    pub fn resolve_sale_dispute(ctx: Context<ResolveSaleDispute>, refund_buyer: bool) -> Result<()> {
+        ctx.accounts.resolve_sale_dispute(refund_buyer, ctx.bumps.event_authority)
+    }
+
+    pub fn update_listing_price(ctx: Context<UpdateListingPrice>, new_price: u64) -> Result<()> {
+        ctx.accounts.update_listing_price(new_price, ctx.bumps.event_authority)
+    }
+
+    pub fn extend_listing(ctx: Context<ExtendListing>, new_expiry: i64) -> Result<()> {
+        ctx.accounts.extend_listing(new_expiry, ctx.bumps.event_authority)
+    }
+
+    pub fn update_listing_category(
+        ctx: Context<UpdateListingCategory>,
+        new_category: u8,
+    ) -> Result<()> {
+        ctx.accounts.update_listing_category(new_category, ctx.bumps.event_authority)
+    }
+
+    pub fn update_allowed_buyer(
+        ctx: Context<UpdateAllowedBuyer>,
+        allowed_buyer: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts.update_allowed_buyer(allowed_buyer, ctx.bumps.event_authority)
+    }
+
+    pub fn update_secondary_price(
+        ctx: Context<UpdateSecondaryPrice>,
+        secondary_price: Option<SecondaryPrice>,
+    ) -> Result<()> {
+        ctx.accounts.update_secondary_price(secondary_price, ctx.bumps.event_authority)
+    }
+
+    pub fn make_offer(ctx: Context<MakeOffer>, amount: u64, expiry: i64) -> Result<()> {
+        ctx.accounts.make_offer(amount, expiry, ctx.bumps)
+    }
+
+    pub fn make_offer_token(ctx: Context<MakeOfferToken>, amount: u64, expiry: i64) -> Result<()> {
+        ctx.accounts.make_offer_token(amount, expiry, ctx.bumps)
+    }
+
+    pub fn accept_offer(ctx: Context<AcceptOffer>) -> Result<()> {
+        ctx.accounts.accept_offer(ctx.bumps)
+    }
+
+    pub fn accept_best_offer(ctx: Context<AcceptOffer>) -> Result<()> {
+        ctx.accounts.accept_best_offer(ctx.bumps)
+    }
+
+    pub fn make_quantity_offer(
+        ctx: Context<MakeQuantityOffer>,
+        quantity: u64,
+        price_per_unit: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        ctx.accounts.make_quantity_offer(quantity, price_per_unit, expiry, ctx.bumps)
+    }
+
+    pub fn accept_offer_partial(
+        ctx: Context<AcceptOfferPartial>,
+        fill_quantity: u64,
+    ) -> Result<()> {
+        ctx.accounts.accept_offer_partial(fill_quantity, ctx.bumps)
+    }
+
+    pub fn cancel_quantity_offer(ctx: Context<CancelQuantityOffer>) -> Result<()> {
+        ctx.accounts.cancel_quantity_offer(ctx.bumps.event_authority)
+    }
+
+    pub fn refund_expired_quantity_offer(ctx: Context<CancelQuantityOffer>) -> Result<()> {
+        ctx.accounts.refund_expired_quantity_offer(ctx.bumps.event_authority)
+    }
+
+    pub fn cancel_offer<'info>(ctx: Context<'_, '_, 'info, 'info, CancelOffer<'info>>) -> Result<()> {
+        ctx.accounts.cancel_offer(ctx.remaining_accounts, ctx.bumps.event_authority)
+    }
+
+    pub fn refund_expired_offer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelOffer<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.refund_expired_offer(ctx.remaining_accounts, ctx.bumps.event_authority)
+    }
+
+    pub fn counter_offer(ctx: Context<CounterOffer>, amount: u64) -> Result<()> {
+        ctx.accounts.counter_offer(amount, ctx.bumps.event_authority)
+    }
+
+    pub fn accept_counter(ctx: Context<AcceptCounter>) -> Result<()> {
+        ctx.accounts.accept_counter(ctx.bumps)
+    }
+
+    pub fn reject_counter(ctx: Context<RejectCounter>) -> Result<()> {
+        ctx.accounts.reject_counter(ctx.bumps.event_authority)
+    }
+
+    pub fn make_nft_offer(ctx: Context<MakeNftOffer>, amount: u64, expiry: i64) -> Result<()> {
+        ctx.accounts.make_nft_offer(amount, expiry, ctx.bumps)
+    }
+
+    pub fn accept_nft_offer(ctx: Context<AcceptNftOffer>) -> Result<()> {
+        ctx.accounts.accept_nft_offer(ctx.bumps)
+    }
+
+    pub fn cancel_nft_offer(ctx: Context<CancelNftOffer>) -> Result<()> {
+        ctx.accounts.cancel_nft_offer(ctx.bumps.event_authority)
+    }
+
+    pub fn refund_expired_nft_offer(ctx: Context<CancelNftOffer>) -> Result<()> {
+        ctx.accounts.refund_expired_nft_offer(ctx.bumps.event_authority)
+    }
+
+    pub fn make_collection_offer(
+        ctx: Context<MakeCollectionOffer>,
+        price: u64,
+        quantity: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        ctx.accounts.make_collection_offer(price, quantity, expiry, ctx.bumps)
+    }
+
+    pub fn accept_collection_offer(ctx: Context<AcceptCollectionOffer>) -> Result<()> {
+        ctx.accounts.accept_collection_offer(ctx.bumps)
+    }
+
+    pub fn cancel_collection_offer(ctx: Context<CancelCollectionOffer>) -> Result<()> {
+        ctx.accounts.cancel_collection_offer(ctx.bumps.event_authority)
+    }
+
+    pub fn refund_expired_collection_offer(ctx: Context<CancelCollectionOffer>) -> Result<()> {
+        ctx.accounts.refund_expired_collection_offer(ctx.bumps.event_authority)
+    }
+
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        ctx.accounts.withdraw_treasury(amount, ctx.bumps.event_authority)
+    }
+
+    pub fn update_fee(ctx: Context<UpdateFee>, new_fee_bps: u16) -> Result<()> {
+        ctx.accounts.update_fee(new_fee_bps, ctx.bumps.event_authority)
+    }
+
+    pub fn set_reward_rate(ctx: Context<SetRewardRate>, reward_rate_bps: u16) -> Result<()> {
+        ctx.accounts.set_reward_rate(reward_rate_bps)
+    }
+
+    pub fn set_referral_bps(ctx: Context<SetReferralBps>, referral_bps: u16) -> Result<()> {
+        ctx.accounts.set_referral_bps(referral_bps)
+    }
+
+    pub fn set_fee_tiers(ctx: Context<SetFeeTiers>, fee_tiers: [FeeTier; FEE_TIER_COUNT]) -> Result<()> {
+        ctx.accounts.set_fee_tiers(fee_tiers)
+    }
+
+    pub fn set_fee_splits(ctx: Context<SetFeeSplits>, fee_splits: Vec<FeeSplit>) -> Result<()> {
+        ctx.accounts.set_fee_splits(fee_splits)
+    }
+
+    pub fn create_seller_stats(ctx: Context<CreateSellerStats>) -> Result<()> {
+        ctx.accounts.create_seller_stats(ctx.bumps)
+    }
+
+    pub fn set_crank_fee(ctx: Context<SetCrankFee>, crank_fee_lamports: u64) -> Result<()> {
+        ctx.accounts.set_crank_fee(crank_fee_lamports)
+    }
+
+    pub fn set_crank_reward(ctx: Context<SetCrankReward>, crank_reward_lamports: u64) -> Result<()> {
+        ctx.accounts.set_crank_reward(crank_reward_lamports)
+    }
+
+    pub fn set_max_listing_duration(
+        ctx: Context<SetMaxListingDuration>,
+        max_listing_duration: u32,
+    ) -> Result<()> {
+        ctx.accounts.set_max_listing_duration(max_listing_duration)
+    }
+
+    pub fn set_price_bounds(
+        ctx: Context<SetPriceBounds>,
+        min_price: u64,
+        max_price: u64,
+    ) -> Result<()> {
+        ctx.accounts.set_price_bounds(min_price, max_price)
+    }
+
+    pub fn set_installment_terms(
+        ctx: Context<SetInstallmentTerms>,
+        grace_secs: u32,
+        forfeit_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts.set_installment_terms(grace_secs, forfeit_bps)
+    }
+
+    pub fn set_holder_discount(
+        ctx: Context<SetHolderDiscount>,
+        threshold: u64,
+        discount_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts.set_holder_discount(threshold, discount_bps)
+    }
+
+    pub fn set_pull_payments(ctx: Context<SetPullPayments>, pull_payments: bool) -> Result<()> {
+        ctx.accounts.set_pull_payments(pull_payments)
+    }
+
+    pub fn claim_proceeds(ctx: Context<ClaimProceeds>) -> Result<()> {
+        ctx.accounts.claim_proceeds(ctx.bumps.event_authority)
+    }
+
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        ctx.accounts.set_paused(paused, ctx.bumps.event_authority)
+    }
+
+    pub fn set_fee_recipient(ctx: Context<SetFeeRecipient>, fee_recipient: Pubkey) -> Result<()> {
+        ctx.accounts.set_fee_recipient(fee_recipient, ctx.bumps.event_authority)
+    }
+
+    pub fn migrate_marketplace(ctx: Context<MigrateMarketplace>) -> Result<()> {
+        ctx.accounts.migrate_marketplace(ctx.bumps)
+    }
+
+    pub fn migrate_marketplace_stats(ctx: Context<MigrateMarketplaceStats>, _name: String) -> Result<()> {
+        ctx.accounts.migrate_marketplace_stats()
+    }
+
+    pub fn get_marketplace_stats(ctx: Context<GetMarketplaceStats>) -> Result<MarketplaceStats> {
+        ctx.accounts.get_marketplace_stats()
+    }
+
+    pub fn get_listing_page(ctx: Context<GetListingPage>, _page: u64) -> Result<ListingPage> {
+        ctx.accounts.get_listing_page()
+    }
+
+    pub fn get_provenance(ctx: Context<GetProvenance>) -> Result<ProvenanceHistory> {
+        ctx.accounts.get_provenance()
+    }
+
+    pub fn refresh_floor<'info>(ctx: Context<'_, '_, 'info, 'info, RefreshFloor<'info>>) -> Result<()> {
+        ctx.accounts.refresh_floor(ctx.remaining_accounts, ctx.bumps.event_authority)
+    }
+
+    pub fn get_collection_stats(ctx: Context<GetCollectionStats>) -> Result<CollectionSummary> {
+        ctx.accounts.get_collection_stats()
+    }
+
+    pub fn add_collection(ctx: Context<AddCollection>) -> Result<()> {
+        ctx.accounts.add_collection(ctx.bumps)
+    }
+
+    pub fn remove_collection(_ctx: Context<RemoveCollection>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn blacklist_wallet(ctx: Context<BlacklistWallet>) -> Result<()> {
+        ctx.accounts.blacklist_wallet(ctx.bumps)
+    }
+
+    pub fn unblacklist_wallet(_ctx: Context<UnblacklistWallet>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn set_block_blacklisted_buyers(
+        ctx: Context<SetBlockBlacklistedBuyers>,
+        block_blacklisted_buyers: bool,
+    ) -> Result<()> {
+        ctx.accounts.set_block_blacklisted_buyers(block_blacklisted_buyers)
+    }
+
+    pub fn set_open_listings(ctx: Context<SetOpenListings>, open_listings: bool) -> Result<()> {
+        ctx.accounts.set_open_listings(open_listings)
+    }
+
+    pub fn clean_expired_listing(ctx: Context<CleanExpiredListing>) -> Result<()> {
+        ctx.accounts.clean_expired_listing(ctx.bumps.event_authority)
+    }
+
+    pub fn garbage_collect(ctx: Context<GarbageCollect>) -> Result<()> {
+        ctx.accounts.garbage_collect(ctx.bumps.event_authority)
+    }
+
+    pub fn admin_delist(ctx: Context<AdminDelist>, reason: u8) -> Result<()> {
+        ctx.accounts.admin_delist(reason, ctx.bumps.event_authority)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_auction(
+        ctx: Context<CreateAuction>,
+        start_price: u64,
+        min_increment: u64,
+        end_time: i64,
+        reserve_price: u64,
+        reserve_hidden: bool,
+        buy_now_price: Option<u64>,
+    ) -> Result<()> {
+        ctx.accounts.create_auction(
+            start_price,
+            min_increment,
+            end_time,
+            reserve_price,
+            reserve_hidden,
+            buy_now_price,
+            ctx.bumps,
+        )
+    }
+
+    pub fn place_bid(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+        ctx.accounts.place_bid(amount, ctx.bumps.event_authority)
+    }
+
+    pub fn settle_auction(ctx: Context<SettleAuction>) -> Result<()> {
+        ctx.accounts.settle_auction(ctx.bumps)
+    }
+
+    pub fn buy_now(ctx: Context<BuyNow>) -> Result<()> {
+        ctx.accounts.buy_now(ctx.bumps)
     }
 }
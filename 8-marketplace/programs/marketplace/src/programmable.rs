@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use anchor_spl::metadata::{
+    mpl_token_metadata::{instructions::TransferCpiBuilder, types::{TokenStandard, TransferArgs}},
+    MetadataAccount,
+};
+
+/// Whether the metadata belongs to a programmable NFT
+/// - pNFT token accounts stay frozen, so they can only move through Token Metadata
+pub fn is_programmable(metadata: &MetadataAccount) -> bool {
+    metadata.token_standard == Some(TokenStandard::ProgrammableNonFungible)
+}
+
+/// A Token Metadata transfer of one programmable NFT
+/// - Thaws the source, moves the token, freezes the destination and checks the rule set
+pub struct ProgrammableTransfer<'info> {
+    pub source: AccountInfo<'info>,
+    pub source_owner: AccountInfo<'info>,
+    pub source_token_record: AccountInfo<'info>,
+    pub destination: AccountInfo<'info>,
+    pub destination_owner: AccountInfo<'info>,
+    pub destination_token_record: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub metadata: AccountInfo<'info>,
+    pub edition: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub authorization_rules: Option<AccountInfo<'info>>,
+    pub authorization_rules_program: Option<AccountInfo<'info>>,
+    pub sysvar_instructions: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    pub metadata_program: AccountInfo<'info>,
+}
+
+impl<'info> ProgrammableTransfer<'info> {
+    /// Run the transfer, signing for PDA authorities with `signer_seeds`
+    ///
+    /// # Arguments
+    /// * `signer_seeds` - Seeds of the authority PDA, or empty when the authority signed the transaction
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error from the CPI
+    pub fn invoke_signed(&self, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        TransferCpiBuilder::new(&self.metadata_program)
+            .token(&self.source)
+            .token_owner(&self.source_owner)
+            .token_record(Some(&self.source_token_record))
+            .destination_token(&self.destination)
+            .destination_owner(&self.destination_owner)
+            .destination_token_record(Some(&self.destination_token_record))
+            .mint(&self.mint)
+            .metadata(&self.metadata)
+            .edition(Some(&self.edition))
+            .authority(&self.authority)
+            .payer(&self.payer)
+            .authorization_rules(self.authorization_rules.as_ref())
+            .authorization_rules_program(self.authorization_rules_program.as_ref())
+            .sysvar_instructions(&self.sysvar_instructions)
+            .system_program(&self.system_program)
+            .spl_token_program(&self.token_program)
+            .spl_ata_program(&self.associated_token_program)
+            .transfer_args(TransferArgs::V1 {
+                amount: 1,
+                authorization_data: None,
+            })
+            .invoke_signed(signer_seeds)?;
+
+        Ok(())
+    }
+}
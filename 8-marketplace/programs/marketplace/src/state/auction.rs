@@ -0,0 +1,139 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Auction {
+    /// The seller's public key who auctions the NFT
+    pub seller: Pubkey,
+
+    /// The mint address of the NFT being auctioned
+    pub mint: Pubkey,
+
+    /// The lowest accepted first bid in lamports
+    pub start_price: u64,
+
+    /// The amount in lamports each new bid must add to the highest bid
+    pub min_increment: u64,
+
+    /// Unix timestamp at which bidding closes and the auction can be settled
+    pub end_time: i64,
+
+    /// The highest bid in lamports, held in the bid escrow
+    /// 0 until the first bid
+    pub highest_bid: u64,
+
+    /// The bidder holding the highest bid
+    /// None until the first bid
+    pub highest_bidder: Option<Pubkey>,
+
+    /// PDA bump seed for this auction account
+    /// Used for deterministic address generation
+    pub bump: u8,
+
+    /// PDA bump seed for the bid escrow account
+    /// Used for signing refunds and payouts
+    pub escrow_bump: u8,
+
+    /// The lowest highest bid in lamports the NFT is sold for at settlement
+    /// Below it the NFT returns to the seller and the top bidder is refunded; 0 sets no reserve
+    pub reserve_price: u64,
+
+    /// Whether the reserve is left out of the auction's events
+    /// The account data stays readable on-chain, so this only keeps it off indexers and UIs
+    pub reserve_hidden: bool,
+
+    /// The price in lamports at which anyone can end the auction at once with `buy_now`
+    /// None disables buy-now
+    pub buy_now_price: Option<u64>,
+}
+
+impl Auction {
+    /// The lowest bid the auction accepts next
+    /// - The start price before any bids, then the highest bid plus the minimum increment
+    pub fn min_next_bid(&self) -> Option<u64> {
+        match self.highest_bidder {
+            None => Some(self.start_price),
+            Some(_) => self.highest_bid.checked_add(self.min_increment),
+        }
+    }
+
+    /// Whether bidding has closed at the given unix timestamp
+    pub fn has_ended(&self, now: i64) -> bool {
+        now >= self.end_time
+    }
+
+    /// Whether the highest bid reaches the reserve price
+    pub fn reserve_met(&self) -> bool {
+        self.highest_bid >= self.reserve_price
+    }
+
+    /// The price `buy_now` charges
+    /// - None when buy-now is disabled or bidding already reached the buy-now price
+    pub fn buy_now_price(&self) -> Option<u64> {
+        self.buy_now_price
+            .filter(|price| self.highest_bidder.is_none() || self.highest_bid < *price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auction(highest_bidder: Option<Pubkey>, highest_bid: u64) -> Auction {
+        Auction {
+            seller: Pubkey::default(),
+            mint: Pubkey::default(),
+            start_price: 1_000,
+            min_increment: 100,
+            end_time: 5_000,
+            highest_bid,
+            highest_bidder,
+            bump: 255,
+            escrow_bump: 254,
+            reserve_price: 0,
+            reserve_hidden: false,
+            buy_now_price: None,
+        }
+    }
+
+    #[test]
+    fn first_bid_starts_at_start_price() {
+        assert_eq!(auction(None, 0).min_next_bid(), Some(1_000));
+    }
+
+    #[test]
+    fn later_bids_add_the_min_increment() {
+        let bidder = Some(Pubkey::new_unique());
+        assert_eq!(auction(bidder, 1_500).min_next_bid(), Some(1_600));
+        assert_eq!(auction(bidder, u64::MAX).min_next_bid(), None);
+    }
+
+    #[test]
+    fn reserve_is_met_from_the_reserve_price_on() {
+        let bidder = Some(Pubkey::new_unique());
+        let reserved = |highest_bid| Auction { reserve_price: 2_000, ..auction(bidder, highest_bid) };
+        assert!(!reserved(1_999).reserve_met());
+        assert!(reserved(2_000).reserve_met());
+        assert!(auction(bidder, 1_000).reserve_met());
+    }
+
+    #[test]
+    fn buy_now_closes_once_bids_reach_it() {
+        let bidder = Some(Pubkey::new_unique());
+        let buy_now = |bidder, highest_bid| Auction {
+            buy_now_price: Some(3_000),
+            ..auction(bidder, highest_bid)
+        };
+        assert_eq!(buy_now(None, 0).buy_now_price(), Some(3_000));
+        assert_eq!(buy_now(bidder, 2_999).buy_now_price(), Some(3_000));
+        assert_eq!(buy_now(bidder, 3_000).buy_now_price(), None);
+        assert_eq!(auction(None, 0).buy_now_price(), None);
+    }
+
+    #[test]
+    fn ends_exactly_at_end_time() {
+        let auction = auction(None, 0);
+        assert!(!auction.has_ended(4_999));
+        assert!(auction.has_ended(5_000));
+    }
+}
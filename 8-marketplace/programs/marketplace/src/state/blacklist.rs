@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Blacklist {
+    /// The marketplace the wallet is blocked on
+    pub marketplace: Pubkey,
+
+    /// The blocked wallet
+    pub wallet: Pubkey,
+
+    /// PDA bump seed for this blacklist entry
+    /// Used for deterministic address generation
+    pub bump: u8,
+}
+
+impl Blacklist {
+    /// Whether a blacklist entry PDA, which may not exist, blocks its wallet
+    /// - Only existence is checked, so the caller must pin the address with its account seeds
+    /// - Lamports sent to the address do not create an entry; only this program can allocate it
+    pub fn is_blacklisted(entry: &AccountInfo) -> bool {
+        !entry.data_is_empty() && *entry.owner == crate::ID
+    }
+}
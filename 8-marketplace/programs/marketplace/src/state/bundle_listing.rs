@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_BUNDLE_ITEMS;
+
+#[account]
+#[derive(InitSpace)]
+pub struct BundleListing {
+    /// The seller who listed the bundle
+    pub seller: Pubkey,
+
+    /// The marketplace the bundle is listed on
+    pub marketplace: Pubkey,
+
+    /// Price of the whole bundle in lamports
+    pub price: u64,
+
+    /// Mints of the NFTs in the bundle, in listing order
+    /// Each is escrowed in the bundle's associated token account
+    #[max_len(MAX_BUNDLE_ITEMS)]
+    pub mints: Vec<Pubkey>,
+
+    /// The seller's listing nonce the bundle was created with, part of its PDA seeds
+    pub nonce: u64,
+
+    /// PDA bump seed for this bundle account
+    /// Used for deterministic address generation
+    pub bump: u8,
+}
+
+impl BundleListing {
+    /// Whether the given mints are exactly the bundle's, in listing order
+    /// - Bundles are only bought or delisted whole
+    ///
+    /// # Arguments
+    /// * `mints` - The mints of the account groups passed in
+    pub fn is_whole_bundle(&self, mints: &[Pubkey]) -> bool {
+        self.mints == mints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle(mints: Vec<Pubkey>) -> BundleListing {
+        BundleListing {
+            seller: Pubkey::default(),
+            marketplace: Pubkey::default(),
+            price: 1_000,
+            mints,
+            nonce: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn only_every_mint_in_order_is_the_whole_bundle() {
+        let mints: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let bundle = bundle(mints.clone());
+
+        assert!(bundle.is_whole_bundle(&mints));
+        assert!(!bundle.is_whole_bundle(&mints[..2]));
+        assert!(!bundle.is_whole_bundle(&[mints[1], mints[0], mints[2]]));
+        assert!(!bundle.is_whole_bundle(&[mints[0], mints[1], mints[2], mints[2]]));
+    }
+}
@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+use anchor_spl::metadata::MetadataAccount;
+
+use crate::{error::MarketplaceError, state::Marketplace};
+
+#[account]
+#[derive(InitSpace)]
+pub struct CollectionConfig {
+    /// The marketplace this collection is approved on
+    pub marketplace: Pubkey,
+
+    /// The verified collection mint whose NFTs may be listed
+    pub collection_mint: Pubkey,
+
+    /// PDA bump seed for this collection config account
+    /// Used for deterministic address generation
+    pub bump: u8,
+}
+
+impl CollectionConfig {
+    /// Check an NFT's collection before it is escrowed for sale
+    /// - A claimed collection must always be verified
+    /// - Unless the marketplace has open listings, it must also be approved on this marketplace
+    /// - The metadata PDA derivation from the NFT mint is checked by the caller's account seeds
+    ///
+    /// # Arguments
+    /// * `marketplace` - The marketplace the NFT is sold on
+    /// * `metadata` - The NFT's metadata account
+    /// * `collection_mint` - The collection mint passed in by the seller
+    /// * `collection_config` - The collection config PDA, which may not exist
+    ///
+    /// # Returns
+    /// * `Result<Option<Pubkey>>` - The verified collection mint, or None if the NFT has no collection
+    pub fn verify_nft(
+        marketplace: &Marketplace,
+        metadata: &MetadataAccount,
+        collection_mint: &Pubkey,
+        collection_config: &AccountInfo,
+    ) -> Result<Option<Pubkey>> {
+        let collection = match metadata.collection.as_ref() {
+            Some(collection) => {
+                require!(collection.verified, MarketplaceError::CollectionNotVerified);
+                Some(Pubkey::new_from_array(collection.key.to_bytes()))
+            }
+            None => None,
+        };
+
+        if marketplace.open_listings {
+            return Ok(collection);
+        }
+
+        // The metadata must claim the collection mint passed in
+        require!(
+            collection == Some(*collection_mint),
+            MarketplaceError::CollectionNotAllowed
+        );
+
+        // The collection must have been approved by the admin
+        require!(
+            !collection_config.data_is_empty() && *collection_config.owner == crate::ID,
+            MarketplaceError::CollectionNotAllowed
+        );
+        let config = CollectionConfig::try_deserialize(&mut &collection_config.try_borrow_data()?[..])
+            .map_err(|_| MarketplaceError::CollectionNotAllowed)?;
+        require!(
+            config.collection_mint == *collection_mint,
+            MarketplaceError::CollectionNotAllowed
+        );
+
+        Ok(collection)
+    }
+}
@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+
+use crate::error::MarketplaceError;
+
+#[account]
+#[derive(InitSpace)]
+pub struct CollectionOffer {
+    /// The marketplace the offer was made on
+    pub marketplace: Pubkey,
+
+    /// The verified collection any of whose NFTs the offer buys
+    pub collection_mint: Pubkey,
+
+    /// The buyer who made the offer and receives the NFTs and refunds
+    pub buyer: Pubkey,
+
+    /// The offered amount per NFT in lamports
+    pub price: u64,
+
+    /// NFTs the offer still buys
+    /// The escrow holds `price` for each of them on top of the account rent
+    pub quantity: u64,
+
+    /// Unix timestamp after which the offer can no longer be accepted
+    /// Expired offers can be refunded by anyone, for the marketplace's crank bounty
+    pub expiry: i64,
+
+    /// PDA bump seed for this offer account
+    /// Used for deterministic address generation
+    pub bump: u8,
+}
+
+impl CollectionOffer {
+    /// Whether the offer has expired at the given unix timestamp
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expiry
+    }
+
+    /// The lamports escrowed for the NFTs the offer still buys
+    pub fn escrowed(&self) -> Result<u64> {
+        self.price
+            .checked_mul(self.quantity)
+            .ok_or(error!(MarketplaceError::MathOverflow))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offer(price: u64, quantity: u64) -> CollectionOffer {
+        CollectionOffer {
+            marketplace: Pubkey::default(),
+            collection_mint: Pubkey::default(),
+            buyer: Pubkey::default(),
+            price,
+            quantity,
+            expiry: 5_000,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn escrow_covers_every_remaining_nft() {
+        assert_eq!(offer(1_000, 3).escrowed().unwrap(), 3_000);
+        assert_eq!(offer(1_000, 0).escrowed().unwrap(), 0);
+        assert!(offer(u64::MAX, 2).escrowed().is_err());
+    }
+
+    #[test]
+    fn expires_exactly_at_expiry() {
+        let offer = offer(1_000, 1);
+        assert!(!offer.is_expired(4_999));
+        assert!(offer.is_expired(5_000));
+    }
+}
@@ -0,0 +1,250 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{VOLUME_BUCKET_COUNT, VOLUME_BUCKET_SECS};
+
+#[account]
+#[derive(InitSpace)]
+pub struct CollectionStats {
+    /// The marketplace the statistics are kept for
+    pub marketplace: Pubkey,
+
+    /// The collection mint the statistics belong to
+    pub collection: Pubkey,
+
+    /// Price of the cheapest fixed-price listing seen, a best-effort floor
+    /// None once that listing closes, until a new listing or `refresh_floor` sets it again
+    pub floor_price: Option<u64>,
+
+    /// The listing `floor_price` was taken from
+    pub floor_listing: Option<Pubkey>,
+
+    /// Listings of the collection opened with the statistics and not yet closed
+    pub active_listings: u64,
+
+    /// Price per token of the most recent sale, None before the first one
+    pub last_sale_price: Option<u64>,
+
+    /// Unix timestamp of the most recent sale, 0 before the first one
+    pub last_sale_ts: i64,
+
+    /// Lifetime sale volume, in lamports or payment token base units
+    pub total_volume: u128,
+
+    /// Number of completed sales
+    pub sales_count: u64,
+
+    /// Sale volume per hour, indexed by the hour modulo VOLUME_BUCKET_COUNT
+    /// Buckets of an earlier hour are reset when the slot is reused
+    pub volume_buckets: [VolumeBucket; VOLUME_BUCKET_COUNT],
+
+    /// PDA bump seed for this statistics account
+    /// Used for deterministic address generation
+    pub bump: u8,
+}
+
+/// Sale volume of one hour
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace)]
+pub struct VolumeBucket {
+    /// Hours since the unix epoch the bucket counts
+    pub hour: i64,
+
+    /// Sale volume in that hour
+    pub volume: u64,
+}
+
+/// Collection statistics returned by `get_collection_stats`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollectionSummary {
+    pub collection: Pubkey,
+    pub floor_price: Option<u64>,
+    pub floor_listing: Option<Pubkey>,
+    pub active_listings: u64,
+    pub last_sale_price: Option<u64>,
+    pub last_sale_ts: i64,
+    pub rolling_volume: u64,
+    pub total_volume: u128,
+    pub sales_count: u64,
+}
+
+impl CollectionStats {
+    /// Statistics of a collection that was never listed
+    ///
+    /// # Arguments
+    /// * `marketplace` - The marketplace the statistics are kept for
+    /// * `collection` - The collection mint the statistics belong to
+    /// * `bump` - PDA bump seed of the statistics account
+    pub fn new(marketplace: Pubkey, collection: Pubkey, bump: u8) -> Self {
+        Self {
+            marketplace,
+            collection,
+            floor_price: None,
+            floor_listing: None,
+            active_listings: 0,
+            last_sale_price: None,
+            last_sale_ts: 0,
+            total_volume: 0,
+            sales_count: 0,
+            volume_buckets: [VolumeBucket::default(); VOLUME_BUCKET_COUNT],
+            bump,
+        }
+    }
+
+    /// Whether the account was just created by `init_if_needed` and still needs `new`
+    pub fn is_uninitialized(&self) -> bool {
+        self.collection == Pubkey::default()
+    }
+
+    /// Count a newly opened listing and make it the floor if it is the cheapest seen
+    ///
+    /// # Arguments
+    /// * `listing` - The listing account
+    /// * `price` - Its price per token, None for Dutch listings whose price still declines
+    pub fn listing_opened(&mut self, listing: Pubkey, price: Option<u64>) {
+        self.active_listings = self.active_listings.saturating_add(1);
+        if let Some(price) = price {
+            self.offer_floor(listing, price);
+        }
+    }
+
+    /// Count a listing that was sold out or delisted
+    /// - Forgets the floor when it was this listing's, as the next cheapest one is unknown
+    pub fn listing_closed(&mut self, listing: Pubkey) {
+        self.active_listings = self.active_listings.saturating_sub(1);
+        if self.floor_listing == Some(listing) {
+            self.floor_price = None;
+            self.floor_listing = None;
+        }
+    }
+
+    /// Lower the floor to a listing's price when it is cheaper or no floor is known
+    pub fn offer_floor(&mut self, listing: Pubkey, price: u64) {
+        if !matches!(self.floor_price, Some(floor) if floor <= price) {
+            self.floor_price = Some(price);
+            self.floor_listing = Some(listing);
+        }
+    }
+
+    /// Forget the floor, so the next candidate replaces it even if more expensive
+    pub fn clear_floor(&mut self) {
+        self.floor_price = None;
+        self.floor_listing = None;
+    }
+
+    /// Count a completed sale
+    /// - Saturates instead of failing, so statistics never block a sale
+    ///
+    /// # Arguments
+    /// * `price` - The total price paid
+    /// * `price_per_token` - The price of one token, kept as the last sale price
+    /// * `now` - Unix timestamp of the sale
+    pub fn record_sale(&mut self, price: u64, price_per_token: u64, now: i64) {
+        self.last_sale_price = Some(price_per_token);
+        self.last_sale_ts = now;
+        self.total_volume = self.total_volume.saturating_add(price as u128);
+        self.sales_count = self.sales_count.saturating_add(1);
+
+        let hour = now.div_euclid(VOLUME_BUCKET_SECS);
+        let bucket = &mut self.volume_buckets[hour.rem_euclid(VOLUME_BUCKET_COUNT as i64) as usize];
+        if bucket.hour != hour {
+            *bucket = VolumeBucket { hour, volume: 0 };
+        }
+        bucket.volume = bucket.volume.saturating_add(price);
+    }
+
+    /// Sale volume of the current hour and the VOLUME_BUCKET_COUNT - 1 hours before it
+    pub fn rolling_volume(&self, now: i64) -> u64 {
+        let hour = now.div_euclid(VOLUME_BUCKET_SECS);
+        self.volume_buckets
+            .iter()
+            .filter(|bucket| bucket.hour > hour - VOLUME_BUCKET_COUNT as i64 && bucket.hour <= hour)
+            .fold(0u64, |total, bucket| total.saturating_add(bucket.volume))
+    }
+
+    /// The statistics exposed to frontends
+    pub fn summary(&self, now: i64) -> CollectionSummary {
+        CollectionSummary {
+            collection: self.collection,
+            floor_price: self.floor_price,
+            floor_listing: self.floor_listing,
+            active_listings: self.active_listings,
+            last_sale_price: self.last_sale_price,
+            last_sale_ts: self.last_sale_ts,
+            rolling_volume: self.rolling_volume(now),
+            total_volume: self.total_volume,
+            sales_count: self.sales_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = VOLUME_BUCKET_SECS;
+
+    fn stats() -> CollectionStats {
+        CollectionStats::new(Pubkey::default(), Pubkey::new_unique(), 255)
+    }
+
+    #[test]
+    fn cheaper_listings_lower_the_floor() {
+        let mut stats = stats();
+        let (first, second, third) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        stats.listing_opened(first, Some(100));
+        stats.listing_opened(second, Some(150));
+        assert_eq!(stats.floor_price, Some(100));
+        stats.listing_opened(third, Some(80));
+        assert_eq!((stats.floor_price, stats.floor_listing), (Some(80), Some(third)));
+        assert_eq!(stats.active_listings, 3);
+    }
+
+    #[test]
+    fn dutch_listings_do_not_set_the_floor() {
+        let mut stats = stats();
+        stats.listing_opened(Pubkey::new_unique(), None);
+        assert_eq!(stats.floor_price, None);
+        assert_eq!(stats.active_listings, 1);
+    }
+
+    #[test]
+    fn closing_the_floor_listing_forgets_the_floor() {
+        let mut stats = stats();
+        let (floor, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        stats.listing_opened(floor, Some(100));
+        stats.listing_opened(other, Some(150));
+        stats.listing_closed(other);
+        assert_eq!(stats.floor_price, Some(100));
+        stats.listing_closed(floor);
+        assert_eq!((stats.floor_price, stats.floor_listing), (None, None));
+        // The next listing becomes the floor even though it is more expensive
+        stats.listing_opened(Pubkey::new_unique(), Some(200));
+        assert_eq!(stats.floor_price, Some(200));
+    }
+
+    #[test]
+    fn rolling_volume_drops_sales_older_than_a_day() {
+        let mut stats = stats();
+        let start = 1_700_000_000 / HOUR * HOUR;
+        stats.record_sale(100, 100, start);
+        stats.record_sale(50, 25, start + HOUR / 2);
+        stats.record_sale(200, 200, start + 5 * HOUR);
+        assert_eq!(stats.rolling_volume(start + 5 * HOUR), 350);
+        assert_eq!(stats.rolling_volume(start + VOLUME_BUCKET_COUNT as i64 * HOUR), 200);
+        assert_eq!(stats.rolling_volume(start + (VOLUME_BUCKET_COUNT as i64 + 5) * HOUR), 0);
+        assert_eq!(stats.last_sale_price, Some(200));
+        assert_eq!(stats.total_volume, 350);
+        assert_eq!(stats.sales_count, 3);
+    }
+
+    #[test]
+    fn reused_buckets_are_reset() {
+        let mut stats = stats();
+        let start = 1_700_000_000 / HOUR * HOUR;
+        stats.record_sale(100, 100, start);
+        // Same slot, one full cycle later
+        let later = start + VOLUME_BUCKET_COUNT as i64 * HOUR;
+        stats.record_sale(30, 30, later);
+        assert_eq!(stats.rolling_volume(later), 30);
+        assert_eq!(stats.total_volume, 130);
+    }
+}
@@ -0,0 +1,143 @@
+use anchor_lang::prelude::*;
+
+use crate::error::MarketplaceError;
+
+#[account]
+#[derive(InitSpace)]
+pub struct InstallmentPlan {
+    /// The marketplace the listing is on
+    pub marketplace: Pubkey,
+
+    /// The listing being bought, locked to the buyer while the plan runs
+    pub listing: Pubkey,
+
+    /// The buyer paying the installments and receiving the NFT after the last one
+    pub buyer: Pubkey,
+
+    /// The seller who listed the NFT
+    pub seller: Pubkey,
+
+    /// Total price of the listing when the plan started, in lamports
+    pub price: u64,
+
+    /// Number of installments the price is paid in, the first one at start
+    pub num_payments: u8,
+
+    /// Number of installments paid so far
+    pub payments_made: u8,
+
+    /// Seconds between two installment due dates
+    pub interval_secs: u32,
+
+    /// Unix timestamp at which the plan started and the first installment was paid
+    pub start_ts: i64,
+
+    /// Lamports paid so far, held on this account on top of its rent until payout
+    pub paid: u64,
+
+    /// PDA bump seed for this plan account
+    /// Used for deterministic address generation
+    pub bump: u8,
+}
+
+impl InstallmentPlan {
+    /// The amount of the next installment
+    /// - The price is split evenly; the last installment also pays the rounding remainder
+    pub fn next_installment(&self) -> u64 {
+        if self.payments_made + 1 >= self.num_payments {
+            self.price - self.paid
+        } else {
+            self.price / self.num_payments as u64
+        }
+    }
+
+    /// Unix timestamp by which the next installment is due
+    pub fn next_due_ts(&self) -> i64 {
+        self.start_ts + self.payments_made as i64 * self.interval_secs as i64
+    }
+
+    /// Whether the next installment is overdue by more than the grace period
+    ///
+    /// # Arguments
+    /// * `now` - The current unix timestamp
+    /// * `grace_secs` - The marketplace's installment grace period
+    pub fn is_defaulted(&self, now: i64, grace_secs: u32) -> bool {
+        !self.is_complete() && now > self.next_due_ts() + grace_secs as i64
+    }
+
+    /// Whether every installment has been paid
+    pub fn is_complete(&self) -> bool {
+        self.payments_made >= self.num_payments
+    }
+
+    /// Count a paid installment
+    ///
+    /// # Arguments
+    /// * `amount` - The lamports just transferred onto the plan
+    pub fn record_payment(&mut self, amount: u64) -> Result<()> {
+        self.paid = self
+            .paid
+            .checked_add(amount)
+            .ok_or(MarketplaceError::MathOverflow)?;
+        self.payments_made += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(price: u64, num_payments: u8) -> InstallmentPlan {
+        InstallmentPlan {
+            marketplace: Pubkey::default(),
+            listing: Pubkey::default(),
+            buyer: Pubkey::default(),
+            seller: Pubkey::default(),
+            price,
+            num_payments,
+            payments_made: 0,
+            interval_secs: 100,
+            start_ts: 1_000,
+            paid: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn installments_add_up_to_the_price() {
+        let mut plan = plan(1_000, 3);
+        let mut amounts = Vec::new();
+        while !plan.is_complete() {
+            let amount = plan.next_installment();
+            amounts.push(amount);
+            plan.record_payment(amount).unwrap();
+        }
+        assert_eq!(amounts, vec![333, 333, 334]);
+        assert_eq!(plan.paid, 1_000);
+    }
+
+    #[test]
+    fn installments_fall_due_one_interval_apart() {
+        let mut plan = plan(1_000, 3);
+        plan.record_payment(333).unwrap();
+        assert_eq!(plan.next_due_ts(), 1_100);
+        plan.record_payment(333).unwrap();
+        assert_eq!(plan.next_due_ts(), 1_200);
+    }
+
+    #[test]
+    fn plans_default_only_after_the_grace_period() {
+        let mut plan = plan(1_000, 3);
+        plan.record_payment(333).unwrap();
+        assert!(!plan.is_defaulted(1_100, 50));
+        assert!(!plan.is_defaulted(1_150, 50));
+        assert!(plan.is_defaulted(1_151, 50));
+        assert!(plan.is_defaulted(1_101, 0));
+
+        // Completed plans cannot default
+        plan.record_payment(333).unwrap();
+        plan.record_payment(334).unwrap();
+        assert!(!plan.is_defaulted(i64::MAX / 2, 0));
+    }
+}
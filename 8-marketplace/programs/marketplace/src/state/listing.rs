@@ -1,15 +1,21 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+
+use crate::error::MarketplaceError;
 
 #[account]
 #[derive(InitSpace)]
 pub struct Listing {
     /// The seller's public key who listed the NFT
     pub seller: Pubkey,
+
+    /// The marketplace the listing was made on
+    pub marketplace: Pubkey,
     
     /// The mint address of the NFT being sold
     pub mint: Pubkey,
     
-    /// The listing price in lamports
+    /// The price per token in lamports, or in payment token base units
+    /// when the marketplace has a payment mint
     pub price: u64,
     
     /// PDA bump seed for this listing account
@@ -19,4 +25,526 @@ pub struct Listing {
     /// Whether this listing is currently active
     /// Set to false when purchased or delisted
     pub is_active: bool,
-}
\ No newline at end of file
+
+    /// The listing's category, one of the `ListingCategory` values
+    /// Kept ahead of the variable-length fields, at `CATEGORY_OFFSET`, for memcmp filters
+    pub category: u8,
+
+    /// The verified collection mint of the NFT
+    /// None when the NFT does not belong to a collection
+    pub collection: Option<Pubkey>,
+
+    /// Unix timestamp from which the listing can no longer be purchased
+    /// 0 means the listing never expires
+    pub expiry: i64,
+
+    /// The price schedule of a Dutch listing
+    /// None for fixed-price listings, which are charged `price`
+    pub dutch: Option<DutchPricing>,
+
+    /// Whether the NFT stays in the seller's wallet, delegated to and frozen by this listing
+    /// False when the NFT is held in the listing's vault
+    pub escrowless: bool,
+
+    /// Tokens still for sale
+    /// 1 for NFTs; semi-fungible listings count down as units are purchased
+    pub quantity: u64,
+
+    /// Whether the NFT is a programmable NFT
+    /// Its vault moves go through Token Metadata transfers instead of SPL transfers
+    pub programmable: bool,
+
+    /// The only wallet allowed to buy a private listing
+    /// None when anyone can buy
+    pub allowed_buyer: Option<Pubkey>,
+
+    /// Seconds the seller proceeds of a purchase stay in a sale escrow, open to buyer disputes
+    /// 0 pays the seller immediately
+    pub protection_window_secs: u32,
+
+    /// Unix timestamp from which the listing can be purchased
+    /// 0 opens the sale immediately
+    pub start_ts: i64,
+
+    /// The seller's listing nonce when the listing was opened, part of its PDA seeds
+    pub nonce: u64,
+
+    /// A second price per token the seller also accepts, in an SPL token such as USDC
+    /// None when the listing only sells in the marketplace currency
+    pub secondary_price: Option<SecondaryPrice>,
+
+    /// SHA-256 of the NFT's metadata name, symbol and URI when it was listed
+    /// Purchases compare it with the live metadata, so the NFT cannot be swapped after listing
+    pub metadata_hash: [u8; 32],
+
+    /// The amount of the highest open offer on the listing, in lamports
+    /// 0 when no offer is tracked
+    pub best_offer_amount: u64,
+
+    /// The offer account holding `best_offer_amount`
+    /// `Pubkey::default()` when no offer is tracked
+    pub best_offer: Pubkey,
+
+    /// Position of the listing in the marketplace's listing index
+    /// None for listings that were never indexed
+    pub index_position: Option<u64>,
+}
+
+/// A price in an SPL token other than the marketplace currency
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct SecondaryPrice {
+    /// The SPL token mint the price is paid in
+    pub mint: Pubkey,
+
+    /// The price per token, in base units of the mint
+    pub amount: u64,
+}
+
+/// Linear price decay of a Dutch listing
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct DutchPricing {
+    /// The price at `start_ts`
+    pub start_price: u64,
+
+    /// The price from `end_ts` on
+    pub floor_price: u64,
+
+    /// Unix timestamp at which the listing can first be purchased
+    pub start_ts: i64,
+
+    /// Unix timestamp at which the price reaches the floor
+    pub end_ts: i64,
+}
+
+impl DutchPricing {
+    /// The price at the given unix timestamp
+    /// - Declines linearly from `start_price` to `floor_price` between `start_ts` and `end_ts`
+    ///
+    /// # Returns
+    /// * `Result<u64>` - The price, or `DutchNotStarted` before `start_ts`
+    pub fn price_at(&self, now: i64) -> Result<u64> {
+        require!(now >= self.start_ts, MarketplaceError::DutchNotStarted);
+        if now >= self.end_ts {
+            return Ok(self.floor_price);
+        }
+
+        // start - (start - floor) * elapsed / duration, rounded in the seller's favour
+        let drop = (self.start_price - self.floor_price) as u128;
+        let elapsed = (now - self.start_ts) as u128;
+        let duration = (self.end_ts - self.start_ts) as u128;
+        let decayed = drop
+            .checked_mul(elapsed)
+            .ok_or(MarketplaceError::MathOverflow)?
+            / duration;
+
+        Ok(self.start_price - decayed as u64)
+    }
+}
+
+impl Listing {
+    /// Byte offset of `category` in the account data, after the discriminator and fixed-size fields
+    pub const CATEGORY_OFFSET: usize = 8 + 32 + 32 + 32 + 8 + 1 + 1;
+
+    /// Hash the metadata fields a buyer judges an NFT by
+    /// - Every field is length-prefixed, so moving bytes between fields changes the hash
+    ///
+    /// # Arguments
+    /// * `name` - The metadata name
+    /// * `symbol` - The metadata symbol
+    /// * `uri` - The metadata URI, pointing at the image and attributes
+    pub fn hash_metadata(name: &str, symbol: &str, uri: &str) -> [u8; 32] {
+        let (name_len, symbol_len, uri_len) = (
+            (name.len() as u32).to_le_bytes(),
+            (symbol.len() as u32).to_le_bytes(),
+            (uri.len() as u32).to_le_bytes(),
+        );
+        hashv(&[
+            &name_len,
+            name.as_bytes(),
+            &symbol_len,
+            symbol.as_bytes(),
+            &uri_len,
+            uri.as_bytes(),
+        ])
+        .to_bytes()
+    }
+
+    /// Whether the listing has expired at the given unix timestamp
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expiry != 0 && now >= self.expiry
+    }
+
+    /// Whether the sale has opened at the given unix timestamp
+    pub fn has_started(&self, now: i64) -> bool {
+        now >= self.start_ts
+    }
+
+    /// Unix timestamp at which the listing can first be purchased, for display
+    pub fn sale_start(&self) -> i64 {
+        self.dutch.map_or(self.start_ts, |dutch| dutch.start_ts)
+    }
+
+    /// Whether `buyer` may purchase or make offers on the listing
+    pub fn can_buy(&self, buyer: &Pubkey) -> bool {
+        self.allowed_buyer.is_none() || self.allowed_buyer == Some(*buyer)
+    }
+
+    /// Whether purchases park the seller proceeds in a sale escrow
+    pub fn is_protected(&self) -> bool {
+        self.protection_window_secs > 0
+    }
+
+    /// The price per token a purchase is charged at the given unix timestamp
+    pub fn current_price(&self, now: i64) -> Result<u64> {
+        match self.dutch {
+            Some(dutch) => dutch.price_at(now),
+            None => Ok(self.price),
+        }
+    }
+
+    /// Whether the listing can also be sold for the given secondary price
+    /// - The mint must differ from the marketplace's own currency
+    /// - Dutch prices only decay in the marketplace currency
+    /// - Sale escrows of protected listings only hold lamports
+    ///
+    /// # Arguments
+    /// * `secondary` - The proposed secondary price
+    /// * `payment_mint` - The marketplace payment mint, None for SOL
+    pub fn accepts_secondary_price(
+        &self,
+        secondary: &SecondaryPrice,
+        payment_mint: Option<Pubkey>,
+    ) -> bool {
+        secondary.amount > 0
+            && payment_mint != Some(secondary.mint)
+            && self.dutch.is_none()
+            && !self.is_protected()
+    }
+
+    /// The secondary price of `amount` tokens
+    ///
+    /// # Returns
+    /// * `Result<(Pubkey, u64)>` - The secondary mint and the total in its base units
+    pub fn total_secondary_price(&self, amount: u64) -> Result<(Pubkey, u64)> {
+        let secondary = self.secondary_price.ok_or(MarketplaceError::NoSecondaryPrice)?;
+        require!(
+            amount > 0 && amount <= self.quantity,
+            MarketplaceError::InsufficientQuantity
+        );
+
+        let total = (secondary.amount as u128)
+            .checked_mul(amount as u128)
+            .ok_or(MarketplaceError::MathOverflow)?;
+
+        Ok((
+            secondary.mint,
+            u64::try_from(total).map_err(|_| error!(MarketplaceError::MathOverflow))?,
+        ))
+    }
+
+    /// The price of `amount` tokens at the given unix timestamp
+    ///
+    /// # Returns
+    /// * `Result<u64>` - The total, or `InsufficientQuantity` if more than the remaining quantity is requested
+    pub fn total_price(&self, now: i64, amount: u64) -> Result<u64> {
+        require!(
+            amount > 0 && amount <= self.quantity,
+            MarketplaceError::InsufficientQuantity
+        );
+
+        let total = (self.current_price(now)? as u128)
+            .checked_mul(amount as u128)
+            .ok_or(MarketplaceError::MathOverflow)?;
+
+        u64::try_from(total).map_err(|_| error!(MarketplaceError::MathOverflow))
+    }
+
+    /// The price per token anyone can buy the listing at, a collection floor candidate
+    /// - None for inactive, expired, private or not yet opened listings
+    pub fn floor_candidate_price(&self, now: i64) -> Option<u64> {
+        if !self.is_active || self.is_expired(now) || self.allowed_buyer.is_some() {
+            return None;
+        }
+        self.current_price(now).ok().filter(|_| self.has_started(now))
+    }
+
+    /// Track an offer as the best offer if it beats the current one
+    /// - Ties keep the earlier offer
+    pub fn offer_made(&mut self, offer: Pubkey, amount: u64) {
+        if amount > self.best_offer_amount {
+            self.best_offer_amount = amount;
+            self.best_offer = offer;
+        }
+    }
+
+    /// Stop tracking an offer that is being closed
+    ///
+    /// # Returns
+    /// * `bool` - Whether it was the best offer, leaving the listing without one
+    pub fn offer_closed(&mut self, offer: &Pubkey) -> bool {
+        if self.best_offer_amount == 0 || self.best_offer != *offer {
+            return false;
+        }
+        self.best_offer_amount = 0;
+        self.best_offer = Pubkey::default();
+        true
+    }
+
+    /// Whether `offer` is the tracked best offer, for exactly `amount`
+    pub fn is_best_offer(&self, offer: &Pubkey, amount: u64) -> bool {
+        self.best_offer_amount > 0 && self.best_offer == *offer && self.best_offer_amount == amount
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+
+    fn listing(expiry: i64) -> Listing {
+        Listing {
+            seller: Pubkey::default(),
+            marketplace: Pubkey::default(),
+            mint: Pubkey::default(),
+            price: 1,
+            bump: 255,
+            is_active: true,
+            category: 0,
+            collection: None,
+            expiry,
+            dutch: None,
+            escrowless: false,
+            quantity: 1,
+            programmable: false,
+            allowed_buyer: None,
+            protection_window_secs: 0,
+            start_ts: 0,
+            nonce: 0,
+            secondary_price: None,
+            metadata_hash: [0; 32],
+            best_offer_amount: 0,
+            best_offer: Pubkey::default(),
+            index_position: None,
+        }
+    }
+
+    fn dutch() -> DutchPricing {
+        DutchPricing {
+            start_price: 10_000,
+            floor_price: 2_000,
+            start_ts: 1_000,
+            end_ts: 1_800,
+        }
+    }
+
+    #[test]
+    fn expires_exactly_at_the_expiry_timestamp() {
+        let listing = listing(1_000);
+        assert!(!listing.is_expired(999));
+        assert!(listing.is_expired(1_000));
+        assert!(listing.is_expired(1_001));
+    }
+
+    #[test]
+    fn zero_expiry_never_expires() {
+        assert!(!listing(0).is_expired(i64::MAX));
+    }
+
+    #[test]
+    fn sale_opens_exactly_at_the_start_timestamp() {
+        let mut scheduled = listing(0);
+        scheduled.start_ts = 1_000;
+        assert!(!scheduled.has_started(999));
+        assert!(scheduled.has_started(1_000));
+        assert!(scheduled.has_started(1_001));
+    }
+
+    #[test]
+    fn zero_start_is_open_immediately() {
+        assert!(listing(0).has_started(0));
+        assert_eq!(listing(0).sale_start(), 0);
+    }
+
+    #[test]
+    fn dutch_listings_start_with_their_schedule() {
+        let mut dutch_listing = listing(0);
+        dutch_listing.dutch = Some(dutch());
+        assert_eq!(dutch_listing.sale_start(), 1_000);
+    }
+
+    #[test]
+    fn dutch_price_decays_linearly() {
+        let dutch = dutch();
+        assert_eq!(dutch.price_at(1_000).unwrap(), 10_000);
+        assert_eq!(dutch.price_at(1_200).unwrap(), 8_000);
+        assert_eq!(dutch.price_at(1_400).unwrap(), 6_000);
+        assert_eq!(dutch.price_at(1_799).unwrap(), 2_010);
+        assert_eq!(dutch.price_at(1_800).unwrap(), 2_000);
+    }
+
+    #[test]
+    fn dutch_price_rounds_in_the_sellers_favour() {
+        let dutch = DutchPricing { start_price: 10, floor_price: 0, start_ts: 0, end_ts: 3 };
+        assert_eq!(dutch.price_at(1).unwrap(), 7);
+        assert_eq!(dutch.price_at(2).unwrap(), 4);
+    }
+
+    #[test]
+    fn dutch_price_is_the_floor_after_the_end() {
+        assert_eq!(dutch().price_at(1_000_000).unwrap(), 2_000);
+    }
+
+    #[test]
+    fn dutch_price_is_unavailable_before_the_start() {
+        assert!(dutch().price_at(999).is_err());
+    }
+
+    #[test]
+    fn fixed_listings_charge_their_price() {
+        assert_eq!(listing(0).current_price(0).unwrap(), 1);
+
+        let mut dutch_listing = listing(0);
+        dutch_listing.dutch = Some(dutch());
+        assert_eq!(dutch_listing.current_price(1_400).unwrap(), 6_000);
+    }
+
+    #[test]
+    fn total_price_multiplies_the_unit_price() {
+        let mut sft = listing(0);
+        sft.price = 2_500;
+        sft.quantity = 10;
+        assert_eq!(sft.total_price(0, 1).unwrap(), 2_500);
+        assert_eq!(sft.total_price(0, 10).unwrap(), 25_000);
+    }
+
+    #[test]
+    fn total_price_rejects_zero_and_more_than_remains() {
+        let mut sft = listing(0);
+        sft.quantity = 3;
+        assert!(sft.total_price(0, 0).is_err());
+        assert!(sft.total_price(0, 4).is_err());
+    }
+
+    #[test]
+    fn total_price_overflow_is_an_error() {
+        let mut sft = listing(0);
+        sft.price = u64::MAX;
+        sft.quantity = 2;
+        assert!(sft.total_price(0, 2).is_err());
+    }
+
+    #[test]
+    fn public_listings_can_be_bought_by_anyone() {
+        assert!(listing(0).can_buy(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn private_listings_can_only_be_bought_by_the_allowed_buyer() {
+        let buyer = Pubkey::new_unique();
+        let mut private = listing(0);
+        private.allowed_buyer = Some(buyer);
+        assert!(private.can_buy(&buyer));
+        assert!(!private.can_buy(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn secondary_prices_need_another_currency_and_a_plain_listing() {
+        let usdc = Pubkey::new_unique();
+        let secondary = SecondaryPrice { mint: usdc, amount: 1_000 };
+        assert!(listing(0).accepts_secondary_price(&secondary, None));
+        assert!(!listing(0).accepts_secondary_price(&secondary, Some(usdc)));
+        assert!(!listing(0).accepts_secondary_price(&SecondaryPrice { amount: 0, ..secondary }, None));
+
+        let mut protected = listing(0);
+        protected.protection_window_secs = 60;
+        assert!(!protected.accepts_secondary_price(&secondary, None));
+
+        let mut dutch = listing(0);
+        dutch.dutch = Some(DutchPricing { start_price: 10, floor_price: 5, start_ts: 0, end_ts: 10 });
+        assert!(!dutch.accepts_secondary_price(&secondary, None));
+    }
+
+    #[test]
+    fn metadata_hash_changes_with_any_field() {
+        let listed = Listing::hash_metadata("Rare #1", "RARE", "https://example.com/1.json");
+        assert_eq!(listed, Listing::hash_metadata("Rare #1", "RARE", "https://example.com/1.json"));
+        assert_ne!(listed, Listing::hash_metadata("Rare #1", "RARE", "https://example.com/2.json"));
+        assert_ne!(listed, Listing::hash_metadata("Rare #2", "RARE", "https://example.com/1.json"));
+        assert_ne!(listed, Listing::hash_metadata("Rare #1", "RAR", "https://example.com/1.json"));
+    }
+
+    #[test]
+    fn metadata_hash_keeps_fields_apart() {
+        assert_ne!(Listing::hash_metadata("ab", "c", ""), Listing::hash_metadata("a", "bc", ""));
+    }
+
+    #[test]
+    fn total_secondary_price_multiplies_the_unit_amount() {
+        let usdc = Pubkey::new_unique();
+        let mut sft = listing(0);
+        sft.quantity = 4;
+        assert!(sft.total_secondary_price(1).is_err());
+
+        sft.secondary_price = Some(SecondaryPrice { mint: usdc, amount: 2_500 });
+        assert_eq!(sft.total_secondary_price(4).unwrap(), (usdc, 10_000));
+        assert!(sft.total_secondary_price(5).is_err());
+
+        sft.secondary_price = Some(SecondaryPrice { mint: usdc, amount: u64::MAX });
+        assert!(sft.total_secondary_price(2).is_err());
+    }
+
+    #[test]
+    fn only_public_open_listings_are_floor_candidates() {
+        let mut listing = listing(2_000);
+        assert_eq!(listing.floor_candidate_price(1_000), Some(1));
+        assert_eq!(listing.floor_candidate_price(2_000), None);
+
+        listing.allowed_buyer = Some(Pubkey::new_unique());
+        assert_eq!(listing.floor_candidate_price(1_000), None);
+        listing.allowed_buyer = None;
+
+        listing.start_ts = 1_500;
+        assert_eq!(listing.floor_candidate_price(1_000), None);
+        listing.start_ts = 0;
+
+        // Dutch listings compete at their current price once the decline started
+        listing.dutch = Some(dutch());
+        assert_eq!(listing.floor_candidate_price(999), None);
+        assert_eq!(listing.floor_candidate_price(1_800), Some(2_000));
+
+        listing.is_active = false;
+        assert_eq!(listing.floor_candidate_price(1_800), None);
+    }
+
+    #[test]
+    fn category_sits_at_its_fixed_offset() {
+        let mut categorized = listing(0);
+        categorized.category = 3;
+        categorized.collection = Some(Pubkey::new_unique());
+        let mut data = Listing::DISCRIMINATOR.to_vec();
+        categorized.serialize(&mut data).unwrap();
+        assert_eq!(data[Listing::CATEGORY_OFFSET], 3);
+    }
+
+    #[test]
+    fn tracks_the_highest_offer() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut listing = listing(0);
+        assert!(!listing.is_best_offer(&Pubkey::default(), 0));
+
+        listing.offer_made(first, 500);
+        listing.offer_made(second, 500);
+        assert!(listing.is_best_offer(&first, 500));
+        listing.offer_made(second, 400);
+        assert!(listing.is_best_offer(&first, 500));
+        assert!(!listing.is_best_offer(&first, 400));
+
+        listing.offer_made(second, 600);
+        assert!(listing.is_best_offer(&second, 600));
+        assert!(!listing.offer_closed(&first));
+        assert!(listing.offer_closed(&second));
+        assert_eq!(listing.best_offer_amount, 0);
+        assert_eq!(listing.best_offer, Pubkey::default());
+        assert!(!listing.offer_closed(&second));
+    }
+}
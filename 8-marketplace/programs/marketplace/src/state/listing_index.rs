@@ -0,0 +1,327 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::LISTING_INDEX_PAGE_SIZE,
+    error::MarketplaceError,
+    state::{Listing, Marketplace},
+};
+
+/// One page of a marketplace's index of open listings
+/// - Position `p` of the index lives on page `p / LISTING_INDEX_PAGE_SIZE`; every page but the
+///   last is full
+/// - Every instruction closing a listing takes its entry out; listings opened before the index
+///   existed were never added
+#[account]
+#[derive(InitSpace)]
+pub struct ListingIndex {
+    /// The marketplace the index belongs to
+    pub marketplace: Pubkey,
+
+    /// Number of the page, part of its PDA seeds
+    pub page: u64,
+
+    /// PDA bump seed for this page
+    /// Used for deterministic address generation
+    pub bump: u8,
+
+    /// Addresses of the listings at the page's positions, in position order
+    #[max_len(LISTING_INDEX_PAGE_SIZE)]
+    pub listings: Vec<Pubkey>,
+}
+
+/// A page of the listing index returned by `get_listing_page`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ListingPage {
+    pub page: u64,
+    pub listings: Vec<Pubkey>,
+    /// Entries in the whole index, telling how many pages follow
+    pub total_listings: u64,
+}
+
+impl ListingIndex {
+    /// An empty page
+    ///
+    /// # Arguments
+    /// * `marketplace` - The marketplace the index belongs to
+    /// * `page` - Number of the page
+    /// * `bump` - PDA bump seed of the page
+    pub fn new(marketplace: Pubkey, page: u64, bump: u8) -> Self {
+        Self {
+            marketplace,
+            page,
+            bump,
+            listings: Vec::new(),
+        }
+    }
+
+    /// Whether the account was just created by `init_if_needed` and still needs `new`
+    pub fn is_uninitialized(&self) -> bool {
+        self.marketplace == Pubkey::default()
+    }
+
+    /// The page holding an index position
+    pub fn page_of(position: u64) -> u64 {
+        position / LISTING_INDEX_PAGE_SIZE as u64
+    }
+
+    /// The slot of an index position within its page
+    pub fn slot_of(position: u64) -> usize {
+        (position % LISTING_INDEX_PAGE_SIZE as u64) as usize
+    }
+
+    /// Append a listing at the end of the index
+    ///
+    /// # Arguments
+    /// * `len` - The number of entries in the whole index, which must end on this page
+    /// * `listing` - The listing to append
+    ///
+    /// # Returns
+    /// * `Result<u64>` - The position the listing was appended at
+    pub fn push(&mut self, len: u64, listing: Pubkey) -> Result<u64> {
+        require!(
+            self.page == Self::page_of(len) && self.listings.len() == Self::slot_of(len),
+            MarketplaceError::InvalidListingIndex
+        );
+
+        self.listings.push(listing);
+        Ok(len)
+    }
+
+    /// Remove the entry at a position of this page, moving the index's last entry into its slot
+    /// - Positions of every other entry stay the same
+    ///
+    /// # Arguments
+    /// * `last_page` - The page of the last entry, only when it is not this page
+    /// * `position` - The position to remove
+    /// * `len` - The number of entries in the whole index
+    /// * `listing` - The listing expected at `position`
+    ///
+    /// # Returns
+    /// * `Result<Option<Pubkey>>` - The listing now at `position`, None when the last entry was removed
+    pub fn swap_remove(
+        &mut self,
+        last_page: Option<&mut ListingIndex>,
+        position: u64,
+        len: u64,
+        listing: &Pubkey,
+    ) -> Result<Option<Pubkey>> {
+        let slot = Self::slot_of(position);
+        let last = len.checked_sub(1).ok_or(MarketplaceError::InvalidListingIndex)?;
+        require!(
+            position <= last
+                && self.page == Self::page_of(position)
+                && self.listings.get(slot) == Some(listing),
+            MarketplaceError::InvalidListingIndex
+        );
+
+        if Self::page_of(last) == self.page {
+            // Passing this page twice would write it back twice
+            require!(
+                last_page.is_none() && self.listings.len() == Self::slot_of(last) + 1,
+                MarketplaceError::InvalidListingIndex
+            );
+            self.listings.swap_remove(slot);
+            return Ok(self.listings.get(slot).copied());
+        }
+
+        let last_page = last_page.ok_or(MarketplaceError::InvalidListingIndex)?;
+        require!(
+            last_page.marketplace == self.marketplace
+                && last_page.page == Self::page_of(last)
+                && last_page.listings.len() == Self::slot_of(last) + 1,
+            MarketplaceError::InvalidListingIndex
+        );
+        let moved = last_page
+            .listings
+            .pop()
+            .ok_or(MarketplaceError::InvalidListingIndex)?;
+        self.listings[slot] = moved;
+        Ok(Some(moved))
+    }
+
+    /// Take a closing listing out of its marketplace's index
+    /// - Does nothing for listings that were never indexed
+    /// - The listing moved into the freed position learns its new position; an entry whose
+    ///   listing was closed without leaving the index is moved as is
+    ///
+    /// # Arguments
+    /// * `marketplace` - The marketplace whose index shrinks
+    /// * `listing` - The closing listing
+    /// * `index` - The page holding the listing's entry
+    /// * `last_index` - The page holding the index's last entry, only when it is another page
+    /// * `moved_listing` - The listing of the last entry, only when it is another listing
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn unindex<'info>(
+        marketplace: &mut Marketplace,
+        listing: &mut Account<'info, Listing>,
+        index: Option<&mut Account<'info, ListingIndex>>,
+        last_index: Option<&mut Account<'info, ListingIndex>>,
+        moved_listing: Option<&UncheckedAccount<'info>>,
+    ) -> Result<()> {
+        let Some(position) = listing.index_position else {
+            return Ok(());
+        };
+        let index = index.ok_or(MarketplaceError::InvalidListingIndex)?;
+        require_keys_eq!(
+            index.marketplace,
+            listing.marketplace,
+            MarketplaceError::InvalidListingIndex
+        );
+
+        let moved = index.swap_remove(
+            last_index.map(|page| &mut **page),
+            position,
+            marketplace.listing_index_len,
+            &listing.key(),
+        )?;
+        if let Some(moved) = moved {
+            let moved_info = moved_listing
+                .ok_or(MarketplaceError::InvalidListingIndex)?
+                .to_account_info();
+            require_keys_eq!(moved_info.key(), moved, MarketplaceError::InvalidListingIndex);
+
+            if moved_info.owner == &crate::ID && !moved_info.data_is_empty() {
+                require!(moved_info.is_writable, MarketplaceError::InvalidListingIndex);
+                let mut data = moved_info.try_borrow_mut_data()?;
+                let mut moved_listing = Listing::try_deserialize(&mut &data[..])?;
+                moved_listing.index_position = Some(position);
+                moved_listing.try_serialize(&mut &mut data[..])?;
+            }
+        }
+
+        marketplace.listing_index_len -= 1;
+        listing.index_position = None;
+        Ok(())
+    }
+
+    /// `unindex` for batched instructions, which pass the index accounts of each listing as
+    /// remaining accounts
+    /// - The program id stands in for any of them that is not needed
+    /// - Pages are written back right away, so later listings of the batch see the shrunk index
+    ///
+    /// # Arguments
+    /// * `marketplace` - The marketplace whose index shrinks
+    /// * `listing` - The closing listing
+    /// * `accounts` - The (listing index, last listing index, moved listing) accounts
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn unindex_remaining<'info>(
+        marketplace: &mut Marketplace,
+        listing: &mut Account<'info, Listing>,
+        accounts: &'info [AccountInfo<'info>],
+    ) -> Result<()> {
+        let [index, last_index, moved_listing] = accounts else {
+            return err!(MarketplaceError::InvalidListingIndex);
+        };
+        let passed = |info: &'info AccountInfo<'info>| (info.key() != crate::ID).then_some(info);
+        let load = |info: &'info AccountInfo<'info>| {
+            Account::<ListingIndex>::try_from(info).map_err(|_| MarketplaceError::InvalidListingIndex)
+        };
+
+        let mut index = passed(index).map(load).transpose()?;
+        let mut last_index = passed(last_index).map(load).transpose()?;
+        let moved_listing = passed(moved_listing).map(UncheckedAccount::try_from);
+        Self::unindex(
+            marketplace,
+            listing,
+            index.as_mut(),
+            last_index.as_mut(),
+            moved_listing.as_ref(),
+        )?;
+
+        for page in index.iter().chain(last_index.iter()) {
+            page.exit(&crate::ID)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: u64 = LISTING_INDEX_PAGE_SIZE as u64;
+
+    // Index pages holding `len` fresh listings, with the listing at each position
+    fn filled(len: u64) -> (Vec<ListingIndex>, Vec<Pubkey>) {
+        let marketplace = Pubkey::new_unique();
+        let mut pages = Vec::new();
+        let mut listings = Vec::new();
+        for position in 0..len {
+            if ListingIndex::slot_of(position) == 0 {
+                pages.push(ListingIndex::new(marketplace, ListingIndex::page_of(position), 255));
+            }
+            let listing = Pubkey::new_unique();
+            let page = pages.last_mut().unwrap();
+            assert_eq!(page.push(position, listing).unwrap(), position);
+            listings.push(listing);
+        }
+        (pages, listings)
+    }
+
+    #[test]
+    fn positions_roll_over_to_the_next_page() {
+        assert_eq!((ListingIndex::page_of(0), ListingIndex::slot_of(0)), (0, 0));
+        assert_eq!(ListingIndex::page_of(SIZE - 1), 0);
+        assert_eq!((ListingIndex::page_of(SIZE), ListingIndex::slot_of(SIZE)), (1, 0));
+
+        let (pages, listings) = filled(SIZE + 1);
+        assert_eq!(pages[0].listings, listings[..SIZE as usize]);
+        assert_eq!(pages[1].listings, [listings[SIZE as usize]]);
+    }
+
+    #[test]
+    fn push_only_appends_at_the_end() {
+        let (mut pages, _) = filled(SIZE);
+        // A full page takes no more entries
+        assert!(pages[0].push(SIZE, Pubkey::new_unique()).is_err());
+        assert!(pages[0].push(3, Pubkey::new_unique()).is_err());
+    }
+
+    #[test]
+    fn removing_from_the_middle_moves_the_last_entry_in() {
+        let (mut pages, listings) = filled(5);
+        let moved = pages[0].swap_remove(None, 2, 5, &listings[2]).unwrap();
+        assert_eq!(moved, Some(listings[4]));
+        assert_eq!(pages[0].listings, [listings[0], listings[1], listings[4], listings[3]]);
+
+        // The last entry leaves without moving anything
+        assert_eq!(pages[0].swap_remove(None, 3, 4, &listings[3]).unwrap(), None);
+        assert_eq!(pages[0].listings, [listings[0], listings[1], listings[4]]);
+    }
+
+    #[test]
+    fn removing_from_a_full_page_takes_the_last_entry_of_the_last_page() {
+        let (mut pages, listings) = filled(SIZE + 2);
+        let (first, rest) = pages.split_at_mut(1);
+
+        // The last page must be passed when it is another page
+        assert!(first[0].swap_remove(None, 3, SIZE + 2, &listings[3]).is_err());
+
+        let moved = first[0]
+            .swap_remove(Some(&mut rest[0]), 3, SIZE + 2, &listings[3])
+            .unwrap();
+        let last = listings[SIZE as usize + 1];
+        assert_eq!(moved, Some(last));
+        assert_eq!(first[0].listings[3], last);
+        assert_eq!(first[0].listings.len(), SIZE as usize);
+        assert_eq!(rest[0].listings, [listings[SIZE as usize]]);
+    }
+
+    #[test]
+    fn removal_checks_the_listing_and_page() {
+        let (mut pages, listings) = filled(5);
+        // Another listing than the one at the position
+        assert!(pages[0].swap_remove(None, 2, 5, &listings[1]).is_err());
+        // Past the end of the index
+        assert!(pages[0].swap_remove(None, 5, 5, &listings[4]).is_err());
+        // The same page passed as the last page too
+        let mut copy = ListingIndex::new(pages[0].marketplace, 0, 255);
+        copy.listings = pages[0].listings.clone();
+        assert!(pages[0].swap_remove(Some(&mut copy), 2, 5, &listings[2]).is_err());
+        assert_eq!(pages[0].listings, listings);
+    }
+}
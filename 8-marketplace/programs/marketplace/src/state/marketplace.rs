@@ -1,14 +1,19 @@
 use anchor_lang::prelude::*;
 
+use crate::{
+    constants::{BPS_DENOMINATOR, FEE_TIER_COUNT, MAX_FEE_BPS, MAX_FEE_SPLITS},
+    error::MarketplaceError,
+};
+
 #[account]
 #[derive(InitSpace)]
 pub struct Marketplace {
     /// The admin public key who can manage the marketplace
     pub admin: Pubkey,
     
-    /// Fee percentage charged on each sale (0-100)
-    /// This percentage is taken from the sale price and sent to treasury
-    pub fee_percentage: u8,
+    /// Fee in basis points charged on each sale (0-MAX_FEE_BPS)
+    /// This share is taken from the sale price and sent to treasury
+    pub fee_bps: u16,
     
     /// PDA bump seed for the marketplace account
     /// Used for deterministic address generation
@@ -17,4 +22,943 @@ pub struct Marketplace {
     /// PDA bump seed for the treasury account
     /// Used for deterministic address generation of the treasury
     pub treasury_bump: u8,
-}
\ No newline at end of file
+
+    /// Whether any NFT can be listed
+    /// When false, only NFTs from collections with a CollectionConfig can be listed
+    pub open_listings: bool,
+
+    /// The SPL token mint sales are paid in
+    /// None when sales are paid in native SOL
+    pub payment_mint: Option<Pubkey>,
+
+    /// PDA bump seed for the reward points mint
+    pub rewards_bump: u8,
+
+    /// Reward points minted to the buyer per sale, in basis points of the price
+    /// The seller receives half as many; 0 turns rewards off
+    pub reward_rate_bps: u16,
+
+    /// Whether listing, purchases and offer acceptance are halted
+    /// Delisting stays available so sellers can always recover their NFTs
+    pub paused: bool,
+
+    /// The account sale fees are paid to
+    /// The treasury PDA unless the admin routes fees elsewhere, e.g. to a DAO multisig
+    pub fee_recipient: Pubkey,
+
+    /// Total sale volume, in lamports or payment token base units
+    pub total_volume: u128,
+
+    /// Number of completed sales
+    pub sales_count: u64,
+
+    /// Total fees charged on sales, in the same units as the volume
+    pub total_fees: u64,
+
+    /// Listings currently open on the marketplace
+    pub active_listings: u64,
+
+    /// Name telling apart the marketplaces of one admin, part of the PDA seeds
+    /// At most MAX_MARKETPLACE_NAME_LEN bytes; empty for the admin's original marketplace
+    #[max_len(32)]
+    pub name: String,
+
+    /// Share of the price paid to a purchase's referrer, in basis points (0-MAX_FEE_BPS)
+    /// Carved out of the marketplace fee, never out of the seller's proceeds; 0 turns referrals off
+    pub referral_bps: u16,
+
+    /// Fee discounts for sellers by lifetime volume, in ascending thresholds
+    /// Unset tiers have a zero threshold; all unset charges every seller `fee_bps`
+    pub fee_tiers: [FeeTier; FEE_TIER_COUNT],
+
+    /// Bounty in lamports paid out of an expired offer's escrow to whoever refunds it for the buyer
+    /// At most MAX_CRANK_FEE_LAMPORTS; 0 pays no bounty
+    pub crank_fee_lamports: u64,
+
+    /// Whether SOL sale proceeds are credited to the seller's Proceeds PDA instead of paid out
+    /// Sellers then sweep them with `claim_proceeds`, so sellers that cannot receive still sell
+    pub pull_payments: bool,
+
+    /// Unix timestamp at which the marketplace was initialized
+    /// 0 for marketplaces migrated from before it was recorded
+    pub created_at: i64,
+
+    /// Reward in lamports paid from the treasury to whoever cleans up an expired listing
+    /// At most MAX_CRANK_REWARD_LAMPORTS; 0 pays no reward
+    pub crank_reward_lamports: u64,
+
+    /// Seconds past a missed installment due date before the seller can default the plan
+    pub installment_grace_secs: u32,
+
+    /// Share of the paid installments in basis points forfeited to the seller on default
+    /// The rest is refunded to the buyer; 0 refunds everything
+    pub installment_forfeit_bps: u16,
+
+    /// Reward points a buyer must hold for the fee discount
+    pub discount_threshold: u64,
+
+    /// Basis points taken off the fee rate of buyers holding `discount_threshold` reward points
+    /// At most MAX_FEE_BPS; 0 gives no discount
+    pub discount_bps: u16,
+
+    /// Whether blacklisted wallets are also blocked from purchasing listings
+    /// Blacklisted wallets can never sell, whatever this flag says
+    pub block_blacklisted_buyers: bool,
+
+    /// Recipients sharing the marketplace fee, with shares summing to BPS_DENOMINATOR
+    /// Empty pays the whole fee to `fee_recipient`
+    #[max_len(MAX_FEE_SPLITS)]
+    pub fee_splits: Vec<FeeSplit>,
+
+    /// Longest time in seconds a listing can be extended to run for, counted from the extension
+    /// 0 sets no limit
+    pub max_listing_duration: u32,
+
+    /// Number of entries in the listing index, which fill its pages in order
+    pub listing_index_len: u64,
+
+    /// Lowest price, in the marketplace currency, a listing, auction or offer can be made at
+    /// 0 sets no minimum beyond the non-zero price every sale needs
+    pub min_price: u64,
+
+    /// Highest price, in the marketplace currency, a listing, auction or offer can be made at
+    /// 0 sets no maximum
+    pub max_price: u64,
+}
+
+/// A seller volume threshold and the fee charged from it on
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace)]
+pub struct FeeTier {
+    /// Lifetime seller volume from which the tier applies, 0 when the tier is unset
+    pub min_volume: u64,
+
+    /// Fee in basis points charged once the threshold is reached
+    pub fee_bps: u16,
+}
+
+impl FeeTier {
+    /// Whether the tier takes part in fee lookups
+    pub fn is_set(&self) -> bool {
+        self.min_volume > 0
+    }
+}
+
+/// A recipient of the marketplace fee and its share of it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct FeeSplit {
+    /// The account paid this share of the fee
+    pub recipient: Pubkey,
+
+    /// Share of the fee in basis points of the whole fee
+    pub bps: u16,
+}
+
+/// Marketplace statistics returned by `get_marketplace_stats`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarketplaceStats {
+    pub total_volume: u128,
+    pub sales_count: u64,
+    pub total_fees: u64,
+    pub active_listings: u64,
+}
+
+/// Where every unit of a sale's price goes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PaymentSplit {
+    pub seller_proceeds: u64,
+    pub marketplace_fee: u64,
+    /// Always 0: purchases do not pay creator royalties
+    pub royalty_paid: u64,
+    pub referral_paid: u64,
+}
+
+impl PaymentSplit {
+    /// Sum of all components, `None` on overflow
+    pub fn total(&self) -> Option<u64> {
+        self.seller_proceeds
+            .checked_add(self.marketplace_fee)?
+            .checked_add(self.royalty_paid)?
+            .checked_add(self.referral_paid)
+    }
+
+    /// Everything the marketplace kept or passed on to a referrer
+    pub fn fee(&self) -> u64 {
+        self.marketplace_fee.saturating_add(self.referral_paid)
+    }
+}
+
+impl Marketplace {
+    /// Space of the statistics fields
+    pub const STATS_SPACE: usize = 16 + 8 + 8 + 8;
+
+    /// Space of the name field
+    pub const NAME_SPACE: usize = 4 + 32;
+
+    /// Space of the referral share field
+    pub const REFERRAL_SPACE: usize = 2;
+
+    /// Space of the fee tier table
+    pub const FEE_TIERS_SPACE: usize = FEE_TIER_COUNT * (8 + 2);
+
+    /// Space of the crank fee field
+    pub const CRANK_FEE_SPACE: usize = 8;
+
+    /// Space of the pull-payment flag
+    pub const PULL_PAYMENTS_SPACE: usize = 1;
+
+    /// Space of the creation timestamp
+    pub const CREATED_AT_SPACE: usize = 8;
+
+    /// Space of the crank reward field
+    pub const CRANK_REWARD_SPACE: usize = 8;
+
+    /// Space of the installment grace period and forfeit share
+    pub const INSTALLMENT_TERMS_SPACE: usize = 4 + 2;
+
+    /// Space of the reward holder discount threshold and rate
+    pub const HOLDER_DISCOUNT_SPACE: usize = 8 + 2;
+
+    /// Space of the blacklisted buyer flag
+    pub const BLACKLIST_SPACE: usize = 1;
+
+    /// Space of the fee split list
+    pub const FEE_SPLITS_SPACE: usize = 4 + MAX_FEE_SPLITS * (32 + 2);
+
+    /// Space of the maximum listing duration
+    pub const MAX_LISTING_DURATION_SPACE: usize = 4;
+
+    /// Space of the listing index length
+    pub const LISTING_INDEX_SPACE: usize = 8;
+
+    /// Space of the minimum and maximum prices
+    pub const PRICE_BOUNDS_SPACE: usize = 8 + 8;
+
+    /// Space of the fields at the end of the layout whose zero bytes are a valid starting value:
+    /// empty statistics, the empty name of an admin's original marketplace, no referral share,
+    /// no fee tiers, no crank fee, direct payments, an unknown creation time, no crank reward,
+    /// installment defaults without grace period or forfeit, no holder discount,
+    /// blacklisted wallets free to buy, no fee splits, no limit on listing extensions, an
+    /// empty listing index and no price bounds
+    pub const ZEROED_TAIL_SPACE: usize = Self::STATS_SPACE
+        + Self::NAME_SPACE
+        + Self::REFERRAL_SPACE
+        + Self::FEE_TIERS_SPACE
+        + Self::CRANK_FEE_SPACE
+        + Self::PULL_PAYMENTS_SPACE
+        + Self::CREATED_AT_SPACE
+        + Self::CRANK_REWARD_SPACE
+        + Self::INSTALLMENT_TERMS_SPACE
+        + Self::HOLDER_DISCOUNT_SPACE
+        + Self::BLACKLIST_SPACE
+        + Self::FEE_SPLITS_SPACE
+        + Self::MAX_LISTING_DURATION_SPACE
+        + Self::LISTING_INDEX_SPACE
+        + Self::PRICE_BOUNDS_SPACE;
+
+    /// The bounty for refunding an expired offer on the buyer's behalf
+    /// - Never more than the escrow, so the refund cannot fail for lack of lamports
+    ///
+    /// # Arguments
+    /// * `escrowed` - The lamports escrowed on the offer
+    pub fn crank_bounty(&self, escrowed: u64) -> u64 {
+        self.crank_fee_lamports.min(escrowed)
+    }
+
+    /// The reward for cleaning up an expired listing, paid from the treasury
+    /// - Skipped entirely rather than paid in part when the treasury cannot cover it
+    ///
+    /// # Arguments
+    /// * `available` - Treasury lamports above its rent-exempt minimum
+    pub fn crank_reward(&self, available: u64) -> u64 {
+        if self.crank_reward_lamports <= available {
+            self.crank_reward_lamports
+        } else {
+            0
+        }
+    }
+
+    /// Whether a listing extended at `now` would run past `max_listing_duration`
+    ///
+    /// # Arguments
+    /// * `now` - The unix timestamp of the extension
+    /// * `expiry` - The requested expiry
+    pub fn exceeds_listing_duration(&self, now: i64, expiry: i64) -> bool {
+        self.max_listing_duration != 0
+            && expiry.saturating_sub(now) > self.max_listing_duration as i64
+    }
+
+    /// Whether a price lies within `min_price` and `max_price`, both inclusive
+    ///
+    /// # Arguments
+    /// * `price` - The price in the marketplace currency
+    pub fn price_in_bounds(&self, price: u64) -> bool {
+        price >= self.min_price && (self.max_price == 0 || price <= self.max_price)
+    }
+
+    /// The part of a defaulted installment plan's payments kept for the seller
+    ///
+    /// # Arguments
+    /// * `paid` - The lamports the buyer paid into the plan
+    pub fn installment_forfeit(&self, paid: u64) -> Result<u64> {
+        let forfeit = (paid as u128)
+            .checked_mul(self.installment_forfeit_bps as u128)
+            .ok_or(MarketplaceError::MathOverflow)?
+            / BPS_DENOMINATOR as u128;
+
+        // installment_forfeit_bps is capped at 100%, so the forfeit always fits in the payments
+        u64::try_from(forfeit).map_err(|_| error!(MarketplaceError::MathOverflow))
+    }
+
+    /// The fee rate charged to a buyer after the reward holder discount
+    /// - Holders of at least `discount_threshold` reward points pay `discount_bps` less, never below zero
+    ///
+    /// # Arguments
+    /// * `fee_bps` - The fee rate before the discount, as returned by `fee_bps_for_volume`
+    /// * `reward_balance` - The buyer's reward points before the sale
+    ///
+    /// # Returns
+    /// * `(u16, u16)` - The discounted fee rate and the discount taken off it
+    pub fn holder_fee_bps(&self, fee_bps: u16, reward_balance: u64) -> (u16, u16) {
+        if self.discount_bps == 0 || reward_balance < self.discount_threshold {
+            return (fee_bps, 0);
+        }
+        let discount = self.discount_bps.min(fee_bps);
+        (fee_bps - discount, discount)
+    }
+
+    /// Calculate the marketplace fee taken from a sale
+    ///
+    /// # Arguments
+    /// * `amount` - The sale amount in lamports or payment token base units
+    ///
+    /// # Returns
+    /// * `Result<u64>` - The fee sent to the treasury, in the same units
+    pub fn fee_for(&self, amount: u64) -> Result<u64> {
+        Self::fee_at(amount, self.fee_bps)
+    }
+
+    /// Calculate the fee taken from a sale at the given rate
+    fn fee_at(amount: u64, fee_bps: u16) -> Result<u64> {
+        let fee = (amount as u128)
+            .checked_mul(fee_bps as u128)
+            .ok_or(MarketplaceError::MathOverflow)?
+            / BPS_DENOMINATOR as u128;
+
+        // fee_bps is capped below 100%, so the fee always fits in the sale amount
+        u64::try_from(fee).map_err(|_| error!(MarketplaceError::MathOverflow))
+    }
+
+    /// Calculate the referrer's share of a sale
+    ///
+    /// # Arguments
+    /// * `amount` - The sale amount in lamports or payment token base units
+    /// * `fee` - The marketplace fee on the sale, as returned by `fee_for`
+    ///
+    /// # Returns
+    /// * `Result<u64>` - The referral, capped at the fee it is carved out of
+    pub fn referral_for(&self, amount: u64, fee: u64) -> Result<u64> {
+        let referral = (amount as u128)
+            .checked_mul(self.referral_bps as u128)
+            .ok_or(MarketplaceError::MathOverflow)?
+            / BPS_DENOMINATOR as u128;
+
+        // A fee lowered below the referral share caps the referral at the whole fee
+        Ok(u64::try_from(referral).map_or(fee, |referral| referral.min(fee)))
+    }
+
+    /// The fee rate charged to a seller with the given lifetime volume
+    /// - The highest tier the volume reaches applies; below every tier the flat `fee_bps` does
+    /// - A tier never charges more than `fee_bps`, even after the flat fee was lowered
+    pub fn fee_bps_for_volume(&self, volume: u128) -> u16 {
+        self.fee_tiers
+            .iter()
+            .rev()
+            .find(|tier| tier.is_set() && volume >= tier.min_volume as u128)
+            .map_or(self.fee_bps, |tier| tier.fee_bps.min(self.fee_bps))
+    }
+
+    /// Whether a fee tier table can be stored on the marketplace
+    /// - Set tiers come first, with strictly ascending thresholds and non-increasing fees
+    /// - Every fee is capped at MAX_FEE_BPS
+    pub fn valid_fee_tiers(tiers: &[FeeTier; FEE_TIER_COUNT]) -> bool {
+        let set = tiers.iter().take_while(|tier| tier.is_set()).count();
+        tiers[set..].iter().all(|tier| *tier == FeeTier::default())
+            && tiers[..set].iter().all(|tier| tier.fee_bps <= MAX_FEE_BPS)
+            && tiers[..set].windows(2).all(|pair| {
+                pair[1].min_volume > pair[0].min_volume && pair[1].fee_bps <= pair[0].fee_bps
+            })
+    }
+
+    /// Whether a fee split list can be stored on the marketplace
+    /// - Empty, or up to MAX_FEE_SPLITS distinct recipients with non-zero shares summing to BPS_DENOMINATOR
+    pub fn valid_fee_splits(splits: &[FeeSplit]) -> bool {
+        if splits.is_empty() {
+            return true;
+        }
+        let total: u32 = splits.iter().map(|split| split.bps as u32).sum();
+        splits.len() <= MAX_FEE_SPLITS
+            && total == BPS_DENOMINATOR as u32
+            && splits.iter().all(|split| split.bps > 0)
+            && splits
+                .iter()
+                .enumerate()
+                .all(|(i, split)| splits[..i].iter().all(|other| other.recipient != split.recipient))
+    }
+
+    /// Divide a marketplace fee among the fee split recipients
+    /// - Each share rounds down; the rounding dust goes to the first recipient
+    ///
+    /// # Arguments
+    /// * `fee` - The marketplace fee, as in `PaymentSplit::marketplace_fee`
+    ///
+    /// # Returns
+    /// * `Result<Vec<u64>>` - The amount owed to each recipient, in `fee_splits` order
+    pub fn split_fee(&self, fee: u64) -> Result<Vec<u64>> {
+        let mut shares = self
+            .fee_splits
+            .iter()
+            .map(|split| Self::fee_at(fee, split.bps))
+            .collect::<Result<Vec<u64>>>()?;
+
+        let paid = shares.iter().try_fold(0u64, |paid, share| paid.checked_add(*share));
+        let dust = paid
+            .and_then(|paid| fee.checked_sub(paid))
+            .ok_or(MarketplaceError::MathOverflow)?;
+        if let Some(first) = shares.first_mut() {
+            *first += dust;
+        }
+
+        Ok(shares)
+    }
+
+    /// Split a sale's price between seller, fee recipient and referrer
+    ///
+    /// # Arguments
+    /// * `price` - The sale price in lamports or payment token base units
+    /// * `fee_bps` - The seller's fee rate, as returned by `fee_bps_for_volume`
+    /// * `referred` - Whether the purchase names a referrer
+    ///
+    /// # Returns
+    /// * `Result<PaymentSplit>` - The amount owed to each recipient
+    pub fn split_payment(&self, price: u64, fee_bps: u16, referred: bool) -> Result<PaymentSplit> {
+        let fee = Self::fee_at(price, fee_bps)?;
+        let referral_paid = if referred { self.referral_for(price, fee)? } else { 0 };
+
+        Ok(PaymentSplit {
+            seller_proceeds: price.checked_sub(fee).ok_or(MarketplaceError::MathOverflow)?,
+            marketplace_fee: fee.checked_sub(referral_paid).ok_or(MarketplaceError::MathOverflow)?,
+            royalty_paid: 0,
+            referral_paid,
+        })
+    }
+
+    /// Calculate the reward points minted for a sale
+    ///
+    /// # Arguments
+    /// * `amount` - The sale amount in lamports or payment token base units
+    ///
+    /// # Returns
+    /// * `Result<(u64, u64)>` - The buyer's and the seller's reward, in reward base units
+    pub fn rewards_for(&self, amount: u64) -> Result<(u64, u64)> {
+        let buyer_reward = (amount as u128)
+            .checked_mul(self.reward_rate_bps as u128)
+            .ok_or(MarketplaceError::MathOverflow)?
+            / BPS_DENOMINATOR as u128;
+        let buyer_reward = u64::try_from(buyer_reward).map_err(|_| error!(MarketplaceError::MathOverflow))?;
+
+        Ok((buyer_reward, buyer_reward / 2))
+    }
+
+    /// Count a completed sale in the statistics
+    /// - Saturates instead of failing, so statistics never block a sale
+    ///
+    /// # Arguments
+    /// * `price` - The total price paid
+    /// * `fee` - The marketplace fee taken from it
+    pub fn record_sale(&mut self, price: u64, fee: u64) {
+        self.total_volume = self.total_volume.saturating_add(price as u128);
+        self.sales_count = self.sales_count.saturating_add(1);
+        self.total_fees = self.total_fees.saturating_add(fee);
+    }
+
+    /// Count a newly opened listing
+    pub fn listing_opened(&mut self) {
+        self.active_listings = self.active_listings.saturating_add(1);
+    }
+
+    /// Count a listing that was sold out, delisted or cleaned up
+    /// - Saturates at zero for listings opened before statistics were tracked
+    pub fn listing_closed(&mut self) {
+        self.active_listings = self.active_listings.saturating_sub(1);
+    }
+
+    /// The statistics exposed to dashboards
+    pub fn stats(&self) -> MarketplaceStats {
+        MarketplaceStats {
+            total_volume: self.total_volume,
+            sales_count: self.sales_count,
+            total_fees: self.total_fees,
+            active_listings: self.active_listings,
+        }
+    }
+}
+
+/// Marketplace layout from before fees moved to basis points
+/// Only read by `migrate_marketplace`
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyMarketplace {
+    pub admin: Pubkey,
+    pub fee_percentage: u8,
+    pub bump: u8,
+    pub treasury_bump: u8,
+    pub open_listings: bool,
+    pub payment_mint: Option<Pubkey>,
+}
+
+impl LegacyMarketplace {
+    /// Space the legacy layout was allocated with, without the discriminator
+    pub const SPACE: usize = 32 + 1 + 1 + 1 + 1 + (1 + 32);
+}
+
+impl From<LegacyMarketplace> for Marketplace {
+    fn from(legacy: LegacyMarketplace) -> Self {
+        Marketplace {
+            admin: legacy.admin,
+            fee_bps: legacy.fee_percentage as u16 * 100,
+            bump: legacy.bump,
+            treasury_bump: legacy.treasury_bump,
+            open_listings: legacy.open_listings,
+            payment_mint: legacy.payment_mint,
+            // Set by `migrate_marketplace` once the rewards mint exists
+            rewards_bump: 0,
+            reward_rate_bps: 0,
+            paused: false,
+            // Set by `migrate_marketplace` to the treasury PDA
+            fee_recipient: Pubkey::default(),
+            total_volume: 0,
+            sales_count: 0,
+            total_fees: 0,
+            active_listings: 0,
+            // Legacy marketplaces live at the PDA of the empty name
+            name: String::new(),
+            referral_bps: 0,
+            fee_tiers: [FeeTier::default(); FEE_TIER_COUNT],
+            crank_fee_lamports: 0,
+            pull_payments: false,
+            created_at: 0,
+            crank_reward_lamports: 0,
+            installment_grace_secs: 0,
+            installment_forfeit_bps: 0,
+            discount_threshold: 0,
+            discount_bps: 0,
+            block_blacklisted_buyers: false,
+            fee_splits: Vec::new(),
+            max_listing_duration: 0,
+            listing_index_len: 0,
+            min_price: 0,
+            max_price: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marketplace(fee_bps: u16) -> Marketplace {
+        Marketplace {
+            admin: Pubkey::default(),
+            fee_bps,
+            bump: 255,
+            treasury_bump: 254,
+            open_listings: false,
+            payment_mint: None,
+            rewards_bump: 253,
+            reward_rate_bps: 0,
+            paused: false,
+            fee_recipient: Pubkey::default(),
+            total_volume: 0,
+            sales_count: 0,
+            total_fees: 0,
+            active_listings: 0,
+            name: String::new(),
+            referral_bps: 0,
+            fee_tiers: [FeeTier::default(); FEE_TIER_COUNT],
+            crank_fee_lamports: 0,
+            pull_payments: false,
+            created_at: 0,
+            crank_reward_lamports: 0,
+            installment_grace_secs: 0,
+            installment_forfeit_bps: 0,
+            discount_threshold: 0,
+            discount_bps: 0,
+            block_blacklisted_buyers: false,
+            fee_splits: Vec::new(),
+            max_listing_duration: 0,
+            listing_index_len: 0,
+            min_price: 0,
+            max_price: 0,
+        }
+    }
+
+    fn tiered() -> Marketplace {
+        Marketplace {
+            fee_tiers: [
+                FeeTier { min_volume: 1_000, fee_bps: 200 },
+                FeeTier { min_volume: 10_000, fee_bps: 100 },
+                FeeTier::default(),
+            ],
+            ..marketplace(250)
+        }
+    }
+
+    fn rewarding(reward_rate_bps: u16) -> Marketplace {
+        Marketplace {
+            reward_rate_bps,
+            ..marketplace(100)
+        }
+    }
+
+    #[test]
+    fn fee_is_price_times_bps_over_ten_thousand() {
+        assert_eq!(marketplace(0).fee_for(1_000_000).unwrap(), 0);
+        assert_eq!(marketplace(250).fee_for(1_000_000).unwrap(), 25_000);
+        assert_eq!(marketplace(1_000).fee_for(1_000_000).unwrap(), 100_000);
+        // Rounds down in the seller's favour
+        assert_eq!(marketplace(1).fee_for(9_999).unwrap(), 0);
+    }
+
+    #[test]
+    fn fee_math_does_not_overflow_on_large_prices() {
+        assert_eq!(marketplace(1_000).fee_for(u64::MAX).unwrap(), u64::MAX / 10);
+    }
+
+    #[test]
+    fn referral_is_carved_out_of_the_fee() {
+        let referring = Marketplace {
+            referral_bps: 50,
+            ..marketplace(100)
+        };
+        assert_eq!(referring.referral_for(1_000_000, 10_000).unwrap(), 5_000);
+        assert_eq!(marketplace(100).referral_for(1_000_000, 10_000).unwrap(), 0);
+
+        // Never more than the fee, even when the fee was lowered below the referral share
+        let lowered = Marketplace {
+            referral_bps: 200,
+            ..marketplace(100)
+        };
+        assert_eq!(lowered.referral_for(1_000_000, 10_000).unwrap(), 10_000);
+    }
+
+    #[test]
+    fn seller_earns_half_the_buyer_reward() {
+        assert_eq!(rewarding(0).rewards_for(1_000_000).unwrap(), (0, 0));
+        assert_eq!(rewarding(100).rewards_for(1_000_000).unwrap(), (10_000, 5_000));
+        // Odd buyer rewards round the seller's half down
+        assert_eq!(rewarding(10_000).rewards_for(3).unwrap(), (3, 1));
+    }
+
+    #[test]
+    fn reward_math_does_not_overflow_on_large_prices() {
+        assert_eq!(rewarding(10_000).rewards_for(u64::MAX).unwrap(), (u64::MAX, u64::MAX / 2));
+    }
+
+    #[test]
+    fn legacy_percentages_convert_to_bps() {
+        let legacy = LegacyMarketplace {
+            admin: Pubkey::new_unique(),
+            fee_percentage: 3,
+            bump: 250,
+            treasury_bump: 251,
+            open_listings: true,
+            payment_mint: None,
+        };
+        let admin = legacy.admin;
+        let migrated = Marketplace::from(legacy);
+        assert_eq!(migrated.admin, admin);
+        assert_eq!(migrated.fee_bps, 300);
+        assert_eq!((migrated.bump, migrated.treasury_bump), (250, 251));
+        assert!(migrated.open_listings);
+        assert_eq!(migrated.reward_rate_bps, 0);
+        assert!(!migrated.paused);
+        assert_eq!(migrated.stats(), marketplace(0).stats());
+    }
+
+    #[test]
+    fn new_layout_adds_bps_fee_reward_pause_fee_recipient_stats_name_referral_and_tier_fields() {
+        // One more byte for fee_bps, then rewards_bump, reward_rate_bps, paused, fee_recipient,
+        // stats, name, referral_bps and fee_tiers
+        assert_eq!(
+            Marketplace::INIT_SPACE,
+            LegacyMarketplace::SPACE + 1 + 1 + 2 + 1 + 32 + Marketplace::ZEROED_TAIL_SPACE
+        );
+    }
+
+    #[test]
+    fn zeroed_tail_deserializes_as_empty_stats_name_referral_tiers_and_crank_fee() {
+        let fee_recipient = Pubkey::new_unique();
+        let mut data = Vec::new();
+        Marketplace { fee_recipient, ..marketplace(100) }.serialize(&mut data).unwrap();
+
+        // A marketplace from before stats, names, referrals, tiers, crank fees, pull payments,
+        // creation times, crank rewards, installment terms, holder discounts, the blacklisted
+        // buyer flag, fee splits, listing durations, the listing index and price bounds, grown
+        // with zero bytes
+        data.truncate(Marketplace::INIT_SPACE - Marketplace::ZEROED_TAIL_SPACE);
+        data.resize(Marketplace::INIT_SPACE, 0);
+
+        let grown = Marketplace::deserialize(&mut &data[..]).unwrap();
+        assert_eq!(grown.fee_recipient, fee_recipient);
+        assert_eq!(grown.stats(), marketplace(0).stats());
+        assert!(grown.name.is_empty());
+        assert_eq!(grown.referral_bps, 0);
+        assert_eq!(grown.fee_bps_for_volume(u128::MAX), 100);
+        assert_eq!(grown.crank_fee_lamports, 0);
+        assert!(!grown.pull_payments);
+        assert_eq!(grown.created_at, 0);
+        assert_eq!(grown.crank_reward_lamports, 0);
+        assert_eq!(grown.installment_grace_secs, 0);
+        assert_eq!(grown.installment_forfeit(1_000_000).unwrap(), 0);
+        assert_eq!(grown.holder_fee_bps(100, u64::MAX), (100, 0));
+        assert!(!grown.block_blacklisted_buyers);
+        assert!(grown.fee_splits.is_empty());
+        assert!(!grown.exceeds_listing_duration(0, i64::MAX));
+        assert_eq!(grown.listing_index_len, 0);
+        assert!(grown.price_in_bounds(1) && grown.price_in_bounds(u64::MAX));
+    }
+
+    #[test]
+    fn holders_above_the_threshold_get_the_discount() {
+        let discounted = Marketplace {
+            discount_threshold: 1_000,
+            discount_bps: 50,
+            ..marketplace(200)
+        };
+        assert_eq!(discounted.holder_fee_bps(200, 999), (200, 0));
+        assert_eq!(discounted.holder_fee_bps(200, 1_000), (150, 50));
+        // Tiers may already charge less than the discount
+        assert_eq!(discounted.holder_fee_bps(30, 1_000), (0, 30));
+        assert_eq!(marketplace(200).holder_fee_bps(200, u64::MAX), (200, 0));
+    }
+
+    #[test]
+    fn crank_bounty_never_exceeds_the_escrow() {
+        let cranked = Marketplace { crank_fee_lamports: 5_000, ..marketplace(100) };
+        assert_eq!(cranked.crank_bounty(1_000_000), 5_000);
+        assert_eq!(cranked.crank_bounty(3_000), 3_000);
+        assert_eq!(marketplace(100).crank_bounty(1_000_000), 0);
+    }
+
+    #[test]
+    fn crank_reward_is_skipped_when_the_treasury_cannot_cover_it() {
+        let rewarding = Marketplace { crank_reward_lamports: 5_000, ..marketplace(100) };
+        assert_eq!(rewarding.crank_reward(5_000), 5_000);
+        assert_eq!(rewarding.crank_reward(4_999), 0);
+        assert_eq!(marketplace(100).crank_reward(0), 0);
+    }
+
+    #[test]
+    fn listing_extensions_are_capped_at_the_max_duration() {
+        let capped = Marketplace { max_listing_duration: 3_600, ..marketplace(100) };
+        assert!(!capped.exceeds_listing_duration(1_000, 4_600));
+        assert!(capped.exceeds_listing_duration(1_000, 4_601));
+        assert!(!marketplace(100).exceeds_listing_duration(1_000, i64::MAX));
+    }
+
+    #[test]
+    fn price_bounds_include_both_ends() {
+        let bounded = Marketplace { min_price: 1_000, max_price: 5_000, ..marketplace(100) };
+        assert!(!bounded.price_in_bounds(999));
+        assert!(bounded.price_in_bounds(1_000));
+        assert!(bounded.price_in_bounds(5_000));
+        assert!(!bounded.price_in_bounds(5_001));
+
+        // A maximum of 0 leaves prices unbounded above
+        let floored = Marketplace { min_price: 1_000, ..marketplace(100) };
+        assert!(floored.price_in_bounds(u64::MAX));
+        assert!(!floored.price_in_bounds(999));
+    }
+
+    #[test]
+    fn installment_forfeit_takes_its_share_of_the_payments() {
+        let forfeiting = Marketplace { installment_forfeit_bps: 2_500, ..marketplace(100) };
+        assert_eq!(forfeiting.installment_forfeit(1_000_000).unwrap(), 250_000);
+        assert_eq!(forfeiting.installment_forfeit(3).unwrap(), 0);
+
+        let everything = Marketplace { installment_forfeit_bps: 10_000, ..marketplace(100) };
+        assert_eq!(everything.installment_forfeit(u64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn sellers_below_every_tier_pay_the_flat_fee() {
+        assert_eq!(tiered().fee_bps_for_volume(0), 250);
+        assert_eq!(tiered().fee_bps_for_volume(999), 250);
+        assert_eq!(marketplace(250).fee_bps_for_volume(u128::MAX), 250);
+    }
+
+    #[test]
+    fn sellers_pay_the_highest_tier_they_reach() {
+        assert_eq!(tiered().fee_bps_for_volume(1_000), 200);
+        assert_eq!(tiered().fee_bps_for_volume(9_999), 200);
+        assert_eq!(tiered().fee_bps_for_volume(10_000), 100);
+        assert_eq!(tiered().fee_bps_for_volume(u128::MAX), 100);
+    }
+
+    #[test]
+    fn tiers_never_charge_more_than_the_flat_fee() {
+        let lowered = Marketplace { fee_bps: 150, ..tiered() };
+        assert_eq!(lowered.fee_bps_for_volume(1_000), 150);
+        assert_eq!(lowered.fee_bps_for_volume(10_000), 100);
+    }
+
+    #[test]
+    fn fee_tiers_must_ascend_and_discount() {
+        assert!(Marketplace::valid_fee_tiers(&tiered().fee_tiers));
+        assert!(Marketplace::valid_fee_tiers(&[FeeTier::default(); FEE_TIER_COUNT]));
+
+        let tier = |min_volume, fee_bps| FeeTier { min_volume, fee_bps };
+        let unset = FeeTier::default();
+        for invalid in [
+            // Thresholds must strictly ascend
+            [tier(1_000, 200), tier(1_000, 100), unset],
+            // Fees must not rise with volume
+            [tier(1_000, 100), tier(2_000, 200), unset],
+            // Unset tiers only at the end
+            [unset, tier(1_000, 100), unset],
+            [tier(1_000, 100), tier(0, 50), unset],
+            // Capped like the flat fee
+            [tier(1_000, MAX_FEE_BPS + 1), unset, unset],
+        ] {
+            assert!(!Marketplace::valid_fee_tiers(&invalid));
+        }
+    }
+
+    #[test]
+    fn fee_splits_must_share_the_whole_fee() {
+        let split = |bps| FeeSplit { recipient: Pubkey::new_unique(), bps };
+        assert!(Marketplace::valid_fee_splits(&[]));
+        assert!(Marketplace::valid_fee_splits(&[split(7_000), split(2_000), split(1_000)]));
+        assert!(Marketplace::valid_fee_splits(&[split(10_000)]));
+
+        // Shares must sum to the whole fee
+        assert!(!Marketplace::valid_fee_splits(&[split(7_000), split(2_000)]));
+        assert!(!Marketplace::valid_fee_splits(&[split(7_000), split(4_000)]));
+        // No empty shares
+        assert!(!Marketplace::valid_fee_splits(&[split(10_000), split(0)]));
+        // At most MAX_FEE_SPLITS recipients
+        let crowded: Vec<FeeSplit> = (0..=MAX_FEE_SPLITS).map(|_| split(2_000)).collect();
+        assert!(!Marketplace::valid_fee_splits(&crowded));
+        // No recipient listed twice
+        let repeated = split(5_000);
+        assert!(!Marketplace::valid_fee_splits(&[repeated, repeated]));
+    }
+
+    #[test]
+    fn split_fee_gives_the_dust_to_the_first_recipient() {
+        let split = |bps| FeeSplit { recipient: Pubkey::new_unique(), bps };
+        let splitting = Marketplace {
+            fee_splits: vec![split(7_000), split(2_000), split(1_000)],
+            ..marketplace(100)
+        };
+        assert_eq!(splitting.split_fee(1_000_000).unwrap(), vec![700_000, 200_000, 100_000]);
+        assert_eq!(splitting.split_fee(1_001).unwrap(), vec![701, 200, 100]);
+        assert_eq!(splitting.split_fee(9).unwrap(), vec![8, 1, 0]);
+        assert_eq!(splitting.split_fee(u64::MAX).unwrap().iter().sum::<u64>(), u64::MAX);
+        assert!(marketplace(100).split_fee(1_000).unwrap().is_empty());
+    }
+
+    #[test]
+    fn payment_split_adds_up_to_the_price() {
+        for fee_bps in [0, 1, 250, 1_000] {
+            for referral_bps in [0, 50, 250, 1_000] {
+                for price in [0, 1, 9_999, 1_000_000, u64::MAX] {
+                    for referred in [false, true] {
+                        let marketplace = Marketplace {
+                            referral_bps,
+                            ..marketplace(fee_bps)
+                        };
+                        let split = marketplace.split_payment(price, fee_bps, referred).unwrap();
+                        assert_eq!(split.total(), Some(price));
+                        assert_eq!(split.fee(), marketplace.fee_for(price).unwrap());
+                        assert_eq!(split.royalty_paid, 0);
+                        if !referred {
+                            assert_eq!(split.referral_paid, 0);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn one_lamport_sales_pay_no_fee() {
+        let referring = Marketplace {
+            referral_bps: MAX_FEE_BPS,
+            ..marketplace(MAX_FEE_BPS)
+        };
+        let split = referring.split_payment(1, MAX_FEE_BPS, true).unwrap();
+        assert_eq!(split.seller_proceeds, 1);
+        assert_eq!(split.fee(), 0);
+    }
+
+    #[test]
+    fn sales_near_u64_max_split_exactly() {
+        let price = u64::MAX - 1;
+        let split = marketplace(MAX_FEE_BPS).split_payment(price, MAX_FEE_BPS, false).unwrap();
+        assert_eq!(
+            split.marketplace_fee,
+            (price as u128 * MAX_FEE_BPS as u128 / BPS_DENOMINATOR as u128) as u64
+        );
+        assert_eq!(split.seller_proceeds + split.marketplace_fee, price);
+    }
+
+    #[test]
+    fn referral_is_carved_out_of_the_marketplace_fee() {
+        let marketplace = Marketplace {
+            referral_bps: 50,
+            ..marketplace(250)
+        };
+        let split = marketplace.split_payment(1_000_000, 250, true).unwrap();
+        assert_eq!(
+            split,
+            PaymentSplit {
+                seller_proceeds: 975_000,
+                marketplace_fee: 20_000,
+                royalty_paid: 0,
+                referral_paid: 5_000,
+            }
+        );
+    }
+
+    #[test]
+    fn sales_add_to_volume_count_and_fees() {
+        let mut marketplace = marketplace(100);
+        marketplace.record_sale(1_000_000, 10_000);
+        marketplace.record_sale(500_000, 5_000);
+        assert_eq!(marketplace.total_volume, 1_500_000);
+        assert_eq!(marketplace.sales_count, 2);
+        assert_eq!(marketplace.total_fees, 15_000);
+    }
+
+    #[test]
+    fn volume_does_not_overflow_past_u64() {
+        let mut marketplace = marketplace(100);
+        marketplace.record_sale(u64::MAX, 0);
+        marketplace.record_sale(u64::MAX, 0);
+        assert_eq!(marketplace.total_volume, 2 * u64::MAX as u128);
+    }
+
+    #[test]
+    fn stats_saturate_instead_of_failing() {
+        let mut marketplace = marketplace(100);
+        marketplace.total_fees = u64::MAX;
+        marketplace.record_sale(1, 1);
+        assert_eq!(marketplace.total_fees, u64::MAX);
+
+        // Closing a listing opened before tracking started stays at zero
+        marketplace.listing_closed();
+        assert_eq!(marketplace.active_listings, 0);
+        marketplace.listing_opened();
+        assert_eq!(marketplace.active_listings, 1);
+    }
+}
@@ -2,4 +2,49 @@ pub mod listing;
 pub use listing::*;
 
 pub mod marketplace;
-pub use marketplace::*;
\ No newline at end of file
+pub use marketplace::*;
+
+pub mod offer;
+pub use offer::*;
+
+pub mod collection_config;
+pub use collection_config::*;
+
+pub mod auction;
+pub use auction::*;
+
+pub mod sale_escrow;
+pub use sale_escrow::*;
+
+pub mod collection_offer;
+pub use collection_offer::*;
+
+pub mod nft_offer;
+pub use nft_offer::*;
+
+pub mod seller_stats;
+pub use seller_stats::*;
+
+pub mod proceeds;
+pub use proceeds::*;
+
+pub mod bundle_listing;
+pub use bundle_listing::*;
+
+pub mod installment_plan;
+pub use installment_plan::*;
+
+pub mod provenance;
+pub use provenance::*;
+
+pub mod collection_stats;
+pub use collection_stats::*;
+
+pub mod blacklist;
+pub use blacklist::*;
+
+pub mod listing_index;
+pub use listing_index::*;
+
+pub mod quantity_offer;
+pub use quantity_offer::*;
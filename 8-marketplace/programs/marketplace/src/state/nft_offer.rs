@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct NftOffer {
+    /// The marketplace the offer was made on
+    pub marketplace: Pubkey,
+
+    /// The NFT the offer buys, listed or not
+    pub mint: Pubkey,
+
+    /// The buyer who made the offer and receives the NFT and refunds
+    pub buyer: Pubkey,
+
+    /// The offered amount in lamports
+    /// Held in escrow on this account on top of its rent
+    pub amount: u64,
+
+    /// Unix timestamp after which the offer can no longer be accepted
+    /// Expired offers can be refunded by anyone, for the marketplace's crank bounty
+    pub expiry: i64,
+
+    /// PDA bump seed for this offer account
+    /// Used for deterministic address generation
+    pub bump: u8,
+}
+
+impl NftOffer {
+    /// Whether the offer has expired at the given unix timestamp
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expiry
+    }
+}
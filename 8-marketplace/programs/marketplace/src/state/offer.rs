@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Offer {
+    /// The buyer who made the offer and receives refunds
+    pub buyer: Pubkey,
+
+    /// The listing this offer was made on
+    pub listing: Pubkey,
+
+    /// The marketplace of the listing, kept for refunds after the listing is closed
+    pub marketplace: Pubkey,
+
+    /// The offered amount in lamports, or in base units of `payment_mint`
+    /// Lamports are held in escrow on this account on top of its rent,
+    /// tokens in the offer's associated token account
+    pub amount: u64,
+
+    /// Unix timestamp after which the offer can no longer be accepted
+    /// Expired offers can be refunded by anyone, for the marketplace's crank bounty
+    pub expiry: i64,
+
+    /// PDA bump seed for this offer account
+    /// Used for deterministic address generation
+    pub bump: u8,
+
+    /// The amount in lamports the seller countered with
+    /// None while the offer stands on its original terms
+    pub counter_amount: Option<u64>,
+
+    /// Unix timestamp after which the counter-offer lapses and the original terms apply again
+    pub counter_expiry: i64,
+
+    /// The SPL token mint the offer is escrowed in
+    /// None for offers in lamports
+    pub payment_mint: Option<Pubkey>,
+}
+
+impl Offer {
+    /// Whether the offer has expired at the given unix timestamp
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expiry
+    }
+
+    /// The counter-offer amount, if a counter-offer is open at the given unix timestamp
+    pub fn open_counter(&self, now: i64) -> Option<u64> {
+        self.counter_amount.filter(|_| now < self.counter_expiry)
+    }
+
+    /// Whether `garbage_collect` may close the offer
+    /// - Only once its listing is closed; expired offers on open listings go through
+    ///   `refund_expired_offer`, which also clears them as the listing's best offer
+    /// - Token offers are refunded from their vault by `refund_expired_offer` instead
+    ///
+    /// # Arguments
+    /// * `listing_closed` - Whether the listing account no longer exists
+    pub fn is_collectable(&self, listing_closed: bool) -> bool {
+        listing_closed && self.payment_mint.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offer() -> Offer {
+        Offer {
+            buyer: Pubkey::default(),
+            listing: Pubkey::default(),
+            marketplace: Pubkey::default(),
+            amount: 1_000,
+            expiry: 10_000,
+            bump: 255,
+            counter_amount: None,
+            counter_expiry: 0,
+            payment_mint: None,
+        }
+    }
+
+    #[test]
+    fn counter_offers_lapse_exactly_at_their_expiry() {
+        let mut countered = offer();
+        assert_eq!(countered.open_counter(0), None);
+
+        countered.counter_amount = Some(1_500);
+        countered.counter_expiry = 2_000;
+        assert_eq!(countered.open_counter(1_999), Some(1_500));
+        assert_eq!(countered.open_counter(2_000), None);
+    }
+
+    #[test]
+    fn only_lamport_offers_on_closed_listings_are_collectable() {
+        assert!(offer().is_collectable(true));
+        assert!(!offer().is_collectable(false));
+
+        let token_offer = Offer { payment_mint: Some(Pubkey::new_unique()), ..offer() };
+        assert!(!token_offer.is_collectable(true));
+    }
+}
@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+
+use crate::error::MarketplaceError;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Proceeds {
+    /// The marketplace the proceeds were earned on
+    pub marketplace: Pubkey,
+
+    /// The seller the proceeds belong to
+    /// Only the seller can claim them, to any destination they sign for
+    pub seller: Pubkey,
+
+    /// Unclaimed sale proceeds in lamports, after the marketplace fee
+    /// Held on this account on top of its rent
+    pub amount: u64,
+
+    /// PDA bump seed for this proceeds account
+    /// Used for deterministic address generation
+    pub bump: u8,
+}
+
+impl Proceeds {
+    /// Proceeds of a seller who has not been paid yet
+    ///
+    /// # Arguments
+    /// * `marketplace` - The marketplace the proceeds are earned on
+    /// * `seller` - The seller the proceeds belong to
+    /// * `bump` - PDA bump seed of the proceeds account
+    pub fn new(marketplace: Pubkey, seller: Pubkey, bump: u8) -> Self {
+        Self {
+            marketplace,
+            seller,
+            amount: 0,
+            bump,
+        }
+    }
+
+    /// Whether the account was just created by `init_if_needed` and still needs `new`
+    pub fn is_uninitialized(&self) -> bool {
+        self.seller == Pubkey::default()
+    }
+
+    /// Add the lamports of a sale to the claimable balance
+    ///
+    /// # Arguments
+    /// * `amount` - The seller proceeds just transferred onto the account
+    pub fn credit(&mut self, amount: u64) -> Result<()> {
+        self.amount = self
+            .amount
+            .checked_add(amount)
+            .ok_or(MarketplaceError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Empty the claimable balance
+    ///
+    /// # Returns
+    /// * `u64` - The lamports to pay out
+    pub fn take(&mut self) -> u64 {
+        std::mem::take(&mut self.amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sales_accumulate_until_taken() {
+        let mut proceeds = Proceeds::new(Pubkey::default(), Pubkey::new_unique(), 255);
+        proceeds.credit(1_000).unwrap();
+        proceeds.credit(500).unwrap();
+        assert_eq!(proceeds.take(), 1_500);
+        assert_eq!(proceeds.amount, 0);
+        assert_eq!(proceeds.take(), 0);
+    }
+
+    #[test]
+    fn balance_overflow_is_an_error() {
+        let mut proceeds = Proceeds::new(Pubkey::default(), Pubkey::new_unique(), 255);
+        proceeds.credit(u64::MAX).unwrap();
+        assert!(proceeds.credit(1).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::PROVENANCE_CAPACITY;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Provenance {
+    /// The marketplace the sales happened on
+    pub marketplace: Pubkey,
+
+    /// The NFT mint the history belongs to
+    pub mint: Pubkey,
+
+    /// Number of sales ever recorded, including those evicted from `sales`
+    /// Also locates the slot of the next sale in `sales`
+    pub total_sales: u64,
+
+    /// Lifetime sale volume, in lamports or payment token base units
+    pub lifetime_volume: u128,
+
+    /// Ring buffer of the most recent sales
+    /// Slot `total_sales % PROVENANCE_CAPACITY` holds the oldest kept sale once the buffer is full
+    pub sales: [SaleRecord; PROVENANCE_CAPACITY],
+
+    /// PDA bump seed for this provenance account
+    /// Used for deterministic address generation
+    pub bump: u8,
+}
+
+/// A single sale of an NFT
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace)]
+pub struct SaleRecord {
+    /// Price paid, 0 for sales in a secondary currency
+    pub price: u64,
+
+    /// The account that bought the NFT
+    pub buyer: Pubkey,
+
+    /// The account that sold the NFT
+    pub seller: Pubkey,
+
+    /// Unix timestamp of the sale
+    pub timestamp: i64,
+}
+
+impl Provenance {
+    /// Provenance of an NFT that was never sold on the marketplace
+    ///
+    /// # Arguments
+    /// * `marketplace` - The marketplace the sales happen on
+    /// * `mint` - The NFT mint the history belongs to
+    /// * `bump` - PDA bump seed of the provenance account
+    pub fn new(marketplace: Pubkey, mint: Pubkey, bump: u8) -> Self {
+        Self {
+            marketplace,
+            mint,
+            total_sales: 0,
+            lifetime_volume: 0,
+            sales: [SaleRecord::default(); PROVENANCE_CAPACITY],
+            bump,
+        }
+    }
+
+    /// Whether the account was just created by `init_if_needed` and still needs `new`
+    pub fn is_uninitialized(&self) -> bool {
+        self.mint == Pubkey::default()
+    }
+
+    /// Append a sale, overwriting the oldest one once the buffer is full
+    /// - Constant time and never grows the account
+    /// - Saturates instead of failing, so provenance never blocks a sale
+    ///
+    /// # Arguments
+    /// * `sale` - The completed sale
+    pub fn record_sale(&mut self, sale: SaleRecord) {
+        let slot = (self.total_sales % PROVENANCE_CAPACITY as u64) as usize;
+        self.sales[slot] = sale;
+        self.total_sales = self.total_sales.saturating_add(1);
+        self.lifetime_volume = self.lifetime_volume.saturating_add(sale.price as u128);
+    }
+
+    /// The kept sales, oldest first
+    pub fn history(&self) -> Vec<SaleRecord> {
+        let kept = self.total_sales.min(PROVENANCE_CAPACITY as u64) as usize;
+        let oldest = if kept < PROVENANCE_CAPACITY {
+            0
+        } else {
+            (self.total_sales % PROVENANCE_CAPACITY as u64) as usize
+        };
+        (0..kept)
+            .map(|i| self.sales[(oldest + i) % PROVENANCE_CAPACITY])
+            .collect()
+    }
+}
+
+/// Provenance returned by `get_provenance`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProvenanceHistory {
+    pub mint: Pubkey,
+    pub total_sales: u64,
+    pub lifetime_volume: u128,
+    pub sales: Vec<SaleRecord>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sale(price: u64) -> SaleRecord {
+        SaleRecord {
+            price,
+            buyer: Pubkey::new_unique(),
+            seller: Pubkey::new_unique(),
+            timestamp: price as i64,
+        }
+    }
+
+    #[test]
+    fn history_lists_sales_oldest_first() {
+        let mut provenance = Provenance::new(Pubkey::default(), Pubkey::new_unique(), 255);
+        assert!(provenance.history().is_empty());
+        provenance.record_sale(sale(1));
+        provenance.record_sale(sale(2));
+        let prices: Vec<u64> = provenance.history().iter().map(|s| s.price).collect();
+        assert_eq!(prices, vec![1, 2]);
+        assert_eq!(provenance.total_sales, 2);
+        assert_eq!(provenance.lifetime_volume, 3);
+    }
+
+    #[test]
+    fn overflowing_the_buffer_evicts_the_oldest_sales() {
+        let mut provenance = Provenance::new(Pubkey::default(), Pubkey::new_unique(), 255);
+        let total = PROVENANCE_CAPACITY as u64 + 3;
+        for price in 1..=total {
+            provenance.record_sale(sale(price));
+        }
+        let prices: Vec<u64> = provenance.history().iter().map(|s| s.price).collect();
+        assert_eq!(prices, (4..=total).collect::<Vec<_>>());
+        // Evicted sales still count towards the totals
+        assert_eq!(provenance.total_sales, total);
+        assert_eq!(provenance.lifetime_volume, (total * (total + 1) / 2) as u128);
+    }
+
+    #[test]
+    fn lifetime_volume_does_not_overflow_past_u64() {
+        let mut provenance = Provenance::new(Pubkey::default(), Pubkey::new_unique(), 255);
+        provenance.record_sale(sale(u64::MAX));
+        provenance.record_sale(sale(u64::MAX));
+        assert_eq!(provenance.lifetime_volume, 2 * u64::MAX as u128);
+    }
+}
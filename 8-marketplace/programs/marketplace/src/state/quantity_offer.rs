@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+
+use crate::error::MarketplaceError;
+
+#[account]
+#[derive(InitSpace)]
+pub struct QuantityOffer {
+    /// The buyer who made the offer and receives the tokens and refunds
+    pub buyer: Pubkey,
+
+    /// The listing the offer was made on
+    /// Only anchors the offer's address; any listing of `mint` can fill it
+    pub listing: Pubkey,
+
+    /// The marketplace of the listing, kept for fills and refunds after the listing is closed
+    pub marketplace: Pubkey,
+
+    /// The semi-fungible token mint the offer buys
+    pub mint: Pubkey,
+
+    /// Tokens the offer still buys
+    pub quantity: u64,
+
+    /// The offered lamports per token
+    pub price_per_unit: u64,
+
+    /// The lamports still escrowed on this account on top of its rent
+    /// `price_per_unit` for each token the offer still buys
+    pub amount: u64,
+
+    /// Unix timestamp after which the offer can no longer be filled
+    /// Expired offers can be refunded by anyone, for the marketplace's crank bounty
+    pub expiry: i64,
+
+    /// PDA bump seed for this offer account
+    /// Used for deterministic address generation
+    pub bump: u8,
+}
+
+impl QuantityOffer {
+    /// Whether the offer has expired at the given unix timestamp
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expiry
+    }
+
+    /// The lamports a partial fill pays out of the escrow
+    /// - Never more than the escrow, so fees taken out of it cannot overdraw the offer
+    ///
+    /// # Arguments
+    /// * `fill_quantity` - Tokens sold into the offer
+    ///
+    /// # Returns
+    /// * `Result<u64>` - The payment, or an error for an empty fill or an over-fill
+    pub fn fill_cost(&self, fill_quantity: u64) -> Result<u64> {
+        require!(fill_quantity > 0, MarketplaceError::InvalidQuantity);
+        require!(fill_quantity <= self.quantity, MarketplaceError::OfferOverfilled);
+
+        let cost = self
+            .price_per_unit
+            .checked_mul(fill_quantity)
+            .ok_or(MarketplaceError::MathOverflow)?;
+        require!(cost <= self.amount, MarketplaceError::MathOverflow);
+
+        Ok(cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quantity_offer(quantity: u64, price_per_unit: u64) -> QuantityOffer {
+        QuantityOffer {
+            buyer: Pubkey::default(),
+            listing: Pubkey::default(),
+            marketplace: Pubkey::default(),
+            mint: Pubkey::default(),
+            quantity,
+            price_per_unit,
+            amount: quantity * price_per_unit,
+            expiry: 10_000,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn partial_fills_pay_per_unit_and_reject_over_fills() {
+        let offer = quantity_offer(100, 10);
+        assert_eq!(offer.fill_cost(40).unwrap(), 400);
+        assert_eq!(offer.fill_cost(100).unwrap(), 1_000);
+        assert!(offer.fill_cost(0).is_err());
+        assert!(offer.fill_cost(101).is_err());
+    }
+
+    #[test]
+    fn fills_never_pay_more_than_the_escrow() {
+        let underfunded = QuantityOffer { amount: 999, ..quantity_offer(100, 10) };
+        assert!(underfunded.fill_cost(100).is_err());
+
+        let overflowing = QuantityOffer { amount: u64::MAX, ..quantity_offer(2, u64::MAX / 4) };
+        let overflowing = QuantityOffer { price_per_unit: u64::MAX / 2 + 1, ..overflowing };
+        assert!(overflowing.fill_cost(2).is_err());
+    }
+}
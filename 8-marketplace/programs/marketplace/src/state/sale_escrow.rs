@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct SaleEscrow {
+    /// The marketplace the sale was made on
+    /// Its admin resolves disputes
+    pub marketplace: Pubkey,
+
+    /// The listing that was purchased
+    pub listing: Pubkey,
+
+    /// The seller who receives the proceeds once released
+    pub seller: Pubkey,
+
+    /// The buyer who received the NFT and paid the escrow rent
+    pub buyer: Pubkey,
+
+    /// The seller proceeds in lamports, after the marketplace fee
+    /// Held in escrow on this account on top of its rent
+    pub amount: u64,
+
+    /// Unix timestamp at which the protection window ends
+    /// Anyone can release the proceeds from then on, unless disputed
+    pub release_ts: i64,
+
+    /// Whether the buyer disputed the sale within the window
+    /// Only the marketplace admin can resolve a disputed sale
+    pub disputed: bool,
+
+    /// PDA bump seed for this escrow account
+    /// Used for deterministic address generation
+    pub bump: u8,
+}
+
+impl SaleEscrow {
+    /// Whether the buyer can still dispute the sale at the given unix timestamp
+    pub fn can_dispute(&self, now: i64) -> bool {
+        !self.disputed && now < self.release_ts
+    }
+
+    /// Whether anyone can release the proceeds to the seller at the given unix timestamp
+    pub fn can_release(&self, now: i64) -> bool {
+        !self.disputed && now >= self.release_ts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escrow(disputed: bool) -> SaleEscrow {
+        SaleEscrow {
+            marketplace: Pubkey::default(),
+            listing: Pubkey::default(),
+            seller: Pubkey::default(),
+            buyer: Pubkey::default(),
+            amount: 1_000,
+            release_ts: 5_000,
+            disputed,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn window_ends_exactly_at_release_ts() {
+        let escrow = escrow(false);
+        assert!(escrow.can_dispute(4_999));
+        assert!(!escrow.can_release(4_999));
+        assert!(!escrow.can_dispute(5_000));
+        assert!(escrow.can_release(5_000));
+    }
+
+    #[test]
+    fn disputed_sales_are_neither_disputable_nor_releasable() {
+        let escrow = escrow(true);
+        assert!(!escrow.can_dispute(4_999));
+        assert!(!escrow.can_release(5_000));
+    }
+}
@@ -0,0 +1,148 @@
+use anchor_lang::prelude::*;
+
+use crate::error::MarketplaceError;
+
+#[account]
+#[derive(InitSpace)]
+pub struct SellerStats {
+    /// The marketplace the statistics are kept for
+    pub marketplace: Pubkey,
+
+    /// The seller the statistics belong to
+    pub seller: Pubkey,
+
+    /// Lifetime sale volume, in lamports or payment token base units
+    /// Looked up against the marketplace fee tiers
+    pub volume: u128,
+
+    /// Number of completed sales
+    pub sales_count: u64,
+
+    /// Number of listings the seller ever opened
+    pub listings_created: u64,
+
+    /// Listings of the seller currently open
+    pub active_listings: u64,
+
+    /// Nonce of the seller's next listing, part of its PDA seeds
+    /// Only ever increases, so every listing of a seller gets a new address
+    pub listing_nonce: u64,
+
+    /// PDA bump seed for this statistics account
+    /// Used for deterministic address generation
+    pub bump: u8,
+}
+
+impl SellerStats {
+    /// Statistics of a seller who has not listed or sold yet
+    ///
+    /// # Arguments
+    /// * `marketplace` - The marketplace the statistics are kept for
+    /// * `seller` - The seller the statistics belong to
+    /// * `bump` - PDA bump seed of the statistics account
+    pub fn new(marketplace: Pubkey, seller: Pubkey, bump: u8) -> Self {
+        Self {
+            marketplace,
+            seller,
+            volume: 0,
+            sales_count: 0,
+            listings_created: 0,
+            active_listings: 0,
+            listing_nonce: 0,
+            bump,
+        }
+    }
+
+    /// Whether the account was just created by `init_if_needed` and still needs `new`
+    pub fn is_uninitialized(&self) -> bool {
+        self.seller == Pubkey::default()
+    }
+
+    /// Count a completed sale
+    /// - Saturates instead of failing, so statistics never block a sale
+    ///
+    /// # Arguments
+    /// * `price` - The total price paid
+    pub fn record_sale(&mut self, price: u64) {
+        self.volume = self.volume.saturating_add(price as u128);
+        self.sales_count = self.sales_count.saturating_add(1);
+    }
+
+    /// Count a newly opened listing and hand out its nonce
+    ///
+    /// # Returns
+    /// * `Result<u64>` - The nonce the listing's PDA was derived with
+    pub fn listing_opened(&mut self) -> Result<u64> {
+        let nonce = self.listing_nonce;
+        self.listing_nonce = nonce.checked_add(1).ok_or(MarketplaceError::MathOverflow)?;
+        self.listings_created = self.listings_created.saturating_add(1);
+        self.active_listings = self.active_listings.saturating_add(1);
+        Ok(nonce)
+    }
+
+    /// Count a listing that was sold out, delisted or cleaned up
+    pub fn listing_closed(&mut self) {
+        self.active_listings = self.active_listings.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats() -> SellerStats {
+        SellerStats {
+            marketplace: Pubkey::default(),
+            seller: Pubkey::default(),
+            volume: 0,
+            sales_count: 0,
+            listings_created: 0,
+            active_listings: 0,
+            listing_nonce: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn sales_add_to_volume_and_count() {
+        let mut stats = stats();
+        stats.record_sale(1_000);
+        stats.record_sale(500);
+        assert_eq!(stats.volume, 1_500);
+        assert_eq!(stats.sales_count, 2);
+    }
+
+    #[test]
+    fn volume_does_not_overflow_past_u64() {
+        let mut stats = stats();
+        stats.record_sale(u64::MAX);
+        stats.record_sale(u64::MAX);
+        assert_eq!(stats.volume, 2 * u64::MAX as u128);
+    }
+
+    #[test]
+    fn every_listing_gets_a_new_nonce() {
+        let mut stats = stats();
+        assert_eq!(stats.listing_opened().unwrap(), 0);
+        assert_eq!(stats.listing_opened().unwrap(), 1);
+        stats.listing_closed();
+        // Closing a listing never hands its nonce out again
+        assert_eq!(stats.listing_opened().unwrap(), 2);
+        assert_eq!(stats.listings_created, 3);
+        assert_eq!(stats.active_listings, 2);
+    }
+
+    #[test]
+    fn active_listings_saturate_at_zero() {
+        let mut stats = stats();
+        stats.listing_closed();
+        assert_eq!(stats.active_listings, 0);
+    }
+
+    #[test]
+    fn exhausted_nonces_are_an_error() {
+        let mut stats = stats();
+        stats.listing_nonce = u64::MAX;
+        assert!(stats.listing_opened().is_err());
+    }
+}
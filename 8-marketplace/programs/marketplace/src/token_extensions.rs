@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        default_account_state::DefaultAccountState, non_transferable::NonTransferable,
+        transfer_hook::TransferHook, BaseStateWithExtensions, StateWithExtensions,
+    },
+    state::{AccountState, Mint},
+};
+
+use crate::error::MarketplaceError;
+
+/// Reject Token-2022 NFT mints whose extensions would make a sale fail mid-transfer
+/// - Mints of the original token program have no extensions and always pass
+///
+/// # Arguments
+/// * `mint` - The NFT mint account
+///
+/// # Returns
+/// * `Result<()>` - Success, or the error naming the unsupported extension
+pub fn check_nft_extensions(mint: &AccountInfo) -> Result<()> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+
+    // Non-transferable tokens can never leave the seller's wallet
+    require!(
+        mint.get_extension::<NonTransferable>().is_err(),
+        MarketplaceError::NonTransferableNft
+    );
+
+    // The vault and the buyer's token account would be created frozen
+    if let Ok(default_state) = mint.get_extension::<DefaultAccountState>() {
+        require!(
+            default_state.state != AccountState::Frozen as u8,
+            MarketplaceError::FrozenByDefaultNft
+        );
+    }
+
+    // Transfer hooks need extra accounts the marketplace does not pass
+    if let Ok(hook) = mint.get_extension::<TransferHook>() {
+        require!(
+            Option::<Pubkey>::from(hook.program_id).is_none(),
+            MarketplaceError::TransferHookUnsupported
+        );
+    }
+
+    Ok(())
+}
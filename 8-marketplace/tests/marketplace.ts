@@ -1,33 +1,64 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import {
+  createFungible,
+  createFungibleAsset,
   createNft,
+  createProgrammableNft,
+  createV1,
+  fetchMetadataFromSeeds,
   findMasterEditionPda,
   findMetadataPda,
+  findTokenRecordPda,
+  mintV1,
+  updateV1,
   verifySizedCollectionItem,
   MPL_TOKEN_METADATA_PROGRAM_ID,
+  TokenStandard,
   mplTokenMetadata
 } from "@metaplex-foundation/mpl-token-metadata";
+import {
+  anyV2,
+  createOrUpdateV1,
+  findRuleSetPda,
+  mplTokenAuthRules,
+  programOwnedListV2,
+  MPL_TOKEN_AUTH_RULES_PROGRAM_ID,
+} from "@metaplex-foundation/mpl-token-auth-rules";
 import {
   createSignerFromKeypair,
   generateSigner,
   keypairIdentity,
   percentAmount,
   publicKey,
+  some,
   KeypairSigner,
 } from "@metaplex-foundation/umi";
 import { createUmi } from "@metaplex-foundation/umi-bundle-defaults";
 import {
   ASSOCIATED_TOKEN_PROGRAM_ID,
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getMint,
   getOrCreateAssociatedTokenAccount,
+  mintTo,
+  transfer,
 } from "@solana/spl-token";
 import {
+  AddressLookupTableProgram,
+  ComputeBudgetProgram,
   Keypair,
   LAMPORTS_PER_SOL,
   PublicKey,
   SystemProgram,
   SendTransactionError,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  TransactionInstruction,
+  TransactionMessage,
+  VersionedTransaction,
 } from "@solana/web3.js";
 import { assert } from "chai";
 import { Marketplace } from "../target/types/marketplace";
@@ -45,6 +76,8 @@ type MarketplaceContext = {
   treasury: PublicKey;
   price: anchor.BN;
   umi: ReturnType<typeof createUmi>;
+  // Token program of the NFT mint, SPL Token unless set
+  tokenProgram?: PublicKey;
 };
   const provider = anchor.getProvider();
 
@@ -54,7 +87,137 @@ describe("marketplace", () => {
   const program = anchor.workspace.marketplace as Program<Marketplace>;
   const connection = provider.connection;
 
-  const setupMarketplace = async (): Promise<MarketplaceContext> => {
+  // The empty name is the admin's original marketplace
+  const marketplacePda = (admin: PublicKey = provider.wallet.publicKey, name = "") =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("marketplace"), admin.toBuffer(), Buffer.from(name)],
+      program.programId
+    )[0];
+
+  const sellerStatsPda = (marketplace: PublicKey, seller: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("seller_stats"), marketplace.toBuffer(), seller.toBuffer()],
+      program.programId
+    )[0];
+
+  // Listings are derived from the seller's listing nonce, which starts at 0 and grows by one per listing
+  const listingPda = (
+    marketplace: PublicKey,
+    seller: PublicKey,
+    mint: PublicKey,
+    nonce: number | anchor.BN = 0
+  ) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("listing"),
+        marketplace.toBuffer(),
+        seller.toBuffer(),
+        mint.toBuffer(),
+        new anchor.BN(nonce).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+
+  // The address the seller's next listing will be created at
+  const nextListingPda = async (marketplace: PublicKey, seller: PublicKey, mint: PublicKey) => {
+    const stats = await program.account.sellerStats.fetchNullable(
+      sellerStatsPda(marketplace, seller),
+      "confirmed"
+    );
+    return listingPda(marketplace, seller, mint, stats ? stats.listingNonce : 0);
+  };
+
+  const listingIndexPageSize = Number(
+    program.idl.constants.find((c) => c.name === "listingIndexPageSize").value
+  );
+
+  const listingIndexPda = (marketplace: PublicKey, page: number) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("index"), marketplace.toBuffer(), new anchor.BN(page).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  // The index page the marketplace's next listing is appended to
+  const nextListingIndexPda = async (marketplace: PublicKey) => {
+    const { listingIndexLen } = await program.account.marketplace.fetch(marketplace, "confirmed");
+    return listingIndexPda(marketplace, Math.floor(listingIndexLen.toNumber() / listingIndexPageSize));
+  };
+
+  // The index accounts taking a closing listing out of its marketplace's listing index
+  const unindexAccounts = async (marketplace: PublicKey, listing: PublicKey) => {
+    const state = await program.account.listing.fetchNullable(listing, "confirmed");
+    if (!state || state.indexPosition === null) {
+      return { listingIndex: null, lastListingIndex: null, movedListing: null };
+    }
+
+    const { listingIndexLen } = await program.account.marketplace.fetch(marketplace, "confirmed");
+    const position = state.indexPosition.toNumber();
+    const last = listingIndexLen.toNumber() - 1;
+    const page = Math.floor(position / listingIndexPageSize);
+    const lastPage = Math.floor(last / listingIndexPageSize);
+    const lastIndex = listingIndexPda(marketplace, lastPage);
+    const movedListing =
+      last === position
+        ? null
+        : (await program.account.listingIndex.fetch(lastIndex, "confirmed")).listings[last % listingIndexPageSize];
+    return {
+      listingIndex: listingIndexPda(marketplace, page),
+      lastListingIndex: lastPage === page ? null : lastIndex,
+      movedListing,
+    };
+  };
+
+  // The index accounts of listings closed one after another by a batch, as (listing index, last
+  // listing index, moved listing) remaining accounts with the program id for those not needed
+  const unindexGroups = async (marketplace: PublicKey, listings: PublicKey[]) => {
+    const { listingIndexLen } = await program.account.marketplace.fetch(marketplace, "confirmed");
+    const entries: PublicKey[] = [];
+    for (let page = 0; page * listingIndexPageSize < listingIndexLen.toNumber(); page++) {
+      const index = await program.account.listingIndex.fetch(listingIndexPda(marketplace, page), "confirmed");
+      entries.push(...index.listings);
+    }
+
+    const none = { pubkey: program.programId, isWritable: false, isSigner: false };
+    const writable = (pubkey: PublicKey) => ({ pubkey, isWritable: true, isSigner: false });
+    // Replay the swap removals, so each listing sees the index the earlier ones left behind
+    return listings.map((listing) => {
+      const position = entries.findIndex((entry) => entry.equals(listing));
+      if (position === -1) {
+        return [none, none, none];
+      }
+      const last = entries.length - 1;
+      const page = Math.floor(position / listingIndexPageSize);
+      const lastPage = Math.floor(last / listingIndexPageSize);
+      const moved = entries.pop();
+      if (position !== last) {
+        entries[position] = moved;
+      }
+      return [
+        writable(listingIndexPda(marketplace, page)),
+        lastPage === page ? none : writable(listingIndexPda(marketplace, lastPage)),
+        position === last ? none : writable(moved),
+      ];
+    });
+  };
+
+  // Purchases compare the NFT's live metadata with the hash stored at listing
+  const metadataPda = (ctx: MarketplaceContext) =>
+    new PublicKey(findMetadataPda(ctx.umi, { mint: ctx.nftMint.publicKey })[0]);
+
+  // Point the context at the address its next listing will be created at
+  const refreshListing = async (ctx: MarketplaceContext) => {
+    const mint = new PublicKey(ctx.nftMint.publicKey);
+    ctx.listing = await nextListingPda(ctx.marketplace, ctx.maker.publicKey, mint);
+    ctx.vault = getAssociatedTokenAddressSync(mint, ctx.listing, true, ctx.tokenProgram ?? TOKEN_PROGRAM_ID);
+  };
+
+  // "verified" NFTs are verified members of the collection, "unverified" only claim it
+  // and "none" NFTs do not belong to any collection
+  const setupMarketplace = async (
+    collection: "verified" | "unverified" | "none" = "verified",
+    admin: PublicKey = provider.wallet.publicKey,
+    name = ""
+  ): Promise<MarketplaceContext> => {
     const umi = createUmi(connection);
     const creatorSigner = createSignerFromKeypair(
       umi,
@@ -73,25 +236,15 @@ describe("marketplace", () => {
 
     const price = new anchor.BN(0.05 * LAMPORTS_PER_SOL);
 
-    const [marketplace] = PublicKey.findProgramAddressSync(
-      [Buffer.from("marketplace")],
-      program.programId
-    );
+    const marketplace = marketplacePda(admin, name);
 
     const [treasury] = PublicKey.findProgramAddressSync(
       [Buffer.from("treasury"), marketplace.toBuffer()],
       program.programId
     );
 
-    const [listing] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from("listing"),
-        marketplace.toBuffer(),
-        maker.publicKey.toBuffer(),
-        new PublicKey(nftMint.publicKey).toBuffer(),
-      ],
-      program.programId
-    );
+    // The maker is fresh, so their first listing takes nonce 0
+    const listing = listingPda(marketplace, maker.publicKey, new PublicKey(nftMint.publicKey));
 
     for (const user of [maker, taker]) {
       const sig = await connection.requestAirdrop(user.publicKey, LAMPORTS_PER_SOL);
@@ -124,7 +277,8 @@ describe("marketplace", () => {
       symbol: "GM",
       uri: "https://arweave.net/123",
       sellerFeeBasisPoints: percentAmount(5.5),
-      collection: { verified: false, key: collectionMint.publicKey },
+      collection:
+        collection === "none" ? undefined : { verified: false, key: collectionMint.publicKey },
       tokenOwner: publicKey(maker.publicKey),
     }).sendAndConfirm(umi);
     console.log("✅ NFT minted:", nftMint.publicKey.toString());
@@ -133,22 +287,24 @@ describe("marketplace", () => {
     throw err;
   }
 
-  try {
-    console.log("🔍 Verifying Collection...");
-    await verifySizedCollectionItem(umi, {
-      metadata: findMetadataPda(umi, { mint: nftMint.publicKey }),
-      collectionAuthority: createSignerFromKeypair(
-        umi,
-        umi.eddsa.createKeypairFromSecretKey(new Uint8Array(provider.wallet.payer.secretKey))
-      ),
-      collectionMint: collectionMint.publicKey,
-      collection: findMetadataPda(umi, { mint: collectionMint.publicKey }),
-      collectionMasterEditionAccount: findMasterEditionPda(umi, { mint: collectionMint.publicKey }),
-    }).sendAndConfirm(umi);
-    console.log("✅ Collection verified.");
-  } catch (err) {
-    console.error("❌ Error during verifySizedCollectionItem:", err);
-    throw err;
+  if (collection === "verified") {
+    try {
+      console.log("🔍 Verifying Collection...");
+      await verifySizedCollectionItem(umi, {
+        metadata: findMetadataPda(umi, { mint: nftMint.publicKey }),
+        collectionAuthority: createSignerFromKeypair(
+          umi,
+          umi.eddsa.createKeypairFromSecretKey(new Uint8Array(provider.wallet.payer.secretKey))
+        ),
+        collectionMint: collectionMint.publicKey,
+        collection: findMetadataPda(umi, { mint: collectionMint.publicKey }),
+        collectionMasterEditionAccount: findMasterEditionPda(umi, { mint: collectionMint.publicKey }),
+      }).sendAndConfirm(umi);
+      console.log("✅ Collection verified.");
+    } catch (err) {
+      console.error("❌ Error during verifySizedCollectionItem:", err);
+      throw err;
+    }
   }
 
     const makerAta = (
@@ -180,6 +336,164 @@ describe("marketplace", () => {
     };
   };
 
+  const collectionConfigPda = (ctx: MarketplaceContext) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("collection"),
+        ctx.marketplace.toBuffer(),
+        new PublicKey(ctx.collectionMint.publicKey).toBuffer(),
+      ],
+      program.programId
+    )[0];
+
+  const addCollection = (ctx: MarketplaceContext, admin?: Keypair) =>
+    program.methods
+      .addCollection()
+      .accounts({
+        admin: admin ? admin.publicKey : provider.wallet.publicKey,
+        collectionMint: ctx.collectionMint.publicKey,
+        //@ts-ignore
+        collectionConfig: collectionConfigPda(ctx),
+        marketplace: ctx.marketplace,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(admin ? [admin] : [])
+      .rpc();
+
+  const setOpenListings = (openListings: boolean) =>
+    program.methods
+      .setOpenListings(openListings)
+      .accounts({
+        admin: provider.wallet.publicKey,
+        //@ts-ignore
+        marketplace: marketplacePda(),
+      })
+      .rpc();
+
+  const listContextNft = async (
+    ctx: MarketplaceContext,
+    expiry = 0,
+    quantity = 1,
+    allowedBuyer: PublicKey | null = null,
+    protectionWindowSecs = 0,
+    startTs = 0,
+    secondaryPrice: { mint: PublicKey; amount: anchor.BN } | null = null,
+    collectionStats: PublicKey | null = null,
+    category = 0
+  ) => {
+    const nftMetadata = findMetadataPda(ctx.umi, { mint: ctx.nftMint.publicKey });
+    const nftEdition = findMasterEditionPda(ctx.umi, { mint: ctx.nftMint.publicKey });
+
+    // Relisting gets a new address, so follow the seller's nonce
+    await refreshListing(ctx);
+
+    return program.methods
+      .listNft(
+        ctx.price,
+        new anchor.BN(expiry),
+        new anchor.BN(quantity),
+        allowedBuyer,
+        protectionWindowSecs,
+        new anchor.BN(startTs),
+        secondaryPrice,
+        category
+      )
+      .accounts({
+        seller: ctx.maker.publicKey,
+        nft: ctx.nftMint.publicKey,
+        //@ts-ignore
+        listing: ctx.listing,
+        listingTokenAccount: ctx.vault,
+        sellerTokenAccount: ctx.makerAta,
+        marketplace: ctx.marketplace,
+        listingIndex: await nextListingIndexPda(ctx.marketplace),
+        collectionMint: ctx.collectionMint.publicKey,
+        collectionConfig: collectionConfigPda(ctx),
+        collectionStats,
+        metadata: new PublicKey(nftMetadata[0]),
+        masterEdition: new PublicKey(nftEdition[0]),
+        sellerTokenRecord: null,
+        listingTokenRecord: null,
+        authorizationRules: null,
+        authorizationRulesProgram: null,
+        sysvarInstructions: null,
+        tokenProgram: ctx.tokenProgram ?? TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+      })
+      .signers([ctx.maker])
+      .rpc({ commitment: "confirmed" });
+  };
+
+  const purchaseContextNft = async (
+    ctx: MarketplaceContext,
+    feeRecipient = ctx.treasury,
+    saleEscrow: PublicKey | null = null,
+    referrer: PublicKey | null = null,
+    proceeds: PublicKey | null = null,
+    acceptChangedMetadata = false,
+    collectionStats: PublicKey | null = null,
+    buyerRewardsAccount: PublicKey | null = null,
+    feeSplitRecipients: PublicKey[] = []
+  ) =>
+    program.methods
+      .purchaseNft(acceptChangedMetadata)
+      .accounts({
+        ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+        buyer: ctx.taker.publicKey,
+        seller: ctx.maker.publicKey,
+        nft: ctx.nftMint.publicKey,
+        //@ts-ignore
+        marketplace: ctx.marketplace,
+        buyerTokenAccount: ctx.takerAta,
+        listingTokenAccount: ctx.vault,
+        sellerTokenAccount: null,
+        masterEdition: null,
+        metadata: metadataPda(ctx),
+        listingTokenRecord: null,
+        buyerTokenRecord: null,
+        authorizationRules: null,
+        authorizationRulesProgram: null,
+        sysvarInstructions: null,
+        metadataProgram: null,
+        listing: ctx.listing,
+        feeRecipient,
+        paymentMint: null,
+        buyerPaymentAccount: null,
+        sellerPaymentAccount: null,
+        feeRecipientPaymentAccount: null,
+        referrer,
+        referrerPaymentAccount: null,
+        saleEscrow,
+        proceeds,
+        collectionStats,
+        // Resolved to the buyer's reward points ATA unless overridden
+        ...(buyerRewardsAccount ? { buyerRewardsAccount } : {}),
+        systemProgram: SystemProgram.programId,
+        tokenProgram: ctx.tokenProgram ?? TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(feeSplitRecipients.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false })))
+      .signers([ctx.taker])
+      .rpc({ commitment: "confirmed" });
+
+  const fundedKeypair = async () => {
+    const keypair = Keypair.generate();
+    const sig = await connection.requestAirdrop(keypair.publicKey, LAMPORTS_PER_SOL);
+    await connection.confirmTransaction(sig);
+    return keypair;
+  };
+
+  const chainTime = async () => connection.getBlockTime(await connection.getSlot());
+
+  // The local validator cannot warp, so wait for the cluster clock to reach a timestamp
+  const waitForChainTime = async (ts: number) => {
+    while ((await chainTime()) < ts) {
+      await sleep(500);
+    }
+  };
+
   describe("marketplace flow", () => {
     let context: MarketplaceContext;
 
@@ -190,12 +504,13 @@ describe("marketplace", () => {
     it("initializes marketplace", async () => {
       try {
         const tx = await program.methods
-          .initializeMarketplace(1)
+          .initializeMarketplace(100, context.treasury, "")
           .accounts({
             admin: provider.wallet.publicKey,
             //@ts-ignore
             marketplace: context.marketplace,
             treasury: context.treasury,
+            paymentMint: null,
             systemProgram: SystemProgram.programId,
           })
           .rpc();
@@ -205,13 +520,19 @@ describe("marketplace", () => {
       }
     });
 
+    it("approves the NFT collection", async () => {
+      await addCollection(context);
+      const config = await program.account.collectionConfig.fetch(collectionConfigPda(context));
+      assert.ok(config.collectionMint.equals(new PublicKey(context.collectionMint.publicKey)));
+    });
+
     it("lists NFT", async () => {
       try {
         const nftMetadata = findMetadataPda(context.umi, { mint: context.nftMint.publicKey });
         const nftEdition = findMasterEditionPda(context.umi, { mint: context.nftMint.publicKey });
 
         const tx = await program.methods
-          .listNft(context.price)
+          .listNft(context.price, new anchor.BN(0), new anchor.BN(1), null, 0, new anchor.BN(0), null, 0)
           .accounts({
             seller: context.maker.publicKey,
             nft: context.nftMint.publicKey,
@@ -220,9 +541,17 @@ describe("marketplace", () => {
             listingTokenAccount: context.vault,
             sellerTokenAccount: context.makerAta,
             marketplace: context.marketplace,
+            listingIndex: await nextListingIndexPda(context.marketplace),
             collectionMint: context.collectionMint.publicKey,
+            collectionConfig: collectionConfigPda(context),
+            collectionStats: null,
             metadata: new PublicKey(nftMetadata[0]),
             masterEdition: new PublicKey(nftEdition[0]),
+            sellerTokenRecord: null,
+            listingTokenRecord: null,
+            authorizationRules: null,
+            authorizationRulesProgram: null,
+            sysvarInstructions: null,
             tokenProgram: TOKEN_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -237,19 +566,33 @@ describe("marketplace", () => {
     });
 
     it("delists NFT", async () => {
+      const sellerBefore = await connection.getBalance(context.maker.publicKey);
+      const rentRefund =
+        (await connection.getBalance(context.listing)) + (await connection.getBalance(context.vault));
       try {
         const tx = await program.methods
           .delistNft()
           .accounts({
+            ...(await unindexAccounts(context.marketplace, context.listing)),
             seller: context.maker.publicKey,
             nft: context.nftMint.publicKey,
             //@ts-ignore
             sellerTokenAccount: context.makerAta,
             listing: context.listing,
             listingTokenAccount: context.vault,
+            masterEdition: null,
+            metadata: null,
+            listingTokenRecord: null,
+            sellerTokenRecord: null,
+            authorizationRules: null,
+            authorizationRulesProgram: null,
+            sysvarInstructions: null,
+            associatedTokenProgram: null,
+            metadataProgram: null,
             marketplace: context.marketplace,
             systemProgram: SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
+            collectionStats: null,
           })
           .signers([context.maker])
           .rpc();
@@ -257,15 +600,25 @@ describe("marketplace", () => {
       } catch (err: any) {
         await handleTxError(err, "delists NFT");
       }
+
+      // The NFT goes back to the seller, and the listing and vault refund their rent
+      assert.equal(Number((await getAccount(connection, context.makerAta)).amount), 1);
+      const sellerAfter = await connection.getBalance(context.maker.publicKey);
+      assert.equal(sellerAfter - sellerBefore, rentRefund);
+      assert.isNull(await connection.getAccountInfo(context.listing));
+      assert.isNull(await connection.getAccountInfo(context.vault));
     });
 
     it("re-lists NFT", async () => {
+      const delisted = context.listing;
+      await refreshListing(context);
+      assert.isFalse(context.listing.equals(delisted));
       try {
         const nftMetadata = findMetadataPda(context.umi, { mint: context.nftMint.publicKey });
         const nftEdition = findMasterEditionPda(context.umi, { mint: context.nftMint.publicKey });
 
         const tx = await program.methods
-          .listNft(context.price)
+          .listNft(context.price, new anchor.BN(0), new anchor.BN(1), null, 0, new anchor.BN(0), null, 0)
           .accounts({
             seller: context.maker.publicKey,
             nft: context.nftMint.publicKey,
@@ -274,9 +627,17 @@ describe("marketplace", () => {
             listingTokenAccount: context.vault,
             sellerTokenAccount: context.makerAta,
             marketplace: context.marketplace,
+            listingIndex: await nextListingIndexPda(context.marketplace),
             collectionMint: context.collectionMint.publicKey,
+            collectionConfig: collectionConfigPda(context),
+            collectionStats: null,
             metadata: new PublicKey(nftMetadata[0]),
             masterEdition: new PublicKey(nftEdition[0]),
+            sellerTokenRecord: null,
+            listingTokenRecord: null,
+            authorizationRules: null,
+            authorizationRulesProgram: null,
+            sysvarInstructions: null,
             tokenProgram: TOKEN_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -290,11 +651,74 @@ describe("marketplace", () => {
       }
     });
 
+    it("rejects a price update from anyone but the seller", async () => {
+      await expectError(
+        program.methods
+          .updateListingPrice(context.price.muln(2))
+          .accounts({
+            seller: context.taker.publicKey,
+            nft: context.nftMint.publicKey,
+            //@ts-ignore
+            listing: context.listing,
+            marketplace: context.marketplace,
+          })
+          .signers([context.taker])
+          .rpc(),
+        "NotListingSeller"
+      );
+    });
+
+    it("rejects a zero price update", async () => {
+      await expectError(
+        program.methods
+          .updateListingPrice(new anchor.BN(0))
+          .accounts({
+            seller: context.maker.publicKey,
+            nft: context.nftMint.publicKey,
+            //@ts-ignore
+            listing: context.listing,
+            marketplace: context.marketplace,
+          })
+          .signers([context.maker])
+          .rpc(),
+        "InvalidPrice"
+      );
+    });
+
+    it("updates the listing price in place", async () => {
+      const newPrice = context.price.muln(2);
+      const tx = await program.methods
+        .updateListingPrice(newPrice)
+        .accounts({
+          seller: context.maker.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          marketplace: context.marketplace,
+        })
+        .signers([context.maker])
+        .rpc({ commitment: "confirmed" });
+
+      const [event] = await parseEvents(tx, "listingUpdatedEvent");
+      assert.ok(event.listing.equals(context.listing));
+      assert.ok(event.oldPrice.eq(context.price));
+      assert.ok(event.newPrice.eq(newPrice));
+
+      const listing = await program.account.listing.fetch(context.listing);
+      assert.ok(listing.price.eq(newPrice));
+      context.price = newPrice;
+    });
+
     it("purchases NFT", async () => {
+      const sellerBefore = await connection.getBalance(context.maker.publicKey);
+      const treasuryBefore = await connection.getBalance(context.treasury);
+      const rentRefund =
+        (await connection.getBalance(context.listing)) + (await connection.getBalance(context.vault));
       try {
         const tx = await program.methods
-          .purchaseNft()
+          .purchaseNft(false)
           .accounts({
+            ...(await unindexAccounts(context.marketplace, context.listing)),
             buyer: context.taker.publicKey,
             seller: context.maker.publicKey,
             nft: context.nftMint.publicKey,
@@ -302,8 +726,26 @@ describe("marketplace", () => {
             marketplace: context.marketplace,
             buyerTokenAccount: context.takerAta,
             listingTokenAccount: context.vault,
+            sellerTokenAccount: null,
+            masterEdition: null,
+            metadata: metadataPda(context),
+            listingTokenRecord: null,
+            buyerTokenRecord: null,
+            authorizationRules: null,
+            authorizationRulesProgram: null,
+            sysvarInstructions: null,
+            metadataProgram: null,
             listing: context.listing,
-            treasury: context.treasury,
+            feeRecipient: context.treasury,
+            paymentMint: null,
+            buyerPaymentAccount: null,
+            sellerPaymentAccount: null,
+            feeRecipientPaymentAccount: null,
+            referrer: null,
+            referrerPaymentAccount: null,
+            saleEscrow: null,
+            proceeds: null,
+            collectionStats: null,
             systemProgram: SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -314,25 +756,7366 @@ describe("marketplace", () => {
       } catch (err: any) {
         await handleTxError(err, "purchases NFT");
       }
+
+      // The purchase settles at the price stored in the listing when it executes,
+      // and the sold-out listing and vault refund their rent to the seller
+      const fee = context.price.muln(100).divn(10_000);
+      const sellerAfter = await connection.getBalance(context.maker.publicKey);
+      assert.equal(sellerAfter - sellerBefore, context.price.sub(fee).toNumber() + rentRefund);
+      assert.equal(await connection.getBalance(context.treasury), treasuryBefore + fee.toNumber());
+      assert.isNull(await connection.getAccountInfo(context.listing));
+      assert.isNull(await connection.getAccountInfo(context.vault));
     });
   });
-});
 
-function sleep(ms: number) {
-  return new Promise((resolve) => setTimeout(resolve, ms));
-}
+  describe("offers", () => {
+    let context: MarketplaceContext;
+    let bidder: Keypair;
+    let offer: PublicKey;
+    let bidderOffer: PublicKey;
+    let offerAmount: anchor.BN;
 
-async function handleTxError(err: any, label: string) {
-  console.error(`❌ Transaction failed during "${label}".`);
+    const offerPda = (listing: PublicKey, buyer: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("offer"), listing.toBuffer(), buyer.toBuffer()],
+        program.programId
+      )[0];
 
-  if (err.logs) {
-    console.error("🚨 Logs from simulation:\n", err.logs.join("\n"));
-  } else if (err instanceof SendTransactionError && err.getLogs) {
-      const logs = await err.getLogs(provider.connection);
-    console.error("🚨 Logs from getLogs():\n", logs?.join("\n") || "No logs found.");
-  } else {
-    console.error("❗Unknown error:\n", err);
-  }
+    const makeOffer = (buyer: Keypair, amount: anchor.BN, expiry: number) =>
+      program.methods
+        .makeOffer(amount, new anchor.BN(expiry))
+        .accounts({
+          buyer: buyer.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          offer: offerPda(context.listing, buyer.publicKey),
+          marketplace: context.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc({ commitment: "confirmed" });
 
-  throw err;
+    const acceptOffer = async (seller: Keypair) =>
+      program.methods
+        .acceptOffer()
+        .accounts({
+          seller: seller.publicKey,
+          buyer: context.taker.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          listingTokenAccount: context.vault,
+          buyerTokenAccount: context.takerAta,
+          offer,
+          marketplace: context.marketplace,
+          feeRecipient: context.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          paymentMint: null,
+          offerVault: null,
+          sellerPaymentAccount: null,
+          feeRecipientPaymentAccount: null,
+          ...(await unindexAccounts(context.marketplace, context.listing)),
+        })
+        .signers([seller])
+        .rpc({ commitment: "confirmed" });
+
+    const cancelOffer = (
+      authority: Keypair,
+      buyer: PublicKey,
+      offerKey: PublicKey,
+      instruction: "cancelOffer" | "refundExpiredOffer" = "cancelOffer"
+    ) =>
+      program.methods[instruction]()
+        .accounts({
+          authority: authority.publicKey,
+          buyer,
+          //@ts-ignore
+          offer: offerKey,
+          marketplace: context.marketplace,
+          listing: null,
+          paymentMint: null,
+          offerVault: null,
+          buyerPaymentAccount: null,
+          tokenProgram: null,
+        })
+        .signers([authority])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      context = await setupMarketplace();
+      bidder = await fundedKeypair();
+      offer = offerPda(context.listing, context.taker.publicKey);
+      bidderOffer = offerPda(context.listing, bidder.publicKey);
+      offerAmount = context.price.divn(2);
+      await addCollection(context);
+      await listContextNft(context);
+    });
+
+    it("rejects an offer that is already expired", async () => {
+      await expectError(
+        makeOffer(context.taker, offerAmount, (await chainTime()) - 1),
+        "InvalidOfferExpiry"
+      );
+    });
+
+    it("escrows the offered lamports", async () => {
+      const expiry = (await chainTime()) + 3600;
+      const tx = await makeOffer(context.taker, offerAmount, expiry);
+
+      const [event] = await parseEvents(tx, "offerMadeEvent");
+      assert.ok(event.offer.equals(offer));
+      assert.ok(event.amount.eq(offerAmount));
+      assert.equal(event.expiry.toNumber(), expiry);
+
+      const rent = await connection.getMinimumBalanceForRentExemption(8 + 32 + 32 + 32 + 8 + 8 + 1 + 9 + 8 + 33);
+      assert.equal(await connection.getBalance(offer), rent + offerAmount.toNumber());
+    });
+
+    it("only lets the buyer cancel an offer, and nobody refund it before it expires", async () => {
+      await makeOffer(bidder, offerAmount, (await chainTime()) + 5);
+      await expectError(
+        cancelOffer(context.maker, bidder.publicKey, bidderOffer),
+        "NotOfferBuyer"
+      );
+      await expectError(
+        cancelOffer(context.maker, bidder.publicKey, bidderOffer, "refundExpiredOffer"),
+        "OfferNotExpired"
+      );
+    });
+
+    it("rejects acceptance by anyone but the seller", async () => {
+      await expectError(acceptOffer(context.taker), "NotListingSeller");
+    });
+
+    it("accepts an offer and pays seller and treasury like a purchase", async () => {
+      const treasuryBefore = await connection.getBalance(context.treasury);
+      const takerBefore = await connection.getBalance(context.taker.publicKey);
+      const offerBalance = await connection.getBalance(offer);
+
+      const tx = await acceptOffer(context.maker);
+
+      const [event] = await parseEvents(tx, "offerAcceptedEvent");
+      assert.ok(event.buyer.equals(context.taker.publicKey));
+      assert.ok(event.amount.eq(offerAmount));
+
+      const fee = offerAmount.muln(100).divn(10_000).toNumber();
+      assert.equal(await connection.getBalance(context.treasury), treasuryBefore + fee);
+      // The buyer only gets the offer account rent back
+      assert.equal(
+        await connection.getBalance(context.taker.publicKey),
+        takerBefore + offerBalance - offerAmount.toNumber()
+      );
+
+      const nft = await connection.getTokenAccountBalance(context.takerAta);
+      assert.equal(nft.value.amount, "1");
+      assert.isNull(await connection.getAccountInfo(context.listing));
+      assert.isNull(await connection.getAccountInfo(context.vault));
+      assert.isNull(await connection.getAccountInfo(offer));
+    });
+
+    it("lets anyone refund an expired offer to the buyer", async () => {
+      await sleep(6000);
+      const bidderBefore = await connection.getBalance(bidder.publicKey);
+      const offerBalance = await connection.getBalance(bidderOffer);
+
+      const tx = await cancelOffer(context.maker, bidder.publicKey, bidderOffer, "refundExpiredOffer");
+
+      const [event] = await parseEvents(tx, "offerCancelledEvent");
+      assert.ok(event.cancelledBy.equals(context.maker.publicKey));
+      assert.ok(event.amount.eq(offerAmount));
+      assert.equal(await connection.getBalance(bidder.publicKey), bidderBefore + offerBalance);
+      assert.isNull(await connection.getAccountInfo(bidderOffer));
+    });
+  });
+
+  describe("treasury withdrawals", () => {
+    const marketplace = marketplacePda();
+    const [treasury] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury"), marketplace.toBuffer()],
+      program.programId
+    );
+    let destination: PublicKey;
+
+    before(async () => {
+      // A funded destination, so small withdrawals do not trip the rent check on a new account
+      destination = (await fundedKeypair()).publicKey;
+    });
+
+    const withdraw = (admin: Keypair | null, amount: number) => {
+      const builder = program.methods
+        .withdrawTreasury(new anchor.BN(amount))
+        .accounts({
+          admin: admin ? admin.publicKey : provider.wallet.publicKey,
+          destination,
+          //@ts-ignore
+          marketplace,
+          treasury,
+          systemProgram: SystemProgram.programId,
+        });
+      return (admin ? builder.signers([admin]) : builder).rpc({ commitment: "confirmed" });
+    };
+
+    it("rejects withdrawals by anyone but the admin", async () => {
+      const stranger = await fundedKeypair();
+      await expectError(withdraw(stranger, 1), "Unauthorized");
+    });
+
+    it("rejects a withdrawal that would dip below the rent floor", async () => {
+      const rentFloor = await connection.getMinimumBalanceForRentExemption(0);
+      const available = (await connection.getBalance(treasury)) - rentFloor;
+      assert.isAbove(available, 0);
+
+      await expectError(withdraw(null, available + 1), "InsufficientTreasuryBalance");
+    });
+
+    it("withdraws everything above the rent floor", async () => {
+      const rentFloor = await connection.getMinimumBalanceForRentExemption(0);
+      const available = (await connection.getBalance(treasury)) - rentFloor;
+      const destinationBefore = await connection.getBalance(destination);
+
+      const tx = await withdraw(null, available);
+
+      const [event] = await parseEvents(tx, "treasuryWithdrawEvent");
+      assert.ok(event.admin.equals(provider.wallet.publicKey));
+      assert.ok(event.destination.equals(destination));
+      assert.equal(event.amount.toNumber(), available);
+      assert.equal(event.remaining.toNumber(), rentFloor);
+
+      assert.equal(await connection.getBalance(treasury), rentFloor);
+      assert.equal(await connection.getBalance(destination), destinationBefore + available);
+      await expectError(withdraw(null, 1), "InsufficientTreasuryBalance");
+    });
+  });
+
+  describe("fee updates", () => {
+    const marketplace = marketplacePda();
+    const maxFeeBps = Number(
+      program.idl.constants.find((c) => c.name === "maxFeeBps").value.replace(/_/g, "")
+    );
+
+    const updateFee = (newFeeBps: number, admin?: Keypair) => {
+      const builder = program.methods
+        .updateFee(newFeeBps)
+        .accounts({
+          admin: admin ? admin.publicKey : provider.wallet.publicKey,
+          //@ts-ignore
+          marketplace,
+        });
+      return (admin ? builder.signers([admin]) : builder).rpc({ commitment: "confirmed" });
+    };
+
+    // Lists a fresh NFT, buys it and returns the treasury's cut
+    const saleFee = async (beforePurchase?: (ctx: MarketplaceContext) => Promise<unknown>) => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      await listContextNft(context);
+      if (beforePurchase) await beforePurchase(context);
+
+      const treasuryBefore = await connection.getBalance(context.treasury);
+      await purchaseContextNft(context);
+
+      return { context, fee: (await connection.getBalance(context.treasury)) - treasuryBefore };
+    };
+
+    afterEach(async () => {
+      await updateFee(100);
+    });
+
+    it("rejects fee updates by anyone but the admin", async () => {
+      const stranger = await fundedKeypair();
+      await expectError(updateFee(200, stranger), "Unauthorized");
+    });
+
+    it("rejects a fee above MAX_FEE_BPS", async () => {
+      await expectError(updateFee(maxFeeBps + 1), "InvalidFeeBps");
+    });
+
+    it("rejects a marketplace initialized above MAX_FEE_BPS", async () => {
+      const admin = await fundedKeypair();
+      const other = marketplacePda(admin.publicKey);
+      await expectError(
+        program.methods
+          .initializeMarketplace(maxFeeBps + 1, admin.publicKey, "")
+          .accounts({
+            admin: admin.publicKey,
+            //@ts-ignore
+            marketplace: other,
+            treasury: PublicKey.findProgramAddressSync(
+              [Buffer.from("treasury"), other.toBuffer()],
+              program.programId
+            )[0],
+            paymentMint: null,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc(),
+        "InvalidFeeBps"
+      );
+    });
+
+    it("updates the fee and charges it on existing listings", async () => {
+      let event: any;
+      const { context, fee } = await saleFee(async () => {
+        [event] = await parseEvents(await updateFee(500), "feeUpdatedEvent");
+      });
+
+      assert.equal(event.oldFeeBps, 100);
+      assert.equal(event.newFeeBps, 500);
+      assert.equal(fee, context.price.muln(500).divn(10_000).toNumber());
+    });
+
+    it("charges nothing at 0 bps", async () => {
+      await updateFee(0);
+      const { fee } = await saleFee();
+      assert.equal(fee, 0);
+    });
+
+    it("charges the maximum fee at MAX_FEE_BPS", async () => {
+      await updateFee(maxFeeBps);
+      const { context, fee } = await saleFee();
+      assert.equal(fee, context.price.muln(maxFeeBps).divn(10_000).toNumber());
+    });
+  });
+
+  describe("metadata checks", () => {
+    before(async () => {
+      await setOpenListings(true);
+    });
+
+    after(async () => {
+      await setOpenListings(false);
+    });
+
+    it("rejects a metadata account that does not derive from the NFT mint", async () => {
+      const context = await setupMarketplace();
+      // A real metadata account, but the one of the collection mint
+      const wrongMetadata = findMetadataPda(context.umi, { mint: context.collectionMint.publicKey });
+      const nftEdition = findMasterEditionPda(context.umi, { mint: context.nftMint.publicKey });
+
+      await expectError(
+        program.methods
+          .listNft(context.price, new anchor.BN(0), new anchor.BN(1), null, 0, new anchor.BN(0), null, 0)
+          .accounts({
+            seller: context.maker.publicKey,
+            nft: context.nftMint.publicKey,
+            //@ts-ignore
+            listing: context.listing,
+            listingTokenAccount: context.vault,
+            sellerTokenAccount: context.makerAta,
+            marketplace: context.marketplace,
+            listingIndex: await nextListingIndexPda(context.marketplace),
+            collectionMint: context.collectionMint.publicKey,
+            collectionConfig: collectionConfigPda(context),
+            collectionStats: null,
+            metadata: new PublicKey(wrongMetadata[0]),
+            masterEdition: new PublicKey(nftEdition[0]),
+            sellerTokenRecord: null,
+            listingTokenRecord: null,
+            authorizationRules: null,
+            authorizationRulesProgram: null,
+            sysvarInstructions: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+          })
+          .signers([context.maker])
+          .rpc(),
+        "ConstraintSeeds"
+      );
+    });
+
+    it("rejects an NFT claiming a collection that is not verified", async () => {
+      const context = await setupMarketplace("unverified");
+      await expectError(listContextNft(context), "CollectionNotVerified");
+    });
+
+    it("stores the verified collection on the listing", async () => {
+      const context = await setupMarketplace();
+      await listContextNft(context);
+
+      const listing = await program.account.listing.fetch(context.listing);
+      assert.ok(listing.collection.equals(new PublicKey(context.collectionMint.publicKey)));
+    });
+
+    it("stores no collection for NFTs outside a collection", async () => {
+      const context = await setupMarketplace("none");
+      await listContextNft(context);
+
+      const listing = await program.account.listing.fetch(context.listing);
+      assert.isNull(listing.collection);
+    });
+  });
+
+  describe("collection whitelist", () => {
+    it("rejects NFTs from a collection that was not approved", async () => {
+      const context = await setupMarketplace();
+      await expectError(listContextNft(context), "CollectionNotAllowed");
+    });
+
+    it("only lets the admin approve collections", async () => {
+      const context = await setupMarketplace();
+      await expectError(
+        program.methods
+          .addCollection()
+          .accounts({
+            admin: context.maker.publicKey,
+            collectionMint: context.collectionMint.publicKey,
+            //@ts-ignore
+            collectionConfig: collectionConfigPda(context),
+            marketplace: context.marketplace,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([context.maker])
+          .rpc(),
+        "Unauthorized"
+      );
+    });
+
+    it("stops new listings once a collection is removed", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      await program.methods
+        .removeCollection()
+        .accounts({
+          admin: provider.wallet.publicKey,
+          //@ts-ignore
+          collectionConfig: collectionConfigPda(context),
+          marketplace: context.marketplace,
+        })
+        .rpc();
+
+      assert.isNull(await connection.getAccountInfo(collectionConfigPda(context)));
+      await expectError(listContextNft(context), "CollectionNotAllowed");
+    });
+
+    it("skips the check when listings are open", async () => {
+      const context = await setupMarketplace();
+      await setOpenListings(true);
+      try {
+        await listContextNft(context);
+        const listing = await program.account.listing.fetch(context.listing);
+        assert.ok(listing.seller.equals(context.maker.publicKey));
+      } finally {
+        await setOpenListings(false);
+      }
+    });
+  });
+
+  describe("listing expiry", () => {
+    const purchase = async (ctx: MarketplaceContext) =>
+      program.methods
+        .purchaseNft(false)
+        .accounts({
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          buyer: ctx.taker.publicKey,
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          buyerTokenAccount: ctx.takerAta,
+          listingTokenAccount: ctx.vault,
+          sellerTokenAccount: null,
+          masterEdition: null,
+          metadata: metadataPda(ctx),
+          listingTokenRecord: null,
+          buyerTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          metadataProgram: null,
+          listing: ctx.listing,
+          feeRecipient: ctx.treasury,
+          paymentMint: null,
+          buyerPaymentAccount: null,
+          sellerPaymentAccount: null,
+          feeRecipientPaymentAccount: null,
+          referrer: null,
+          referrerPaymentAccount: null,
+          saleEscrow: null,
+          proceeds: null,
+          collectionStats: null,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([ctx.taker])
+        .rpc();
+
+    const cleanExpired = async (ctx: MarketplaceContext, cleaner: Keypair) =>
+      program.methods
+        .cleanExpiredListing()
+        .accounts({
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          cleaner: cleaner.publicKey,
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          sellerTokenAccount: ctx.makerAta,
+          marketplace: ctx.marketplace,
+          treasury: ctx.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([cleaner])
+        .rpc({ commitment: "confirmed" });
+
+    const setCrankReward = (ctx: MarketplaceContext, lamports: number, admin = provider.wallet.payer) =>
+      program.methods
+        .setCrankReward(new anchor.BN(lamports))
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+        })
+        .signers([admin])
+        .rpc();
+
+    // Lists a fresh NFT that expires in a few seconds and waits for the expiry
+    const expiredListing = async (admin = provider.wallet.publicKey, collectionAdmin?: Keypair) => {
+      const context = await setupMarketplace("verified", admin);
+      await addCollection(context, collectionAdmin);
+      const expiry = (await chainTime()) + 3;
+      await listContextNft(context, expiry);
+      await waitForChainTime(expiry);
+      return context;
+    };
+
+    it("rejects an expiry in the past", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      await expectError(listContextNft(context, (await chainTime()) - 1), "InvalidListingExpiry");
+    });
+
+    it("rejects purchases and cleanup around the expiry", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const expiry = (await chainTime()) + 5;
+      await listContextNft(context, expiry);
+
+      const listing = await program.account.listing.fetch(context.listing);
+      assert.equal(listing.expiry.toNumber(), expiry);
+      await expectError(cleanExpired(context, context.taker), "ListingNotExpired");
+
+      await waitForChainTime(expiry);
+      await expectError(purchase(context), "ListingExpired");
+    });
+
+    it("lets anyone return the NFT of an expired listing to the seller", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const expiry = (await chainTime()) + 3;
+      await listContextNft(context, expiry);
+      await waitForChainTime(expiry);
+
+      const tx = await cleanExpired(context, context.taker);
+
+      const [event] = await parseEvents(tx, "expiredListingCleanedEvent");
+      assert.ok(event.listing.equals(context.listing));
+      assert.ok(event.cleanedBy.equals(context.taker.publicKey));
+      assert.equal(event.expiry.toNumber(), expiry);
+
+      const nft = await connection.getTokenAccountBalance(context.makerAta);
+      assert.equal(nft.value.amount, "1");
+      assert.isNull(await connection.getAccountInfo(context.listing));
+      assert.isNull(await connection.getAccountInfo(context.vault));
+    });
+
+    it("caps the crank reward and restricts it to the admin", async () => {
+      const context = await setupMarketplace();
+      const maxCrankRewardLamports = Number(
+        program.idl.constants.find((c) => c.name === "maxCrankRewardLamports").value.replace(/_/g, "")
+      );
+      await expectError(setCrankReward(context, maxCrankRewardLamports + 1), "InvalidCrankReward");
+      await expectError(setCrankReward(context, 5_000, await fundedKeypair()), "Unauthorized");
+    });
+
+    it("pays a third-party cleaner the crank reward from the treasury", async () => {
+      const reward = 5_000;
+      const context = await expiredListing();
+      await setCrankReward(context, reward);
+      try {
+        // Make sure the treasury holds more than its rent-exempt minimum
+        await provider.sendAndConfirm(
+          new anchor.web3.Transaction().add(
+            SystemProgram.transfer({
+              fromPubkey: provider.wallet.publicKey,
+              toPubkey: context.treasury,
+              lamports: 0.01 * LAMPORTS_PER_SOL,
+            })
+          )
+        );
+        const cleaner = await fundedKeypair();
+        const balances = async () =>
+          Promise.all(
+            [cleaner.publicKey, context.treasury, context.maker.publicKey].map((key) =>
+              connection.getBalance(key)
+            )
+          );
+        const [cleanerBefore, treasuryBefore, sellerBefore] = await balances();
+        const rent =
+          (await connection.getBalance(context.listing)) + (await connection.getBalance(context.vault));
+
+        const tx = await cleanExpired(context, cleaner);
+
+        const [cleanerAfter, treasuryAfter, sellerAfter] = await balances();
+        // The provider wallet pays the transaction fee, so the cleaner only gains the reward
+        assert.equal(cleanerAfter, cleanerBefore + reward);
+        assert.equal(treasuryAfter, treasuryBefore - reward);
+        assert.equal(sellerAfter, sellerBefore + rent);
+        const [event] = await parseEvents(tx, "expiredListingCleanedEvent");
+        assert.ok(event.reward.eqn(reward));
+      } finally {
+        await setCrankReward(context, 0);
+      }
+    });
+
+    it("skips the crank reward when the treasury cannot cover it", async () => {
+      const admin = await fundedKeypair();
+      const setup = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .initializeMarketplace(100, setup.treasury, "")
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: setup.marketplace,
+          treasury: setup.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      await setCrankReward(setup, 5_000, admin);
+      const context = await expiredListing(admin.publicKey, admin);
+      const cleaner = await fundedKeypair();
+      const cleanerBefore = await connection.getBalance(cleaner.publicKey);
+
+      // The fresh treasury holds nothing, yet the listing is still cleaned up
+      const [event] = await parseEvents(await cleanExpired(context, cleaner), "expiredListingCleanedEvent");
+
+      assert.ok(event.reward.eqn(0));
+      assert.equal(await connection.getBalance(cleaner.publicKey), cleanerBefore);
+      assert.isNull(await connection.getAccountInfo(context.listing));
+    });
+
+    it("still lets the seller delist an expired listing", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const expiry = (await chainTime()) + 3;
+      await listContextNft(context, expiry);
+      await waitForChainTime(expiry);
+
+      await program.methods
+        .delistNft()
+        .accounts({
+          ...(await unindexAccounts(context.marketplace, context.listing)),
+          seller: context.maker.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          sellerTokenAccount: context.makerAta,
+          listing: context.listing,
+          listingTokenAccount: context.vault,
+          masterEdition: null,
+          metadata: null,
+          listingTokenRecord: null,
+          sellerTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          associatedTokenProgram: null,
+          metadataProgram: null,
+          marketplace: context.marketplace,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          collectionStats: null,
+        })
+        .signers([context.maker])
+        .rpc();
+
+      const nft = await connection.getTokenAccountBalance(context.makerAta);
+      assert.equal(nft.value.amount, "1");
+    });
+  });
+
+  describe("spl token payments", () => {
+    let usdcAdmin: Keypair;
+    let usdc: PublicKey;
+    let context: MarketplaceContext;
+
+    const purchase = async (
+      ctx: MarketplaceContext,
+      withPaymentAccounts: boolean,
+      feeSplitRecipients: PublicKey[] = []
+    ) =>
+      program.methods
+        .purchaseNft(false)
+        .accounts({
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          buyer: ctx.taker.publicKey,
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          buyerTokenAccount: ctx.takerAta,
+          listingTokenAccount: ctx.vault,
+          sellerTokenAccount: null,
+          masterEdition: null,
+          metadata: metadataPda(ctx),
+          listingTokenRecord: null,
+          buyerTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          metadataProgram: null,
+          listing: ctx.listing,
+          feeRecipient: ctx.treasury,
+          paymentMint: withPaymentAccounts ? usdc : null,
+          buyerPaymentAccount: withPaymentAccounts
+            ? getAssociatedTokenAddressSync(usdc, ctx.taker.publicKey)
+            : null,
+          sellerPaymentAccount: withPaymentAccounts
+            ? getAssociatedTokenAddressSync(usdc, ctx.maker.publicKey)
+            : null,
+          feeRecipientPaymentAccount: withPaymentAccounts
+            ? getAssociatedTokenAddressSync(usdc, ctx.treasury, true)
+            : null,
+          referrer: null,
+          referrerPaymentAccount: null,
+          saleEscrow: null,
+          proceeds: null,
+          collectionStats: null,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(feeSplitRecipients.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false })))
+        .signers([ctx.taker])
+        .rpc();
+
+    before(async () => {
+      usdcAdmin = await fundedKeypair();
+      usdc = await createMint(connection, provider.wallet.payer, provider.wallet.publicKey, null, 6);
+
+      context = await setupMarketplace("verified", usdcAdmin.publicKey);
+      context.price = new anchor.BN(25_000_000);
+
+      await program.methods
+        .initializeMarketplace(100, context.treasury, "")
+        .accounts({
+          admin: usdcAdmin.publicKey,
+          //@ts-ignore
+          marketplace: context.marketplace,
+          treasury: context.treasury,
+          paymentMint: usdc,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([usdcAdmin])
+        .rpc();
+      await addCollection(context, usdcAdmin);
+
+      const takerUsdc = await getOrCreateAssociatedTokenAccount(
+        connection,
+        provider.wallet.payer,
+        usdc,
+        context.taker.publicKey
+      );
+      await mintTo(connection, provider.wallet.payer, usdc, takerUsdc.address, provider.wallet.payer, 100_000_000);
+    });
+
+    it("stores the payment mint on the marketplace", async () => {
+      const marketplace = await program.account.marketplace.fetch(context.marketplace);
+      assert.ok(marketplace.paymentMint.equals(usdc));
+    });
+
+    it("lists an NFT priced in USDC base units", async () => {
+      await listContextNft(context);
+      const listing = await program.account.listing.fetch(context.listing);
+      assert.ok(listing.price.eq(context.price));
+    });
+
+    it("rejects SOL offers on a USDC marketplace", async () => {
+      await expectError(
+        program.methods
+          .makeOffer(new anchor.BN(1_000), new anchor.BN((await chainTime()) + 3600))
+          .accounts({
+            buyer: context.taker.publicKey,
+            nft: context.nftMint.publicKey,
+            //@ts-ignore
+            listing: context.listing,
+            marketplace: context.marketplace,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([context.taker])
+          .rpc(),
+        "NativePaymentOnly"
+      );
+    });
+
+    it("rejects a purchase without the payment token accounts", async () => {
+      await expectError(purchase(context, false), "MissingPaymentAccounts");
+    });
+
+    it("pays the seller and treasury in USDC", async () => {
+      const sellerSol = await connection.getBalance(context.maker.publicKey);
+
+      await purchase(context, true);
+
+      const fee = context.price.muln(100).divn(10_000);
+      const balance = async (owner: PublicKey) =>
+        (await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(usdc, owner, true))).value.amount;
+      assert.equal(await balance(context.maker.publicKey), context.price.sub(fee).toString());
+      assert.equal(await balance(context.treasury), fee.toString());
+      assert.equal(await balance(context.taker.publicKey), new anchor.BN(100_000_000).sub(context.price).toString());
+
+      // No lamports change hands for the sale itself
+      assert.equal(await connection.getBalance(context.maker.publicKey), sellerSol);
+      const nft = await connection.getTokenAccountBalance(context.takerAta);
+      assert.equal(nft.value.amount, "1");
+    });
+
+    it("splits the USDC fee among the recipients' token accounts, the dust going to the first", async () => {
+      const ctx = await setupMarketplace("verified", usdcAdmin.publicKey);
+      ctx.price = new anchor.BN(25_000_700);
+      await addCollection(ctx, usdcAdmin);
+      await listContextNft(ctx);
+      const takerUsdc = await getOrCreateAssociatedTokenAccount(connection, provider.wallet.payer, usdc, ctx.taker.publicKey);
+      await mintTo(connection, provider.wallet.payer, usdc, takerUsdc.address, provider.wallet.payer, 100_000_000);
+
+      const recipients = [(await fundedKeypair()).publicKey, (await fundedKeypair()).publicKey];
+      const recipientAccounts = await Promise.all(
+        recipients.map(
+          async (recipient) =>
+            (await getOrCreateAssociatedTokenAccount(connection, provider.wallet.payer, usdc, recipient)).address
+        )
+      );
+      const setFeeSplits = (splits: { recipient: PublicKey; bps: number }[]) =>
+        program.methods
+          .setFeeSplits(splits)
+          .accounts({
+            admin: usdcAdmin.publicKey,
+            //@ts-ignore
+            marketplace: ctx.marketplace,
+          })
+          .signers([usdcAdmin])
+          .rpc();
+      await setFeeSplits([
+        { recipient: recipients[0], bps: 7_000 },
+        { recipient: recipients[1], bps: 3_000 },
+      ]);
+
+      // Token purchases pay the recipients' USDC accounts, not their wallets
+      await expectError(purchase(ctx, true, recipients), "InvalidFeeRecipient");
+      await expectError(purchase(ctx, true, [...recipientAccounts].reverse()), "InvalidFeeRecipient");
+
+      const balance = async (owner: PublicKey) =>
+        Number((await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(usdc, owner, true))).value.amount);
+      const treasuryBefore = await balance(ctx.treasury);
+      await purchase(ctx, true, recipientAccounts);
+
+      // 1% of 25_000_700 is 250_007: 175_004 and 75_002 rounded down, 1 unit of dust
+      assert.equal(await balance(recipients[0]), 175_005);
+      assert.equal(await balance(recipients[1]), 75_002);
+      assert.equal(await balance(ctx.treasury), treasuryBefore);
+      assert.equal(await balance(ctx.maker.publicKey), 25_000_700 - 250_007);
+
+      await setFeeSplits([]);
+    });
+  });
+
+  describe("english auction", () => {
+    const startPrice = new anchor.BN(0.01 * LAMPORTS_PER_SOL);
+    const minIncrement = new anchor.BN(0.005 * LAMPORTS_PER_SOL);
+
+    const auctionAccounts = (ctx: MarketplaceContext) => {
+      const [auction] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("auction"),
+          ctx.marketplace.toBuffer(),
+          ctx.maker.publicKey.toBuffer(),
+          new PublicKey(ctx.nftMint.publicKey).toBuffer(),
+        ],
+        program.programId
+      );
+      const [bidEscrow] = PublicKey.findProgramAddressSync(
+        [Buffer.from("bid_escrow"), auction.toBuffer()],
+        program.programId
+      );
+      const vault = getAssociatedTokenAddressSync(new PublicKey(ctx.nftMint.publicKey), auction, true);
+      return { auction, bidEscrow, vault };
+    };
+
+    const createAuction = (
+      ctx: MarketplaceContext,
+      endTime: number,
+      reservePrice = new anchor.BN(0),
+      reserveHidden = false,
+      buyNowPrice: anchor.BN | null = null
+    ) => {
+      const { auction, bidEscrow, vault } = auctionAccounts(ctx);
+      const nftMetadata = findMetadataPda(ctx.umi, { mint: ctx.nftMint.publicKey });
+      const nftEdition = findMasterEditionPda(ctx.umi, { mint: ctx.nftMint.publicKey });
+
+      return program.methods
+        .createAuction(startPrice, minIncrement, new anchor.BN(endTime), reservePrice, reserveHidden, buyNowPrice)
+        .accounts({
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          auction,
+          auctionTokenAccount: vault,
+          sellerTokenAccount: ctx.makerAta,
+          bidEscrow,
+          marketplace: ctx.marketplace,
+          collectionMint: ctx.collectionMint.publicKey,
+          collectionConfig: collectionConfigPda(ctx),
+          metadata: new PublicKey(nftMetadata[0]),
+          masterEdition: new PublicKey(nftEdition[0]),
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+    };
+
+    const placeBid = (
+      ctx: MarketplaceContext,
+      bidder: Keypair,
+      amount: anchor.BN,
+      previousBidder: PublicKey | null
+    ) => {
+      const { auction, bidEscrow } = auctionAccounts(ctx);
+      return program.methods
+        .placeBid(amount)
+        .accounts({
+          bidder: bidder.publicKey,
+          //@ts-ignore
+          auction,
+          bidEscrow,
+          previousBidder,
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([bidder])
+        .rpc({ commitment: "confirmed" });
+    };
+
+    // Below the reserve the highest bidder is still passed, to be refunded, and the NFT returns to the seller
+    const settleAuction = (
+      ctx: MarketplaceContext,
+      settler: Keypair,
+      winner: PublicKey | null,
+      belowReserve = false
+    ) => {
+      const { auction, bidEscrow, vault } = auctionAccounts(ctx);
+      const nft = new PublicKey(ctx.nftMint.publicKey);
+      return program.methods
+        .settleAuction()
+        .accounts({
+          settler: settler.publicKey,
+          seller: ctx.maker.publicKey,
+          winner,
+          nft,
+          //@ts-ignore
+          auction,
+          auctionTokenAccount: vault,
+          bidEscrow,
+          winnerTokenAccount: winner && !belowReserve ? getAssociatedTokenAddressSync(nft, winner) : null,
+          sellerTokenAccount: winner && !belowReserve ? null : ctx.makerAta,
+          marketplace: ctx.marketplace,
+          feeRecipient: ctx.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([settler])
+        .rpc({ commitment: "confirmed" });
+    };
+
+    describe("with bids", () => {
+      let context: MarketplaceContext;
+      let rival: Keypair;
+      let endTime: number;
+
+      before(async () => {
+        context = await setupMarketplace();
+        rival = await fundedKeypair();
+        await addCollection(context);
+      });
+
+      it("escrows the NFT when the auction is created", async () => {
+        endTime = (await chainTime()) + 15;
+        const tx = await createAuction(context, endTime);
+
+        const [event] = await parseEvents(tx, "auctionCreatedEvent");
+        assert.ok(event.seller.equals(context.maker.publicKey));
+        assert.ok(event.startPrice.eq(startPrice));
+        assert.equal(event.endTime.toNumber(), endTime);
+
+        const { vault } = auctionAccounts(context);
+        const nft = await connection.getTokenAccountBalance(vault);
+        assert.equal(nft.value.amount, "1");
+      });
+
+      it("rejects a first bid below the start price", async () => {
+        await expectError(placeBid(context, context.taker, startPrice.subn(1), null), "BidTooLow");
+      });
+
+      it("escrows the first bid", async () => {
+        const { bidEscrow } = auctionAccounts(context);
+        const escrowBefore = await connection.getBalance(bidEscrow);
+
+        const tx = await placeBid(context, context.taker, startPrice, null);
+
+        const [event] = await parseEvents(tx, "bidPlacedEvent");
+        assert.isNull(event.previousBidder);
+        assert.equal(await connection.getBalance(bidEscrow), escrowBefore + startPrice.toNumber());
+      });
+
+      it("rejects a bid below the highest bid plus the minimum increment", async () => {
+        const amount = startPrice.add(minIncrement).subn(1);
+        await expectError(placeBid(context, rival, amount, context.taker.publicKey), "BidTooLow");
+      });
+
+      it("requires the outbid bidder to refund them", async () => {
+        const amount = startPrice.add(minIncrement);
+        await expectError(placeBid(context, rival, amount, rival.publicKey), "InvalidBidder");
+      });
+
+      it("refunds the previous bidder when outbid", async () => {
+        const amount = startPrice.add(minIncrement);
+        const takerBefore = await connection.getBalance(context.taker.publicKey);
+
+        const tx = await placeBid(context, rival, amount, context.taker.publicKey);
+
+        const [event] = await parseEvents(tx, "bidPlacedEvent");
+        assert.ok(event.previousBidder.equals(context.taker.publicKey));
+        assert.ok(event.previousBid.eq(startPrice));
+        assert.equal(
+          await connection.getBalance(context.taker.publicKey),
+          takerBefore + startPrice.toNumber()
+        );
+      });
+
+      it("cannot be settled before the end time", async () => {
+        await expectError(settleAuction(context, context.taker, rival.publicKey), "AuctionNotEnded");
+      });
+
+      it("rejects bids after the end time", async () => {
+        await waitForChainTime(endTime);
+        const amount = startPrice.add(minIncrement.muln(2));
+        await expectError(placeBid(context, context.taker, amount, rival.publicKey), "AuctionEnded");
+      });
+
+      it("sends the NFT to the winner and pays the seller minus the fee", async () => {
+        const { auction, bidEscrow, vault } = auctionAccounts(context);
+        const amount = startPrice.add(minIncrement);
+        const fee = amount.muln(100).divn(10_000).toNumber();
+        const treasuryBefore = await connection.getBalance(context.treasury);
+        const sellerBefore = await connection.getBalance(context.maker.publicKey);
+        const rents =
+          (await connection.getBalance(auction)) +
+          (await connection.getBalance(vault)) +
+          (await connection.getBalance(bidEscrow)) -
+          amount.toNumber();
+
+        const tx = await settleAuction(context, context.taker, rival.publicKey);
+
+        const [event] = await parseEvents(tx, "auctionSettledEvent");
+        assert.ok(event.winner.equals(rival.publicKey));
+        assert.ok(event.amount.eq(amount));
+        assert.equal(event.fee.toNumber(), fee);
+
+        assert.equal(await connection.getBalance(context.treasury), treasuryBefore + fee);
+        assert.equal(
+          await connection.getBalance(context.maker.publicKey),
+          sellerBefore + amount.toNumber() - fee + rents
+        );
+        const nft = await connection.getTokenAccountBalance(
+          getAssociatedTokenAddressSync(new PublicKey(context.nftMint.publicKey), rival.publicKey)
+        );
+        assert.equal(nft.value.amount, "1");
+        assert.isNull(await connection.getAccountInfo(auction));
+        assert.isNull(await connection.getAccountInfo(vault));
+        assert.equal(await connection.getBalance(bidEscrow), 0);
+      });
+    });
+
+    it("returns the NFT to the seller when nobody bid", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const endTime = (await chainTime()) + 3;
+      await createAuction(context, endTime);
+      await waitForChainTime(endTime);
+
+      const tx = await settleAuction(context, context.taker, null);
+
+      const [event] = await parseEvents(tx, "auctionSettledEvent");
+      assert.isNull(event.winner);
+      assert.equal(event.fee.toNumber(), 0);
+      const nft = await connection.getTokenAccountBalance(context.makerAta);
+      assert.equal(nft.value.amount, "1");
+      assert.isNull(await connection.getAccountInfo(auctionAccounts(context).auction));
+    });
+
+    const buyNow = (ctx: MarketplaceContext, buyer: Keypair, previousBidder: PublicKey | null) => {
+      const { auction, bidEscrow, vault } = auctionAccounts(ctx);
+      const nft = new PublicKey(ctx.nftMint.publicKey);
+      return program.methods
+        .buyNow()
+        .accounts({
+          buyer: buyer.publicKey,
+          seller: ctx.maker.publicKey,
+          nft,
+          //@ts-ignore
+          auction,
+          auctionTokenAccount: vault,
+          bidEscrow,
+          previousBidder,
+          buyerTokenAccount: getAssociatedTokenAddressSync(nft, buyer.publicKey),
+          marketplace: ctx.marketplace,
+          feeRecipient: ctx.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([buyer])
+        .rpc({ commitment: "confirmed" });
+    };
+
+    it("rejects buy-now prices below the start or reserve price", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const endTime = (await chainTime()) + 60;
+
+      await expectError(
+        createAuction(context, endTime, new anchor.BN(0), false, startPrice.subn(1)),
+        "InvalidBuyNowPrice"
+      );
+      await expectError(
+        createAuction(context, endTime, startPrice.muln(3), false, startPrice.muln(2)),
+        "InvalidBuyNowPrice"
+      );
+    });
+
+    it("hides a hidden reserve from the creation event", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const reserve = startPrice.muln(2);
+
+      const tx = await createAuction(context, (await chainTime()) + 60, reserve, true);
+
+      const [event] = await parseEvents(tx, "auctionCreatedEvent");
+      assert.isNull(event.reservePrice);
+      const auction = await program.account.auction.fetch(auctionAccounts(context).auction);
+      assert.ok(auction.reservePrice.eq(reserve));
+    });
+
+    it("returns the NFT and refunds the top bidder when bidding ends below the reserve", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const endTime = (await chainTime()) + 5;
+      await createAuction(context, endTime, startPrice.muln(2));
+      await placeBid(context, context.taker, startPrice, null);
+      await waitForChainTime(endTime);
+
+      const takerBefore = await connection.getBalance(context.taker.publicKey);
+      const treasuryBefore = await connection.getBalance(context.treasury);
+      const settler = await fundedKeypair();
+
+      const tx = await settleAuction(context, settler, context.taker.publicKey, true);
+
+      const [event] = await parseEvents(tx, "auctionSettledEvent");
+      assert.isNull(event.winner);
+      assert.isFalse(event.reserveMet);
+      assert.equal(event.fee.toNumber(), 0);
+      assert.equal(
+        await connection.getBalance(context.taker.publicKey),
+        takerBefore + startPrice.toNumber()
+      );
+      assert.equal(await connection.getBalance(context.treasury), treasuryBefore);
+      const nft = await connection.getTokenAccountBalance(context.makerAta);
+      assert.equal(nft.value.amount, "1");
+      assert.isNull(await connection.getAccountInfo(auctionAccounts(context).auction));
+    });
+
+    it("lets a buy-now beat a racing bid, refunding the top bidder", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const rival = await fundedKeypair();
+      const buyNowPrice = startPrice.muln(4);
+      await createAuction(context, (await chainTime()) + 60, new anchor.BN(0), false, buyNowPrice);
+      await placeBid(context, context.taker, startPrice, null);
+
+      const takerBefore = await connection.getBalance(context.taker.publicKey);
+      const treasuryBefore = await connection.getBalance(context.treasury);
+      const fee = buyNowPrice.muln(100).divn(10_000).toNumber();
+
+      const tx = await buyNow(context, rival, context.taker.publicKey);
+
+      // The bid that lost the race finds the auction already closed
+      await expectError(
+        placeBid(context, context.taker, startPrice.add(minIncrement), context.taker.publicKey),
+        "AccountNotInitialized"
+      );
+
+      const [event] = await parseEvents(tx, "auctionBoughtNowEvent");
+      assert.ok(event.buyer.equals(rival.publicKey));
+      assert.ok(event.price.eq(buyNowPrice));
+      assert.equal(event.fee.toNumber(), fee);
+      assert.ok(event.refundedBidder.equals(context.taker.publicKey));
+      assert.ok(event.refundedBid.eq(startPrice));
+
+      assert.equal(
+        await connection.getBalance(context.taker.publicKey),
+        takerBefore + startPrice.toNumber()
+      );
+      assert.equal(await connection.getBalance(context.treasury), treasuryBefore + fee);
+      const nft = await connection.getTokenAccountBalance(
+        getAssociatedTokenAddressSync(new PublicKey(context.nftMint.publicKey), rival.publicKey)
+      );
+      assert.equal(nft.value.amount, "1");
+      assert.isNull(await connection.getAccountInfo(auctionAccounts(context).auction));
+    });
+
+    it("closes buy-now once bidding reaches the buy-now price", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const rival = await fundedKeypair();
+      const buyNowPrice = startPrice.muln(2);
+      await createAuction(context, (await chainTime()) + 60, new anchor.BN(0), false, buyNowPrice);
+      await placeBid(context, context.taker, buyNowPrice, null);
+
+      await expectError(buyNow(context, rival, context.taker.publicKey), "BuyNowUnavailable");
+    });
+  });
+
+  describe("dutch listings", () => {
+    const startPrice = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+    const floorPrice = new anchor.BN(0.02 * LAMPORTS_PER_SOL);
+
+    const listDutch = async (ctx: MarketplaceContext, startTs: number, endTs: number) => {
+      await refreshListing(ctx);
+      const nftMetadata = findMetadataPda(ctx.umi, { mint: ctx.nftMint.publicKey });
+      const nftEdition = findMasterEditionPda(ctx.umi, { mint: ctx.nftMint.publicKey });
+
+      return program.methods
+        .listNftDutch(startPrice, floorPrice, new anchor.BN(startTs), new anchor.BN(endTs))
+        .accounts({
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          sellerTokenAccount: ctx.makerAta,
+          marketplace: ctx.marketplace,
+          listingIndex: await nextListingIndexPda(ctx.marketplace),
+          collectionMint: ctx.collectionMint.publicKey,
+          collectionConfig: collectionConfigPda(ctx),
+          collectionStats: null,
+          metadata: new PublicKey(nftMetadata[0]),
+          masterEdition: new PublicKey(nftEdition[0]),
+          sellerTokenRecord: null,
+          listingTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+        })
+        .signers([ctx.maker])
+        .rpc();
+    };
+
+    const purchase = purchaseContextNft;
+
+    // Mirrors DutchPricing::price_at
+    const expectedPrice = (now: number, startTs: number, endTs: number) => {
+      if (now >= endTs) return floorPrice;
+      const drop = startPrice.sub(floorPrice);
+      return startPrice.sub(drop.muln(now - startTs).divn(endTs - startTs));
+    };
+
+    // Returns the price the buyer paid, from the seller and treasury balance changes
+    // net of the listing and vault rent refunded to the seller
+    const purchaseAndMeasure = async (ctx: MarketplaceContext) => {
+      const sellerBefore = await connection.getBalance(ctx.maker.publicKey);
+      const treasuryBefore = await connection.getBalance(ctx.treasury);
+      const rentRefund =
+        (await connection.getBalance(ctx.listing)) + (await connection.getBalance(ctx.vault));
+
+      const sig = await purchase(ctx);
+      const tx = await connection.getTransaction(sig, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+
+      const paid =
+        (await connection.getBalance(ctx.maker.publicKey)) -
+        sellerBefore -
+        rentRefund +
+        (await connection.getBalance(ctx.treasury)) -
+        treasuryBefore;
+      return { paid, blockTime: tx.blockTime };
+    };
+
+    it("rejects a floor above the start price", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const now = await chainTime();
+      await expectError(
+        program.methods
+          .listNftDutch(floorPrice, startPrice, new anchor.BN(now), new anchor.BN(now + 60))
+          .accounts({
+            seller: context.maker.publicKey,
+            nft: context.nftMint.publicKey,
+            //@ts-ignore
+            listing: context.listing,
+            listingTokenAccount: context.vault,
+            sellerTokenAccount: context.makerAta,
+            marketplace: context.marketplace,
+            listingIndex: await nextListingIndexPda(context.marketplace),
+            collectionMint: context.collectionMint.publicKey,
+            collectionConfig: collectionConfigPda(context),
+            collectionStats: null,
+            metadata: new PublicKey(findMetadataPda(context.umi, { mint: context.nftMint.publicKey })[0]),
+            masterEdition: new PublicKey(findMasterEditionPda(context.umi, { mint: context.nftMint.publicKey })[0]),
+            sellerTokenRecord: null,
+            listingTokenRecord: null,
+            authorizationRules: null,
+            authorizationRulesProgram: null,
+            sysvarInstructions: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+          })
+          .signers([context.maker])
+          .rpc(),
+        "InvalidDutchSchedule"
+      );
+    });
+
+    it("rejects purchases before the start and charges the decayed price after", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const startTs = (await chainTime()) + 4;
+      const endTs = startTs + 120;
+      await listDutch(context, startTs, endTs);
+
+      const listing = await program.account.listing.fetch(context.listing);
+      assert.ok(listing.dutch.startPrice.eq(startPrice));
+      assert.ok(listing.dutch.floorPrice.eq(floorPrice));
+      await expectError(purchase(context), "DutchNotStarted");
+
+      await waitForChainTime(startTs + 2);
+      const { paid, blockTime } = await purchaseAndMeasure(context);
+      const expected = expectedPrice(blockTime, startTs, endTs);
+      assert.isTrue(expected.lt(startPrice) && expected.gt(floorPrice));
+      assert.equal(paid, expected.toNumber());
+    });
+
+    it("charges the floor price after the end", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const startTs = (await chainTime()) + 1;
+      const endTs = startTs + 3;
+      await listDutch(context, startTs, endTs);
+
+      await waitForChainTime(endTs);
+      const { paid } = await purchaseAndMeasure(context);
+      assert.equal(paid, floorPrice.toNumber());
+    });
+
+    it("rejects repricing a dutch listing", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const startTs = await chainTime();
+      await listDutch(context, startTs, startTs + 60);
+
+      await expectError(
+        program.methods
+          .updateListingPrice(startPrice)
+          .accounts({
+            seller: context.maker.publicKey,
+            nft: context.nftMint.publicKey,
+            //@ts-ignore
+            listing: context.listing,
+            marketplace: context.marketplace,
+          })
+          .signers([context.maker])
+          .rpc(),
+        "NotFixedPriceListing"
+      );
+    });
+  });
+
+  describe("reward points", () => {
+    const marketplace = marketplacePda();
+    const rewardsMint = PublicKey.findProgramAddressSync(
+      [Buffer.from("rewards"), marketplace.toBuffer()],
+      program.programId
+    )[0];
+
+    const setRewardRate = (rewardRateBps: number, admin?: Keypair) => {
+      const builder = program.methods
+        .setRewardRate(rewardRateBps)
+        .accounts({
+          admin: admin ? admin.publicKey : provider.wallet.publicKey,
+          //@ts-ignore
+          marketplace,
+        });
+      return (admin ? builder.signers([admin]) : builder).rpc({ commitment: "confirmed" });
+    };
+
+    const rewardBalance = async (owner: PublicKey) => {
+      const account = await connection.getTokenAccountBalance(
+        getAssociatedTokenAddressSync(rewardsMint, owner),
+        "confirmed"
+      );
+      return new anchor.BN(account.value.amount);
+    };
+
+    afterEach(async () => {
+      await setRewardRate(0);
+    });
+
+    it("creates the rewards mint owned by the marketplace", async () => {
+      const mint = await getMint(connection, rewardsMint);
+      assert.ok(mint.mintAuthority.equals(marketplace));
+      assert.equal(
+        mint.decimals,
+        Number(program.idl.constants.find((c) => c.name === "rewardsDecimals").value)
+      );
+      assert.equal((await program.account.marketplace.fetch(marketplace)).rewardRateBps, 0);
+    });
+
+    it("rejects reward rate updates by anyone but the admin", async () => {
+      const stranger = await fundedKeypair();
+      await expectError(setRewardRate(100, stranger), "Unauthorized");
+    });
+
+    it("rejects a reward rate above 100%", async () => {
+      await expectError(setRewardRate(10_001), "InvalidRewardRate");
+    });
+
+    it("mints rewards to the buyer and half to the seller", async () => {
+      await setRewardRate(250);
+      const context = await setupMarketplace();
+      await addCollection(context);
+      await listContextNft(context);
+
+      const [event] = await parseEvents(await purchaseContextNft(context), "rewardsMintedEvent");
+
+      const buyerReward = context.price.muln(250).divn(10_000);
+      const sellerReward = buyerReward.divn(2);
+      assert.ok((await rewardBalance(context.taker.publicKey)).eq(buyerReward));
+      assert.ok((await rewardBalance(context.maker.publicKey)).eq(sellerReward));
+      assert.ok(event.listing.equals(context.listing));
+      assert.ok(event.buyer.equals(context.taker.publicKey));
+      assert.ok(event.seller.equals(context.maker.publicKey));
+      assert.ok(event.buyerAmount.eq(buyerReward));
+      assert.ok(event.sellerAmount.eq(sellerReward));
+    });
+
+    it("skips the mints when the rate is zero", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      await listContextNft(context);
+
+      const events = await parseEvents(await purchaseContextNft(context), "rewardsMintedEvent");
+
+      assert.lengthOf(events, 0);
+      assert.ok((await rewardBalance(context.taker.publicKey)).isZero());
+      assert.ok((await rewardBalance(context.maker.publicKey)).isZero());
+    });
+  });
+
+  describe("escrowless listings", () => {
+    const editionOf = (ctx: MarketplaceContext) =>
+      new PublicKey(findMasterEditionPda(ctx.umi, { mint: ctx.nftMint.publicKey })[0]);
+
+    const listEscrowless = async (ctx: MarketplaceContext) => {
+      await refreshListing(ctx);
+      return program.methods
+        .listNftEscrowless(ctx.price, new anchor.BN(0))
+        .accounts({
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          sellerTokenAccount: ctx.makerAta,
+          marketplace: ctx.marketplace,
+          collectionMint: ctx.collectionMint.publicKey,
+          collectionConfig: collectionConfigPda(ctx),
+          metadata: new PublicKey(findMetadataPda(ctx.umi, { mint: ctx.nftMint.publicKey })[0]),
+          masterEdition: editionOf(ctx),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+    };
+
+    const purchaseEscrowless = async (ctx: MarketplaceContext, withEdition = true) =>
+      program.methods
+        .purchaseNft(false)
+        .accounts({
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          buyer: ctx.taker.publicKey,
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          buyerTokenAccount: ctx.takerAta,
+          listingTokenAccount: null,
+          sellerTokenAccount: ctx.makerAta,
+          masterEdition: withEdition ? editionOf(ctx) : null,
+          metadata: metadataPda(ctx),
+          listingTokenRecord: null,
+          buyerTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          metadataProgram: withEdition ? MPL_TOKEN_METADATA_PROGRAM_ID : null,
+          listing: ctx.listing,
+          feeRecipient: ctx.treasury,
+          paymentMint: null,
+          buyerPaymentAccount: null,
+          sellerPaymentAccount: null,
+          feeRecipientPaymentAccount: null,
+          referrer: null,
+          referrerPaymentAccount: null,
+          saleEscrow: null,
+          proceeds: null,
+          collectionStats: null,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([ctx.taker])
+        .rpc({ commitment: "confirmed" });
+
+    const delistEscrowless = async (ctx: MarketplaceContext) =>
+      program.methods
+        .delistNft()
+        .accounts({
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          sellerTokenAccount: ctx.makerAta,
+          listing: ctx.listing,
+          listingTokenAccount: null,
+          masterEdition: editionOf(ctx),
+          metadata: null,
+          listingTokenRecord: null,
+          sellerTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          associatedTokenProgram: null,
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          collectionStats: null,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+
+    let context: MarketplaceContext;
+
+    before(async () => {
+      context = await setupMarketplace();
+      await addCollection(context);
+      await listEscrowless(context);
+    });
+
+    it("keeps the NFT frozen in the seller's wallet with the listing as delegate", async () => {
+      const listing = await program.account.listing.fetch(context.listing);
+      assert.isTrue(listing.escrowless);
+
+      const sellerAccount = await getAccount(connection, context.makerAta, "confirmed");
+      assert.equal(Number(sellerAccount.amount), 1);
+      assert.isTrue(sellerAccount.isFrozen);
+      assert.ok(sellerAccount.delegate.equals(context.listing));
+      assert.isNull(await connection.getAccountInfo(context.vault));
+    });
+
+    it("stops the seller from moving a listed NFT", async () => {
+      const stranger = Keypair.generate();
+      const strangerAta = await getOrCreateAssociatedTokenAccount(
+        connection,
+        context.maker,
+        new PublicKey(context.nftMint.publicKey),
+        stranger.publicKey
+      );
+
+      let failed = false;
+      try {
+        await transfer(connection, context.maker, context.makerAta, strangerAta.address, context.maker, 1);
+      } catch (err: any) {
+        failed = true;
+        // SPL Token error 0x11: Account is frozen
+        assert.include(String(err.logs ?? err), "frozen");
+      }
+      assert.isTrue(failed, "expected the transfer of a frozen NFT to fail");
+    });
+
+    it("rejects a purchase without the master edition", async () => {
+      await expectError(purchaseEscrowless(context, false), "MissingEscrowlessAccounts");
+    });
+
+    it("thaws and transfers the NFT to the buyer on purchase", async () => {
+      await purchaseEscrowless(context);
+
+      const buyerAccount = await getAccount(connection, context.takerAta, "confirmed");
+      assert.equal(Number(buyerAccount.amount), 1);
+
+      const sellerAccount = await getAccount(connection, context.makerAta, "confirmed");
+      assert.equal(Number(sellerAccount.amount), 0);
+      assert.isFalse(sellerAccount.isFrozen);
+      assert.isNull(sellerAccount.delegate);
+    });
+
+    it("thaws and revokes the delegate on delist", async () => {
+      const other = await setupMarketplace();
+      await addCollection(other);
+      await listEscrowless(other);
+
+      await delistEscrowless(other);
+
+      const sellerAccount = await getAccount(connection, other.makerAta, "confirmed");
+      assert.equal(Number(sellerAccount.amount), 1);
+      assert.isFalse(sellerAccount.isFrozen);
+      assert.isNull(sellerAccount.delegate);
+      assert.isNull(await connection.getAccountInfo(other.listing));
+    });
+  });
+
+  describe("pause", () => {
+    const marketplace = marketplacePda();
+    let context: MarketplaceContext;
+    let offer: PublicKey;
+
+    const setPaused = (paused: boolean, admin?: Keypair) => {
+      const builder = program.methods
+        .setPaused(paused)
+        .accounts({
+          admin: admin ? admin.publicKey : provider.wallet.publicKey,
+          //@ts-ignore
+          marketplace,
+        });
+      return (admin ? builder.signers([admin]) : builder).rpc({ commitment: "confirmed" });
+    };
+
+    before(async () => {
+      context = await setupMarketplace();
+      await addCollection(context);
+      await listContextNft(context);
+
+      offer = PublicKey.findProgramAddressSync(
+        [Buffer.from("offer"), context.listing.toBuffer(), context.taker.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      await program.methods
+        .makeOffer(context.price.divn(2), new anchor.BN((await chainTime()) + 3600))
+        .accounts({
+          buyer: context.taker.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          offer,
+          marketplace: context.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([context.taker])
+        .rpc();
+    });
+
+    after(async () => {
+      const [event] = await parseEvents(await setPaused(false), "marketplaceUnpausedEvent");
+      assert.ok(event.marketplace.equals(marketplace));
+      assert.isFalse((await program.account.marketplace.fetch(marketplace)).paused);
+    });
+
+    it("rejects pausing by anyone but the admin", async () => {
+      const stranger = await fundedKeypair();
+      await expectError(setPaused(true, stranger), "Unauthorized");
+    });
+
+    it("pauses the marketplace", async () => {
+      const [event] = await parseEvents(await setPaused(true), "marketplacePausedEvent");
+
+      assert.ok(event.marketplace.equals(marketplace));
+      assert.ok(event.admin.equals(provider.wallet.publicKey));
+      assert.isTrue((await program.account.marketplace.fetch(marketplace)).paused);
+    });
+
+    it("rejects new listings while paused", async () => {
+      const other = await setupMarketplace();
+      await addCollection(other);
+      await expectError(listContextNft(other), "MarketplacePaused");
+    });
+
+    it("rejects purchases while paused", async () => {
+      await expectError(purchaseContextNft(context), "MarketplacePaused");
+    });
+
+    it("rejects accepting offers while paused", async () => {
+      await expectError(
+        program.methods
+          .acceptOffer()
+          .accounts({
+            seller: context.maker.publicKey,
+            buyer: context.taker.publicKey,
+            nft: context.nftMint.publicKey,
+            //@ts-ignore
+            listing: context.listing,
+            listingTokenAccount: context.vault,
+            buyerTokenAccount: context.takerAta,
+            offer,
+            marketplace: context.marketplace,
+            feeRecipient: context.treasury,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            paymentMint: null,
+            offerVault: null,
+            sellerPaymentAccount: null,
+            feeRecipientPaymentAccount: null,
+          })
+          .signers([context.maker])
+          .rpc(),
+        "MarketplacePaused"
+      );
+    });
+
+    it("still lets the seller delist while paused", async () => {
+      await program.methods
+        .delistNft()
+        .accounts({
+          ...(await unindexAccounts(context.marketplace, context.listing)),
+          seller: context.maker.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          sellerTokenAccount: context.makerAta,
+          listing: context.listing,
+          listingTokenAccount: context.vault,
+          masterEdition: null,
+          metadata: null,
+          listingTokenRecord: null,
+          sellerTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          associatedTokenProgram: null,
+          metadataProgram: null,
+          marketplace: context.marketplace,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          collectionStats: null,
+        })
+        .signers([context.maker])
+        .rpc({ commitment: "confirmed" });
+
+      const sellerAccount = await getAccount(connection, context.makerAta, "confirmed");
+      assert.equal(Number(sellerAccount.amount), 1);
+      assert.isNull(await connection.getAccountInfo(context.listing));
+    });
+  });
+
+  describe("semi-fungible listings", () => {
+    const supply = 10;
+    const listed = 6;
+    let context: MarketplaceContext;
+
+    // Mints `supply` units of a zero-decimal fungible asset to a fresh maker;
+    // listings are open, as the asset belongs to no collection
+    const setupSft = async (): Promise<MarketplaceContext> => {
+      const base = await setupMarketplace("none");
+      const sftMint = generateSigner(base.umi);
+      await createFungibleAsset(base.umi, {
+        mint: sftMint,
+        name: "Potion",
+        symbol: "POT",
+        uri: "https://arweave.net/123",
+        sellerFeeBasisPoints: percentAmount(0),
+        decimals: some(0),
+      }).sendAndConfirm(base.umi);
+      await mintV1(base.umi, {
+        mint: sftMint.publicKey,
+        amount: supply,
+        tokenOwner: publicKey(base.maker.publicKey),
+        tokenStandard: TokenStandard.FungibleAsset,
+      }).sendAndConfirm(base.umi);
+
+      const mint = new PublicKey(sftMint.publicKey);
+      const listing = await nextListingPda(base.marketplace, base.maker.publicKey, mint);
+
+      return {
+        ...base,
+        nftMint: sftMint,
+        makerAta: getAssociatedTokenAddressSync(mint, base.maker.publicKey),
+        takerAta: getAssociatedTokenAddressSync(mint, base.taker.publicKey),
+        vault: getAssociatedTokenAddressSync(mint, listing, true),
+        listing,
+        price: new anchor.BN(0.01 * LAMPORTS_PER_SOL),
+      };
+    };
+
+    const listSft = async (ctx: MarketplaceContext, quantity: number) =>
+      program.methods
+        .listNft(ctx.price, new anchor.BN(0), new anchor.BN(quantity), null, 0, new anchor.BN(0), null, 0)
+        .accounts({
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          sellerTokenAccount: ctx.makerAta,
+          marketplace: ctx.marketplace,
+          listingIndex: await nextListingIndexPda(ctx.marketplace),
+          collectionMint: ctx.collectionMint.publicKey,
+          collectionConfig: collectionConfigPda(ctx),
+          collectionStats: null,
+          metadata: new PublicKey(findMetadataPda(ctx.umi, { mint: ctx.nftMint.publicKey })[0]),
+          masterEdition: null,
+          sellerTokenRecord: null,
+          listingTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+
+    const purchaseQuantity = async (ctx: MarketplaceContext, amount: number) =>
+      program.methods
+        .purchaseQuantity(new anchor.BN(amount), false)
+        .accounts({
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          buyer: ctx.taker.publicKey,
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          buyerTokenAccount: ctx.takerAta,
+          listingTokenAccount: ctx.vault,
+          sellerTokenAccount: null,
+          masterEdition: null,
+          metadata: metadataPda(ctx),
+          listingTokenRecord: null,
+          buyerTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          metadataProgram: null,
+          listing: ctx.listing,
+          feeRecipient: ctx.treasury,
+          paymentMint: null,
+          buyerPaymentAccount: null,
+          sellerPaymentAccount: null,
+          feeRecipientPaymentAccount: null,
+          referrer: null,
+          referrerPaymentAccount: null,
+          saleEscrow: null,
+          proceeds: null,
+          collectionStats: null,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([ctx.taker])
+        .rpc({ commitment: "confirmed" });
+
+    const tokenBalance = async (account: PublicKey) =>
+      Number((await getAccount(connection, account, "confirmed")).amount);
+
+    before(async () => {
+      await setOpenListings(true);
+      context = await setupSft();
+    });
+
+    after(async () => {
+      await setOpenListings(false);
+    });
+
+    it("rejects a zero quantity", async () => {
+      await expectError(listSft(context, 0), "InvalidQuantity");
+    });
+
+    it("rejects more than one unit of an NFT", async () => {
+      const nft = await setupMarketplace("none");
+      await expectError(listContextNft(nft, 0, 2), "InvalidQuantity");
+    });
+
+    it("lists a quantity of a semi-fungible token into the vault", async () => {
+      await listSft(context, listed);
+
+      const listing = await program.account.listing.fetch(context.listing);
+      assert.equal(listing.quantity.toNumber(), listed);
+      assert.ok(listing.price.eq(context.price));
+      assert.equal(await tokenBalance(context.vault), listed);
+      assert.equal(await tokenBalance(context.makerAta), supply - listed);
+    });
+
+    it("rejects offers on a multi-unit listing", async () => {
+      const [offer] = PublicKey.findProgramAddressSync(
+        [Buffer.from("offer"), context.listing.toBuffer(), context.taker.publicKey.toBuffer()],
+        program.programId
+      );
+      await expectError(
+        program.methods
+          .makeOffer(context.price, new anchor.BN((await chainTime()) + 3600))
+          .accounts({
+            buyer: context.taker.publicKey,
+            nft: context.nftMint.publicKey,
+            //@ts-ignore
+            listing: context.listing,
+            offer,
+            marketplace: context.marketplace,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([context.taker])
+          .rpc(),
+        "NotSingleTokenListing"
+      );
+    });
+
+    it("sells part of the listing at the unit price", async () => {
+      const sellerBefore = await connection.getBalance(context.maker.publicKey);
+      const treasuryBefore = await connection.getBalance(context.treasury);
+
+      await purchaseQuantity(context, 2);
+
+      const total = context.price.muln(2);
+      const fee = total.muln(100).divn(10_000);
+      assert.equal(await tokenBalance(context.takerAta), 2);
+      assert.equal(await tokenBalance(context.vault), listed - 2);
+      assert.equal(
+        (await program.account.listing.fetch(context.listing)).quantity.toNumber(),
+        listed - 2
+      );
+      assert.equal(
+        (await connection.getBalance(context.maker.publicKey)) - sellerBefore,
+        total.sub(fee).toNumber()
+      );
+      assert.equal(await connection.getBalance(context.treasury), treasuryBefore + fee.toNumber());
+    });
+
+    it("rejects buying more than remains", async () => {
+      await expectError(purchaseQuantity(context, listed - 1), "InsufficientQuantity");
+    });
+
+    it("closes the listing and vault when the last units sell", async () => {
+      await purchaseQuantity(context, listed - 2);
+
+      assert.equal(await tokenBalance(context.takerAta), listed);
+      assert.isNull(await connection.getAccountInfo(context.listing));
+      assert.isNull(await connection.getAccountInfo(context.vault));
+    });
+
+    describe("partial offer fills", () => {
+      const wanted = 6;
+      let first: MarketplaceContext;
+      let second: MarketplaceContext;
+      let offer: PublicKey;
+
+      const acceptOfferPartial = async (ctx: MarketplaceContext, fillQuantity: number) =>
+        program.methods
+          .acceptOfferPartial(new anchor.BN(fillQuantity))
+          .accounts({
+            seller: ctx.maker.publicKey,
+            buyer: first.taker.publicKey,
+            nft: ctx.nftMint.publicKey,
+            //@ts-ignore
+            listing: ctx.listing,
+            listingTokenAccount: ctx.vault,
+            buyerTokenAccount: first.takerAta,
+            offer,
+            marketplace: ctx.marketplace,
+            feeRecipient: ctx.treasury,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          })
+          .signers([ctx.maker])
+          .rpc({ commitment: "confirmed" });
+
+      // A second seller of the same token, holding 5 units moved over from the first
+      const secondSeller = async (ctx: MarketplaceContext): Promise<MarketplaceContext> => {
+        const maker = await fundedKeypair();
+        const mint = new PublicKey(ctx.nftMint.publicKey);
+        const makerAta = await getOrCreateAssociatedTokenAccount(connection, maker, mint, maker.publicKey);
+        await transfer(connection, ctx.maker, ctx.makerAta, makerAta.address, ctx.maker, 5);
+        const listing = await nextListingPda(ctx.marketplace, maker.publicKey, mint);
+
+        return {
+          ...ctx,
+          maker,
+          makerAta: makerAta.address,
+          listing,
+          vault: getAssociatedTokenAddressSync(mint, listing, true),
+        };
+      };
+
+      before(async () => {
+        first = await setupSft();
+        await listSft(first, 4);
+        second = await secondSeller(first);
+        await listSft(second, 5);
+
+        offer = PublicKey.findProgramAddressSync(
+          [Buffer.from("quantity_offer"), first.listing.toBuffer(), first.taker.publicKey.toBuffer()],
+          program.programId
+        )[0];
+        await program.methods
+          .makeQuantityOffer(new anchor.BN(wanted), first.price, new anchor.BN((await chainTime()) + 3600))
+          .accounts({
+            buyer: first.taker.publicKey,
+            nft: first.nftMint.publicKey,
+            //@ts-ignore
+            listing: first.listing,
+            offer,
+            marketplace: first.marketplace,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([first.taker])
+          .rpc({ commitment: "confirmed" });
+      });
+
+      it("escrows the price of every unit and leaves the best offer alone", async () => {
+        const escrowed = await program.account.quantityOffer.fetch(offer);
+        assert.equal(escrowed.quantity.toNumber(), wanted);
+        assert.ok(escrowed.amount.eq(first.price.muln(wanted)));
+        assert.equal((await program.account.listing.fetch(first.listing)).bestOfferAmount.toNumber(), 0);
+      });
+
+      it("fills part of the offer from the first seller's listing", async () => {
+        const offerBefore = await connection.getBalance(offer);
+        const treasuryBefore = await connection.getBalance(first.treasury);
+
+        const tx = await acceptOfferPartial(first, 3);
+
+        const total = first.price.muln(3);
+        const fee = total.muln(100).divn(10_000);
+        const [event] = await parseEvents(tx, "offerPartiallyAcceptedEvent");
+        assert.ok(event.sellerProceeds.eq(total.sub(fee)));
+        assert.equal(event.offerRemaining.toNumber(), wanted - 3);
+        assert.equal(event.listingRemaining.toNumber(), 1);
+        assert.equal(offerBefore - (await connection.getBalance(offer)), total.toNumber());
+        assert.equal(await connection.getBalance(first.treasury), treasuryBefore + fee.toNumber());
+        assert.equal(await tokenBalance(first.takerAta), 3);
+        assert.equal(await tokenBalance(first.vault), 1);
+        assert.ok((await program.account.quantityOffer.fetch(offer)).amount.eq(first.price.muln(wanted - 3)));
+      });
+
+      it("rejects filling more than the offer still buys", async () => {
+        await expectError(acceptOfferPartial(second, wanted - 2), "OfferOverfilled");
+      });
+
+      it("rejects filling more than the listing holds", async () => {
+        await expectError(acceptOfferPartial(first, 2), "InsufficientQuantity");
+      });
+
+      it("rejects accepting the quantity offer as a whole-listing offer", async () => {
+        await expectError(
+          program.methods
+            .acceptOffer()
+            .accounts({
+              seller: first.maker.publicKey,
+              buyer: first.taker.publicKey,
+              nft: first.nftMint.publicKey,
+              //@ts-ignore
+              listing: first.listing,
+              listingTokenAccount: first.vault,
+              buyerTokenAccount: first.takerAta,
+              offer,
+              marketplace: first.marketplace,
+              feeRecipient: first.treasury,
+              paymentMint: null,
+              offerVault: null,
+              sellerPaymentAccount: null,
+              feeRecipientPaymentAccount: null,
+              systemProgram: SystemProgram.programId,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            })
+            .signers([first.maker])
+            .rpc(),
+          "AccountDiscriminatorMismatch"
+        );
+      });
+
+      it("completes the offer from a second seller's listing and closes it", async () => {
+        const buyerBefore = await connection.getBalance(first.taker.publicKey);
+        const offerRent = await connection.getBalance(offer);
+
+        const tx = await acceptOfferPartial(second, wanted - 3);
+
+        const [event] = await parseEvents(tx, "offerPartiallyAcceptedEvent");
+        assert.equal(event.offerRemaining.toNumber(), 0);
+        assert.equal(event.listingRemaining.toNumber(), 2);
+        assert.isNull(await connection.getAccountInfo(offer));
+        assert.equal(await tokenBalance(first.takerAta), wanted);
+        assert.equal(await tokenBalance(second.vault), 2);
+        // Only the rent is left to refund once every unit is paid for
+        const rentLeft = offerRent - first.price.muln(wanted - 3).toNumber();
+        assert.equal((await connection.getBalance(first.taker.publicKey)) - buyerBefore, rentLeft);
+      });
+
+      it("closes the listing and vault when the fill sells it out", async () => {
+        await program.methods
+          .makeQuantityOffer(new anchor.BN(2), first.price, new anchor.BN((await chainTime()) + 3600))
+          .accounts({
+            buyer: first.taker.publicKey,
+            nft: first.nftMint.publicKey,
+            //@ts-ignore
+            listing: first.listing,
+            offer,
+            marketplace: first.marketplace,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([first.taker])
+          .rpc({ commitment: "confirmed" });
+
+        await acceptOfferPartial(first, 1);
+
+        assert.isNull(await connection.getAccountInfo(first.listing));
+        assert.isNull(await connection.getAccountInfo(first.vault));
+        const remaining = await program.account.quantityOffer.fetch(offer);
+        assert.equal(remaining.quantity.toNumber(), 1);
+      });
+
+      it("lets only the buyer cancel the rest of the offer, even after its listing closed", async () => {
+        const cancel = (authority: Keypair) =>
+          program.methods
+            .cancelQuantityOffer()
+            .accounts({
+              authority: authority.publicKey,
+              buyer: first.taker.publicKey,
+              //@ts-ignore
+              offer,
+              marketplace: first.marketplace,
+            })
+            .signers([authority])
+            .rpc({ commitment: "confirmed" });
+        await expectError(cancel(second.maker), "NotOfferBuyer");
+
+        const [event] = await parseEvents(await cancel(first.taker), "quantityOfferCancelledEvent");
+        assert.equal(event.quantity.toNumber(), 1);
+        assert.ok(event.refunded.eq(first.price));
+        assert.equal(event.bounty.toNumber(), 0);
+        assert.isNull(await connection.getAccountInfo(offer));
+      });
+    });
+  });
+
+  describe("programmable nfts", () => {
+    type PnftContext = MarketplaceContext & { ruleSet: PublicKey };
+
+    // Royalty enforcement: owner transfers must come from or go to an account owned by an allowed program
+    const setupPnft = async (allowedProgram: PublicKey): Promise<PnftContext> => {
+      const base = await setupMarketplace("none");
+      base.umi.use(mplTokenAuthRules());
+
+      const owner = base.umi.identity.publicKey;
+      const name = `royalties-${Keypair.generate().publicKey.toBase58().slice(0, 8)}`;
+      const ruleSetPda = findRuleSetPda(base.umi, { owner, name });
+      const allowed = [publicKey(allowedProgram)];
+      await createOrUpdateV1(base.umi, {
+        ruleSetPda,
+        ruleSetRevision: some({
+          libVersion: 2,
+          name,
+          owner,
+          operations: {
+            "Transfer:Owner": anyV2([
+              programOwnedListV2("Source", allowed),
+              programOwnedListV2("Destination", allowed),
+            ]),
+          },
+        }),
+      }).sendAndConfirm(base.umi);
+
+      const pnftMint = generateSigner(base.umi);
+      await createProgrammableNft(base.umi, {
+        mint: pnftMint,
+        name: "GM",
+        symbol: "GM",
+        uri: "https://arweave.net/123",
+        sellerFeeBasisPoints: percentAmount(5.5),
+        tokenOwner: publicKey(base.maker.publicKey),
+        ruleSet: ruleSetPda[0],
+      }).sendAndConfirm(base.umi);
+
+      const mint = new PublicKey(pnftMint.publicKey);
+      const listing = await nextListingPda(base.marketplace, base.maker.publicKey, mint);
+
+      return {
+        ...base,
+        nftMint: pnftMint,
+        makerAta: getAssociatedTokenAddressSync(mint, base.maker.publicKey),
+        takerAta: getAssociatedTokenAddressSync(mint, base.taker.publicKey),
+        vault: getAssociatedTokenAddressSync(mint, listing, true),
+        listing,
+        ruleSet: new PublicKey(ruleSetPda[0]),
+      };
+    };
+
+    const tokenRecord = (ctx: PnftContext, token: PublicKey) =>
+      new PublicKey(
+        findTokenRecordPda(ctx.umi, { mint: ctx.nftMint.publicKey, token: publicKey(token) })[0]
+      );
+
+    const metadataOf = (ctx: PnftContext) =>
+      new PublicKey(findMetadataPda(ctx.umi, { mint: ctx.nftMint.publicKey })[0]);
+
+    const editionOf = (ctx: PnftContext) =>
+      new PublicKey(findMasterEditionPda(ctx.umi, { mint: ctx.nftMint.publicKey })[0]);
+
+    const rulesAccounts = (ctx: PnftContext) => ({
+      authorizationRules: ctx.ruleSet,
+      authorizationRulesProgram: MPL_TOKEN_AUTH_RULES_PROGRAM_ID,
+      sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+    });
+
+    const listPnft = async (ctx: PnftContext, withRecords = true) =>
+      program.methods
+        .listNft(ctx.price, new anchor.BN(0), new anchor.BN(1), null, 0, new anchor.BN(0), null, 0)
+        .accounts({
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          sellerTokenAccount: ctx.makerAta,
+          marketplace: ctx.marketplace,
+          listingIndex: await nextListingIndexPda(ctx.marketplace),
+          collectionMint: ctx.collectionMint.publicKey,
+          collectionConfig: collectionConfigPda(ctx),
+          collectionStats: null,
+          metadata: metadataOf(ctx),
+          masterEdition: editionOf(ctx),
+          sellerTokenRecord: withRecords ? tokenRecord(ctx, ctx.makerAta) : null,
+          listingTokenRecord: withRecords ? tokenRecord(ctx, ctx.vault) : null,
+          ...rulesAccounts(ctx),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+
+    const purchasePnft = async (ctx: PnftContext) =>
+      program.methods
+        .purchaseNft(false)
+        .accounts({
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          buyer: ctx.taker.publicKey,
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          buyerTokenAccount: ctx.takerAta,
+          listingTokenAccount: ctx.vault,
+          sellerTokenAccount: null,
+          masterEdition: editionOf(ctx),
+          metadata: metadataOf(ctx),
+          listingTokenRecord: tokenRecord(ctx, ctx.vault),
+          buyerTokenRecord: tokenRecord(ctx, ctx.takerAta),
+          ...rulesAccounts(ctx),
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+          listing: ctx.listing,
+          feeRecipient: ctx.treasury,
+          paymentMint: null,
+          buyerPaymentAccount: null,
+          sellerPaymentAccount: null,
+          feeRecipientPaymentAccount: null,
+          referrer: null,
+          referrerPaymentAccount: null,
+          saleEscrow: null,
+          proceeds: null,
+          collectionStats: null,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([ctx.taker])
+        .rpc({ commitment: "confirmed" });
+
+    const delistPnft = async (ctx: PnftContext) =>
+      program.methods
+        .delistNft()
+        .accounts({
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          sellerTokenAccount: ctx.makerAta,
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          masterEdition: editionOf(ctx),
+          metadata: metadataOf(ctx),
+          listingTokenRecord: tokenRecord(ctx, ctx.vault),
+          sellerTokenRecord: tokenRecord(ctx, ctx.makerAta),
+          ...rulesAccounts(ctx),
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          collectionStats: null,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+
+    const tokenBalance = async (account: PublicKey) =>
+      Number((await getAccount(connection, account, "confirmed")).amount);
+
+    before(async () => {
+      await setOpenListings(true);
+    });
+
+    after(async () => {
+      await setOpenListings(false);
+    });
+
+    it("requires the token records to list a pNFT", async () => {
+      const context = await setupPnft(program.programId);
+      await expectError(listPnft(context, false), "MissingProgrammableAccounts");
+    });
+
+    it("lists a pNFT into a frozen vault and sells it through the rule set", async () => {
+      const context = await setupPnft(program.programId);
+      await listPnft(context);
+
+      const listing = await program.account.listing.fetch(context.listing);
+      assert.isTrue(listing.programmable);
+      const vault = await getAccount(connection, context.vault, "confirmed");
+      assert.equal(Number(vault.amount), 1);
+      assert.isTrue(vault.isFrozen);
+
+      await purchasePnft(context);
+
+      const buyerAccount = await getAccount(connection, context.takerAta, "confirmed");
+      assert.equal(Number(buyerAccount.amount), 1);
+      assert.isTrue(buyerAccount.isFrozen);
+      assert.isNull(await connection.getAccountInfo(context.listing));
+    });
+
+    it("returns a delisted pNFT to the seller", async () => {
+      const context = await setupPnft(program.programId);
+      await listPnft(context);
+
+      await delistPnft(context);
+
+      assert.equal(await tokenBalance(context.makerAta), 1);
+      assert.equal(await tokenBalance(context.vault), 0);
+      assert.isNull(await connection.getAccountInfo(context.listing));
+    });
+
+    it("cannot list a pNFT whose rule set does not allow the marketplace", async () => {
+      const context = await setupPnft(SystemProgram.programId);
+
+      let failed = false;
+      try {
+        await listPnft(context);
+      } catch {
+        failed = true;
+      }
+      assert.isTrue(failed, "expected the rule set to reject the transfer to the vault");
+      assert.equal(await tokenBalance(context.makerAta), 1);
+    });
+  });
+
+  describe("private listings", () => {
+    let context: MarketplaceContext;
+    let stranger: Keypair;
+
+    const updateAllowedBuyer = (allowedBuyer: PublicKey | null, seller = context.maker) =>
+      program.methods
+        .updateAllowedBuyer(allowedBuyer)
+        .accounts({
+          seller: seller.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          marketplace: context.marketplace,
+        })
+        .signers([seller])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      context = await setupMarketplace();
+      await addCollection(context);
+      stranger = await fundedKeypair();
+      await listContextNft(context, 0, 1, context.taker.publicKey);
+    });
+
+    it("stores the allowed buyer on the listing", async () => {
+      const listing = await program.account.listing.fetch(context.listing);
+      assert.ok(listing.allowedBuyer.equals(context.taker.publicKey));
+    });
+
+    it("rejects purchases from other wallets", async () => {
+      const strangerContext = {
+        ...context,
+        taker: stranger,
+        takerAta: getAssociatedTokenAddressSync(
+          new PublicKey(context.nftMint.publicKey),
+          stranger.publicKey
+        ),
+      };
+      await expectError(purchaseContextNft(strangerContext), "NotAllowedBuyer");
+    });
+
+    it("rejects offers from other wallets", async () => {
+      const offer = PublicKey.findProgramAddressSync(
+        [Buffer.from("offer"), context.listing.toBuffer(), stranger.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      const makeOffer = program.methods
+        .makeOffer(context.price.divn(2), new anchor.BN((await chainTime()) + 3600))
+        .accounts({
+          buyer: stranger.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          offer,
+          marketplace: context.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([stranger])
+        .rpc();
+      await expectError(makeOffer, "NotAllowedBuyer");
+    });
+
+    it("rejects retargeting by anyone but the seller", async () => {
+      await expectError(updateAllowedBuyer(null, stranger), "NotListingSeller");
+    });
+
+    it("lets the seller open the listing and retarget it", async () => {
+      const [opened] = await parseEvents(await updateAllowedBuyer(null), "allowedBuyerUpdatedEvent");
+      assert.ok(opened.listing.equals(context.listing));
+      assert.ok(opened.oldAllowedBuyer.equals(context.taker.publicKey));
+      assert.isNull(opened.newAllowedBuyer);
+      assert.isNull((await program.account.listing.fetch(context.listing)).allowedBuyer);
+
+      const [retargeted] = await parseEvents(
+        await updateAllowedBuyer(context.taker.publicKey),
+        "allowedBuyerUpdatedEvent"
+      );
+      assert.isNull(retargeted.oldAllowedBuyer);
+      assert.ok(retargeted.newAllowedBuyer.equals(context.taker.publicKey));
+    });
+
+    it("rejects offers from a buyer the listing was retargeted away from", async () => {
+      const offer = PublicKey.findProgramAddressSync(
+        [Buffer.from("offer"), context.listing.toBuffer(), stranger.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+      // Offer while the listing is public, then reserve it for the taker again
+      await updateAllowedBuyer(null);
+      await program.methods
+        .makeOffer(context.price.divn(2), new anchor.BN((await chainTime()) + 3600))
+        .accounts({
+          buyer: stranger.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          offer,
+          marketplace: context.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([stranger])
+        .rpc({ commitment: "confirmed" });
+      await updateAllowedBuyer(context.taker.publicKey);
+
+      const acceptOffer = program.methods
+        .acceptOffer()
+        .accounts({
+          seller: context.maker.publicKey,
+          buyer: stranger.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          listingTokenAccount: context.vault,
+          buyerTokenAccount: getAssociatedTokenAddressSync(
+            new PublicKey(context.nftMint.publicKey),
+            stranger.publicKey
+          ),
+          offer,
+          marketplace: context.marketplace,
+          feeRecipient: context.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          paymentMint: null,
+          offerVault: null,
+          sellerPaymentAccount: null,
+          feeRecipientPaymentAccount: null,
+        })
+        .signers([context.maker])
+        .rpc();
+      await expectError(acceptOffer, "NotAllowedBuyer");
+      assert.equal(Number((await getAccount(connection, context.vault)).amount), 1);
+    });
+
+    it("sells to the allowed buyer and flags the sale as private", async () => {
+      const tx = await purchaseContextNft(context);
+
+      const [event] = await parseEvents(tx, "nftPurchasedEvent");
+      assert.ok(event.listing.equals(context.listing));
+      assert.ok(event.buyer.equals(context.taker.publicKey));
+      assert.ok(event.price.eq(context.price));
+      assert.isTrue(event.private);
+      assert.equal(Number((await getAccount(connection, context.takerAta)).amount), 1);
+    });
+  });
+
+  describe("bulk listings", () => {
+    let context: MarketplaceContext;
+    let mints: PublicKey[];
+
+    // Items take consecutive listing nonces of the fresh maker, in batch order
+    const listingOf = (mint: PublicKey, nonce: number) =>
+      listingPda(context.marketplace, context.maker.publicKey, mint, nonce);
+
+    const vaultOf = (mint: PublicKey, nonce: number) =>
+      getAssociatedTokenAddressSync(mint, listingOf(mint, nonce), true);
+
+    const makerAtaOf = (mint: PublicKey) =>
+      getAssociatedTokenAddressSync(mint, context.maker.publicKey);
+
+    const writable = (pubkey: PublicKey, isWritable = true) => ({
+      pubkey,
+      isWritable,
+      isSigner: false,
+    });
+
+    const mintNfts = async (count: number) => {
+      const minted: PublicKey[] = [];
+      for (let i = 0; i < count; i++) {
+        const mint = generateSigner(context.umi);
+        await createNft(context.umi, {
+          mint,
+          name: "GM",
+          symbol: "GM",
+          uri: "https://arweave.net/123",
+          sellerFeeBasisPoints: percentAmount(5.5),
+          tokenOwner: publicKey(context.maker.publicKey),
+        }).sendAndConfirm(context.umi);
+        minted.push(new PublicKey(mint.publicKey));
+      }
+      return minted;
+    };
+
+    const listGroup = (mint: PublicKey, nonce: number) => [
+      writable(mint, false),
+      writable(makerAtaOf(mint)),
+      writable(vaultOf(mint, nonce)),
+      writable(listingOf(mint, nonce)),
+      writable(new PublicKey(findMetadataPda(context.umi, { mint: publicKey(mint) })[0]), false),
+    ];
+
+    const bulkList = (prices: anchor.BN[], groups: ReturnType<typeof listGroup>[]) =>
+      program.methods
+        .bulkList(prices)
+        .accounts({
+          seller: context.maker.publicKey,
+          //@ts-ignore
+          marketplace: context.marketplace,
+          collectionMint: context.collectionMint.publicKey,
+          collectionConfig: collectionConfigPda(context),
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+        })
+        .remainingAccounts(groups.flat())
+        .preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })])
+        .signers([context.maker])
+        .rpc({ commitment: "confirmed" });
+
+    const bulkDelist = async (mintsToDelist: PublicKey[]) => {
+      const index = await unindexGroups(
+        context.marketplace,
+        mintsToDelist.map((mint, nonce) => listingOf(mint, nonce))
+      );
+      return program.methods
+        .bulkDelist()
+        .accounts({
+          seller: context.maker.publicKey,
+          //@ts-ignore
+          marketplace: context.marketplace,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          mintsToDelist.flatMap((mint, nonce) => [
+            writable(mint, false),
+            writable(makerAtaOf(mint)),
+            writable(vaultOf(mint, nonce)),
+            writable(listingOf(mint, nonce)),
+            ...index[nonce],
+          ])
+        )
+        .signers([context.maker])
+        .rpc({ commitment: "confirmed" });
+    };
+
+    const prices = (count: number) =>
+      Array.from({ length: count }, (_, i) => context.price.addn(i));
+
+    before(async () => {
+      await setOpenListings(true);
+      context = await setupMarketplace("none");
+      mints = await mintNfts(3);
+    });
+
+    after(async () => {
+      await setOpenListings(false);
+    });
+
+    it("rejects a batch whose prices do not match the account groups", async () => {
+      await expectError(bulkList(prices(2), mints.map((mint, i) => listGroup(mint, i))), "InvalidBatchSize");
+    });
+
+    it("rejects batches above the size cap", async () => {
+      const max = Number(program.idl.constants.find((c) => c.name === "maxBulkItems").value);
+      await expectError(bulkList(prices(max + 1), []), "InvalidBatchSize");
+    });
+
+    it("lists every NFT of the batch and emits one event per item", async () => {
+      const tx = await bulkList(prices(3), mints.map((mint, i) => listGroup(mint, i)));
+
+      const events = await parseEvents(tx, "nftListedEvent");
+      assert.equal(events.length, 3);
+      for (const [i, mint] of mints.entries()) {
+        assert.ok(events[i].listing.equals(listingOf(mint, i)));
+        assert.ok(events[i].nft.equals(mint));
+        assert.ok(events[i].price.eq(context.price.addn(i)));
+
+        const listing = await program.account.listing.fetch(listingOf(mint, i));
+        assert.isTrue(listing.isActive);
+        assert.ok(listing.price.eq(context.price.addn(i)));
+        assert.ok(listing.quantity.eqn(1));
+        assert.equal(Number((await getAccount(connection, vaultOf(mint, i), "confirmed")).amount), 1);
+      }
+    });
+
+    it("reverts the whole batch when one item is invalid", async () => {
+      const [first, second] = await mintNfts(2);
+      // The first batch took nonces 0 to 2
+      const badGroup = listGroup(second, 4);
+      // Point the second item's vault at the first item's vault
+      badGroup[2] = writable(vaultOf(first, 3));
+
+      await expectError(bulkList(prices(2), [listGroup(first, 3), badGroup]), "InvalidBulkAccount");
+      assert.isNull(await connection.getAccountInfo(listingOf(first, 3)));
+      assert.equal(Number((await getAccount(connection, makerAtaOf(first))).amount), 1);
+    });
+
+    it("delists every listing of the batch and refunds their rent", async () => {
+      const sellerBefore = await connection.getBalance(context.maker.publicKey);
+      let rentRefund = 0;
+      for (const [i, mint] of mints.entries()) {
+        rentRefund +=
+          (await connection.getBalance(listingOf(mint, i))) +
+          (await connection.getBalance(vaultOf(mint, i)));
+      }
+
+      const tx = await bulkDelist(mints);
+
+      const events = await parseEvents(tx, "nftDelistedEvent");
+      assert.equal(events.length, 3);
+      for (const [i, mint] of mints.entries()) {
+        assert.isNull(await connection.getAccountInfo(listingOf(mint, i)));
+        assert.isNull(await connection.getAccountInfo(vaultOf(mint, i)));
+        assert.equal(Number((await getAccount(connection, makerAtaOf(mint))).amount), 1);
+      }
+      const sellerAfter = await connection.getBalance(context.maker.publicKey);
+      assert.equal(sellerAfter - sellerBefore, rentRefund);
+    });
+  });
+
+  describe("fee recipient", () => {
+    const marketplace = marketplacePda();
+    let context: MarketplaceContext;
+    let recipient: Keypair;
+
+    const setFeeRecipient = (feeRecipient: PublicKey, admin?: Keypair) => {
+      const builder = program.methods
+        .setFeeRecipient(feeRecipient)
+        .accounts({
+          admin: admin ? admin.publicKey : provider.wallet.publicKey,
+          //@ts-ignore
+          marketplace,
+        });
+      return (admin ? builder.signers([admin]) : builder).rpc({ commitment: "confirmed" });
+    };
+
+    before(async () => {
+      context = await setupMarketplace();
+      await addCollection(context);
+      // Listed before the recipient changes
+      await listContextNft(context);
+      // Funded so it is rent exempt when the first fee lands
+      recipient = await fundedKeypair();
+    });
+
+    after(async () => {
+      await setFeeRecipient(context.treasury);
+      const state = await program.account.marketplace.fetch(marketplace);
+      assert.ok(state.feeRecipient.equals(context.treasury));
+    });
+
+    it("starts with fees going to the treasury", async () => {
+      const state = await program.account.marketplace.fetch(marketplace);
+      assert.ok(state.feeRecipient.equals(context.treasury));
+    });
+
+    it("rejects fee recipient changes by anyone but the admin", async () => {
+      const stranger = await fundedKeypair();
+      await expectError(setFeeRecipient(stranger.publicKey, stranger), "Unauthorized");
+    });
+
+    it("updates the fee recipient and emits an event", async () => {
+      const tx = await setFeeRecipient(recipient.publicKey);
+
+      const [event] = await parseEvents(tx, "feeRecipientUpdatedEvent");
+      assert.ok(event.marketplace.equals(marketplace));
+      assert.ok(event.oldFeeRecipient.equals(context.treasury));
+      assert.ok(event.newFeeRecipient.equals(recipient.publicKey));
+    });
+
+    it("rejects purchases paying fees to the old recipient", async () => {
+      await expectError(purchaseContextNft(context, context.treasury), "InvalidFeeRecipient");
+    });
+
+    it("pays fees on existing listings to the new recipient", async () => {
+      const recipientBefore = await connection.getBalance(recipient.publicKey);
+      const treasuryBefore = await connection.getBalance(context.treasury);
+
+      await purchaseContextNft(context, recipient.publicKey);
+
+      const fee = context.price.muln(100).divn(10_000).toNumber();
+      assert.equal(await connection.getBalance(recipient.publicKey), recipientBefore + fee);
+      assert.equal(await connection.getBalance(context.treasury), treasuryBefore);
+    });
+  });
+
+  describe("marketplace stats", () => {
+    const marketplace = marketplacePda();
+
+    const stats = () => program.methods.getMarketplaceStats().accounts({ marketplace }).view();
+
+    const delistContextNft = async (ctx: MarketplaceContext) =>
+      program.methods
+        .delistNft()
+        .accounts({
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          sellerTokenAccount: ctx.makerAta,
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          masterEdition: null,
+          metadata: null,
+          listingTokenRecord: null,
+          sellerTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          associatedTokenProgram: null,
+          metadataProgram: null,
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          collectionStats: null,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+
+    it("counts listings opened and closed by delisting", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const before = await stats();
+
+      await listContextNft(context);
+      assert.ok((await stats()).activeListings.eq(before.activeListings.addn(1)));
+
+      await delistContextNft(context);
+      const after = await stats();
+      assert.ok(after.activeListings.eq(before.activeListings));
+      assert.ok(after.salesCount.eq(before.salesCount));
+    });
+
+    it("adds purchases to the volume, sales count and fees", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      await listContextNft(context);
+      const before = await stats();
+
+      await purchaseContextNft(context);
+
+      const after = await stats();
+      const fee = context.price.muln(100).divn(10_000);
+      assert.ok(after.totalVolume.eq(before.totalVolume.add(context.price)));
+      assert.ok(after.salesCount.eq(before.salesCount.addn(1)));
+      assert.ok(after.totalFees.eq(before.totalFees.add(fee)));
+      assert.ok(after.activeListings.eq(before.activeListings.subn(1)));
+    });
+
+    it("rejects migrating a marketplace that already tracks stats", async () => {
+      await expectError(
+        program.methods
+          .migrateMarketplaceStats("")
+          .accounts({
+            admin: provider.wallet.publicKey,
+            //@ts-ignore
+            marketplace,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc(),
+        "AlreadyMigrated"
+      );
+    });
+  });
+
+  describe("named marketplaces", () => {
+    let admin: Keypair;
+    let main: MarketplaceContext;
+    let test: MarketplaceContext;
+
+    const initializeNamed = (ctx: MarketplaceContext, feeBps: number, name: string) =>
+      program.methods
+        .initializeMarketplace(feeBps, ctx.treasury, name)
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          treasury: ctx.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    const stats = (ctx: MarketplaceContext) =>
+      program.methods.getMarketplaceStats().accounts({ marketplace: ctx.marketplace }).view();
+
+    before(async () => {
+      admin = await fundedKeypair();
+      main = await setupMarketplace("verified", admin.publicKey, "main");
+      test = await setupMarketplace("verified", admin.publicKey, "test");
+
+      await initializeNamed(main, 100, "main");
+      await initializeNamed(test, 500, "test");
+      for (const ctx of [main, test]) {
+        await addCollection(ctx, admin);
+        await listContextNft(ctx);
+      }
+    });
+
+    it("rejects names longer than 32 bytes", async () => {
+      const name = "x".repeat(33);
+      const marketplace = PublicKey.findProgramAddressSync(
+        [Buffer.from("marketplace"), admin.publicKey.toBuffer(), Buffer.from(name.slice(0, 32))],
+        program.programId
+      )[0];
+      const [treasury] = PublicKey.findProgramAddressSync(
+        [Buffer.from("treasury"), marketplace.toBuffer()],
+        program.programId
+      );
+      await expectError(
+        initializeNamed({ ...main, marketplace, treasury }, 100, name),
+        "InvalidMarketplaceName"
+      );
+    });
+
+    it("stores the name and fee of each marketplace under its own PDA", async () => {
+      assert.notOk(main.marketplace.equals(test.marketplace));
+
+      const mainState = await program.account.marketplace.fetch(main.marketplace);
+      const testState = await program.account.marketplace.fetch(test.marketplace);
+      assert.equal(mainState.name, "main");
+      assert.equal(testState.name, "test");
+      assert.equal(mainState.feeBps, 100);
+      assert.equal(testState.feeBps, 500);
+    });
+
+    it("announces new marketplaces with an event", async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey, "events");
+      const before = await chainTime();
+
+      const [event] = await parseEvents(
+        await initializeNamed(ctx, 250, "events"),
+        "marketplaceInitializedEvent"
+      );
+
+      assert.ok(event.marketplace.equals(ctx.marketplace));
+      assert.ok(event.admin.equals(admin.publicKey));
+      assert.equal(event.feeBps, 250);
+      assert.ok(event.treasury.equals(ctx.treasury));
+      assert.ok(event.feeRecipient.equals(ctx.treasury));
+      assert.equal(event.name, "events");
+      assert.isNull(event.paymentMint);
+
+      const state = await program.account.marketplace.fetch(ctx.marketplace);
+      assert.approximately(state.createdAt.toNumber(), before, 60);
+    });
+
+    it("scopes listings to their marketplace", async () => {
+      const listing = await program.account.listing.fetch(main.listing);
+      assert.ok(listing.marketplace.equals(main.marketplace));
+
+      // The main listing cannot be bought through the test marketplace
+      let failed = false;
+      try {
+        await purchaseContextNft({ ...main, marketplace: test.marketplace, treasury: test.treasury });
+      } catch {
+        failed = true;
+      }
+      assert.isTrue(failed, "expected the purchase through the other marketplace to fail");
+    });
+
+    it("charges each marketplace's fee to its own treasury", async () => {
+      for (const [ctx, other, feeBps] of [
+        [main, test, 100],
+        [test, main, 500],
+      ] as const) {
+        const treasuryBefore = await connection.getBalance(ctx.treasury);
+        const otherBefore = await connection.getBalance(other.treasury);
+
+        await purchaseContextNft(ctx);
+
+        const fee = ctx.price.muln(feeBps).divn(10_000).toNumber();
+        assert.equal(await connection.getBalance(ctx.treasury), treasuryBefore + fee);
+        assert.equal(await connection.getBalance(other.treasury), otherBefore);
+      }
+
+      for (const ctx of [main, test]) {
+        const marketStats = await stats(ctx);
+        assert.ok(marketStats.salesCount.eqn(1));
+        assert.ok(marketStats.totalVolume.eq(ctx.price));
+        assert.ok(marketStats.activeListings.eqn(0));
+      }
+    });
+  });
+
+  describe("nft validation", () => {
+    // Mints `amount` units of a fungible token to a fresh maker; it has metadata but no master edition
+    const setupFungible = async (decimals: number, amount: number): Promise<MarketplaceContext> => {
+      const base = await setupMarketplace("none");
+      const mintSigner = generateSigner(base.umi);
+      await createFungible(base.umi, {
+        mint: mintSigner,
+        name: "Coin",
+        symbol: "COIN",
+        uri: "https://arweave.net/123",
+        sellerFeeBasisPoints: percentAmount(0),
+        decimals: some(decimals),
+      }).sendAndConfirm(base.umi);
+      await mintV1(base.umi, {
+        mint: mintSigner.publicKey,
+        amount,
+        tokenOwner: publicKey(base.maker.publicKey),
+        tokenStandard: TokenStandard.Fungible,
+      }).sendAndConfirm(base.umi);
+
+      const mint = new PublicKey(mintSigner.publicKey);
+      const listing = await nextListingPda(base.marketplace, base.maker.publicKey, mint);
+
+      return {
+        ...base,
+        nftMint: mintSigner,
+        makerAta: getAssociatedTokenAddressSync(mint, base.maker.publicKey),
+        vault: getAssociatedTokenAddressSync(mint, listing, true),
+        listing,
+      };
+    };
+
+    const listUnit = async (ctx: MarketplaceContext) =>
+      program.methods
+        .listNft(ctx.price, new anchor.BN(0), new anchor.BN(1), null, 0, new anchor.BN(0), null, 0)
+        .accounts({
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          sellerTokenAccount: ctx.makerAta,
+          marketplace: ctx.marketplace,
+          listingIndex: await nextListingIndexPda(ctx.marketplace),
+          collectionMint: ctx.collectionMint.publicKey,
+          collectionConfig: collectionConfigPda(ctx),
+          collectionStats: null,
+          metadata: new PublicKey(findMetadataPda(ctx.umi, { mint: ctx.nftMint.publicKey })[0]),
+          masterEdition: null,
+          sellerTokenRecord: null,
+          listingTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+        })
+        .signers([ctx.maker])
+        .rpc();
+
+    before(async () => {
+      await setOpenListings(true);
+    });
+
+    after(async () => {
+      await setOpenListings(false);
+    });
+
+    it("rejects one unit of a fungible token", async () => {
+      const context = await setupFungible(6, 1_000_000);
+      await expectError(listUnit(context), "NotAnNft");
+    });
+
+    it("rejects a zero-decimal mint with a supply of 2", async () => {
+      const context = await setupFungible(0, 2);
+      await expectError(listUnit(context), "NotAnNft");
+      assert.isNull(await connection.getAccountInfo(context.listing));
+    });
+
+    it("lists a one-of-one NFT held by the seller", async () => {
+      const context = await setupMarketplace("none");
+      await listContextNft(context);
+
+      const nft = await getMint(connection, new PublicKey(context.nftMint.publicKey));
+      assert.equal(nft.decimals, 0);
+      assert.equal(Number(nft.supply), 1);
+      assert.equal(Number((await getAccount(connection, context.vault)).amount), 1);
+    });
+  });
+
+  describe("buyer protection", () => {
+    const windowSecs = 4;
+    let admin: Keypair;
+    let stranger: Keypair;
+
+    const saleEscrowPda = (ctx: MarketplaceContext) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("sale_escrow"), ctx.listing.toBuffer(), ctx.taker.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    // Sets up a fresh NFT whose collection is approved on the admin's marketplace
+    const setupNft = async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await addCollection(ctx, admin);
+      return ctx;
+    };
+
+    // Lists a fresh NFT with a protection window and sells it into a sale escrow
+    const protectedSale = async () => {
+      const ctx = await setupNft();
+      await listContextNft(ctx, 0, 1, null, windowSecs);
+      const tx = await purchaseContextNft(ctx, ctx.treasury, saleEscrowPda(ctx));
+      return { ctx, tx };
+    };
+
+    const releaseSale = (ctx: MarketplaceContext, authority: Keypair) =>
+      program.methods
+        .releaseSale()
+        .accounts({
+          authority: authority.publicKey,
+          seller: ctx.maker.publicKey,
+          buyer: ctx.taker.publicKey,
+          //@ts-ignore
+          saleEscrow: saleEscrowPda(ctx),
+        })
+        .signers([authority])
+        .rpc({ commitment: "confirmed" });
+
+    const disputeSale = (ctx: MarketplaceContext) =>
+      program.methods
+        .disputeSale()
+        .accounts({
+          buyer: ctx.taker.publicKey,
+          //@ts-ignore
+          saleEscrow: saleEscrowPda(ctx),
+        })
+        .signers([ctx.taker])
+        .rpc({ commitment: "confirmed" });
+
+    const resolveDispute = (ctx: MarketplaceContext, refundBuyer: boolean, signer = admin) =>
+      program.methods
+        .resolveSaleDispute(refundBuyer)
+        .accounts({
+          admin: signer.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          seller: ctx.maker.publicKey,
+          buyer: ctx.taker.publicKey,
+          saleEscrow: saleEscrowPda(ctx),
+        })
+        .signers([signer])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      admin = await fundedKeypair();
+      stranger = await fundedKeypair();
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .initializeMarketplace(100, ctx.treasury, "")
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          treasury: ctx.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("rejects windows longer than 30 days", async () => {
+      const ctx = await setupNft();
+      const maxWindow = Number(
+        program.idl.constants
+          .find((c) => c.name === "maxProtectionWindowSecs")
+          .value.replace(/_/g, "")
+      );
+      await expectError(listContextNft(ctx, 0, 1, null, maxWindow + 1), "InvalidProtectionWindow");
+    });
+
+    it("requires the sale escrow exactly for protected listings", async () => {
+      const ctx = await setupNft();
+      await listContextNft(ctx, 0, 1, null, windowSecs);
+      await expectError(purchaseContextNft(ctx), "InvalidSaleEscrow");
+    });
+
+    it("hands over the NFT but parks the proceeds until the buyer releases them", async () => {
+      const { ctx, tx } = await protectedSale();
+      const fee = ctx.price.muln(100).divn(10_000);
+      const proceeds = ctx.price.sub(fee);
+
+      const [event] = await parseEvents(tx, "saleEscrowedEvent");
+      assert.ok(event.amount.eq(proceeds));
+      assert.equal(Number((await getAccount(connection, ctx.takerAta)).amount), 1);
+
+      const escrow = await program.account.saleEscrow.fetch(saleEscrowPda(ctx));
+      assert.ok(escrow.seller.equals(ctx.maker.publicKey));
+      assert.ok(escrow.amount.eq(proceeds));
+      assert.isFalse(escrow.disputed);
+
+      await expectError(releaseSale(ctx, stranger), "ProtectionWindowOpen");
+
+      const sellerBefore = await connection.getBalance(ctx.maker.publicKey);
+      await releaseSale(ctx, ctx.taker);
+      assert.equal(
+        await connection.getBalance(ctx.maker.publicKey),
+        sellerBefore + proceeds.toNumber()
+      );
+      assert.isNull(await connection.getAccountInfo(saleEscrowPda(ctx)));
+    });
+
+    it("lets anyone release the proceeds once the window ends", async () => {
+      const { ctx } = await protectedSale();
+      const escrow = await program.account.saleEscrow.fetch(saleEscrowPda(ctx));
+      await waitForChainTime(escrow.releaseTs.toNumber());
+
+      await expectError(disputeSale(ctx), "ProtectionWindowClosed");
+
+      const sellerBefore = await connection.getBalance(ctx.maker.publicKey);
+      const [event] = await parseEvents(await releaseSale(ctx, stranger), "saleReleasedEvent");
+      assert.ok(event.releasedBy.equals(stranger.publicKey));
+      assert.equal(
+        await connection.getBalance(ctx.maker.publicKey),
+        sellerBefore + escrow.amount.toNumber()
+      );
+    });
+
+    it("holds disputed proceeds for the admin to refund the buyer", async () => {
+      const { ctx } = await protectedSale();
+      await disputeSale(ctx);
+      const escrow = await program.account.saleEscrow.fetch(saleEscrowPda(ctx));
+      assert.isTrue(escrow.disputed);
+
+      await waitForChainTime(escrow.releaseTs.toNumber());
+      await expectError(releaseSale(ctx, stranger), "SaleDisputed");
+      await expectError(resolveDispute(ctx, true, stranger), "Unauthorized");
+
+      const escrowLamports = await connection.getBalance(saleEscrowPda(ctx));
+      const buyerBefore = await connection.getBalance(ctx.taker.publicKey);
+      const [event] = await parseEvents(await resolveDispute(ctx, true), "saleDisputeResolvedEvent");
+      assert.ok(event.resolvedTo.equals(ctx.taker.publicKey));
+      assert.equal(await connection.getBalance(ctx.taker.publicKey), buyerBefore + escrowLamports);
+      assert.isNull(await connection.getAccountInfo(saleEscrowPda(ctx)));
+    });
+
+    it("lets the admin resolve a dispute in the seller's favour", async () => {
+      const { ctx } = await protectedSale();
+      await disputeSale(ctx);
+      const escrow = await program.account.saleEscrow.fetch(saleEscrowPda(ctx));
+
+      const sellerBefore = await connection.getBalance(ctx.maker.publicKey);
+      await resolveDispute(ctx, false);
+      assert.equal(
+        await connection.getBalance(ctx.maker.publicKey),
+        sellerBefore + escrow.amount.toNumber()
+      );
+    });
+  });
+
+  describe("referrals", () => {
+    let admin: Keypair;
+    let referrer: Keypair;
+
+    const setReferralBps = (referralBps: number, ctx: MarketplaceContext, signer = admin) =>
+      program.methods
+        .setReferralBps(referralBps)
+        .accounts({
+          admin: signer.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+        })
+        .signers([signer])
+        .rpc();
+
+    // Lists a fresh NFT on the admin's marketplace
+    const listedNft = async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await addCollection(ctx, admin);
+      await listContextNft(ctx);
+      return ctx;
+    };
+
+    before(async () => {
+      admin = await fundedKeypair();
+      referrer = await fundedKeypair();
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .initializeMarketplace(100, ctx.treasury, "")
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          treasury: ctx.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("caps the referral share and restricts it to the admin", async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await expectError(setReferralBps(1_001, ctx), "InvalidReferralBps");
+      await expectError(setReferralBps(40, ctx, referrer), "Unauthorized");
+    });
+
+    it("pays the full fee to the fee recipient without a referrer", async () => {
+      const ctx = await listedNft();
+      await setReferralBps(40, ctx);
+      const treasuryBefore = await connection.getBalance(ctx.treasury);
+
+      const [event] = await parseEvents(await purchaseContextNft(ctx), "nftPurchasedEvent");
+
+      const fee = ctx.price.muln(100).divn(10_000).toNumber();
+      assert.equal(await connection.getBalance(ctx.treasury), treasuryBefore + fee);
+      assert.isNull(event.referrer);
+      assert.ok(event.referralPaid.eqn(0));
+    });
+
+    it("carves the referral out of the marketplace fee", async () => {
+      const ctx = await listedNft();
+      const treasuryBefore = await connection.getBalance(ctx.treasury);
+      const referrerBefore = await connection.getBalance(referrer.publicKey);
+      const sellerBefore = await connection.getBalance(ctx.maker.publicKey);
+
+      const tx = await purchaseContextNft(ctx, ctx.treasury, null, referrer.publicKey);
+
+      const fee = ctx.price.muln(100).divn(10_000).toNumber();
+      const referral = ctx.price.muln(40).divn(10_000).toNumber();
+      assert.equal(await connection.getBalance(referrer.publicKey), referrerBefore + referral);
+      assert.equal(await connection.getBalance(ctx.treasury), treasuryBefore + fee - referral);
+
+      // The seller still receives the price minus the full fee, plus the listing and vault rent
+      const sellerGain = (await connection.getBalance(ctx.maker.publicKey)) - sellerBefore;
+      assert.isAtLeast(sellerGain, ctx.price.toNumber() - fee);
+
+      const [event] = await parseEvents(tx, "nftPurchasedEvent");
+      assert.ok(event.referrer.equals(referrer.publicKey));
+      assert.ok(event.referralPaid.eqn(referral));
+    });
+  });
+
+  describe("collection offers", () => {
+    let admin: Keypair;
+    let context: MarketplaceContext;
+    const price = new anchor.BN(0.02 * LAMPORTS_PER_SOL);
+
+    const collectionOfferPda = (ctx: MarketplaceContext, buyer = ctx.taker.publicKey) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("coll_offer"),
+          ctx.marketplace.toBuffer(),
+          new PublicKey(ctx.collectionMint.publicKey).toBuffer(),
+          buyer.toBuffer(),
+        ],
+        program.programId
+      )[0];
+
+    const makeCollectionOffer = (ctx: MarketplaceContext, quantity: number, expiry: number) =>
+      program.methods
+        .makeCollectionOffer(price, new anchor.BN(quantity), new anchor.BN(expiry))
+        .accounts({
+          buyer: ctx.taker.publicKey,
+          collectionMint: ctx.collectionMint.publicKey,
+          //@ts-ignore
+          collectionOffer: collectionOfferPda(ctx),
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ctx.taker])
+        .rpc({ commitment: "confirmed" });
+
+    // `seller` sells the NFT of `nftCtx` into the collection offer of `ctx`
+    const acceptCollectionOffer = (ctx: MarketplaceContext, nftCtx = ctx) =>
+      program.methods
+        .acceptCollectionOffer()
+        .accounts({
+          seller: nftCtx.maker.publicKey,
+          buyer: ctx.taker.publicKey,
+          nft: nftCtx.nftMint.publicKey,
+          metadata: new PublicKey(findMetadataPda(nftCtx.umi, { mint: nftCtx.nftMint.publicKey })[0]),
+          sellerTokenAccount: nftCtx.makerAta,
+          buyerTokenAccount: getAssociatedTokenAddressSync(
+            new PublicKey(nftCtx.nftMint.publicKey),
+            ctx.taker.publicKey
+          ),
+          //@ts-ignore
+          collectionOffer: collectionOfferPda(ctx),
+          collectionConfig: collectionConfigPda(ctx),
+          marketplace: ctx.marketplace,
+          feeRecipient: ctx.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+        })
+        .signers([nftCtx.maker])
+        .rpc({ commitment: "confirmed" });
+
+    const cancelCollectionOffer = (
+      ctx: MarketplaceContext,
+      authority: Keypair,
+      instruction: "cancelCollectionOffer" | "refundExpiredCollectionOffer" = "cancelCollectionOffer"
+    ) =>
+      program.methods[instruction]()
+        .accounts({
+          authority: authority.publicKey,
+          buyer: ctx.taker.publicKey,
+          //@ts-ignore
+          collectionOffer: collectionOfferPda(ctx),
+          marketplace: ctx.marketplace,
+        })
+        .signers([authority])
+        .rpc({ commitment: "confirmed" });
+
+    // Sets up a fresh NFT whose collection is approved on the admin's marketplace
+    const setupNft = async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await addCollection(ctx, admin);
+      return ctx;
+    };
+
+    before(async () => {
+      admin = await fundedKeypair();
+      context = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .initializeMarketplace(100, context.treasury, "")
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: context.marketplace,
+          treasury: context.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      await addCollection(context, admin);
+    });
+
+    it("escrows the price of every NFT the offer buys", async () => {
+      const tx = await makeCollectionOffer(context, 2, (await chainTime()) + 3600);
+
+      const offer = await program.account.collectionOffer.fetch(collectionOfferPda(context));
+      assert.ok(offer.collectionMint.equals(new PublicKey(context.collectionMint.publicKey)));
+      assert.ok(offer.price.eq(price));
+      assert.equal(offer.quantity.toNumber(), 2);
+
+      const rent = await connection.getMinimumBalanceForRentExemption(
+        (await connection.getAccountInfo(collectionOfferPda(context))).data.length
+      );
+      assert.equal(
+        await connection.getBalance(collectionOfferPda(context)),
+        rent + price.muln(2).toNumber()
+      );
+
+      const [event] = await parseEvents(tx, "collectionOfferMadeEvent");
+      assert.equal(event.quantity.toNumber(), 2);
+    });
+
+    it("rejects NFTs from another collection", async () => {
+      const other = await setupNft();
+      await expectError(acceptCollectionOffer(context, other), "CollectionNotAllowed");
+    });
+
+    it("lets any holder of the collection sell into the offer", async () => {
+      const sellerBefore = await connection.getBalance(context.maker.publicKey);
+      const treasuryBefore = await connection.getBalance(context.treasury);
+
+      const tx = await acceptCollectionOffer(context);
+
+      const fee = price.muln(100).divn(10_000).toNumber();
+      assert.equal(await connection.getBalance(context.treasury), treasuryBefore + fee);
+      // The seller also pays the buyer's new token account
+      assert.isAbove(
+        (await connection.getBalance(context.maker.publicKey)) - sellerBefore,
+        0
+      );
+      assert.equal(Number((await getAccount(connection, context.takerAta)).amount), 1);
+
+      const offer = await program.account.collectionOffer.fetch(collectionOfferPda(context));
+      assert.equal(offer.quantity.toNumber(), 1);
+      const [event] = await parseEvents(tx, "collectionOfferAcceptedEvent");
+      assert.ok(event.nft.equals(new PublicKey(context.nftMint.publicKey)));
+      assert.equal(event.remaining.toNumber(), 1);
+    });
+
+    it("lets only the buyer cancel before expiry and refunds the remaining escrow", async () => {
+      await expectError(cancelCollectionOffer(context, admin), "NotOfferBuyer");
+      await expectError(
+        cancelCollectionOffer(context, admin, "refundExpiredCollectionOffer"),
+        "OfferNotExpired"
+      );
+
+      const offerLamports = await connection.getBalance(collectionOfferPda(context));
+      const buyerBefore = await connection.getBalance(context.taker.publicKey);
+      const tx = await cancelCollectionOffer(context, context.taker);
+
+      const [event] = await parseEvents(tx, "collectionOfferCancelledEvent");
+      assert.ok(event.refunded.eq(price));
+      assert.isNull(await connection.getAccountInfo(collectionOfferPda(context)));
+      // The buyer also pays the transaction fee
+      assert.isAbove(
+        await connection.getBalance(context.taker.publicKey),
+        buyerBefore + offerLamports - 10_000
+      );
+    });
+
+    it("closes the offer to the buyer once its last NFT is bought", async () => {
+      const ctx = await setupNft();
+      await makeCollectionOffer(ctx, 1, (await chainTime()) + 3600);
+      await acceptCollectionOffer(ctx);
+      assert.isNull(await connection.getAccountInfo(collectionOfferPda(ctx)));
+    });
+
+    it("rejects expired offers and lets anyone refund them", async () => {
+      const ctx = await setupNft();
+      const expiry = (await chainTime()) + 3;
+      await makeCollectionOffer(ctx, 1, expiry);
+      await waitForChainTime(expiry);
+
+      await expectError(acceptCollectionOffer(ctx), "OfferExpired");
+      const [event] = await parseEvents(
+        await cancelCollectionOffer(ctx, admin, "refundExpiredCollectionOffer"),
+        "collectionOfferCancelledEvent"
+      );
+      assert.ok(event.cancelledBy.equals(admin.publicKey));
+      assert.isNull(await connection.getAccountInfo(collectionOfferPda(ctx)));
+    });
+  });
+
+  describe("nft offers", () => {
+    let admin: Keypair;
+    let stranger: Keypair;
+    const amount = new anchor.BN(0.03 * LAMPORTS_PER_SOL);
+
+    const nftOfferPda = (ctx: MarketplaceContext) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("nft_offer"),
+          ctx.marketplace.toBuffer(),
+          new PublicKey(ctx.nftMint.publicKey).toBuffer(),
+          ctx.taker.publicKey.toBuffer(),
+        ],
+        program.programId
+      )[0];
+
+    const makeNftOffer = (ctx: MarketplaceContext, expiry: number) =>
+      program.methods
+        .makeNftOffer(amount, new anchor.BN(expiry))
+        .accounts({
+          buyer: ctx.taker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          nftOffer: nftOfferPda(ctx),
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ctx.taker])
+        .rpc({ commitment: "confirmed" });
+
+    const acceptNftOffer = (ctx: MarketplaceContext, holder = ctx.maker) =>
+      program.methods
+        .acceptNftOffer()
+        .accounts({
+          holder: holder.publicKey,
+          buyer: ctx.taker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          holderTokenAccount: getAssociatedTokenAddressSync(
+            new PublicKey(ctx.nftMint.publicKey),
+            holder.publicKey
+          ),
+          buyerTokenAccount: ctx.takerAta,
+          //@ts-ignore
+          nftOffer: nftOfferPda(ctx),
+          marketplace: ctx.marketplace,
+          feeRecipient: ctx.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([holder])
+        .rpc({ commitment: "confirmed" });
+
+    const cancelNftOffer = (
+      ctx: MarketplaceContext,
+      authority: Keypair,
+      instruction: "cancelNftOffer" | "refundExpiredNftOffer" = "cancelNftOffer"
+    ) =>
+      program.methods[instruction]()
+        .accounts({
+          authority: authority.publicKey,
+          buyer: ctx.taker.publicKey,
+          //@ts-ignore
+          nftOffer: nftOfferPda(ctx),
+          marketplace: ctx.marketplace,
+        })
+        .signers([authority])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      admin = await fundedKeypair();
+      stranger = await fundedKeypair();
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .initializeMarketplace(100, ctx.treasury, "")
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          treasury: ctx.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("sells an unlisted NFT to the offer at its holder's signature", async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await makeNftOffer(ctx, (await chainTime()) + 3600);
+      assert.isNull(await connection.getAccountInfo(ctx.listing));
+
+      // A wallet without the NFT cannot accept, even with its own token account
+      await getOrCreateAssociatedTokenAccount(
+        connection,
+        stranger,
+        new PublicKey(ctx.nftMint.publicKey),
+        stranger.publicKey
+      );
+      await expectError(acceptNftOffer(ctx, stranger), "NotNftHolder");
+
+      const treasuryBefore = await connection.getBalance(ctx.treasury);
+      const holderBefore = await connection.getBalance(ctx.maker.publicKey);
+      const tx = await acceptNftOffer(ctx);
+
+      const fee = amount.muln(100).divn(10_000).toNumber();
+      assert.equal(await connection.getBalance(ctx.treasury), treasuryBefore + fee);
+      assert.equal(
+        await connection.getBalance(ctx.maker.publicKey),
+        holderBefore + amount.toNumber() - fee - 5_000
+      );
+      assert.equal(Number((await getAccount(connection, ctx.takerAta)).amount), 1);
+      assert.isNull(await connection.getAccountInfo(nftOfferPda(ctx)));
+
+      const [event] = await parseEvents(tx, "nftOfferAcceptedEvent");
+      assert.ok(event.holder.equals(ctx.maker.publicKey));
+      assert.ok(event.amount.eq(amount));
+    });
+
+    it("lets only the buyer cancel before expiry", async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await makeNftOffer(ctx, (await chainTime()) + 3600);
+
+      await expectError(cancelNftOffer(ctx, stranger), "NotOfferBuyer");
+      await expectError(cancelNftOffer(ctx, stranger, "refundExpiredNftOffer"), "OfferNotExpired");
+      await cancelNftOffer(ctx, ctx.taker);
+      assert.isNull(await connection.getAccountInfo(nftOfferPda(ctx)));
+    });
+
+    it("rejects expired offers and lets anyone refund them", async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      const expiry = (await chainTime()) + 3;
+      await makeNftOffer(ctx, expiry);
+      await waitForChainTime(expiry);
+
+      await expectError(acceptNftOffer(ctx), "OfferExpired");
+      const [event] = await parseEvents(
+        await cancelNftOffer(ctx, stranger, "refundExpiredNftOffer"),
+        "nftOfferCancelledEvent"
+      );
+      assert.ok(event.cancelledBy.equals(stranger.publicKey));
+      assert.ok(event.amount.eq(amount));
+    });
+
+    it("pays the crank fee to whoever refunds an expired offer, but not to the buyer", async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      const setCrankFee = (crankFeeLamports: number, signer = admin) =>
+        program.methods
+          .setCrankFee(new anchor.BN(crankFeeLamports))
+          .accounts({
+            admin: signer.publicKey,
+            //@ts-ignore
+            marketplace: ctx.marketplace,
+          })
+          .signers([signer])
+          .rpc({ commitment: "confirmed" });
+      const max = Number(
+        program.idl.constants.find((c) => c.name === "maxCrankFeeLamports").value.replace(/_/g, "")
+      );
+      await expectError(setCrankFee(max + 1), "InvalidCrankFee");
+      await expectError(setCrankFee(10_000, stranger), "Unauthorized");
+      await setCrankFee(10_000);
+
+      // The buyer cancelling their own offer pays no bounty
+      await makeNftOffer(ctx, (await chainTime()) + 3600);
+      const [own] = await parseEvents(await cancelNftOffer(ctx, ctx.taker), "nftOfferCancelledEvent");
+      assert.equal(own.bounty.toNumber(), 0);
+
+      const expiry = (await chainTime()) + 3;
+      await makeNftOffer(ctx, expiry);
+      await expectError(cancelNftOffer(ctx, stranger, "refundExpiredNftOffer"), "OfferNotExpired");
+      await waitForChainTime(expiry);
+
+      const escrow = await connection.getBalance(nftOfferPda(ctx));
+      const buyerBefore = await connection.getBalance(ctx.taker.publicKey);
+      const [event] = await parseEvents(
+        await cancelNftOffer(ctx, stranger, "refundExpiredNftOffer"),
+        "nftOfferCancelledEvent"
+      );
+
+      assert.equal(event.bounty.toNumber(), 10_000);
+      assert.ok(event.cancelledBy.equals(stranger.publicKey));
+      assert.equal(await connection.getBalance(ctx.taker.publicKey), buyerBefore + escrow - 10_000);
+      assert.isNull(await connection.getAccountInfo(nftOfferPda(ctx)));
+    });
+
+    it("rejects a holder accepting their own offer", async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      const self = { ...ctx, taker: ctx.maker, takerAta: ctx.makerAta };
+      await makeNftOffer(self, (await chainTime()) + 3600);
+
+      await expectError(acceptNftOffer(self), "SelfPurchase");
+      assert.equal(Number((await getAccount(connection, ctx.makerAta)).amount), 1);
+    });
+  });
+
+  describe("payment breakdown", () => {
+    let admin: Keypair;
+    let referrer: Keypair;
+
+    const configure = async (feeBps: number, referralBps: number) => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .updateFee(feeBps)
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+        })
+        .signers([admin])
+        .rpc();
+      await program.methods
+        .setReferralBps(referralBps)
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+        })
+        .signers([admin])
+        .rpc();
+      await addCollection(ctx, admin);
+      await listContextNft(ctx);
+      return ctx;
+    };
+
+    before(async () => {
+      admin = await fundedKeypair();
+      referrer = await fundedKeypair();
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .initializeMarketplace(100, ctx.treasury, "")
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          treasury: ctx.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    for (const [feeBps, referralBps, referred] of [
+      [0, 0, false],
+      [100, 0, false],
+      [250, 40, true],
+      [1_000, 1_000, true],
+    ] as const) {
+      it(`adds up to the price with a ${feeBps} bps fee and ${referred ? referralBps : 0} bps referral`, async () => {
+        const ctx = await configure(feeBps, referralBps);
+        const sellerBefore = await connection.getBalance(ctx.maker.publicKey);
+
+        const tx = await purchaseContextNft(ctx, ctx.treasury, null, referred ? referrer.publicKey : null);
+        const [event] = await parseEvents(tx, "nftPurchasedEvent");
+
+        const fee = ctx.price.muln(feeBps).divn(10_000);
+        const referral = referred ? anchor.BN.min(ctx.price.muln(referralBps).divn(10_000), fee) : new anchor.BN(0);
+        assert.ok(event.marketplaceFee.eq(fee.sub(referral)));
+        assert.ok(event.referralPaid.eq(referral));
+        assert.ok(event.royaltyPaid.eqn(0));
+        assert.ok(event.sellerProceeds.eq(ctx.price.sub(fee)));
+        assert.ok(
+          event.sellerProceeds.add(event.marketplaceFee).add(event.royaltyPaid).add(event.referralPaid).eq(event.price)
+        );
+        assert.ok(event.paymentMint.equals(SystemProgram.programId));
+
+        // The seller also gets the listing and vault rent back
+        const sellerGain = (await connection.getBalance(ctx.maker.publicKey)) - sellerBefore;
+        assert.isAtLeast(sellerGain, event.sellerProceeds.toNumber());
+      });
+    }
+  });
+
+  describe("scheduled listings", () => {
+    const offerPda = (ctx: MarketplaceContext) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("offer"), ctx.listing.toBuffer(), ctx.taker.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const makeOffer = async (ctx: MarketplaceContext) =>
+      program.methods
+        .makeOffer(ctx.price.divn(2), new anchor.BN((await chainTime()) + 3600))
+        .accounts({
+          buyer: ctx.taker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          offer: offerPda(ctx),
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ctx.taker])
+        .rpc({ commitment: "confirmed" });
+
+    const acceptOffer = async (ctx: MarketplaceContext) =>
+      program.methods
+        .acceptOffer()
+        .accounts({
+          seller: ctx.maker.publicKey,
+          buyer: ctx.taker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          buyerTokenAccount: ctx.takerAta,
+          offer: offerPda(ctx),
+          marketplace: ctx.marketplace,
+          feeRecipient: ctx.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          paymentMint: null,
+          offerVault: null,
+          sellerPaymentAccount: null,
+          feeRecipientPaymentAccount: null,
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+
+    const delistContextNft = async (ctx: MarketplaceContext) =>
+      program.methods
+        .delistNft()
+        .accounts({
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          sellerTokenAccount: ctx.makerAta,
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          masterEdition: null,
+          metadata: null,
+          listingTokenRecord: null,
+          sellerTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          associatedTokenProgram: null,
+          metadataProgram: null,
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          collectionStats: null,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+
+    const scheduledNft = async (startTs: number) => {
+      const ctx = await setupMarketplace();
+      await addCollection(ctx);
+      const tx = await listContextNft(ctx, 0, 1, null, 0, startTs);
+      return { ctx, tx };
+    };
+
+    it("rejects a start at or after the expiry", async () => {
+      const ctx = await setupMarketplace();
+      await addCollection(ctx);
+      const expiry = (await chainTime()) + 3600;
+      await expectError(listContextNft(ctx, expiry, 1, null, 0, expiry), "InvalidListingStart");
+    });
+
+    it("announces the start time for countdowns", async () => {
+      const startTs = (await chainTime()) + 3600;
+      const { ctx, tx } = await scheduledNft(startTs);
+
+      const [event] = await parseEvents(tx, "nftListedEvent");
+      assert.ok(event.listing.equals(ctx.listing));
+      assert.equal(event.startTs.toNumber(), startTs);
+      const listing = await program.account.listing.fetch(ctx.listing);
+      assert.equal(listing.startTs.toNumber(), startTs);
+    });
+
+    it("rejects purchases before the start and accepts them after", async () => {
+      const startTs = (await chainTime()) + 3;
+      const { ctx } = await scheduledNft(startTs);
+
+      // Still a second or more before the start
+      await expectError(purchaseContextNft(ctx), "SaleNotStarted");
+
+      await waitForChainTime(startTs + 1);
+      await purchaseContextNft(ctx);
+      const nft = await connection.getTokenAccountBalance(ctx.takerAta);
+      assert.equal(nft.value.amount, "1");
+    });
+
+    it("rejects accepting an offer before the start", async () => {
+      const { ctx } = await scheduledNft((await chainTime()) + 3600);
+      await makeOffer(ctx);
+      await expectError(acceptOffer(ctx), "SaleNotStarted");
+    });
+
+    it("lets the seller delist before the start", async () => {
+      const { ctx } = await scheduledNft((await chainTime()) + 3600);
+      await delistContextNft(ctx);
+
+      const nft = await connection.getTokenAccountBalance(ctx.makerAta);
+      assert.equal(nft.value.amount, "1");
+      assert.isNull(await connection.getAccountInfo(ctx.listing));
+    });
+  });
+
+  describe("admin delist", () => {
+    const adminDelist = async (ctx: MarketplaceContext, admin?: Keypair, seller = ctx.maker.publicKey) => {
+      const builder = program.methods
+        .adminDelist(2)
+        .accounts({
+          admin: admin ? admin.publicKey : provider.wallet.publicKey,
+          seller,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          sellerTokenAccount: getAssociatedTokenAddressSync(ctx.nftMint.publicKey, seller),
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        });
+      return (admin ? builder.signers([admin]) : builder).rpc({ commitment: "confirmed" });
+    };
+
+    const listedNft = async () => {
+      const ctx = await setupMarketplace();
+      await addCollection(ctx);
+      await listContextNft(ctx);
+      return ctx;
+    };
+
+    it("restricts force-delisting to the admin", async () => {
+      const ctx = await listedNft();
+      await expectError(adminDelist(ctx, ctx.taker), "NotDelistAdmin");
+    });
+
+    it("can only return the NFT to the recorded seller", async () => {
+      const ctx = await listedNft();
+      await expectError(adminDelist(ctx, undefined, provider.wallet.publicKey), "ListingSellerMismatch");
+    });
+
+    it("returns the NFT to the seller and blocks purchases", async () => {
+      const ctx = await listedNft();
+
+      const tx = await adminDelist(ctx);
+
+      const [event] = await parseEvents(tx, "adminDelistEvent");
+      assert.ok(event.listing.equals(ctx.listing));
+      assert.ok(event.mint.equals(ctx.nftMint.publicKey));
+      assert.ok(event.seller.equals(ctx.maker.publicKey));
+      assert.equal(event.reason, 2);
+
+      const nft = await connection.getTokenAccountBalance(ctx.makerAta);
+      assert.equal(nft.value.amount, "1");
+      assert.isNull(await connection.getAccountInfo(ctx.listing));
+      assert.isNull(await connection.getAccountInfo(ctx.vault));
+
+      await expectError(purchaseContextNft(ctx), "AccountNotInitialized");
+    });
+  });
+
+  describe("fee tiers", () => {
+    let admin: Keypair;
+    const unset = { minVolume: new anchor.BN(0), feeBps: 0 };
+
+    const setFeeTiers = (ctx: MarketplaceContext, tiers: { minVolume: anchor.BN; feeBps: number }[], signer = admin) =>
+      program.methods
+        .setFeeTiers(tiers)
+        .accounts({
+          admin: signer.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+        })
+        .signers([signer])
+        .rpc();
+
+    // A second NFT on the admin's marketplace, handed to the seller of `ctx` to list
+    const secondNftOf = async (ctx: MarketplaceContext): Promise<MarketplaceContext> => {
+      const other = await setupMarketplace("verified", admin.publicKey);
+      await addCollection(other, admin);
+      const mint = new PublicKey(other.nftMint.publicKey);
+      const makerAta = await getOrCreateAssociatedTokenAccount(
+        connection,
+        provider.wallet.payer,
+        mint,
+        ctx.maker.publicKey
+      );
+      await transfer(connection, provider.wallet.payer, other.makerAta, makerAta.address, other.maker, 1);
+
+      const listing = await nextListingPda(other.marketplace, ctx.maker.publicKey, mint);
+      return {
+        ...other,
+        maker: ctx.maker,
+        makerAta: makerAta.address,
+        listing,
+        vault: getAssociatedTokenAddressSync(mint, listing, true),
+      };
+    };
+
+    before(async () => {
+      admin = await fundedKeypair();
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .initializeMarketplace(100, ctx.treasury, "")
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          treasury: ctx.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("rejects tiers that do not ascend or raise the fee, and non-admins", async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      const tier = (minVolume: number, feeBps: number) => ({ minVolume: new anchor.BN(minVolume), feeBps });
+      await expectError(setFeeTiers(ctx, [tier(2_000, 50), tier(1_000, 25), unset]), "InvalidFeeTiers");
+      await expectError(setFeeTiers(ctx, [tier(1_000, 50), tier(2_000, 75), unset]), "InvalidFeeTiers");
+      await expectError(setFeeTiers(ctx, [tier(1_000, 1_001), unset, unset]), "InvalidFeeTiers");
+      await expectError(setFeeTiers(ctx, [tier(1_000, 50), unset, unset], ctx.taker), "Unauthorized");
+    });
+
+    it("lets a seller create their statistics ahead of their first sale", async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .createSellerStats()
+        .accounts({
+          seller: ctx.maker.publicKey,
+          //@ts-ignore
+          sellerStats: sellerStatsPda(ctx.marketplace, ctx.maker.publicKey),
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ctx.maker])
+        .rpc();
+
+      const stats = await program.account.sellerStats.fetch(sellerStatsPda(ctx.marketplace, ctx.maker.publicKey));
+      assert.ok(stats.seller.equals(ctx.maker.publicKey));
+      assert.ok(stats.volume.eqn(0));
+    });
+
+    it("lowers the fee once the seller crosses a volume tier", async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await setFeeTiers(ctx, [{ minVolume: ctx.price, feeBps: 50 }, unset, unset]);
+      await addCollection(ctx, admin);
+      await listContextNft(ctx);
+
+      // Listing created the statistics with no volume yet, so the flat fee applies
+      const listed = await program.account.sellerStats.fetch(sellerStatsPda(ctx.marketplace, ctx.maker.publicKey));
+      assert.ok(listed.volume.eqn(0));
+      const [first] = await parseEvents(await purchaseContextNft(ctx), "nftPurchasedEvent");
+      assert.ok(first.marketplaceFee.eq(ctx.price.muln(100).divn(10_000)));
+
+      // The first sale reached the threshold, so the next one is discounted
+      const second = await secondNftOf(ctx);
+      await listContextNft(second);
+      const [event] = await parseEvents(await purchaseContextNft(second), "nftPurchasedEvent");
+      assert.ok(event.marketplaceFee.eq(second.price.muln(50).divn(10_000)));
+
+      const stats = await program.account.sellerStats.fetch(sellerStatsPda(ctx.marketplace, ctx.maker.publicKey));
+      assert.ok(stats.volume.eq(ctx.price.add(second.price)));
+      assert.equal(stats.salesCount.toNumber(), 2);
+    });
+  });
+
+  describe("token-2022 nfts", () => {
+    // Mints a Token-2022 NFT with Token Metadata to a fresh maker; listings are open,
+    // as the NFT belongs to no collection
+    const setupToken2022Nft = async (): Promise<MarketplaceContext> => {
+      const base = await setupMarketplace("none");
+      const nftMint = generateSigner(base.umi);
+      const mint = new PublicKey(nftMint.publicKey);
+      const makerAta = getAssociatedTokenAddressSync(mint, base.maker.publicKey, false, TOKEN_2022_PROGRAM_ID);
+      await createV1(base.umi, {
+        mint: nftMint,
+        name: "GM",
+        symbol: "GM",
+        uri: "https://arweave.net/123",
+        sellerFeeBasisPoints: percentAmount(0),
+        tokenStandard: TokenStandard.NonFungible,
+        splTokenProgram: publicKey(TOKEN_2022_PROGRAM_ID),
+      }).sendAndConfirm(base.umi);
+      await mintV1(base.umi, {
+        mint: nftMint.publicKey,
+        amount: 1,
+        token: publicKey(makerAta),
+        tokenOwner: publicKey(base.maker.publicKey),
+        tokenStandard: TokenStandard.NonFungible,
+        splTokenProgram: publicKey(TOKEN_2022_PROGRAM_ID),
+      }).sendAndConfirm(base.umi);
+
+      const listing = await nextListingPda(base.marketplace, base.maker.publicKey, mint);
+      return {
+        ...base,
+        nftMint,
+        makerAta,
+        takerAta: getAssociatedTokenAddressSync(mint, base.taker.publicKey, false, TOKEN_2022_PROGRAM_ID),
+        vault: getAssociatedTokenAddressSync(mint, listing, true, TOKEN_2022_PROGRAM_ID),
+        listing,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      };
+    };
+
+    const tokenBalance = async (account: PublicKey) =>
+      Number((await getAccount(connection, account, "confirmed", TOKEN_2022_PROGRAM_ID)).amount);
+
+    before(async () => {
+      await setOpenListings(true);
+    });
+
+    after(async () => {
+      await setOpenListings(false);
+    });
+
+    it("lists and delists a Token-2022 NFT", async () => {
+      const ctx = await setupToken2022Nft();
+      await listContextNft(ctx);
+      assert.equal(await tokenBalance(ctx.vault), 1);
+
+      await program.methods
+        .delistNft()
+        .accounts({
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          sellerTokenAccount: ctx.makerAta,
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          masterEdition: null,
+          metadata: null,
+          listingTokenRecord: null,
+          sellerTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          associatedTokenProgram: null,
+          metadataProgram: null,
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          collectionStats: null,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+
+      assert.equal(await tokenBalance(ctx.makerAta), 1);
+      assert.isNull(await connection.getAccountInfo(ctx.vault));
+    });
+
+    it("sells a Token-2022 NFT", async () => {
+      const ctx = await setupToken2022Nft();
+      await listContextNft(ctx);
+      const sellerBefore = await connection.getBalance(ctx.maker.publicKey);
+
+      await purchaseContextNft(ctx);
+
+      assert.equal(await tokenBalance(ctx.takerAta), 1);
+      assert.isNull(await connection.getAccountInfo(ctx.vault));
+      const fee = ctx.price.muln(100).divn(10_000).toNumber();
+      assert.isAtLeast(
+        (await connection.getBalance(ctx.maker.publicKey)) - sellerBefore,
+        ctx.price.toNumber() - fee
+      );
+    });
+
+    it("rejects a mint owned by another token program than the one passed", async () => {
+      const ctx = await setupToken2022Nft();
+      await expectError(listContextNft({ ...ctx, tokenProgram: TOKEN_PROGRAM_ID }), "ConstraintMintTokenProgram");
+    });
+  });
+
+  describe("seller listing nonces", () => {
+    const delistContextNft = async (ctx: MarketplaceContext) =>
+      program.methods
+        .delistNft()
+        .accounts({
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          sellerTokenAccount: ctx.makerAta,
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          masterEdition: null,
+          metadata: null,
+          listingTokenRecord: null,
+          sellerTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          associatedTokenProgram: null,
+          metadataProgram: null,
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          collectionStats: null,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+
+    const sellerStats = (ctx: MarketplaceContext) =>
+      program.account.sellerStats.fetch(sellerStatsPda(ctx.marketplace, ctx.maker.publicKey), "confirmed");
+
+    it("relists the same NFT at a new address", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      const mint = new PublicKey(context.nftMint.publicKey);
+
+      await listContextNft(context);
+      const first = context.listing;
+      assert.ok(first.equals(listingPda(context.marketplace, context.maker.publicKey, mint, 0)));
+      await delistContextNft(context);
+
+      await listContextNft(context);
+      assert.ok(context.listing.equals(listingPda(context.marketplace, context.maker.publicKey, mint, 1)));
+      assert.isFalse(context.listing.equals(first));
+      const listing = await program.account.listing.fetch(context.listing, "confirmed");
+      assert.ok(listing.nonce.eqn(1));
+
+      const stats = await sellerStats(context);
+      assert.ok(stats.listingsCreated.eqn(2));
+      assert.ok(stats.activeListings.eqn(1));
+      assert.ok(stats.listingNonce.eqn(2));
+    });
+
+    it("counts a sold out listing off the seller's active listings", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      await listContextNft(context);
+      assert.ok((await sellerStats(context)).activeListings.eqn(1));
+
+      await purchaseContextNft(context);
+
+      const stats = await sellerStats(context);
+      assert.ok(stats.listingsCreated.eqn(1));
+      assert.ok(stats.activeListings.eqn(0));
+      assert.ok(stats.salesCount.eqn(1));
+    });
+  });
+
+  describe("self purchases", () => {
+    it("rejects sellers buying their own listing", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      await listContextNft(context);
+      const before = await program.account.sellerStats.fetch(
+        sellerStatsPda(context.marketplace, context.maker.publicKey)
+      );
+
+      const self = { ...context, taker: context.maker, takerAta: context.makerAta };
+      await expectError(purchaseContextNft(self), "SelfPurchase");
+
+      // Nothing was sold, so no volume was recorded and the listing stays open
+      const after = await program.account.sellerStats.fetch(
+        sellerStatsPda(context.marketplace, context.maker.publicKey)
+      );
+      assert.ok(after.volume.eq(before.volume));
+      assert.ok(after.salesCount.eq(before.salesCount));
+      assert.isTrue((await program.account.listing.fetch(context.listing)).isActive);
+
+      await purchaseContextNft(context);
+      assert.equal(Number((await getAccount(connection, context.takerAta)).amount), 1);
+    });
+  });
+
+  describe("pull payments", () => {
+    let admin: Keypair;
+
+    const proceedsPda = (ctx: MarketplaceContext) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("proceeds"), ctx.marketplace.toBuffer(), ctx.maker.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const setPullPayments = (pullPayments: boolean, ctx: MarketplaceContext, signer = admin) =>
+      program.methods
+        .setPullPayments(pullPayments)
+        .accounts({
+          admin: signer.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+        })
+        .signers([signer])
+        .rpc();
+
+    const claimProceeds = (ctx: MarketplaceContext, destination: Keypair) =>
+      program.methods
+        .claimProceeds()
+        .accounts({
+          seller: ctx.maker.publicKey,
+          destination: destination.publicKey,
+          //@ts-ignore
+          proceeds: proceedsPda(ctx),
+        })
+        .signers([ctx.maker, destination])
+        .rpc({ commitment: "confirmed" });
+
+    // Lists a fresh NFT on the admin's marketplace
+    const listedNft = async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await addCollection(ctx, admin);
+      await listContextNft(ctx);
+      return ctx;
+    };
+
+    before(async () => {
+      admin = await fundedKeypair();
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .initializeMarketplace(100, ctx.treasury, "")
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          treasury: ctx.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("restricts the payment mode to the admin", async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      const stranger = await fundedKeypair();
+      await expectError(setPullPayments(true, ctx, stranger), "Unauthorized");
+      assert.isFalse((await program.account.marketplace.fetch(ctx.marketplace)).pullPayments);
+    });
+
+    it("requires the proceeds account exactly when pull payments are on", async () => {
+      const ctx = await listedNft();
+      await setPullPayments(true, ctx);
+      await expectError(purchaseContextNft(ctx), "InvalidProceedsAccount");
+
+      await setPullPayments(false, ctx);
+      await expectError(
+        purchaseContextNft(ctx, ctx.treasury, null, null, proceedsPda(ctx)),
+        "InvalidProceedsAccount"
+      );
+    });
+
+    it("credits a seller carrying another program's data and lets them claim", async () => {
+      const ctx = await listedNft();
+      await setPullPayments(true, ctx);
+
+      // Turn the seller into a data account of another program, like a program-owned PDA
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          SystemProgram.allocate({ accountPubkey: ctx.maker.publicKey, space: 64 }),
+          SystemProgram.assign({
+            accountPubkey: ctx.maker.publicKey,
+            programId: Keypair.generate().publicKey,
+          })
+        ),
+        [ctx.maker]
+      );
+      const treasuryBefore = await connection.getBalance(ctx.treasury);
+
+      await purchaseContextNft(ctx, ctx.treasury, null, null, proceedsPda(ctx));
+
+      const fee = ctx.price.muln(100).divn(10_000).toNumber();
+      const sellerAmount = ctx.price.toNumber() - fee;
+      assert.equal(await connection.getBalance(ctx.treasury), treasuryBefore + fee);
+      const proceeds = await program.account.proceeds.fetch(proceedsPda(ctx));
+      assert.ok(proceeds.seller.equals(ctx.maker.publicKey));
+      assert.ok(proceeds.amount.eqn(sellerAmount));
+
+      // The seller sweeps the proceeds to a wallet that can receive them
+      const destination = await fundedKeypair();
+      const destinationBefore = await connection.getBalance(destination.publicKey);
+      const proceedsLamports = await connection.getBalance(proceedsPda(ctx));
+      const [event] = await parseEvents(await claimProceeds(ctx, destination), "proceedsClaimedEvent");
+
+      assert.equal(await connection.getBalance(destination.publicKey), destinationBefore + sellerAmount);
+      assert.equal(await connection.getBalance(proceedsPda(ctx)), proceedsLamports - sellerAmount);
+      assert.ok(event.amount.eqn(sellerAmount));
+      assert.ok((await program.account.proceeds.fetch(proceedsPda(ctx))).amount.eqn(0));
+
+      await expectError(claimProceeds(ctx, destination), "NoProceedsToClaim");
+      await setPullPayments(false, ctx);
+    });
+  });
+
+  describe("fee math", () => {
+    it("sells for one lamport without paying the fee recipient", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      context.price = new anchor.BN(1);
+      await listContextNft(context);
+      const treasuryBefore = await connection.getBalance(context.treasury);
+
+      const [event] = await parseEvents(await purchaseContextNft(context), "nftPurchasedEvent");
+
+      // The fee rounds to zero, so the fee transfer is skipped rather than sent empty
+      assert.equal(await connection.getBalance(context.treasury), treasuryBefore);
+      assert.ok(event.marketplaceFee.eqn(0));
+      assert.ok(event.sellerProceeds.eqn(1));
+    });
+  });
+
+  describe("secondary prices", () => {
+    let admin: Keypair;
+    let usdc: PublicKey;
+    const usdcPrice = new anchor.BN(40_000_000);
+
+    const usdcBalance = async (owner: PublicKey) =>
+      (await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(usdc, owner, true))).value.amount;
+
+    const purchaseWithToken = async (ctx: MarketplaceContext) =>
+      program.methods
+        .purchaseNftWithToken(false)
+        .accounts({
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          buyer: ctx.taker.publicKey,
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          buyerTokenAccount: ctx.takerAta,
+          listingTokenAccount: ctx.vault,
+          sellerTokenAccount: null,
+          masterEdition: null,
+          metadata: metadataPda(ctx),
+          listingTokenRecord: null,
+          buyerTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          metadataProgram: null,
+          listing: ctx.listing,
+          feeRecipient: ctx.treasury,
+          paymentMint: usdc,
+          buyerPaymentAccount: getAssociatedTokenAddressSync(usdc, ctx.taker.publicKey),
+          sellerPaymentAccount: getAssociatedTokenAddressSync(usdc, ctx.maker.publicKey),
+          feeRecipientPaymentAccount: getAssociatedTokenAddressSync(usdc, ctx.treasury, true),
+          referrer: null,
+          referrerPaymentAccount: null,
+          saleEscrow: null,
+          proceeds: null,
+          collectionStats: null,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([ctx.taker])
+        .rpc({ commitment: "confirmed" });
+
+    const updateSecondaryPrice = (
+      ctx: MarketplaceContext,
+      secondaryPrice: { mint: PublicKey; amount: anchor.BN } | null
+    ) =>
+      program.methods
+        .updateSecondaryPrice(secondaryPrice)
+        .accounts({
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          marketplace: ctx.marketplace,
+        })
+        .signers([ctx.maker])
+        .rpc();
+
+    // Lists a fresh NFT on the admin's marketplace and funds its buyer with USDC
+    const listedNft = async (secondaryPrice: { mint: PublicKey; amount: anchor.BN } | null) => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await addCollection(ctx, admin);
+      await listContextNft(ctx, 0, 1, null, 0, 0, secondaryPrice);
+      const takerUsdc = await getOrCreateAssociatedTokenAccount(
+        connection,
+        provider.wallet.payer,
+        usdc,
+        ctx.taker.publicKey
+      );
+      await mintTo(connection, provider.wallet.payer, usdc, takerUsdc.address, provider.wallet.payer, 100_000_000);
+      return ctx;
+    };
+
+    before(async () => {
+      admin = await fundedKeypair();
+      usdc = await createMint(connection, provider.wallet.payer, provider.wallet.publicKey, null, 6);
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .initializeMarketplace(100, ctx.treasury, "")
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          treasury: ctx.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("sells for the secondary price in USDC", async () => {
+      const ctx = await listedNft({ mint: usdc, amount: usdcPrice });
+      const sellerSol = await connection.getBalance(ctx.maker.publicKey);
+
+      const [event] = await parseEvents(await purchaseWithToken(ctx), "nftPurchasedEvent");
+
+      const fee = usdcPrice.muln(100).divn(10_000);
+      assert.equal(await usdcBalance(ctx.maker.publicKey), usdcPrice.sub(fee).toString());
+      assert.equal(await usdcBalance(ctx.treasury), fee.toString());
+      assert.ok(event.paymentMint.equals(usdc));
+      assert.ok(event.price.eq(usdcPrice));
+      assert.ok(event.marketplaceFee.eq(fee));
+
+      // The seller only gets the listing and vault rent back in SOL
+      assert.isAbove(await connection.getBalance(ctx.maker.publicKey), sellerSol);
+      assert.isNull(await connection.getAccountInfo(ctx.listing));
+      assert.equal(Number((await getAccount(connection, ctx.takerAta)).amount), 1);
+    });
+
+    it("keeps the SOL path, and whichever purchase lands first wins", async () => {
+      const ctx = await listedNft({ mint: usdc, amount: usdcPrice });
+
+      const [event] = await parseEvents(await purchaseContextNft(ctx), "nftPurchasedEvent");
+      assert.ok(event.paymentMint.equals(SystemProgram.programId));
+      assert.ok(event.price.eq(ctx.price));
+
+      await expectError(purchaseWithToken(ctx), "AccountNotInitialized");
+    });
+
+    it("sets the secondary price after listing", async () => {
+      const ctx = await listedNft(null);
+      await expectError(purchaseWithToken(ctx), "NoSecondaryPrice");
+
+      await updateSecondaryPrice(ctx, { mint: usdc, amount: usdcPrice });
+      const listing = await program.account.listing.fetch(ctx.listing);
+      assert.ok(listing.secondaryPrice.mint.equals(usdc));
+      assert.ok(listing.secondaryPrice.amount.eq(usdcPrice));
+
+      await purchaseWithToken(ctx);
+      assert.equal(Number((await getAccount(connection, ctx.takerAta)).amount), 1);
+    });
+
+    it("rejects secondary prices of zero or on protected listings", async () => {
+      const ctx = await listedNft(null);
+      await expectError(
+        updateSecondaryPrice(ctx, { mint: usdc, amount: new anchor.BN(0) }),
+        "InvalidSecondaryPrice"
+      );
+
+      const protectedCtx = await setupMarketplace("verified", admin.publicKey);
+      await addCollection(protectedCtx, admin);
+      await expectError(
+        listContextNft(protectedCtx, 0, 1, null, 3600, 0, { mint: usdc, amount: usdcPrice }),
+        "InvalidSecondaryPrice"
+      );
+    });
+  });
+
+  describe("metadata swaps", () => {
+    // The provider wallet created the NFT, so it holds the update authority
+    const swapUri = async (ctx: MarketplaceContext, uri: string) => {
+      const metadata = await fetchMetadataFromSeeds(ctx.umi, { mint: ctx.nftMint.publicKey });
+      await updateV1(ctx.umi, {
+        mint: ctx.nftMint.publicKey,
+        data: { ...metadata, uri },
+      }).sendAndConfirm(ctx.umi);
+    };
+
+    it("stores the metadata hash on the listing", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      await listContextNft(context);
+
+      const listing = await program.account.listing.fetch(context.listing);
+      assert.notDeepEqual(listing.metadataHash, new Array(32).fill(0));
+    });
+
+    it("rejects purchases after the metadata was swapped, unless the buyer accepts it", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      await listContextNft(context);
+
+      await swapUri(context, "https://arweave.net/swapped");
+      await expectError(purchaseContextNft(context), "MetadataChanged");
+
+      await purchaseContextNft(context, context.treasury, null, null, null, true);
+      assert.equal(Number((await getAccount(connection, context.takerAta)).amount), 1);
+    });
+
+    it("rejects the metadata of another NFT", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      await listContextNft(context);
+
+      // The collection NFT's metadata has the same name, symbol and URI, but another mint
+      const other = { ...context, nftMint: context.collectionMint };
+      await expectError(
+        program.methods
+          .purchaseNft(false)
+          .accounts({
+            ...(await unindexAccounts(context.marketplace, context.listing)),
+            buyer: context.taker.publicKey,
+            seller: context.maker.publicKey,
+            nft: context.nftMint.publicKey,
+            //@ts-ignore
+            marketplace: context.marketplace,
+            buyerTokenAccount: context.takerAta,
+            listingTokenAccount: context.vault,
+            sellerTokenAccount: null,
+            masterEdition: null,
+            metadata: metadataPda(other),
+            listingTokenRecord: null,
+            buyerTokenRecord: null,
+            authorizationRules: null,
+            authorizationRulesProgram: null,
+            sysvarInstructions: null,
+            metadataProgram: null,
+            listing: context.listing,
+            feeRecipient: context.treasury,
+            paymentMint: null,
+            buyerPaymentAccount: null,
+            sellerPaymentAccount: null,
+            feeRecipientPaymentAccount: null,
+            referrer: null,
+            referrerPaymentAccount: null,
+            saleEscrow: null,
+            proceeds: null,
+            collectionStats: null,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          })
+          .signers([context.taker])
+          .rpc(),
+        "InvalidMetadataAccount"
+      );
+    });
+  });
+
+  describe("batch purchases", () => {
+    let context: MarketplaceContext;
+    let mints: PublicKey[];
+
+    const writable = (pubkey: PublicKey, isWritable = true) => ({
+      pubkey,
+      isWritable,
+      isSigner: false,
+    });
+
+    // The fresh maker's bulk listing gives its NFTs nonces 0 to 2
+    const listingOf = (i: number) =>
+      listingPda(context.marketplace, context.maker.publicKey, mints[i], i);
+
+    const vaultOf = (i: number) => getAssociatedTokenAddressSync(mints[i], listingOf(i), true);
+
+    const rewardsMint = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("rewards"), context.marketplace.toBuffer()],
+        program.programId
+      )[0];
+
+    const purchaseGroup = (i: number) => [
+      writable(mints[i], false),
+      writable(listingOf(i)),
+      writable(vaultOf(i)),
+      writable(getAssociatedTokenAddressSync(mints[i], context.taker.publicKey)),
+      writable(context.maker.publicKey),
+      writable(sellerStatsPda(context.marketplace, context.maker.publicKey)),
+      writable(new PublicKey(findMetadataPda(context.umi, { mint: publicKey(mints[i]) })[0]), false),
+      writable(getAssociatedTokenAddressSync(rewardsMint(), context.maker.publicKey)),
+    ];
+
+    const purchaseMany = async (maxTotal: anchor.BN, groups: ReturnType<typeof purchaseGroup>[]) => {
+      const index = await unindexGroups(
+        context.marketplace,
+        groups.map((group) => group[1].pubkey)
+      );
+      return program.methods
+        .purchaseMany(maxTotal)
+        .accounts({
+          buyer: context.taker.publicKey,
+          //@ts-ignore
+          marketplace: context.marketplace,
+          feeRecipient: context.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(groups.flatMap((group, i) => [...group, ...index[i]]))
+        .preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })])
+        .signers([context.taker])
+        .rpc({ commitment: "confirmed" });
+    };
+
+    const priceOf = (i: number) => context.price.addn(i);
+
+    before(async () => {
+      await setOpenListings(true);
+      context = await setupMarketplace("none");
+      mints = [];
+      for (let i = 0; i < 3; i++) {
+        const mint = generateSigner(context.umi);
+        await createNft(context.umi, {
+          mint,
+          name: "GM",
+          symbol: "GM",
+          uri: "https://arweave.net/123",
+          sellerFeeBasisPoints: percentAmount(5.5),
+          tokenOwner: publicKey(context.maker.publicKey),
+        }).sendAndConfirm(context.umi);
+        mints.push(new PublicKey(mint.publicKey));
+      }
+
+      await program.methods
+        .bulkList([0, 1, 2].map(priceOf))
+        .accounts({
+          seller: context.maker.publicKey,
+          //@ts-ignore
+          marketplace: context.marketplace,
+          collectionMint: context.collectionMint.publicKey,
+          collectionConfig: collectionConfigPda(context),
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          mints.flatMap((mint, i) => [
+            writable(mint, false),
+            writable(getAssociatedTokenAddressSync(mint, context.maker.publicKey)),
+            writable(vaultOf(i)),
+            writable(listingOf(i)),
+            writable(new PublicKey(findMetadataPda(context.umi, { mint: publicKey(mint) })[0]), false),
+          ])
+        )
+        .preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })])
+        .signers([context.maker])
+        .rpc({ commitment: "confirmed" });
+    });
+
+    after(async () => {
+      await setOpenListings(false);
+    });
+
+    it("rejects a batch whose accounts do not split into purchase groups", async () => {
+      // A full batch of groups exceeds the transaction size without a lookup table
+      const groups = [purchaseGroup(0), purchaseGroup(1).slice(1)];
+      await expectError(purchaseMany(new anchor.BN(LAMPORTS_PER_SOL), groups), "InvalidPurchaseBatchSize");
+    });
+
+    it("reverts the whole batch when it would exceed the maximum total", async () => {
+      const total = priceOf(0).add(priceOf(1));
+      await expectError(
+        purchaseMany(total.subn(1), [purchaseGroup(0), purchaseGroup(1)]),
+        "MaxTotalExceeded"
+      );
+
+      // The first purchase was rolled back with the second
+      assert.isTrue((await program.account.listing.fetch(listingOf(0))).isActive);
+      assert.equal(Number((await getAccount(connection, vaultOf(0))).amount), 1);
+    });
+
+    it("reverts the whole batch when one listing is invalid", async () => {
+      const badGroup = purchaseGroup(1);
+      // Point the second item's vault at the first item's vault
+      badGroup[2] = writable(vaultOf(0));
+
+      await expectError(
+        purchaseMany(new anchor.BN(LAMPORTS_PER_SOL), [purchaseGroup(0), badGroup]),
+        "InvalidBulkAccount"
+      );
+      assert.isTrue((await program.account.listing.fetch(listingOf(0))).isActive);
+    });
+
+    it("buys every listing of the batch and emits one event per NFT", async () => {
+      const statsBefore = await program.account.sellerStats.fetch(
+        sellerStatsPda(context.marketplace, context.maker.publicKey)
+      );
+      const total = priceOf(0).add(priceOf(1));
+
+      const tx = await purchaseMany(total, [purchaseGroup(0), purchaseGroup(1)]);
+
+      const events = await parseEvents(tx, "nftPurchasedEvent");
+      assert.equal(events.length, 2);
+      for (const i of [0, 1]) {
+        assert.ok(events[i].listing.equals(listingOf(i)));
+        assert.ok(events[i].buyer.equals(context.taker.publicKey));
+        assert.ok(events[i].price.eq(priceOf(i)));
+        assert.ok(events[i].sellerProceeds.add(events[i].marketplaceFee).eq(priceOf(i)));
+
+        assert.isNull(await connection.getAccountInfo(listingOf(i)));
+        assert.isNull(await connection.getAccountInfo(vaultOf(i)));
+        const buyerAta = getAssociatedTokenAddressSync(mints[i], context.taker.publicKey);
+        assert.equal(Number((await getAccount(connection, buyerAta, "confirmed")).amount), 1);
+      }
+
+      // Both sales were recorded against the same seller
+      const statsAfter = await program.account.sellerStats.fetch(
+        sellerStatsPda(context.marketplace, context.maker.publicKey)
+      );
+      assert.ok(statsAfter.volume.sub(statsBefore.volume).eq(total));
+      assert.ok(statsAfter.salesCount.sub(statsBefore.salesCount).eqn(2));
+    });
+
+    it("rejects a batch including a listing that was already sold", async () => {
+      await expectError(
+        purchaseMany(new anchor.BN(LAMPORTS_PER_SOL), [purchaseGroup(2), purchaseGroup(0)]),
+        "InvalidBulkAccount"
+      );
+      assert.isTrue((await program.account.listing.fetch(listingOf(2))).isActive);
+    });
+  });
+
+  describe("bundle listings", () => {
+    let context: MarketplaceContext;
+
+    const writable = (pubkey: PublicKey, isWritable = true) => ({
+      pubkey,
+      isWritable,
+      isSigner: false,
+    });
+
+    // Bundles take the seller's listing nonce like single listings
+    const bundlePda = (nonce: number) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("bundle"),
+          context.marketplace.toBuffer(),
+          context.maker.publicKey.toBuffer(),
+          new anchor.BN(nonce).toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+
+    const vaultOf = (mint: PublicKey, bundle: PublicKey) =>
+      getAssociatedTokenAddressSync(mint, bundle, true);
+
+    const mintNfts = async (count: number) => {
+      const minted: PublicKey[] = [];
+      for (let i = 0; i < count; i++) {
+        const mint = generateSigner(context.umi);
+        await createNft(context.umi, {
+          mint,
+          name: "GM",
+          symbol: "GM",
+          uri: "https://arweave.net/123",
+          sellerFeeBasisPoints: percentAmount(5.5),
+          tokenOwner: publicKey(context.maker.publicKey),
+        }).sendAndConfirm(context.umi);
+        minted.push(new PublicKey(mint.publicKey));
+      }
+      return minted;
+    };
+
+    const listBundle = (mints: PublicKey[], bundle: PublicKey) =>
+      program.methods
+        .listBundle(context.price)
+        .accounts({
+          seller: context.maker.publicKey,
+          //@ts-ignore
+          marketplace: context.marketplace,
+          bundle,
+          collectionMint: context.collectionMint.publicKey,
+          collectionConfig: collectionConfigPda(context),
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          mints.flatMap((mint) => [
+            writable(mint, false),
+            writable(getAssociatedTokenAddressSync(mint, context.maker.publicKey)),
+            writable(vaultOf(mint, bundle)),
+            writable(new PublicKey(findMetadataPda(context.umi, { mint: publicKey(mint) })[0]), false),
+          ])
+        )
+        .preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })])
+        .signers([context.maker])
+        .rpc({ commitment: "confirmed" });
+
+    const purchaseBundle = (mints: PublicKey[], bundle: PublicKey) =>
+      program.methods
+        .purchaseBundle()
+        .accounts({
+          buyer: context.taker.publicKey,
+          seller: context.maker.publicKey,
+          //@ts-ignore
+          marketplace: context.marketplace,
+          bundle,
+          feeRecipient: context.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          mints.flatMap((mint) => [
+            writable(mint, false),
+            writable(vaultOf(mint, bundle)),
+            writable(getAssociatedTokenAddressSync(mint, context.taker.publicKey)),
+          ])
+        )
+        .preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })])
+        .signers([context.taker])
+        .rpc({ commitment: "confirmed" });
+
+    const delistBundle = (mints: PublicKey[], bundle: PublicKey) =>
+      program.methods
+        .delistBundle()
+        .accounts({
+          seller: context.maker.publicKey,
+          //@ts-ignore
+          marketplace: context.marketplace,
+          bundle,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          mints.flatMap((mint) => [
+            writable(mint, false),
+            writable(vaultOf(mint, bundle)),
+            writable(getAssociatedTokenAddressSync(mint, context.maker.publicKey)),
+          ])
+        )
+        .signers([context.maker])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      await setOpenListings(true);
+      context = await setupMarketplace("none");
+    });
+
+    after(async () => {
+      await setOpenListings(false);
+    });
+
+    it("sells a 3-NFT bundle whole for one price", async () => {
+      const mints = await mintNfts(3);
+      // The fresh maker's first listing takes nonce 0
+      const bundle = bundlePda(0);
+
+      const listTx = await listBundle(mints, bundle);
+      const [listed] = await parseEvents(listTx, "bundleListedEvent");
+      assert.ok(listed.bundle.equals(bundle));
+      assert.deepEqual(listed.mints.map((m: PublicKey) => m.toBase58()), mints.map((m) => m.toBase58()));
+      const state = await program.account.bundleListing.fetch(bundle);
+      assert.ok(state.price.eq(context.price));
+      assert.equal(state.mints.length, 3);
+      for (const mint of mints) {
+        assert.equal(Number((await getAccount(connection, vaultOf(mint, bundle))).amount), 1);
+      }
+
+      // Partial purchases are rejected
+      await expectError(purchaseBundle(mints.slice(0, 2), bundle), "InvalidBundleAccount");
+
+      const sellerBefore = await connection.getBalance(context.maker.publicKey);
+      let rentRefund = await connection.getBalance(bundle);
+      for (const mint of mints) {
+        rentRefund += await connection.getBalance(vaultOf(mint, bundle));
+      }
+
+      const tx = await purchaseBundle(mints, bundle);
+
+      const [event] = await parseEvents(tx, "bundlePurchasedEvent");
+      assert.ok(event.price.eq(context.price));
+      assert.ok(event.sellerProceeds.add(event.marketplaceFee).eq(context.price));
+      assert.equal(event.mints.length, 3);
+      for (const mint of mints) {
+        const buyerAta = getAssociatedTokenAddressSync(mint, context.taker.publicKey);
+        assert.equal(Number((await getAccount(connection, buyerAta, "confirmed")).amount), 1);
+        assert.isNull(await connection.getAccountInfo(vaultOf(mint, bundle)));
+      }
+      assert.isNull(await connection.getAccountInfo(bundle));
+
+      // The seller is paid once, and refunded the bundle and vault rent
+      const sellerAfter = await connection.getBalance(context.maker.publicKey);
+      assert.equal(sellerAfter - sellerBefore, event.sellerProceeds.toNumber() + rentRefund);
+    });
+
+    it("delists a bundle and returns every NFT", async () => {
+      const mints = await mintNfts(3);
+      const bundle = bundlePda(1);
+      await listBundle(mints, bundle);
+
+      await expectError(delistBundle(mints.slice(1), bundle), "InvalidBundleAccount");
+
+      const tx = await delistBundle(mints, bundle);
+
+      const [event] = await parseEvents(tx, "bundleDelistedEvent");
+      assert.ok(event.bundle.equals(bundle));
+      for (const mint of mints) {
+        const makerAta = getAssociatedTokenAddressSync(mint, context.maker.publicKey);
+        assert.equal(Number((await getAccount(connection, makerAta)).amount), 1);
+        assert.isNull(await connection.getAccountInfo(vaultOf(mint, bundle)));
+      }
+      assert.isNull(await connection.getAccountInfo(bundle));
+    });
+  });
+
+  describe("installment purchases", () => {
+    const installmentPlanPda = (ctx: MarketplaceContext) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("installment"), ctx.listing.toBuffer()],
+        program.programId
+      )[0];
+
+    const setInstallmentTerms = (graceSecs: number, forfeitBps: number) =>
+      program.methods
+        .setInstallmentTerms(graceSecs, forfeitBps)
+        .accounts({
+          admin: provider.wallet.publicKey,
+          //@ts-ignore
+          marketplace: marketplacePda(),
+        })
+        .rpc();
+
+    const startInstallmentPurchase = (ctx: MarketplaceContext, numPayments: number, intervalSecs: number) =>
+      program.methods
+        .startInstallmentPurchase(numPayments, intervalSecs)
+        .accounts({
+          buyer: ctx.taker.publicKey,
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          listing: ctx.listing,
+          installmentPlan: installmentPlanPda(ctx),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ctx.taker])
+        .rpc({ commitment: "confirmed" });
+
+    const payInstallment = async (ctx: MarketplaceContext) =>
+      program.methods
+        .payInstallment()
+        .accounts({
+          buyer: ctx.taker.publicKey,
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          buyerTokenAccount: ctx.takerAta,
+          installmentPlan: installmentPlanPda(ctx),
+          feeRecipient: ctx.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+        })
+        .signers([ctx.taker])
+        .rpc({ commitment: "confirmed" });
+
+    const defaultInstallment = (ctx: MarketplaceContext) =>
+      program.methods
+        .defaultInstallment()
+        .accounts({
+          seller: ctx.maker.publicKey,
+          buyer: ctx.taker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          listing: ctx.listing,
+          installmentPlan: installmentPlanPda(ctx),
+          feeRecipient: ctx.treasury,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+
+    const listedContext = async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      await listContextNft(context);
+      return context;
+    };
+
+    before(async () => {
+      await setInstallmentTerms(2, 2_500);
+    });
+
+    after(async () => {
+      await setInstallmentTerms(0, 0);
+    });
+
+    it("rejects forfeit shares above 100%", async () => {
+      await expectError(setInstallmentTerms(0, 10_001), "InvalidInstallmentTerms");
+    });
+
+    it("rejects schedules outside the installment bounds", async () => {
+      const context = await listedContext();
+      const max = Number(program.idl.constants.find((c) => c.name === "maxInstallments").value);
+      await expectError(startInstallmentPurchase(context, 1, 60), "InvalidInstallmentSchedule");
+      await expectError(startInstallmentPurchase(context, max + 1, 60), "InvalidInstallmentSchedule");
+      await expectError(startInstallmentPurchase(context, 3, 0), "InvalidInstallmentSchedule");
+    });
+
+    it("locks the listing and transfers the NFT after the last installment", async () => {
+      const context = await listedContext();
+      const plan = installmentPlanPda(context);
+
+      const startTx = await startInstallmentPurchase(context, 3, 3600);
+      const [started] = await parseEvents(startTx, "installmentPurchaseStartedEvent");
+      assert.ok(started.price.eq(context.price));
+      assert.ok(started.firstPayment.eq(context.price.divn(3)));
+      assert.isFalse((await program.account.listing.fetch(context.listing)).isActive);
+
+      // Nobody else can buy the locked listing, and the seller cannot take it back
+      const other = { ...context, taker: await fundedKeypair() };
+      other.takerAta = getAssociatedTokenAddressSync(new PublicKey(context.nftMint.publicKey), other.taker.publicKey);
+      await expectError(purchaseContextNft(other), "ListingNotActive");
+
+      const paidTx = await payInstallment(context);
+      const [paid] = await parseEvents(paidTx, "installmentPaidEvent");
+      assert.equal(paid.paymentsMade, 2);
+      assert.equal(Number((await getAccount(connection, context.vault)).amount), 1);
+
+      const sellerBefore = await connection.getBalance(context.maker.publicKey);
+      const feeRecipientBefore = await connection.getBalance(context.treasury);
+      const rentRefund =
+        (await connection.getBalance(context.listing)) + (await connection.getBalance(context.vault));
+
+      const lastTx = await payInstallment(context);
+
+      const [completed] = await parseEvents(lastTx, "installmentPurchaseCompletedEvent");
+      assert.ok(completed.sellerProceeds.add(completed.marketplaceFee).eq(context.price));
+      assert.equal(Number((await getAccount(connection, context.takerAta, "confirmed")).amount), 1);
+      assert.isNull(await connection.getAccountInfo(context.listing));
+      assert.isNull(await connection.getAccountInfo(context.vault));
+      assert.isNull(await connection.getAccountInfo(plan));
+
+      const sellerAfter = await connection.getBalance(context.maker.publicKey);
+      const feeRecipientAfter = await connection.getBalance(context.treasury);
+      assert.equal(sellerAfter - sellerBefore, completed.sellerProceeds.toNumber() + rentRefund);
+      assert.equal(feeRecipientAfter - feeRecipientBefore, completed.marketplaceFee.toNumber());
+    });
+
+    it("rejects defaults before the grace period has passed", async () => {
+      const context = await listedContext();
+      await startInstallmentPurchase(context, 2, 3600);
+
+      await expectError(defaultInstallment(context), "InstallmentNotDefaulted");
+    });
+
+    it("forfeits part of a missed plan to the seller and relists the NFT", async () => {
+      const context = await listedContext();
+      const plan = installmentPlanPda(context);
+      await startInstallmentPurchase(context, 3, 1);
+      const { startTs, paid } = await program.account.installmentPlan.fetch(plan);
+
+      // The second installment falls due one second in, then the two-second grace period runs
+      await waitForChainTime(startTs.toNumber() + 1 + 2 + 1);
+
+      const buyerBefore = await connection.getBalance(context.taker.publicKey);
+      const sellerBefore = await connection.getBalance(context.maker.publicKey);
+      const planRent = await connection.getBalance(plan) - paid.toNumber();
+
+      const tx = await defaultInstallment(context);
+
+      const [event] = await parseEvents(tx, "installmentDefaultedEvent");
+      assert.ok(event.paid.eq(paid));
+      assert.ok(event.forfeited.eq(paid.muln(2_500).divn(10_000)));
+      assert.ok(event.refunded.eq(paid.sub(event.forfeited)));
+
+      const buyerAfter = await connection.getBalance(context.taker.publicKey);
+      const sellerAfter = await connection.getBalance(context.maker.publicKey);
+      assert.equal(buyerAfter - buyerBefore, event.refunded.toNumber() + planRent);
+      assert.equal(sellerAfter - sellerBefore, event.forfeited.sub(event.marketplaceFee).toNumber());
+      assert.isNull(await connection.getAccountInfo(plan));
+
+      // The NFT never left the vault and can be bought again
+      assert.isTrue((await program.account.listing.fetch(context.listing)).isActive);
+      await purchaseContextNft(context);
+      assert.equal(Number((await getAccount(connection, context.takerAta)).amount), 1);
+    });
+  });
+
+  describe("provenance", () => {
+    const provenancePda = (ctx: MarketplaceContext) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("provenance"),
+          ctx.marketplace.toBuffer(),
+          new PublicKey(ctx.nftMint.publicKey).toBuffer(),
+        ],
+        program.programId
+      )[0];
+
+    const getProvenance = (ctx: MarketplaceContext) =>
+      program.methods.getProvenance().accounts({ provenance: provenancePda(ctx) }).view();
+
+    // The buyer of the last sale relists the NFT, so the next sale goes the other way
+    const flipParties = (ctx: MarketplaceContext) => {
+      [ctx.maker, ctx.taker] = [ctx.taker, ctx.maker];
+      [ctx.makerAta, ctx.takerAta] = [ctx.takerAta, ctx.makerAta];
+    };
+
+    it("records a sale with its buyer and seller", async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      await listContextNft(context);
+      await purchaseContextNft(context);
+
+      const history = await getProvenance(context);
+      assert.ok(history.totalSales.eqn(1));
+      assert.ok(history.lifetimeVolume.eq(context.price));
+      assert.equal(history.sales.length, 1);
+      assert.ok(history.sales[0].price.eq(context.price));
+      assert.ok(history.sales[0].buyer.equals(context.taker.publicKey));
+      assert.ok(history.sales[0].seller.equals(context.maker.publicKey));
+      assert.isAbove(history.sales[0].timestamp.toNumber(), 0);
+    });
+
+    it("evicts the oldest sales once the history is full", async () => {
+      const capacity = Number(
+        program.idl.constants.find((c) => c.name === "provenanceCapacity").value
+      );
+      const context = await setupMarketplace();
+      await addCollection(context);
+
+      const buyers: PublicKey[] = [];
+      const sales = capacity + 2;
+      for (let i = 0; i < sales; i++) {
+        await listContextNft(context);
+        await purchaseContextNft(context);
+        buyers.push(context.taker.publicKey);
+        flipParties(context);
+      }
+
+      const history = await getProvenance(context);
+      assert.ok(history.totalSales.eqn(sales));
+      assert.ok(history.lifetimeVolume.eq(context.price.muln(sales)));
+      // Only the most recent sales are kept, oldest first
+      assert.equal(history.sales.length, capacity);
+      history.sales.forEach((sale, i) => {
+        assert.ok(sale.buyer.equals(buyers[sales - capacity + i]));
+      });
+      for (let i = 1; i < capacity; i++) {
+        assert.isAtLeast(history.sales[i].timestamp.toNumber(), history.sales[i - 1].timestamp.toNumber());
+      }
+    });
+  });
+
+  describe("collection stats", () => {
+    let context: MarketplaceContext;
+    let cheaper: MarketplaceContext;
+    let collectionStats: PublicKey;
+
+    const collectionStatsPda = (ctx: MarketplaceContext) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("collection_stats"),
+          ctx.marketplace.toBuffer(),
+          new PublicKey(ctx.collectionMint.publicKey).toBuffer(),
+        ],
+        program.programId
+      )[0];
+
+    const getCollectionStats = () =>
+      program.methods.getCollectionStats().accounts({ collectionStats }).view();
+
+    // Another verified NFT of the context's collection, held by the same seller
+    const nftOfCollection = async (ctx: MarketplaceContext, price: anchor.BN): Promise<MarketplaceContext> => {
+      const nftMint = generateSigner(ctx.umi);
+      await createNft(ctx.umi, {
+        mint: nftMint,
+        name: "GM",
+        symbol: "GM",
+        uri: "https://arweave.net/123",
+        sellerFeeBasisPoints: percentAmount(5.5),
+        collection: { verified: false, key: ctx.collectionMint.publicKey },
+        tokenOwner: publicKey(ctx.maker.publicKey),
+      }).sendAndConfirm(ctx.umi);
+      await verifySizedCollectionItem(ctx.umi, {
+        metadata: findMetadataPda(ctx.umi, { mint: nftMint.publicKey }),
+        collectionAuthority: createSignerFromKeypair(
+          ctx.umi,
+          ctx.umi.eddsa.createKeypairFromSecretKey(new Uint8Array(provider.wallet.payer.secretKey))
+        ),
+        collectionMint: ctx.collectionMint.publicKey,
+        collection: findMetadataPda(ctx.umi, { mint: ctx.collectionMint.publicKey }),
+        collectionMasterEditionAccount: findMasterEditionPda(ctx.umi, { mint: ctx.collectionMint.publicKey }),
+      }).sendAndConfirm(ctx.umi);
+
+      const mint = new PublicKey(nftMint.publicKey);
+      const takerAta = (
+        await getOrCreateAssociatedTokenAccount(connection, ctx.taker, mint, ctx.taker.publicKey)
+      ).address;
+      return {
+        ...ctx,
+        nftMint,
+        makerAta: getAssociatedTokenAddressSync(mint, ctx.maker.publicKey),
+        takerAta,
+        price,
+      };
+    };
+
+    const delistContextNft = async (ctx: MarketplaceContext) =>
+      program.methods
+        .delistNft()
+        .accounts({
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          sellerTokenAccount: ctx.makerAta,
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          masterEdition: null,
+          metadata: null,
+          listingTokenRecord: null,
+          sellerTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          marketplace: ctx.marketplace,
+          collectionStats,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          metadataProgram: null,
+          associatedTokenProgram: null,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+
+    const refreshFloor = (listings: PublicKey[]) =>
+      program.methods
+        .refreshFloor()
+        .accounts({ collectionStats })
+        .remainingAccounts(listings.map((pubkey) => ({ pubkey, isWritable: false, isSigner: false })))
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      context = await setupMarketplace();
+      await addCollection(context);
+      collectionStats = collectionStatsPda(context);
+      cheaper = await nftOfCollection(context, context.price.divn(2));
+    });
+
+    it("tracks the cheapest listing as the floor", async () => {
+      await listContextNft(context, 0, 1, null, 0, 0, null, collectionStats);
+      let stats = await getCollectionStats();
+      assert.ok(stats.floorPrice.eq(context.price));
+
+      await listContextNft(cheaper, 0, 1, null, 0, 0, null, collectionStats);
+      stats = await getCollectionStats();
+      assert.ok(stats.floorPrice.eq(cheaper.price));
+      assert.ok(stats.floorListing.equals(cheaper.listing));
+      assert.ok(stats.activeListings.eqn(2));
+      assert.isNull(stats.lastSalePrice);
+    });
+
+    it("rejects statistics of another collection", async () => {
+      const other = await setupMarketplace();
+      await addCollection(other);
+      const otherStats = collectionStatsPda(other);
+      await listContextNft(other, 0, 1, null, 0, 0, null, otherStats);
+      await expectError(
+        purchaseContextNft(context, context.treasury, null, null, null, false, otherStats),
+        "InvalidCollectionStats"
+      );
+    });
+
+    it("records the sale and forgets the floor once the floor listing sells", async () => {
+      await purchaseContextNft(cheaper, cheaper.treasury, null, null, null, false, collectionStats);
+
+      const stats = await getCollectionStats();
+      assert.isNull(stats.floorPrice);
+      assert.isNull(stats.floorListing);
+      assert.ok(stats.activeListings.eqn(1));
+      assert.ok(stats.lastSalePrice.eq(cheaper.price));
+      assert.ok(stats.rollingVolume.eq(cheaper.price));
+      assert.ok(stats.salesCount.eqn(1));
+    });
+
+    it("refreshes the floor from candidate listings, skipping closed ones", async () => {
+      const tx = await refreshFloor([cheaper.listing, context.listing]);
+
+      const [event] = await parseEvents(tx, "collectionFloorRefreshedEvent");
+      assert.ok(event.floorPrice.eq(context.price));
+      const stats = await getCollectionStats();
+      assert.ok(stats.floorPrice.eq(context.price));
+      assert.ok(stats.floorListing.equals(context.listing));
+    });
+
+    it("forgets the floor when the floor listing is delisted", async () => {
+      await delistContextNft(context);
+
+      const stats = await getCollectionStats();
+      assert.isNull(stats.floorPrice);
+      assert.ok(stats.activeListings.eqn(0));
+    });
+  });
+
+  describe("reward holder discount", () => {
+    const marketplace = marketplacePda();
+    let buyer: Keypair;
+
+    const setHolderDiscount = (threshold: anchor.BN, discountBps: number) =>
+      program.methods
+        .setHolderDiscount(threshold, discountBps)
+        .accounts({
+          admin: provider.wallet.publicKey,
+          //@ts-ignore
+          marketplace,
+        })
+        .rpc({ commitment: "confirmed" });
+
+    const setRewardRate = (rewardRateBps: number) =>
+      program.methods
+        .setRewardRate(rewardRateBps)
+        .accounts({
+          admin: provider.wallet.publicKey,
+          //@ts-ignore
+          marketplace,
+        })
+        .rpc({ commitment: "confirmed" });
+
+    // A listed NFT bought by the shared buyer, so their reward points carry over between tests
+    const listedForBuyer = async () => {
+      const context = await setupMarketplace();
+      await addCollection(context);
+      await listContextNft(context);
+      const mint = new PublicKey(context.nftMint.publicKey);
+      const takerAta = await getOrCreateAssociatedTokenAccount(connection, buyer, mint, buyer.publicKey);
+      return { ...context, taker: buyer, takerAta: takerAta.address };
+    };
+
+    before(async () => {
+      buyer = await fundedKeypair();
+      // Every lamport paid earns a reward point, so one purchase reaches a threshold of one price
+      await setRewardRate(10_000);
+    });
+
+    after(async () => {
+      await setRewardRate(0);
+      await setHolderDiscount(new anchor.BN(0), 0);
+    });
+
+    it("rejects a discount without a threshold or above the maximum fee", async () => {
+      const maxFeeBps = Number(
+        program.idl.constants.find((c) => c.name === "maxFeeBps").value.replace(/_/g, "")
+      );
+      await expectError(setHolderDiscount(new anchor.BN(0), 50), "InvalidHolderDiscount");
+      await expectError(setHolderDiscount(new anchor.BN(1), maxFeeBps + 1), "InvalidHolderDiscount");
+    });
+
+    it("charges buyers below the threshold the full fee", async () => {
+      const context = await listedForBuyer();
+      await setHolderDiscount(context.price, 50);
+      const { feeBps } = await program.account.marketplace.fetch(marketplace);
+
+      const [event] = await parseEvents(await purchaseContextNft(context), "nftPurchasedEvent");
+
+      assert.equal(event.feeDiscountBps, 0);
+      assert.ok(event.marketplaceFee.eq(context.price.muln(feeBps).divn(10_000)));
+    });
+
+    it("discounts the fee of buyers holding the threshold", async () => {
+      const context = await listedForBuyer();
+      const { feeBps } = await program.account.marketplace.fetch(marketplace);
+      const discount = Math.min(50, feeBps);
+
+      const [event] = await parseEvents(await purchaseContextNft(context), "nftPurchasedEvent");
+
+      assert.equal(event.feeDiscountBps, discount);
+      assert.ok(event.marketplaceFee.eq(context.price.muln(feeBps - discount).divn(10_000)));
+    });
+
+    it("rejects a reward account of another mint", async () => {
+      const context = await listedForBuyer();
+      const otherMint = await createMint(connection, buyer, buyer.publicKey, null, 6);
+      const forged = await getOrCreateAssociatedTokenAccount(connection, buyer, otherMint, buyer.publicKey);
+      await mintTo(connection, buyer, otherMint, forged.address, buyer, 1_000_000_000_000);
+
+      await expectError(
+        purchaseContextNft(context, context.treasury, null, null, null, false, null, forged.address),
+        "ConstraintTokenMint"
+      );
+    });
+  });
+
+  describe("listing categories", () => {
+    const categoryCount = Number(
+      program.idl.constants.find((c) => c.name === "listingCategoryCount").value
+    );
+    // Discriminator, seller, marketplace, mint, price, bump and is_active come first
+    const categoryOffset = 8 + 32 + 32 + 32 + 8 + 1 + 1;
+    const ART = 1;
+    const GAMING = 2;
+
+    const updateListingCategory = (ctx: MarketplaceContext, category: number, seller = ctx.maker) =>
+      program.methods
+        .updateListingCategory(category)
+        .accounts({
+          seller: seller.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          marketplace: ctx.marketplace,
+        })
+        .signers([seller])
+        .rpc({ commitment: "confirmed" });
+
+    // The seller's listings in a category, found with memcmp filters alone
+    const listingsIn = (seller: PublicKey, category: number) =>
+      program.account.listing.all([
+        { memcmp: { offset: 8, bytes: seller.toBase58() } },
+        { memcmp: { offset: categoryOffset, bytes: anchor.utils.bytes.bs58.encode(Buffer.from([category])) } },
+      ]);
+
+    let context: MarketplaceContext;
+
+    before(async () => {
+      context = await setupMarketplace();
+      await addCollection(context);
+    });
+
+    it("rejects unknown categories", async () => {
+      await expectError(
+        listContextNft(context, 0, 1, null, 0, 0, null, null, categoryCount),
+        "InvalidCategory"
+      );
+    });
+
+    it("stores the category at its fixed offset and in the listed event", async () => {
+      const tx = await listContextNft(context, 0, 1, null, 0, 0, null, null, ART);
+
+      const [event] = await parseEvents(tx, "nftListedEvent");
+      assert.equal(event.category, ART);
+      assert.equal((await program.account.listing.fetch(context.listing)).category, ART);
+
+      const found = await listingsIn(context.maker.publicKey, ART);
+      assert.deepEqual(
+        found.map((listing) => listing.publicKey.toBase58()),
+        [context.listing.toBase58()]
+      );
+      assert.lengthOf(await listingsIn(context.maker.publicKey, GAMING), 0);
+    });
+
+    it("lets only the seller recategorize the listing", async () => {
+      const stranger = await fundedKeypair();
+      await expectError(updateListingCategory(context, GAMING, stranger), "NotListingSeller");
+      await expectError(updateListingCategory(context, categoryCount), "InvalidCategory");
+
+      const tx = await updateListingCategory(context, GAMING);
+
+      const [event] = await parseEvents(tx, "listingCategoryUpdatedEvent");
+      assert.equal(event.oldCategory, ART);
+      assert.equal(event.newCategory, GAMING);
+      assert.lengthOf(await listingsIn(context.maker.publicKey, ART), 0);
+      assert.lengthOf(await listingsIn(context.maker.publicKey, GAMING), 1);
+    });
+  });
+
+  describe("best offers", () => {
+    let context: MarketplaceContext;
+    let low: Keypair;
+    let high: Keypair;
+    let lowest: Keypair;
+
+    const offerPda = (buyer: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("offer"), context.listing.toBuffer(), buyer.toBuffer()],
+        program.programId
+      )[0];
+
+    const makeOffer = async (buyer: Keypair, amount: anchor.BN) =>
+      program.methods
+        .makeOffer(amount, new anchor.BN((await chainTime()) + 3600))
+        .accounts({
+          buyer: buyer.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          offer: offerPda(buyer.publicKey),
+          marketplace: context.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc({ commitment: "confirmed" });
+
+    const cancelOffer = (buyer: Keypair, candidates: PublicKey[]) =>
+      program.methods
+        .cancelOffer()
+        .accounts({
+          authority: buyer.publicKey,
+          buyer: buyer.publicKey,
+          //@ts-ignore
+          offer: offerPda(buyer.publicKey),
+          marketplace: context.marketplace,
+          listing: context.listing,
+          paymentMint: null,
+          offerVault: null,
+          buyerPaymentAccount: null,
+          tokenProgram: null,
+        })
+        .remainingAccounts(
+          candidates.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }))
+        )
+        .signers([buyer])
+        .rpc({ commitment: "confirmed" });
+
+    const acceptBestOffer = async (buyer: Keypair) =>
+      program.methods
+        .acceptBestOffer()
+        .accounts({
+          seller: context.maker.publicKey,
+          buyer: buyer.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          listingTokenAccount: context.vault,
+          buyerTokenAccount: getAssociatedTokenAddressSync(context.nftMint.publicKey, buyer.publicKey),
+          offer: offerPda(buyer.publicKey),
+          marketplace: context.marketplace,
+          feeRecipient: context.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          paymentMint: null,
+          offerVault: null,
+          sellerPaymentAccount: null,
+          feeRecipientPaymentAccount: null,
+          ...(await unindexAccounts(context.marketplace, context.listing)),
+        })
+        .signers([context.maker])
+        .rpc({ commitment: "confirmed" });
+
+    const bestOffer = async () => {
+      const listing = await program.account.listing.fetch(context.listing);
+      return { offer: listing.bestOffer, amount: listing.bestOfferAmount.toNumber() };
+    };
+
+    before(async () => {
+      context = await setupMarketplace();
+      [low, high, lowest] = await Promise.all([fundedKeypair(), fundedKeypair(), fundedKeypair()]);
+      await addCollection(context);
+      await listContextNft(context);
+    });
+
+    it("tracks only offers that beat the current best", async () => {
+      await makeOffer(low, context.price.divn(4));
+      await makeOffer(high, context.price.divn(2));
+      await makeOffer(lowest, context.price.divn(8));
+
+      const best = await bestOffer();
+      assert.ok(best.offer.equals(offerPda(high.publicKey)));
+      assert.equal(best.amount, context.price.divn(2).toNumber());
+    });
+
+    it("only accepts the tracked best offer", async () => {
+      await expectError(acceptBestOffer(low), "NotBestOffer");
+    });
+
+    it("recomputes the best offer from candidates when it is cancelled", async () => {
+      await cancelOffer(high, [offerPda(high.publicKey), offerPda(lowest.publicKey), offerPda(low.publicKey)]);
+
+      const best = await bestOffer();
+      assert.ok(best.offer.equals(offerPda(low.publicKey)));
+      assert.equal(best.amount, context.price.divn(4).toNumber());
+    });
+
+    it("rejects the cancelled best offer", async () => {
+      await expectError(acceptBestOffer(high), "AccountNotInitialized");
+    });
+
+    it("accepts the best offer without naming its buyer up front", async () => {
+      const tx = await acceptBestOffer(low);
+
+      const [event] = await parseEvents(tx, "offerAcceptedEvent");
+      assert.ok(event.buyer.equals(low.publicKey));
+      assert.ok(event.amount.eq(context.price.divn(4)));
+      assert.isNull(await connection.getAccountInfo(context.listing));
+    });
+  });
+
+  describe("counter-offers", () => {
+    let above: MarketplaceContext;
+    let below: MarketplaceContext;
+
+    const offerPda = (ctx: MarketplaceContext) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("offer"), ctx.listing.toBuffer(), ctx.taker.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const provenancePda = (ctx: MarketplaceContext) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("provenance"),
+          ctx.marketplace.toBuffer(),
+          new PublicKey(ctx.nftMint.publicKey).toBuffer(),
+        ],
+        program.programId
+      )[0];
+
+    const makeOffer = async (ctx: MarketplaceContext) =>
+      program.methods
+        .makeOffer(ctx.price.divn(2), new anchor.BN((await chainTime()) + 3600))
+        .accounts({
+          buyer: ctx.taker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          offer: offerPda(ctx),
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ctx.taker])
+        .rpc({ commitment: "confirmed" });
+
+    const counterOffer = (ctx: MarketplaceContext, amount: anchor.BN, seller = ctx.maker) =>
+      program.methods
+        .counterOffer(amount)
+        .accounts({
+          seller: seller.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          offer: offerPda(ctx),
+          marketplace: ctx.marketplace,
+        })
+        .signers([seller])
+        .rpc({ commitment: "confirmed" });
+
+    const rejectCounter = (ctx: MarketplaceContext) =>
+      program.methods
+        .rejectCounter()
+        .accounts({
+          buyer: ctx.taker.publicKey,
+          //@ts-ignore
+          offer: offerPda(ctx),
+        })
+        .signers([ctx.taker])
+        .rpc({ commitment: "confirmed" });
+
+    const acceptCounter = async (ctx: MarketplaceContext) =>
+      program.methods
+        .acceptCounter()
+        .accounts({
+          buyer: ctx.taker.publicKey,
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          buyerTokenAccount: ctx.takerAta,
+          offer: offerPda(ctx),
+          marketplace: ctx.marketplace,
+          feeRecipient: ctx.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          ...(await unindexAccounts(ctx.marketplace, ctx.listing)),
+        })
+        .signers([ctx.taker])
+        .rpc({ commitment: "confirmed" });
+
+    // Accept the open counter-offer and check every lamport of the escrow adjustment and payout
+    const acceptAndCheckBalances = async (ctx: MarketplaceContext, counter: anchor.BN) => {
+      const offer = offerPda(ctx);
+      const offerAmount = ctx.price.divn(2).toNumber();
+      const [buyerBefore, sellerBefore, treasuryBefore, offerBalance, listingRent, vaultRent] =
+        await Promise.all(
+          [ctx.taker.publicKey, ctx.maker.publicKey, ctx.treasury, offer, ctx.listing, ctx.vault].map(
+            (account) => connection.getBalance(account)
+          )
+        );
+
+      const tx = await acceptCounter(ctx);
+
+      const [event] = await parseEvents(tx, "counterOfferAcceptedEvent");
+      assert.equal(event.offerAmount.toNumber(), offerAmount);
+      assert.ok(event.amount.eq(counter));
+
+      const fee = counter.muln(100).divn(10_000).toNumber();
+      const provenanceRent = await connection.getBalance(provenancePda(ctx));
+      assert.equal(await connection.getBalance(ctx.treasury), treasuryBefore + fee);
+      assert.equal(
+        await connection.getBalance(ctx.maker.publicKey),
+        sellerBefore + counter.toNumber() - fee + listingRent + vaultRent
+      );
+      // The buyer pays the difference, or is refunded it, and gets the offer rent back
+      assert.equal(
+        await connection.getBalance(ctx.taker.publicKey),
+        buyerBefore + (offerBalance - offerAmount) + offerAmount - counter.toNumber() - provenanceRent
+      );
+      assert.equal((await getAccount(connection, ctx.takerAta)).amount, BigInt(1));
+      assert.isNull(await connection.getAccountInfo(offer));
+      assert.isNull(await connection.getAccountInfo(ctx.listing));
+    };
+
+    before(async () => {
+      above = await setupMarketplace();
+      below = await setupMarketplace();
+      for (const ctx of [above, below]) {
+        await addCollection(ctx);
+        await listContextNft(ctx);
+        await makeOffer(ctx);
+      }
+    });
+
+    it("only lets the seller counter with a new non-zero amount", async () => {
+      await expectError(counterOffer(above, above.price, above.taker), "NotListingSeller");
+      await expectError(counterOffer(above, above.price.divn(2)), "InvalidCounterOffer");
+      await expectError(counterOffer(above, new anchor.BN(0)), "InvalidCounterOffer");
+      await expectError(acceptCounter(above), "NoCounterOffer");
+    });
+
+    it("restores the original terms when the buyer rejects the counter-offer", async () => {
+      const counter = above.price.muln(3).divn(4);
+      const tx = await counterOffer(above, counter);
+      const [made] = await parseEvents(tx, "counterOfferMadeEvent");
+      assert.ok(made.counterAmount.eq(counter));
+      assert.ok(made.offerAmount.eq(above.price.divn(2)));
+      assert.ok((await program.account.offer.fetch(offerPda(above))).counterAmount.eq(counter));
+
+      const [rejected] = await parseEvents(await rejectCounter(above), "counterOfferRejectedEvent");
+      assert.ok(rejected.counterAmount.eq(counter));
+
+      const offer = await program.account.offer.fetch(offerPda(above));
+      assert.isNull(offer.counterAmount);
+      assert.ok(offer.amount.eq(above.price.divn(2)));
+      await expectError(rejectCounter(above), "NoCounterOffer");
+    });
+
+    it("tops up the escrow when the counter-offer is above the offer", async () => {
+      const counter = above.price.muln(3).divn(4);
+      await counterOffer(above, counter);
+      await acceptAndCheckBalances(above, counter);
+    });
+
+    it("refunds the difference when the counter-offer is below the offer", async () => {
+      const counter = below.price.divn(3);
+      await counterOffer(below, counter);
+      await acceptAndCheckBalances(below, counter);
+    });
+  });
+
+  describe("token offers", () => {
+    let context: MarketplaceContext;
+    let bidder: Keypair;
+    let usdc: PublicKey;
+    const usdcAmount = new anchor.BN(30_000_000);
+
+    const offerPda = (buyer: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("offer"), context.listing.toBuffer(), buyer.toBuffer()],
+        program.programId
+      )[0];
+
+    const offerVault = (buyer: PublicKey, mint = usdc) =>
+      getAssociatedTokenAddressSync(mint, offerPda(buyer), true);
+
+    const usdcBalance = async (owner: PublicKey) =>
+      (await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(usdc, owner, true))).value.amount;
+
+    const makeOfferToken = (buyer: Keypair, expiry: number, mint = usdc) =>
+      program.methods
+        .makeOfferToken(usdcAmount, new anchor.BN(expiry))
+        .accounts({
+          buyer: buyer.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          offer: offerPda(buyer.publicKey),
+          paymentMint: mint,
+          buyerPaymentAccount: getAssociatedTokenAddressSync(mint, buyer.publicKey),
+          offerVault: offerVault(buyer.publicKey, mint),
+          marketplace: context.marketplace,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([buyer])
+        .rpc({ commitment: "confirmed" });
+
+    const cancelOffer = (
+      authority: Keypair,
+      buyer: PublicKey,
+      instruction: "cancelOffer" | "refundExpiredOffer" = "cancelOffer"
+    ) =>
+      program.methods[instruction]()
+        .accounts({
+          authority: authority.publicKey,
+          buyer,
+          //@ts-ignore
+          offer: offerPda(buyer),
+          marketplace: context.marketplace,
+          listing: context.listing,
+          paymentMint: usdc,
+          offerVault: offerVault(buyer),
+          buyerPaymentAccount: getAssociatedTokenAddressSync(usdc, buyer),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc({ commitment: "confirmed" });
+
+    const acceptOffer = async (buyer: Keypair) =>
+      program.methods
+        .acceptOffer()
+        .accounts({
+          seller: context.maker.publicKey,
+          buyer: buyer.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          listingTokenAccount: context.vault,
+          buyerTokenAccount: getAssociatedTokenAddressSync(context.nftMint.publicKey, buyer.publicKey),
+          offer: offerPda(buyer.publicKey),
+          marketplace: context.marketplace,
+          feeRecipient: context.treasury,
+          paymentMint: usdc,
+          offerVault: offerVault(buyer.publicKey),
+          sellerPaymentAccount: getAssociatedTokenAddressSync(usdc, context.maker.publicKey),
+          feeRecipientPaymentAccount: getAssociatedTokenAddressSync(usdc, context.treasury, true),
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          ...(await unindexAccounts(context.marketplace, context.listing)),
+        })
+        .signers([context.maker])
+        .rpc({ commitment: "confirmed" });
+
+    const fundUsdc = async (owner: PublicKey, mint = usdc) => {
+      const account = await getOrCreateAssociatedTokenAccount(connection, provider.wallet.payer, mint, owner);
+      await mintTo(connection, provider.wallet.payer, mint, account.address, provider.wallet.payer, 100_000_000);
+    };
+
+    before(async () => {
+      usdc = await createMint(connection, provider.wallet.payer, provider.wallet.publicKey, null, 6);
+      context = await setupMarketplace();
+      bidder = await fundedKeypair();
+      await addCollection(context);
+      await listContextNft(context, 0, 1, null, 0, 0, { mint: usdc, amount: usdcAmount.muln(2) });
+      await fundUsdc(context.taker.publicKey);
+      await fundUsdc(bidder.publicKey);
+    });
+
+    it("rejects mints the listing is not priced in", async () => {
+      const other = await createMint(connection, provider.wallet.payer, provider.wallet.publicKey, null, 6);
+      await fundUsdc(bidder.publicKey, other);
+      await expectError(
+        makeOfferToken(bidder, (await chainTime()) + 3600, other),
+        "InvalidPaymentMint"
+      );
+    });
+
+    it("escrows the offered tokens in the offer's vault", async () => {
+      const expiry = (await chainTime()) + 3600;
+      const tx = await makeOfferToken(context.taker, expiry);
+
+      const [event] = await parseEvents(tx, "tokenOfferMadeEvent");
+      assert.ok(event.paymentMint.equals(usdc));
+      assert.ok(event.amount.eq(usdcAmount));
+      assert.equal(await usdcBalance(context.taker.publicKey), (100_000_000 - usdcAmount.toNumber()).toString());
+      assert.equal(await usdcBalance(offerPda(context.taker.publicKey)), usdcAmount.toString());
+
+      const offer = await program.account.offer.fetch(offerPda(context.taker.publicKey));
+      assert.ok(offer.paymentMint.equals(usdc));
+      // The listing is priced in SOL, so a USDC offer does not compete for its best offer
+      assert.equal((await program.account.listing.fetch(context.listing)).bestOfferAmount.toNumber(), 0);
+    });
+
+    it("refunds an expired token offer and closes its vault", async () => {
+      await makeOfferToken(bidder, (await chainTime()) + 3);
+      await sleep(4000);
+      const bidderSol = await connection.getBalance(bidder.publicKey);
+      const rent =
+        (await connection.getBalance(offerPda(bidder.publicKey))) +
+        (await connection.getBalance(offerVault(bidder.publicKey)));
+
+      const tx = await cancelOffer(context.maker, bidder.publicKey, "refundExpiredOffer");
+
+      const [event] = await parseEvents(tx, "offerCancelledEvent");
+      assert.equal(event.bounty.toNumber(), 0);
+      assert.equal(await usdcBalance(bidder.publicKey), "100000000");
+      assert.equal(await connection.getBalance(bidder.publicKey), bidderSol + rent);
+      assert.isNull(await connection.getAccountInfo(offerVault(bidder.publicKey)));
+      assert.isNull(await connection.getAccountInfo(offerPda(bidder.publicKey)));
+    });
+
+    it("pays the seller and treasury in tokens when accepted", async () => {
+      const tx = await acceptOffer(context.taker);
+
+      const [event] = await parseEvents(tx, "offerAcceptedEvent");
+      assert.ok(event.paymentMint.equals(usdc));
+      assert.ok(event.amount.eq(usdcAmount));
+
+      const fee = usdcAmount.muln(100).divn(10_000);
+      assert.equal(await usdcBalance(context.maker.publicKey), usdcAmount.sub(fee).toString());
+      assert.equal(await usdcBalance(context.treasury), fee.toString());
+      assert.equal(Number((await getAccount(connection, context.takerAta)).amount), 1);
+      assert.isNull(await connection.getAccountInfo(offerVault(context.taker.publicKey)));
+      assert.isNull(await connection.getAccountInfo(offerPda(context.taker.publicKey)));
+      assert.isNull(await connection.getAccountInfo(context.listing));
+    });
+  });
+
+  describe("accepting offers on listed NFTs", () => {
+    let context: MarketplaceContext;
+    const offerAmount = new anchor.BN(8 * LAMPORTS_PER_SOL);
+
+    const offerPda = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("offer"), context.listing.toBuffer(), context.taker.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    before(async () => {
+      context = await setupMarketplace();
+      context.price = new anchor.BN(10 * LAMPORTS_PER_SOL);
+      const sig = await connection.requestAirdrop(context.taker.publicKey, 10 * LAMPORTS_PER_SOL);
+      await connection.confirmTransaction(sig);
+      await addCollection(context);
+      await listContextNft(context);
+    });
+
+    it("sells the escrowed NFT for an offer below the list price and closes everything", async () => {
+      await program.methods
+        .makeOffer(offerAmount, new anchor.BN((await chainTime()) + 3600))
+        .accounts({
+          buyer: context.taker.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          offer: offerPda(),
+          marketplace: context.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([context.taker])
+        .rpc({ commitment: "confirmed" });
+
+      const [sellerBefore, treasuryBefore, listingRent, vaultRent] = await Promise.all(
+        [context.maker.publicKey, context.treasury, context.listing, context.vault].map((account) =>
+          connection.getBalance(account)
+        )
+      );
+
+      const tx = await program.methods
+        .acceptOffer()
+        .accounts({
+          seller: context.maker.publicKey,
+          buyer: context.taker.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          listingTokenAccount: context.vault,
+          buyerTokenAccount: context.takerAta,
+          offer: offerPda(),
+          marketplace: context.marketplace,
+          feeRecipient: context.treasury,
+          paymentMint: null,
+          offerVault: null,
+          sellerPaymentAccount: null,
+          feeRecipientPaymentAccount: null,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          ...(await unindexAccounts(context.marketplace, context.listing)),
+        })
+        .signers([context.maker])
+        .rpc({ commitment: "confirmed" });
+
+      const [event] = await parseEvents(tx, "offerAcceptedEvent");
+      assert.ok(event.amount.eq(offerAmount));
+      assert.isNull(event.paymentMint);
+
+      // The seller is paid the offer, not the list price, and recovers the listing and vault rent
+      const fee = offerAmount.muln(100).divn(10_000).toNumber();
+      assert.equal(await connection.getBalance(context.treasury), treasuryBefore + fee);
+      assert.equal(
+        await connection.getBalance(context.maker.publicKey),
+        sellerBefore + offerAmount.toNumber() - fee + listingRent + vaultRent
+      );
+      assert.equal(Number((await getAccount(connection, context.takerAta)).amount), 1);
+      assert.isNull(await connection.getAccountInfo(context.listing));
+      assert.isNull(await connection.getAccountInfo(context.vault));
+      assert.isNull(await connection.getAccountInfo(offerPda()));
+
+      const [sellerStats] = PublicKey.findProgramAddressSync(
+        [Buffer.from("seller_stats"), context.marketplace.toBuffer(), context.maker.publicKey.toBuffer()],
+        program.programId
+      );
+      const stats = await program.account.sellerStats.fetch(sellerStats);
+      assert.equal(stats.volume.toString(), offerAmount.toString());
+      assert.equal(stats.activeListings.toNumber(), 0);
+    });
+  });
+
+  describe("blacklist", () => {
+    let context: MarketplaceContext;
+    let buyerContext: MarketplaceContext;
+
+    const blacklistPda = (ctx: MarketplaceContext, wallet: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("blacklist"), ctx.marketplace.toBuffer(), wallet.toBuffer()],
+        program.programId
+      )[0];
+
+    const offerPda = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("offer"), context.listing.toBuffer(), context.taker.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const blacklistWallet = (ctx: MarketplaceContext, wallet: PublicKey, admin?: Keypair) =>
+      program.methods
+        .blacklistWallet()
+        .accounts({
+          admin: admin ? admin.publicKey : provider.wallet.publicKey,
+          wallet,
+          //@ts-ignore
+          blacklist: blacklistPda(ctx, wallet),
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers(admin ? [admin] : [])
+        .rpc({ commitment: "confirmed" });
+
+    const unblacklistWallet = (ctx: MarketplaceContext, wallet: PublicKey) =>
+      program.methods
+        .unblacklistWallet()
+        .accounts({
+          admin: provider.wallet.publicKey,
+          //@ts-ignore
+          blacklist: blacklistPda(ctx, wallet),
+          marketplace: ctx.marketplace,
+        })
+        .rpc({ commitment: "confirmed" });
+
+    const setBlockBlacklistedBuyers = (block: boolean) =>
+      program.methods
+        .setBlockBlacklistedBuyers(block)
+        .accounts({
+          admin: provider.wallet.publicKey,
+          //@ts-ignore
+          marketplace: marketplacePda(),
+        })
+        .rpc({ commitment: "confirmed" });
+
+    const acceptOffer = async () =>
+      program.methods
+        .acceptOffer()
+        .accounts({
+          seller: context.maker.publicKey,
+          buyer: context.taker.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          listingTokenAccount: context.vault,
+          buyerTokenAccount: context.takerAta,
+          offer: offerPda(),
+          marketplace: context.marketplace,
+          feeRecipient: context.treasury,
+          paymentMint: null,
+          offerVault: null,
+          sellerPaymentAccount: null,
+          feeRecipientPaymentAccount: null,
+          sellerBlacklist: blacklistPda(context, context.maker.publicKey),
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          ...(await unindexAccounts(context.marketplace, context.listing)),
+        })
+        .signers([context.maker])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      context = await setupMarketplace();
+      buyerContext = await setupMarketplace();
+      await addCollection(context);
+      await addCollection(buyerContext);
+    });
+
+    after(async () => {
+      await setBlockBlacklistedBuyers(false);
+    });
+
+    it("only lets the admin blacklist wallets", async () => {
+      const stranger = await fundedKeypair();
+      await expectError(blacklistWallet(context, context.maker.publicKey, stranger), "Unauthorized");
+    });
+
+    it("blocks blacklisted sellers from listing until they are removed", async () => {
+      await blacklistWallet(context, context.maker.publicKey);
+      const entry = await program.account.blacklist.fetch(blacklistPda(context, context.maker.publicKey));
+      assert.ok(entry.wallet.equals(context.maker.publicKey));
+
+      await expectError(listContextNft(context), "WalletBlacklisted");
+
+      await unblacklistWallet(context, context.maker.publicKey);
+      assert.isNull(await connection.getAccountInfo(blacklistPda(context, context.maker.publicKey)));
+      await listContextNft(context);
+      assert.isTrue((await program.account.listing.fetch(context.listing)).isActive);
+    });
+
+    it("blocks blacklisted sellers from accepting offers", async () => {
+      await program.methods
+        .makeOffer(context.price.divn(2), new anchor.BN((await chainTime()) + 3600))
+        .accounts({
+          buyer: context.taker.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          offer: offerPda(),
+          marketplace: context.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([context.taker])
+        .rpc({ commitment: "confirmed" });
+
+      await blacklistWallet(context, context.maker.publicKey);
+      await expectError(acceptOffer(), "WalletBlacklisted");
+
+      await unblacklistWallet(context, context.maker.publicKey);
+      await acceptOffer();
+      assert.equal(Number((await getAccount(connection, context.takerAta)).amount), 1);
+    });
+
+    it("only blocks blacklisted buyers when the marketplace says so", async () => {
+      await listContextNft(buyerContext);
+      await blacklistWallet(buyerContext, buyerContext.taker.publicKey);
+
+      await setBlockBlacklistedBuyers(true);
+      await expectError(purchaseContextNft(buyerContext), "WalletBlacklisted");
+
+      await setBlockBlacklistedBuyers(false);
+      await purchaseContextNft(buyerContext);
+      assert.equal(Number((await getAccount(connection, buyerContext.takerAta)).amount), 1);
+    });
+  });
+
+  describe("fee splits", () => {
+    let admin: Keypair;
+    let recipients: PublicKey[];
+
+    const setFeeSplits = (ctx: MarketplaceContext, splits: { recipient: PublicKey; bps: number }[], signer = admin) =>
+      program.methods
+        .setFeeSplits(splits)
+        .accounts({
+          admin: signer.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+        })
+        .signers([signer])
+        .rpc();
+
+    // A listing whose 1% fee does not divide evenly among the splits below
+    const listedContext = async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      ctx.price = new anchor.BN(50_000_700);
+      await addCollection(ctx, admin);
+      await listContextNft(ctx);
+      return ctx;
+    };
+
+    const balances = (accounts: PublicKey[]) => Promise.all(accounts.map((account) => connection.getBalance(account)));
+
+    before(async () => {
+      admin = await fundedKeypair();
+      recipients = (await Promise.all([fundedKeypair(), fundedKeypair(), fundedKeypair()])).map(
+        (keypair) => keypair.publicKey
+      );
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .initializeMarketplace(100, ctx.treasury, "")
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          treasury: ctx.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("rejects splits that do not share the whole fee, and non-admins", async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      const [treasury, devFund] = recipients;
+      await expectError(
+        setFeeSplits(ctx, [{ recipient: treasury, bps: 7_000 }, { recipient: devFund, bps: 2_000 }]),
+        "InvalidFeeSplits"
+      );
+      await expectError(
+        setFeeSplits(ctx, [{ recipient: treasury, bps: 5_000 }, { recipient: treasury, bps: 5_000 }]),
+        "InvalidFeeSplits"
+      );
+      await expectError(setFeeSplits(ctx, [{ recipient: treasury, bps: 10_000 }], ctx.taker), "Unauthorized");
+    });
+
+    it("splits the fee among the recipients, the dust going to the first", async () => {
+      const ctx = await listedContext();
+      await setFeeSplits(ctx, [
+        { recipient: recipients[0], bps: 7_000 },
+        { recipient: recipients[1], bps: 2_000 },
+        { recipient: recipients[2], bps: 1_000 },
+      ]);
+
+      // Recipients are checked against the stored splits, in order
+      await expectError(
+        purchaseContextNft(ctx, ctx.treasury, null, null, null, false, null, null, [...recipients].reverse()),
+        "InvalidFeeRecipient"
+      );
+      await expectError(
+        purchaseContextNft(ctx, ctx.treasury, null, null, null, false, null, null, recipients.slice(0, 2)),
+        "InvalidFeeRecipient"
+      );
+
+      const treasuryBefore = await connection.getBalance(ctx.treasury);
+      const before = await balances(recipients);
+      const [event] = await parseEvents(
+        await purchaseContextNft(ctx, ctx.treasury, null, null, null, false, null, null, recipients),
+        "nftPurchasedEvent"
+      );
+      const after = await balances(recipients);
+
+      // 1% of 50_000_700 is 500_007: 350_004, 100_001 and 50_000 rounded down, 2 lamports of dust
+      assert.equal(event.marketplaceFee.toNumber(), 500_007);
+      assert.deepEqual(
+        after.map((balance, i) => balance - before[i]),
+        [350_006, 100_001, 50_000]
+      );
+      assert.equal(await connection.getBalance(ctx.treasury), treasuryBefore);
+    });
+
+    it("pays the whole fee to the fee recipient once the splits are cleared", async () => {
+      const ctx = await listedContext();
+      await setFeeSplits(ctx, []);
+
+      const treasuryBefore = await connection.getBalance(ctx.treasury);
+      const before = await balances(recipients);
+      await purchaseContextNft(ctx);
+
+      assert.equal((await connection.getBalance(ctx.treasury)) - treasuryBefore, 500_007);
+      assert.deepEqual(await balances(recipients), before);
+    });
+  });
+
+  describe("listing extensions", () => {
+    let admin: Keypair;
+
+    const extendListing = (ctx: MarketplaceContext, newExpiry: number, seller = ctx.maker) =>
+      program.methods
+        .extendListing(new anchor.BN(newExpiry))
+        .accounts({
+          seller: seller.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          marketplace: ctx.marketplace,
+        })
+        .signers([seller])
+        .rpc({ commitment: "confirmed" });
+
+    const shortListing = async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await addCollection(ctx, admin);
+      const expiry = (await chainTime()) + 3;
+      await listContextNft(ctx, expiry);
+      return { ctx, expiry };
+    };
+
+    before(async () => {
+      admin = await fundedKeypair();
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .initializeMarketplace(100, ctx.treasury, "")
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          treasury: ctx.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("renews a listing before it expires", async () => {
+      const { ctx, expiry } = await shortListing();
+      const newExpiry = (await chainTime()) + 3600;
+      await expectError(extendListing(ctx, newExpiry, ctx.taker), "NotListingSeller");
+      await expectError(extendListing(ctx, (await chainTime()) - 1), "InvalidListingExpiry");
+
+      const [event] = await parseEvents(await extendListing(ctx, newExpiry), "listingExtendedEvent");
+      assert.equal(event.oldExpiry.toNumber(), expiry);
+      assert.equal(event.newExpiry.toNumber(), newExpiry);
+      assert.isFalse(event.revived);
+
+      // The old expiry no longer applies
+      await waitForChainTime(expiry);
+      await purchaseContextNft(ctx);
+      assert.equal(Number((await getAccount(connection, ctx.takerAta)).amount), 1);
+    });
+
+    it("revives a listing that expired but was not cleaned up", async () => {
+      const { ctx, expiry } = await shortListing();
+      await waitForChainTime(expiry);
+      await expectError(purchaseContextNft(ctx), "ListingExpired");
+
+      const [event] = await parseEvents(
+        await extendListing(ctx, (await chainTime()) + 3600),
+        "listingExtendedEvent"
+      );
+      assert.isTrue(event.revived);
+
+      await purchaseContextNft(ctx);
+      assert.equal(Number((await getAccount(connection, ctx.takerAta)).amount), 1);
+    });
+
+    it("caps extensions at the marketplace's maximum listing duration", async () => {
+      const { ctx } = await shortListing();
+      await program.methods
+        .setMaxListingDuration(600)
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+        })
+        .signers([admin])
+        .rpc();
+
+      await expectError(extendListing(ctx, (await chainTime()) + 3600), "ListingDurationTooLong");
+      const newExpiry = (await chainTime()) + 300;
+      await extendListing(ctx, newExpiry);
+
+      const listing = await program.account.listing.fetch(ctx.listing);
+      assert.equal(listing.expiry.toNumber(), newExpiry);
+    });
+  });
+
+  describe("authorization errors", () => {
+    let context: MarketplaceContext;
+
+    before(async () => {
+      context = await setupMarketplace();
+      await addCollection(context);
+      await listContextNft(context);
+    });
+
+    it("rejects delisting by anyone but the recorded seller", async () => {
+      await expectError(
+        program.methods
+          .delistNft()
+          .accounts({
+            ...(await unindexAccounts(context.marketplace, context.listing)),
+            seller: context.taker.publicKey,
+            nft: context.nftMint.publicKey,
+            //@ts-ignore
+            sellerTokenAccount: context.makerAta,
+            listing: context.listing,
+            listingTokenAccount: context.vault,
+            masterEdition: null,
+            metadata: null,
+            listingTokenRecord: null,
+            sellerTokenRecord: null,
+            authorizationRules: null,
+            authorizationRulesProgram: null,
+            sysvarInstructions: null,
+            associatedTokenProgram: null,
+            metadataProgram: null,
+            marketplace: context.marketplace,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            collectionStats: null,
+            sellerStats: sellerStatsPda(context.marketplace, context.maker.publicKey),
+          })
+          .signers([context.taker])
+          .rpc(),
+        "NotListingSeller"
+      );
+    });
+
+    it("rejects purchases paying anyone but the recorded seller", async () => {
+      const stranger = await fundedKeypair();
+      await expectError(
+        program.methods
+          .purchaseNft(false)
+          .accounts({
+            ...(await unindexAccounts(context.marketplace, context.listing)),
+            buyer: context.taker.publicKey,
+            seller: stranger.publicKey,
+            nft: context.nftMint.publicKey,
+            //@ts-ignore
+            marketplace: context.marketplace,
+            buyerTokenAccount: context.takerAta,
+            listingTokenAccount: context.vault,
+            sellerTokenAccount: null,
+            masterEdition: null,
+            metadata: metadataPda(context),
+            listingTokenRecord: null,
+            buyerTokenRecord: null,
+            authorizationRules: null,
+            authorizationRulesProgram: null,
+            sysvarInstructions: null,
+            metadataProgram: null,
+            listing: context.listing,
+            feeRecipient: context.treasury,
+            paymentMint: null,
+            buyerPaymentAccount: null,
+            sellerPaymentAccount: null,
+            feeRecipientPaymentAccount: null,
+            referrer: null,
+            referrerPaymentAccount: null,
+            saleEscrow: null,
+            proceeds: null,
+            collectionStats: null,
+            sellerStats: sellerStatsPda(context.marketplace, context.maker.publicKey),
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          })
+          .signers([context.taker])
+          .rpc(),
+        "ListingSellerMismatch"
+      );
+
+      // The recorded seller can still be paid
+      await purchaseContextNft(context);
+      assert.equal(Number((await getAccount(connection, context.takerAta)).amount), 1);
+    });
+  });
+
+  describe("listing index", () => {
+    let admin: Keypair;
+    let marketplace: PublicKey;
+    const listed: MarketplaceContext[] = [];
+
+    const getListingPage = async (page: number) =>
+      program.methods
+        .getListingPage(new anchor.BN(page))
+        .accounts({
+          //@ts-ignore
+          marketplace,
+          listingIndex: listingIndexPda(marketplace, page),
+        })
+        .view();
+
+    // Every listing sits at the position it stores
+    const assertConsistent = async () => {
+      const { listingIndexLen } = await program.account.marketplace.fetch(marketplace, "confirmed");
+      const len = listingIndexLen.toNumber();
+      for (let page = 0; page * listingIndexPageSize < len; page++) {
+        const { listings, totalListings } = await getListingPage(page);
+        assert.equal(totalListings.toNumber(), len);
+        assert.equal(listings.length, Math.min(listingIndexPageSize, len - page * listingIndexPageSize));
+        for (const [slot, listing] of listings.entries()) {
+          const state = await program.account.listing.fetch(listing, "confirmed");
+          assert.equal(state.indexPosition.toNumber(), page * listingIndexPageSize + slot);
+        }
+      }
+    };
+
+    before(async () => {
+      admin = await fundedKeypair();
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      marketplace = ctx.marketplace;
+      await program.methods
+        .initializeMarketplace(100, ctx.treasury, "")
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          treasury: ctx.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("fills a page and rolls over to the next one", async () => {
+      for (let i = 0; i <= listingIndexPageSize; i++) {
+        const ctx = await setupMarketplace("verified", admin.publicKey);
+        await addCollection(ctx, admin);
+        await listContextNft(ctx);
+        listed.push(ctx);
+      }
+
+      const first = await getListingPage(0);
+      assert.deepEqual(
+        first.listings.map((listing: PublicKey) => listing.toBase58()),
+        listed.slice(0, listingIndexPageSize).map((ctx) => ctx.listing.toBase58())
+      );
+      const second = await getListingPage(1);
+      assert.equal(second.listings.length, 1);
+      assert.ok(second.listings[0].equals(listed[listingIndexPageSize].listing));
+      assert.equal(second.totalListings.toNumber(), listingIndexPageSize + 1);
+      await assertConsistent();
+    });
+
+    it("moves the last listing into a delisted listing's position", async () => {
+      const middle = listed[3];
+      const last = listed[listingIndexPageSize];
+      const accounts = await unindexAccounts(marketplace, middle.listing);
+      assert.ok(accounts.lastListingIndex.equals(listingIndexPda(marketplace, 1)));
+      assert.ok(accounts.movedListing.equals(last.listing));
+
+      // The moved listing must be the one at the last position
+      await expectError(
+        program.methods
+          .delistNft()
+          .accounts({
+            seller: middle.maker.publicKey,
+            nft: middle.nftMint.publicKey,
+            //@ts-ignore
+            sellerTokenAccount: middle.makerAta,
+            listing: middle.listing,
+            listingTokenAccount: middle.vault,
+            masterEdition: null,
+            metadata: null,
+            listingTokenRecord: null,
+            sellerTokenRecord: null,
+            authorizationRules: null,
+            authorizationRulesProgram: null,
+            sysvarInstructions: null,
+            associatedTokenProgram: null,
+            metadataProgram: null,
+            marketplace,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            collectionStats: null,
+            ...accounts,
+            movedListing: listed[5].listing,
+          })
+          .signers([middle.maker])
+          .rpc(),
+        "InvalidListingIndex"
+      );
+
+      await program.methods
+        .delistNft()
+        .accounts({
+          seller: middle.maker.publicKey,
+          nft: middle.nftMint.publicKey,
+          //@ts-ignore
+          sellerTokenAccount: middle.makerAta,
+          listing: middle.listing,
+          listingTokenAccount: middle.vault,
+          masterEdition: null,
+          metadata: null,
+          listingTokenRecord: null,
+          sellerTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          associatedTokenProgram: null,
+          metadataProgram: null,
+          marketplace,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          collectionStats: null,
+          ...accounts,
+        })
+        .signers([middle.maker])
+        .rpc({ commitment: "confirmed" });
+
+      const moved = await program.account.listing.fetch(last.listing, "confirmed");
+      assert.equal(moved.indexPosition.toNumber(), 3);
+      const first = await getListingPage(0);
+      assert.ok(first.listings[3].equals(last.listing));
+      assert.equal(first.totalListings.toNumber(), listingIndexPageSize);
+      await assertConsistent();
+    });
+
+    it("takes purchased listings out of the index and reuses the freed page", async () => {
+      // The last entry of a page leaves without moving another listing
+      const tail = listed[listingIndexPageSize - 1];
+      const accounts = await unindexAccounts(marketplace, tail.listing);
+      assert.isNull(accounts.movedListing);
+      await purchaseContextNft(tail);
+
+      await purchaseContextNft(listed[0]);
+      await assertConsistent();
+      const { listingIndexLen } = await program.account.marketplace.fetch(marketplace, "confirmed");
+      assert.equal(listingIndexLen.toNumber(), listingIndexPageSize - 2);
+
+      // New listings fill the page back up before rolling over again
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await addCollection(ctx, admin);
+      await listContextNft(ctx);
+      const state = await program.account.listing.fetch(ctx.listing, "confirmed");
+      assert.equal(state.indexPosition.toNumber(), listingIndexPageSize - 2);
+      await assertConsistent();
+    });
+
+    it("takes a listing sold through an offer out of the middle of a page", async () => {
+      const middle = listed[2];
+      const before = await getListingPage(0);
+      assert.ok(before.listings[2].equals(middle.listing));
+      const last = before.listings[before.listings.length - 1];
+      const offer = PublicKey.findProgramAddressSync(
+        [Buffer.from("offer"), middle.listing.toBuffer(), middle.taker.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+      await program.methods
+        .makeOffer(middle.price, new anchor.BN((await chainTime()) + 3600))
+        .accounts({
+          buyer: middle.taker.publicKey,
+          nft: middle.nftMint.publicKey,
+          //@ts-ignore
+          listing: middle.listing,
+          offer,
+          marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([middle.taker])
+        .rpc({ commitment: "confirmed" });
+      await program.methods
+        .acceptOffer()
+        .accounts({
+          seller: middle.maker.publicKey,
+          buyer: middle.taker.publicKey,
+          nft: middle.nftMint.publicKey,
+          //@ts-ignore
+          listing: middle.listing,
+          listingTokenAccount: middle.vault,
+          buyerTokenAccount: middle.takerAta,
+          offer,
+          marketplace,
+          feeRecipient: middle.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          paymentMint: null,
+          offerVault: null,
+          sellerPaymentAccount: null,
+          feeRecipientPaymentAccount: null,
+          ...(await unindexAccounts(marketplace, middle.listing)),
+        })
+        .signers([middle.maker])
+        .rpc({ commitment: "confirmed" });
+
+      assert.isNull(await connection.getAccountInfo(middle.listing));
+      const moved = await program.account.listing.fetch(last, "confirmed");
+      assert.equal(moved.indexPosition.toNumber(), 2);
+      const after = await getListingPage(0);
+      assert.ok(after.listings[2].equals(last));
+      assert.equal(after.totalListings.toNumber(), before.totalListings.toNumber() - 1);
+      await assertConsistent();
+    });
+
+    it("takes a listing bought in a batch out of the middle of a page", async () => {
+      const middle = listed[1];
+      const before = await getListingPage(0);
+      assert.ok(before.listings[1].equals(middle.listing));
+      const last = before.listings[before.listings.length - 1];
+
+      const writable = (pubkey: PublicKey, isWritable = true) => ({
+        pubkey,
+        isWritable,
+        isSigner: false,
+      });
+      const rewardsMint = PublicKey.findProgramAddressSync(
+        [Buffer.from("rewards"), marketplace.toBuffer()],
+        program.programId
+      )[0];
+      const [index] = await unindexGroups(marketplace, [middle.listing]);
+      await program.methods
+        .purchaseMany(middle.price)
+        .accounts({
+          buyer: middle.taker.publicKey,
+          //@ts-ignore
+          marketplace,
+          feeRecipient: middle.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          writable(middle.nftMint.publicKey, false),
+          writable(middle.listing),
+          writable(middle.vault),
+          writable(middle.takerAta),
+          writable(middle.maker.publicKey),
+          writable(sellerStatsPda(marketplace, middle.maker.publicKey)),
+          writable(metadataPda(middle), false),
+          writable(getAssociatedTokenAddressSync(rewardsMint, middle.maker.publicKey)),
+          ...index,
+        ])
+        .signers([middle.taker])
+        .rpc({ commitment: "confirmed" });
+
+      assert.isNull(await connection.getAccountInfo(middle.listing));
+      const moved = await program.account.listing.fetch(last, "confirmed");
+      assert.equal(moved.indexPosition.toNumber(), 1);
+      const after = await getListingPage(0);
+      assert.ok(after.listings[1].equals(last));
+      assert.equal(after.totalListings.toNumber(), before.totalListings.toNumber() - 1);
+      await assertConsistent();
+    });
+  });
+
+  describe("self-CPI events", () => {
+    let context: MarketplaceContext;
+    let mints: PublicKey[];
+
+    const batchSize = program.idl.constants.find((c) => c.name === "maxPurchaseManyItems").value;
+
+    const writable = (pubkey: PublicKey, isWritable = true) => ({
+      pubkey,
+      isWritable,
+      isSigner: false,
+    });
+
+    // The fresh maker's bulk listings give its NFTs consecutive nonces from 0
+    const listingOf = (i: number) =>
+      listingPda(context.marketplace, context.maker.publicKey, mints[i], i);
+
+    const vaultOf = (i: number) => getAssociatedTokenAddressSync(mints[i], listingOf(i), true);
+
+    const metadataOf = (i: number) =>
+      new PublicKey(findMetadataPda(context.umi, { mint: publicKey(mints[i]) })[0]);
+
+    const rewardsMint = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("rewards"), context.marketplace.toBuffer()],
+        program.programId
+      )[0];
+
+    const purchaseGroup = (i: number) => [
+      writable(mints[i], false),
+      writable(listingOf(i)),
+      writable(vaultOf(i)),
+      writable(getAssociatedTokenAddressSync(mints[i], context.taker.publicKey)),
+      writable(context.maker.publicKey),
+      writable(sellerStatsPda(context.marketplace, context.maker.publicKey)),
+      writable(metadataOf(i), false),
+      writable(getAssociatedTokenAddressSync(rewardsMint(), context.maker.publicKey)),
+    ];
+
+    const bulkList = (items: number[]) =>
+      program.methods
+        .bulkList(items.map((i) => context.price.addn(i)))
+        .accounts({
+          seller: context.maker.publicKey,
+          //@ts-ignore
+          marketplace: context.marketplace,
+          collectionMint: context.collectionMint.publicKey,
+          collectionConfig: collectionConfigPda(context),
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          items.flatMap((i) => [
+            writable(mints[i], false),
+            writable(getAssociatedTokenAddressSync(mints[i], context.maker.publicKey)),
+            writable(vaultOf(i)),
+            writable(listingOf(i)),
+            writable(metadataOf(i), false),
+          ])
+        )
+        .preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })])
+        .signers([context.maker])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      await setOpenListings(true);
+      context = await setupMarketplace("none");
+      mints = [];
+      for (let i = 0; i < batchSize; i++) {
+        const mint = generateSigner(context.umi);
+        await createNft(context.umi, {
+          mint,
+          name: "GM",
+          symbol: "GM",
+          uri: "https://arweave.net/123",
+          sellerFeeBasisPoints: percentAmount(5.5),
+          tokenOwner: publicKey(context.maker.publicKey),
+        }).sendAndConfirm(context.umi);
+        mints.push(new PublicKey(mint.publicKey));
+      }
+
+      const items = [...Array(batchSize).keys()];
+      await bulkList(items.slice(0, 3));
+      await bulkList(items.slice(3));
+    });
+
+    after(async () => {
+      await setOpenListings(false);
+    });
+
+    it("recovers one event per item of a full batch purchase with truncated logs", async () => {
+      const items = [...Array(batchSize).keys()];
+      const total = items.reduce((sum, i) => sum.add(context.price.addn(i)), new anchor.BN(0));
+      const index = await unindexGroups(context.marketplace, items.map(listingOf));
+      const ix = await program.methods
+        .purchaseMany(total)
+        .accounts({
+          buyer: context.taker.publicKey,
+          //@ts-ignore
+          marketplace: context.marketplace,
+          feeRecipient: context.treasury,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(items.flatMap((i) => [...purchaseGroup(i), ...index[i]]))
+        .instruction();
+
+      // A full batch only fits in a transaction through a lookup table
+      const slot = await connection.getSlot("finalized");
+      const [createTable, table] = AddressLookupTableProgram.createLookupTable({
+        authority: provider.wallet.publicKey,
+        payer: provider.wallet.publicKey,
+        recentSlot: slot,
+      });
+      const addresses = [...new Set(ix.keys.map((key) => key.pubkey.toBase58()))]
+        .map((key) => new PublicKey(key))
+        .filter((key) => !key.equals(context.taker.publicKey));
+      const extendTable = [];
+      for (let i = 0; i < addresses.length; i += 20) {
+        extendTable.push(
+          AddressLookupTableProgram.extendLookupTable({
+            lookupTable: table,
+            authority: provider.wallet.publicKey,
+            payer: provider.wallet.publicKey,
+            addresses: addresses.slice(i, i + 20),
+          })
+        );
+      }
+      await provider.sendAndConfirm(new anchor.web3.Transaction().add(createTable, extendTable[0]));
+      for (const extend of extendTable.slice(1)) {
+        await provider.sendAndConfirm(new anchor.web3.Transaction().add(extend));
+      }
+      // Lookup table entries only resolve from the slot after they were added
+      await sleep(1000);
+      const lookupTable = (await connection.getAddressLookupTable(table)).value;
+
+      // Each memo logs a few hundred bytes, so the batch's logs start past the 10kB log limit
+      const memoProgram = new PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+      const logSpam = [...Array(48)].map(
+        () => new TransactionInstruction({ programId: memoProgram, keys: [], data: Buffer.from("x") })
+      );
+
+      const { blockhash } = await connection.getLatestBlockhash();
+      const message = new TransactionMessage({
+        payerKey: context.taker.publicKey,
+        recentBlockhash: blockhash,
+        instructions: [
+          ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 }),
+          ...logSpam,
+          ix,
+        ],
+      }).compileToV0Message([lookupTable]);
+      const tx = new VersionedTransaction(message);
+      tx.sign([context.taker]);
+      const signature = await connection.sendTransaction(tx);
+      await connection.confirmTransaction(signature, "confirmed");
+
+      // The truncated logs show none of the batch's event self-CPIs
+      const { meta } = await connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      assert.include(meta.logMessages, "Log truncated");
+      const selfCpis = meta.logMessages.filter(
+        (log) => log === `Program ${program.programId.toBase58()} invoke [2]`
+      );
+      assert.isBelow(selfCpis.length, batchSize);
+
+      const events = await parseEvents(signature, "nftPurchasedEvent");
+      assert.equal(events.length, batchSize);
+      for (const i of items) {
+        assert.ok(events[i].listing.equals(listingOf(i)));
+        assert.ok(events[i].buyer.equals(context.taker.publicKey));
+        assert.ok(events[i].seller.equals(context.maker.publicKey));
+        assert.ok(events[i].price.eq(context.price.addn(i)));
+        assert.isNull(await connection.getAccountInfo(listingOf(i)));
+      }
+    });
+  });
+
+  describe("garbage collection", () => {
+    let admin: Keypair;
+    let collector: Keypair;
+    let offerer: Keypair;
+    let context: MarketplaceContext;
+    const crankFee = 10_000;
+
+    const offerPda = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("offer"), context.listing.toBuffer(), offerer.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const nftOfferPda = () =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("nft_offer"),
+          context.marketplace.toBuffer(),
+          new PublicKey(context.nftMint.publicKey).toBuffer(),
+          offerer.publicKey.toBuffer(),
+        ],
+        program.programId
+      )[0];
+
+    const garbageCollect = (target: PublicKey, proof: PublicKey | null) =>
+      program.methods
+        .garbageCollect()
+        .accounts({
+          collector: collector.publicKey,
+          target,
+          owner: offerer.publicKey,
+          //@ts-ignore
+          marketplace: context.marketplace,
+          proof,
+          nft: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([collector])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      admin = await fundedKeypair();
+      collector = await fundedKeypair();
+      offerer = await fundedKeypair();
+      context = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .initializeMarketplace(100, context.treasury, "")
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: context.marketplace,
+          treasury: context.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      await program.methods
+        .setCrankFee(new anchor.BN(crankFee))
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: context.marketplace,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+      await addCollection(context, admin);
+      await listContextNft(context);
+
+      const expiry = new anchor.BN((await chainTime()) + 3600);
+      await program.methods
+        .makeOffer(context.price.divn(2), expiry)
+        .accounts({
+          buyer: offerer.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          listing: context.listing,
+          offer: offerPda(),
+          marketplace: context.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([offerer])
+        .rpc({ commitment: "confirmed" });
+      await program.methods
+        .makeNftOffer(context.price.divn(4), expiry)
+        .accounts({
+          buyer: offerer.publicKey,
+          nft: context.nftMint.publicKey,
+          //@ts-ignore
+          nftOffer: nftOfferPda(),
+          marketplace: context.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([offerer])
+        .rpc({ commitment: "confirmed" });
+    });
+
+    it("rejects collecting offers that can still be acted on", async () => {
+      // The listing is still open, so its offer can still be accepted
+      await expectError(garbageCollect(offerPda(), context.listing), "NotCollectable");
+      // The proof must be the offer's own listing
+      await expectError(garbageCollect(offerPda(), collector.publicKey), "NotCollectable");
+      // The NFT offer has not expired
+      await expectError(garbageCollect(nftOfferPda(), null), "NotCollectable");
+
+      assert.isNotNull(await connection.getAccountInfo(offerPda()));
+      assert.isNotNull(await connection.getAccountInfo(nftOfferPda()));
+    });
+
+    it("refunds an offer whose listing was bought by someone else and pays the bounty", async () => {
+      await purchaseContextNft(context);
+      assert.isNull(await connection.getAccountInfo(context.listing));
+
+      const escrow = await connection.getBalance(offerPda());
+      const offererBefore = await connection.getBalance(offerer.publicKey);
+      const [event] = await parseEvents(
+        await garbageCollect(offerPda(), context.listing),
+        "garbageCollectedEvent"
+      );
+
+      assert.deepEqual(event.kind, { closedListingOffer: {} });
+      assert.ok(event.collector.equals(collector.publicKey));
+      assert.equal(event.bounty.toNumber(), crankFee);
+      assert.equal(event.refunded.toNumber(), escrow - crankFee);
+      assert.equal(await connection.getBalance(offerer.publicKey), offererBefore + escrow - crankFee);
+      assert.isNull(await connection.getAccountInfo(offerPda()));
+
+      // Nothing is left to collect a second time
+      await expectError(garbageCollect(offerPda(), context.listing), "NotCollectable");
+    });
+  });
+
+  describe("price bounds", () => {
+    let admin: Keypair;
+    const minPrice = new anchor.BN(0.01 * LAMPORTS_PER_SOL);
+    const maxPrice = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+
+    const setPriceBounds = (ctx: MarketplaceContext, min: anchor.BN, max: anchor.BN, signer = admin) =>
+      program.methods
+        .setPriceBounds(min, max)
+        .accounts({
+          admin: signer.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+        })
+        .signers([signer])
+        .rpc({ commitment: "confirmed" });
+
+    const boundedContext = async () => {
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await addCollection(ctx, admin);
+      return ctx;
+    };
+
+    const listAt = (ctx: MarketplaceContext, price: anchor.BN) => {
+      ctx.price = price;
+      return listContextNft(ctx);
+    };
+
+    const updatePrice = (ctx: MarketplaceContext, price: anchor.BN) =>
+      program.methods
+        .updateListingPrice(price)
+        .accounts({
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          marketplace: ctx.marketplace,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+
+    const makeOffer = async (ctx: MarketplaceContext, amount: anchor.BN) =>
+      program.methods
+        .makeOffer(amount, new anchor.BN((await chainTime()) + 3600))
+        .accounts({
+          buyer: ctx.taker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          offer: PublicKey.findProgramAddressSync(
+            [Buffer.from("offer"), ctx.listing.toBuffer(), ctx.taker.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          marketplace: ctx.marketplace,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ctx.taker])
+        .rpc({ commitment: "confirmed" });
+
+    const listDutch = async (ctx: MarketplaceContext, startPrice: anchor.BN, floorPrice: anchor.BN) => {
+      await refreshListing(ctx);
+      const now = await chainTime();
+      return program.methods
+        .listNftDutch(startPrice, floorPrice, new anchor.BN(now), new anchor.BN(now + 3600))
+        .accounts({
+          seller: ctx.maker.publicKey,
+          nft: ctx.nftMint.publicKey,
+          //@ts-ignore
+          listing: ctx.listing,
+          listingTokenAccount: ctx.vault,
+          sellerTokenAccount: ctx.makerAta,
+          marketplace: ctx.marketplace,
+          listingIndex: await nextListingIndexPda(ctx.marketplace),
+          collectionMint: ctx.collectionMint.publicKey,
+          collectionConfig: collectionConfigPda(ctx),
+          collectionStats: null,
+          metadata: new PublicKey(findMetadataPda(ctx.umi, { mint: ctx.nftMint.publicKey })[0]),
+          masterEdition: new PublicKey(findMasterEditionPda(ctx.umi, { mint: ctx.nftMint.publicKey })[0]),
+          sellerTokenRecord: null,
+          listingTokenRecord: null,
+          authorizationRules: null,
+          authorizationRulesProgram: null,
+          sysvarInstructions: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+    };
+
+    const createAuction = async (ctx: MarketplaceContext, startPrice: anchor.BN, buyNowPrice: anchor.BN | null) => {
+      const nft = new PublicKey(ctx.nftMint.publicKey);
+      const [auction] = PublicKey.findProgramAddressSync(
+        [Buffer.from("auction"), ctx.marketplace.toBuffer(), ctx.maker.publicKey.toBuffer(), nft.toBuffer()],
+        program.programId
+      );
+      return program.methods
+        .createAuction(
+          startPrice,
+          new anchor.BN(1_000),
+          new anchor.BN((await chainTime()) + 3600),
+          new anchor.BN(0),
+          false,
+          buyNowPrice
+        )
+        .accounts({
+          seller: ctx.maker.publicKey,
+          nft,
+          //@ts-ignore
+          auction,
+          auctionTokenAccount: getAssociatedTokenAddressSync(nft, auction, true),
+          sellerTokenAccount: ctx.makerAta,
+          bidEscrow: PublicKey.findProgramAddressSync(
+            [Buffer.from("bid_escrow"), auction.toBuffer()],
+            program.programId
+          )[0],
+          marketplace: ctx.marketplace,
+          collectionMint: ctx.collectionMint.publicKey,
+          collectionConfig: collectionConfigPda(ctx),
+          metadata: new PublicKey(findMetadataPda(ctx.umi, { mint: ctx.nftMint.publicKey })[0]),
+          masterEdition: new PublicKey(findMasterEditionPda(ctx.umi, { mint: ctx.nftMint.publicKey })[0]),
+          metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([ctx.maker])
+        .rpc({ commitment: "confirmed" });
+    };
+
+    before(async () => {
+      admin = await fundedKeypair();
+      const ctx = await setupMarketplace("verified", admin.publicKey);
+      await program.methods
+        .initializeMarketplace(100, ctx.treasury, "")
+        .accounts({
+          admin: admin.publicKey,
+          //@ts-ignore
+          marketplace: ctx.marketplace,
+          treasury: ctx.treasury,
+          paymentMint: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      const stranger = await fundedKeypair();
+      await expectError(setPriceBounds(ctx, minPrice, maxPrice, stranger), "Unauthorized");
+      await expectError(setPriceBounds(ctx, maxPrice, minPrice), "InvalidPriceBounds");
+      await setPriceBounds(ctx, minPrice, maxPrice);
+
+      const marketplace = await program.account.marketplace.fetch(ctx.marketplace);
+      assert.ok(marketplace.minPrice.eq(minPrice));
+      assert.ok(marketplace.maxPrice.eq(maxPrice));
+    });
+
+    it("accepts listing and repricing exactly at the bounds and nothing past them", async () => {
+      const ctx = await boundedContext();
+      await expectError(listAt(ctx, minPrice.subn(1)), "PriceOutOfBounds");
+      await expectError(listAt(ctx, maxPrice.addn(1)), "PriceOutOfBounds");
+      await listAt(ctx, minPrice);
+
+      await expectError(updatePrice(ctx, maxPrice.addn(1)), "PriceOutOfBounds");
+      await updatePrice(ctx, maxPrice);
+      await expectError(updatePrice(ctx, minPrice.subn(1)), "PriceOutOfBounds");
+      await updatePrice(ctx, minPrice);
+      assert.ok((await program.account.listing.fetch(ctx.listing)).price.eq(minPrice));
+    });
+
+    it("bounds offers on listings", async () => {
+      const ctx = await boundedContext();
+      await listAt(ctx, maxPrice);
+
+      await expectError(makeOffer(ctx, minPrice.subn(1)), "PriceOutOfBounds");
+      await expectError(makeOffer(ctx, maxPrice.addn(1)), "PriceOutOfBounds");
+      await makeOffer(ctx, minPrice);
+    });
+
+    it("keeps both ends of a Dutch decline within the bounds", async () => {
+      const ctx = await boundedContext();
+      await expectError(listDutch(ctx, maxPrice, minPrice.subn(1)), "PriceOutOfBounds");
+      await expectError(listDutch(ctx, maxPrice.addn(1), minPrice), "PriceOutOfBounds");
+      await listDutch(ctx, maxPrice, minPrice);
+    });
+
+    it("bounds auction start and buy-now prices", async () => {
+      const ctx = await boundedContext();
+      await expectError(createAuction(ctx, minPrice.subn(1), null), "PriceOutOfBounds");
+      await expectError(createAuction(ctx, minPrice, maxPrice.addn(1)), "PriceOutOfBounds");
+      await createAuction(ctx, minPrice, maxPrice);
+    });
+  });
+});
+
+function sleep(ms: number) {
+  return new Promise((resolve) => setTimeout(resolve, ms));
+}
+
+async function handleTxError(err: any, label: string) {
+  console.error(`❌ Transaction failed during "${label}".`);
+
+  if (err.logs) {
+    console.error("🚨 Logs from simulation:\n", err.logs.join("\n"));
+  } else if (err instanceof SendTransactionError && err.getLogs) {
+      const logs = await err.getLogs(provider.connection);
+    console.error("🚨 Logs from getLogs():\n", logs?.join("\n") || "No logs found.");
+  } else {
+    console.error("❗Unknown error:\n", err);
+  }
+
+  throw err;
+}
+
+async function expectError(promise: Promise<unknown>, code: string) {
+  let failed = false;
+  try {
+    await promise;
+  } catch (err: any) {
+    failed = true;
+    assert.include(err.toString(), code);
+  }
+  assert.isTrue(failed, `Expected ${code}`);
+}
+
+// Tag opening the data of the self-CPIs `emit_cpi` makes, ahead of the event's discriminator
+const EVENT_IX_TAG = Buffer.from("e445a52e51cb9a1d", "hex");
+
+// Events are emitted through self-CPIs, so they are decoded from the inner instructions
+async function parseEvents(signature: string, name: string) {
+  const program = anchor.workspace.marketplace as Program<Marketplace>;
+  const tx = await provider.connection.getTransaction(signature, {
+    commitment: "confirmed",
+    maxSupportedTransactionVersion: 0,
+  });
+  const keys = tx.transaction.message.getAccountKeys({
+    accountKeysFromLookups: tx.meta.loadedAddresses,
+  });
+  return tx.meta.innerInstructions
+    .flatMap((inner) => inner.instructions)
+    .filter((ix) => keys.get(ix.programIdIndex).equals(program.programId))
+    .map((ix) => Buffer.from(anchor.utils.bytes.bs58.decode(ix.data)))
+    .filter((data) => data.subarray(0, 8).equals(EVENT_IX_TAG))
+    .map((data) => program.coder.events.decode(data.subarray(8).toString("base64")))
+    .filter((event) => event?.name === name)
+    .map((event) => event.data);
 }